@@ -0,0 +1,69 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Rendering every line of a big buffer, like an export does, against the old way of
+//! formatting each byte with its own format! call.
+//!
+//! Run with `cargo bench --bench encode`
+
+use hex_it::{dump::render_line, DumpOptions};
+use std::time::{Duration, Instant};
+
+/// Size of the buffer rendered
+const SIZE: usize = 32 << 20;
+
+/// How render_line used to do it, a String per byte
+fn format_per_byte(offset: usize, bytes: &[u8], options: &DumpOptions) -> String {
+    let mut hex = String::with_capacity(options.hex_width());
+
+    for (i, byte) in bytes.iter().enumerate() {
+        if options.group > 0 && i > 0 && i % options.group == 0 {
+            hex.push(' ');
+        }
+        hex.push_str(&format!("{:02X} ", byte));
+    }
+
+    let text = String::from_utf8_lossy(bytes).replace('\n', ".");
+
+    format!(
+        "{:08X}  {:<width$}  {}",
+        offset,
+        hex,
+        text,
+        width = options.hex_width()
+    )
+}
+
+/// Renders the whole buffer a line at a time, returns how long it took and the total length
+fn time(bytes: &[u8], render: impl Fn(usize, &[u8]) -> String) -> (Duration, usize) {
+    let started = Instant::now();
+    let length = bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(line, chunk)| render(line * 16, chunk).len())
+        .sum();
+
+    (started.elapsed(), length)
+}
+
+fn main() {
+    let options = DumpOptions {
+        colors: false,
+        ..DumpOptions::default()
+    };
+    let bytes = (0..SIZE)
+        .map(|i| (i.wrapping_mul(2654435761) >> 7) as u8)
+        .collect::<Vec<u8>>();
+
+    let (old, old_length) = time(&bytes, |offset, chunk| {
+        format_per_byte(offset, chunk, &options)
+    });
+    let (new, new_length) = time(&bytes, |offset, chunk| render_line(offset, chunk, &options));
+    assert_eq!(old_length, new_length);
+
+    let throughput = |elapsed: Duration| SIZE as f64 / (1 << 20) as f64 / elapsed.as_secs_f64();
+    println!("format! per byte: {:?} ({:.0} MiB/s)", old, throughput(old));
+    println!("render_line:      {:?} ({:.0} MiB/s)", new, throughput(new));
+}
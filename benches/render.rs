@@ -0,0 +1,48 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Redrawing the screen after typing a byte, for files of different sizes.
+//! With the line cache the time per edit should stay the same however big the file is.
+//!
+//! Run with `cargo bench --bench render`
+
+use hex_it::{dump::LineCache, Document, DumpOptions};
+use std::time::Instant;
+
+/// Lines on a typical screen
+const VISIBLE_LINES: usize = 50;
+
+/// Edits timed per file size
+const EDITS: usize = 1000;
+
+fn main() {
+    let options = DumpOptions::default();
+
+    for megabytes in [1, 16, 64] {
+        let mut document = Document::new(vec![0x41; megabytes << 20]);
+        let changes = document.subscribe();
+        let mut cache = LineCache::default();
+        let top = document.len() / options.cols / 2;
+
+        let started = Instant::now();
+        for edit in 0..EDITS {
+            document
+                .overwrite((top + edit % VISIBLE_LINES) * options.cols, &[edit as u8])
+                .unwrap();
+            for change in changes.try_iter() {
+                cache.invalidate(&change, options.cols);
+            }
+            for index in top..top + VISIBLE_LINES {
+                cache.line(index, document.bytes(), &options);
+            }
+        }
+
+        println!(
+            "{:>3} MiB: {:?} per edit and redraw",
+            megabytes,
+            started.elapsed() / EDITS as u32
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Search throughput over a big buffer for needles of a few lengths, fed in chunks like
+//! the editor does.
+//!
+//! Run with `cargo bench --bench search`
+
+use hex_it::search::{ChunkedSearch, Finder};
+use std::time::Instant;
+
+/// Size of the buffer searched
+const SIZE: usize = 256 << 20;
+
+/// Chunk size the editor feeds the search with
+const CHUNK: usize = 4 << 20;
+
+fn main() {
+    // Text-like bytes, so the needles' bytes turn up often and the skips are realistic
+    let bytes = (0..SIZE)
+        .map(|i| b'a' + ((i.wrapping_mul(2654435761) >> 11) % 26) as u8)
+        .collect::<Vec<u8>>();
+
+    for needle in [
+        &b"q"[..],
+        b"zqxj",
+        b"the needle isn't",
+        b"a needle that is long enough",
+    ] {
+        let mut search = ChunkedSearch::new(Finder::new(needle), usize::MAX);
+
+        let started = Instant::now();
+        for chunk in bytes.chunks(CHUNK) {
+            search.feed(chunk);
+        }
+        let elapsed = started.elapsed();
+
+        println!(
+            "{:>2} byte needle: {:>5.0} MiB/s, {} matches",
+            needle.len(),
+            (SIZE >> 20) as f64 / elapsed.as_secs_f64(),
+            search.matches().len()
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Splitting a command line into arguments the way a shell does: runs of whitespace separate
+//! them, quotes keep whitespace inside one and a backslash takes the char after it as is.
+//! Inside double quotes a backslash starts an escape instead, see `escape`
+
+use crate::escape::escape;
+
+/// One argument of a command line
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    /// The argument with its quotes and escapes taken out. Escapes like `\xFF` can make it
+    /// anything, not just UTF-8
+    pub bytes: Vec<u8>,
+    /// Whether any of it was in quotes, so `find "cafe"` can tell text from hex
+    pub quoted: bool,
+}
+
+impl Token {
+    /// The argument as text, for paths, names and numbers. Bytes that aren't UTF-8 come out as �
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.bytes).into_owned()
+    }
+
+    fn push(&mut self, c: char) {
+        let mut buffer = [0; 4];
+        self.bytes
+            .extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+    }
+}
+
+/// Where the tokenizer is within the line
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Quote {
+    None,
+    /// Inside "...", where the escapes of `escape` apply
+    Double,
+    /// Inside '...', where everything is taken as is
+    Single,
+}
+
+/// Splits a command line into its arguments.
+///
+/// Outside quotes a backslash makes the next char part of the argument, whitespace included.
+/// Inside double quotes a backslash starts one of the escapes `help syntax` lists, like `\x00`
+/// or `\"`. Single quotes keep everything up to the closing quote. A quote left open, an invalid
+/// escape or a backslash at the very end is an error, with the column (1-based) where it starts
+pub fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut quote = Quote::None;
+    let mut quote_start = 0;
+    let mut chars = line.char_indices().enumerate();
+
+    while let Some((column, (index, c))) = chars.next() {
+        match (quote, c) {
+            (Quote::None, c) if c.is_whitespace() => tokens.extend(current.take()),
+            (Quote::None, '"') | (Quote::None, '\'') => {
+                quote = if c == '"' {
+                    Quote::Double
+                } else {
+                    Quote::Single
+                };
+                quote_start = column;
+                token(&mut current).quoted = true;
+            }
+            (Quote::None, '\\') => match chars.next() {
+                Some((_, (_, escaped))) => token(&mut current).push(escaped),
+                None => {
+                    return Err(format!(
+                        "nothing after the backslash at column {}",
+                        column + 1
+                    ))
+                }
+            },
+            (Quote::Double, '"') | (Quote::Single, '\'') => quote = Quote::None,
+            (Quote::Double, '\\') => {
+                let after = &line[index + 1..];
+
+                // The quote is still open, which is the error to report
+                if after.is_empty() {
+                    break;
+                }
+
+                let (byte, taken) =
+                    escape(after).map_err(|e| format!("{} at column {}", e, column + 1))?;
+                token(&mut current).bytes.push(byte);
+                chars.nth(taken - 1);
+            }
+            (_, c) => token(&mut current).push(c),
+        }
+    }
+
+    match quote {
+        Quote::None => {
+            tokens.extend(current);
+            Ok(tokens)
+        }
+        Quote::Double => Err(format!("unterminated \" at column {}", quote_start + 1)),
+        Quote::Single => Err(format!("unterminated ' at column {}", quote_start + 1)),
+    }
+}
+
+/// The token being built, started if there isn't one yet
+fn token(current: &mut Option<Token>) -> &mut Token {
+    current.get_or_insert_with(|| Token {
+        bytes: Vec::new(),
+        quoted: false,
+    })
+}
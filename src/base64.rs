@@ -0,0 +1,115 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Base64 for `b64`. Encoding writes the standard alphabet with `=` padding. Decoding takes
+//! the standard and the URL-safe alphabet alike, even mixed, skips whitespace so wrapped text
+//! decodes, and doesn't need the padding
+
+use std::fmt;
+
+/// The standard alphabet, `+` and `/` for 62 and 63
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Why some text isn't base64, and where
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Base64Error {
+    /// Index of the byte of the text that is wrong
+    pub position: usize,
+    /// What is wrong with it
+    pub reason: &'static str,
+}
+
+impl fmt::Display for Base64Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {:X}", self.reason, self.position)
+    }
+}
+
+/// Encodes bytes as base64, padded with `=` to a multiple of four characters
+pub fn encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (index, byte)| {
+            group | (*byte as u32) << (16 - 8 * index)
+        });
+
+        // Three bytes make four characters, one or two left at the end make two or three
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => text.push(ALPHABET[(group >> (18 - 6 * index) & 0x3F) as usize] as char),
+                false => text.push('='),
+            }
+        }
+    }
+
+    text
+}
+
+/// The value of a character in either alphabet
+fn value(c: u8) -> Option<u32> {
+    let value = match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' | b'-' => 62,
+        b'/' | b'_' => 63,
+        _ => return None,
+    };
+    Some(value as u32)
+}
+
+/// Decodes base64 text. Fails at the first character that is neither in an alphabet nor
+/// whitespace, at padding where nothing is left to pad, and when the text ends a character
+/// into a group, which can't hold a whole byte
+pub fn decode(text: &[u8]) -> Result<Vec<u8>, Base64Error> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    // The characters of the group being read, and the `=` after them
+    let (mut group, mut count, mut padding) = (0u32, 0, 0);
+    let mut last = 0;
+
+    for (position, &c) in text.iter().enumerate() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        last = position;
+
+        let error = |reason| Err(Base64Error { position, reason });
+        match (c, value(c)) {
+            // Padding fills out the last group, which takes at least two characters
+            (b'=', _) if count < 2 || count + padding == 4 => {
+                return error("padding where there is nothing to pad")
+            }
+            (b'=', _) => padding += 1,
+            (_, None) => return error("not a base64 character"),
+            (_, Some(_)) if padding > 0 => return error("data after the padding"),
+            (_, Some(value)) => {
+                group = group << 6 | value;
+                count += 1;
+                if count == 4 {
+                    bytes.extend_from_slice(&group.to_be_bytes()[1..]);
+                    (group, count) = (0, 0);
+                }
+            }
+        }
+    }
+
+    match count {
+        0 => {}
+        1 => {
+            return Err(Base64Error {
+                position: last,
+                reason: "one character more than whole bytes take",
+            })
+        }
+        // Two characters hold a byte and three hold two, the bits left over are dropped
+        _ => {
+            let group = group << (6 * (4 - count));
+            bytes.extend_from_slice(&group.to_be_bytes()[1..count]);
+        }
+    }
+
+    Ok(bytes)
+}
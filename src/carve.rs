@@ -0,0 +1,276 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Carving, finding every region of a buffer that starts with a signature, for `carve`. A region
+//! runs for a fixed length or up to an end marker, and presets know the markers of a few
+//! formats, so `carve jpeg out/` finds the JPEGs in a disk image
+
+use crate::search::{Finder, Pattern};
+
+/// Where a carved region ends
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Extent {
+    /// A fixed number of bytes from the start of the signature
+    Length(usize),
+    /// At the first end marker after the signature, including the marker and `trailing`
+    /// more bytes after it
+    End {
+        /// The bytes the region ends with
+        marker: Pattern,
+        /// Bytes after the marker that belong to the region, like the rest of a zip's end record
+        trailing: usize,
+    },
+}
+
+/// What was found at a match of the signature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// A whole region
+    Whole,
+    /// A region of fixed length cut off by the end of the buffer, what there is of it
+    CutShort,
+    /// No end marker after the signature, there is nothing to carve
+    NoEnd,
+    /// The signature is inside the region carved at this offset, so it is left out
+    Inside(usize),
+}
+
+/// A match of the signature and the region carved there
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// Where the signature starts
+    pub offset: usize,
+    /// How many bytes the region has, 0 when nothing is carved
+    pub length: usize,
+    /// What became of the match
+    pub status: Status,
+}
+
+impl Region {
+    /// Whether the region has bytes to write out
+    pub fn carved(&self) -> bool {
+        matches!(self.status, Status::Whole | Status::CutShort)
+    }
+}
+
+/// A format `carve` knows the markers of
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Preset {
+    /// What it is asked for by
+    pub name: &'static str,
+    /// The signature, in the hex of `Pattern::parse_hex`
+    pub start: &'static str,
+    /// The end marker, in the same hex
+    pub end: &'static str,
+    /// Bytes after the end marker that are part of the file
+    pub trailing: usize,
+}
+
+/// The built-in presets
+pub const PRESETS: [Preset; 4] = [
+    // Start of image and the next end of image. A thumbnail inside a JPEG ends it early
+    Preset {
+        name: "jpeg",
+        start: "FF D8 FF",
+        end: "FF D9",
+        trailing: 0,
+    },
+    // The signature and the IEND chunk with its CRC
+    Preset {
+        name: "png",
+        start: "89 50 4E 47 0D 0A 1A 0A",
+        end: "49 45 4E 44 AE 42 60 82",
+        trailing: 0,
+    },
+    // GIF87a and GIF89a, up to the trailer after the last block
+    Preset {
+        name: "gif",
+        start: "47 49 46 38 ?? 61",
+        end: "00 3B",
+        trailing: 0,
+    },
+    // The first local file header and the end of central directory record, without a comment
+    Preset {
+        name: "zip",
+        start: "50 4B 03 04",
+        end: "50 4B 05 06",
+        trailing: 18,
+    },
+];
+
+impl Preset {
+    /// The preset with a name, in any case
+    pub fn find(name: &str) -> Option<Preset> {
+        PRESETS
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name))
+            .copied()
+    }
+
+    /// The signature and the extent to carve with
+    pub fn patterns(&self) -> (Pattern, Extent) {
+        let parse = |hex| Pattern::parse_hex(hex).expect("the presets are valid hex");
+        (
+            parse(self.start),
+            Extent::End {
+                marker: parse(self.end),
+                trailing: self.trailing,
+            },
+        )
+    }
+}
+
+/// Finds up to `limit` matches of the signature and the region of each. A match inside the
+/// region before it is left out, so a region is carved once however often the signature
+/// shows up in it
+pub fn carve(bytes: &[u8], start: &Pattern, extent: &Extent, limit: usize) -> Vec<Region> {
+    let mut carver = Carver::new(start, extent, limit);
+    while !carver.is_done(bytes.len()) {
+        carver.feed(bytes, usize::MAX);
+    }
+
+    carver.into_regions()
+}
+
+/// A carve that looks for the signature a chunk of the buffer at a time, so it can run a slice
+/// at a time and be stopped in between. Fed the whole buffer chunk by chunk, it finds what
+/// `carve` does
+pub struct Carver {
+    finder: Finder,
+    /// Bytes in the signature
+    width: usize,
+    extent: Extent,
+    /// The end marker's finder and how far past a match of it the region ends
+    end_finder: Option<(Finder, usize)>,
+    /// Where the end marker was last looked for and not found. It isn't after anything later
+    /// either, so the signatures that follow aren't searched to the end again
+    no_end_from: Option<usize>,
+    limit: usize,
+    regions: Vec<Region>,
+    /// Where the last carved region started and ended
+    last: Option<(usize, usize)>,
+    /// The first offset the signature wasn't looked for at yet
+    searched: usize,
+}
+
+impl Carver {
+    /// Starts a carve that stops after `limit` matches
+    pub fn new(start: &Pattern, extent: &Extent, limit: usize) -> Carver {
+        let end_finder = match extent {
+            Extent::Length(_) => None,
+            Extent::End { marker, trailing } => {
+                Some((Finder::masked(marker), marker.values.len() + trailing))
+            }
+        };
+
+        Carver {
+            finder: Finder::masked(start),
+            width: start.values.len(),
+            extent: extent.clone(),
+            end_finder,
+            no_end_from: None,
+            limit,
+            regions: Vec::new(),
+            last: None,
+            searched: 0,
+        }
+    }
+
+    /// Looks for the signature at the next `chunk` offsets of `bytes`, which has to be the same
+    /// buffer every time. The end of a region is looked for in the whole buffer
+    pub fn feed(&mut self, bytes: &[u8], chunk: usize) {
+        let bound = self.searched.saturating_add(chunk).min(bytes.len());
+        // A signature starting before the bound can run past it
+        let window = &bytes[..bound
+            .saturating_add(self.width.saturating_sub(1))
+            .min(bytes.len())];
+
+        while !self.is_done(bytes.len()) {
+            let Some(offset) = self.finder.find_from(window, self.searched) else {
+                self.searched = bound;
+                break;
+            };
+            self.searched = offset + 1;
+            let region = self.region(bytes, offset);
+            self.regions.push(region);
+        }
+    }
+
+    /// The region at a match of the signature
+    fn region(&mut self, bytes: &[u8], offset: usize) -> Region {
+        if let Some((carved, _)) = self.last.filter(|(_, end)| offset < *end) {
+            return Region {
+                offset,
+                length: 0,
+                status: Status::Inside(carved),
+            };
+        }
+
+        let after = offset + self.width;
+        let (end, status) = match (&self.end_finder, &self.extent) {
+            (Some(_), _) if self.no_end_from.is_some_and(|from| from <= after) => {
+                (offset, Status::NoEnd)
+            }
+            (Some((end_finder, past)), _) => {
+                match end_finder
+                    .find_from(bytes, after)
+                    .map(|found| found + past)
+                    .filter(|end| *end <= bytes.len())
+                {
+                    Some(end) => (end, Status::Whole),
+                    None => {
+                        self.no_end_from = Some(after);
+                        (offset, Status::NoEnd)
+                    }
+                }
+            }
+            (None, Extent::Length(length)) => match offset.checked_add(*length) {
+                Some(end) if end <= bytes.len() => (end, Status::Whole),
+                _ => (bytes.len(), Status::CutShort),
+            },
+            (None, Extent::End { .. }) => (offset, Status::NoEnd),
+        };
+
+        let region = Region {
+            offset,
+            length: end - offset,
+            status,
+        };
+        if region.carved() {
+            self.last = Some((offset, end));
+        }
+        region
+    }
+
+    /// How many offsets the signature was looked for at
+    pub fn searched(&self) -> usize {
+        self.searched
+    }
+
+    /// Whether the carve got to the end of a buffer of `len` bytes or found `limit` matches
+    pub fn is_done(&self, len: usize) -> bool {
+        self.searched >= len || self.regions.len() >= self.limit
+    }
+
+    /// Whether the carve stopped at `limit` matches
+    pub fn is_full(&self) -> bool {
+        self.regions.len() >= self.limit
+    }
+
+    /// The regions found so far
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// The regions found, in order
+    pub fn into_regions(self) -> Vec<Region> {
+        self.regions
+    }
+}
+
+/// The name a carved region is written to, like carve_00001F00.bin
+pub fn file_name(offset: usize) -> String {
+    format!("carve_{:08X}.bin", offset)
+}
@@ -0,0 +1,333 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Checksums formats put in their headers: Adler-32, Fletcher-16 and -32, and any CRC that
+//! fits the Rocksoft model, up to 64 bits wide. Like the digests they are fed a chunk at a
+//! time, so they run as background jobs
+
+/// Largest prime below 65536, what Adler-32 sums are taken modulo
+const ADLER_MOD: u32 = 65521;
+
+/// Bytes Adler-32 can add up before its sums have to be reduced, so they don't overflow a u32
+const ADLER_RUN: usize = 5552;
+
+/// An Adler-32 checksum being worked out, as zlib puts it after the compressed data
+#[derive(Clone)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Default for Adler32 {
+    fn default() -> Adler32 {
+        Adler32::new()
+    }
+}
+
+impl Adler32 {
+    /// The checksum of nothing so far, 1
+    pub fn new() -> Adler32 {
+        Adler32 { a: 1, b: 0 }
+    }
+
+    /// Adds the bytes that follow the ones fed before
+    pub fn update(&mut self, bytes: &[u8]) {
+        for run in bytes.chunks(ADLER_RUN) {
+            for &byte in run {
+                self.a += u32::from(byte);
+                self.b += self.a;
+            }
+            self.a %= ADLER_MOD;
+            self.b %= ADLER_MOD;
+        }
+    }
+
+    /// The checksum of everything fed
+    pub fn value(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+/// A Fletcher-16 checksum being worked out, sums of bytes modulo 255
+#[derive(Clone, Default)]
+pub struct Fletcher16 {
+    sum1: u16,
+    sum2: u16,
+}
+
+impl Fletcher16 {
+    /// The checksum of nothing so far, 0
+    pub fn new() -> Fletcher16 {
+        Fletcher16::default()
+    }
+
+    /// Adds the bytes that follow the ones fed before
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.sum1 = (self.sum1 + u16::from(byte)) % 255;
+            self.sum2 = (self.sum2 + self.sum1) % 255;
+        }
+    }
+
+    /// The checksum of everything fed
+    pub fn value(&self) -> u16 {
+        (self.sum2 << 8) | self.sum1
+    }
+}
+
+/// A Fletcher-32 checksum being worked out, sums of little endian 16 bit words modulo 65535.
+/// An odd byte at the end is taken as a word with a zero high byte
+#[derive(Clone, Default)]
+pub struct Fletcher32 {
+    sum1: u32,
+    sum2: u32,
+    /// The low byte of a word split between two chunks
+    pending: Option<u8>,
+}
+
+impl Fletcher32 {
+    /// The checksum of nothing so far, 0
+    pub fn new() -> Fletcher32 {
+        Fletcher32::default()
+    }
+
+    /// Adds the bytes that follow the ones fed before
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        if let (Some(low), Some((&high, rest))) = (self.pending, bytes.split_first()) {
+            self.add(u16::from_le_bytes([low, high]));
+            self.pending = None;
+            bytes = rest;
+        }
+
+        let mut words = bytes.chunks_exact(2);
+        for word in &mut words {
+            self.add(u16::from_le_bytes([word[0], word[1]]));
+        }
+        if let [low] = words.remainder() {
+            self.pending = Some(*low);
+        }
+    }
+
+    fn add(&mut self, word: u16) {
+        self.sum1 = (self.sum1 + u32::from(word)) % 65535;
+        self.sum2 = (self.sum2 + self.sum1) % 65535;
+    }
+
+    /// The checksum of everything fed, a trailing odd byte included
+    pub fn value(&self) -> u32 {
+        let mut sums = self.clone();
+        if let Some(low) = sums.pending {
+            sums.add(u16::from(low));
+        }
+        (sums.sum2 << 16) | sums.sum1
+    }
+}
+
+/// The parameters of a CRC in the Rocksoft model, the way CRC catalogues list them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrcParams {
+    /// Bits in the CRC, 1 to 64
+    pub width: u32,
+    /// The polynomial without its top bit, like 0x1021
+    pub poly: u64,
+    /// What the register starts as
+    pub init: u64,
+    /// Whether each byte is fed least significant bit first
+    pub reflect_in: bool,
+    /// Whether the register is reversed before the final XOR
+    pub reflect_out: bool,
+    /// What the register is XORed with at the end
+    pub xorout: u64,
+}
+
+/// CRCs that can be asked for by name, with their parameters
+pub const CRC_PRESETS: [(&str, CrcParams); 4] = [
+    (
+        "crc32",
+        CrcParams {
+            width: 32,
+            poly: 0x04C11DB7,
+            init: 0xFFFFFFFF,
+            reflect_in: true,
+            reflect_out: true,
+            xorout: 0xFFFFFFFF,
+        },
+    ),
+    (
+        "crc32c",
+        CrcParams {
+            width: 32,
+            poly: 0x1EDC6F41,
+            init: 0xFFFFFFFF,
+            reflect_in: true,
+            reflect_out: true,
+            xorout: 0xFFFFFFFF,
+        },
+    ),
+    (
+        "crc16-ccitt",
+        CrcParams {
+            width: 16,
+            poly: 0x1021,
+            init: 0xFFFF,
+            reflect_in: false,
+            reflect_out: false,
+            xorout: 0,
+        },
+    ),
+    (
+        "crc16-arc",
+        CrcParams {
+            width: 16,
+            poly: 0x8005,
+            init: 0,
+            reflect_in: true,
+            reflect_out: true,
+            xorout: 0,
+        },
+    ),
+];
+
+impl CrcParams {
+    /// The parameters of a preset, like "crc32"
+    pub fn preset(name: &str) -> Option<CrcParams> {
+        CRC_PRESETS
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+            .map(|(_, params)| *params)
+    }
+
+    /// The bits a value of this width can have set
+    pub fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.width)
+    }
+
+    /// Makes sure the width is one a u64 register holds and the other values fit it
+    pub fn check(&self) -> Result<(), String> {
+        if !(1..=64).contains(&self.width) {
+            return Err(format!("width {} is not between 1 and 64", self.width));
+        }
+
+        for (name, value) in [
+            ("poly", self.poly),
+            ("init", self.init),
+            ("xorout", self.xorout),
+        ] {
+            if value & !self.mask() != 0 {
+                return Err(format!(
+                    "{name} {value:X} doesn't fit in {} bits",
+                    self.width
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// How the parameters are written out with a result
+    pub fn describe(&self) -> String {
+        let digits = self.width.div_ceil(4) as usize;
+        format!(
+            "width {} poly {:0digits$X} init {:0digits$X} xorout {:0digits$X} refin {} refout {}",
+            self.width, self.poly, self.init, self.xorout, self.reflect_in, self.reflect_out
+        )
+    }
+}
+
+/// A CRC being worked out a byte at a time through a table
+#[derive(Clone)]
+pub struct Crc {
+    params: CrcParams,
+    table: Box<[u64; 256]>,
+    /// Reflected CRCs keep the register in its low bits shifting right, the others keep it in
+    /// the top bits shifting left, so any width works a byte at a time
+    register: u64,
+}
+
+impl Crc {
+    /// A CRC of nothing so far, the parameters have to pass `CrcParams::check`
+    pub fn new(params: CrcParams) -> Crc {
+        let mut table = Box::new([0; 256]);
+
+        for (index, entry) in table.iter_mut().enumerate() {
+            *entry = match params.reflect_in {
+                true => {
+                    let poly = reflect(params.poly, params.width);
+                    (0..8).fold(index as u64, |value, _| match value & 1 {
+                        1 => (value >> 1) ^ poly,
+                        _ => value >> 1,
+                    })
+                }
+                false => {
+                    let poly = params.poly << (64 - params.width);
+                    (0..8).fold((index as u64) << 56, |value, _| match value >> 63 {
+                        1 => (value << 1) ^ poly,
+                        _ => value << 1,
+                    })
+                }
+            };
+        }
+
+        let register = match params.reflect_in {
+            true => reflect(params.init, params.width),
+            false => params.init << (64 - params.width),
+        };
+
+        Crc {
+            params,
+            table,
+            register,
+        }
+    }
+
+    /// Adds the bytes that follow the ones fed before
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.register = match self.params.reflect_in {
+                true => {
+                    (self.register >> 8)
+                        ^ self.table[((self.register ^ u64::from(byte)) & 0xFF) as usize]
+                }
+                false => {
+                    (self.register << 8)
+                        ^ self.table[((self.register >> 56) ^ u64::from(byte)) as usize]
+                }
+            };
+        }
+    }
+
+    /// The CRC of everything fed
+    pub fn value(&self) -> u64 {
+        let width = self.params.width;
+        // The register as an unreflected value of the width
+        let register = match self.params.reflect_in {
+            true => reflect(self.register, width),
+            false => self.register >> (64 - width),
+        };
+
+        let out = match self.params.reflect_out {
+            true => reflect(register, width),
+            false => register,
+        };
+        out ^ self.params.xorout
+    }
+
+    /// The parameters the CRC is worked out with
+    pub fn params(&self) -> &CrcParams {
+        &self.params
+    }
+}
+
+/// The CRC-32 of some bytes, the one gzip, zip and PNG use
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc::new(CrcParams::preset("crc32").expect("crc32 is a preset"));
+    crc.update(bytes);
+    crc.value() as u32
+}
+
+/// The low `width` bits of a value in reverse order
+fn reflect(value: u64, width: u32) -> u64 {
+    value.reverse_bits() >> (64 - width)
+}
@@ -0,0 +1,247 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Walking formats built from length-prefixed chunks, for `chunks`. A `Layout` says how one
+//! format lays out its chunks, so adding a format is adding a layout: PNG and MNG, RIFF (WAV,
+//! AVI, WebP) and IFF (AIFF) are built in
+
+use crate::{checksum::crc32, hex::bytes_to_hex, template::Endian};
+use std::ops::Range;
+
+/// How a format lays out its chunks. Every chunk has a 4 byte type and a 4 byte length of its
+/// data, in one order or the other, then the data
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// What the format is called
+    pub name: &'static str,
+    /// The bytes files of the format start with
+    pub signature: &'static [u8],
+    /// Where the first chunk starts, after the signature when it isn't a chunk itself
+    pub start: usize,
+    /// Byte order of the lengths
+    pub endian: Endian,
+    /// Whether the type comes before the length, like RIFF, or after it, like PNG
+    pub type_first: bool,
+    /// Whether a CRC-32 of the type and data follows the data, only with the length first
+    pub crc: bool,
+    /// Chunks start at a multiple of this many bytes, padded after odd lengths
+    pub align: usize,
+    /// Types whose data is a 4 byte form type followed by chunks of their own, like LIST
+    pub containers: &'static [&'static [u8]],
+    /// The type of the last chunk, like IEND, None when the chunks run to the end
+    pub end: Option<&'static [u8]>,
+}
+
+/// PNG images, chunks of big endian length, type, data and CRC after an 8 byte signature
+pub const PNG: Layout = Layout {
+    name: "PNG",
+    signature: b"\x89PNG\r\n\x1A\n",
+    start: 8,
+    endian: Endian::Big,
+    type_first: false,
+    crc: true,
+    align: 1,
+    containers: &[],
+    end: Some(b"IEND"),
+};
+
+/// MNG animations, laid out like PNG
+pub const MNG: Layout = Layout {
+    name: "MNG",
+    signature: b"\x8AMNG\r\n\x1A\n",
+    end: Some(b"MEND"),
+    ..PNG
+};
+
+/// RIFF files like WAV, AVI and WebP: one RIFF chunk holding all the others, little endian
+/// lengths and chunks padded to even offsets
+pub const RIFF: Layout = Layout {
+    name: "RIFF",
+    signature: b"RIFF",
+    start: 0,
+    endian: Endian::Little,
+    type_first: true,
+    crc: false,
+    align: 2,
+    containers: &[b"RIFF", b"LIST"],
+    end: None,
+};
+
+/// IFF files like AIFF, RIFF with big endian lengths
+pub const IFF: Layout = Layout {
+    name: "IFF",
+    signature: b"FORM",
+    endian: Endian::Big,
+    containers: &[b"FORM", b"LIST", b"CAT "],
+    ..RIFF
+};
+
+/// Every built-in layout, in the order they are tried
+pub const LAYOUTS: [Layout; 4] = [PNG, MNG, RIFF, IFF];
+
+/// A chunk found by `walk`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    /// Its 4 character type, like IHDR or fmt
+    pub kind: String,
+    /// Where the chunk starts, at its length or type
+    pub offset: usize,
+    /// How many bytes of data it says it has
+    pub length: usize,
+    /// How many containers it is in
+    pub depth: usize,
+    /// The form type at the start of a container's data, like WAVE
+    pub form: Option<String>,
+    /// Whether its CRC matches, None when the format has none or it was cut off
+    pub crc: Option<bool>,
+}
+
+/// What `walk` found
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Walk {
+    /// The name of the layout that was walked
+    pub format: &'static str,
+    /// Every chunk up to where the walk stopped, containers before what they hold
+    pub chunks: Vec<Chunk>,
+    /// Why the walk stopped before the end of the file, or what is left after the last chunk
+    pub note: Option<String>,
+}
+
+/// Walks the chunks of the first layout whose signature the bytes start with, None when no
+/// layout matches
+pub fn walk(bytes: &[u8]) -> Option<Walk> {
+    LAYOUTS
+        .iter()
+        .find(|layout| bytes.starts_with(layout.signature))
+        .map(|layout| walk_layout(layout, bytes))
+}
+
+/// Walks the chunks of the bytes as laid out by `layout`, stopping at the first one that
+/// doesn't make sense
+pub fn walk_layout(layout: &Layout, bytes: &[u8]) -> Walk {
+    let mut walk = Walk {
+        format: layout.name,
+        chunks: Vec::new(),
+        note: None,
+    };
+
+    walk.note = match walk_range(
+        layout,
+        bytes,
+        layout.start..bytes.len(),
+        0,
+        &mut walk.chunks,
+    ) {
+        Ok(end) if end < bytes.len() => Some(format!(
+            "{:X} more bytes after the last chunk, at {:X}",
+            bytes.len() - end,
+            end
+        )),
+        Ok(_) => None,
+        Err(note) => Some(note),
+    };
+
+    walk
+}
+
+/// Walks the chunks in `range`, returns where the last one ended or why the walk stopped
+fn walk_range(
+    layout: &Layout,
+    bytes: &[u8],
+    range: Range<usize>,
+    depth: usize,
+    chunks: &mut Vec<Chunk>,
+) -> Result<usize, String> {
+    let mut at = range.start;
+
+    while at < range.end {
+        let Some(header) = bytes.get(at..at + 8).filter(|_| at + 8 <= range.end) else {
+            return Err(format!("the chunk header at {at:X} is cut short"));
+        };
+        let (kind, length) = match layout.type_first {
+            true => (&header[..4], &header[4..]),
+            false => (&header[4..], &header[..4]),
+        };
+
+        // Types are letters and digits, RIFF pads short ones with spaces
+        if !kind
+            .iter()
+            .all(|byte| byte.is_ascii_alphanumeric() || *byte == b' ')
+        {
+            return Err(format!(
+                "the chunk at {at:X} has no type, {} instead",
+                bytes_to_hex(kind)
+            ));
+        }
+
+        let length = read_length(length, layout.endian);
+        let data = at + 8;
+        let data_end = data.saturating_add(length);
+        let chunk_end = data_end.saturating_add(if layout.crc { 4 } else { 0 });
+        let kind_name = String::from_utf8_lossy(kind).trim_end().to_string();
+
+        let mut chunk = Chunk {
+            kind: kind_name.clone(),
+            offset: at,
+            length,
+            depth,
+            form: None,
+            crc: None,
+        };
+
+        let container = layout.containers.contains(&kind);
+        if container && length >= 4 && data + 4 <= range.end {
+            chunk.form = Some(
+                String::from_utf8_lossy(&bytes[data..data + 4])
+                    .trim_end()
+                    .to_string(),
+            );
+        }
+
+        if chunk_end > range.end {
+            let form = chunk.form.is_some();
+            chunks.push(chunk);
+
+            // What a cut short container holds is still worth walking, up to where it stops
+            if form {
+                walk_range(layout, bytes, data + 4..range.end, depth + 1, chunks)?;
+            }
+            return Err(format!(
+                "the {kind_name} chunk at {at:X} runs past the end, {length:X} bytes long"
+            ));
+        }
+
+        if layout.crc {
+            let stored = read_length(&bytes[data_end..chunk_end], Endian::Big);
+            chunk.crc = Some(stored as u32 == crc32(&bytes[at + 4..data_end]));
+        }
+
+        let form = chunk.form.is_some();
+        chunks.push(chunk);
+
+        if form {
+            walk_range(layout, bytes, data + 4..data_end, depth + 1, chunks)?;
+        }
+
+        // The padding of the last chunk may be missing
+        at = chunk_end
+            .next_multiple_of(layout.align.max(1))
+            .min(range.end);
+
+        if layout.end == Some(kind) {
+            return Ok(at);
+        }
+    }
+
+    Ok(at)
+}
+
+fn read_length(bytes: &[u8], endian: Endian) -> usize {
+    let fold = |value: usize, byte: &u8| value << 8 | usize::from(*byte);
+    match endian {
+        Endian::Little => bytes.iter().rev().fold(0, fold),
+        Endian::Big => bytes.iter().fold(0, fold),
+    }
+}
@@ -0,0 +1,5152 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+use crate::{
+    editor::{EditorState, OpenSettings, BYTES_PER_LINE},
+    terminal::{release_lock, terminal_size, Input},
+};
+use hex_it::{
+    args::{tokenize, Token},
+    base64, bytes_to_hex,
+    carve::{self, Carver, Extent, Preset, Region, Status},
+    checksum::{Adler32, Crc, CrcParams, Fletcher16, Fletcher32},
+    chunks::{self, LAYOUTS},
+    compare::{compare_ranges, next_difference, prev_difference, range_differs},
+    config::searches_path,
+    conv::{describe, describe_position, parse_value},
+    cyclic::{cyclic, cyclic_find, DEFAULT_ORDER, FIND_LIMIT},
+    digest::{Entropy, Sha256},
+    dump::{render_line, write_dump, write_html, write_json, write_markdown, MARKDOWN_ROWS},
+    editlog::CSV_HEADER,
+    entmap::{self, EntropyMap},
+    escape::ESCAPES,
+    file::{self, FileKind},
+    filter::{self, shell},
+    gzip, headers, hex_to_bytes, histogram,
+    inspect::{table, MAX_WIDTH},
+    job::{Job, JobEvent, Task},
+    keymap::Keymap,
+    keymap::Mode,
+    labels::{common_prefix, Labels},
+    line::InputLine,
+    magic::scan,
+    matches::{MatchFormat, MatchList},
+    offset::{evaluate, Variables},
+    parse_offset, parse_size, patchfile,
+    progress::Progress,
+    random::{system_seed, SplitMix64},
+    recover::Recovery,
+    registers::{parse_register, Registers, UNNAMED},
+    runs::longest_runs,
+    screen::{Frame, Listing},
+    search::{find_other, float_bytes, rfind_other, ChunkedSearch, Finder, FloatScan, Pattern},
+    searches::{SearchEntry, SearchHistory},
+    session::{BufferSession, Session},
+    stride::Stride,
+    strings::{find_strings, unit_start, utf16_bytes, Encoding},
+    suggest::{did_you_mean, suggestions},
+    template::{Endian, Template},
+    theme::{describe_color, parse_color, Marks, Theme, PALETTE_NAMES},
+    DumpOptions,
+};
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// How much of the buffer a search feeds the finder at once
+const SEARCH_CHUNK: usize = 4 << 20;
+
+/// How long a search runs before the main loop gets to draw its progress and check for Esc
+const SEARCH_SLICE: Duration = Duration::from_millis(100);
+
+/// Most matches a search keeps for n and N to jump through
+const MAX_MATCHES: usize = 100_000;
+
+/// Most runs of text `strings` lists
+const MAX_STRINGS: usize = 10_000;
+
+/// Differences `cmp` lists unless given -n
+const CMP_LISTED: usize = 100;
+
+/// Bytes of a field `template show` lists before cutting them short
+const TEMPLATE_RAW: usize = 8;
+
+/// Width of the bytes column of `template show`, room for TEMPLATE_RAW bytes and a `…`
+const TEMPLATE_RAW_WIDTH: usize = TEMPLATE_RAW * 3 + 1;
+
+/// Where `session save` and `session load` go without a path
+pub const DEFAULT_SESSION_PATH: &str = "hex_it.session";
+
+/// Exit code used when the editor is interrupted with Ctrl+C
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// An interactive step a command is waiting on before it can finish
+#[derive(Clone, Copy)]
+pub enum Pending {
+    /// `get` is waiting for the replacement hex of this line
+    EditLine(usize),
+    /// Quitting with this exit code is waiting for confirmation to discard unsaved changes
+    ConfirmQuit(i32),
+    /// `reload` is waiting for confirmation to discard unsaved changes
+    ConfirmReload,
+    /// `save` found the file changed on disk and is waiting for overwrite, reload or cancel.
+    /// Holds whether to compress, as for `save`
+    SaveConflict(Option<bool>),
+    /// `save` of a decompressed buffer over its gzip file is waiting for whether to compress it
+    /// again, write the raw bytes or cancel. Holds whether the save was forced
+    ConfirmCompress(bool),
+    /// `close` is waiting for confirmation to discard unsaved changes
+    ConfirmClose,
+    /// The buffer just opened has a journal from a session that didn't end, waiting for
+    /// whether to replay the edits in it, delete it or leave it
+    Replay,
+    /// The buffer just opened has a recovery file from a session that didn't end, waiting for
+    /// whether to restore the changes in it, delete it or leave it
+    Recover,
+    /// A listing is on screen until it is paged through or closed
+    Continue,
+}
+
+/// A `find` that is still going through the buffer
+struct RunningSearch {
+    search: ChunkedSearch,
+    /// How much of the buffer was searched
+    progress: Progress,
+    /// What is being looked for, as typed
+    needle: String,
+}
+
+/// A carve still looking for its signature, run a slice at a time like a search
+struct RunningCarve {
+    carver: Carver,
+    /// How much of the buffer was looked through
+    progress: Progress,
+    /// Where the regions are written once it's done
+    dir: PathBuf,
+    /// Whether files already in `dir` are replaced
+    force: bool,
+}
+
+/// Runs what is typed on the command line against the editor
+pub struct CommandLine {
+    /// The buffer on screen
+    pub editor: EditorState,
+    /// The other open buffers in order, without the one on screen
+    others: Vec<EditorState>,
+    /// Where the buffer on screen sits among all of them
+    current: usize,
+    /// Bytes yanked from any buffer, so they can be put into another
+    registers: Registers,
+    /// How `open` reads files, the same way as the file from the command line
+    settings: OpenSettings,
+    /// A search that didn't finish within one slice, the main loop keeps it going
+    search: Option<RunningSearch>,
+    /// A carve that didn't finish within one slice, kept going the same way
+    carving: Option<RunningCarve>,
+    /// The searches run before, read from the searches file the first time they are needed
+    searches: Option<SearchHistory>,
+    /// The command line being run, what goes into the search history and the edit log
+    command: String,
+    /// A hash or entropy scan running in the background, one at a time
+    job: Option<Job>,
+    pub keymap: Keymap,
+    command_names: Vec<(String, String)>,
+    pub pending: Option<Pending>,
+    /// Help or another listing drawn over the dump while `Pending::Continue` waits
+    listing: Option<Listing>,
+    /// Set when the editor should close with this exit code
+    pub exit_code: Option<i32>,
+}
+
+impl CommandLine {
+    pub fn new(editor: EditorState, keymap: Keymap, settings: OpenSettings) -> CommandLine {
+        let commands = [
+            (
+                "help",
+                "Prints the help menu, help syntax explains quoting and escapes",
+            ),
+            ("quit", "Quit the menu"),
+            ("get", "Get a line of hex and be able to edit it."),
+            ("save", "Save the file"),
+            ("open", "open <file>: open another file in a new buffer"),
+            (
+                "new",
+                "new <size> [<fill byte>]: a new buffer of zeros or the fill byte, size like 512, 0x200 or 4k",
+            ),
+            (
+                "export",
+                "export json <file>, export dump <file> [--ansi] [<offset> <length>], export html <file> [<offset> <length>] or export md <file> [<offset> <length>] [-f] [--fenced]: write the dump to a file",
+            ),
+            (
+                "find",
+                "find <hex>, find [-u16|-u16be] \"text\" or find -f32|-f64 <value> [~<tolerance>] [-be]: find every match, n/N jump between them. ?? matches any byte, find alone runs the last search again",
+            ),
+            (
+                "runs",
+                "runs [<byte>] [-n <count>]: list the 16 or count longest runs of the byte, or of any byte repeated, goto #<n> jumps to one",
+            ),
+            (
+                "strings",
+                "strings [-u16|-u16be] [-n <min>]: list the runs of ASCII or UTF-16 text, 4 characters or longer, goto #<n> jumps to one",
+            ),
+            (
+                "findb",
+                "findb <pattern>: find bytes with wildcard bits, like findb 1010xxxx 1F or findb A0/F0 Ax",
+            ),
+            (
+                "searches",
+                "searches [<n>]: list the searches run before, latest first, or run search n again",
+            ),
+            ("buffers", "List the open buffers"),
+            (
+                "session",
+                "session save [<file>] or load [<file>]: remember the open files and positions",
+            ),
+            (
+                "patchfile",
+                "patchfile apply <file> or create <file>: write 'offset: hex' lines over the buffer, or save the unsaved changes as them",
+            ),
+            ("buffer", "buffer <n|name>: switch to another buffer"),
+            ("close", "Close the current buffer"),
+            (
+                "yank",
+                "yank [<register>] [<offset> <length>]: copy the selection or a range, into register a to z when one is named",
+            ),
+            (
+                "put",
+                "put [<register>] [<offset>]: write the unnamed register, or one from a to z, over the bytes from the cursor or an offset",
+            ),
+            ("registers", "List the registers with their size and first bytes"),
+            ("hash", "hash sha256: hash the buffer in the background, Esc to stop"),
+            (
+                "checksum",
+                "checksum adler32|fletcher16|fletcher32|crc32|crc32c|crc <params> [<offset> <length>]: checksum the selection, a range or the buffer",
+            ),
+            (
+                "histogram",
+                "histogram [<offset> <length>] [<buckets>] or --per-byte [<n>]: chart how often byte values come up",
+            ),
+            (
+                "entmap",
+                "entmap [<block size>]: map the entropy of every block of the buffer in the background, goto block <n> jumps to one",
+            ),
+            (
+                "entropy",
+                "Work out the entropy of the buffer in the background, Esc to stop",
+            ),
+            ("save!", "Save the file even if it changed on disk"),
+            ("reload", "Read the file from disk again, dropping unsaved changes"),
+            (
+                "decompress",
+                "Show what is inside a gzip compressed buffer instead of its bytes",
+            ),
+            ("bindings", "List the active key bindings"),
+            (
+                "edit",
+                "Type hex over the grid from the cursor, or from a hex offset",
+            ),
+            (
+                "tedit",
+                "Type text over the text column from the cursor, or from a hex offset",
+            ),
+            ("undo", "Undo the last change"),
+            (
+                "compare",
+                "compare <path>: show another file under this one, read-only. compare next|prev|off",
+            ),
+            (
+                "extract",
+                "extract [<offset> <length>] <file> [-f]: write the selection or a range to a new file",
+            ),
+            (
+                "carve",
+                "carve <signature> <length> <dir> [-f], carve <signature> to <end> <dir> or carve jpeg|png|gif|zip <dir>: write every region starting with a signature to a file",
+            ),
+            (
+                "insert-file",
+                "insert-file <path> [<offset>]: insert another file's bytes at the offset or the cursor",
+            ),
+            (
+                "read-file",
+                "read-file <path> [<offset>] [--extend]: write another file's bytes over the ones at the offset or the cursor",
+            ),
+            (
+                "settext",
+                "settext <offset> \"text\" [-z] [--extend]: write text over the bytes at an offset, -z adds a NUL",
+            ),
+            (
+                "changes",
+                "List every change since the last save, changes export <file> writes them as CSV",
+            ),
+            (
+                "log",
+                "log [--iso]: list every edit of the session with its time and command, undone ones too, log export <file> writes them as CSV",
+            ),
+            (
+                "matches",
+                "matches export <file> [--format csv|json]: write the matches of the last search with their offsets and the bytes around them",
+            ),
+            (
+                "goto",
+                "goto <offset>, goto <label> or goto #<n>: move the cursor to an offset, like goto sel.start + 0d16, a label, or to change n (entry n after headers or chunks)",
+            ),
+            (
+                "labels",
+                "labels [load <file> [--base <address>] | clear]: list the labels, read them from a symbol or map file to show at their rows, Tab completes them after goto",
+            ),
+            (
+                "skip",
+                "skip [-b]: move the cursor past the run of the byte under it, -b backwards (s and S)",
+            ),
+            (
+                "skipzero",
+                "skipzero [-b]: move the cursor to the next byte that isn't 00, -b backwards",
+            ),
+            (
+                "skipff",
+                "skipff [-b]: move the cursor to the next byte that isn't FF, -b backwards",
+            ),
+            (
+                "pos",
+                "pos [<offset>]: the cursor or an offset in hex, decimal and octal, its line and column, the byte there and how far through the file it is",
+            ),
+            (
+                "calc",
+                "calc <expression>: work out + - * / % with hex, 0d decimal, cursor, len, sel.start and sel.len",
+            ),
+            (
+                "view",
+                "view [-c] <offset|cursor>: scroll an offset to the top, or the middle with -c, leaving the cursor",
+            ),
+            (
+                "swap",
+                "swap <offset> <offset> <length>: exchange two ranges of the same length",
+            ),
+            (
+                "cmp",
+                "cmp <offset> <offset> <length> [-n <count>] [-g]: compare two ranges, list the first differences or with -g jump to the first",
+            ),
+            (
+                "move",
+                "move <offset> <length> <destination>: move a range in front of the byte now at the destination",
+            ),
+            (
+                "randfill",
+                "randfill [<offset> <length>] [<seed>]: fill a range or the selection with random bytes, the same seed always gives the same bytes",
+            ),
+            (
+                "b64",
+                "b64 encode|decode [<start> <end>] [replace]: show the selection or the bytes from start up to end as base64, or decode them from base64 and with replace write the result in their place",
+            ),
+            (
+                "cyclic",
+                "cyclic fill [<offset> <length>] [-n <order>] or cyclic find <hex bytes>: write a pwntools cyclic pattern over a range or the selection, or find where bytes are in it",
+            ),
+            (
+                "resize",
+                "resize <size> [<fill byte>]: cut the end off or pad with zeros or the fill byte",
+            ),
+            (
+                "pad",
+                "pad <alignment> [<fill byte>] or pad --to <size> [<fill byte>]: append FF or the fill byte up to a multiple of the alignment or a size",
+            ),
+            (
+                "trim",
+                "trim [<byte>] [--keep <n>] [-f]: cut the run of 00 or the byte off the end, -f to empty a buffer of nothing else",
+            ),
+            (
+                "reverse",
+                "reverse [<offset> <length>]: reverse the order of the bytes in a range or the selection",
+            ),
+            (
+                "!",
+                "! <command>: replace the selection with what a shell command prints when fed it, like ! tr a-z A-Z",
+            ),
+            ("!!", "!! <command>: show what a shell command prints, fed the selection if there is one"),
+            (
+                "template",
+                "template [load] <file.toml> [<offset>], show, set <field> <value> or clear: decode the file with a structure template",
+            ),
+            ("detect", "Guess the file type from its magic number"),
+            (
+                "stride",
+                "stride <record size> [<field offset>] [--base <offset>] or stride off: tint one field of every record of an array, records starting at the base",
+            ),
+            (
+                "colset",
+                "colset <record size> <field offset> <hex> [<start> [<count>]]: write the bytes at the field offset of every record in the selection or from start, in one undo step",
+            ),
+            (
+                "hlbyte",
+                "hlbyte on|off: underline the bytes on screen equal to the one under the cursor",
+            ),
+            (
+                "inspect",
+                "inspect [<offset>]: the bytes at the cursor or an offset as every size of number, both byte orders",
+            ),
+            (
+                "inspector",
+                "inspector on|off: decode the bytes at the cursor on a line above the command line as it moves",
+            ),
+            (
+                "gutter",
+                "gutter on|off: show or hide the offsets at the start of the dump lines",
+            ),
+            (
+                "palette",
+                "palette show|reset or palette set <name>=<color>...: change the colors of the dump, like palette set zero=dim modified=bold+red",
+            ),
+            (
+                "squeeze",
+                "squeeze on|off: show runs of identical lines as a single * row, like xxd",
+            ),
+            (
+                "encoding",
+                "encoding [ascii|utf16le|utf16be] [even|odd]: read the text column as bytes or UTF-16, with code units at even or odd offsets",
+            ),
+            (
+                "conv",
+                "conv <value>: show a number in hex, decimal, octal and binary and as bytes, like conv 0x1F or conv -2",
+            ),
+            (
+                "filetype",
+                "List known signatures found anywhere in the file, n/N jump between them",
+            ),
+            (
+                "headers",
+                "List the headers, segments and sections of an ELF or PE file, goto #<n> jumps to one",
+            ),
+            (
+                "chunks",
+                "List the chunks of a PNG, RIFF or IFF file and check their CRCs, goto #<n> jumps to one",
+            ),
+        ];
+
+        CommandLine {
+            editor,
+            others: Vec::new(),
+            current: 0,
+            registers: Registers::default(),
+            settings,
+            search: None,
+            carving: None,
+            searches: None,
+            command: String::new(),
+            job: None,
+            keymap,
+            command_names: commands
+                .iter()
+                .map(|(name, description)| (name.to_string(), description.to_string()))
+                .collect(),
+            pending: None,
+            listing: None,
+            exit_code: None,
+        }
+    }
+
+    /// The text shown in front of the user's input
+    pub fn prompt(&self) -> &'static str {
+        match (self.pending, self.editor.mode) {
+            (Some(Pending::EditLine(_)), _) => "New hex: ",
+            (Some(Pending::ConfirmQuit(_)), _) => "Unsaved changes, quit anyway? (y/n) ",
+            (Some(Pending::ConfirmReload), _) => "Unsaved changes, reload anyway? (y/n) ",
+            (Some(Pending::ConfirmClose), _) => "Unsaved changes, close anyway? (y/n) ",
+            (Some(Pending::SaveConflict(_)), _) => {
+                "File changed on disk: (o)verwrite, (r)eload or (c)ancel? "
+            }
+            (Some(Pending::ConfirmCompress(_)), _) => {
+                "Decompressed from gzip: save (g)zipped again, (r)aw bytes or (c)ancel? "
+            }
+            (Some(Pending::Replay), _) => "Edits from before: (r)eplay, (d)elete or (l)eave them? ",
+            (Some(Pending::Recover), _) => {
+                "Unsaved changes from before: (r)estore, (d)elete or (l)eave them? "
+            }
+            (Some(Pending::Continue), _) => "Press any key to continue",
+            (None, Mode::Command) => ":",
+            (None, Mode::Normal) | (None, Mode::Overwrite) | (None, Mode::Text) => "",
+        }
+    }
+
+    /// Draws the listing waiting to be dismissed, or else the buffer with the prompt and input
+    pub fn draw(&mut self, input: &InputLine) {
+        let Some(listing) = &self.listing else {
+            return self.editor.draw(self.prompt(), input);
+        };
+
+        let (width, height) = terminal_size();
+        let mut frame = Frame::new(width, height);
+        listing.draw(&mut frame);
+
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(frame.as_str().as_bytes()).unwrap();
+        stdout.flush().unwrap();
+    }
+
+    /// Shows lines over the dump until a key is pressed, a page at a time if they don't fit
+    fn show_listing(&mut self, lines: Vec<String>) {
+        self.listing = Some(Listing::new(lines));
+        self.pending = Some(Pending::Continue);
+    }
+
+    /// Turns the page of the listing on screen, closing it after the last page or when `close`
+    /// is set. The dump comes back as it was, nothing underneath was touched
+    pub fn page_listing(&mut self, close: bool) {
+        let rows = terminal_size().1.saturating_sub(1);
+
+        if let Some(listing) = &mut self.listing {
+            if !close && listing.next_page(rows) {
+                return;
+            }
+        }
+
+        self.cancel();
+        self.editor.mode = Mode::Normal;
+    }
+
+    /// Quits right away, or asks first if any buffer has unsaved changes
+    pub fn request_quit(&mut self, code: i32) {
+        let modified = self
+            .others
+            .iter()
+            .filter(|editor| editor.modified())
+            .map(|editor| editor.name())
+            .collect::<Vec<String>>();
+
+        if !modified.is_empty() {
+            self.editor.message = format!("Unsaved changes in {}", modified.join(", "));
+        }
+
+        if modified.is_empty() && !self.editor.modified() {
+            self.exit_code = Some(code);
+        } else {
+            self.pending = Some(Pending::ConfirmQuit(code));
+        }
+    }
+
+    /// Looks at every buffer's file after a watcher saw one of them change
+    pub fn check_disk(&mut self) {
+        self.editor.check_disk();
+        for editor in &mut self.others {
+            editor.check_disk();
+        }
+    }
+
+    /// How many buffers are open
+    fn buffer_count(&self) -> usize {
+        self.others.len() + 1
+    }
+
+    /// The buffer at an index counting every buffer, the one on screen included
+    fn buffer(&self, index: usize) -> &EditorState {
+        match index.cmp(&self.current) {
+            std::cmp::Ordering::Less => &self.others[index],
+            std::cmp::Ordering::Equal => &self.editor,
+            std::cmp::Ordering::Greater => &self.others[index - 1],
+        }
+    }
+
+    /// Puts another buffer on screen, the one shown so far keeps its place in the list
+    fn switch_buffer(&mut self, index: usize) {
+        if index == self.current || index >= self.buffer_count() {
+            return;
+        }
+
+        let other = if index < self.current {
+            index
+        } else {
+            index - 1
+        };
+        let previous = std::mem::replace(&mut self.editor, self.others.remove(other));
+
+        let position = if self.current < index {
+            self.current
+        } else {
+            self.current - 1
+        };
+        self.others.insert(position, previous);
+        self.current = index;
+
+        self.update_positions();
+    }
+
+    /// Tells every buffer where it is for the status bar, nothing while there is only one
+    fn update_positions(&mut self) {
+        let count = self.buffer_count();
+
+        for index in 0..count {
+            let position = (count > 1).then_some((index + 1, count));
+
+            match index.cmp(&self.current) {
+                std::cmp::Ordering::Less => self.others[index].position = position,
+                std::cmp::Ordering::Equal => self.editor.position = position,
+                std::cmp::Ordering::Greater => self.others[index - 1].position = position,
+            }
+        }
+    }
+
+    /// Finds an open buffer by its 1-based number, its path or its file name
+    fn find_buffer(&self, name: &str) -> Option<usize> {
+        if let Ok(number) = name.parse::<usize>() {
+            return (1..=self.buffer_count())
+                .contains(&number)
+                .then_some(number - 1);
+        }
+
+        let path = Path::new(name);
+        (0..self.buffer_count()).find(|&index| {
+            let editor = self.buffer(index);
+            !editor.is_unnamed()
+                && (editor.file == path || editor.file.file_name() == Some(path.as_os_str()))
+        })
+    }
+
+    /// Opens a file in a new buffer, or switches to it if it is open already
+    fn open_buffer(&mut self, path: &Path) -> Result<(), String> {
+        // Two buffers of one file would fight over its lock and overwrite each other's saves
+        let canonical = fs::canonicalize(path).ok();
+        if let Some(index) = (0..self.buffer_count()).find(|&index| {
+            canonical.is_some() && fs::canonicalize(&self.buffer(index).file).ok() == canonical
+        }) {
+            self.switch_buffer(index);
+            return Ok(());
+        }
+
+        let mut editor = EditorState::open(path.to_path_buf(), &self.settings)?;
+        editor.mode = self.editor.mode;
+        editor.same_bytes = self.editor.same_bytes;
+        editor.inspector = self.editor.inspector;
+        editor.dump.squeeze = self.editor.dump.squeeze;
+        self.others.push(editor);
+        self.switch_buffer(self.buffer_count() - 1);
+
+        Ok(())
+    }
+
+    /// The `open` command
+    fn open(&mut self, path: &str) {
+        if path.is_empty() {
+            return self.editor.fail("No file specified.");
+        }
+
+        match self.open_buffer(Path::new(path)) {
+            Ok(()) => self.offer_recovery(),
+            Err(e) => self.editor.fail(e),
+        }
+    }
+
+    /// The `new` command, opens a buffer of `size` bytes of `fill` that isn't a file yet
+    fn new_buffer(&mut self, args: &[String]) {
+        let usage = "Usage: new <size> [<fill byte>], like new 512 or new 4k FF";
+        let (Some(size), None) = (args.first(), args.get(2)) else {
+            return self.editor.fail(usage);
+        };
+
+        let size = match parse_size(size) {
+            Ok(size) => size,
+            Err(e) => return self.editor.fail(format!("Invalid size '{size}': {e}")),
+        };
+
+        let fill = match parse_fill(args.get(1)) {
+            Ok(fill) => fill,
+            Err(e) => return self.editor.fail(e),
+        };
+
+        match EditorState::blank(size, fill, &self.settings) {
+            Ok(mut editor) => {
+                editor.mode = self.editor.mode;
+                editor.same_bytes = self.editor.same_bytes;
+                editor.inspector = self.editor.inspector;
+                editor.dump.squeeze = self.editor.dump.squeeze;
+                self.others.push(editor);
+                self.switch_buffer(self.buffer_count() - 1);
+                self.editor.message = format!(
+                    "New buffer of {:X} bytes, save <file> to write it out",
+                    size
+                );
+            }
+            Err(e) => self.editor.fail(e),
+        }
+    }
+
+    /// The `resize` command, cuts the end off the buffer or pads it with a fill byte.
+    /// Either way it is one undo step
+    fn resize(&mut self, args: &[String]) {
+        let usage = "Usage: resize <size> [<fill byte>], like resize 0x100000 FF";
+        let (Some(size), None) = (args.first(), args.get(2)) else {
+            return self.editor.fail(usage);
+        };
+
+        let size = match parse_size(size) {
+            Ok(size) => size,
+            Err(e) => return self.editor.fail(format!("Invalid size '{size}': {e}")),
+        };
+
+        let fill = match parse_fill(args.get(1)) {
+            Ok(fill) => fill,
+            Err(e) => return self.editor.fail(e),
+        };
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        if self.editor.kind == FileKind::Device {
+            return self.editor.fail("Can't change the size of a device");
+        }
+
+        let len = self.editor.len();
+        self.editor.document.end_undo_group();
+
+        let result = match size.cmp(&len) {
+            std::cmp::Ordering::Equal => {
+                return self.editor.message = format!("The buffer is {:X} bytes already.", len)
+            }
+            std::cmp::Ordering::Less => self.editor.document.delete(size..len).map(|_| ()),
+            std::cmp::Ordering::Greater => {
+                let mut padding = Vec::new();
+                if padding.try_reserve_exact(size - len).is_err() {
+                    return self.editor.fail(format!(
+                        "Can't grow the buffer to {:X} bytes, not enough memory",
+                        size
+                    ));
+                }
+                padding.resize(size - len, fill);
+                self.editor.document.insert(len, &padding)
+            }
+        };
+
+        if let Err(e) = result {
+            return self.editor.fail(e.to_string());
+        }
+
+        self.editor.message = format!("Resized from {:X} to {:X} bytes", len, size);
+        if self.editor.forget_past_end() {
+            self.editor.message += ", matches and the selection past the end were dropped";
+        }
+    }
+
+    /// The `swap` command, exchanges two ranges of the same length in one undo step
+    fn swap(&mut self, args: &[String]) {
+        let [first, second, length] = args else {
+            return self
+                .editor
+                .fail("Usage: swap <offset> <offset> <length>, like swap 100 200 10");
+        };
+
+        let mut values = [0; 3];
+        for (value, (arg, what)) in
+            values
+                .iter_mut()
+                .zip([(first, "offset"), (second, "offset"), (length, "length")])
+        {
+            match self.offset(arg) {
+                Ok(parsed) => *value = parsed,
+                Err(e) => return self.editor.fail(format!("Invalid {what} '{arg}': {e}")),
+            }
+        }
+        let [first, second, length] = values;
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        self.editor.document.end_undo_group();
+        match self.editor.document.swap(first, second, length) {
+            Ok(()) => {
+                self.editor.message =
+                    format!("Swapped {length:X} bytes at {first:X} and {second:X}")
+            }
+            Err(e) => self.editor.fail(format!("Can't swap: {e}")),
+        }
+    }
+
+    /// The `cmp` command, compares two ranges of the buffer of the same length and lists the
+    /// first differences numbered for `goto #<n>`, or with -g moves the cursor to the first
+    fn compare_ranges(&mut self, args: &[String]) {
+        let usage = "Usage: cmp <offset> <offset> <length> [-n <count>] [-g], like cmp 100 200 10";
+        let (mut values, mut limit, mut jump) = (Vec::new(), CMP_LISTED, false);
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-g" => jump = true,
+                "-n" => match args.next().map(|count| count.parse::<usize>()) {
+                    Some(Ok(count)) if count > 0 => limit = count,
+                    _ => {
+                        return self
+                            .editor
+                            .fail("-n takes how many differences to list, like -n 20")
+                    }
+                },
+                _ if values.len() < 3 => {
+                    let what = ["offset", "offset", "length"][values.len()];
+                    match self.offset(arg) {
+                        Ok(value) => values.push(value),
+                        Err(e) => return self.editor.fail(format!("Invalid {what} '{arg}': {e}")),
+                    }
+                }
+                _ => return self.editor.fail(usage),
+            }
+        }
+        let [a, b, length] = values[..] else {
+            return self.editor.fail(usage);
+        };
+
+        let (differences, total) =
+            match compare_ranges(self.editor.document.bytes(), a, b, length, limit) {
+                Ok(found) => found,
+                Err(e) => return self.editor.fail(format!("Can't compare: {e}")),
+            };
+
+        let Some(first) = differences.first() else {
+            return self.editor.message = format!("Identical, {length:X} bytes at {a:X} and {b:X}");
+        };
+
+        if jump {
+            self.editor.move_cursor_to(a + first.offset);
+            return self.editor.message = format!(
+                "{total} differences, the first at +{:X}: {:02X} at {:X}, {:02X} at {:X}",
+                first.offset,
+                first.a,
+                a + first.offset,
+                first.b,
+                b + first.offset
+            );
+        }
+
+        let mut lines = vec![format!(
+            "{length:X} bytes at A {a:X} and B {b:X}, goto #<n> jumps to a difference in A:"
+        )];
+        for (index, difference) in differences.iter().enumerate() {
+            lines.push(format!(
+                "#{:<5} +{:<8X} A {:02X}  B {:02X}",
+                index + 1,
+                difference.offset,
+                difference.a,
+                difference.b
+            ));
+        }
+        lines.push(match total > differences.len() {
+            true => format!(
+                "{total} differences, the first {} listed",
+                differences.len()
+            ),
+            false if total == 1 => "1 difference".to_string(),
+            false => format!("{total} differences"),
+        });
+
+        let offsets = differences
+            .iter()
+            .map(|difference| a + difference.offset)
+            .collect();
+        self.editor.entries = Some(("cmp", offsets));
+        self.show_listing(lines);
+    }
+
+    /// The `move` command, takes a range out and puts it back in front of the byte at the
+    /// destination, which is counted before the move like the source
+    fn move_range(&mut self, args: &[String]) {
+        let [source, length, destination] = args else {
+            return self
+                .editor
+                .fail("Usage: move <offset> <length> <destination>, like move 100 10 400");
+        };
+
+        let mut values = [0; 3];
+        for (value, (arg, what)) in values.iter_mut().zip([
+            (source, "offset"),
+            (length, "length"),
+            (destination, "destination"),
+        ]) {
+            match self.offset(arg) {
+                Ok(parsed) => *value = parsed,
+                Err(e) => return self.editor.fail(format!("Invalid {what} '{arg}': {e}")),
+            }
+        }
+        let [source, length, destination] = values;
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        self.editor.document.end_undo_group();
+        match self.editor.document.move_range(source, length, destination) {
+            Ok(moved_to) if moved_to == source => {
+                self.editor.message = "The range is there already, nothing moved".to_string()
+            }
+            Ok(moved_to) => {
+                self.editor.shift_for_move(source, length, destination);
+                self.editor.message = format!(
+                    "Moved {length:X} bytes from {source:X}, they start at {moved_to:X} now"
+                )
+            }
+            Err(e) => self.editor.fail(format!("Can't move: {e}")),
+        }
+    }
+
+    /// The `randfill` command, overwrites a range or the selection with random bytes. They come
+    /// from a seed, one from the system if none is given, which is reported so the fill can be
+    /// repeated
+    fn random_fill(&mut self, args: &[String]) {
+        let usage = "Usage: randfill [<offset> <length>] [<seed>]";
+        let (range, seed) = match args {
+            [] | [_] => match self.editor.selected() {
+                Some(range) => (*range.start()..*range.end() + 1, args.first()),
+                None => return self.editor.fail(
+                    "Nothing selected, select bytes or give randfill <offset> <length> [<seed>]",
+                ),
+            },
+            [offset, length] | [offset, length, _] => {
+                match (self.offset(offset), self.offset(length)) {
+                    (Ok(offset), Ok(length)) => {
+                        (offset..offset.saturating_add(length), args.get(2))
+                    }
+                    (Err(e), _) => {
+                        return self.editor.fail(format!("Invalid offset '{offset}': {e}"))
+                    }
+                    (_, Err(e)) => {
+                        return self.editor.fail(format!("Invalid length '{length}': {e}"))
+                    }
+                }
+            }
+            _ => return self.editor.fail(usage),
+        };
+
+        // Seeds are decimal like sizes, that is how they are usually written down
+        let (seed, generated) = match seed {
+            Some(seed) => match parse_size(seed) {
+                Ok(value) => (value as u64, false),
+                Err(e) => return self.editor.fail(format!("Invalid seed '{seed}': {e}")),
+            },
+            None => (system_seed(), true),
+        };
+
+        if let Err(e) = self.editor.document.read(range.clone()) {
+            return self.editor.fail(e.to_string());
+        }
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        let mut bytes = vec![0; range.len()];
+        SplitMix64::new(seed).fill(&mut bytes);
+
+        self.editor.document.end_undo_group();
+        match self.editor.document.overwrite(range.start, &bytes) {
+            Ok(()) => {
+                self.editor.message = format!(
+                    "Filled {:X} bytes at {:X} with random bytes, seed {}{}",
+                    bytes.len(),
+                    range.start,
+                    seed,
+                    if generated {
+                        ", give it to randfill to get the same bytes again"
+                    } else {
+                        ""
+                    }
+                )
+            }
+            Err(e) => self.editor.fail(e.to_string()),
+        }
+    }
+
+    /// The `b64` command, shows a range or the selection as base64, or decodes it from base64
+    /// to show as a dump or with `replace` to write over it, one undo step like `!`
+    fn base64(&mut self, args: &[String]) {
+        let usage = "Usage: b64 encode [<start> <end>] or b64 decode [<start> <end>] [replace]";
+        let Some((direction, args)) = args.split_first() else {
+            return self.editor.fail(usage);
+        };
+        let (args, replace) = match args {
+            [rest @ .., last] if direction == "decode" && last == "replace" => (rest, true),
+            _ => (args, false),
+        };
+
+        let range = match args {
+            [start, end] => match (self.offset(start), self.offset(end)) {
+                (Ok(start), Ok(end)) if start <= end && end <= self.editor.len() => start..end,
+                (Ok(_), Ok(_)) => {
+                    return self.editor.fail(format!(
+                        "{start} to {end} isn't a range of the buffer, it has {:X} bytes",
+                        self.editor.len()
+                    ))
+                }
+                (Err(e), _) => return self.editor.fail(format!("Invalid start '{start}': {e}")),
+                (_, Err(e)) => return self.editor.fail(format!("Invalid end '{end}': {e}")),
+            },
+            [] => match self.editor.selected() {
+                Some(range) => *range.start()..*range.end() + 1,
+                None => {
+                    return self
+                        .editor
+                        .fail("Nothing selected, select bytes or give b64 <start> <end>")
+                }
+            },
+            _ => return self.editor.fail(usage),
+        };
+        let input = match self.editor.document.read(range.clone()) {
+            Ok(input) => input,
+            Err(e) => return self.editor.fail(e.to_string()),
+        };
+
+        match direction.as_str() {
+            "encode" => {
+                let text = base64::encode(input);
+                let width = Listing::content_width(terminal_size().0);
+                let mut lines = vec![format!(
+                    "{:X} bytes at {:X} as base64, {:X} characters:",
+                    input.len(),
+                    range.start,
+                    text.len()
+                )];
+                // The text is ASCII, a line can be cut anywhere
+                lines.extend(
+                    text.as_bytes()
+                        .chunks(width)
+                        .map(|line| String::from_utf8_lossy(line).into_owned()),
+                );
+                self.show_listing(lines);
+            }
+            "decode" => {
+                let decoded = match base64::decode(input) {
+                    Ok(decoded) => decoded,
+                    Err(e) => {
+                        return self.editor.fail(format!(
+                            "Not base64: {} at {:X}",
+                            e.reason,
+                            range.start + e.position
+                        ))
+                    }
+                };
+
+                if !replace {
+                    let options = DumpOptions {
+                        squeeze: false,
+                        ..self.editor.dump
+                    };
+                    let mut lines = vec![format!(
+                        "{:X} characters at {:X} decode to {:X} bytes:",
+                        input.len(),
+                        range.start,
+                        decoded.len()
+                    )];
+                    for (index, line) in decoded.chunks(BYTES_PER_LINE).enumerate() {
+                        lines.push(render_line(index * BYTES_PER_LINE, line, &options));
+                    }
+                    return self.show_listing(lines);
+                }
+
+                if !self.editor.check_writable() {
+                    return;
+                }
+                if self.editor.kind == FileKind::Device && decoded.len() != range.len() {
+                    return self.editor.fail(format!(
+                        "Can't change the size of a device, {:X} bytes decode to {:X}",
+                        range.len(),
+                        decoded.len()
+                    ));
+                }
+
+                self.editor.document.end_undo_group();
+                if let Err(e) = self.editor.document.replace(range.clone(), &decoded) {
+                    return self.editor.fail(e.to_string());
+                }
+
+                self.editor.shift_for_replace(range.clone(), decoded.len());
+                self.editor.message = format!(
+                    "Replaced {:X} characters of base64 at {:08X} with the {:X} bytes they decode to",
+                    range.len(),
+                    range.start,
+                    decoded.len()
+                );
+            }
+            _ => self.editor.fail(usage),
+        }
+    }
+
+    /// The `cyclic` command, writes the De Bruijn pattern pwntools calls cyclic over a range or
+    /// the selection, or finds where bytes that came from it are in it
+    fn cyclic(&mut self, args: &[String]) {
+        let usage = "Usage: cyclic fill [<offset> <length>] [-n <order>] or cyclic find <hex bytes> [-n <order>]";
+        let Some((subcommand, args)) = args.split_first() else {
+            return self.editor.fail(usage);
+        };
+
+        // -n goes anywhere after the subcommand, like it does for pwn cyclic
+        let mut order = DEFAULT_ORDER;
+        let mut rest = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-n" => match args.next().map(|order| order.parse::<usize>()) {
+                    Some(Ok(n)) if (1..=8).contains(&n) => order = n,
+                    _ => {
+                        return self
+                            .editor
+                            .fail("-n takes how long the unique runs are, 1 to 8, like -n 8")
+                    }
+                },
+                _ => rest.push(arg),
+            }
+        }
+
+        match (subcommand.as_str(), &rest[..]) {
+            ("fill", [] | [_, _]) => {
+                let range = match rest[..] {
+                    [offset, length] => match (self.offset(offset), self.offset(length)) {
+                        (Ok(offset), Ok(length)) => offset..offset.saturating_add(length),
+                        (Err(e), _) => {
+                            return self.editor.fail(format!("Invalid offset '{offset}': {e}"))
+                        }
+                        (_, Err(e)) => {
+                            return self.editor.fail(format!("Invalid length '{length}': {e}"))
+                        }
+                    },
+                    _ => match self.editor.selected() {
+                        Some(range) => *range.start()..*range.end() + 1,
+                        None => return self.editor.fail(
+                            "Nothing selected, select bytes or give cyclic fill <offset> <length>",
+                        ),
+                    },
+                };
+
+                if let Err(e) = self.editor.document.read(range.clone()) {
+                    return self.editor.fail(e.to_string());
+                }
+
+                let pattern = cyclic(range.len(), order);
+                if pattern.len() < range.len() {
+                    return self.editor.fail(format!(
+                        "The pattern with -n {order} is only {:X} bytes long, use a larger -n",
+                        pattern.len()
+                    ));
+                }
+
+                if !self.editor.check_writable() {
+                    return;
+                }
+
+                self.editor.document.end_undo_group();
+                match self.editor.document.overwrite(range.start, &pattern) {
+                    Ok(()) => {
+                        self.editor.message = format!(
+                            "Filled {:X} bytes at {:X} with the cyclic pattern, cyclic find <bytes> gives where bytes are in it",
+                            pattern.len(),
+                            range.start
+                        )
+                    }
+                    Err(e) => self.editor.fail(e.to_string()),
+                }
+            }
+            ("find", [hex]) => {
+                let needle = match hex_to_bytes(hex) {
+                    Some(needle) if needle.len() >= order => needle,
+                    Some(_) => {
+                        return self.editor.fail(format!(
+                            "Give at least {order} bytes, shorter runs are in the pattern more than once"
+                        ))
+                    }
+                    None => return self.editor.fail(format!("'{hex}' isn't hex bytes")),
+                };
+
+                // Bytes copied out of a register are a little endian value, backwards from
+                // memory. Both orders can be in the pattern, then both are given
+                let reversed = needle.iter().rev().copied().collect::<Vec<u8>>();
+                let hex = hex.to_uppercase();
+                self.editor.message = match (
+                    cyclic_find(&needle, order, FIND_LIMIT),
+                    cyclic_find(&reversed, order, FIND_LIMIT),
+                ) {
+                    (Some(at), Some(value)) if at != value => format!(
+                        "{hex} is at {at:X} (0d{at}) in the cyclic pattern as bytes, at {value:X} (0d{value}) as a little endian value"
+                    ),
+                    (Some(at), _) => format!("{hex} is at {at:X} (0d{at}) in the cyclic pattern"),
+                    (None, Some(value)) => format!(
+                        "{hex} is at {value:X} (0d{value}) in the cyclic pattern as a little endian value"
+                    ),
+                    (None, None) => format!("{hex} isn't in the cyclic pattern"),
+                };
+            }
+            _ => self.editor.fail(usage),
+        }
+    }
+
+    /// The `pad` command, appends fill bytes until the length is a multiple of the alignment,
+    /// or with --to until it is a given size. Flash is erased to FF, so that is the default fill
+    fn pad(&mut self, args: &[String]) {
+        let usage = "Usage: pad <alignment> [<fill byte>], pad --to <size> [<fill byte>] or pad <alignment> <fill byte> --to <size>, like pad 4k or pad 200 00";
+        let mut to = None;
+        let mut rest = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--to" => match args.next() {
+                    Some(size) if to.is_none() => to = Some(size),
+                    _ => return self.editor.fail(usage),
+                },
+                _ => rest.push(arg),
+            }
+        }
+
+        // With --to a lone argument is the fill byte, an alignment comes with one
+        let (alignment, fill) = match (to, rest.as_slice()) {
+            (None, [alignment]) => (Some(*alignment), None),
+            (_, [alignment, fill]) => (Some(*alignment), Some(*fill)),
+            (Some(_), []) => (None, None),
+            (Some(_), [fill]) => (None, Some(*fill)),
+            _ => return self.editor.fail(usage),
+        };
+        let fill = match fill.map(|fill| parse_fill(Some(fill))) {
+            Some(Ok(fill)) => fill,
+            Some(Err(e)) => return self.editor.fail(e),
+            None => 0xFF,
+        };
+        let alignment = match alignment.map(|alignment| (alignment, parse_size(alignment))) {
+            Some((_, Ok(0))) => return self.editor.fail("The alignment has to be more than 0"),
+            Some((_, Ok(alignment))) => Some(alignment),
+            Some((text, Err(e))) => {
+                return self.editor.fail(format!("Invalid alignment '{text}': {e}"))
+            }
+            None => None,
+        };
+        let to = match to.map(|size| (size, parse_size(size))) {
+            Some((_, Ok(size))) => Some(size),
+            Some((text, Err(e))) => return self.editor.fail(format!("Invalid size '{text}': {e}")),
+            None => None,
+        };
+
+        let len = self.editor.len();
+        let target = match (to, alignment) {
+            (Some(size), _) if size < len => {
+                return self.editor.fail(format!(
+                    "The buffer is {len:X} bytes already, more than {size:X}. Use resize to cut it"
+                ))
+            }
+            (Some(size), Some(alignment)) if !size.is_multiple_of(alignment) => {
+                return self
+                    .editor
+                    .fail(format!("{size:X} isn't a multiple of {alignment:X}"))
+            }
+            (Some(size), _) => size,
+            (None, Some(alignment)) => match len.checked_next_multiple_of(alignment) {
+                Some(target) => target,
+                None => {
+                    return self
+                        .editor
+                        .fail(format!("Can't pad to a multiple of {alignment:X}"))
+                }
+            },
+            (None, None) => return self.editor.fail(usage),
+        };
+
+        if target == len {
+            return self.editor.message = match alignment {
+                Some(alignment) if to.is_none() => {
+                    format!("{len:X} is a multiple of {alignment:X} already, nothing padded")
+                }
+                _ => format!("The buffer is {len:X} bytes already, nothing padded"),
+            };
+        }
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        if self.editor.kind == FileKind::Device {
+            return self.editor.fail("Can't change the size of a device");
+        }
+
+        let mut padding = Vec::new();
+        if padding.try_reserve_exact(target - len).is_err() {
+            return self.editor.fail(format!(
+                "Can't grow the buffer to {target:X} bytes, not enough memory"
+            ));
+        }
+        padding.resize(target - len, fill);
+
+        self.editor.document.end_undo_group();
+        match self.editor.document.insert(len, &padding) {
+            Ok(()) => {
+                self.editor.message = format!("Padded {:X} bytes to {target:X}", padding.len())
+            }
+            Err(e) => self.editor.fail(e.to_string()),
+        }
+    }
+
+    /// The `palette` command, shows or changes the colors of the theme in every buffer
+    fn palette(&mut self, args: &[String]) {
+        let usage = "Usage: palette show, palette set <name>=<color>... or palette reset";
+        let theme = self.settings.theme.unwrap_or_default();
+
+        let theme = match args.split_first() {
+            Some((action, [])) if action == "show" => {
+                let mut lines = vec![format!("{:<12}{:<24}sample", "name", "color")];
+                for name in PALETTE_NAMES {
+                    let color = theme.get(name).unwrap_or_default();
+                    lines.push(format!(
+                        "{:<12}{:<24}{}",
+                        name,
+                        describe_color(color),
+                        match self.editor.dump.colors {
+                            true => Theme::paint(color, "00 0A 41 FF"),
+                            false => "00 0A 41 FF".to_string(),
+                        }
+                    ));
+                }
+                return self.show_listing(lines);
+            }
+            Some((action, [])) if action == "reset" => Theme::default(),
+            Some((action, entries)) if action == "set" && !entries.is_empty() => {
+                // Every entry is checked before any of them is applied
+                let mut theme = theme;
+                for entry in entries {
+                    let Some((name, color)) = entry.split_once('=') else {
+                        return self
+                            .editor
+                            .fail(format!("Expected <name>=<color>, not '{entry}'"));
+                    };
+                    if let Err(e) = parse_color(color).and_then(|color| theme.set(name, color)) {
+                        return self.editor.fail(format!("Can't set {name}: {e}"));
+                    }
+                }
+                theme
+            }
+            _ => return self.editor.fail(usage),
+        };
+
+        self.settings.theme = Some(theme);
+        self.editor.set_theme(Some(theme));
+        for editor in &mut self.others {
+            editor.set_theme(Some(theme));
+        }
+
+        self.editor.message = match self.editor.dump.colors {
+            true => "Palette updated".to_string(),
+            false => "Palette updated, but colors are off".to_string(),
+        };
+    }
+
+    /// The `trim` command, cuts the run of filler bytes off the end, leaving --keep of them.
+    /// A buffer of nothing but filler is only emptied with -f
+    fn trim(&mut self, args: &[String]) {
+        let usage = "Usage: trim [<byte>] [--keep <n>] [-f], like trim FF or trim 00 --keep 1";
+        let mut filler = None;
+        let mut keep = 0;
+        let mut force = false;
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-f" => force = true,
+                "--keep" => match args.next().map(|n| (n, parse_size(n))) {
+                    Some((_, Ok(n))) => keep = n,
+                    Some((n, Err(e))) => {
+                        return self.editor.fail(format!("Invalid count '{n}': {e}"))
+                    }
+                    None => return self.editor.fail(usage),
+                },
+                _ if filler.is_none() => match parse_fill(Some(arg)) {
+                    Ok(byte) => filler = Some(byte),
+                    Err(e) => return self.editor.fail(e),
+                },
+                _ => return self.editor.fail(usage),
+            }
+        }
+        let filler = filler.unwrap_or(0);
+
+        let len = self.editor.len();
+        let trimmed = self
+            .editor
+            .document
+            .trailing_run(filler)
+            .saturating_sub(keep);
+        if trimmed == 0 {
+            return self.editor.message = match keep {
+                0 => format!("Nothing to trim, the buffer doesn't end in {filler:02X}"),
+                _ => format!(
+                    "Nothing to trim, the buffer doesn't end in more than {keep:X} {filler:02X} bytes"
+                ),
+            };
+        }
+
+        if trimmed == len && !force {
+            return self.editor.fail(format!(
+                "The buffer is all {filler:02X}, trimming would empty it. Use trim {filler:02X} -f to do it anyway"
+            ));
+        }
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        if self.editor.kind == FileKind::Device {
+            return self.editor.fail("Can't change the size of a device");
+        }
+
+        self.editor.document.end_undo_group();
+        if let Err(e) = self.editor.document.delete(len - trimmed..len) {
+            return self.editor.fail(e.to_string());
+        }
+
+        self.editor.message = format!(
+            "Trimmed {trimmed:X} {filler:02X} bytes, {:X} left",
+            len - trimmed
+        );
+        if self.editor.forget_past_end() {
+            self.editor.message += ", matches and the selection past the end were dropped";
+        }
+    }
+
+    /// The `reverse` command, flips the order of the bytes in a range or the selection
+    fn reverse(&mut self, args: &[String]) {
+        let range = match args {
+            [] => match self.editor.selected() {
+                Some(range) => *range.start()..*range.end() + 1,
+                None => {
+                    return self
+                        .editor
+                        .fail("Nothing selected, select bytes or give reverse <offset> <length>")
+                }
+            },
+            [offset, length] => match (self.offset(offset), self.offset(length)) {
+                (Ok(offset), Ok(length)) => offset..offset.saturating_add(length),
+                (Err(e), _) => return self.editor.fail(format!("Invalid offset '{offset}': {e}")),
+                (_, Err(e)) => return self.editor.fail(format!("Invalid length '{length}': {e}")),
+            },
+            _ => return self.editor.fail("Usage: reverse [<offset> <length>]"),
+        };
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        self.editor.document.end_undo_group();
+        match self.editor.document.reverse(range.clone()) {
+            Ok(()) if range.len() < 2 => {
+                self.editor.message = "Fewer than two bytes, nothing to reverse".to_string()
+            }
+            Ok(()) => {
+                self.editor.message =
+                    format!("Reversed {:X} bytes at {:X}", range.len(), range.start)
+            }
+            Err(e) => self.editor.fail(e.to_string()),
+        }
+    }
+
+    /// `! <command>` runs the selection through a shell command and replaces it with the output,
+    /// as one undo step. `!! <command>` only shows the output, with the selection as its input
+    /// if there is one. A command that fails changes nothing
+    fn filter(&mut self, line: &str) {
+        let (replace, command) = match line.strip_prefix("!!") {
+            Some(command) => (false, command.trim()),
+            None => (true, line[1..].trim()),
+        };
+
+        if command.is_empty() {
+            return self.editor.fail(
+                "Usage: ! <command> to replace the selection with its output, !! <command> to show it",
+            );
+        }
+
+        let range = match self.editor.selected() {
+            Some(range) => *range.start()..*range.end() + 1,
+            None if replace => return self.editor.fail(
+                "Nothing selected, select the bytes for the command or use !! to show its output",
+            ),
+            None => 0..0,
+        };
+
+        if replace && !self.editor.check_writable() {
+            return;
+        }
+
+        let limit = usize::try_from(self.settings.load.stream_limit).unwrap_or(usize::MAX);
+        let input = match self.editor.document.read(range.clone()) {
+            Ok(input) => input,
+            Err(e) => return self.editor.fail(e.to_string()),
+        };
+        let output = match filter::run(
+            &shell(env::var_os("SHELL").as_deref()),
+            command,
+            input,
+            limit,
+        ) {
+            Ok(output) => output,
+            Err(e) => return self.editor.fail(format!("{command}: {e}")),
+        };
+
+        if !replace {
+            let text = String::from_utf8_lossy(&output);
+            let mut lines = text
+                .lines()
+                .map(|line| line.replace(|c: char| c.is_control(), "."))
+                .collect::<Vec<String>>();
+
+            return match lines.len() {
+                0 => self.editor.message = format!("{command} printed nothing"),
+                1 => self.editor.message = lines.remove(0),
+                _ => self.show_listing(lines),
+            };
+        }
+
+        if output == input {
+            return self.editor.message = "The output is the same as the selection".to_string();
+        }
+
+        if self.editor.kind == FileKind::Device && output.len() != range.len() {
+            return self.editor.fail(format!(
+                "Can't change the size of a device, the output is {:X} bytes for {:X} selected",
+                output.len(),
+                range.len()
+            ));
+        }
+
+        // A command's change is its own undo step, even in the middle of typing over the grid
+        self.editor.document.end_undo_group();
+        if let Err(e) = self.editor.document.replace(range.clone(), &output) {
+            return self.editor.fail(e.to_string());
+        }
+
+        self.editor.shift_for_replace(range.clone(), output.len());
+        self.editor.message = format!(
+            "Replaced {:X} bytes at {:08X} with {:X} from {command}",
+            range.len(),
+            range.start,
+            output.len()
+        );
+    }
+
+    /// Evaluates an offset typed in a command, which can use the cursor, the length and the
+    /// selection by name
+    fn offset(&self, expr: &str) -> Result<usize, String> {
+        let variables = Variables {
+            cursor: self.editor.cursor,
+            len: self.editor.len(),
+            selection: self
+                .editor
+                .selected()
+                .map(|range| *range.start()..*range.end() + 1),
+        };
+
+        evaluate(expr, Some(&variables))
+    }
+
+    /// The `pos` command, shows everything about the cursor or an offset: the offset in every
+    /// base, where it is in the dump, the byte there and how far through the buffer it is
+    fn position(&mut self, args: &[String]) {
+        let offset = match args.join(" ").trim() {
+            "" => self.editor.cursor,
+            expr => match self.offset(expr) {
+                Ok(offset) => offset,
+                Err(e) => return self.editor.fail(format!("Invalid offset '{expr}': {e}")),
+            },
+        };
+
+        match describe_position(offset, self.editor.document.bytes(), BYTES_PER_LINE) {
+            Some(lines) => self.show_listing(lines),
+            None if self.editor.len() == 0 => self.editor.fail("The buffer is empty"),
+            None => self.editor.fail(format!(
+                "{offset:X} is past the end, the last byte is at {:X}",
+                self.editor.len() - 1
+            )),
+        }
+    }
+
+    /// The `calc` command, shows what an expression comes to in hex and decimal
+    fn calc(&mut self, args: &[String]) {
+        let expr = args.join(" ");
+        if expr.trim().is_empty() {
+            return self
+                .editor
+                .fail("Usage: calc <expression>, like calc 0x200 + 3 * 0d40 or calc len - cursor");
+        }
+
+        match self.offset(&expr) {
+            Ok(value) => self.editor.message = format!("{expr} = {value:X} hex, {value} decimal"),
+            Err(e) => self.editor.fail(format!("Can't work out '{expr}': {e}")),
+        }
+    }
+
+    /// The `view` command, scrolls to an offset or back to the cursor without moving the cursor
+    fn view(&mut self, args: &[String]) {
+        let (center, args) = match args.split_first() {
+            Some((flag, rest)) if flag == "-c" => (true, rest),
+            _ => (false, args),
+        };
+
+        // An expression can have spaces in it, `cursor` is one of its names
+        let expr = args.join(" ");
+        let offset = match self.offset(&expr) {
+            _ if args.is_empty() => return self.editor.fail("Usage: view [-c] <offset|cursor>"),
+            Ok(offset) if offset < self.editor.len() => offset,
+            Ok(_) => return self.editor.fail("Offset out of range."),
+            Err(e) => return self.editor.fail(format!("Invalid offset '{expr}': {e}")),
+        };
+
+        self.editor.view(offset, center);
+    }
+
+    /// The `compare` command, shows another file under the buffer on screen or moves between
+    /// the bytes that differ
+    fn compare(&mut self, args: &[String]) {
+        let usage = "Usage: compare <path>, compare next, compare prev or compare off";
+        let [arg] = args else {
+            return self.editor.fail(usage);
+        };
+
+        match arg.as_str() {
+            "off" => match self.editor.compare.take() {
+                Some(_) => self.editor.message = "Compare off".to_string(),
+                None => self.editor.fail("Not comparing."),
+            },
+            "next" | "prev" => {
+                let Some(other) = &self.editor.compare else {
+                    return self.editor.fail("Not comparing, use compare <path> first.");
+                };
+
+                let (mine, theirs) = (self.editor.document.bytes(), other.document.bytes());
+                let found = match arg.as_str() {
+                    "next" => next_difference(mine, theirs, self.editor.cursor),
+                    _ => prev_difference(mine, theirs, self.editor.cursor),
+                };
+
+                match found {
+                    Some(offset) if offset < self.editor.len() => {
+                        self.editor.move_cursor_to(offset);
+                        self.editor.message = format!("Difference at {offset:X}");
+                    }
+                    // Past the end of this buffer, only the other file has bytes there
+                    Some(offset) => {
+                        self.editor.message =
+                            format!("{} goes on past the end, from {offset:X}", other.name())
+                    }
+                    None => self.editor.message = "No more differences".to_string(),
+                }
+            }
+            path => {
+                // Loaded the way any file is, but never written to and never locked
+                let settings = OpenSettings {
+                    load: self.settings.load,
+                    colors: self.settings.colors,
+                    theme: self.settings.theme,
+                    gutter: self.settings.gutter,
+                    readonly: true,
+                    read_write: false,
+                    force: false,
+                    signatures: self.settings.signatures.clone(),
+                    watch: None,
+                    journal: false,
+                    decompress: self.settings.decompress,
+                };
+
+                match EditorState::open(PathBuf::from(path), &settings) {
+                    Ok(other) => {
+                        let same = !range_differs(
+                            self.editor.document.bytes(),
+                            other.document.bytes(),
+                            0..self.editor.len().max(other.len()),
+                        );
+                        self.editor.message = match same {
+                            true => format!("Comparing with {}, no differences", other.name()),
+                            false => format!(
+                                "Comparing with {}, compare next and prev move between differences",
+                                other.name()
+                            ),
+                        };
+                        self.editor.compare = Some(Box::new(other));
+                        // Half the rows are left, the cursor stays on screen
+                        self.editor.set_cursor(self.editor.cursor);
+                    }
+                    Err(e) => self.editor.fail(e),
+                }
+            }
+        }
+    }
+
+    /// The `changes` command, lists every run of bytes that differs from the last save with
+    /// what was there before, or writes them to a CSV file
+    fn changes(&mut self, args: &[String]) {
+        let modifications = self.editor.document.modifications();
+
+        let path = match args {
+            [] => None,
+            [export, path] if export == "export" => Some(path),
+            _ => return self.editor.fail("Usage: changes or changes export <file>"),
+        };
+
+        if let Some(path) = path {
+            let mut csv =
+                "index,offset,original_length,current_length,original,current\n".to_string();
+            for (index, change) in modifications.iter().enumerate() {
+                csv += &format!(
+                    "{},{:08X},{:X},{:X},{},{}\n",
+                    index + 1,
+                    change.offset,
+                    change.original.len(),
+                    change.current.len(),
+                    bytes_to_hex(&change.original).replace(' ', ""),
+                    bytes_to_hex(&change.current).replace(' ', "")
+                );
+            }
+
+            return match fs::write(path, csv) {
+                Ok(()) => {
+                    self.editor.message = format!("Wrote {} changes to {path}", modifications.len())
+                }
+                Err(e) => self.editor.fail(format!("Can't write {path}: {e}")),
+            };
+        }
+
+        if modifications.is_empty() {
+            return self.editor.message = "No changes since the last save".to_string();
+        }
+
+        // Numbers in this listing are changes again
+        self.editor.entries = None;
+
+        let mut lines = vec!["Changes since the last save, goto #<n> jumps to one:".to_string()];
+        for (index, change) in modifications.iter().enumerate() {
+            lines.push(format!(
+                "#{:<3} {:08X}  {:X} bytes, now {:X}",
+                index + 1,
+                change.offset,
+                change.original.len(),
+                change.current.len()
+            ));
+            lines.push(format!("     was {}", preview_bytes(&change.original)));
+            lines.push(format!("     now {}", preview_bytes(&change.current)));
+        }
+
+        let (before, after) = modifications
+            .iter()
+            .fold((0, 0), |(before, after), change| {
+                (before + change.original.len(), after + change.current.len())
+            });
+        lines.push(format!(
+            "{} changes: {:X} bytes before, {:X} bytes now",
+            modifications.len(),
+            before,
+            after
+        ));
+
+        self.show_listing(lines);
+    }
+
+    /// The `matches export` command, writes the matches of the last search to a CSV or JSON
+    /// file, a match at a time
+    fn export_matches(&mut self, args: &[String]) {
+        let usage = "Usage: matches export <file> [--format csv|json]";
+        let (path, format) = match args {
+            [export, path] if export == "export" => (path, MatchFormat::for_path(path)),
+            [export, path, flag, format] if export == "export" && flag == "--format" => {
+                match MatchFormat::parse(format) {
+                    Some(format) => (path, format),
+                    None => {
+                        return self
+                            .editor
+                            .fail(format!("Unknown format '{format}', use csv or json"))
+                    }
+                }
+            }
+            _ => return self.editor.fail(usage),
+        };
+
+        if self.editor.matches.is_empty() {
+            return self
+                .editor
+                .fail("No matches to export, search with find or filetype first");
+        }
+
+        let list = MatchList {
+            search: &self.editor.match_search,
+            bytes: self.editor.document.bytes(),
+            offsets: &self.editor.matches,
+            width: self.editor.match_width,
+        };
+        let name = self.editor.file.display().to_string();
+
+        let result = fs::File::create(path).and_then(|file| {
+            let mut out = io::BufWriter::new(file);
+            match format {
+                MatchFormat::Csv => list.write_csv(&mut out)?,
+                MatchFormat::Json => list.write_json(&mut out, &name)?,
+            }
+            out.flush()
+        });
+
+        match result {
+            Ok(()) => {
+                self.editor.message =
+                    format!("Wrote {} matches to {path}", self.editor.matches.len())
+            }
+            Err(e) => self.editor.fail(format!("Can't write {path}: {e}")),
+        }
+    }
+
+    /// The `goto` command, moves the cursor to an offset or to where a change listed by
+    /// `changes` starts, or an entry listed by `headers` or `chunks` when one of those was
+    /// listed last
+    fn goto(&mut self, args: &[String]) {
+        let target = args.join(" ");
+        if target.is_empty() {
+            return self.editor.fail("Usage: goto <offset> or goto #<n>");
+        }
+
+        if let Some(block) = target.strip_prefix("block ") {
+            let Some(size) = self.editor.map_block else {
+                return self.editor.fail("goto block needs a map, run entmap first");
+            };
+            return match block
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_mul(size))
+            {
+                Some(offset) if offset < self.editor.len() => self.editor.move_cursor_to(offset),
+                _ => self.editor.fail(format!(
+                    "No block {}, there are {} of {size:X} bytes",
+                    block.trim(),
+                    self.editor.len().div_ceil(size)
+                )),
+            };
+        }
+
+        // A label wins over the offset its name could also be read as, like one called add
+        if let Some(offset) = self.editor.labels.offset_of(&target) {
+            return match offset < self.editor.len() {
+                true => self.editor.move_cursor_to(offset),
+                false => self
+                    .editor
+                    .fail(format!("Label {target} is past the end of the buffer")),
+            };
+        }
+
+        let offset = match (target.strip_prefix('#'), self.editor.entries.clone()) {
+            (Some(index), Some((command, offsets))) => match index.parse::<usize>() {
+                Ok(index) if (1..=offsets.len()).contains(&index) => match offsets[index - 1] {
+                    offset if offset <= self.editor.len() => offset,
+                    offset => {
+                        return self.editor.fail(format!(
+                            "Entry {target} at {offset:X} is past the end of the buffer"
+                        ))
+                    }
+                },
+                _ => {
+                    return self.editor.fail(format!(
+                        "No entry {target}, there are {} (see {command})",
+                        offsets.len()
+                    ))
+                }
+            },
+            (Some(index), None) => {
+                let modifications = self.editor.document.modifications();
+                match index.parse::<usize>() {
+                    Ok(index) if (1..=modifications.len()).contains(&index) => {
+                        modifications[index - 1].offset
+                    }
+                    _ => {
+                        return self.editor.fail(format!(
+                            "No change {target}, there are {} (see changes)",
+                            modifications.len()
+                        ))
+                    }
+                }
+            }
+            (None, _) => match self.offset(&target) {
+                Ok(offset) if offset < self.editor.len() => offset,
+                Ok(_) => return self.editor.fail("Offset out of range."),
+                Err(e) => return self.editor.fail(format!("Invalid offset '{target}': {e}")),
+            },
+        };
+
+        // A deletion at the very end starts past the last byte, which is the closest one
+        self.editor.move_cursor_to(offset);
+    }
+
+    /// The `labels` command, reads names for offsets from a symbol or map file, lists them or
+    /// drops them
+    fn labels(&mut self, args: &[String]) {
+        let usage = "Usage: labels, labels load <file> [--base <address>] or labels clear";
+        let (path, base) = match args {
+            [] => return self.list_labels(),
+            [clear] if clear == "clear" => {
+                self.editor.labels = Labels::default();
+                return self.editor.message = "Labels cleared".to_string();
+            }
+            [load, path] if load == "load" => (path, 0),
+            [load, path, flag, base] if load == "load" && flag == "--base" => {
+                match self.offset(base) {
+                    Ok(base) => (path, base),
+                    Err(e) => return self.editor.fail(format!("Invalid base '{base}': {e}")),
+                }
+            }
+            _ => return self.editor.fail(usage),
+        };
+
+        let text = match fs::read(path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => return self.editor.fail(format!("Can't read {path}: {e}")),
+        };
+
+        let (labels, report) = Labels::parse(&text, base);
+        if labels.is_empty() {
+            return self.editor.fail(match report.warnings.first() {
+                Some(warning) => format!("No labels in {path}, {warning}"),
+                None => format!("No labels in {path}, expected lines of '<hex address> <name>'"),
+            });
+        }
+
+        let past_end = labels
+            .iter()
+            .filter(|(offset, _)| *offset >= self.editor.len())
+            .count();
+        let mut message = format!("Loaded {} labels from {path}", labels.len());
+        if report.skipped > 0 {
+            message += &format!(", skipped {} lines that aren't labels", report.skipped);
+        }
+        if past_end > 0 {
+            message += &format!(", {past_end} past the end of the buffer");
+        }
+        self.editor.labels = labels;
+
+        // Duplicates and addresses below the base are listed, the labels are loaded all the same
+        if report.warnings.is_empty() {
+            return self.editor.message = message;
+        }
+        let mut lines = vec![format!("{message}, with warnings:")];
+        lines.extend(report.warnings);
+        self.show_listing(lines);
+    }
+
+    /// Lists the labels of the buffer in order, numbered for `goto #<n>`
+    fn list_labels(&mut self) {
+        if self.editor.labels.is_empty() {
+            return self
+                .editor
+                .fail("No labels, read them with labels load <file>");
+        }
+
+        let mut lines = vec!["Labels, goto <label> or goto #<n> jumps to one:".to_string()];
+        for (index, (offset, name)) in self.editor.labels.iter().enumerate() {
+            lines.push(format!("#{:<5} {:08X}  {}", index + 1, offset, name));
+        }
+        lines.push(format!("{} labels", self.editor.labels.len()));
+
+        let offsets = self
+            .editor
+            .labels
+            .iter()
+            .map(|(offset, _)| offset)
+            .collect();
+        self.editor.entries = Some(("labels", offsets));
+        self.show_listing(lines);
+    }
+
+    /// Completes the word being typed as far as its candidates agree, listing them on the
+    /// message line when there is more than one: a label after `goto`, a path after the
+    /// commands that open a file
+    pub fn complete(&mut self, input: &mut InputLine) {
+        let typed = input.chars[..input.cursor].iter().collect::<String>();
+        let Some((command, word)) = typed.trim_start().rsplit_once(char::is_whitespace) else {
+            return;
+        };
+        let command = command.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+        // Paths are listed by their last part, the directory is the same for all of them
+        let (candidates, missing, shown_from) = match command.as_str() {
+            "goto" => {
+                let names = self.editor.labels.complete(word);
+                let missing = match self.editor.labels.is_empty() {
+                    true => "No labels loaded".to_string(),
+                    false => format!("No label starts with {word}"),
+                };
+                (names.into_iter().map(String::from).collect(), missing, 0)
+            }
+            "open" | "compare" | "template load" | "labels load" => (
+                file::complete_path(word),
+                format!("No file starts with {word}"),
+                word.rfind('/').map_or(0, |slash| slash + 1),
+            ),
+            _ => return,
+        };
+
+        let names = candidates
+            .iter()
+            .map(|candidate| candidate.as_str())
+            .collect::<Vec<&str>>();
+        for c in common_prefix(&names).chars().skip(word.chars().count()) {
+            input.insert(c);
+        }
+
+        self.editor.message = match names.len() {
+            0 => missing,
+            1 => String::new(),
+            _ => names
+                .iter()
+                .map(|name| &name[shown_from..])
+                .collect::<Vec<&str>>()
+                .join("  "),
+        };
+    }
+
+    /// The `skip`, `skipzero` and `skipff` commands, move the cursor past a run of the byte under
+    /// it, 00 or FF, forward or with -b backwards
+    fn skip(&mut self, byte: Option<u8>, args: &[String]) {
+        match args {
+            [] => self.skip_run(byte, false),
+            [flag] if flag == "-b" => self.skip_run(byte, true),
+            _ => self
+                .editor
+                .fail("Usage: skip [-b], skipzero [-b] or skipff [-b]"),
+        }
+    }
+
+    /// Moves the cursor to the next byte after it that isn't `byte`, or the byte under the cursor
+    /// when there is none, or to the last one before it when going `backwards`
+    pub fn skip_run(&mut self, byte: Option<u8>, backwards: bool) {
+        let cursor = self.editor.cursor;
+        let bytes = self.editor.document.bytes();
+        let Some(byte) = byte.or_else(|| bytes.get(cursor).copied()) else {
+            return self.editor.fail("Nothing to skip, the buffer is empty");
+        };
+
+        let landing = match backwards {
+            false => find_other(bytes.get(cursor + 1..).unwrap_or_default(), byte)
+                .map(|at| cursor + 1 + at),
+            true => rfind_other(&bytes[..cursor.min(bytes.len())], byte),
+        };
+
+        // The byte under the cursor counts when it is part of the run
+        let on_run = usize::from(bytes.get(cursor) == Some(&byte));
+        match landing {
+            Some(offset) => {
+                let skipped = cursor.abs_diff(offset) - 1 + on_run;
+                self.editor.move_cursor_to(offset);
+                self.editor.message = format!("Skipped {skipped:X} bytes of {byte:02X}");
+            }
+            None if backwards => self
+                .editor
+                .fail(format!("Only {byte:02X} before the cursor")),
+            None => self
+                .editor
+                .fail(format!("Only {byte:02X} after the cursor")),
+        }
+    }
+
+    /// The `headers` command, lists where the headers, segments and sections of an ELF or PE
+    /// file are, numbered for `goto #<n>`
+    fn headers(&mut self) {
+        let Some(found) = headers::parse(self.editor.document.bytes()) else {
+            return self
+                .editor
+                .fail(format!("{} isn't an ELF or PE file", self.editor.name()));
+        };
+
+        let mut lines = vec![format!("{}, goto #<n> jumps to an entry:", found.format)];
+        for (index, entry) in found.entries.iter().enumerate() {
+            let line = format!(
+                "#{:<3} {:08X}  {:<8X} {:<8} {:<20} {}",
+                index + 1,
+                entry.offset,
+                entry.size,
+                entry.kind.name(),
+                entry.name,
+                entry.flags
+            );
+            lines.push(line.trim_end().to_string());
+        }
+        for warning in &found.warnings {
+            lines.push(format!("warning: {warning}"));
+        }
+
+        let offsets = found
+            .entries
+            .iter()
+            .map(|entry| usize::try_from(entry.offset).unwrap_or(usize::MAX))
+            .collect();
+        self.editor.entries = Some(("headers", offsets));
+        self.show_listing(lines);
+    }
+
+    /// The `chunks` command, lists the chunks of a PNG, RIFF or similar file with whether their
+    /// CRCs match, numbered for `goto #<n>`
+    fn chunks(&mut self) {
+        let Some(walk) = chunks::walk(self.editor.document.bytes()) else {
+            let names = LAYOUTS.iter().map(|layout| layout.name);
+            return self.editor.fail(format!(
+                "{} isn't made of chunks, chunks knows {}",
+                self.editor.name(),
+                names.collect::<Vec<&str>>().join(", ")
+            ));
+        };
+
+        let mut lines = vec![format!("{} chunks, goto #<n> jumps to one:", walk.format)];
+        for (index, chunk) in walk.chunks.iter().enumerate() {
+            // What a container holds is indented under it
+            let mut name = "  ".repeat(chunk.depth) + &chunk.kind;
+            if let Some(form) = &chunk.form {
+                name += &format!(" {form}");
+            }
+            let crc = match chunk.crc {
+                Some(true) => "CRC ok",
+                Some(false) => "CRC wrong",
+                None => "",
+            };
+
+            let line = format!(
+                "#{:<3} {:08X}  {:<16} {:<8X} {}",
+                index + 1,
+                chunk.offset,
+                name,
+                chunk.length,
+                crc
+            );
+            lines.push(line.trim_end().to_string());
+        }
+
+        let wrong = walk
+            .chunks
+            .iter()
+            .filter(|chunk| chunk.crc == Some(false))
+            .count();
+        lines.push(match wrong {
+            0 => format!("{} chunks", walk.chunks.len()),
+            _ => format!("{} chunks, {} with a wrong CRC", walk.chunks.len(), wrong),
+        });
+        if let Some(note) = &walk.note {
+            lines.push(format!("note: {note}"));
+        }
+
+        let offsets = walk.chunks.iter().map(|chunk| chunk.offset).collect();
+        self.editor.entries = Some(("chunks", offsets));
+        self.show_listing(lines);
+    }
+
+    /// The `strings` command, lists the runs of text in the buffer at least `-n` characters
+    /// long, numbered for `goto #<n>`
+    fn strings(&mut self, args: &[String]) {
+        let usage = "Usage: strings [-u16|-u16be] [-n <min>]";
+        let (mut encoding, mut min) = (Encoding::Ascii, 4);
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match (arg.as_str(), Encoding::from_flag(arg)) {
+                (_, Some(wide)) => encoding = wide,
+                ("-n", _) => match args.next().map(|min| min.parse::<usize>()) {
+                    Some(Ok(at_least)) if at_least > 0 => min = at_least,
+                    _ => {
+                        return self
+                            .editor
+                            .fail("-n takes a number of characters, like -n 8")
+                    }
+                },
+                _ => return self.editor.fail(usage),
+            }
+        }
+
+        let found = find_strings(self.editor.document.bytes(), encoding, min, MAX_STRINGS);
+        if found.is_empty() {
+            self.editor.entries = None;
+            return self.editor.message = format!("No text of {min} characters or more");
+        }
+
+        let what = match encoding {
+            Encoding::Ascii => "ASCII".to_string(),
+            wide => format!("UTF-16 ({})", wide.flag()),
+        };
+        let mut lines = vec![format!("{what} text, goto #<n> jumps to one:")];
+        for (index, text) in found.iter().enumerate() {
+            lines.push(format!(
+                "#{:<5} {:08X}  {}",
+                index + 1,
+                text.offset,
+                text.text.escape_debug()
+            ));
+        }
+        lines.push(match found.len() {
+            MAX_STRINGS => format!("Stopped after {MAX_STRINGS} strings"),
+            1 => "1 string".to_string(),
+            count => format!("{count} strings"),
+        });
+
+        let offsets = found.iter().map(|text| text.offset).collect();
+        self.editor.entries = Some(("strings", offsets));
+        self.show_listing(lines);
+    }
+
+    /// The `runs` command, lists the longest runs of a byte, or of any byte repeated, numbered
+    /// for `goto #<n>`
+    fn runs(&mut self, args: &[String]) {
+        let usage = "Usage: runs [<byte>] [-n <count>], like runs 00 or runs -n 40";
+        let (mut byte, mut top) = (None, 16);
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-n" => match args.next().map(|top| top.parse::<usize>()) {
+                    Some(Ok(count)) if count > 0 => top = count,
+                    _ => return self.editor.fail("-n takes a number of runs, like -n 40"),
+                },
+                _ if byte.is_none() => match parse_offset(arg).map(u8::try_from) {
+                    Ok(Ok(value)) => byte = Some(value),
+                    _ => {
+                        return self
+                            .editor
+                            .fail(format!("Invalid byte '{arg}': expected 00 to FF"))
+                    }
+                },
+                _ => return self.editor.fail(usage),
+            }
+        }
+
+        let found = longest_runs(self.editor.document.bytes(), byte, top);
+        if found.is_empty() {
+            self.editor.entries = None;
+            return self.editor.message = match byte {
+                Some(byte) => format!("No {byte:02X} in the buffer"),
+                None => "No byte comes twice in a row".to_string(),
+            };
+        }
+
+        let what = match byte {
+            Some(byte) => format!("of {byte:02X}"),
+            None => "of one byte".to_string(),
+        };
+        let mut lines = vec![format!("Longest runs {what}, goto #<n> jumps to one:")];
+        for (index, run) in found.iter().enumerate() {
+            lines.push(format!(
+                "#{:<5} {:08X}  {:X} bytes of {:02X}, to {:08X}",
+                index + 1,
+                run.offset,
+                run.len,
+                run.byte,
+                run.offset + run.len - 1
+            ));
+        }
+        lines.push(match found.len() {
+            1 => "1 run".to_string(),
+            count => format!("{count} runs"),
+        });
+
+        let offsets = found.iter().map(|run| run.offset).collect();
+        self.editor.entries = Some(("runs", offsets));
+        self.show_listing(lines);
+    }
+
+    /// The `stride` command, tints the field at an offset into every record of a fixed size, and
+    /// faintly the first byte of each record. `stride off` drops it
+    fn stride(&mut self, args: &[String]) {
+        let usage = "Usage: stride <record size> [<field offset>] [--base <offset>] or stride off";
+        let (size, field, base) = match args {
+            [] => {
+                return self.editor.message = match self.editor.stride {
+                    Some(stride) => format!(
+                        "Byte {} of every {} byte record from {:X} is tinted",
+                        stride.field, stride.size, stride.base
+                    ),
+                    None => "No stride, stride <record size> [<field offset>] sets one".to_string(),
+                }
+            }
+            [off] if off.eq_ignore_ascii_case("off") => {
+                self.editor.stride = None;
+                return self.editor.message = "Stride off".to_string();
+            }
+            [size, rest @ ..] => {
+                let (field, base) = match rest {
+                    [] => (None, None),
+                    [field] => (Some(field), None),
+                    [flag, base] if flag == "--base" => (None, Some(base)),
+                    [field, flag, base] if flag == "--base" => (Some(field), Some(base)),
+                    _ => return self.editor.fail(usage),
+                };
+                (size, field, base)
+            }
+        };
+
+        // Sizes within a record are counted in decimal like sizes, the base is an offset
+        let size = match parse_size(size) {
+            Ok(size) => size,
+            Err(e) => {
+                return self
+                    .editor
+                    .fail(format!("Invalid record size '{size}': {e}"))
+            }
+        };
+        let field = match field.map(|field| parse_size(field)) {
+            None => 0,
+            Some(Ok(field)) => field,
+            Some(Err(e)) => return self.editor.fail(format!("Invalid field offset: {e}")),
+        };
+        let base = match base.map(|base| self.offset(base)) {
+            None => 0,
+            Some(Ok(base)) => base,
+            Some(Err(e)) => return self.editor.fail(format!("Invalid base: {e}")),
+        };
+
+        match Stride::new(size, field, base) {
+            Ok(stride) => {
+                self.editor.stride = Some(stride);
+                self.editor.message = match self.editor.dump.colors {
+                    true => {
+                        format!("Tinting byte {field} of every {size} byte record from {base:X}")
+                    }
+                    false => "Colors are off, nothing is tinted".to_string(),
+                };
+            }
+            Err(e) => self.editor.fail(e),
+        }
+    }
+
+    /// The `colset` command, writes the same bytes at a field offset into every record of a
+    /// fixed size, in the selection, from a start offset or over the whole buffer. A record cut
+    /// short at the end is left alone
+    fn colset(&mut self, args: &[String]) {
+        let usage = "Usage: colset <record size> <field offset> <hex> [<start> [<count>]], like colset 16 3 01";
+        let [size, field, hex, rest @ ..] = args else {
+            return self.editor.fail(usage);
+        };
+
+        let (size, field) = match (parse_size(size), parse_size(field)) {
+            (Ok(size), Ok(field)) => (size, field),
+            (Err(e), _) => {
+                return self
+                    .editor
+                    .fail(format!("Invalid record size '{size}': {e}"))
+            }
+            (_, Err(e)) => {
+                return self
+                    .editor
+                    .fail(format!("Invalid field offset '{field}': {e}"))
+            }
+        };
+        let value = match hex_to_bytes(hex) {
+            Some(value) if !value.is_empty() => value,
+            _ => {
+                return self
+                    .editor
+                    .fail(format!("Invalid hex '{hex}', like 01 or 0A0B"))
+            }
+        };
+
+        // Without a start the records cover the selection, or else the whole buffer
+        let (start, end, count) = match rest {
+            [] => match self.editor.selected() {
+                Some(range) => (*range.start(), *range.end() + 1, None),
+                None => (0, self.editor.len(), None),
+            },
+            [start] | [start, _] => match self.offset(start) {
+                Ok(start) if start < self.editor.len() => (start, self.editor.len(), rest.get(1)),
+                Ok(_) => return self.editor.fail("Start out of range."),
+                Err(e) => return self.editor.fail(format!("Invalid start '{start}': {e}")),
+            },
+            _ => return self.editor.fail(usage),
+        };
+
+        let stride = match Stride::new(size, field, start) {
+            Ok(stride) => stride,
+            Err(e) => return self.editor.fail(e),
+        };
+        if let Err(e) = stride.fits(value.len()) {
+            return self.editor.fail(e);
+        }
+
+        let (mut records, mut partial) = stride.whole_records(end);
+        if let Some(count) = count {
+            match parse_size(count) {
+                Ok(count) if count <= records => (records, partial) = (count, 0),
+                Ok(count) => {
+                    return self.editor.fail(format!(
+                        "Only {records} whole records from {start:X}, not {count}"
+                    ))
+                }
+                Err(e) => return self.editor.fail(format!("Invalid count '{count}': {e}")),
+            }
+        }
+        if records == 0 {
+            return self
+                .editor
+                .fail(format!("No whole {size} byte record from {start:X}"));
+        }
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        // The records are written in one go, so they are a single undo step
+        let span = start..start + records * size;
+        let mut bytes = match self.editor.document.read(span.clone()) {
+            Ok(bytes) => bytes.to_vec(),
+            Err(e) => return self.editor.fail(e.to_string()),
+        };
+        for record in 0..records {
+            let at = stride.field_offset(record) - start;
+            bytes[at..at + value.len()].copy_from_slice(&value);
+        }
+
+        self.editor.document.end_undo_group();
+        match self.editor.document.overwrite(span.start, &bytes) {
+            Ok(()) => {
+                self.editor.message = format!(
+                    "Set {} bytes at {field} in {records} records of {size} bytes from {start:X}",
+                    value.len()
+                );
+                if partial > 0 {
+                    self.editor.message += &format!(
+                        ", skipped the last {partial} bytes at {:X}, not a whole record",
+                        span.end
+                    );
+                }
+            }
+            Err(e) => self.editor.fail(e.to_string()),
+        }
+    }
+
+    /// The `encoding` command, picks how the text column reads the bytes of this buffer and
+    /// for UTF-16 which byte a code unit starts at. Without arguments says what it is now
+    fn encoding(&mut self, args: &[String]) {
+        let (mut text, mut odd) = (self.editor.dump.text, self.editor.dump.odd_units);
+        for arg in args {
+            match arg.to_ascii_lowercase().as_str() {
+                "ascii" => text = Encoding::Ascii,
+                "utf16le" | "utf16" => text = Encoding::Utf16(Endian::Little),
+                "utf16be" => text = Encoding::Utf16(Endian::Big),
+                "even" => odd = false,
+                "odd" => odd = true,
+                _ => {
+                    return self
+                        .editor
+                        .fail("Usage: encoding [ascii|utf16le|utf16be] [even|odd]")
+                }
+            }
+        }
+
+        if !args.is_empty() {
+            self.editor.set_text_encoding(text, odd);
+        }
+        self.editor.message = match text {
+            Encoding::Ascii => "The text column shows the bytes as they are".to_string(),
+            Encoding::Utf16(endian) => format!(
+                "The text column reads UTF-16 {} endian, code units at {} offsets",
+                if endian == Endian::Little {
+                    "little"
+                } else {
+                    "big"
+                },
+                if odd { "odd" } else { "even" }
+            ),
+        };
+    }
+
+    /// Drops the buffer on screen and shows the one before it, quits if it was the last one
+    fn close_buffer(&mut self) {
+        if self.others.is_empty() {
+            return self.exit_code = Some(0);
+        }
+
+        let next = if self.current > 0 {
+            self.current - 1
+        } else {
+            0
+        };
+        let mut closed = std::mem::replace(&mut self.editor, self.others.remove(next));
+        closed.remove_recovery();
+        closed.remove_journal();
+        release_lock(&closed.file);
+
+        self.current = next;
+        self.update_positions();
+    }
+
+    /// Lists every buffer with its number, marking the one on screen and unsaved ones
+    fn list_buffers(&mut self) {
+        let mut lines = vec!["Buffers:".to_string()];
+
+        for index in 0..self.buffer_count() {
+            let editor = self.buffer(index);
+            lines.push(format!(
+                "{:>3} {}{} {} ({:X} bytes)",
+                index + 1,
+                if index == self.current { '%' } else { ' ' },
+                if editor.modified() { '+' } else { ' ' },
+                editor.name(),
+                editor.len()
+            ));
+        }
+
+        self.show_listing(lines);
+    }
+
+    /// Where every buffer is, to be written out as a session
+    fn session(&self) -> Session {
+        Session {
+            buffers: (0..self.buffer_count())
+                .filter(|&index| !self.buffer(index).is_unnamed())
+                .map(|index| {
+                    let editor = self.buffer(index);
+                    BufferSession {
+                        path: fs::canonicalize(&editor.file).unwrap_or(editor.file.clone()),
+                        size: editor.len() as u64,
+                        cursor: editor.cursor,
+                        top_line: editor.top_line,
+                        selection: editor.selection,
+                    }
+                })
+                .collect(),
+            // Buffers without a file aren't in the session, so they don't count
+            current: (0..self.current)
+                .filter(|&index| !self.buffer(index).is_unnamed())
+                .count(),
+            colors: self.editor.dump.colors,
+            cols: self.editor.dump.cols,
+            group: self.editor.dump.group,
+            ..Session::default()
+        }
+    }
+
+    /// Writes the session to a file, warning that unsaved changes aren't part of it
+    fn save_session(&mut self, path: &str) {
+        let contents = self.session().to_string();
+        if let Err(e) = fs::write(path, contents) {
+            return self.editor.fail(format!("Can't write {path}: {e}"));
+        }
+
+        let modified = (0..self.buffer_count()).any(|index| self.buffer(index).modified());
+        self.editor.message = if modified {
+            format!(
+                "Saved the session to {path}, but not the unsaved changes, save them separately"
+            )
+        } else {
+            format!("Saved the session to {path}")
+        };
+    }
+
+    /// Reads a session file and restores it, see `restore`
+    fn load_session(&mut self, path: &str) {
+        let (session, mut problems) = match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| Session::parse(&contents))
+        {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                return self
+                    .editor
+                    .fail(format!("Can't load the session {path}: {e}"))
+            }
+        };
+
+        problems.extend(self.restore(&session));
+        self.editor.message = if problems.is_empty() {
+            format!("Restored the session from {path}")
+        } else {
+            problems.join("; ")
+        };
+    }
+
+    /// The `patchfile apply` command, writes every edit of a patch file over the buffer as one
+    /// undo step. The whole file is read and checked first, so a mistake on any line leaves
+    /// the buffer as it was
+    fn apply_patch(&mut self, path: &str) {
+        let edits = match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| patchfile::parse(&text))
+        {
+            Ok(edits) => edits,
+            Err(e) => return self.editor.fail(format!("Can't apply {path}: {e}")),
+        };
+        if edits.is_empty() {
+            return self.editor.message = format!("{path} has no edits");
+        }
+        if !self.editor.check_writable() {
+            return;
+        }
+        if let Err(e) = patchfile::check_bounds(&edits, self.editor.len()) {
+            return self.editor.fail(format!("Can't apply {path}: {e}"));
+        }
+
+        let document = &mut self.editor.document;
+        document.end_undo_group();
+        document.begin_undo_group();
+        let result = edits
+            .iter()
+            .try_for_each(|edit| document.overwrite(edit.offset, &edit.bytes));
+        document.end_undo_group();
+        if let Err(e) = result {
+            return self.editor.fail(format!("Can't apply {path}: {e}"));
+        }
+
+        let bytes: usize = edits.iter().map(|edit| edit.bytes.len()).sum();
+        self.editor.message = format!(
+            "Applied {} edit{} from {path}, {bytes:X} bytes",
+            edits.len(),
+            if edits.len() == 1 { "" } else { "s" }
+        );
+    }
+
+    /// The `patchfile create` command, writes the unsaved changes of the buffer as a patch file
+    fn create_patch(&mut self, path: &str) {
+        let modifications = self.editor.document.modifications();
+        if modifications.is_empty() {
+            return self.editor.fail("No unsaved changes to write");
+        }
+
+        let text = match patchfile::create(&modifications) {
+            Ok(text) => text,
+            Err(e) => return self.editor.fail(format!("Can't create a patch file: {e}")),
+        };
+        match fs::write(path, text) {
+            Ok(()) => {
+                self.editor.message = format!(
+                    "Wrote {} edit{} to {path}",
+                    modifications.len(),
+                    if modifications.len() == 1 { "" } else { "s" }
+                )
+            }
+            Err(e) => self.editor.fail(format!("Can't write {path}: {e}")),
+        }
+    }
+
+    /// Opens the files of a session, or switches to them if they are open already, and puts
+    /// the cursor and view back where they were. Returns what couldn't be restored
+    pub fn restore(&mut self, session: &Session) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut current = None;
+
+        for (index, buffer) in session.buffers.iter().enumerate() {
+            if let Err(e) = self.open_buffer(&buffer.path) {
+                problems.push(e);
+                continue;
+            }
+
+            if index == session.current {
+                current = Some(self.current);
+            }
+
+            let editor = &mut self.editor;
+            if editor.len() as u64 != buffer.size {
+                problems.push(format!(
+                    "{} changed size from {:X} to {:X} bytes",
+                    buffer.path.display(),
+                    buffer.size,
+                    editor.len()
+                ));
+            }
+
+            editor.top_line = buffer.top_line;
+            editor.scroll(0);
+            editor.set_cursor(buffer.cursor);
+            editor.selection = buffer
+                .selection
+                .filter(|_| editor.len() > 0)
+                .map(|anchor| anchor.min(editor.len() - 1));
+        }
+
+        // The grid is always BYTES_PER_LINE wide, so only colors carry over
+        if (session.cols, session.group) != (BYTES_PER_LINE, 0) {
+            problems.push(format!(
+                "a {} column dump isn't supported, showing {}",
+                session.cols, BYTES_PER_LINE
+            ));
+        }
+
+        // A session can turn colors off, but not back on where --color or NO_COLOR turned them off
+        let colors = session.colors && self.settings.colors;
+        self.editor.set_colors(colors);
+        for editor in &mut self.others {
+            editor.set_colors(colors);
+        }
+
+        if let Some(current) = current {
+            self.switch_buffer(current);
+        }
+
+        problems
+    }
+
+    /// Writes the dump of the buffer to a file, as JSON, as the lines on screen, as an HTML page
+    /// or as Markdown. All but JSON take an offset and length to write only part of it,
+    /// `export dump` takes `--ansi` to keep the colors and `export md` takes `--fenced` for a code
+    /// block instead of a table and `-f` to write more than `MARKDOWN_ROWS` lines
+    fn export(&mut self, args: &[String]) {
+        let usage = "Usage: export json <file> | export dump <file> [--ansi] [<offset> <length>] | export html <file> [<offset> <length>] | export md <file> [<offset> <length>] [-f] [--fenced]";
+        let (Some(format), Some(path)) = (
+            args.first().map(|s| s.as_str()),
+            args.get(1).filter(|path| !path.is_empty()),
+        ) else {
+            return self.editor.fail(usage);
+        };
+
+        let flags = ["--ansi", "-f", "--fenced"];
+        let has = |flag: &str| args[2..].iter().any(|arg| arg == flag);
+        let (ansi, force, fenced) = (has("--ansi"), has("-f"), has("--fenced"));
+
+        match format {
+            "dump" if force || fenced => {
+                return self
+                    .editor
+                    .fail("Usage: export dump <file> [--ansi] [<offset> <length>]")
+            }
+            "html" if ansi || force || fenced => {
+                return self
+                    .editor
+                    .fail("export html is always colored, it takes only a file and a range")
+            }
+            "md" if ansi => {
+                return self
+                    .editor
+                    .fail("Usage: export md <file> [<offset> <length>] [-f] [--fenced]")
+            }
+            "dump" | "html" | "md" => {}
+            "json" if args.len() == 2 => {}
+            "json" => return self.editor.fail("export json takes only a file"),
+            _ => return self.editor.fail(usage),
+        }
+
+        let range = match args[2..]
+            .iter()
+            .filter(|arg| !flags.contains(&arg.as_str()))
+            .collect::<Vec<&String>>()[..]
+        {
+            [] => 0..self.editor.len(),
+            [offset, length] => match (self.offset(offset), self.offset(length)) {
+                (Ok(offset), Ok(length)) => offset..offset.saturating_add(length),
+                (Err(e), _) => return self.editor.fail(format!("Invalid offset '{offset}': {e}")),
+                (_, Err(e)) => return self.editor.fail(format!("Invalid length '{length}': {e}")),
+            },
+            _ => return self.editor.fail(usage),
+        };
+
+        let lines = range.len().div_ceil(self.editor.dump.cols.max(1));
+        if format == "md" && lines > MARKDOWN_ROWS && !force {
+            return self.editor.fail(format!(
+                "That is {} lines, more than {} for Markdown, -f to export it anyway",
+                lines, MARKDOWN_ROWS
+            ));
+        }
+
+        let bytes = match self.editor.document.read(range.clone()) {
+            Ok(bytes) => bytes,
+            Err(e) => return self.editor.fail(e.to_string()),
+        };
+
+        // Plain dumps are rendered without colors rather than stripped, so the columns stay lined up
+        let options = DumpOptions {
+            colors: ansi,
+            ..self.editor.dump
+        };
+        let name = self.editor.file.display().to_string();
+
+        let result = match format {
+            "json" if range == (0..self.editor.len()) && !ansi => Ok(()),
+            "json" => return self.editor.fail("export json writes the whole buffer"),
+            "dump" | "html" | "md" => Ok(()),
+            _ => return self.editor.fail(usage),
+        }
+        .and_then(|()| fs::File::create(path))
+        .and_then(|file| {
+            let mut out = io::BufWriter::new(file);
+            match format {
+                "json" => write_json(&mut out, &name, bytes, &options)?,
+                "html" => write_html(&mut out, &name, range.start, bytes, &options, |offset| {
+                    Marks {
+                        found: self.editor.in_match(offset),
+                        modified: self.editor.document.is_modified(offset),
+                    }
+                })?,
+                "md" => write_markdown(&mut out, range.start, bytes, &options, fenced)?,
+                _ => write_dump(&mut out, range.start, bytes, &options)?,
+            }
+            out.flush()
+        });
+
+        match result {
+            Ok(()) => self.editor.message = format!("Exported the dump to {path}"),
+            Err(e) => self.editor.fail(format!("Can't write {path}: {e}")),
+        }
+    }
+
+    /// Starts looking for bytes, given as hex digits or as "text" in quotes.
+    /// A search that doesn't finish within a slice carries on from the main loop
+    fn find(&mut self, args: &[Token]) {
+        let usage = "Usage: find <hex> | find \"text\"";
+        let (needle, pattern) = match args {
+            [] => {
+                let last = self
+                    .search_history()
+                    .last()
+                    .map(|entry| entry.command.clone());
+                return match last {
+                    Some(command) => self.new_command(command),
+                    None => self.editor.fail(usage),
+                };
+            }
+            [text] if text.quoted && text.bytes.is_empty() => return self.editor.fail(usage),
+            [text] if text.quoted => (format!("\"{}\"", text.text()), Pattern::exact(&text.bytes)),
+            _ if args.iter().any(|arg| arg.quoted) => return self.editor.fail(usage),
+            _ => {
+                let needle = args
+                    .iter()
+                    .map(|arg| arg.text())
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                match Pattern::parse_hex(&needle) {
+                    Ok(pattern) => (needle, pattern),
+                    Err(e) => {
+                        return self.editor.fail(format!(
+                            "Invalid hex '{needle}': {e}. Put text in quotes: find \"text\""
+                        ))
+                    }
+                }
+            }
+        };
+
+        self.start_search(
+            ChunkedSearch::new(Finder::masked(&pattern), MAX_MATCHES),
+            needle,
+        );
+    }
+
+    /// `find -u16 "text"` and `find -u16be "text"`, for text stored as UTF-16 like the strings
+    /// of Windows programs. Matches cover the two or four bytes of every character
+    fn find_utf16(&mut self, endian: Endian, args: &[Token]) {
+        let [text] = args else {
+            return self.editor.fail("Usage: find -u16|-u16be \"text\"");
+        };
+        if text.bytes.is_empty() {
+            return self.editor.fail("Usage: find -u16|-u16be \"text\"");
+        }
+
+        // Escapes can make bytes that aren't characters, which have no UTF-16 to look for
+        let Ok(chars) = std::str::from_utf8(&text.bytes) else {
+            return self
+                .editor
+                .fail("The text isn't UTF-8, \\x escapes can only make ASCII for -u16");
+        };
+
+        let needle = utf16_bytes(chars, endian);
+        self.start_search(
+            ChunkedSearch::new(Finder::new(&needle), MAX_MATCHES),
+            format!("{} \"{}\"", Encoding::Utf16(endian).flag(), chars),
+        );
+    }
+
+    /// `find -f32 <value>` and `find -f64 <value>`, for the bytes of the float or with a
+    /// `~tolerance` for any float that close to it. Floats are looked for where they would
+    /// sit in an array of them unless `-unaligned` is given
+    fn find_float(&mut self, width: usize, args: &[String]) {
+        let usage = "Usage: find -f32|-f64 <value> [~<tolerance>] [-le|-be] [-unaligned]";
+        let (mut value, mut tolerance, mut endian, mut align) = (None, None, Endian::Little, width);
+
+        for arg in args {
+            match arg.as_str() {
+                "-le" => endian = Endian::Little,
+                "-be" => endian = Endian::Big,
+                "-unaligned" => align = 1,
+                _ if arg.starts_with('~') && tolerance.is_none() => tolerance = Some(&arg[1..]),
+                _ if value.is_none() => value = Some(arg.as_str()),
+                _ => return self.editor.fail(usage),
+            }
+        }
+
+        let Some(text) = value else {
+            return self.editor.fail(usage);
+        };
+
+        let parsed = match width {
+            4 => text.parse::<f32>().map(f64::from),
+            _ => text.parse::<f64>(),
+        };
+        let Ok(value) = parsed else {
+            return self.editor.fail(format!("Invalid number '{text}'"));
+        };
+
+        let tolerance = match tolerance.map(|tolerance| (tolerance, tolerance.parse::<f64>())) {
+            None => None,
+            Some((_, Ok(tolerance))) if tolerance >= 0.0 && tolerance.is_finite() => {
+                Some(tolerance)
+            }
+            Some((tolerance, _)) => {
+                return self.editor.fail(format!(
+                    "Invalid tolerance '{tolerance}', expected a number like ~0.001"
+                ))
+            }
+        };
+
+        let mut needle = format!("f{} {}", width * 8, text);
+        if let Some(tolerance) = tolerance {
+            needle += &format!(" ~{}", tolerance);
+        }
+
+        // NaNs have many bit patterns, so they are always looked for by value
+        let search = if tolerance.is_some() || value.is_nan() {
+            let scan = FloatScan {
+                width,
+                endian,
+                value,
+                tolerance: tolerance.unwrap_or(0.0),
+            };
+            ChunkedSearch::new(scan, MAX_MATCHES)
+        } else {
+            match float_bytes(text, width, endian) {
+                Ok(bytes) => ChunkedSearch::new(Finder::new(&bytes), MAX_MATCHES),
+                Err(e) => return self.editor.fail(e),
+            }
+        };
+
+        self.start_search(search.aligned(align), needle);
+    }
+
+    /// The `settext` command, writes text over the bytes at an offset. Escapes work in double
+    /// quotes, so `settext 10 "FULL\0"` writes a NUL too. `-z` adds one after the text and
+    /// `--extend` lets the text run past the end instead of refusing
+    fn set_text(&mut self, args: &[Token]) {
+        let usage = "Usage: settext <offset> \"text\" [-z] [--extend]";
+        let (mut nul, mut extend) = (false, false);
+        let mut rest = Vec::new();
+
+        for arg in args {
+            match (arg.quoted, arg.bytes.as_slice()) {
+                (false, b"-z") => nul = true,
+                (false, b"--extend") => extend = true,
+                _ => rest.push(arg),
+            }
+        }
+
+        let [offset, text] = rest.as_slice() else {
+            return self.editor.fail(usage);
+        };
+
+        let offset = match self.offset(&offset.text()) {
+            Ok(offset) if offset <= self.editor.len() => offset,
+            Ok(_) => return self.editor.fail("Offset out of range."),
+            Err(e) => {
+                return self
+                    .editor
+                    .fail(format!("Invalid offset '{}': {e}", offset.text()))
+            }
+        };
+
+        let mut text = text.bytes.clone();
+        if nul {
+            text.push(0);
+        }
+
+        if text.is_empty() {
+            return self.editor.fail(usage);
+        }
+
+        // Written the way the text column reads it, so the text shows as typed
+        let encoding = self.editor.dump.text;
+        let bytes = match encoding.encode(&text) {
+            Ok(bytes) => bytes,
+            Err(e) => return self.editor.fail(format!("Can't write the text: {e}")),
+        };
+        let odd_units = self.editor.dump.odd_units;
+        if encoding != Encoding::Ascii && unit_start(offset, odd_units) != offset {
+            return self.editor.fail(format!(
+                "{offset:X} is in the middle of a UTF-16 code unit, they start at {} offsets",
+                if odd_units { "odd" } else { "even" }
+            ));
+        }
+
+        let end = offset + bytes.len();
+        if end > self.editor.len() && !extend {
+            return self.editor.fail(format!(
+                "The text runs {:X} bytes past the end, use --extend to make the file longer",
+                end - self.editor.len()
+            ));
+        }
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        // A command's change is its own undo step, even in the middle of typing over the grid
+        self.editor.document.end_undo_group();
+        match self.editor.document.overwrite_extending(offset, &bytes) {
+            Ok(()) => {
+                self.editor.message = format!("Wrote {:X} bytes at {:08X}", bytes.len(), offset)
+            }
+            Err(e) => self.editor.fail(e.to_string()),
+        }
+    }
+
+    /// `insert-file <path> [<offset>]` puts another file's bytes in front of an offset, and
+    /// `read-file <path> [<offset>] [--extend]` writes them over the bytes there instead.
+    /// The offset is the cursor if it is left out. Nothing changes if the file can't be read
+    fn splice_file(&mut self, args: &[String], insert: bool) {
+        let usage = match insert {
+            true => "Usage: insert-file <path> [<offset>]",
+            false => "Usage: read-file <path> [<offset>] [--extend]",
+        };
+        let extend = !insert && args.iter().any(|arg| arg == "--extend");
+        let args = args
+            .iter()
+            .filter(|arg| insert || *arg != "--extend")
+            .collect::<Vec<&String>>();
+
+        let (path, offset) = match args.as_slice() {
+            [path] => (path, self.editor.cursor.min(self.editor.len())),
+            [path, offset] => match self.offset(offset) {
+                Ok(offset) if offset <= self.editor.len() => (path, offset),
+                Ok(_) => return self.editor.fail("Offset out of range."),
+                Err(e) => return self.editor.fail(format!("Invalid offset '{offset}': {e}")),
+            },
+            _ => return self.editor.fail(usage),
+        };
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => return self.editor.fail(format!("Can't read {path}: {e}")),
+        };
+
+        let end = offset + bytes.len();
+        if !insert && end > self.editor.len() && !extend {
+            return self.editor.fail(format!(
+                "{path} runs {:X} bytes past the end, use --extend to make the file longer",
+                end - self.editor.len()
+            ));
+        }
+
+        // A command's change is its own undo step, even in the middle of typing over the grid
+        self.editor.document.end_undo_group();
+        let result = match insert {
+            true => self.editor.document.insert(offset, &bytes),
+            false => self.editor.document.overwrite_extending(offset, &bytes),
+        };
+
+        if let Err(e) = result {
+            return self.editor.fail(e.to_string());
+        }
+
+        if insert {
+            self.editor.shift_for_insert(offset, bytes.len());
+        }
+
+        self.editor.message = format!(
+            "{} {:X} bytes from {path} at {:08X}",
+            if insert { "Inserted" } else { "Wrote" },
+            bytes.len(),
+            offset
+        );
+    }
+
+    /// The `carve` command, writes every region that starts with a signature to a file of its
+    /// own in a directory and lists them, numbered for `goto #<n>`. Nothing is written when a
+    /// file of the same name is there already, unless -f is given. The signature is looked for
+    /// a slice at a time like a search, and Esc stops it before anything is written
+    fn carve(&mut self, args: &[String]) {
+        let usage = "Usage: carve <signature> <length> <dir>, carve <signature> to <end> <dir> \
+                     or carve jpeg|png|gif|zip <dir>, with -f to replace files";
+        let force = args.iter().any(|arg| arg == "-f");
+        let args = args
+            .iter()
+            .filter(|arg| *arg != "-f")
+            .map(|arg| arg.as_str())
+            .collect::<Vec<&str>>();
+
+        let hex = |text: &str| {
+            Pattern::parse_hex(text).map_err(|e| format!("Invalid signature '{text}': {e}"))
+        };
+        let parsed = match args[..] {
+            [name, dir] => match Preset::find(name) {
+                Some(preset) => Ok((preset.patterns(), dir)),
+                None => Err(format!("Unknown preset '{name}'. {usage}")),
+            },
+            [start, "to", end, dir] => hex(start).and_then(|start| {
+                let marker = hex(end)?;
+                Ok((
+                    (
+                        start,
+                        Extent::End {
+                            marker,
+                            trailing: 0,
+                        },
+                    ),
+                    dir,
+                ))
+            }),
+            [start, length, dir] => hex(start).and_then(|start| match self.offset(length) {
+                Ok(0) => Err("The length can't be 0".to_string()),
+                Ok(length) => Ok(((start, Extent::Length(length)), dir)),
+                Err(e) => Err(format!("Invalid length '{length}': {e}")),
+            }),
+            _ => Err(usage.to_string()),
+        };
+        let ((start, extent), dir) = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => return self.editor.fail(e),
+        };
+
+        let progress = Progress::new("carve", self.editor.len());
+        self.carving = Some(RunningCarve {
+            carver: Carver::new(&start, &extent, MAX_MATCHES),
+            progress,
+            dir: PathBuf::from(dir),
+            force,
+        });
+        self.continue_carve();
+    }
+
+    /// Looks for the carve's signature for a slice of time, then says how far it got or writes
+    /// the regions out if it's done
+    fn continue_carve(&mut self) {
+        let Some(running) = &mut self.carving else {
+            return;
+        };
+
+        let bytes = self.editor.document.bytes();
+        let started = Instant::now();
+        while !running.carver.is_done(bytes.len()) && started.elapsed() < SEARCH_SLICE {
+            running.carver.feed(bytes, SEARCH_CHUNK);
+        }
+
+        running.progress.update(running.carver.searched());
+        if !running.carver.is_done(bytes.len()) {
+            return self.editor.message = format!(
+                "{}, {} found so far",
+                running.progress.status(),
+                running.carver.regions().len()
+            );
+        }
+
+        if let Some(running) = self.carving.take() {
+            self.write_carved(running.carver.into_regions(), &running.dir, running.force);
+        }
+    }
+
+    /// Writes out the regions a carve found and lists them
+    fn write_carved(&mut self, regions: Vec<Region>, dir: &Path, force: bool) {
+        if regions.is_empty() {
+            return self.editor.fail("The signature isn't in the buffer");
+        }
+
+        // Every file is checked before the first is written, so a carve is done in full or not
+        if let Err(e) = fs::create_dir_all(dir) {
+            return self
+                .editor
+                .fail(format!("Can't make the directory {}: {e}", dir.display()));
+        }
+        let carved = regions.iter().filter(|region| region.carved());
+        if let Some(region) = carved
+            .clone()
+            .find(|region| !force && dir.join(carve::file_name(region.offset)).exists())
+        {
+            return self.editor.fail(format!(
+                "{} is in {} already, use carve ... -f to replace it",
+                carve::file_name(region.offset),
+                dir.display()
+            ));
+        }
+
+        let mut written = 0;
+        for region in carved {
+            let path = dir.join(carve::file_name(region.offset));
+            let range = region.offset..region.offset + region.length;
+            if let Err(e) = file::save(&path, &self.editor.document.bytes()[range]) {
+                return self.editor.fail(format!(
+                    "Can't write {}: {e}, {written} of the regions were written",
+                    path.display()
+                ));
+            }
+            written += 1;
+        }
+
+        let mut lines = vec![format!(
+            "{} match{} of the signature, {} written to {}, goto #<n> jumps to one:",
+            regions.len(),
+            if regions.len() == 1 { "" } else { "es" },
+            written,
+            dir.display()
+        )];
+        for (index, region) in regions.iter().enumerate() {
+            let what = match region.status {
+                Status::Whole => carve::file_name(region.offset),
+                Status::CutShort => {
+                    format!("{}, cut short by the end", carve::file_name(region.offset))
+                }
+                Status::NoEnd => "no end marker after it, not written".to_string(),
+                Status::Inside(carved) => format!("inside the region at {carved:X}, not written"),
+            };
+            lines.push(format!(
+                "#{:<3} {:08X}  {:<8X} {}",
+                index + 1,
+                region.offset,
+                region.length,
+                what
+            ));
+        }
+        if regions.len() == MAX_MATCHES {
+            lines.push(format!("stopped after {MAX_MATCHES} matches"));
+        }
+
+        let offsets = regions.iter().map(|region| region.offset).collect();
+        self.editor.entries = Some(("carve", offsets));
+        self.show_listing(lines);
+    }
+
+    /// The `extract` command, writes a range of the buffer as it is now to a new file. The range
+    /// is the selection when no offsets are given, and an existing file is only replaced with -f
+    fn extract(&mut self, args: &[String]) {
+        let usage = "Usage: extract [<offset> <length>] <file> [-f]";
+        let force = args.iter().any(|arg| arg == "-f");
+        let args = args
+            .iter()
+            .filter(|arg| *arg != "-f")
+            .collect::<Vec<&String>>();
+
+        let (range, path) = match args.as_slice() {
+            [path] => {
+                match self.editor.selected() {
+                    Some(range) => (*range.start()..*range.end() + 1, path),
+                    None => return self.editor.fail(
+                        "Nothing selected, select bytes or give extract <offset> <length> <file>",
+                    ),
+                }
+            }
+            [offset, length, path] => match (self.offset(offset), self.offset(length)) {
+                (Ok(offset), Ok(length)) => (offset..offset.saturating_add(length), path),
+                (Err(e), _) => return self.editor.fail(format!("Invalid offset '{offset}': {e}")),
+                (_, Err(e)) => return self.editor.fail(format!("Invalid length '{length}': {e}")),
+            },
+            _ => return self.editor.fail(usage),
+        };
+
+        let bytes = match self.editor.document.read(range) {
+            Ok(bytes) => bytes,
+            Err(e) => return self.editor.fail(e.to_string()),
+        };
+
+        let path = PathBuf::from(path);
+        match file::save_new(&path, bytes, force) {
+            Ok(()) => {
+                self.editor.message = format!(
+                    "Extracted {:X} bytes to {}",
+                    bytes.len(),
+                    path.canonicalize().unwrap_or(path).display()
+                )
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => self.editor.fail(format!(
+                "{} already exists, use extract ... -f to replace it",
+                path.display()
+            )),
+            Err(e) => self
+                .editor
+                .fail(format!("Can't extract to {}: {}", path.display(), e)),
+        }
+    }
+
+    /// The `findb` command, a search where some bits of each byte match anything
+    fn find_masked(&mut self, args: &[String]) {
+        let needle = args.join(" ");
+        if needle.is_empty() {
+            return self
+                .editor
+                .fail("Usage: findb <pattern>, like findb 1010xxxx 1F or findb A0/F0 Ax");
+        }
+
+        let pattern = match Pattern::parse(&needle) {
+            Ok(pattern) => pattern,
+            Err(e) => return self.editor.fail(format!("Invalid pattern: {e}")),
+        };
+
+        self.start_search(
+            ChunkedSearch::new(Finder::masked(&pattern), MAX_MATCHES),
+            needle,
+        );
+    }
+
+    /// Starts a search, remembering the command line that started it in the search history
+    fn start_search(&mut self, search: ChunkedSearch, needle: String) {
+        if let Some(entry) = SearchEntry::new(&self.command) {
+            self.search_history().record(entry);
+            self.save_searches();
+        }
+
+        let progress = Progress::new(&format!("Searching for {needle}"), self.editor.len());
+        self.search = Some(RunningSearch {
+            search,
+            progress,
+            needle,
+        });
+        self.continue_search();
+    }
+
+    /// The searches run before, read from the searches file the first time
+    fn search_history(&mut self) -> &mut SearchHistory {
+        self.searches.get_or_insert_with(|| {
+            match searches_path().map(fs::read_to_string) {
+                Some(Ok(contents)) => SearchHistory::parse(&contents),
+                // No file yet, or one that can't be read, is an empty history
+                _ => SearchHistory::default(),
+            }
+        })
+    }
+
+    /// Writes the search history to the searches file. Searching goes on if that fails, the
+    /// history is just not kept for next time
+    fn save_searches(&mut self) {
+        let (Some(path), Some(history)) = (searches_path(), &self.searches) else {
+            return;
+        };
+
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(path, history.contents());
+    }
+
+    /// The `searches` command, lists the searches run before, latest first. `searches <n>`
+    /// runs search n again
+    fn searches(&mut self, args: &[String]) {
+        let history = self.search_history();
+        let mut commands = history
+            .latest_first()
+            .map(|entry| (entry.kind.name(), entry.command.clone()))
+            .collect::<Vec<_>>();
+        if commands.is_empty() {
+            return self.editor.message = "No searches yet".to_string();
+        }
+
+        match args {
+            [] => {
+                let mut lines =
+                    vec!["Searches, latest first. searches <n> runs one again:".to_string()];
+                for (index, (kind, command)) in commands.iter().enumerate() {
+                    lines.push(format!("#{:<4} {:<6} {}", index + 1, kind, command));
+                }
+                self.show_listing(lines);
+            }
+            [index] => match index.trim_start_matches('#').parse::<usize>() {
+                Ok(index) if (1..=commands.len()).contains(&index) => {
+                    let (_, command) = commands.swap_remove(index - 1);
+                    self.new_command(command);
+                }
+                _ => self
+                    .editor
+                    .fail(format!("No search {index}, there are {}", commands.len())),
+            },
+            _ => self.editor.fail("Usage: searches [<n>]"),
+        }
+    }
+
+    /// Whether a search or a carve is still going
+    pub fn searching(&self) -> bool {
+        self.search.is_some() || self.carving.is_some()
+    }
+
+    /// Searches on for a slice of time, then says how far it got or finishes if it's done
+    pub fn continue_search(&mut self) {
+        if self.carving.is_some() {
+            return self.continue_carve();
+        }
+        let Some(running) = &mut self.search else {
+            return;
+        };
+
+        let bytes = self.editor.document.bytes();
+        let started = Instant::now();
+
+        while running.search.searched() < bytes.len()
+            && !running.search.is_full()
+            && started.elapsed() < SEARCH_SLICE
+        {
+            let start = running.search.searched();
+            running
+                .search
+                .feed(&bytes[start..(start + SEARCH_CHUNK).min(bytes.len())]);
+        }
+
+        running.progress.update(running.search.searched());
+        if running.search.searched() < bytes.len() && !running.search.is_full() {
+            self.editor.message = format!(
+                "{}, {} found so far",
+                running.progress.status(),
+                running.search.matches().len()
+            );
+        } else {
+            self.finish_search(false);
+        }
+    }
+
+    /// Stops a running search, keeping what it found so far, or a carve, writing nothing.
+    /// Returns false if there wasn't one
+    pub fn cancel_search(&mut self) -> bool {
+        if let Some(running) = self.carving.take() {
+            self.editor.message = format!("{}, nothing was written", running.progress.cancelled());
+            return true;
+        }
+        if self.search.is_none() {
+            return false;
+        }
+
+        self.finish_search(true);
+        true
+    }
+
+    /// Hands the matches to n and N and jumps to the first one after the cursor
+    fn finish_search(&mut self, stopped: bool) {
+        let Some(running) = self.search.take() else {
+            return;
+        };
+
+        let searched = running.search.searched();
+        let full = running.search.is_full();
+        let width = running.search.width();
+        let matches = running.search.into_matches();
+
+        if matches.is_empty() {
+            self.editor.set_matches(matches, width, &running.needle);
+            return self.editor.message = if stopped {
+                format!(
+                    "Search cancelled at {}% ({:08X}), no matches so far",
+                    running.progress.percent(),
+                    searched
+                )
+            } else {
+                format!("No matches for {}", running.needle)
+            };
+        }
+
+        self.editor.set_matches(matches, width, &running.needle);
+        self.editor.jump_to_match(true);
+
+        if stopped {
+            self.editor.message += &format!(
+                ", search cancelled at {}% ({:08X})",
+                running.progress.percent(),
+                searched
+            );
+        } else if full {
+            self.editor.message += &format!(", stopped looking after {}", MAX_MATCHES);
+        }
+    }
+
+    /// Runs a read-only task over a snapshot of the buffer on a worker thread, which wakes the
+    /// main loop up whenever it has progress or its result. Refused while another job runs
+    fn start_job(&mut self, name: &str, task: Box<dyn Task>) {
+        let bytes = self.editor.document.snapshot();
+        self.start_job_over(name, task, bytes);
+    }
+
+    /// Like `start_job`, over some bytes instead of the whole buffer
+    fn start_job_over(&mut self, name: &str, task: Box<dyn Task>, bytes: Arc<Vec<u8>>) {
+        if let Some(job) = &self.job {
+            return self.editor.fail(format!(
+                "Can't start {name}, {} is still running ({}%). Esc to stop it",
+                job.name(),
+                job.percent()
+            ));
+        }
+
+        let wake = self.settings.watch.clone();
+        let job = Job::spawn(name, bytes, task, move || {
+            if let Some(tx) = &wake {
+                let _ = tx.send(Input::JobUpdate);
+            }
+        });
+        self.editor.message = job.progress().status();
+        self.job = Some(job);
+    }
+
+    /// The `checksum` command, works out a checksum of the selection, a range or the whole
+    /// buffer in the background
+    fn checksum(&mut self, args: &[String]) {
+        let usage = "Usage: checksum adler32|fletcher16|fletcher32|crc32|crc32c|crc16-ccitt|crc16-arc \
+                     [<offset> <length>], or checksum crc --width <bits> --poly <poly> [--init <value>] \
+                     [--xorout <value>] [--reflect-in] [--reflect-out] [<offset> <length>]";
+        let Some((algorithm, rest)) = args.split_first() else {
+            return self.editor.fail(usage);
+        };
+
+        let (task, rest): (Box<dyn Task>, Vec<&String>) = match algorithm.as_str() {
+            "adler32" => (Box::new(Adler32::new()), rest.iter().collect()),
+            "fletcher16" => (Box::new(Fletcher16::new()), rest.iter().collect()),
+            "fletcher32" => (Box::new(Fletcher32::new()), rest.iter().collect()),
+            "crc" => match parse_crc_params(rest) {
+                Ok((params, rest)) => (Box::new(Crc::new(params)), rest),
+                Err(e) => return self.editor.fail(e),
+            },
+            name => match CrcParams::preset(name) {
+                Some(params) => (Box::new(Crc::new(params)), rest.iter().collect()),
+                None => {
+                    return self
+                        .editor
+                        .fail(format!("Unknown checksum '{name}'. {usage}"))
+                }
+            },
+        };
+
+        let range = match rest[..] {
+            [] => match self.editor.selected() {
+                Some(range) => *range.start()..*range.end() + 1,
+                None => 0..self.editor.len(),
+            },
+            [offset, length] => match (self.offset(offset), self.offset(length)) {
+                (Ok(offset), Ok(length)) => offset..offset.saturating_add(length),
+                (Err(e), _) => return self.editor.fail(format!("Invalid offset '{offset}': {e}")),
+                (_, Err(e)) => return self.editor.fail(format!("Invalid length '{length}': {e}")),
+            },
+            _ => return self.editor.fail(usage),
+        };
+
+        // The whole buffer is shared as it is, a part of it has to be copied out
+        let bytes = match range == (0..self.editor.len()) {
+            true => self.editor.document.snapshot(),
+            false => match self.editor.document.read(range) {
+                Ok(bytes) => Arc::new(bytes.to_vec()),
+                Err(e) => return self.editor.fail(e.to_string()),
+            },
+        };
+
+        self.start_job_over(&format!("checksum {algorithm}"), task, bytes);
+    }
+
+    /// The `entmap` command, works out the entropy of every block of the buffer in the
+    /// background and draws it as a map, a character per block
+    fn entmap(&mut self, args: &[String]) {
+        let (width, height) = terminal_size();
+        let block = match args {
+            // Room for the map with its header and legend, on one page
+            [] => entmap::default_block(self.editor.len(), width, height.saturating_sub(4)),
+            [block] => match parse_size(block) {
+                Ok(0) => return self.editor.fail("The block size can't be 0"),
+                Ok(block) => block,
+                Err(e) => {
+                    return self
+                        .editor
+                        .fail(format!("Invalid block size '{block}': {e}"))
+                }
+            },
+            _ => {
+                return self
+                    .editor
+                    .fail("Usage: entmap [<block size>], like entmap 4k")
+            }
+        };
+
+        let task = EntropyMap::new(block, width, self.editor.dump.colors);
+        self.start_job("entmap", Box::new(task));
+        if self.job.is_some() {
+            self.editor.map_block = Some(block);
+        }
+    }
+
+    /// The `histogram` command, charts how often the byte values come up in the selection, a
+    /// range or the buffer: in buckets of values, or the most common values with --per-byte
+    fn histogram(&mut self, args: &[String]) {
+        let usage = "Usage: histogram [<offset> <length>] [<buckets>] [--per-byte [<n>]]";
+        let per_byte = args.iter().position(|arg| arg == "--per-byte");
+        let (args, top) = match per_byte {
+            None => (args, None),
+            Some(at) => match &args[at + 1..] {
+                [] => (&args[..at], Some(16)),
+                [top] => match top.parse::<usize>() {
+                    Ok(top) if top > 0 => (&args[..at], Some(top)),
+                    _ => return self.editor.fail(format!("Invalid count '{top}'. {usage}")),
+                },
+                _ => return self.editor.fail(usage),
+            },
+        };
+
+        let (range, buckets) = match args {
+            [offset, length, ..] => match (self.offset(offset), self.offset(length)) {
+                (Ok(offset), Ok(length)) => {
+                    (Some(offset..offset.saturating_add(length)), &args[2..])
+                }
+                (Err(e), _) => return self.editor.fail(format!("Invalid offset '{offset}': {e}")),
+                (_, Err(e)) => return self.editor.fail(format!("Invalid length '{length}': {e}")),
+            },
+            _ => (None, args),
+        };
+        let buckets = match (buckets, top) {
+            ([], _) => 16,
+            ([buckets], None) => match buckets.parse::<usize>() {
+                Ok(buckets) if (1..=256).contains(&buckets) => buckets,
+                _ => {
+                    return self
+                        .editor
+                        .fail("Buckets are a decimal number from 1 to 256")
+                }
+            },
+            _ => return self.editor.fail(usage),
+        };
+
+        let range = range.unwrap_or_else(|| match self.editor.selected() {
+            Some(range) => *range.start()..*range.end() + 1,
+            None => 0..self.editor.len(),
+        });
+        let bytes = match self.editor.document.read(range.clone()) {
+            Ok(bytes) => bytes,
+            Err(e) => return self.editor.fail(e.to_string()),
+        };
+
+        let mut counted = Entropy::new();
+        counted.update(bytes);
+        let rows = match top {
+            Some(top) => histogram::top_values(counted.counts(), top),
+            None => histogram::buckets(counted.counts(), buckets),
+        };
+
+        let mut lines = vec![match top {
+            Some(_) => format!(
+                "The {} most common of {:X} bytes at {:X}, {:.4} bits of entropy per byte:",
+                rows.len(),
+                range.len(),
+                range.start,
+                counted.bits_per_byte()
+            ),
+            None => format!(
+                "{:X} bytes at {:X} in {} buckets, {:.4} bits of entropy per byte:",
+                range.len(),
+                range.start,
+                buckets,
+                counted.bits_per_byte()
+            ),
+        }];
+        lines.extend(histogram::render(&rows, counted.total(), terminal_size().0));
+        self.show_listing(lines);
+    }
+
+    /// Shows how far the background job got, or its result once it's done
+    pub fn update_job(&mut self) {
+        let Some(job) = &mut self.job else {
+            return;
+        };
+
+        let message = match job.poll() {
+            None => return,
+            Some(JobEvent::Progress(_)) => return self.editor.message = job.progress().status(),
+            // A result of several lines, like a map, is a listing of its own
+            Some(JobEvent::Done(result)) if result.contains('\n') => {
+                self.job = None;
+                self.editor.message.clear();
+                return self.show_listing(result.lines().map(str::to_string).collect());
+            }
+            Some(JobEvent::Done(result)) => result,
+            Some(JobEvent::Cancelled) => job.progress().cancelled(),
+            Some(JobEvent::Failed) => {
+                let error = format!("{} failed", job.name());
+                self.job = None;
+                return self.editor.fail(error);
+            }
+        };
+
+        self.job = None;
+        self.editor.message = message;
+    }
+
+    /// Asks the background job to stop, its worker says so once it has. Returns false if there wasn't one
+    pub fn cancel_job(&mut self) -> bool {
+        let Some(job) = &self.job else {
+            return false;
+        };
+
+        job.cancel();
+        self.editor.message = format!("Stopping {}", job.name());
+        true
+    }
+
+    /// Copies bytes to a register, the unnamed one unless a letter comes first: the given
+    /// range, the selection or the byte under the cursor
+    pub fn yank(&mut self, args: &[String]) {
+        let (register, args) = split_register(args);
+        let range = match args {
+            [] => match self.editor.selected() {
+                Some(range) => *range.start()..*range.end() + 1,
+                None => self.editor.cursor..self.editor.cursor + 1,
+            },
+            [offset, length] => match (self.offset(offset), self.offset(length)) {
+                (Ok(offset), Ok(length)) => offset..offset.saturating_add(length),
+                (Err(e), _) => return self.editor.fail(format!("Invalid offset '{offset}': {e}")),
+                (_, Err(e)) => return self.editor.fail(format!("Invalid length '{length}': {e}")),
+            },
+            _ => {
+                return self
+                    .editor
+                    .fail("Usage: yank [<register>] [<offset> <length>]")
+            }
+        };
+
+        match self.editor.document.read(range) {
+            Ok(bytes) => {
+                self.registers.set(register, bytes.to_vec());
+                self.editor.message = match register {
+                    UNNAMED => format!("Yanked {:X} bytes", bytes.len()),
+                    name => format!("Yanked {:X} bytes into {name}", bytes.len()),
+                };
+                self.editor.selection = None;
+            }
+            Err(e) => self.editor.fail(e.to_string()),
+        }
+    }
+
+    /// Writes a register, the unnamed one unless a letter comes first, over the bytes at an
+    /// offset or at the cursor
+    pub fn put(&mut self, args: &[String]) {
+        let (register, args) = split_register(args);
+        let offset = match args {
+            [] => self.editor.cursor,
+            [offset] => match self.offset(offset) {
+                Ok(offset) => offset,
+                Err(e) => return self.editor.fail(format!("Invalid offset '{offset}': {e}")),
+            },
+            _ => return self.editor.fail("Usage: put [<register>] [<offset>]"),
+        };
+
+        let Some(bytes) = self.registers.get(register) else {
+            return self.editor.fail(match register {
+                UNNAMED => "Nothing yanked yet.".to_string(),
+                name => format!("Register {name} is empty, yank {name} fills it"),
+            });
+        };
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        // A put is its own undo step, even in the middle of typing over the grid
+        self.editor.document.end_undo_group();
+        match self.editor.document.overwrite(offset, bytes) {
+            Ok(()) => self.editor.message = format!("Put {:X} bytes", bytes.len()),
+            Err(e) => self.editor.fail(e.to_string()),
+        }
+    }
+
+    /// The `registers` command, lists the registers that hold something with their size and
+    /// first bytes
+    fn list_registers(&mut self) {
+        let mut lines =
+            vec!["Registers, yank <letter> fills one and put <letter> writes it:".to_string()];
+        for (name, bytes) in self.registers.filled() {
+            lines.push(format!(
+                "{name}  {:>8X} bytes  {}",
+                bytes.len(),
+                preview_bytes(bytes)
+            ));
+        }
+
+        match lines.len() {
+            1 => self.editor.message = "Nothing yanked yet.".to_string(),
+            _ => self.show_listing(lines),
+        }
+    }
+
+    /// Handles Ctrl+C, pressing it again while asking about unsaved changes forces the quit
+    pub fn interrupt(&mut self) {
+        if let Some(Pending::ConfirmQuit(_)) = self.pending {
+            self.pending = None;
+            self.exit_code = Some(INTERRUPTED_EXIT_CODE);
+        } else {
+            self.request_quit(INTERRUPTED_EXIT_CODE);
+        }
+    }
+
+    /// Cancels a pending interactive step, returns false if there was nothing to cancel
+    pub fn cancel(&mut self) -> bool {
+        self.listing = None;
+        self.pending.take().is_some()
+    }
+
+    pub fn new_command(&mut self, command: String) {
+        self.editor.message.clear();
+
+        // Feed the input to a command that is waiting on it
+        if let Some(pending) = self.pending.take() {
+            return self.finish_pending(pending, command);
+        }
+
+        // The rest of the line goes to the shell as it is, its quoting isn't ours
+        if command.trim_start().starts_with('!') {
+            self.command = command.trim().to_string();
+            return self.filter(command.trim_start());
+        }
+
+        match tokenize(&command) {
+            Ok(tokens) if tokens.is_empty() => {}
+            Ok(tokens) => {
+                self.command = command.trim().to_string();
+                self.parse_command(tokens)
+            }
+            Err(e) => self
+                .editor
+                .fail(format!("Can't read the command: {e}, see 'help syntax'")),
+        }
+    }
+
+    fn finish_pending(&mut self, pending: Pending, input: String) {
+        match pending {
+            Pending::EditLine(line) => {
+                if input.is_empty() || !self.editor.check_writable() {
+                    return;
+                }
+
+                let bytes = match hex_to_bytes(&input) {
+                    Some(bytes) => bytes,
+                    None => return self.editor.fail(format!("Invalid hex '{input}'")),
+                };
+
+                // The line is replaced in place, so it has to stay the same length
+                let range = self.editor.line_range(line);
+                if bytes.len() != range.len() {
+                    return self.editor.fail(format!(
+                        "Expected {} bytes, got {}",
+                        range.len(),
+                        bytes.len()
+                    ));
+                }
+
+                // A command's change is its own undo step, even in the middle of typing over the grid
+                self.editor.document.end_undo_group();
+                if let Err(e) = self.editor.document.overwrite(range.start, &bytes) {
+                    self.editor.fail(e.to_string());
+                }
+            }
+            Pending::ConfirmQuit(code) => {
+                if input.eq_ignore_ascii_case("y") {
+                    self.exit_code = Some(code);
+                }
+            }
+            Pending::ConfirmReload => {
+                if input.eq_ignore_ascii_case("y") {
+                    self.reload();
+                }
+            }
+            Pending::ConfirmClose => {
+                if input.eq_ignore_ascii_case("y") {
+                    self.close_buffer();
+                }
+            }
+            Pending::SaveConflict(compress) => match input.to_ascii_lowercase().as_str() {
+                "o" | "overwrite" => self.save(None, true, compress),
+                "r" | "reload" => self.reload(),
+                _ => {}
+            },
+            Pending::ConfirmCompress(force) => match input.to_ascii_lowercase().as_str() {
+                "g" | "gzip" => self.save(None, force, Some(true)),
+                "r" | "raw" => self.save(None, force, Some(false)),
+                _ => {}
+            },
+            Pending::Replay => match input.to_ascii_lowercase().as_str() {
+                "r" | "replay" => self.replay_journal(),
+                "d" | "delete" => {
+                    let Some(path) = self.editor.leftover_journal() else {
+                        return;
+                    };
+                    self.editor.message = match fs::remove_file(&path) {
+                        Ok(()) => format!("Deleted {}", path.display()),
+                        Err(e) => {
+                            return self.editor.fail(format!(
+                                "Can't delete {}: {}",
+                                path.display(),
+                                e
+                            ))
+                        }
+                    };
+                    if self.settings.journal && !self.editor.readonly {
+                        self.editor.start_journal();
+                    }
+                }
+                _ => {
+                    if let Some(path) = self.editor.leftover_journal() {
+                        self.editor.message = format!(
+                            "Left {}, edits to {} aren't journaled while it is there",
+                            path.display(),
+                            self.editor.name()
+                        );
+                    }
+                }
+            },
+            Pending::Recover => match input.to_ascii_lowercase().as_str() {
+                "r" | "restore" => self.restore_recovery(),
+                "d" | "delete" => {
+                    let Some(path) = self.editor.recovery_path() else {
+                        return;
+                    };
+                    self.editor.message = match fs::remove_file(&path) {
+                        Ok(()) => format!("Deleted {}", path.display()),
+                        Err(e) => format!("Can't delete {}: {}", path.display(), e),
+                    };
+                }
+                _ => {}
+            },
+            Pending::Continue => {}
+        }
+    }
+
+    /// Asks what to do with the journal or recovery file of the buffer on screen, if a session
+    /// that didn't end left one behind. The journal comes first, it has the undo steps too
+    pub fn offer_recovery(&mut self) {
+        if let Some(path) = self.editor.leftover_journal() {
+            self.editor.message = format!(
+                "{} has edits to {} in it",
+                path.display(),
+                self.editor.name()
+            );
+            self.pending = Some(Pending::Replay);
+        } else if let Some(path) = self.editor.newer_recovery() {
+            self.editor.message = format!(
+                "{} has unsaved changes to {} in it",
+                path.display(),
+                self.editor.name()
+            );
+            self.pending = Some(Pending::Recover);
+        }
+    }
+
+    /// Puts the changes in the recovery file back over the buffer on screen, as one undo step
+    fn restore_recovery(&mut self) {
+        let Some(path) = self.editor.recovery_path() else {
+            return;
+        };
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        let restored = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| Recovery::parse(&contents))
+            .and_then(|recovery| recovery.apply(&mut self.editor.document));
+
+        match restored {
+            Ok(count) => {
+                self.editor.adopt_recovery();
+                self.editor.message = format!(
+                    "Restored {} change{} from {}, save to keep them",
+                    count,
+                    if count == 1 { "" } else { "s" },
+                    path.display()
+                );
+            }
+            Err(e) => self
+                .editor
+                .fail(format!("Can't restore from {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Replays the edits in the journal of the buffer on screen
+    fn replay_journal(&mut self) {
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        let name = self.editor.name();
+        match self.editor.replay_journal(self.settings.journal) {
+            Ok((count, damaged)) => {
+                self.editor.message = format!(
+                    "Replayed {} edit{} to {}, save to keep them",
+                    count,
+                    if count == 1 { "" } else { "s" },
+                    name
+                );
+                if damaged {
+                    self.editor.message +=
+                        ". The end of the journal was damaged, what came after is lost";
+                }
+            }
+            Err(e) => self
+                .editor
+                .fail(format!("Can't replay the journal of {name}: {e}")),
+        }
+    }
+
+    /// Logs the edits made in every buffer and writes them to its journal, after each key.
+    /// They are put down to the command line run, or to typing in the mode they were typed in
+    pub fn flush_journals(&mut self) {
+        let command = match (self.command.is_empty(), self.editor.mode) {
+            (false, _) => self.command.clone(),
+            (true, Mode::Overwrite) => "typed hex".to_string(),
+            (true, Mode::Text) => "typed text".to_string(),
+            (true, _) => "key".to_string(),
+        };
+        // A command waiting for input hasn't made its edits yet
+        if self.pending.is_none() {
+            self.command.clear();
+        }
+
+        if let Err(e) = self.editor.flush_journal(&command) {
+            self.editor.fail(e);
+        }
+        for editor in &mut self.others {
+            if let Err(e) = editor.flush_journal(&command) {
+                self.editor.fail(e);
+            }
+        }
+    }
+
+    /// The `log` command, lists every edit of the session with when it was made and the
+    /// command that made it, or writes them to a CSV file
+    fn edit_log(&mut self, args: &[String]) {
+        let iso = args.iter().any(|arg| arg == "--iso");
+        let args = args
+            .iter()
+            .filter(|arg| *arg != "--iso")
+            .collect::<Vec<&String>>();
+        let entries = self.editor.log.entries();
+
+        match args[..] {
+            [] if entries.is_empty() => {
+                self.editor.message = "Nothing edited yet".to_string();
+            }
+            [] => {
+                let mut lines = vec![format!("Edits to {}, oldest first:", self.editor.name())];
+                lines.extend(entries.iter().map(|entry| entry.line(iso)));
+                self.show_listing(lines);
+            }
+            [export, path] if export == "export" => {
+                let mut csv = CSV_HEADER.to_string();
+                for entry in entries {
+                    csv += &entry.csv(iso);
+                }
+
+                let count = entries.len();
+                match fs::write(path, csv) {
+                    Ok(()) => self.editor.message = format!("Wrote {count} edits to {path}"),
+                    Err(e) => self.editor.fail(format!("Can't write {path}: {e}")),
+                }
+            }
+            _ => self
+                .editor
+                .fail("Usage: log [--iso] or log export <file> [--iso]"),
+        }
+    }
+
+    /// Writes the unsaved changes of every buffer to its recovery file, for the autosave timer
+    pub fn autosave(&mut self) {
+        if let Err(e) = self.editor.autosave() {
+            self.editor.fail(e);
+        }
+        for editor in &mut self.others {
+            if let Err(e) = editor.autosave() {
+                self.editor.fail(e);
+            }
+        }
+    }
+
+    /// Removes the recovery files and journals of every buffer, when quitting
+    pub fn remove_recovery_files(&mut self) {
+        self.editor.remove_recovery();
+        self.editor.remove_journal();
+        for editor in &mut self.others {
+            editor.remove_recovery();
+            editor.remove_journal();
+        }
+    }
+
+    /// Loads, shows, edits through or clears the structure template. `template <file>
+    /// [<offset>]` loads and shows it in one go
+    fn template(&mut self, args: &[String]) {
+        match args.first().map(|s| s.as_str()) {
+            Some("load") => self.load_template(&args[1..], false),
+            Some("show") if args.len() == 1 => self.show_template(),
+            Some("set") => self.set_template_field(&args[1..]),
+            Some("clear") if args.len() == 1 => self.editor.set_template(None),
+            Some(path) if !path.is_empty() => self.load_template(args, true),
+            _ => self.editor.fail(
+                "Usage: template [load] <file.toml> [<offset>] | template show | template set <field> <value> | template clear",
+            ),
+        }
+    }
+
+    /// Reads a template and puts it at an offset, the start of the file if none is given
+    fn load_template(&mut self, args: &[String], show: bool) {
+        let (path, base) = match args {
+            [path] => (path, 0),
+            [path, offset] => match self.offset(offset) {
+                Ok(offset) => (path, offset),
+                Err(e) => return self.editor.fail(format!("Invalid offset '{offset}': {e}")),
+            },
+            [] => return self.editor.fail("No template file specified."),
+            _ => {
+                return self
+                    .editor
+                    .fail("Usage: template load <file.toml> [<offset>]")
+            }
+        };
+        if path.is_empty() {
+            return self.editor.fail("No template file specified.");
+        }
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => return self.editor.fail(format!("Can't read {path}: {e}")),
+        };
+
+        match Template::parse(&contents) {
+            Ok(mut template) => {
+                template.base = base;
+                self.editor.message = format!(
+                    "Loaded template '{}' with {} fields at {:X}, type 'template show' to decode",
+                    template.name,
+                    template.fields.len(),
+                    base
+                );
+                self.editor.set_template(Some(template));
+                if show {
+                    self.show_template();
+                }
+            }
+            Err(e) => self.editor.fail(format!("Invalid template {path}: {e}")),
+        }
+    }
+
+    /// Lists each field of the template with its offset, size, type, first bytes and value
+    fn show_template(&mut self) {
+        let Some(template) = &self.editor.template else {
+            return self
+                .editor
+                .fail("No template loaded, use 'template load <file.toml>'.");
+        };
+        let bytes = self.editor.document.bytes();
+
+        let mut lines = vec![
+            format!("{} at {:X}:", template.name, template.base),
+            format!(
+                "{:<8}  {:>4}  {:<16} {:<10} {:<TEMPLATE_RAW_WIDTH$} value",
+                "offset", "size", "name", "type", "bytes"
+            ),
+        ];
+
+        for decoded in template.decode(bytes) {
+            let range = decoded.range.clone();
+            let mut raw =
+                bytes_to_hex(&bytes[range.start..range.end.min(range.start + TEMPLATE_RAW)]);
+            if range.len() > TEMPLATE_RAW {
+                raw += " …";
+            }
+            let value = match &decoded.value {
+                Some(value) => value.to_string(),
+                None => "truncated".to_string(),
+            };
+
+            lines.push(format!(
+                "{:08X}  {:>4}  {:<16} {:<10} {:<TEMPLATE_RAW_WIDTH$} {}",
+                range.start,
+                range.len(),
+                decoded.field.name,
+                decoded.field.kind.to_string(),
+                raw,
+                value
+            ));
+        }
+
+        self.show_listing(lines);
+    }
+
+    /// The `template set` command, writes a value into a field of the template in its type
+    fn set_template_field(&mut self, args: &[String]) {
+        let Some((name, value)) = args.split_first().filter(|(_, value)| !value.is_empty()) else {
+            return self
+                .editor
+                .fail("Usage: template set <field> <value>, like template set magic 7F454C46");
+        };
+        let value = value.join(" ");
+
+        let Some(template) = &self.editor.template else {
+            return self
+                .editor
+                .fail("No template loaded, use 'template load <file.toml>'.");
+        };
+        let encoded = match template
+            .decode(self.editor.document.bytes())
+            .into_iter()
+            .find(|decoded| decoded.field.name == *name)
+        {
+            None => Err(format!("No field '{name}' in the template.")),
+            Some(decoded) if decoded.value.is_none() => {
+                Err(format!("Field '{name}' runs past the end of the file."))
+            }
+            Some(decoded) => match decoded.field.encode(&value) {
+                // A longer string would move every field after it
+                Ok(bytes) if bytes.len() > decoded.range.len() => Err(format!(
+                    "'{value}' doesn't fit in the {} bytes of '{name}', NUL included",
+                    decoded.range.len()
+                )),
+                Ok(bytes) => Ok((decoded.range.start, bytes)),
+                Err(e) => Err(format!("Can't set '{name}': {e}")),
+            },
+        };
+        let (offset, bytes) = match encoded {
+            Ok(encoded) => encoded,
+            Err(e) => return self.editor.fail(e),
+        };
+
+        if !self.editor.check_writable() {
+            return;
+        }
+
+        self.editor.document.end_undo_group();
+        match self.editor.document.overwrite(offset, &bytes) {
+            Ok(()) => {
+                self.editor.message =
+                    format!("Set {name} at {offset:X} to {}", bytes_to_hex(&bytes))
+            }
+            Err(e) => self.editor.fail(format!("Can't set '{name}': {e}")),
+        }
+    }
+
+    /// Reads the file again and says how it went
+    fn reload(&mut self) {
+        match self.editor.reload() {
+            Ok(()) => {
+                self.editor.remove_recovery();
+                self.editor.message = format!("Reloaded {}", self.editor.name());
+            }
+            Err(e) => self.editor.fail(e),
+        }
+    }
+
+    /// Writes the buffer out and quits, to the file it came from or to another path.
+    /// Devices are saved in place by writing back only the bytes that changed.
+    /// Saving over a file that changed on disk asks first, unless forced. A decompressed buffer
+    /// saved over its gzip file is compressed again when `compress` is Some(true), written as it
+    /// is with Some(false) and asks which with None. Other paths get the bytes as they are
+    fn save(&mut self, path: Option<PathBuf>, force: bool, compress: Option<bool>) {
+        let in_place = path.is_none();
+        let path = path.unwrap_or_else(|| self.editor.file.clone());
+        self.editor.sync_changes();
+
+        if in_place && self.editor.is_unnamed() {
+            return self
+                .editor
+                .fail("The buffer isn't a file yet, give a path: save <file>");
+        }
+
+        // What was read from a pipe is gone from it, there is nothing to write back to
+        if in_place && self.editor.kind == FileKind::Stream {
+            return self
+                .editor
+                .fail("Can't save back to a pipe, give a path: save <file>");
+        }
+
+        if in_place && !self.editor.check_writable() {
+            return;
+        }
+
+        // Never change what the file holds without being told to
+        let onto_gzip = self.editor.gzip.is_some() && path == self.editor.file;
+        let compress = match compress {
+            None if onto_gzip => return self.pending = Some(Pending::ConfirmCompress(force)),
+            compress => onto_gzip && compress == Some(true),
+        };
+
+        if in_place && !force {
+            if self.editor.disk_changed {
+                return self.pending = Some(Pending::SaveConflict(Some(compress)));
+            }
+
+            // The watcher only looks every so often, so check once more right before writing
+            if self
+                .editor
+                .on_disk
+                .is_some_and(|on_disk| !on_disk.still_matches(&path))
+            {
+                self.editor.disk_changed = true;
+                return self.editor.fail("file changed on disk since it was loaded — use save! to overwrite or reload to refresh");
+            }
+        }
+
+        let result = if compress {
+            if self.editor.kind == FileKind::Device {
+                return self
+                    .editor
+                    .fail("Can't compress onto a device, save the raw bytes or to a file instead");
+            }
+
+            let compressed = gzip::compress(self.editor.document.bytes());
+            let size = compressed.len();
+            file::save(&path, &compressed).map(|()| self.editor.gzip = Some(size))
+        } else if in_place && self.editor.kind == FileKind::Device {
+            if self.editor.length_changed {
+                return self
+                    .editor
+                    .fail("Can't change the size of a device, save to a file instead");
+            }
+
+            file::save_in_place(
+                &path,
+                self.editor.document.bytes(),
+                &self.editor.unsaved_ranges,
+            )
+        } else {
+            file::save(&path, self.editor.document.bytes())
+        };
+
+        if let Err(e) = result {
+            return self
+                .editor
+                .fail(format!("Can't save to {}: {}", path.display(), e));
+        }
+
+        self.editor.document.mark_clean();
+        self.editor.restart_journal();
+        self.editor.unsaved_ranges.clear();
+        self.editor.length_changed = false;
+        self.editor.record_disk_state();
+
+        let mut saved = format!("Saved to {}", path.canonicalize().unwrap_or(path).display());
+        if let (true, Some(size)) = (compress, self.editor.gzip) {
+            saved += &format!(", compressed to {:X} bytes", size);
+        }
+
+        // With other buffers open only this one is done, the editor keeps going
+        if self.others.is_empty() {
+            println!("{}", saved);
+        }
+        self.close_buffer();
+        self.editor.message = saved;
+    }
+
+    fn parse_command(&mut self, tokens: Vec<Token>) {
+        let args = tokens
+            .iter()
+            .map(|token| token.text())
+            .collect::<Vec<String>>();
+
+        match args[0].to_ascii_lowercase().as_str() {
+            "help" if args.get(1).is_some_and(|topic| topic == "syntax") => {
+                let mut lines = vec![
+                    "Arguments are separated by spaces. To put spaces in one:".to_string(),
+                    "  \"My Files/out.bin\"   double quotes, the escapes below work inside"
+                        .to_string(),
+                    "  'My Files/out.bin'   single quotes, everything is taken as it is"
+                        .to_string(),
+                    "  My\\ Files/out.bin    a backslash outside quotes takes the next char as is"
+                        .to_string(),
+                    String::new(),
+                    "Escapes inside double quotes, the same for every command:".to_string(),
+                ];
+
+                for (escape, meaning) in ESCAPES {
+                    lines.push(format!("  {:<6} {}", escape, meaning));
+                }
+
+                lines.push(String::new());
+                lines.push(
+                    "Any other backslash in double quotes is an error, like find \"a\\qb\"."
+                        .to_string(),
+                );
+                self.show_listing(lines);
+            }
+            "help" => {
+                let mut lines = vec!["Commands:".to_string()];
+
+                for command in &self.command_names {
+                    lines.push(format!("{} - {}", command.0, command.1));
+                }
+
+                self.show_listing(lines);
+            }
+            "quit" => {
+                self.request_quit(0);
+            }
+            "edit" | "tedit" => {
+                if let Some(offset) = args.get(1) {
+                    match self.offset(offset) {
+                        Ok(offset) if offset < self.editor.len() => self.editor.set_cursor(offset),
+                        Ok(_) => return self.editor.fail("Offset out of range."),
+                        Err(e) => {
+                            return self.editor.fail(format!("Invalid value '{offset}': {e}"))
+                        }
+                    }
+                }
+
+                match args[0].as_str() {
+                    "edit" => self.editor.enter_overwrite_mode(),
+                    _ => self.editor.enter_text_mode(),
+                }
+            }
+            "undo" => {
+                if !self.editor.undo() {
+                    self.editor.message = "Nothing to undo.".to_string();
+                }
+            }
+            "template" => self.template(&args[1..]),
+            "detect" => {
+                self.editor.message = self.editor.detect_file_type();
+            }
+            "filetype" => {
+                let hits = scan(&self.editor.signatures, self.editor.document.bytes());
+
+                if hits.is_empty() {
+                    self.editor.set_matches(Vec::new(), 1, "filetype");
+                    return self.editor.message = "No known signatures found.".to_string();
+                }
+
+                let mut lines = vec!["Signatures found:".to_string()];
+
+                // Runs of the same signature, like the entries of an archive, are listed once
+                let mut index = 0;
+                while index < hits.len() {
+                    let hit = hits[index];
+                    let run = hits[index..]
+                        .iter()
+                        .take_while(|other| other.signature == hit.signature)
+                        .count();
+
+                    let mut line = format!("{:08X}  {}", hit.offset, hit.signature.description);
+                    if run > 1 {
+                        line += &format!(
+                            " (and {} more up to {:08X})",
+                            run - 1,
+                            hits[index + run - 1].offset
+                        );
+                    }
+                    lines.push(line);
+
+                    index += run;
+                }
+
+                let offsets = hits.iter().map(|hit| hit.offset).collect();
+                self.editor.set_matches(offsets, 1, "filetype");
+                self.show_listing(lines);
+            }
+            "bindings" => {
+                let mut lines = vec!["Key bindings:".to_string()];
+                lines.extend(self.keymap.describe());
+                self.show_listing(lines);
+            }
+            "get" => {
+                if let Some(line) = args.get(1) {
+                    if line.is_empty() {
+                        return self.editor.fail("No line specified.");
+                    }
+
+                    // Convert the hex string to a decimal value
+                    let decimal_value = match self.offset(line) {
+                        Ok(value) => value / BYTES_PER_LINE,
+                        Err(e) => {
+                            return self.editor.fail(format!("Invalid value '{line}': {e}"));
+                        }
+                    };
+
+                    // Check if the line is out of range
+                    if decimal_value >= self.editor.line_count() {
+                        return self.editor.fail("Line out of range.");
+                    }
+
+                    let range = self.editor.line_range(decimal_value);
+                    let line_found = self.editor.document.read(range).unwrap_or_default();
+
+                    self.editor.message = bytes_to_hex(line_found);
+
+                    // The replacement is typed into the command line, Esc cancels it
+                    self.pending = Some(Pending::EditLine(decimal_value));
+                } else {
+                    self.editor.fail("No line specified.");
+                }
+            }
+            "save" | "save!" => match single_arg(&args[1..], "save [<file>]") {
+                Ok(path) => self.save(
+                    path.map(PathBuf::from),
+                    args[0].eq_ignore_ascii_case("save!"),
+                    None,
+                ),
+                Err(e) => self.editor.fail(e),
+            },
+            "open" => match single_arg(&args[1..], "open <file>") {
+                Ok(path) => self.open(path.unwrap_or_default()),
+                Err(e) => self.editor.fail(e),
+            },
+            "new" => self.new_buffer(&args[1..]),
+            "resize" => self.resize(&args[1..]),
+            "view" => self.view(&args[1..]),
+            "swap" => self.swap(&args[1..]),
+            "cmp" => self.compare_ranges(&args[1..]),
+            "move" => self.move_range(&args[1..]),
+            "randfill" => self.random_fill(&args[1..]),
+            "cyclic" => self.cyclic(&args[1..]),
+            "b64" => self.base64(&args[1..]),
+            "pad" => self.pad(&args[1..]),
+            "trim" => self.trim(&args[1..]),
+            "reverse" => self.reverse(&args[1..]),
+            "conv" => match &args[1..] {
+                [value] => match parse_value(value) {
+                    Ok(value) => self.show_listing(describe(value)),
+                    Err(e) => self.editor.fail(format!("Invalid value: {e}")),
+                },
+                _ => self
+                    .editor
+                    .fail("Usage: conv <value>, like conv 0x1F, conv 255 or conv -1"),
+            },
+            "changes" => self.changes(&args[1..]),
+            "log" => self.edit_log(&args[1..]),
+            "matches" => self.export_matches(&args[1..]),
+            "headers" => self.headers(),
+            "chunks" => self.chunks(),
+            "strings" => self.strings(&args[1..]),
+            "runs" => self.runs(&args[1..]),
+            "goto" => self.goto(&args[1..]),
+            "labels" => self.labels(&args[1..]),
+            "skip" => self.skip(None, &args[1..]),
+            "skipzero" => self.skip(Some(0x00), &args[1..]),
+            "skipff" => self.skip(Some(0xFF), &args[1..]),
+            "calc" => self.calc(&args[1..]),
+            "pos" | "where" => self.position(&args[1..]),
+            "palette" => self.palette(&args[1..]),
+            "gutter" => {
+                let on = match args.get(1).map(|arg| arg.to_ascii_lowercase()).as_deref() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => return self.editor.fail("Usage: gutter on|off"),
+                };
+
+                self.settings.gutter = on;
+                self.editor.set_gutter(on);
+                for editor in &mut self.others {
+                    editor.set_gutter(on);
+                }
+                self.editor.message = match on {
+                    true => "Showing offsets".to_string(),
+                    false => "Offsets hidden, the status bar still shows the cursor's".to_string(),
+                };
+            }
+            "inspect" => {
+                let offset = match args.get(1) {
+                    Some(_) => match self.offset(&args[1..].join(" ")) {
+                        Ok(offset) if offset < self.editor.len() => offset,
+                        Ok(_) => return self.editor.fail("Offset out of range."),
+                        Err(e) => return self.editor.fail(format!("Invalid offset: {e}")),
+                    },
+                    None => self.editor.cursor,
+                };
+                let end = (offset + MAX_WIDTH).min(self.editor.len());
+
+                match self.editor.document.bytes().get(offset..end) {
+                    Some(bytes) if !bytes.is_empty() => self.show_listing(table(offset, bytes)),
+                    _ => self.editor.fail("Nothing to inspect, the buffer is empty"),
+                }
+            }
+            "inspector" => {
+                let on = match args.get(1).map(|arg| arg.to_ascii_lowercase()).as_deref() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => return self.editor.fail("Usage: inspector on|off"),
+                };
+
+                self.editor.inspector = on;
+                for editor in &mut self.others {
+                    editor.inspector = on;
+                }
+                // The line comes out of the dump, which may have to scroll to keep the cursor
+                self.editor.set_cursor(self.editor.cursor);
+                self.editor.message = match on {
+                    true => "Decoding the bytes at the cursor below".to_string(),
+                    false => "Inspector off".to_string(),
+                };
+            }
+            "hlbyte" => {
+                let on = match args.get(1).map(|arg| arg.to_ascii_lowercase()).as_deref() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => return self.editor.fail("Usage: hlbyte on|off"),
+                };
+
+                self.editor.same_bytes = on;
+                for editor in &mut self.others {
+                    editor.same_bytes = on;
+                }
+                self.editor.message = match (on, self.editor.dump.colors) {
+                    (true, false) => "Colors are off, nothing is highlighted".to_string(),
+                    (true, true) => {
+                        "Highlighting bytes equal to the one under the cursor".to_string()
+                    }
+                    (false, _) => "Byte highlighting off".to_string(),
+                };
+            }
+            "stride" => self.stride(&args[1..]),
+            "colset" => self.colset(&args[1..]),
+            "encoding" => self.encoding(&args[1..]),
+            "squeeze" => {
+                let on = match args.get(1).map(|arg| arg.to_ascii_lowercase()).as_deref() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => return self.editor.fail("Usage: squeeze on|off"),
+                };
+
+                self.editor.dump.squeeze = on;
+                for editor in &mut self.others {
+                    editor.dump.squeeze = on;
+                }
+                // Rows cover more lines now, or fewer, so the cursor may have gone off screen
+                self.editor.set_cursor(self.editor.cursor);
+                self.editor.message = match on {
+                    true => "Squeezing runs of identical lines".to_string(),
+                    false => "Showing every line".to_string(),
+                };
+            }
+            "buffers" => self.list_buffers(),
+            "buffer" => {
+                let name = match single_arg(&args[1..], "buffer <n|name>") {
+                    Ok(name) => name.unwrap_or_default(),
+                    Err(e) => return self.editor.fail(e),
+                };
+                match self.find_buffer(name) {
+                    Some(index) => self.switch_buffer(index),
+                    None if name.is_empty() => self.editor.fail("Usage: buffer <n|name>"),
+                    None => self
+                        .editor
+                        .fail(format!("No buffer '{name}', see 'buffers'")),
+                }
+            }
+            "close" => {
+                if self.editor.modified() {
+                    self.pending = Some(Pending::ConfirmClose);
+                } else {
+                    self.close_buffer();
+                }
+            }
+            "session" => {
+                let usage = "session save [<file>] | session load [<file>]";
+                let path = match single_arg(args.get(2..).unwrap_or_default(), usage) {
+                    Ok(None) | Ok(Some("")) => DEFAULT_SESSION_PATH.to_string(),
+                    Ok(Some(path)) => path.to_string(),
+                    Err(e) => return self.editor.fail(e),
+                };
+
+                match args.get(1).map(|s| s.as_str()) {
+                    Some("save") => self.save_session(&path),
+                    Some("load") => self.load_session(&path),
+                    _ => self
+                        .editor
+                        .fail("Usage: session save [<file>] | session load [<file>]"),
+                }
+            }
+            "export" => self.export(&args[1..]),
+            "patchfile" => match &args[1..] {
+                [action, path] if action == "apply" => self.apply_patch(path),
+                [action, path] if action == "create" => self.create_patch(path),
+                _ => self
+                    .editor
+                    .fail("Usage: patchfile apply <file> | patchfile create <file>"),
+            },
+            "find" => match args.get(1).map(|flag| flag.as_str()) {
+                Some("-f32") => self.find_float(4, &args[2..]),
+                Some("-f64") => self.find_float(8, &args[2..]),
+                Some("-u16") => self.find_utf16(Endian::Little, &tokens[2..]),
+                Some("-u16be") => self.find_utf16(Endian::Big, &tokens[2..]),
+                _ => self.find(&tokens[1..]),
+            },
+            "findb" => self.find_masked(&args[1..]),
+            "searches" => self.searches(&args[1..]),
+            "settext" => self.set_text(&tokens[1..]),
+            "insert-file" => self.splice_file(&args[1..], true),
+            "read-file" => self.splice_file(&args[1..], false),
+            "extract" => self.extract(&args[1..]),
+            "carve" => self.carve(&args[1..]),
+            "compare" => self.compare(&args[1..]),
+            "hash" => match args.get(1).map(|s| s.as_str()) {
+                Some("sha256") => self.start_job("hash sha256", Box::new(Sha256::new())),
+                Some(other) => self
+                    .editor
+                    .fail(format!("Unknown hash '{other}', only sha256 is supported")),
+                None => self.editor.fail("Usage: hash sha256"),
+            },
+            "entropy" => self.start_job("entropy", Box::new(Entropy::new())),
+            "entmap" => self.entmap(&args[1..]),
+            "histogram" => self.histogram(&args[1..]),
+            "checksum" => self.checksum(&args[1..]),
+            "yank" => self.yank(&args[1..]),
+            "put" => self.put(&args[1..]),
+            "registers" => self.list_registers(),
+            "decompress" => match self.editor.decompress() {
+                Ok(()) => {
+                    self.editor.message = format!(
+                        "Decompressed {}, {:X} bytes",
+                        self.editor.name(),
+                        self.editor.len()
+                    )
+                }
+                Err(e) => self.editor.fail(e),
+            },
+            "reload" => {
+                if self.editor.modified() {
+                    self.pending = Some(Pending::ConfirmReload);
+                } else {
+                    self.reload();
+                }
+            }
+            _ if args[0].is_empty() => {}
+            _ => {
+                let names = self.command_names.iter().map(|(name, _)| name.as_str());
+                let hint = match did_you_mean(&suggestions(&args[0], names)) {
+                    Some(hint) => format!("{} ", hint),
+                    None => String::new(),
+                };
+
+                self.editor.fail(format!(
+                    "Unknown command '{}'. {}Type 'help' for a list of commands.",
+                    args[0], hint
+                ));
+            }
+        }
+    }
+}
+
+/// The one argument a command takes, None if it was left out. More than one is an error that
+/// points at quoting, since that's usually a path with spaces in it
+fn single_arg<'a>(args: &'a [String], usage: &str) -> Result<Option<&'a str>, String> {
+    match args {
+        [] => Ok(None),
+        [arg] => Ok(Some(arg)),
+        _ => Err(format!(
+            "Too many arguments, usage: {usage}. Put paths with spaces in quotes"
+        )),
+    }
+}
+
+/// The fill byte of `new` and `resize`, hex like offsets and 00 when it is left out
+fn parse_fill(fill: Option<&String>) -> Result<u8, String> {
+    let Some(fill) = fill else {
+        return Ok(0);
+    };
+
+    match parse_offset(fill) {
+        Ok(byte) => {
+            u8::try_from(byte).map_err(|_| format!("Invalid fill byte '{fill}': expected 00 to FF"))
+        }
+        Err(e) => Err(format!("Invalid fill byte '{fill}': {e}")),
+    }
+}
+
+/// Parses the parameters of `checksum crc`, leaving the arguments that aren't one of them
+fn parse_crc_params(args: &[String]) -> Result<(CrcParams, Vec<&String>), String> {
+    let mut params = CrcParams {
+        width: 0,
+        poly: 0,
+        init: 0,
+        reflect_in: false,
+        reflect_out: false,
+        xorout: 0,
+    };
+    let (mut width, mut poly) = (None, None);
+    let mut rest = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+            parse_size(value)
+                .map(|value| value as u64)
+                .map_err(|e| format!("Invalid {arg} '{value}': {e}"))
+        };
+
+        match arg.as_str() {
+            "--width" => width = Some(value()?),
+            "--poly" => poly = Some(value()?),
+            "--init" => params.init = value()?,
+            "--xorout" => params.xorout = value()?,
+            "--reflect-in" => params.reflect_in = true,
+            "--reflect-out" => params.reflect_out = true,
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown crc parameter '{flag}'"))
+            }
+            _ => rest.push(arg),
+        }
+    }
+
+    let (Some(width), Some(poly)) = (width, poly) else {
+        return Err(
+            "checksum crc needs --width and --poly, like checksum crc --width 16 --poly 0x1021"
+                .to_string(),
+        );
+    };
+    params.width = u32::try_from(width).unwrap_or(u32::MAX);
+    params.poly = poly;
+    params.check().map_err(|e| format!("Invalid crc: {e}"))?;
+
+    Ok((params, rest))
+}
+
+/// The register a `yank` or `put` names with a letter in front, or the unnamed one, and the
+/// arguments after it
+fn split_register(args: &[String]) -> (char, &[String]) {
+    match args.split_first() {
+        Some((first, rest)) => match parse_register(first) {
+            Some(name) => (name, rest),
+            None => (UNNAMED, args),
+        },
+        None => (UNNAMED, args),
+    }
+}
+
+/// Bytes as hex for a listing, the first 16 of them and how many more there are
+fn preview_bytes(bytes: &[u8]) -> String {
+    match bytes.len() {
+        0 => "nothing".to_string(),
+        len if len <= 16 => bytes_to_hex(bytes),
+        len => format!("{} ... and {:X} more", bytes_to_hex(&bytes[..16]), len - 16),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_it::{document::Document, file::LoadOptions};
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// Counts the bytes allocated by threads that turned COUNTING on, so tests running
+    /// alongside don't add to it
+    struct CountingAllocator;
+
+    static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+    thread_local! {
+        static COUNTING: Cell<bool> = const { Cell::new(false) };
+    }
+
+    fn count(size: usize) {
+        if COUNTING.with(|counting| counting.get()) {
+            ALLOCATED.fetch_add(size, Ordering::Relaxed);
+        }
+    }
+
+    // SAFETY: every call goes straight on to the system allocator
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            count(layout.size());
+            System.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            count(layout.size());
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            count(new_size);
+            System.realloc(ptr, layout, new_size)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+
+    fn settings() -> OpenSettings {
+        OpenSettings {
+            load: LoadOptions::default(),
+            colors: false,
+            theme: None,
+            gutter: true,
+            readonly: false,
+            read_write: false,
+            force: false,
+            decompress: false,
+            signatures: Vec::new(),
+            watch: None,
+            journal: false,
+        }
+    }
+
+    /// A command line editing a buffer of these bytes
+    fn command_line(bytes: Vec<u8>) -> CommandLine {
+        let file = env::temp_dir().join(format!("hex_it-commands-{}.bin", std::process::id()));
+        let editor = EditorState::new(file, Document::new(bytes), false);
+        CommandLine::new(editor, Keymap::default(), settings())
+    }
+
+    #[test]
+    fn commands_leave_a_large_buffer_where_it_is() {
+        const SIZE: usize = 8 << 20;
+        let mut command_line = command_line(vec![0xAB; SIZE]);
+        let data = command_line.editor.document.bytes().as_ptr();
+
+        ALLOCATED.store(0, Ordering::Relaxed);
+        COUNTING.with(|counting| counting.set(true));
+        for command in [
+            "goto 400000",
+            "pos",
+            "goto 10",
+            "hlbyte AB",
+            "cyclic fill 20 8",
+        ] {
+            command_line.new_command(command.to_string());
+        }
+        COUNTING.with(|counting| counting.set(false));
+
+        // Handing the editor back and forth used to clone the whole buffer each time
+        let allocated = ALLOCATED.load(Ordering::Relaxed);
+        assert!(allocated < SIZE / 8, "{allocated} bytes allocated");
+        assert_eq!(command_line.editor.document.bytes().as_ptr(), data);
+        assert_eq!(command_line.editor.document.get(0x20), Some(b'a'));
+    }
+
+    #[test]
+    fn open_failures_keep_the_buffer_on_screen() {
+        let mut command_line = command_line(b"kept".to_vec());
+        let file = command_line.editor.file.clone();
+        let missing = env::temp_dir().join(format!("hex_it-missing-{}", std::process::id()));
+        let directory = env::temp_dir();
+
+        for path in [&missing, &directory] {
+            command_line.new_command(format!("open '{}'", path.display()));
+
+            let message = &command_line.editor.message;
+            assert!(
+                message.starts_with(&format!("Can't open {}: ", path.display())),
+                "{message}"
+            );
+            assert_eq!(command_line.buffer_count(), 1);
+            assert_eq!(command_line.editor.file, file);
+            assert_eq!(command_line.editor.document.bytes(), b"kept");
+        }
+    }
+
+    #[test]
+    fn tab_completes_paths_after_open() {
+        let directory = env::temp_dir().join(format!("hex_it-tab-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        for name in ["dump-a.bin", "dump-b.bin"] {
+            fs::write(directory.join(name), b"").unwrap();
+        }
+        let mut command_line = command_line(Vec::new());
+
+        let mut input = InputLine::default();
+        for c in format!("open {}/du", directory.display()).chars() {
+            input.insert(c);
+        }
+        command_line.complete(&mut input);
+
+        let line = input.chars.iter().collect::<String>();
+        assert_eq!(line, format!("open {}/dump-", directory.display()));
+        assert_eq!(command_line.editor.message, "dump-a.bin  dump-b.bin");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn resize_shrinks_and_grows_in_one_undo_step() {
+        let mut command_line = command_line(vec![0xAA; 0x10]);
+
+        command_line.new_command("resize 8".to_string());
+        assert_eq!(command_line.editor.document.bytes(), [0xAA; 8]);
+        assert_eq!(command_line.editor.message, "Resized from 10 to 8 bytes");
+
+        command_line.new_command("resize 12".to_string());
+        assert_eq!(&command_line.editor.document.bytes()[8..], [0; 4]);
+        command_line.new_command("resize 16 FF".to_string());
+        assert_eq!(&command_line.editor.document.bytes()[0xC..], [0xFF; 4]);
+
+        command_line.new_command("resize 16".to_string());
+        assert_eq!(
+            command_line.editor.message,
+            "The buffer is 10 bytes already."
+        );
+
+        // Each resize comes off with a single undo
+        command_line.new_command("undo".to_string());
+        assert_eq!(command_line.editor.len(), 0xC);
+        command_line.new_command("undo".to_string());
+        command_line.new_command("undo".to_string());
+        assert_eq!(command_line.editor.document.bytes(), [0xAA; 0x10]);
+    }
+
+    #[test]
+    fn resize_drops_what_is_past_the_new_end() {
+        let mut command_line = command_line(vec![0; 0x20]);
+        command_line.editor.set_matches(vec![4, 0x18], 2, "00 00");
+        command_line.editor.selection = Some(0x1C);
+
+        // Sizes are decimal like 512 or 4k, the message shows them in hex
+        command_line.new_command("resize 16".to_string());
+        assert_eq!(
+            command_line.editor.message,
+            "Resized from 20 to 10 bytes, matches and the selection past the end were dropped"
+        );
+        assert_eq!(command_line.editor.matches, [4]);
+        assert_eq!(command_line.editor.selection, None);
+        assert!(command_line.editor.cursor < 0x10);
+    }
+
+    #[test]
+    fn resize_refuses_devices() {
+        let mut command_line = command_line(vec![0; 0x10]);
+        command_line.editor.kind = FileKind::Device;
+
+        command_line.new_command("resize 32".to_string());
+        assert_eq!(
+            command_line.editor.message,
+            "Can't change the size of a device"
+        );
+        assert_eq!(command_line.editor.len(), 0x10);
+    }
+
+    #[test]
+    fn settext_stops_at_the_end_unless_told_to_extend() {
+        let mut command_line = command_line(vec![0; 8]);
+
+        // Ending exactly at the last byte still fits
+        command_line.new_command("settext 4 \"abcd\"".to_string());
+        assert_eq!(command_line.editor.document.bytes(), b"\0\0\0\0abcd");
+
+        command_line.new_command("settext 5 \"wxyz\"".to_string());
+        assert_eq!(
+            command_line.editor.message,
+            "The text runs 1 bytes past the end, use --extend to make the file longer"
+        );
+        assert_eq!(command_line.editor.len(), 8);
+
+        command_line.new_command("settext 5 \"wxyz\" --extend".to_string());
+        assert_eq!(command_line.editor.document.bytes(), b"\0\0\0\0awxyz");
+
+        // -z counts its NUL towards the end too
+        command_line.new_command("settext 6 \"xyz\" -z".to_string());
+        assert_eq!(command_line.editor.len(), 9);
+        command_line.new_command("settext 5 \"xyz\" -z".to_string());
+        assert_eq!(command_line.editor.document.bytes(), b"\0\0\0\0axyz\0");
+    }
+
+    #[test]
+    fn pad_appends_filler_up_to_the_alignment() {
+        let mut command_line = command_line(vec![0; 10]);
+
+        command_line.new_command("pad 16".to_string());
+        assert_eq!(command_line.editor.message, "Padded 6 bytes to 10");
+        assert_eq!(&command_line.editor.document.bytes()[10..], [0xFF; 6]);
+
+        command_line.new_command("pad 16".to_string());
+        assert_eq!(
+            command_line.editor.message,
+            "10 is a multiple of 10 already, nothing padded"
+        );
+        command_line.new_command("pad 0".to_string());
+        assert_eq!(
+            command_line.editor.message,
+            "The alignment has to be more than 0"
+        );
+        command_line.new_command("pad --to 8".to_string());
+        assert_eq!(
+            command_line.editor.message,
+            "The buffer is 10 bytes already, more than 8. Use resize to cut it"
+        );
+        assert_eq!(command_line.editor.len(), 16);
+
+        // --to goes anywhere, with an alignment the size has to be a multiple of it
+        command_line.new_command("pad 8 00 --to 36".to_string());
+        assert_eq!(command_line.editor.message, "24 isn't a multiple of 8");
+        command_line.new_command("pad 8 00 --to 32".to_string());
+        assert_eq!(command_line.editor.len(), 32);
+        assert_eq!(&command_line.editor.document.bytes()[16..], [0; 16]);
+
+        // Each pad comes off with a single undo
+        command_line.new_command("undo".to_string());
+        assert_eq!(command_line.editor.len(), 16);
+        command_line.new_command("undo".to_string());
+        assert_eq!(command_line.editor.document.bytes(), [0; 10]);
+    }
+
+    /// A buffer padded to 0x20 bytes with the selection started on its last byte, then undone
+    fn undone_past_selection() -> CommandLine {
+        let mut command_line = command_line(vec![0; 0x10]);
+        command_line.new_command("pad 32".to_string());
+        command_line.editor.selection = Some(0x1F);
+        command_line.editor.cursor = 0x1F;
+        command_line.new_command("undo".to_string());
+        command_line
+    }
+
+    #[test]
+    fn filters_refuse_a_selection_past_the_end() {
+        // Undo drops a selection it leaves past the end
+        let mut command_line = undone_past_selection();
+        assert_eq!(command_line.editor.len(), 0x10);
+        assert_eq!(command_line.editor.selection, None);
+
+        // One that gets there anyway is an error, not a panic
+        command_line.editor.selection = Some(0x1F);
+        command_line.new_command("! cat".to_string());
+        assert_eq!(
+            command_line.editor.message,
+            "range F-20 is past the end of the buffer (10 bytes)"
+        );
+        assert_eq!(command_line.editor.document.bytes(), [0; 0x10]);
+    }
+
+    #[test]
+    fn base64_refuses_a_selection_past_the_end() {
+        let mut command_line = undone_past_selection();
+
+        for command in ["b64 encode", "b64 decode", "b64 decode replace"] {
+            command_line.editor.selection = Some(0x1F);
+            command_line.new_command(command.to_string());
+            assert_eq!(
+                command_line.editor.message, "range F-20 is past the end of the buffer (10 bytes)",
+                "{command}"
+            );
+        }
+        assert_eq!(command_line.editor.document.bytes(), [0; 0x10]);
+    }
+
+    #[test]
+    fn settext_writes_in_the_text_encoding() {
+        let mut command_line = command_line(vec![0; 16]);
+
+        command_line.new_command("encoding utf16le".to_string());
+        command_line.new_command("settext 2 \"Hi\"".to_string());
+        assert_eq!(&command_line.editor.document.bytes()[2..6], b"H\0i\0");
+
+        command_line.new_command("settext 3 \"x\"".to_string());
+        assert_eq!(
+            command_line.editor.message,
+            "3 is in the middle of a UTF-16 code unit, they start at even offsets"
+        );
+        command_line.new_command("settext 0 \"\\xFF\"".to_string());
+        assert_eq!(
+            command_line.editor.message,
+            "Can't write the text: byte 0 isn't text, only text can be written as UTF-16"
+        );
+
+        // -z ends the text with a NUL code unit
+        command_line.new_command("encoding utf16be".to_string());
+        command_line.new_command("settext 8 \"é\" -z".to_string());
+        assert_eq!(&command_line.editor.document.bytes()[8..12], b"\0\xE9\0\0");
+
+        command_line.new_command("encoding odd".to_string());
+        command_line.new_command("settext D \"x\"".to_string());
+        assert_eq!(&command_line.editor.document.bytes()[0xD..0xF], b"\0x");
+    }
+
+    #[test]
+    fn text_mode_types_utf16_over_the_unit_under_the_cursor() {
+        let mut command_line = command_line(vec![0; 7]);
+        let editor = &mut command_line.editor;
+        editor.set_text_encoding(Encoding::Utf16(Endian::Little), false);
+
+        // The cursor on the second byte of a unit types over the whole unit
+        editor.cursor = 1;
+        assert!(editor.type_char('é'));
+        assert_eq!(editor.cursor, 2);
+        assert!(editor.type_char('😀'));
+        assert_eq!(&editor.document.bytes()[..6], b"\xE9\0\x3D\xD8\x00\xDE");
+        assert_eq!(editor.cursor, 6);
+
+        assert!(!editor.type_char('a'));
+        assert_eq!(
+            editor.message,
+            "'a' takes 2 bytes, there is no room for it before the end"
+        );
+        assert!(!editor.type_char('\n'));
+
+        editor.set_text_encoding(Encoding::Ascii, false);
+        assert!(editor.type_char('a'));
+        assert_eq!(editor.document.get(6), Some(b'a'));
+    }
+}
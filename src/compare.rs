@@ -0,0 +1,92 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Comparing two buffers byte by byte, for `compare`, and two ranges of one buffer, for `cmp`.
+//! Where one buffer is longer than the other, its extra bytes count as different
+
+use std::ops::Range;
+
+/// Whether the byte at an offset differs between the two, or is only in one of them
+pub fn differs(a: &[u8], b: &[u8], offset: usize) -> bool {
+    a.get(offset) != b.get(offset)
+}
+
+/// Whether any byte in a range differs between the two
+pub fn range_differs(a: &[u8], b: &[u8], range: Range<usize>) -> bool {
+    range.into_iter().any(|offset| differs(a, b, offset))
+}
+
+/// Where the next run of differing bytes after the one at `from` starts, None if there isn't one
+pub fn next_difference(a: &[u8], b: &[u8], from: usize) -> Option<usize> {
+    let end = a.len().max(b.len());
+
+    // The run the offset is in doesn't count, it is the one being looked at
+    let mut offset = from;
+    while offset < end && differs(a, b, offset) {
+        offset += 1;
+    }
+
+    (offset..end).find(|&offset| differs(a, b, offset))
+}
+
+/// Where the run of differing bytes before the one at `from` starts, None if there isn't one
+pub fn prev_difference(a: &[u8], b: &[u8], from: usize) -> Option<usize> {
+    // Back to the start of the run the offset is in, then on to the end of the one before
+    let mut offset = from.min(a.len().max(b.len()));
+    while offset > 0 && differs(a, b, offset - 1) && differs(a, b, offset) {
+        offset -= 1;
+    }
+
+    let mut end = (0..offset).rev().find(|&offset| differs(a, b, offset))?;
+    while end > 0 && differs(a, b, end - 1) {
+        end -= 1;
+    }
+
+    Some(end)
+}
+
+/// A byte that differs between two ranges of one buffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RangeDifference {
+    /// How far into the ranges it is
+    pub offset: usize,
+    /// The byte in the first range
+    pub a: u8,
+    /// The byte in the second range
+    pub b: u8,
+}
+
+/// Compares the `length` bytes at `a` with the ones at `b`, returning the first `limit`
+/// differences and how many there are in all. The ranges may overlap, nothing is written while
+/// they are read. Fails if either goes past the end of the bytes
+pub fn compare_ranges(
+    bytes: &[u8],
+    a: usize,
+    b: usize,
+    length: usize,
+    limit: usize,
+) -> Result<(Vec<RangeDifference>, usize), String> {
+    let range = |start: usize, name: &str| match start.checked_add(length) {
+        Some(end) if end <= bytes.len() => Ok(&bytes[start..end]),
+        _ => Err(format!(
+            "range {name} {start:X}+{length:X} goes past the end at {:X}",
+            bytes.len()
+        )),
+    };
+    let (first, second) = (range(a, "A")?, range(b, "B")?);
+
+    let mut differences = Vec::new();
+    let mut total = 0;
+    for (offset, (&a, &b)) in first.iter().zip(second).enumerate() {
+        if a != b {
+            if differences.len() < limit {
+                differences.push(RangeDifference { offset, a, b });
+            }
+            total += 1;
+        }
+    }
+
+    Ok((differences, total))
+}
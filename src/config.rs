@@ -0,0 +1,174 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The config file, a line per setting:
+//!
+//! ```text
+//! # comments start with a hash
+//! bind [mode] <action> = <key>
+//! stream-limit <bytes>
+//! palette <name> = <color>
+//! gutter on|off
+//! autosave <seconds>|off
+//! journal on|off
+//! ```
+//!
+//! Extra file type signatures are read from `signatures` in the same directory, see magic::parse_signatures.
+//! The searches run before are kept in `searches` there too, see searches::SearchHistory
+
+use crate::{
+    file::DEFAULT_STREAM_LIMIT,
+    keymap::Keymap,
+    magic::{parse_signatures, Signature},
+    offset::parse_offset,
+    theme::{parse_color, Theme},
+};
+use std::{env, fs, path::PathBuf, time::Duration};
+
+/// Settings read from the config file
+pub struct Config {
+    /// The default bindings with the config's bindings applied on top
+    pub keymap: Keymap,
+    /// File type signatures from the signatures file, on top of the built-in ones
+    pub signatures: Vec<Signature>,
+    /// Most bytes read from a pipe before giving up
+    pub stream_limit: u64,
+    /// Colors changed from the default theme, by their names in theme::PALETTE_NAMES. Any of
+    /// them colors the dump as if --colors was given
+    pub palette: Vec<(String, &'static str)>,
+    /// Whether dump lines start with their offset
+    pub gutter: bool,
+    /// How often unsaved changes are written to recovery files, None to never
+    pub autosave: Option<Duration>,
+    /// Whether every edit is written to a journal next to the file, to replay after a crash
+    pub journal: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            keymap: Keymap::default(),
+            signatures: Vec::new(),
+            stream_limit: DEFAULT_STREAM_LIMIT,
+            palette: Vec::new(),
+            gutter: true,
+            autosave: None,
+            journal: true,
+        }
+    }
+}
+
+/// Where the config file lives: $HEX_IT_CONFIG, or hex_it/config under the XDG config directory
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("HEX_IT_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("hex_it").join("config"))
+}
+
+/// Where the user's extra signatures live, next to the config file
+pub fn signatures_path() -> Option<PathBuf> {
+    Some(config_path()?.with_file_name("signatures"))
+}
+
+/// Where the searches run before are kept, next to the config file
+pub fn searches_path() -> Option<PathBuf> {
+    Some(config_path()?.with_file_name("searches"))
+}
+
+impl Config {
+    /// Loads the config file if there is one, returning the problems found along with it
+    pub fn load() -> (Config, Vec<String>) {
+        let (mut config, mut errors) = match config_path().map(fs::read_to_string) {
+            Some(Ok(contents)) => Config::parse(&contents),
+            // No config file is fine, everything stays at the defaults
+            _ => (Config::default(), Vec::new()),
+        };
+
+        if let Some(Ok(contents)) = signatures_path().map(fs::read_to_string) {
+            let (signatures, signature_errors) = parse_signatures(&contents);
+            config.signatures = signatures;
+            errors.extend(signature_errors);
+        }
+
+        (config, errors)
+    }
+
+    /// Reads settings from the contents of a config file. Lines with problems are skipped
+    /// and described in the returned list, the rest still apply
+    pub fn parse(contents: &str) -> (Config, Vec<String>) {
+        let mut config = Config::default();
+        let mut errors = Vec::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+
+            // Skip blank lines and comments
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (setting, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let result = match setting {
+                "bind" => config.keymap.bind(rest),
+                "stream-limit" => {
+                    parse_offset(rest).map(|limit| config.stream_limit = limit as u64)
+                }
+                "palette" => parse_palette_entry(rest).map(|entry| config.palette.push(entry)),
+                "gutter" => match rest.trim() {
+                    "on" | "off" => {
+                        config.gutter = rest.trim() == "on";
+                        Ok(())
+                    }
+                    _ => Err("expected 'gutter on' or 'gutter off'".to_string()),
+                },
+                "journal" => match rest.trim() {
+                    "on" | "off" => {
+                        config.journal = rest.trim() == "on";
+                        Ok(())
+                    }
+                    _ => Err("expected 'journal on' or 'journal off'".to_string()),
+                },
+                "autosave" => match rest.trim() {
+                    "off" => {
+                        config.autosave = None;
+                        Ok(())
+                    }
+                    seconds => match seconds.parse::<u64>() {
+                        Ok(seconds) if seconds > 0 => {
+                            config.autosave = Some(Duration::from_secs(seconds));
+                            Ok(())
+                        }
+                        _ => Err("expected 'autosave <seconds>' or 'autosave off'".to_string()),
+                    },
+                },
+                _ => Err(format!("unknown setting '{}'", setting)),
+            };
+
+            if let Err(e) = result {
+                errors.push(format!("config line {} '{}': {}", number + 1, line, e));
+            }
+        }
+
+        (config, errors)
+    }
+}
+
+/// Reads the rest of a "palette <name> = <color>" line
+fn parse_palette_entry(entry: &str) -> Result<(String, &'static str), String> {
+    let (name, color) = entry
+        .split_once('=')
+        .ok_or("expected 'palette <name> = <color>'")?;
+    let (name, color) = (name.trim(), parse_color(color)?);
+
+    // Only to check the name
+    Theme::default().set(name, color)?;
+    Ok((name.to_string(), color))
+}
@@ -0,0 +1,149 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Numbers shown in every base and as the bytes that encode them, for `conv`, and everything
+//! about an offset for `pos`. Values go from -2^63 up to u64::MAX, negative ones are encoded in
+//! two's complement
+
+/// Smallest value that fits, i64::MIN
+const MIN: i128 = i64::MIN as i128;
+
+/// Largest value that fits, u64::MAX
+const MAX: i128 = u64::MAX as i128;
+
+/// Byte widths encodings are shown at
+pub const WIDTHS: [usize; 4] = [1, 2, 4, 8];
+
+/// Parses a number with an optional minus sign. 0x, 0o and 0b pick the base, bare digits are
+/// decimal, and underscores can split up long numbers
+pub fn parse_value(text: &str) -> Result<i128, String> {
+    let trimmed = text.trim();
+    let (negative, unsigned) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed),
+    };
+    let (digits, radix) = match unsigned.get(..2).map(|prefix| prefix.to_ascii_lowercase()) {
+        Some(prefix) if prefix == "0x" => (&unsigned[2..], 16),
+        Some(prefix) if prefix == "0o" => (&unsigned[2..], 8),
+        Some(prefix) if prefix == "0b" => (&unsigned[2..], 2),
+        _ => (unsigned, 10),
+    };
+
+    let mut value: i128 = 0;
+    let mut any = false;
+    for c in digits.chars() {
+        if c == '_' {
+            continue;
+        }
+        let digit = c
+            .to_digit(radix)
+            .ok_or_else(|| format!("'{c}' is not a base {radix} digit in '{trimmed}'"))?;
+        value = value * i128::from(radix) + i128::from(digit);
+        any = true;
+
+        if value > MAX + 1 {
+            return Err(format!("'{trimmed}' doesn't fit in 64 bits"));
+        }
+    }
+
+    if !any {
+        return Err(format!("missing number in '{trimmed}'"));
+    }
+
+    let value = if negative { -value } else { value };
+    match (MIN..=MAX).contains(&value) {
+        true => Ok(value),
+        false => Err(format!("'{trimmed}' doesn't fit in 64 bits")),
+    }
+}
+
+/// Bits needed to hold the value: unsigned for positive ones, two's complement with the sign
+/// bit for negative ones. At least 1
+pub fn bit_width(value: i128) -> u32 {
+    match value {
+        0.. => (128 - value.leading_zeros()).max(1),
+        _ => 128 - (!value).leading_zeros() + 1,
+    }
+}
+
+/// The value as `width` little endian bytes, None if it doesn't fit. Negative values are in
+/// two's complement
+pub fn encode(value: i128, width: usize) -> Option<Vec<u8>> {
+    if bit_width(value) as usize > width * 8 {
+        return None;
+    }
+
+    Some(value.to_le_bytes()[..width].to_vec())
+}
+
+/// A count and what is counted, like "1 byte" or "2 bytes"
+fn plural(count: u32, what: &str) -> String {
+    match count {
+        1 => format!("1 {what}"),
+        _ => format!("{count} {what}s"),
+    }
+}
+
+/// The lines `conv` shows: the value in every base, how wide it is and its encodings
+pub fn describe(value: i128) -> Vec<String> {
+    let sign = if value < 0 { "-" } else { "" };
+    let magnitude = value.unsigned_abs();
+    let bits = bit_width(value);
+
+    let mut lines = vec![
+        format!("hex  {sign}0x{magnitude:X}"),
+        format!("dec  {value}"),
+        format!("oct  {sign}0o{magnitude:o}"),
+        format!("bin  {sign}0b{magnitude:b}"),
+        format!(
+            "{}{}, {}",
+            plural(bits, "bit"),
+            if value < 0 { " with the sign" } else { "" },
+            plural(bits.div_ceil(8), "byte")
+        ),
+        format!("{:<7}{:<25}{}", "bytes", "little endian", "big endian"),
+    ];
+
+    for width in WIDTHS {
+        let Some(mut bytes) = encode(value, width) else {
+            continue;
+        };
+        let little = crate::bytes_to_hex(&bytes);
+        bytes.reverse();
+        lines.push(format!(
+            "{:<7}{:<25}{}",
+            width,
+            little,
+            crate::bytes_to_hex(&bytes)
+        ));
+    }
+
+    lines
+}
+
+/// The lines `pos` shows for an offset of the bytes: the offset in every base, the line and
+/// column of a dump `cols` bytes wide it is on, the byte there and how far it is through the
+/// bytes and from their end. None if the offset is past the last byte
+pub fn describe_position(offset: usize, bytes: &[u8], cols: usize) -> Option<Vec<String>> {
+    let byte = *bytes.get(offset)?;
+    let cols = cols.max(1);
+    let (line, column) = (offset / cols, offset % cols);
+    let left = bytes.len() - offset;
+
+    let char = match byte {
+        b' '..=b'~' => format!("'{}'", byte as char),
+        _ => "not printable".to_string(),
+    };
+
+    Some(vec![
+        format!("offset  0x{offset:X}  0d{offset}  0o{offset:o}"),
+        format!("dump    line 0x{line:X} (0d{line}), column 0x{column:X} (0d{column})"),
+        format!("byte    0x{byte:02X}  0d{byte}  0b{byte:08b}  {char}"),
+        format!(
+            "{:.2}% through, 0x{left:X} (0d{left}) bytes from here to the end",
+            offset as f64 * 100.0 / bytes.len() as f64
+        ),
+    ])
+}
@@ -0,0 +1,111 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Cyclic patterns for `cyclic`, the De Bruijn sequence pwntools and Metasploit users know as
+//! `cyclic`: `aaaabaaacaaadaaae...`. Every run of `order` letters shows up in it at most once,
+//! so the bytes that ended up in a register after an overflow tell how far into the pattern
+//! they were. The sequence is the same one `pwn cyclic` prints, lowercase letters in
+//! lexicographic order, so offsets agree between the two
+
+/// The letters the pattern is made of
+pub const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+/// How many letters long the runs are that appear only once, 4 like pwntools for 32-bit values
+pub const DEFAULT_ORDER: usize = 4;
+
+/// Bytes of the pattern `find` looks through before giving up. The whole pattern is that long
+/// for order 4, longer orders have one too long to go through
+pub const FIND_LIMIT: usize = 1 << 26;
+
+/// The pattern a byte at a time, ending after ALPHABET.len() to the power of `order` bytes.
+///
+/// It is made of the Lyndon words over the alphabet whose length divides the order, in
+/// lexicographic order, each generated from the one before as in Duval's algorithm
+#[derive(Clone, Debug)]
+pub struct DeBruijn {
+    order: usize,
+    /// The Lyndon word being worked on, as indices into ALPHABET
+    word: Vec<usize>,
+    /// How many letters of the current word were given out, once it is one that goes in
+    emitted: usize,
+}
+
+impl DeBruijn {
+    /// The pattern for runs of `order` letters, at least 1
+    pub fn new(order: usize) -> DeBruijn {
+        // "a" goes in whatever the order, one letter divides anything
+        DeBruijn {
+            order: order.max(1),
+            word: vec![0],
+            emitted: 0,
+        }
+    }
+
+    /// Moves on to the next Lyndon word that goes in, false once there are no more
+    fn next_word(&mut self) -> bool {
+        let last = ALPHABET.len() - 1;
+        loop {
+            // Repeat the word up to the order, then drop the letters that can't go any higher
+            let len = self.word.len();
+            while self.word.len() < self.order {
+                self.word.push(self.word[self.word.len() - len]);
+            }
+            while self.word.last() == Some(&last) {
+                self.word.pop();
+            }
+
+            let Some(end) = self.word.last_mut() else {
+                return false;
+            };
+            *end += 1;
+
+            if self.order.is_multiple_of(self.word.len()) {
+                self.emitted = 0;
+                return true;
+            }
+        }
+    }
+}
+
+impl Iterator for DeBruijn {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.emitted == self.word.len() && !self.next_word() {
+            return None;
+        }
+
+        self.emitted += 1;
+        Some(ALPHABET[self.word[self.emitted - 1]])
+    }
+}
+
+/// The first `length` bytes of the pattern, shorter if the pattern itself is
+pub fn cyclic(length: usize, order: usize) -> Vec<u8> {
+    DeBruijn::new(order).take(length).collect()
+}
+
+/// Where the bytes first appear in the pattern, looking through at most `limit` bytes of it.
+/// None if they don't, bytes that aren't lowercase letters never do
+pub fn cyclic_find(needle: &[u8], order: usize, limit: usize) -> Option<usize> {
+    if needle.is_empty() || !needle.iter().all(|byte| ALPHABET.contains(byte)) {
+        return None;
+    }
+
+    // The last bytes of the pattern, compared with the needle as each one comes in
+    let mut window = Vec::with_capacity(needle.len());
+    for (index, byte) in DeBruijn::new(order).take(limit).enumerate() {
+        if window.len() == needle.len() {
+            window.remove(0);
+        }
+        window.push(byte);
+
+        if window == needle {
+            return Some(index + 1 - needle.len());
+        }
+    }
+
+    None
+}
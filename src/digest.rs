@@ -0,0 +1,220 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Summaries of a whole buffer: the SHA-256 hash and the Shannon entropy. Both are fed a chunk
+//! at a time, so they can run over a big buffer in the background and stop in between
+
+use crate::hex::push_hex_lower;
+
+/// SHA-256 round constants, the first 32 bits of the fractional parts of the cube roots
+/// of the first 64 primes
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// The hash before anything is fed, the first 32 bits of the fractional parts of the square roots
+/// of the first 8 primes
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// A SHA-256 hash being worked out
+#[derive(Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    /// The start of a block that hasn't been filled yet
+    block: [u8; 64],
+    filled: usize,
+    /// Bytes fed so far
+    length: u64,
+}
+
+impl Default for Sha256 {
+    fn default() -> Sha256 {
+        Sha256::new()
+    }
+}
+
+impl Sha256 {
+    /// The hash of nothing so far
+    pub fn new() -> Sha256 {
+        Sha256 {
+            state: INITIAL_STATE,
+            block: [0; 64],
+            filled: 0,
+            length: 0,
+        }
+    }
+
+    /// Hashes the bytes that follow the ones fed before
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        self.length += bytes.len() as u64;
+
+        // Top up a block left over from last time first
+        if self.filled > 0 {
+            let taken = bytes.len().min(64 - self.filled);
+            self.block[self.filled..self.filled + taken].copy_from_slice(&bytes[..taken]);
+            self.filled += taken;
+            bytes = &bytes[taken..];
+
+            if self.filled < 64 {
+                return;
+            }
+            let block = self.block;
+            self.compress(&block);
+            self.filled = 0;
+        }
+
+        let mut blocks = bytes.chunks_exact(64);
+        for block in &mut blocks {
+            // chunks_exact only hands out blocks of 64
+            self.compress(block.try_into().expect("a 64 byte block"));
+        }
+
+        let rest = blocks.remainder();
+        self.block[..rest.len()].copy_from_slice(rest);
+        self.filled = rest.len();
+    }
+
+    /// Pads the last block and returns the hash
+    pub fn finish(mut self) -> [u8; 32] {
+        let bits = self.length.wrapping_mul(8);
+
+        // A 1 bit, zeros up to 8 bytes short of a block, then the length in bits
+        let mut padding = vec![0x80];
+        let used = (self.filled + 1) % 64;
+        padding.resize(1 + (120 - used) % 64, 0);
+        padding.extend_from_slice(&bits.to_be_bytes());
+
+        let length = self.length;
+        self.update(&padding);
+        self.length = length;
+
+        let mut hash = [0; 32];
+        for (word, out) in self.state.iter().zip(hash.chunks_exact_mut(4)) {
+            out.copy_from_slice(&word.to_be_bytes());
+        }
+        hash
+    }
+
+    /// Pads the last block and returns the hash as lowercase hex, the way sha256sum writes it
+    pub fn finish_hex(self) -> String {
+        let mut hex = String::with_capacity(64);
+        for byte in self.finish() {
+            push_hex_lower(&mut hex, byte);
+        }
+        hex
+    }
+
+    /// Mixes one block into the state
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut w = [0u32; 64];
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let temp1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(majority);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+/// Counts how often each byte value comes up, for the Shannon entropy and `histogram`
+#[derive(Clone)]
+pub struct Entropy {
+    counts: [u64; 256],
+    total: u64,
+}
+
+impl Default for Entropy {
+    fn default() -> Entropy {
+        Entropy::new()
+    }
+}
+
+impl Entropy {
+    /// Nothing counted so far
+    pub fn new() -> Entropy {
+        Entropy {
+            counts: [0; 256],
+            total: 0,
+        }
+    }
+
+    /// Counts the bytes that follow the ones fed before
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.counts[usize::from(byte)] += 1;
+        }
+        self.total += bytes.len() as u64;
+    }
+
+    /// How often each byte value came up, by value
+    pub fn counts(&self) -> &[u64; 256] {
+        &self.counts
+    }
+
+    /// How many bytes were counted
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Bits per byte, from 0 for a single repeated value up to 8 for every value equally often.
+    /// Nothing counted is 0
+    pub fn bits_per_byte(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+
+        let total = self.total as f64;
+        self.counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / total;
+                -p * p.log2()
+            })
+            .sum()
+    }
+}
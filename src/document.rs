@@ -0,0 +1,651 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The bytes being edited, with undo and change notifications
+
+use crate::journal::Record;
+use std::{
+    fmt, mem,
+    ops::Range,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+};
+
+/// Why an edit was refused. The document is left untouched when one is returned
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DocumentError {
+    /// The range, or the offset as an empty range, doesn't fit inside the document
+    OutOfBounds {
+        /// Start of the range that was asked for
+        start: usize,
+        /// End of the range that was asked for, exclusive
+        end: usize,
+        /// Length of the document at the time
+        len: usize,
+    },
+    /// Undo was asked for with no changes left to undo
+    NothingToUndo,
+    /// A range was to be moved to an offset inside itself
+    MoveIntoItself {
+        /// Start of the range
+        start: usize,
+        /// End of the range, exclusive
+        end: usize,
+        /// Where it was to go
+        destination: usize,
+    },
+    /// Two ranges that have to be apart share bytes
+    Overlapping {
+        /// Start of the first range
+        first: usize,
+        /// Start of the second range
+        second: usize,
+        /// Length of both
+        len: usize,
+    },
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DocumentError::OutOfBounds { start, end, len } if start == end => write!(
+                f,
+                "offset {:X} is past the end of the buffer ({:X} bytes)",
+                start, len
+            ),
+            DocumentError::OutOfBounds { start, end, len } => write!(
+                f,
+                "range {:X}-{:X} is past the end of the buffer ({:X} bytes)",
+                start, end, len
+            ),
+            DocumentError::NothingToUndo => write!(f, "nothing to undo"),
+            DocumentError::MoveIntoItself {
+                start,
+                end,
+                destination,
+            } => write!(
+                f,
+                "can't move {:X}-{:X} to {:X}, that is inside it",
+                start, end, destination
+            ),
+            DocumentError::Overlapping { first, second, len } => write!(
+                f,
+                "ranges {:X}-{:X} and {:X}-{:X} overlap",
+                first,
+                first + len,
+                second,
+                second + len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+/// What an edit did: at `offset`, `removed` bytes were replaced by `inserted` new ones.
+/// An overwrite removes and inserts the same number, an insert removes none and a delete inserts none
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Where the change starts
+    pub offset: usize,
+    /// How many bytes were there before
+    pub removed: usize,
+    /// How many bytes are there now
+    pub inserted: usize,
+}
+
+impl Change {
+    /// Whether the bytes after the change moved
+    pub fn shifts(&self) -> bool {
+        self.removed != self.inserted
+    }
+}
+
+/// A run of bytes that differs from the last clean state, as `Document::modifications` lists them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Modification {
+    /// Where the run starts now
+    pub offset: usize,
+    /// The bytes that were there when the document was last clean
+    pub original: Vec<u8>,
+    /// The bytes there now, a different number of them if bytes were inserted or deleted
+    pub current: Vec<u8>,
+}
+
+/// Bytes that changed since the last clean state: where they are now, how many there are now
+/// and what was there before
+#[derive(Clone, Debug)]
+struct Modified {
+    start: usize,
+    len: usize,
+    original: Vec<u8>,
+}
+
+/// A change as kept for undo, with the bytes it replaced
+#[derive(Clone, Debug)]
+struct Edit {
+    offset: usize,
+    removed: Vec<u8>,
+    inserted: usize,
+}
+
+/// Edits undone together
+#[derive(Clone, Debug)]
+struct UndoGroup {
+    /// Tells apart groups that end up at the same depth, so the clean state can be found again
+    id: u64,
+    edits: Vec<Edit>,
+}
+
+/// The bytes being edited.
+///
+/// Every edit is checked against the bounds first and either fails without changing anything
+/// or is applied and recorded for undo. Each edit is its own undo step, unless it is made
+/// between begin_undo_group and end_undo_group
+#[derive(Default)]
+pub struct Document {
+    /// Shared with the snapshots taken since the last edit, an edit copies them first if there are any
+    bytes: Arc<Vec<u8>>,
+    undo_stack: Vec<UndoGroup>,
+    /// Set between begin_undo_group and end_undo_group
+    grouping: bool,
+    /// Whether the current undo group has been pushed, it only is once something is edited
+    group_started: bool,
+    next_group_id: u64,
+    /// Id of the group on top of the undo stack when the document was last clean, None for an empty stack
+    clean_id: Option<u64>,
+    /// What changed since the last clean state, in order and never overlapping. Kept up to date
+    /// by undo as well, so it stays right even after undoing past a save
+    modified: Vec<Modified>,
+    /// Counts every change, undos included
+    version: u64,
+    /// Edits and undos take_journal hasn't handed out yet, None unless they are kept
+    journal: Option<Vec<Record>>,
+    subscribers: Vec<Sender<Change>>,
+}
+
+impl Document {
+    /// A clean document holding these bytes
+    pub fn new(bytes: Vec<u8>) -> Document {
+        Document {
+            bytes: Arc::new(bytes),
+            ..Document::default()
+        }
+    }
+
+    /// A document holding bytes that were never saved anywhere, dirty until marked clean.
+    /// Undo can't make it clean either, there is nothing on disk it could match
+    pub fn unsaved(bytes: Vec<u8>) -> Document {
+        Document {
+            clean_id: Some(u64::MAX),
+            ..Document::new(bytes)
+        }
+    }
+
+    /// Number of bytes in the document
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Whether the document has no bytes at all
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// All of the bytes
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The bytes as they are now, for reading on another thread. Taking one doesn't copy anything,
+    /// the next edit copies the bytes instead while the snapshot is still around
+    pub fn snapshot(&self) -> Arc<Vec<u8>> {
+        Arc::clone(&self.bytes)
+    }
+
+    /// The bytes in a range, which has to be inside the document
+    pub fn read(&self, range: Range<usize>) -> Result<&[u8], DocumentError> {
+        self.check(range.clone())?;
+
+        Ok(&self.bytes[range])
+    }
+
+    /// The byte at an offset, None past the end
+    pub fn get(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(offset).copied()
+    }
+
+    /// How many bytes at the end are all `byte`, the whole length if every byte is
+    pub fn trailing_run(&self, byte: u8) -> usize {
+        self.bytes.iter().rev().take_while(|&&b| b == byte).count()
+    }
+
+    /// Goes up with every change, undos included, so whatever was worked out from the bytes
+    /// can tell whether it is still up to date
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Keeps a record of every edit and undo from now on, for take_journal to hand out
+    pub fn start_journal(&mut self) {
+        self.journal = Some(Vec::new());
+    }
+
+    /// The edits and undos since the last call, in the order they were made
+    pub fn take_journal(&mut self) -> Vec<Record> {
+        self.journal.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    /// Whether the document differs from when it was loaded or last marked clean
+    pub fn is_dirty(&self) -> bool {
+        self.undo_stack.last().map(|group| group.id) != self.clean_id
+    }
+
+    /// Marks the current contents as saved, undoing back to here makes the document clean again
+    pub fn mark_clean(&mut self) {
+        self.end_undo_group();
+        self.clean_id = self.undo_stack.last().map(|group| group.id);
+        self.modified.clear();
+        // What was saved is where a new journal starts
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+    }
+
+    /// Every run of bytes that differs from the last clean state, in order. Bytes that were
+    /// changed back to what they were don't count
+    pub fn modifications(&self) -> Vec<Modification> {
+        let mut modifications = Vec::new();
+
+        for modified in &self.modified {
+            let current = &self.bytes[modified.start..modified.start + modified.len];
+            let original = &modified.original[..];
+
+            // Only what actually differs, typing the same byte over itself isn't a change
+            let prefix = current
+                .iter()
+                .zip(original)
+                .take_while(|(a, b)| a == b)
+                .count();
+            let suffix = current[prefix..]
+                .iter()
+                .rev()
+                .zip(original[prefix..].iter().rev())
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if prefix + suffix == current.len() && current.len() == original.len() {
+                continue;
+            }
+
+            modifications.push(Modification {
+                offset: modified.start + prefix,
+                original: original[prefix..original.len() - suffix].to_vec(),
+                current: current[prefix..current.len() - suffix].to_vec(),
+            });
+        }
+
+        modifications
+    }
+
+    /// Whether the byte at an offset differs from the last clean state. In a run whose length
+    /// changed every byte counts, there is nothing to line it up with
+    pub fn is_modified(&self, offset: usize) -> bool {
+        let index = self
+            .modified
+            .partition_point(|run| run.start + run.len <= offset);
+
+        match self.modified.get(index) {
+            Some(run) if run.start <= offset => {
+                run.len != run.original.len()
+                    || self.bytes[offset] != run.original[offset - run.start]
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns a receiver that gets every change made from now on, including undos.
+    /// Dropping the receiver unsubscribes
+    pub fn subscribe(&mut self) -> Receiver<Change> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    /// Replaces bytes in place, the whole range has to be inside the document
+    pub fn overwrite(&mut self, offset: usize, bytes: &[u8]) -> Result<(), DocumentError> {
+        let end = offset
+            .checked_add(bytes.len())
+            .ok_or(DocumentError::OutOfBounds {
+                start: offset,
+                end: usize::MAX,
+                len: self.len(),
+            })?;
+        self.check(offset..end)?;
+
+        self.splice(offset, bytes.len(), bytes);
+        Ok(())
+    }
+
+    /// Replaces bytes like overwrite, but the ones that run past the end make the document longer.
+    /// The offset itself can be at most the length of the document
+    pub fn overwrite_extending(
+        &mut self,
+        offset: usize,
+        bytes: &[u8],
+    ) -> Result<(), DocumentError> {
+        self.check(offset..offset)?;
+
+        let replaced = bytes.len().min(self.len() - offset);
+        self.splice(offset, replaced, bytes);
+        Ok(())
+    }
+
+    /// Inserts bytes in front of an offset, the length of the document inserts at the end
+    pub fn insert(&mut self, offset: usize, bytes: &[u8]) -> Result<(), DocumentError> {
+        self.check(offset..offset)?;
+
+        self.splice(offset, 0, bytes);
+        Ok(())
+    }
+
+    /// Removes a range of bytes and returns them
+    pub fn delete(&mut self, range: Range<usize>) -> Result<Vec<u8>, DocumentError> {
+        self.check(range.clone())?;
+
+        let removed = self.bytes[range.clone()].to_vec();
+        self.splice(range.start, range.len(), &[]);
+        Ok(removed)
+    }
+
+    /// Replaces a range with bytes of any length, one undo step for both. The range has to be
+    /// inside the document, returns the bytes it had
+    pub fn replace(&mut self, range: Range<usize>, bytes: &[u8]) -> Result<Vec<u8>, DocumentError> {
+        self.check(range.clone())?;
+
+        let removed = self.bytes[range.clone()].to_vec();
+        self.splice(range.start, range.len(), bytes);
+        Ok(removed)
+    }
+
+    /// Exchanges two ranges of the same length, as one undo step unless a group is open. Both
+    /// have to be inside the document and apart, they can touch
+    pub fn swap(&mut self, first: usize, second: usize, len: usize) -> Result<(), DocumentError> {
+        for start in [first, second] {
+            let end = start.checked_add(len).ok_or(DocumentError::OutOfBounds {
+                start,
+                end: usize::MAX,
+                len: self.len(),
+            })?;
+            self.check(start..end)?;
+        }
+
+        if first.max(second) < first.min(second) + len {
+            return Err(DocumentError::Overlapping { first, second, len });
+        }
+
+        let a = self.bytes[first..first + len].to_vec();
+        let b = self.bytes[second..second + len].to_vec();
+
+        // Part of the group open already, if there is one
+        let grouping = self.grouping;
+        self.begin_undo_group();
+        self.splice(first, len, &b);
+        self.splice(second, len, &a);
+        if !grouping {
+            self.end_undo_group();
+        }
+
+        Ok(())
+    }
+
+    /// Reverses the order of the bytes in a range, in place. Fewer than two bytes are left as
+    /// they are, without an undo step
+    pub fn reverse(&mut self, range: Range<usize>) -> Result<(), DocumentError> {
+        self.check(range.clone())?;
+
+        if range.len() < 2 {
+            return Ok(());
+        }
+
+        let mut bytes = self.bytes[range.clone()].to_vec();
+        bytes.reverse();
+        self.splice(range.start, range.len(), &bytes);
+        Ok(())
+    }
+
+    /// Moves `len` bytes from `source` to in front of the byte at `destination`, both counted
+    /// before the move, as one undo step unless a group is open. Returns where the bytes start
+    /// now. A destination at either end of the range leaves everything where it is, one inside
+    /// it is an error
+    pub fn move_range(
+        &mut self,
+        source: usize,
+        len: usize,
+        destination: usize,
+    ) -> Result<usize, DocumentError> {
+        let end = source.checked_add(len).ok_or(DocumentError::OutOfBounds {
+            start: source,
+            end: usize::MAX,
+            len: self.len(),
+        })?;
+        self.check(source..end)?;
+        self.check(destination..destination)?;
+
+        if destination > source && destination < end {
+            return Err(DocumentError::MoveIntoItself {
+                start: source,
+                end,
+                destination,
+            });
+        }
+
+        let moved_to = moved_offset(source, source, len, destination);
+        if moved_to == source {
+            return Ok(source);
+        }
+
+        let grouping = self.grouping;
+        self.begin_undo_group();
+        let bytes = self.bytes[source..end].to_vec();
+        self.splice(source, len, &[]);
+        self.splice(moved_to, 0, &bytes);
+        if !grouping {
+            self.end_undo_group();
+        }
+
+        Ok(moved_to)
+    }
+
+    /// Makes the following edits one undo step, until end_undo_group.
+    /// Does nothing if a group is already open
+    pub fn begin_undo_group(&mut self) {
+        if !self.grouping {
+            self.grouping = true;
+            self.group_started = false;
+        }
+    }
+
+    /// Ends the undo step started by begin_undo_group, the next edit starts a new one
+    pub fn end_undo_group(&mut self) {
+        self.grouping = false;
+    }
+
+    /// Whether there is anything to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Reverts the last undo step, returning the offset of its first edit
+    pub fn undo(&mut self) -> Result<usize, DocumentError> {
+        self.end_undo_group();
+
+        let group = self.undo_stack.pop().ok_or(DocumentError::NothingToUndo)?;
+        if let Some(journal) = &mut self.journal {
+            journal.push(Record::Undo);
+        }
+
+        for edit in group.edits.iter().rev() {
+            let undone = Arc::make_mut(&mut self.bytes)
+                .splice(
+                    edit.offset..edit.offset + edit.inserted,
+                    edit.removed.iter().copied(),
+                )
+                .collect::<Vec<u8>>();
+            self.track(edit.offset, &undone, edit.removed.len());
+            self.notify(Change {
+                offset: edit.offset,
+                removed: edit.inserted,
+                inserted: edit.removed.len(),
+            });
+        }
+
+        Ok(group.edits.first().map_or(0, |edit| edit.offset))
+    }
+
+    /// Fails unless the range lies inside the document
+    fn check(&self, range: Range<usize>) -> Result<(), DocumentError> {
+        if range.start > range.end || range.end > self.len() {
+            return Err(DocumentError::OutOfBounds {
+                start: range.start,
+                end: range.end,
+                len: self.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn push_group(&mut self) {
+        self.next_group_id += 1;
+        self.undo_stack.push(UndoGroup {
+            id: self.next_group_id,
+            edits: Vec::new(),
+        });
+    }
+
+    /// Replaces `removed` bytes at an offset with new ones, records it for undo and tells the subscribers.
+    /// The bounds have been checked already
+    fn splice(&mut self, offset: usize, removed: usize, inserted: &[u8]) {
+        // Edits that don't do anything don't get an undo step or a notification
+        if removed == 0 && inserted.is_empty() {
+            return;
+        }
+
+        let old = Arc::make_mut(&mut self.bytes)
+            .splice(offset..offset + removed, inserted.iter().copied())
+            .collect::<Vec<u8>>();
+        self.track(offset, &old, inserted.len());
+
+        let new_step = !self.grouping || !self.group_started;
+        if new_step {
+            self.push_group();
+            self.group_started = true;
+        }
+
+        if let Some(journal) = &mut self.journal {
+            journal.push(Record::Edit {
+                new_step,
+                offset,
+                removed: old.clone(),
+                inserted: inserted.to_vec(),
+            });
+        }
+
+        if let Some(group) = self.undo_stack.last_mut() {
+            group.edits.push(Edit {
+                offset,
+                removed: old,
+                inserted: inserted.len(),
+            });
+        }
+
+        self.notify(Change {
+            offset,
+            removed,
+            inserted: inserted.len(),
+        });
+    }
+
+    /// Records that `removed` bytes at an offset were replaced by `inserted` new ones. Runs the
+    /// edit touches are merged with it into one, whose original bytes are pieced together from
+    /// theirs and the removed bytes in between
+    fn track(&mut self, offset: usize, removed: &[u8], inserted: usize) {
+        let end = offset + removed.len();
+        let first = self
+            .modified
+            .partition_point(|run| run.start + run.len < offset);
+        let last = first
+            + self.modified[first..]
+                .iter()
+                .take_while(|run| run.start <= end)
+                .count();
+
+        let start = self
+            .modified
+            .get(first)
+            .filter(|_| first < last)
+            .map_or(offset, |run| run.start.min(offset));
+        let mut original = Vec::new();
+        let mut position = start;
+        for run in &self.modified[first..last] {
+            // Bytes between the runs are the removed ones, unchanged until now
+            if run.start > position {
+                original.extend_from_slice(&removed[position - offset..run.start - offset]);
+            }
+            original.extend_from_slice(&run.original);
+            position = run.start + run.len;
+        }
+        if end > position {
+            original.extend_from_slice(&removed[position.max(offset) - offset..]);
+        }
+
+        let merged_end = position.max(end);
+        let merged = Modified {
+            start,
+            len: merged_end - start - removed.len() + inserted,
+            original,
+        };
+
+        self.modified.splice(first..last, [merged]);
+        for run in &mut self.modified[first + 1..] {
+            run.start = run.start + inserted - removed.len();
+        }
+    }
+
+    fn notify(&mut self, change: Change) {
+        self.version += 1;
+        // Subscribers that dropped their receiver are forgotten
+        self.subscribers.retain(|tx| tx.send(change).is_ok());
+    }
+}
+
+/// Where the byte at `offset` ends up after `move_range(source, len, destination)`. The moved
+/// bytes keep their order, the ones they passed over close the gap or make room
+pub fn moved_offset(offset: usize, source: usize, len: usize, destination: usize) -> usize {
+    let end = source + len;
+
+    match offset {
+        _ if (destination >= source && destination <= end) => offset,
+        // Moved forward, the bytes in between shift back
+        _ if destination > end => match offset {
+            offset if (source..end).contains(&offset) => offset - source + destination - len,
+            offset if (end..destination).contains(&offset) => offset - len,
+            offset => offset,
+        },
+        // Moved back, the bytes in between shift forward
+        offset if (source..end).contains(&offset) => offset - source + destination,
+        offset if (destination..source).contains(&offset) => offset + len,
+        offset => offset,
+    }
+}
+
+impl From<Vec<u8>> for Document {
+    fn from(bytes: Vec<u8>) -> Document {
+        Document::new(bytes)
+    }
+}
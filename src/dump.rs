@@ -0,0 +1,876 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The layout of the dump: "OFFSET  HEX  TEXT", a line per `cols` bytes
+
+use crate::{
+    document::Change,
+    hex::{push_hex, push_hex_lower},
+    strings::Encoding,
+    template::Endian,
+    theme::{css, ByteClass, Marks, Theme, RESET},
+};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{self, Write},
+    ops::Range,
+};
+use tui_tools::Colors;
+
+/// Width of the offset column plus the two spaces after it, in terminal columns
+pub const GUTTER_WIDTH: usize = 10;
+
+/// How the dump is laid out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DumpOptions {
+    /// Bytes per line
+    pub cols: usize,
+    /// Bytes per group, groups get an extra space between them. 0 doesn't group at all
+    pub group: usize,
+    /// Whether the dump may use ANSI colors at all. Without a theme only the newline dot is colored
+    pub colors: bool,
+    /// Colors for the whole dump, set by --colors. Only used when `colors` is on
+    pub theme: Option<Theme>,
+    /// Whether runs of identical lines after the first are shown as a single `*` row, like xxd
+    pub squeeze: bool,
+    /// Whether lines start with their offset. Without it the hex starts right at the left edge
+    pub gutter: bool,
+    /// How the text column reads the bytes, set by `encoding`
+    pub text: Encoding,
+    /// Whether UTF-16 code units start at odd offsets instead of even ones
+    pub odd_units: bool,
+}
+
+impl Default for DumpOptions {
+    fn default() -> DumpOptions {
+        DumpOptions {
+            cols: 16,
+            group: 0,
+            colors: true,
+            theme: None,
+            squeeze: false,
+            gutter: true,
+            text: Encoding::Ascii,
+            odd_units: false,
+        }
+    }
+}
+
+impl DumpOptions {
+    /// The theme to color the dump with, None when colors are off or there is no theme
+    pub fn theme(&self) -> Option<&Theme> {
+        self.theme.as_ref().filter(|_| self.colors)
+    }
+
+    /// Width of the offset column plus the spaces after it, 0 with the gutter off
+    pub fn gutter_width(&self) -> usize {
+        match self.gutter {
+            true => GUTTER_WIDTH,
+            false => 0,
+        }
+    }
+
+    /// Extra spaces between the groups of a full line
+    fn gaps(&self) -> usize {
+        match self.group {
+            0 => 0,
+            group => (self.cols.max(1) - 1) / group,
+        }
+    }
+
+    /// Width of the hex column, a full line of pairs with a space after each
+    pub fn hex_width(&self) -> usize {
+        self.cols * 3 + self.gaps()
+    }
+
+    /// Screen column (0-based) the text column starts at
+    pub fn text_start(&self) -> usize {
+        self.gutter_width() + self.hex_width() + 2
+    }
+
+    /// Screen column (0-based) of the hex pair of a byte within its line
+    pub fn hex_column(&self, byte: usize) -> usize {
+        let gaps = match self.group {
+            0 => 0,
+            group => byte / group,
+        };
+
+        self.gutter_width() + byte * 3 + gaps
+    }
+
+    /// Maps a 0-based screen column to the index of the byte within its dump line,
+    /// clicks on a hex pair (or the spaces after it) and on the text column both count
+    pub fn column_to_byte(&self, column: usize) -> Option<usize> {
+        let hex_start = self.gutter_width();
+        let hex_end = hex_start + self.hex_width();
+        let text_start = self.text_start();
+
+        if (hex_start..hex_end).contains(&column) {
+            (0..self.cols)
+                .rev()
+                .find(|&byte| self.hex_column(byte) <= column)
+        } else if (text_start..text_start + self.cols).contains(&column) {
+            Some(column - text_start)
+        } else {
+            None
+        }
+    }
+}
+
+/// Most lines a `LineCache` keeps, past that it starts over with just what is on screen
+const MAX_CACHED_LINES: usize = 4096;
+
+/// Rendered dump lines kept between redraws, keyed by line index. An edit only throws away
+/// the lines it touched, so redrawing after typing a byte doesn't depend on the size of the file
+#[derive(Default)]
+pub struct LineCache {
+    lines: BTreeMap<usize, String>,
+}
+
+impl LineCache {
+    /// Forgets every line, for when the layout or the whole buffer changes
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Forgets the lines a change touched. A change that moves the bytes after it
+    /// touches every line from its first one to the end
+    pub fn invalidate(&mut self, change: &Change, cols: usize) {
+        let cols = cols.max(1);
+        let first = change.offset / cols;
+
+        if change.shifts() {
+            self.lines.split_off(&first);
+        } else if change.inserted > 0 {
+            let last = (change.offset + change.inserted - 1) / cols;
+            self.lines.retain(|&line, _| line < first || line > last);
+        }
+    }
+
+    /// The line at an index, rendered from `bytes` (the whole buffer) unless it is cached already
+    pub fn line(&mut self, index: usize, bytes: &[u8], options: &DumpOptions) -> &str {
+        if self.lines.len() >= MAX_CACHED_LINES && !self.lines.contains_key(&index) {
+            self.lines.clear();
+        }
+
+        self.lines.entry(index).or_insert_with(|| {
+            let cols = options.cols.max(1);
+            let start = index.saturating_mul(cols).min(bytes.len());
+            let end = start.saturating_add(cols).min(bytes.len());
+
+            render_line_within(0, bytes, start..end, options)
+        })
+    }
+
+    /// How many lines are cached right now
+    pub fn cached(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+/// Renders one line of the dump, `offset` is where `bytes` starts in the whole buffer.
+/// The line is built in one string with its room reserved up front, this runs for every line of an export.
+/// With a theme every part gets its color, the columns line up the same since padding goes by
+/// what is visible rather than by the length of the string
+pub fn render_line(offset: usize, bytes: &[u8], options: &DumpOptions) -> String {
+    render_line_within(offset, bytes, 0..bytes.len(), options)
+}
+
+/// Renders the bytes of `buffer` in `line_range` as a line, `base` being where `buffer` starts
+/// in the whole buffer. The bytes around the line are only looked at for UTF-16 characters that cross into it
+pub fn render_line_within(
+    base: usize,
+    buffer: &[u8],
+    line_range: Range<usize>,
+    options: &DumpOptions,
+) -> String {
+    let offset = base + line_range.start;
+    let bytes = &buffer[line_range.clone()];
+    let theme = options.theme();
+    let mut line = String::with_capacity(options.text_start() + bytes.len() * 2);
+
+    // Writing into a String can't fail
+    match theme {
+        _ if !options.gutter => {}
+        Some(theme) => {
+            let _ = write!(line, "{}{:08X}{}  ", theme.offset, offset, RESET);
+        }
+        None => {
+            let _ = write!(line, "{:08X}  ", offset);
+        }
+    }
+    let mut visible = options.gutter_width();
+
+    for (i, byte) in bytes.iter().enumerate() {
+        if options.group > 0 && i > 0 && i % options.group == 0 {
+            line.push(' ');
+            visible += 1;
+        }
+        match theme {
+            Some(theme) => {
+                line.push_str(theme.byte(*byte));
+                push_hex(&mut line, *byte);
+                line.push_str(RESET);
+            }
+            None => push_hex(&mut line, *byte),
+        }
+        line.push(' ');
+        visible += 3;
+    }
+
+    // A short last line is padded so its text lines up with the lines above
+    for _ in visible..options.text_start() {
+        line.push(' ');
+    }
+
+    // Enable ansi colors based on the flag
+    let newline_replacement = match theme {
+        Some(theme) => format!("{}.{}{}", theme.newline, RESET, theme.text),
+        None if options.colors => ".".bold_black(),
+        None => ".".to_string(),
+    };
+
+    if let Some(theme) = theme {
+        line.push_str(theme.text);
+    }
+
+    match options.text {
+        Encoding::Ascii => {
+            for c in String::from_utf8_lossy(bytes).chars() {
+                match c {
+                    '\n' => line.push_str(&newline_replacement),
+                    c => line.push(c),
+                }
+            }
+        }
+        Encoding::Utf16(endian) => {
+            let continuation = match theme {
+                Some(theme) => format!("\x1b[2m{UNIT_CONTINUATION}{RESET}{}", theme.text),
+                None if options.colors => UNIT_CONTINUATION.to_string().bold_black(),
+                None => UNIT_CONTINUATION.to_string(),
+            };
+            let start = (base, options.odd_units);
+            for cell in utf16_cells(buffer, start, endian, line_range) {
+                match cell {
+                    Some(c) => line.push(c),
+                    None => line.push_str(&continuation),
+                }
+            }
+        }
+    }
+
+    if theme.is_some() {
+        line.push_str(RESET);
+    }
+
+    line
+}
+
+/// Shown in the text column for the bytes of a UTF-16 character after its first one
+pub const UNIT_CONTINUATION: char = '·';
+
+/// The cells of the text column for `line` of `buffer` read as UTF-16, one per byte: the
+/// character in the cell of its first byte and None for the bytes after it. `start` is where
+/// `buffer` starts in the whole buffer and whether code units start at odd offsets. Bytes that
+/// aren't a character, and characters that don't take exactly one column, get a `.`
+pub fn utf16_cells(
+    buffer: &[u8],
+    start: (usize, bool),
+    endian: Endian,
+    line: Range<usize>,
+) -> Vec<Option<char>> {
+    let (base, odd) = start;
+    let unit = |at: usize| {
+        let pair = [*buffer.get(at)?, *buffer.get(at + 1)?];
+        Some(match endian {
+            Endian::Little => u16::from_le_bytes(pair),
+            Endian::Big => u16::from_be_bytes(pair),
+        })
+    };
+    let high = |unit: u16| (0xD800..0xDC00).contains(&unit);
+    let low = |unit: u16| (0xDC00..0xE000).contains(&unit);
+
+    // Back up to the code unit the line starts in, and to the start of its surrogate pair
+    let mut at = line.start;
+    if (base + at) % 2 != usize::from(odd) && at > 0 {
+        at -= 1;
+    }
+    if at >= 2 && unit(at).is_some_and(low) && unit(at - 2).is_some_and(high) {
+        at -= 2;
+    }
+
+    let mut cells = Vec::with_capacity(line.len());
+    let mut push = |from: usize, width: usize, c: char| {
+        for cell in from..from + width {
+            if line.contains(&cell) {
+                cells.push((cell == from).then_some(c));
+            }
+        }
+    };
+
+    while at < line.end {
+        // A byte before the first code unit, or one left over at the end
+        let Some(first) = unit(at).filter(|_| (base + at) % 2 == usize::from(odd)) else {
+            push(at, 1, '.');
+            at += 1;
+            continue;
+        };
+        let (c, width) = match (first, unit(at + 2)) {
+            (first, Some(second)) if high(first) && low(second) => {
+                let decoded = char::decode_utf16([first, second]).next();
+                (decoded.and_then(Result::ok), 4)
+            }
+            (first, _) => (char::from_u32(u32::from(first)), 2),
+        };
+        let c = c.filter(|c| takes_one_column(*c)).unwrap_or('.');
+        push(at, width, c);
+        at += width;
+    }
+
+    cells
+}
+
+/// Whether a character takes up exactly one column of the terminal. Controls take none or
+/// move the cursor, and the wide characters of East Asian scripts and emoji take two, which
+/// would push the rest of the line out of its column. Goes by the common ranges
+fn takes_one_column(c: char) -> bool {
+    const ZERO: [(char, char); 9] = [
+        ('\u{300}', '\u{36F}'),
+        ('\u{1AB0}', '\u{1AFF}'),
+        ('\u{1DC0}', '\u{1DFF}'),
+        ('\u{200B}', '\u{200F}'),
+        ('\u{2028}', '\u{202E}'),
+        ('\u{2060}', '\u{206F}'),
+        ('\u{20D0}', '\u{20FF}'),
+        ('\u{FE00}', '\u{FE0F}'),
+        ('\u{FE20}', '\u{FEFF}'),
+    ];
+    const WIDE: [(char, char); 12] = [
+        ('\u{1100}', '\u{115F}'),
+        ('\u{2E80}', '\u{303E}'),
+        ('\u{3041}', '\u{33FF}'),
+        ('\u{3400}', '\u{4DBF}'),
+        ('\u{4E00}', '\u{9FFF}'),
+        ('\u{A000}', '\u{A4CF}'),
+        ('\u{AC00}', '\u{D7A3}'),
+        ('\u{F900}', '\u{FAFF}'),
+        ('\u{FE30}', '\u{FE4F}'),
+        ('\u{FF00}', '\u{FF60}'),
+        ('\u{FFE0}', '\u{FFE6}'),
+        ('\u{1F000}', '\u{3FFFD}'),
+    ];
+
+    !c.is_control()
+        && !ZERO
+            .iter()
+            .chain(WIDE.iter())
+            .any(|(first, last)| (*first..=*last).contains(&c))
+}
+
+/// A row of a squeezed dump
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Row {
+    /// A line shown as it is, by index
+    Line(usize),
+    /// Lines from `first` on that all repeat the line above them, shown as one `*` row
+    Skipped {
+        /// Index of the first line left out
+        first: usize,
+        /// How many lines are left out
+        count: usize,
+    },
+}
+
+impl Row {
+    /// Index of the first line on the row
+    pub fn line(&self) -> usize {
+        self.lines().start
+    }
+
+    /// Indexes of the lines on the row
+    pub fn lines(&self) -> Range<usize> {
+        match *self {
+            Row::Line(line) => line..line + 1,
+            Row::Skipped { first, count } => first..first + count,
+        }
+    }
+}
+
+/// Whether a squeezed dump leaves a line out: it is the same as the line above it and it isn't
+/// the last line, so where the bytes end is always shown. `keep` is a line that is never left
+/// out, the one the cursor is on
+pub fn repeats(bytes: &[u8], cols: usize, line: usize, keep: Option<usize>) -> bool {
+    let cols = cols.max(1);
+    let start = line.saturating_mul(cols);
+
+    line > 0
+        && keep != Some(line)
+        && start.saturating_add(cols) < bytes.len()
+        && bytes[start..start + cols] == bytes[start - cols..start]
+}
+
+/// The rows of a squeezed dump from line `top` on, at most `count` of them. Each run of lines
+/// left out becomes one `Row::Skipped`, so a top line inside a run starts with the rest of it
+pub fn squeezed_rows(
+    bytes: &[u8],
+    cols: usize,
+    top: usize,
+    count: usize,
+    keep: Option<usize>,
+) -> Vec<Row> {
+    let lines = bytes.len().div_ceil(cols.max(1));
+    let mut rows = Vec::with_capacity(count.min(lines));
+    let mut line = top;
+
+    while line < lines && rows.len() < count {
+        if !repeats(bytes, cols, line, keep) {
+            rows.push(Row::Line(line));
+            line += 1;
+            continue;
+        }
+
+        let first = line;
+        while line < lines && repeats(bytes, cols, line, keep) {
+            line += 1;
+        }
+        rows.push(Row::Skipped {
+            first,
+            count: line - first,
+        });
+    }
+
+    rows
+}
+
+/// The first line of the row above the one line `line` starts, 0 at the top
+pub fn previous_row(bytes: &[u8], cols: usize, line: usize, keep: Option<usize>) -> usize {
+    let mut previous = line.saturating_sub(1);
+
+    while previous > 0
+        && repeats(bytes, cols, previous, keep)
+        && repeats(bytes, cols, previous - 1, keep)
+    {
+        previous -= 1;
+    }
+
+    previous
+}
+
+/// The `*` row standing for lines left out of a squeezed dump
+pub fn render_skipped(count: usize, cols: usize, options: &DumpOptions) -> String {
+    let text = format!(
+        "{:<width$}{} identical line{} ({:X} bytes)",
+        "*",
+        count,
+        if count == 1 { "" } else { "s" },
+        count * cols.max(1),
+        // Without the gutter the star still needs a space after it
+        width = options.gutter_width().max(2)
+    );
+
+    match options.theme() {
+        Some(theme) => Theme::paint(theme.offset, &text),
+        None => text,
+    }
+}
+
+/// Renders bytes as dump lines, the first line starts at offset 0
+pub fn render(bytes: &[u8], options: &DumpOptions) -> Vec<String> {
+    bytes
+        .chunks(options.cols.max(1))
+        .enumerate()
+        .map(|(line, chunk)| {
+            let start = line * options.cols.max(1);
+            render_line_within(0, bytes, start..start + chunk.len(), options)
+        })
+        .collect()
+}
+
+/// Writes the dump of bytes that start at `offset` in the buffer, the same lines `render` makes.
+/// Colors come from rendering with `options.colors`, so a plain dump lines up just like a colored one.
+/// With `options.squeeze` runs of identical lines are written as one `*` row
+pub fn write_dump<W: Write>(
+    out: &mut W,
+    offset: usize,
+    bytes: &[u8],
+    options: &DumpOptions,
+) -> io::Result<()> {
+    let cols = options.cols.max(1);
+
+    if options.squeeze {
+        for row in squeezed_rows(bytes, cols, 0, usize::MAX, None) {
+            match row {
+                Row::Line(line) => {
+                    let range = line * cols..((line + 1) * cols).min(bytes.len());
+                    writeln!(out, "{}", render_line_within(offset, bytes, range, options))?;
+                }
+                Row::Skipped { count, .. } => {
+                    writeln!(out, "{}", render_skipped(count, cols, options))?
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    for (line, chunk) in bytes.chunks(cols).enumerate() {
+        let start = line * cols;
+        let range = start..start + chunk.len();
+        writeln!(out, "{}", render_line_within(offset, bytes, range, options))?;
+    }
+
+    Ok(())
+}
+
+/// Text and page color of the HTML export, a dark terminal
+const HTML_FOREGROUND: &str = "#d0d0d0";
+const HTML_BACKGROUND: &str = "#1e1e1e";
+
+/// Escapes text for HTML, inside elements and quoted attributes alike
+pub fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// The CSS class of a byte's hex pair in the HTML export, what `Theme::layer` would color it with
+fn html_class(byte: u8, marks: Marks) -> &'static str {
+    match (marks, ByteClass::of(byte)) {
+        (Marks { found: true, .. }, _) => "f",
+        (Marks { modified: true, .. }, _) => "m",
+        (_, ByteClass::Null) => "z",
+        (_, ByteClass::Whitespace) => "w",
+        (_, ByteClass::Printable) => "p",
+        (_, ByteClass::Control) => "c",
+        (_, ByteClass::High) => "h",
+    }
+}
+
+/// Renders one line of the HTML export, laid out like `render_line` with spans for the colors.
+/// The line is its own anchor, named after its offset like `0x1F0`. Neighbouring hex pairs of
+/// the same class share a span
+fn render_html_line(
+    offset: usize,
+    bytes: &[u8],
+    options: &DumpOptions,
+    marks: &impl Fn(usize) -> Marks,
+) -> String {
+    let mut line = String::with_capacity(options.text_start() * 4 + bytes.len() * 24);
+
+    // Writing into a String can't fail
+    let _ = write!(line, "<span id=\"0x{:X}\">", offset);
+    if options.gutter {
+        let _ = write!(
+            line,
+            "<a class=\"o\" href=\"#0x{0:X}\">{0:08X}</a>  ",
+            offset
+        );
+    }
+    let mut visible = options.gutter_width();
+
+    // Spaces go in after the pair they follow once the next one is known, so a span never
+    // ends in one
+    let mut open = None;
+    let mut spaces = 0;
+
+    for (i, byte) in bytes.iter().enumerate() {
+        if options.group > 0 && i > 0 && i % options.group == 0 {
+            spaces += 1;
+        }
+
+        let class = html_class(*byte, marks(offset + i));
+        if open != Some(class) {
+            if open.is_some() {
+                line.push_str("</span>");
+            }
+            line.extend(std::iter::repeat_n(' ', spaces));
+            let _ = write!(line, "<span class=\"{}\">", class);
+            open = Some(class);
+        } else {
+            line.extend(std::iter::repeat_n(' ', spaces));
+        }
+
+        push_hex(&mut line, *byte);
+        visible += spaces + 2;
+        spaces = 1;
+    }
+
+    if open.is_some() {
+        line.push_str("</span>");
+    }
+
+    // A short last line is padded so its text lines up with the lines above
+    line.extend(std::iter::repeat_n(
+        ' ',
+        options.text_start().saturating_sub(visible),
+    ));
+
+    line.push_str("<span class=\"t\">");
+    for c in String::from_utf8_lossy(bytes).chars() {
+        match c {
+            '\n' => line.push_str("<span class=\"n\">.</span>"),
+            c if c.is_control() => line.push('.'),
+            c => line.push_str(&html_escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    line.push_str("</span></span>");
+
+    line
+}
+
+/// Writes the dump of bytes that start at `offset` in the buffer as a standalone HTML page,
+/// the same layout as `write_dump` in a `<pre>`. It is always colored, with the theme or the
+/// default one, and `marks` says which bytes show as modified or in a match. Every line is an
+/// anchor named after its offset, so `dump.html#0x200` links to one.
+/// Lines are written one at a time, so big ranges don't end up in one giant string
+pub fn write_html<W: Write>(
+    out: &mut W,
+    name: &str,
+    offset: usize,
+    bytes: &[u8],
+    options: &DumpOptions,
+    marks: impl Fn(usize) -> Marks,
+) -> io::Result<()> {
+    let theme = options.theme.unwrap_or_default();
+    let cols = options.cols.max(1);
+    let classes = [
+        ("o", theme.offset),
+        ("z", theme.null),
+        ("w", theme.whitespace),
+        ("p", theme.printable),
+        ("c", theme.control),
+        ("h", theme.high),
+        ("t", theme.text),
+        ("n", theme.newline),
+        ("m", theme.modified),
+        ("f", theme.found),
+    ];
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", html_escape(name))?;
+    writeln!(out, "<style>")?;
+    writeln!(
+        out,
+        "body {{ background: {}; color: {}; }}",
+        HTML_BACKGROUND, HTML_FOREGROUND
+    )?;
+    writeln!(out, "pre {{ font-family: monospace; }}")?;
+    writeln!(out, "a {{ color: inherit; text-decoration: none; }}")?;
+    writeln!(out, ":target {{ background: #3a3a3a; }}")?;
+    for (class, color) in classes {
+        writeln!(
+            out,
+            ".{} {{ {} }}",
+            class,
+            css(color, HTML_FOREGROUND, HTML_BACKGROUND)
+        )?;
+    }
+    writeln!(out, "</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<pre>")?;
+
+    let line = |line: usize| {
+        let chunk = &bytes[line * cols..((line + 1) * cols).min(bytes.len())];
+        render_html_line(offset + line * cols, chunk, options, &marks)
+    };
+
+    if options.squeeze {
+        let plain = DumpOptions {
+            colors: false,
+            ..*options
+        };
+        for row in squeezed_rows(bytes, cols, 0, usize::MAX, None) {
+            match row {
+                Row::Line(index) => writeln!(out, "{}", line(index))?,
+                Row::Skipped { count, .. } => writeln!(
+                    out,
+                    "<span class=\"o\">{}</span>",
+                    html_escape(&render_skipped(count, cols, &plain))
+                )?,
+            }
+        }
+    } else {
+        for index in 0..bytes.len().div_ceil(cols) {
+            writeln!(out, "{}", line(index))?;
+        }
+    }
+
+    writeln!(out, "</pre>")?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+/// The text column of the JSON export: printable ASCII as is, a dot for everything else
+pub fn printable_text(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            0x20..=0x7E => byte as char,
+            _ => '.',
+        })
+        .collect()
+}
+
+/// Most lines `export md` writes without being forced, Markdown is for small regions
+pub const MARKDOWN_ROWS: usize = 0x100;
+
+/// The hex pairs of a line separated by spaces, with the extra space between groups
+fn hex_pairs(bytes: &[u8], group: usize) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 3);
+
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            hex.push(' ');
+        }
+        if group > 0 && i > 0 && i % group == 0 {
+            hex.push(' ');
+        }
+        push_hex(&mut hex, *byte);
+    }
+
+    hex
+}
+
+/// Text as a Markdown code span that is also safe in a table cell. The span is delimited by
+/// more backticks than the text has in a row, padded with spaces when the text starts or ends
+/// with a backtick or is padded itself, and pipes are escaped so they don't split the cell
+pub fn markdown_code(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let ticks = "`".repeat(longest + 1);
+    let pad = text.starts_with('`')
+        || text.ends_with('`')
+        || (text.starts_with(' ') && text.ends_with(' ') && text.trim() != "");
+    let pad = if pad { " " } else { "" };
+
+    format!("{ticks}{pad}{}{pad}{ticks}", text.replace('|', "\\|"))
+}
+
+/// Writes the dump of bytes that start at `offset` in the buffer as Markdown, a table with an
+/// offset, hex and ASCII column per line. With `fenced` it is a code block with the lines of a
+/// plain dump instead, for renderers where tables look bad. Either way the text column has a
+/// dot for everything but printable ASCII, like the JSON export
+pub fn write_markdown<W: Write>(
+    out: &mut W,
+    offset: usize,
+    bytes: &[u8],
+    options: &DumpOptions,
+    fenced: bool,
+) -> io::Result<()> {
+    let cols = options.cols.max(1);
+
+    if fenced {
+        // Every line starts with its offset, so no backticks in the text can close the fence
+        let width = options.hex_width().saturating_sub(1);
+
+        writeln!(out, "```")?;
+        for (line, chunk) in bytes.chunks(cols).enumerate() {
+            writeln!(
+                out,
+                "{:08X}  {:<width$}  {}",
+                offset + line * cols,
+                hex_pairs(chunk, options.group),
+                printable_text(chunk),
+                width = width
+            )?;
+        }
+        return writeln!(out, "```");
+    }
+
+    writeln!(out, "| Offset | Hex | ASCII |")?;
+    writeln!(out, "|--------|-----|-------|")?;
+
+    for (line, chunk) in bytes.chunks(cols).enumerate() {
+        writeln!(
+            out,
+            "| `{:08X}` | `{}` | {} |",
+            offset + line * cols,
+            hex_pairs(chunk, options.group),
+            markdown_code(&printable_text(chunk))
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Quotes a string for JSON, escaping quotes, backslashes and control characters
+pub fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Writes the dump as JSON, a header with the file name, size and layout, then a line object
+/// per `cols` bytes with its offset, the bytes in hex and the printable text.
+/// Lines are written one at a time, so big files don't end up in one giant string
+pub fn write_json<W: Write>(
+    out: &mut W,
+    name: &str,
+    bytes: &[u8],
+    options: &DumpOptions,
+) -> io::Result<()> {
+    let cols = options.cols.max(1);
+
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"file\": {},", json_string(name))?;
+    writeln!(out, "  \"size\": {},", bytes.len())?;
+    writeln!(out, "  \"cols\": {},", cols)?;
+    writeln!(out, "  \"group\": {},", options.group)?;
+    write!(out, "  \"lines\": [")?;
+
+    for (line, chunk) in bytes.chunks(cols).enumerate() {
+        let mut hex = String::with_capacity(chunk.len() * 2);
+        for byte in chunk {
+            push_hex_lower(&mut hex, *byte);
+        }
+
+        write!(
+            out,
+            "{}\n    {{\"offset\": {}, \"bytes\": \"{}\", \"ascii\": {}}}",
+            if line == 0 { "" } else { "," },
+            line * cols,
+            hex,
+            json_string(&printable_text(chunk))
+        )?;
+    }
+
+    if !bytes.is_empty() {
+        write!(out, "\n  ")?;
+    }
+    writeln!(out, "]")?;
+    writeln!(out, "}}")
+}
@@ -0,0 +1,272 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The edit log for `log`: when each edit of a session was made and the command that made it,
+//! for an audit trail of what was patched. Unlike the undo steps it only ever grows, an edit
+//! that is undone stays in it marked with when it was undone
+
+use crate::{hex::bytes_to_hex, journal::Record};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Most bytes of what an edit replaced and wrote that are kept to show
+pub const LOG_PREVIEW: usize = 16;
+
+/// The first line of `log export`
+pub const CSV_HEADER: &str = "time,command,offset,removed,inserted,old,new,undone\n";
+
+/// One edit, or a run of typing over bytes one after the other in the same undo step
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    /// When it was made
+    pub time: SystemTime,
+    /// The command line that made it, or what was pressed
+    pub command: String,
+    /// Where it starts
+    pub offset: usize,
+    /// How many bytes it replaced
+    pub removed: usize,
+    /// How many bytes it wrote
+    pub inserted: usize,
+    /// The first bytes it replaced
+    pub old: Vec<u8>,
+    /// The first bytes it wrote
+    pub new: Vec<u8>,
+    /// When its undo step was undone, if it was
+    pub undone: Option<SystemTime>,
+    /// The undo step it was part of
+    step: u64,
+}
+
+impl LogEntry {
+    /// Where the edit is, like `1C4` or `200+100` for more than one byte
+    pub fn range(&self) -> String {
+        match self.removed.max(self.inserted) {
+            0 | 1 => format!("{:X}", self.offset),
+            len => format!("{:X}+{:X}", self.offset, len),
+        }
+    }
+
+    /// The bytes before and after, like `00 → 7F`, with `…` where there are more than shown
+    pub fn change(&self) -> String {
+        let side = |bytes: &[u8], len: usize| match len > bytes.len() {
+            true => format!("{} …", bytes_to_hex(bytes)),
+            false => bytes_to_hex(bytes),
+        };
+
+        format!(
+            "{} → {}",
+            side(&self.old, self.removed),
+            side(&self.new, self.inserted)
+        )
+        .trim()
+        .to_string()
+    }
+
+    /// The entry as `log` lists it
+    pub fn line(&self, iso: bool) -> String {
+        let mut line = format!(
+            "{}  {}  {}  {}",
+            format_time(self.time, iso),
+            self.range(),
+            self.change(),
+            self.command
+        );
+        if let Some(undone) = self.undone {
+            line += &format!("  [undone {}]", format_time(undone, iso));
+        }
+        line
+    }
+
+    /// The entry as a line of `log export`, under CSV_HEADER
+    pub fn csv(&self, iso: bool) -> String {
+        format!(
+            "{},{},{:X},{:X},{:X},{},{},{}\n",
+            format_time(self.time, iso),
+            csv_field(&self.command),
+            self.offset,
+            self.removed,
+            self.inserted,
+            bytes_to_hex(&self.old).replace(' ', ""),
+            bytes_to_hex(&self.new).replace(' ', ""),
+            self.undone
+                .map(|undone| format_time(undone, iso))
+                .unwrap_or_default()
+        )
+    }
+
+    /// Takes in an overwrite that carries on where the entry's own overwrite left off, or
+    /// goes over part of it again, like typing a byte at a time. False if it doesn't
+    fn absorb(&mut self, offset: usize, removed: &[u8], inserted: &[u8]) -> bool {
+        let end = self.offset + self.inserted;
+        if removed.len() != inserted.len()
+            || self.removed != self.inserted
+            || !(self.offset..=end).contains(&offset)
+        {
+            return false;
+        }
+
+        let at = offset - self.offset;
+        for (index, (&old, &new)) in removed.iter().zip(inserted).enumerate() {
+            let position = at + index;
+            match position < self.inserted {
+                // Typed over again, what was there first stays the old byte
+                true if position < LOG_PREVIEW => self.new[position] = new,
+                false if position < LOG_PREVIEW => {
+                    self.old.push(old);
+                    self.new.push(new);
+                }
+                _ => {}
+            }
+        }
+
+        self.inserted = self.inserted.max(at + inserted.len());
+        self.removed = self.inserted;
+        true
+    }
+}
+
+/// Every edit of a session in the order they were made
+#[derive(Clone, Debug, Default)]
+pub struct EditLog {
+    entries: Vec<LogEntry>,
+    /// The undo steps made while logging that haven't been undone, latest last
+    steps: Vec<u64>,
+    next_step: u64,
+}
+
+impl EditLog {
+    /// Takes in the records of a document's edits and undos, made by `command` at `time`
+    pub fn record(&mut self, records: &[Record], command: &str, time: SystemTime) {
+        for record in records {
+            match record {
+                Record::Edit {
+                    new_step,
+                    offset,
+                    removed,
+                    inserted,
+                } => {
+                    if *new_step || self.steps.is_empty() {
+                        self.next_step += 1;
+                        self.steps.push(self.next_step);
+                    }
+                    let step = self.next_step;
+
+                    let absorbed = self.entries.last_mut().is_some_and(|last| {
+                        last.step == step
+                            && last.command == command
+                            && last.absorb(*offset, removed, inserted)
+                    });
+                    if !absorbed {
+                        self.entries.push(LogEntry {
+                            time,
+                            command: command.to_string(),
+                            offset: *offset,
+                            removed: removed.len(),
+                            inserted: inserted.len(),
+                            old: removed[..removed.len().min(LOG_PREVIEW)].to_vec(),
+                            new: inserted[..inserted.len().min(LOG_PREVIEW)].to_vec(),
+                            undone: None,
+                            step,
+                        });
+                    }
+                }
+                Record::Undo => {
+                    // Steps from before the log started aren't in it
+                    let Some(step) = self.steps.pop() else {
+                        continue;
+                    };
+                    for entry in self.entries.iter_mut().rev() {
+                        if entry.step == step {
+                            entry.undone = Some(time);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The edits, oldest first
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    /// Whether nothing was edited yet
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A time as `log` shows it: the time of day in local time, like `14:32:07`, or with `iso`
+/// the date and time with the offset from UTC, like `2024-03-09T14:32:07+01:00`
+pub fn format_time(time: SystemTime, iso: bool) -> String {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    };
+    format_seconds(seconds, utc_offset(seconds), iso)
+}
+
+/// A time given as seconds since 1970 in UTC, shown at `offset` seconds from UTC
+pub fn format_seconds(seconds: i64, offset: i64, iso: bool) -> String {
+    let local = seconds + offset;
+    let (days, time) = (local.div_euclid(86_400), local.rem_euclid(86_400));
+    let clock = format!("{:02}:{:02}:{:02}", time / 3600, time / 60 % 60, time % 60);
+
+    if !iso {
+        return clock;
+    }
+
+    let (year, month, day) = civil_date(days);
+    let sign = if offset < 0 { '-' } else { '+' };
+    format!(
+        "{year:04}-{month:02}-{day:02}T{clock}{sign}{:02}:{:02}",
+        offset.abs() / 3600,
+        offset.abs() / 60 % 60
+    )
+}
+
+/// The year, month and day a number of days after 1970-01-01 falls on
+fn civil_date(days: i64) -> (i64, i64, i64) {
+    // Counted in 400 year eras from March 1st of year 0, so leap days come last in a year
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// How far local time is ahead of UTC at a time, in seconds
+#[cfg(unix)]
+pub fn utc_offset(seconds: i64) -> i64 {
+    let time = seconds as libc::time_t;
+    // SAFETY: localtime_r only writes the tm it is handed
+    unsafe {
+        let mut tm = std::mem::zeroed::<libc::tm>();
+        match libc::localtime_r(&time, &mut tm).is_null() {
+            true => 0,
+            false => tm.tm_gmtoff as i64,
+        }
+    }
+}
+
+/// How far local time is ahead of UTC, taken as none where there is no way to ask
+#[cfg(not(unix))]
+pub fn utc_offset(_seconds: i64) -> i64 {
+    0
+}
+
+/// A CSV field, quoted when it holds a comma, a quote or a line break
+pub fn csv_field(text: &str) -> String {
+    match text.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
@@ -0,0 +1,1442 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+use crate::{
+    terminal::{hold_lock, terminal_size, Input},
+    watch::{watch_file, Watcher},
+};
+use hex_it::{
+    compare::{differs, range_differs},
+    document::{moved_offset, Change, Document},
+    dump::{previous_row, render_skipped, squeezed_rows, LineCache, Row},
+    editlog::EditLog,
+    file::{self, FileKind, FileLock, Fingerprint, LoadOptions},
+    gzip::{self, is_gzip},
+    inspect::{summary, MAX_WIDTH},
+    journal::{journal_path, Journal, JournalWriter},
+    keymap::Mode,
+    labels::Labels,
+    line::InputLine,
+    magic::{builtin_signatures, detect, Signature},
+    recover::{newer_recovery, recovery_path, Recovery},
+    screen::{column_index, Frame},
+    stride::Stride,
+    strings::{unit_start, utf16_bytes, Encoding},
+    template::Template,
+    theme::{Marks, Theme, RESET},
+    DumpOptions,
+};
+use std::{
+    fs,
+    io::{self, Write},
+    ops::Range,
+    path::PathBuf,
+    sync::mpsc::{Receiver, Sender},
+    thread::{self, JoinHandle},
+    time::SystemTime,
+};
+
+/// How files are opened, kept so a file opened mid-session gets the same treatment as the first
+pub struct OpenSettings {
+    pub load: LoadOptions,
+    /// Whether to use colors at all, as --color, NO_COLOR and the terminal decided
+    pub colors: bool,
+    /// Colors for the whole dump, set by --colors
+    pub theme: Option<Theme>,
+    /// Whether dump lines start with their offset, `gutter` in the config
+    pub gutter: bool,
+    /// Open everything read-only, set by --readonly
+    pub readonly: bool,
+    /// Allow editing devices, set by --read-write
+    pub read_write: bool,
+    /// Edit even if another process has the file locked, set by --force
+    pub force: bool,
+    /// Show what is inside gzip compressed files instead of their bytes, set by --decompress
+    pub decompress: bool,
+    /// Signatures from the config, on top of the built-in ones
+    pub signatures: Vec<Signature>,
+    /// Where file watchers report changes and background jobs their progress,
+    /// None to not watch files
+    pub watch: Option<Sender<Input>>,
+    /// Whether edits are written to a journal next to the file, `journal` in the config
+    pub journal: bool,
+}
+
+/// The buffer being edited and everything about how it is shown
+pub struct EditorState {
+    pub file: PathBuf,
+    pub dump: DumpOptions,
+    pub document: Document,
+    /// Whether the file is a regular file, a device or a pipe
+    pub kind: FileKind,
+    /// How many bytes were read when the file was opened
+    pub bytes_read: usize,
+    /// Size of the gzip file the buffer was decompressed from, None when it holds the file's
+    /// own bytes
+    pub gzip: Option<usize>,
+    /// How the file was read, used again to reload it
+    pub load_options: LoadOptions,
+    /// The file as it was when loaded, None if it isn't watched
+    pub on_disk: Option<Fingerprint>,
+    /// Set once the file has been changed on disk by something else
+    pub disk_changed: bool,
+    /// Polls the file for changes while the buffer is open
+    watcher: Option<Watcher>,
+    /// The document version the recovery file was last written for, None while this buffer
+    /// has no recovery file of its own
+    autosaved: Option<u64>,
+    /// Writes the recovery file in the background, one write at a time
+    recovery_writer: Option<JoinHandle<io::Result<()>>>,
+    /// Appends every edit to the journal next to the file, None while edits aren't journaled
+    journal: Option<JournalWriter>,
+    /// When each edit of the session was made and by what, for `log`
+    pub log: EditLog,
+    /// Which buffer this is and how many there are, None while it is the only one
+    pub position: Option<(usize, usize)>,
+    /// Set when edits aren't allowed, by --readonly, for devices without --read-write
+    /// or when another process has the file locked
+    pub readonly: bool,
+    /// Set when the file was opened read-only because another process holds its lock
+    pub locked_elsewhere: bool,
+    /// Changes to the document since the pretty print was generated
+    changes: Receiver<Change>,
+    /// Ranges changed since the last save, written back when saving a device in place
+    pub unsaved_ranges: Vec<Range<usize>>,
+    /// Set once an edit since the last save moved bytes around, which devices can't take
+    pub length_changed: bool,
+    /// Dump lines rendered so far, edits throw away just the lines they touched
+    lines: LineCache,
+    /// The signatures file types are guessed from
+    pub signatures: Vec<Signature>,
+    /// The best guess for what kind of file this is, None if nothing matched
+    pub file_type: Option<Signature>,
+    /// The structure template the file is decoded with
+    pub template: Option<Template>,
+    /// Names for offsets from `labels load`, shown at the end of their rows
+    pub labels: Labels,
+    /// Bytes covered by each template field, worked out along with the pretty print
+    field_ranges: Vec<Range<usize>>,
+    /// Index of the first line shown on screen
+    pub top_line: usize,
+    /// Offset of the byte the grid cursor is on
+    pub cursor: usize,
+    /// Where the selection started, it runs from here to the cursor
+    pub selection: Option<usize>,
+    /// Whether the bytes on screen equal to the one under the cursor are underlined, set by hlbyte
+    pub same_bytes: bool,
+    /// Whether the bytes at the cursor are decoded on a line above the command line, `inspector`
+    pub inspector: bool,
+    /// The records whose field bytes are tinted, set by `stride`
+    pub stride: Option<Stride>,
+    pub mode: Mode,
+    /// In overwrite mode, set once the high nibble of the byte under the cursor has been typed
+    high_nibble_typed: bool,
+    /// Offsets found by the last search or scan, n and N jump between them
+    pub matches: Vec<usize>,
+    /// How many bytes each match covers
+    pub match_width: usize,
+    /// What the matches are of, the search as typed or the command that found them
+    pub match_search: String,
+    /// Where the entries the last `headers` or `chunks` listed start, with the command that
+    /// listed them, what `goto #<n>` jumps to until `changes` is listed again
+    pub entries: Option<(&'static str, Vec<usize>)>,
+    /// The block size of the last `entmap` of the buffer, for `goto block <n>`
+    pub map_block: Option<usize>,
+    /// The file `compare` shows under this one, read-only and scrolled along with it
+    pub compare: Option<Box<EditorState>>,
+    /// Shown under the dump until the next command runs
+    pub message: String,
+    /// The last message shown with `fail`, the message is drawn as an error while it still is this one
+    error: Option<String>,
+}
+
+/// The bytes of a file as the buffer holds them: inflated when `decompress` is set and they are
+/// gzip compressed, with the size of the gzip file then
+pub fn inflate(
+    bytes: Vec<u8>,
+    decompress: bool,
+    load: &LoadOptions,
+) -> Result<(Vec<u8>, Option<usize>), String> {
+    if !decompress || !is_gzip(&bytes) {
+        return Ok((bytes, None));
+    }
+
+    let inflated = gzip::decompress(&bytes, inflate_limit(load))?;
+    Ok((inflated, Some(bytes.len())))
+}
+
+/// Most bytes a gzip file inflates to, the same as what is read from a pipe
+fn inflate_limit(load: &LoadOptions) -> usize {
+    usize::try_from(load.stream_limit).unwrap_or(usize::MAX)
+}
+
+/// Lines scrolled per notch of the mouse wheel
+pub const WHEEL_SCROLL_LINES: usize = 3;
+
+/// Bytes on each line of the dump
+pub const BYTES_PER_LINE: usize = 16;
+
+/// Backgrounds the template fields cycle through in the dump
+const FIELD_COLORS: [&str; 6] = [
+    "\x1b[30;46m",
+    "\x1b[30;43m",
+    "\x1b[30;42m",
+    "\x1b[30;45m",
+    "\x1b[30;44m",
+    "\x1b[30;41m",
+];
+
+/// The bytes at the field offset of every record of `stride`
+const STRIDE_FIELD: &str = "\x1b[30;106m";
+
+/// The first byte of every record of `stride`
+const STRIDE_RECORD: &str = "\x1b[100m";
+
+impl EditorState {
+    pub fn new(file: PathBuf, mut document: Document, colors: bool) -> EditorState {
+        // Kept for the edit log and the journal
+        document.start_journal();
+
+        EditorState {
+            file,
+            dump: DumpOptions {
+                cols: BYTES_PER_LINE,
+                group: 0,
+                colors,
+                theme: None,
+                squeeze: false,
+                gutter: true,
+                text: Encoding::Ascii,
+                odd_units: false,
+            },
+            kind: FileKind::Regular,
+            bytes_read: document.len(),
+            gzip: None,
+            load_options: LoadOptions::default(),
+            on_disk: None,
+            disk_changed: false,
+            watcher: None,
+            autosaved: None,
+            recovery_writer: None,
+            journal: None,
+            log: EditLog::default(),
+            position: None,
+            readonly: false,
+            locked_elsewhere: false,
+            changes: document.subscribe(),
+            unsaved_ranges: Vec::new(),
+            length_changed: false,
+            document,
+            lines: LineCache::default(),
+            signatures: builtin_signatures(),
+            file_type: None,
+            template: None,
+            labels: Labels::default(),
+            field_ranges: Vec::new(),
+            top_line: 0,
+            cursor: 0,
+            selection: None,
+            same_bytes: false,
+            inspector: false,
+            stride: None,
+            mode: Mode::Normal,
+            high_nibble_typed: false,
+            matches: Vec::new(),
+            match_width: 1,
+            match_search: String::new(),
+            entries: None,
+            map_block: None,
+            compare: None,
+            message: String::new(),
+            error: None,
+        }
+    }
+
+    /// Catches up on changes to the document, returns whether there were any.
+    /// Only the dump lines a change touched are rendered again
+    pub fn sync_changes(&mut self) -> bool {
+        let mut changed = false;
+
+        for change in self.changes.try_iter() {
+            let end = change.offset + change.removed.max(change.inserted);
+            self.unsaved_ranges.push(change.offset..end);
+            self.length_changed |= change.shifts();
+            self.lines.invalidate(&change, self.dump.cols);
+            // A UTF-16 character shows in the cell of its first byte, which can be up to 3
+            // bytes before the change and on the line above
+            if let Encoding::Utf16(_) = self.dump.text {
+                let reach = change.offset.saturating_sub(3)..end + 3;
+                self.lines.invalidate(
+                    &Change {
+                        offset: reach.start,
+                        removed: reach.len(),
+                        inserted: reach.len(),
+                    },
+                    self.dump.cols,
+                );
+            }
+            changed = true;
+        }
+
+        if changed {
+            self.decode_fields();
+        }
+
+        changed
+    }
+
+    /// Whether edits are allowed, if not the message says why
+    pub fn check_writable(&mut self) -> bool {
+        if self.readonly {
+            self.message = match self.kind {
+                _ if self.locked_elsewhere => {
+                    "Another process has the file locked, start with --force to edit anyway"
+                }
+                FileKind::Device => "Devices are read-only, start with --read-write to edit",
+                FileKind::Regular | FileKind::Stream => "Opened read-only",
+            }
+            .to_string();
+        }
+
+        !self.readonly
+    }
+
+    /// Loads a file into a new buffer. The error says why it couldn't be opened
+    pub fn open(file: PathBuf, settings: &OpenSettings) -> Result<EditorState, String> {
+        let (bytes, kind) = file::load(&file, &settings.load)
+            .map_err(|e| format!("Can't open {}: {}", file.display(), e))?;
+        let compressed = is_gzip(&bytes);
+        let (bytes, gzip) = inflate(bytes, settings.decompress, &settings.load)
+            .map_err(|e| format!("Can't decompress {}: {}", file.display(), e))?;
+
+        let mut editor = EditorState::new(file, Document::new(bytes), settings.colors);
+        editor.gzip = gzip;
+        if compressed && gzip.is_none() {
+            editor.message = format!(
+                "{} is gzip compressed, 'decompress' shows what is inside",
+                editor.file.display()
+            );
+        }
+        editor.dump.theme = settings.theme;
+        editor.dump.gutter = settings.gutter;
+        editor.kind = kind;
+        editor.load_options = settings.load;
+        editor.readonly = settings.readonly || (kind == FileKind::Device && !settings.read_write);
+        editor
+            .signatures
+            .extend(settings.signatures.iter().cloned());
+        editor.detect_file_type();
+
+        // A journal left by a session that didn't end waits until it is replayed or deleted
+        if settings.journal && !editor.readonly && editor.leftover_journal().is_none() {
+            editor.start_journal();
+        }
+
+        editor.record_disk_state();
+        if let (Some(_), Some(tx)) = (editor.on_disk, &settings.watch) {
+            editor.watcher = Some(watch_file(editor.file.clone(), tx.clone()));
+        }
+
+        // Only one editor at a time gets to write, read-only viewers don't need the lock
+        if !editor.readonly && kind != FileKind::Stream {
+            match FileLock::try_lock(&editor.file) {
+                Ok(Some(lock)) => hold_lock(&editor.file, lock),
+                Ok(None) if settings.force => {
+                    editor.message =
+                        "Another process has the file locked, editing anyway".to_string();
+                }
+                Ok(None) => {
+                    editor.readonly = true;
+                    editor.locked_elsewhere = true;
+                    editor.message = "Another process has the file locked, opened read-only. Start with --force to edit anyway".to_string();
+                }
+                // Some filesystems don't do locks at all, that shouldn't stop editing
+                Err(_) => {}
+            }
+        }
+
+        Ok(editor)
+    }
+
+    /// A new buffer of `size` bytes of `fill` that isn't any file yet, it only gets a path
+    /// once it is saved somewhere
+    pub fn blank(size: usize, fill: u8, settings: &OpenSettings) -> Result<EditorState, String> {
+        let mut bytes = Vec::new();
+        bytes
+            .try_reserve_exact(size)
+            .map_err(|_| format!("Can't make a buffer of {:X} bytes, not enough memory", size))?;
+        bytes.resize(size, fill);
+
+        let mut editor =
+            EditorState::new(PathBuf::new(), Document::unsaved(bytes), settings.colors);
+        editor.dump.theme = settings.theme;
+        editor.dump.gutter = settings.gutter;
+        editor
+            .signatures
+            .extend(settings.signatures.iter().cloned());
+        editor.detect_file_type();
+
+        Ok(editor)
+    }
+
+    /// Whether the buffer was made with `new` and hasn't been saved to a file yet
+    pub fn is_unnamed(&self) -> bool {
+        self.file.as_os_str().is_empty()
+    }
+
+    /// The path of the file, or [new] for a buffer that isn't saved anywhere yet
+    pub fn name(&self) -> String {
+        match self.is_unnamed() {
+            true => "[new]".to_string(),
+            false => self.file.display().to_string(),
+        }
+    }
+
+    /// Where the unsaved changes of the buffer are autosaved to, None unless it is a regular
+    /// file. Devices have nowhere next to them to write to and the rest have no file at all
+    pub fn recovery_path(&self) -> Option<PathBuf> {
+        (self.kind == FileKind::Regular && !self.is_unnamed()).then(|| recovery_path(&self.file))
+    }
+
+    /// The recovery file left by an earlier session, if there is one newer than the file
+    pub fn newer_recovery(&self) -> Option<PathBuf> {
+        self.recovery_path()?;
+        newer_recovery(&self.file)
+    }
+
+    /// Writes the unsaved changes to the recovery file in the background, unless they were
+    /// written already or the last write is still going. Only the changes are written, so a
+    /// large buffer costs no more than a small one. A buffer with nothing unsaved doesn't
+    /// need its recovery file anymore. The error is from the write before, if it failed
+    pub fn autosave(&mut self) -> Result<(), String> {
+        let Some(path) = self.recovery_path() else {
+            return Ok(());
+        };
+
+        if !self.modified() {
+            self.remove_recovery();
+            return Ok(());
+        }
+
+        if let Some(writer) = &self.recovery_writer {
+            if !writer.is_finished() {
+                return Ok(());
+            }
+        }
+        let written = match self.recovery_writer.take().map(JoinHandle::join) {
+            Some(Ok(Err(e))) => Err(format!("Can't autosave to {}: {}", path.display(), e)),
+            _ => Ok(()),
+        };
+
+        if self.autosaved != Some(self.document.version()) {
+            let recovery = Recovery::of(&self.document);
+            self.autosaved = Some(self.document.version());
+            self.recovery_writer = Some(thread::spawn(move || {
+                file::save_atomic(&path, recovery.contents().as_bytes())
+            }));
+        }
+
+        written
+    }
+
+    /// Takes the recovery file of the buffer over, so saving or closing it removes the file
+    pub fn adopt_recovery(&mut self) {
+        self.autosaved = Some(self.document.version());
+    }
+
+    /// Removes the recovery file this buffer wrote, once its changes are saved or thrown away
+    pub fn remove_recovery(&mut self) {
+        // A write still going would put the file back after it is gone
+        if let Some(writer) = self.recovery_writer.take() {
+            let _ = writer.join();
+        }
+
+        if let (Some(_), Some(path)) = (self.autosaved.take(), self.recovery_path()) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// The journal a session that didn't end left next to the file, if there is one
+    pub fn leftover_journal(&self) -> Option<PathBuf> {
+        self.recovery_path()?;
+        let path = journal_path(&self.file);
+        (self.journal.is_none() && path.exists()).then_some(path)
+    }
+
+    /// Journals the edits from now on, against the file as the buffer holds it now. Only
+    /// regular files get a journal, the same as recovery files
+    pub fn start_journal(&mut self) {
+        if self.recovery_path().is_some() {
+            self.journal = Some(JournalWriter::new(journal_path(&self.file), self.len()));
+        }
+    }
+
+    /// Starts the journal over when the file and the buffer agree again, after a save or reload
+    pub fn restart_journal(&mut self) {
+        if let Some(journal) = self.journal.take() {
+            journal.remove();
+            self.start_journal();
+        }
+    }
+
+    /// Removes the journal, once the edits in it were saved or given up on
+    pub fn remove_journal(&mut self) {
+        if let Some(journal) = self.journal.take() {
+            journal.remove();
+        }
+    }
+
+    /// Logs the edits made since the last time as made by `command` and writes them to the
+    /// journal. If writing fails the edits aren't journaled anymore, rather than failing
+    /// again at every key
+    pub fn flush_journal(&mut self, command: &str) -> Result<(), String> {
+        let records = self.document.take_journal();
+        if records.is_empty() {
+            return Ok(());
+        }
+        self.log.record(&records, command, SystemTime::now());
+
+        let Some(journal) = &mut self.journal else {
+            return Ok(());
+        };
+        if let Err(e) = journal.append(&records) {
+            let path = journal.path().display().to_string();
+            self.journal = None;
+            return Err(format!(
+                "Can't write to {path}, edits aren't journaled anymore: {e}"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Replays the journal a session that didn't end left, over the file as it is now, undo
+    /// steps and all, and journals on into it when `journaling`. Returns how many records
+    /// were replayed and whether some at the end were damaged and left out
+    pub fn replay_journal(&mut self, journaling: bool) -> Result<(usize, bool), String> {
+        let path = journal_path(&self.file);
+        let bytes = fs::read(&path).map_err(|e| e.to_string())?;
+        let journal = Journal::parse(&bytes)?;
+        let document = journal.replay(self.document.bytes().to_vec())?;
+
+        self.set_document(document);
+        if journaling {
+            let writer = JournalWriter::resume(path, &journal).map_err(|e| e.to_string())?;
+            self.journal = Some(writer);
+        }
+
+        Ok((journal.records.len(), journal.intact < bytes.len()))
+    }
+
+    /// Remembers what the file looks like on disk now, so later changes can be noticed.
+    /// Only regular files are watched, devices and pipes don't have a meaningful mtime
+    pub fn record_disk_state(&mut self) {
+        self.disk_changed = false;
+        self.on_disk = match self.kind {
+            FileKind::Regular => Fingerprint::of(&self.file).ok(),
+            FileKind::Device | FileKind::Stream => None,
+        };
+    }
+
+    /// Looks at the file on disk again after the watcher saw it change.
+    /// A file that was only touched keeps counting as unchanged
+    pub fn check_disk(&mut self) {
+        let Some(on_disk) = self.on_disk else {
+            return;
+        };
+
+        match Fingerprint::of(&self.file) {
+            // Compare against the touched file from now on, its hash doesn't have to be worked out again
+            Ok(now) if now.same_contents(&on_disk) => self.on_disk = Some(now),
+            // Gone or changed, either way the buffer no longer matches the disk
+            _ => {
+                if !self.disk_changed {
+                    self.message = format!(
+                        "{} changed on disk, type 'reload' to load the new contents",
+                        self.file.display()
+                    );
+                }
+                self.disk_changed = true;
+            }
+        }
+    }
+
+    /// Reads the file again, throwing away any unsaved changes
+    pub fn reload(&mut self) -> Result<(), String> {
+        if self.is_unnamed() {
+            return Err(
+                "Nothing to reload, the buffer hasn't been saved to a file yet".to_string(),
+            );
+        }
+
+        if self.kind == FileKind::Stream {
+            return Err("Can't reload a pipe, what it sent has been read already".to_string());
+        }
+
+        let (bytes, kind) = file::load(&self.file, &self.load_options)
+            .map_err(|e| format!("Can't reload {}: {}", self.file.display(), e))?;
+        let (bytes, gzip) = inflate(bytes, self.gzip.is_some(), &self.load_options)
+            .map_err(|e| format!("Can't decompress {}: {}", self.file.display(), e))?;
+
+        self.kind = kind;
+        self.gzip = gzip;
+        self.set_contents(bytes);
+        self.record_disk_state();
+
+        Ok(())
+    }
+
+    /// Shows what is inside a gzip compressed buffer instead of its bytes. There must be no
+    /// unsaved changes, saving compresses it again only when asked
+    pub fn decompress(&mut self) -> Result<(), String> {
+        if self.gzip.is_some() {
+            return Err("The buffer is decompressed already".to_string());
+        }
+
+        if !is_gzip(self.document.bytes()) {
+            return Err(format!("{} isn't gzip compressed", self.name()));
+        }
+
+        if self.modified() {
+            return Err("Unsaved changes, save or undo them before decompressing".to_string());
+        }
+
+        let inflated = gzip::decompress(self.document.bytes(), inflate_limit(&self.load_options))
+            .map_err(|e| format!("Can't decompress {}: {}", self.name(), e))?;
+        self.gzip = Some(self.len());
+        self.matches.clear();
+        self.set_contents(inflated);
+
+        Ok(())
+    }
+
+    /// Puts new bytes in the buffer as its unmodified contents, like after opening it
+    fn set_contents(&mut self, bytes: Vec<u8>) {
+        self.set_document(Document::new(bytes));
+        self.restart_journal();
+    }
+
+    /// Puts another document in the buffer, everything worked out from the old one goes
+    fn set_document(&mut self, document: Document) {
+        self.document = document;
+        self.document.start_journal();
+        self.changes = self.document.subscribe();
+        self.unsaved_ranges.clear();
+        self.length_changed = false;
+        self.lines.clear();
+        self.decode_fields();
+        self.high_nibble_typed = false;
+        self.entries = None;
+        self.map_block = None;
+        self.selection = None;
+        self.set_cursor(self.cursor);
+        self.detect_file_type();
+    }
+
+    /// Guesses the file type from the current contents, returns a description of the match
+    pub fn detect_file_type(&mut self) -> String {
+        self.file_type = detect(&self.signatures, self.document.bytes()).cloned();
+
+        match &self.file_type {
+            Some(signature) => signature.describe_match(self.document.bytes()),
+            None => "unknown".to_string(),
+        }
+    }
+
+    /// Decodes the file with a template from now on, None stops highlighting the fields
+    pub fn set_template(&mut self, template: Option<Template>) {
+        self.template = template;
+        self.decode_fields();
+    }
+
+    /// Works out where the template's fields are in the current contents
+    fn decode_fields(&mut self) {
+        self.field_ranges = match &self.template {
+            Some(template) => template
+                .decode(self.document.bytes())
+                .into_iter()
+                .map(|decoded| decoded.range)
+                .collect(),
+            None => Vec::new(),
+        };
+    }
+
+    /// Turns the colors of the dump on or off
+    pub fn set_colors(&mut self, colors: bool) {
+        self.dump.colors = colors;
+        self.lines.clear();
+    }
+
+    /// Colors the dump with another theme, or none
+    pub fn set_theme(&mut self, theme: Option<Theme>) {
+        self.dump.theme = theme;
+        self.lines.clear();
+    }
+
+    /// Shows or hides the offsets at the start of the dump lines
+    pub fn set_gutter(&mut self, gutter: bool) {
+        self.dump.gutter = gutter;
+        self.lines.clear();
+    }
+
+    /// Reads the text column in another encoding, with UTF-16 code units starting at odd
+    /// offsets when `odd_units` is set
+    pub fn set_text_encoding(&mut self, text: Encoding, odd_units: bool) {
+        self.dump.text = text;
+        self.dump.odd_units = odd_units;
+        self.lines.clear();
+    }
+
+    /// Shows a message that says something went wrong, drawn in the theme's error color
+    pub fn fail(&mut self, error: impl Into<String>) {
+        self.message = error.into();
+        self.error = Some(self.message.clone());
+    }
+
+    /// Index of the template field covering an offset
+    fn field_at(&self, offset: usize) -> Option<usize> {
+        self.field_ranges
+            .iter()
+            .position(|range| range.contains(&offset))
+    }
+
+    /// Whether the buffer differs from the file on disk
+    pub fn modified(&self) -> bool {
+        self.document.is_dirty()
+    }
+
+    /// How many lines of the dump fit on screen, leaving room for the divider, message and command line
+    pub fn visible_lines(&self) -> usize {
+        let (_, height) = terminal_size();
+        // The status bar, the message and the command line, and the inspector when it is on
+        let rows = height.saturating_sub(3 + usize::from(self.inspector));
+
+        // Comparing, the other file gets the bottom half below a divider of its own
+        match self.compare {
+            Some(_) => (rows.saturating_sub(1) / 2).max(1),
+            None => rows.max(1),
+        }
+    }
+
+    /// The rows on screen: a row per line from the top one, or with squeeze on a `*` row for each
+    /// run of identical lines. The line the cursor is on is never squeezed away
+    pub fn rows(&self) -> Vec<Row> {
+        self.rows_from(self.top_line, self.visible_lines())
+    }
+
+    /// Up to `count` rows from the one line `top` starts
+    fn rows_from(&self, top: usize, count: usize) -> Vec<Row> {
+        match self.dump.squeeze {
+            true => squeezed_rows(
+                self.document.bytes(),
+                BYTES_PER_LINE,
+                top,
+                count,
+                Some(self.cursor / BYTES_PER_LINE),
+            ),
+            false => (top..self.line_count())
+                .take(count)
+                .map(Row::Line)
+                .collect(),
+        }
+    }
+
+    /// First line of the row a number of rows above (negative) or below the one `line` starts,
+    /// the line count when that is past the end
+    fn rows_away(&self, line: usize, rows: isize) -> usize {
+        if !self.dump.squeeze {
+            return line.saturating_add_signed(rows);
+        }
+
+        let keep = Some(self.cursor / BYTES_PER_LINE);
+        match rows {
+            ..0 => (0..rows.unsigned_abs()).fold(line, |line, _| {
+                previous_row(self.document.bytes(), BYTES_PER_LINE, line, keep)
+            }),
+            _ => self
+                .rows_from(line, rows as usize + 1)
+                .get(rows as usize)
+                .map_or(self.line_count(), Row::line),
+        }
+    }
+
+    /// The lowest the top line goes, with the last line on the bottom row
+    fn last_top(&self) -> usize {
+        let rows = self.visible_lines() as isize - 1;
+
+        self.rows_away(self.line_count().saturating_sub(1), -rows)
+    }
+
+    /// Moves the view up (negative) or down by a number of rows, stopping at either end
+    pub fn scroll(&mut self, rows: isize) {
+        self.top_line = self.rows_away(self.top_line, rows).min(self.last_top());
+    }
+
+    /// Scrolls so the line with an offset is at the top of the screen, or in the middle with
+    /// `center`, as far as the end of the dump allows. The cursor stays where it is
+    pub fn view(&mut self, offset: usize, center: bool) {
+        let line = offset / BYTES_PER_LINE;
+        let top = match center {
+            true => self.rows_away(line, -((self.visible_lines() / 2) as isize)),
+            false => line,
+        };
+
+        self.top_line = top.min(self.last_top());
+    }
+
+    /// Whether the line the cursor is on is on screen
+    pub fn cursor_visible(&self) -> bool {
+        let line = self.cursor / BYTES_PER_LINE;
+
+        self.rows().iter().any(|row| row.lines().contains(&line))
+    }
+
+    /// Number of bytes in the buffer
+    pub fn len(&self) -> usize {
+        self.document.len()
+    }
+
+    /// Number of lines in the dump
+    pub fn line_count(&self) -> usize {
+        self.len().div_ceil(BYTES_PER_LINE)
+    }
+
+    /// Offsets of the bytes on a line of the dump, the last line can be shorter than the rest
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        let start = (line * BYTES_PER_LINE).min(self.len());
+
+        start..(start + BYTES_PER_LINE).min(self.len())
+    }
+
+    /// Reverts the last undo step and moves the cursor to it, returns false if there was nothing to undo.
+    /// Matches and a selection left past the end by it are dropped
+    pub fn undo(&mut self) -> bool {
+        match self.document.undo() {
+            Ok(offset) => {
+                self.set_cursor(offset);
+                self.forget_past_end();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Types a hex digit over the byte under the cursor in overwrite mode.
+    /// The first digit replaces the high nibble straight away, the second the low nibble
+    /// and then the cursor moves on to the next byte
+    pub fn type_nibble(&mut self, digit: u8) {
+        let Some(byte) = self.document.get(self.cursor) else {
+            return;
+        };
+
+        // Everything typed until the cursor is moved or the mode is left is one undo step
+        self.document.begin_undo_group();
+
+        // The cursor is always inside the document, so these can't fail
+        if self.high_nibble_typed {
+            let _ = self
+                .document
+                .overwrite(self.cursor, &[(byte & 0xF0) | digit]);
+            self.high_nibble_typed = false;
+
+            if self.cursor + 1 < self.len() {
+                self.set_cursor(self.cursor + 1);
+            }
+        } else {
+            let _ = self
+                .document
+                .overwrite(self.cursor, &[(digit << 4) | (byte & 0x0F)]);
+            self.high_nibble_typed = true;
+        }
+    }
+
+    /// Starts typing over the grid, the changes made until the mode is left are one undo step
+    pub fn enter_overwrite_mode(&mut self) {
+        self.enter_typing_mode(Mode::Overwrite);
+    }
+
+    /// Starts typing chars over the text column, undone like overwrite mode
+    pub fn enter_text_mode(&mut self) {
+        self.enter_typing_mode(Mode::Text);
+    }
+
+    fn enter_typing_mode(&mut self, mode: Mode) {
+        if !self.check_writable() {
+            return;
+        }
+
+        self.mode = mode;
+        self.high_nibble_typed = false;
+        self.document.end_undo_group();
+    }
+
+    /// Replaces the bytes under the cursor with a char typed in text mode, encoded like the
+    /// text column reads them, and moves on past them. In ASCII only printable ASCII has a
+    /// single byte to write, in UTF-16 the char goes over the code unit the cursor is in.
+    /// False if nothing was written
+    pub fn type_char(&mut self, c: char) -> bool {
+        if self.document.get(self.cursor).is_none() {
+            return false;
+        }
+
+        let (offset, bytes) = match self.dump.text {
+            Encoding::Ascii if c == ' ' || c.is_ascii_graphic() => (self.cursor, vec![c as u8]),
+            Encoding::Ascii => {
+                self.fail(format!(
+                    "'{}' is not printable ASCII, only that can be typed here",
+                    c.escape_default()
+                ));
+                return false;
+            }
+            Encoding::Utf16(_) if c.is_control() => {
+                self.fail(format!(
+                    "'{}' is a control character, it can't be typed here",
+                    c.escape_default()
+                ));
+                return false;
+            }
+            Encoding::Utf16(endian) => (
+                unit_start(self.cursor, self.dump.odd_units),
+                utf16_bytes(c.encode_utf8(&mut [0; 4]), endian),
+            ),
+        };
+        if offset + bytes.len() > self.len() {
+            self.fail(format!(
+                "'{}' takes {} bytes, there is no room for it before the end",
+                c,
+                bytes.len()
+            ));
+            return false;
+        }
+
+        // Like hex digits, everything typed until the cursor is moved is one undo step
+        self.document.begin_undo_group();
+        // The bytes are inside the document, so this can't fail
+        let _ = self.document.overwrite(offset, &bytes);
+
+        // Past the last byte the cursor stays on it
+        self.set_cursor(offset + bytes.len());
+        true
+    }
+
+    /// Moves the cursor, keeping it inside the buffer and scrolling so it stays on screen
+    pub fn set_cursor(&mut self, offset: usize) {
+        self.cursor = offset.min(self.len().saturating_sub(1));
+
+        let line = self.cursor / BYTES_PER_LINE;
+        let visible = self.visible_lines() as isize;
+
+        if line < self.top_line {
+            self.top_line = line;
+        } else if !self.cursor_visible() {
+            self.top_line = self.rows_away(line, 1 - visible);
+        }
+    }
+
+    /// Moves the cursor back (negative) or forward by a number of bytes.
+    /// In overwrite mode this ends the typing burst, a half typed byte keeps its new high nibble
+    pub fn move_cursor(&mut self, bytes: isize) {
+        self.move_cursor_to(self.cursor.saturating_add_signed(bytes));
+    }
+
+    /// Replaces the list n and N jump through, each match covering `width` bytes, found by
+    /// `search`
+    pub fn set_matches(&mut self, matches: Vec<usize>, width: usize, search: &str) {
+        self.matches = matches;
+        self.match_width = width.max(1);
+        self.match_search = search.to_string();
+    }
+
+    /// Whether an offset is inside one of the matches
+    pub fn in_match(&self, offset: usize) -> bool {
+        // Matches all cover the same width, so the last one starting at or before the offset
+        // reaches furthest
+        match self.matches.partition_point(|&start| start <= offset) {
+            0 => false,
+            after => offset < self.matches[after - 1] + self.match_width,
+        }
+    }
+
+    /// Moves the cursor, the selection and the matches at or after an offset along with the
+    /// bytes after `len` bytes were inserted there
+    pub fn shift_for_insert(&mut self, offset: usize, len: usize) {
+        let shift = |at: &mut usize| {
+            if *at >= offset {
+                *at += len;
+            }
+        };
+
+        self.matches.iter_mut().for_each(shift);
+        self.selection.iter_mut().for_each(shift);
+        let mut cursor = self.cursor;
+        shift(&mut cursor);
+        self.set_cursor(cursor);
+    }
+
+    /// Moves the matches after a range along with the bytes after the range was replaced by
+    /// `len` bytes, dropping the ones inside it, and selects the new bytes
+    pub fn shift_for_replace(&mut self, range: std::ops::Range<usize>, len: usize) {
+        self.matches
+            .retain(|&offset| offset < range.start || offset >= range.end);
+        for offset in self
+            .matches
+            .iter_mut()
+            .filter(|offset| **offset >= range.end)
+        {
+            *offset = *offset - range.len() + len;
+        }
+
+        self.selection = (len > 0).then_some(range.start);
+        self.move_cursor_to(range.start + len.saturating_sub(1));
+    }
+
+    /// Moves the matches and the selection start along with the bytes after `len` bytes at
+    /// `source` were moved to `destination`, and puts the cursor on the first moved byte
+    pub fn shift_for_move(&mut self, source: usize, len: usize, destination: usize) {
+        let shift = |at: &mut usize| *at = moved_offset(*at, source, len, destination);
+
+        self.matches.iter_mut().for_each(shift);
+        self.matches.sort_unstable();
+        self.selection.iter_mut().for_each(shift);
+        self.move_cursor_to(moved_offset(source, source, len, destination));
+    }
+
+    /// Drops the matches and the selection start that are past the end after the buffer shrank
+    /// and moves the cursor back inside. Returns whether anything was dropped
+    pub fn forget_past_end(&mut self) -> bool {
+        let len = self.len();
+        let matches = self.matches.len();
+        self.matches.retain(|&offset| offset < len);
+        let selection = self.selection.take_if(|start| *start >= len).is_some();
+        self.set_cursor(self.cursor);
+
+        selection || self.matches.len() != matches
+    }
+
+    /// Moves the cursor to the next match after it, or the one before it, wrapping around at either end
+    pub fn jump_to_match(&mut self, forward: bool) {
+        if self.matches.is_empty() {
+            return self.message = "No matches.".to_string();
+        }
+
+        let index = if forward {
+            self.matches
+                .iter()
+                .position(|offset| *offset > self.cursor)
+                .unwrap_or(0)
+        } else {
+            self.matches
+                .iter()
+                .rposition(|offset| *offset < self.cursor)
+                .unwrap_or(self.matches.len() - 1)
+        };
+
+        self.move_cursor_to(self.matches[index]);
+        self.message = format!("Match {} of {}", index + 1, self.matches.len());
+    }
+
+    /// Moves the cursor to an offset, ending a typing burst like move_cursor does
+    pub fn move_cursor_to(&mut self, offset: usize) {
+        self.high_nibble_typed = false;
+        self.document.end_undo_group();
+        self.set_cursor(offset);
+    }
+
+    /// The selected offsets, cursor included
+    pub fn selected(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        self.selection
+            .map(|anchor| anchor.min(self.cursor)..=anchor.max(self.cursor))
+    }
+
+    /// Adds the cursor and selection highlights to the hex part of a dump line, and with colors
+    /// on everything else that marks bytes. The first that applies wins: cursor and selection,
+    /// search matches, modified bytes, differences from the compared file, hlbyte, the stride
+    /// field, template fields and last the start of each stride record
+    fn highlight_line(&self, index: usize, line: &str) -> String {
+        let selected = self.selected();
+        let len = self.len();
+        let other = self.compare.as_ref().map(|other| other.document.bytes());
+        let under_cursor = self.document.get(self.cursor).filter(|_| self.same_bytes);
+        // Typing text the cursor is in the text column, the hex pair is just underlined
+        let text_mode = self.mode == Mode::Text;
+        let theme = self.dump.theme();
+
+        let painted = paint_pairs(line, &self.dump, |byte| {
+            let offset = index * BYTES_PER_LINE + byte;
+            let in_selection = selected
+                .as_ref()
+                .is_some_and(|range| range.contains(&offset));
+            let marks = Marks {
+                found: self.in_match(offset),
+                modified: self.document.is_modified(offset),
+            };
+
+            match (offset == self.cursor, in_selection) {
+                _ if offset >= len => None,
+                (true, false) if text_mode => Some("\x1b[4m"),
+                (true, true) => Some("\x1b[4;7m"),
+                (true, false) | (false, true) => Some("\x1b[7m"),
+                (false, false) if !self.dump.colors => None,
+                // Search matches and modified bytes get their theme color over the byte's class
+                (false, false) if theme.is_some() && (marks.found || marks.modified) => {
+                    theme.map(|theme| theme.layer(self.document.get(offset).unwrap_or(0), marks))
+                }
+                // Bytes that differ from the compared file stand out more than template fields
+                (false, false)
+                    if other.is_some_and(|other| differs(self.document.bytes(), other, offset)) =>
+                {
+                    Some(self.difference_color())
+                }
+                // Underlined, so the byte keeps its own color
+                (false, false)
+                    if under_cursor.is_some() && self.document.get(offset) == under_cursor =>
+                {
+                    Some("\x1b[4m")
+                }
+                (false, false) if self.stride.is_some_and(|stride| stride.is_field(offset)) => {
+                    Some(STRIDE_FIELD)
+                }
+                // Template fields get a color each, neighbours always differ. Record starts only
+                // get a faint background, so the stride field stands out more
+                (false, false) => match self.field_at(offset) {
+                    Some(field) => Some(FIELD_COLORS[field % FIELD_COLORS.len()]),
+                    None => self
+                        .stride
+                        .filter(|stride| stride.is_record_start(offset))
+                        .map(|_| STRIDE_RECORD),
+                },
+            }
+        });
+
+        match self.cursor / BYTES_PER_LINE == index && text_mode {
+            true => paint_char(
+                &painted,
+                self.dump.text_start() + self.cursor % BYTES_PER_LINE,
+                self.dump.theme().map_or("", |theme| theme.text),
+            ),
+            false => painted,
+        }
+    }
+
+    /// What bytes that differ from the compared file are colored with
+    fn difference_color(&self) -> &'static str {
+        self.dump.theme().map_or("\x1b[1;31m", |theme| theme.error)
+    }
+
+    /// Puts `=` in the gutter of a row that is the same in both compared files, `≠` otherwise
+    fn mark_compared_line(&self, row: Row, line: &str, other: &[u8]) -> String {
+        let lines = row.lines();
+        let range = lines.start * BYTES_PER_LINE..lines.end * BYTES_PER_LINE;
+        let same = !range_differs(self.document.bytes(), other, range);
+        // Without the gutter there is no room for the mark
+        let Some(gap) = self.dump.gutter_width().checked_sub(1) else {
+            return line.to_string();
+        };
+        let gap = column_index(line, gap);
+
+        match line.get(gap..gap + 1) {
+            Some(" ") => format!(
+                "{}{}{}",
+                &line[..gap],
+                if same { '=' } else { '≠' },
+                &line[gap + 1..]
+            ),
+            _ => line.to_string(),
+        }
+    }
+
+    /// The lines of the compared file shown under this one, row for row, with the divider above
+    /// them. Where this file is squeezed the other one is too, so offsets stay lined up
+    fn compared_lines(&mut self, rows: &[Row]) -> Vec<String> {
+        let mine = self.document.bytes();
+        let dump = self.dump;
+        let Some(other) = self.compare.as_deref_mut() else {
+            return Vec::new();
+        };
+
+        // The other file is drawn with the colors of this one, whatever they are now
+        if other.dump != dump {
+            other.dump = dump;
+            other.lines.clear();
+        }
+
+        let name = other.name();
+        let divider = format!(
+            "{:-<80}",
+            format!("-- compare -- {} {:08X} [RO] ", name, other.len())
+        );
+        let mut lines = vec![match dump.theme() {
+            Some(theme) => Theme::paint(theme.divider, &divider),
+            None => divider,
+        }];
+
+        let color = dump.theme().map_or("\x1b[1;31m", |theme| theme.error);
+        for &row in rows {
+            let index = match row {
+                Row::Line(index) if index < other.line_count() => index,
+                Row::Line(_) => break,
+                Row::Skipped { first, count } => {
+                    // Only the lines the other file has are left out of it
+                    let count = count.min(other.line_count().saturating_sub(first));
+                    if count == 0 {
+                        break;
+                    }
+                    let line = render_skipped(count, BYTES_PER_LINE, &other.dump);
+                    lines.push(other.mark_compared_line(row, &line, mine));
+                    continue;
+                }
+            };
+            let line = other
+                .lines
+                .line(index, other.document.bytes(), &other.dump)
+                .to_string();
+            let line = match dump.colors {
+                true => paint_pairs(&line, &dump, |byte| {
+                    let offset = index * BYTES_PER_LINE + byte;
+                    (offset < other.len() && differs(mine, other.document.bytes(), offset))
+                        .then_some(color)
+                }),
+                false => line,
+            };
+            lines.push(other.mark_compared_line(row, &line, mine));
+        }
+
+        lines
+    }
+
+    /// Finds the byte under a 1-based terminal position, None if it isn't on a byte of the dump
+    pub fn offset_at(&self, column: usize, row: usize) -> Option<usize> {
+        // A `*` row stands for its first line
+        let line = self.rows().get(row.checked_sub(1)?)?.line();
+        let byte = self.dump.column_to_byte(column.checked_sub(1)?)?;
+
+        let offset = line * BYTES_PER_LINE + byte;
+
+        // The last line can be shorter than the rest
+        (offset < self.len()).then_some(offset)
+    }
+
+    /// The divider between the dump and the command line, showing where the cursor is
+    fn status_bar(&self) -> String {
+        let mode = match self.mode {
+            Mode::Normal => "NORMAL",
+            Mode::Command => "COMMAND",
+            Mode::Overwrite => "OVERWRITE",
+            Mode::Text => "TEXT",
+        };
+
+        let file_type = self
+            .file_type
+            .as_ref()
+            .map_or("unknown", |signature| signature.description.as_str());
+
+        let mut status = format!("-- {} -- ", mode);
+
+        if let Some((buffer, buffers)) = self.position {
+            status += &format!("{}/{} ", buffer, buffers);
+        }
+
+        // Just the name, the whole path would push everything else off the bar
+        match self.file.file_name() {
+            Some(name) => status += &format!("{} ", name.to_string_lossy()),
+            None => status += &format!("{} ", self.name()),
+        }
+
+        // Scrolled away from the cursor, so where the view is has to be said separately
+        if !self.cursor_visible() {
+            status += &format!("view {:08X} ", self.top_line * BYTES_PER_LINE);
+        }
+
+        status += &format!("{:08X} / {:08X} {} ", self.cursor, self.len(), file_type);
+
+        if let Some(range) = self.selected() {
+            status += &format!("sel {:08X}-{:08X} ", range.start(), range.end());
+        }
+
+        if self.modified() {
+            status += "[+] ";
+        }
+
+        if self.readonly {
+            status += "[RO] ";
+        }
+
+        if self.locked_elsewhere {
+            status += "[locked by another process] ";
+        }
+
+        if self.disk_changed {
+            status += "[changed on disk] ";
+        }
+
+        if self.kind == FileKind::Stream {
+            status += &format!("[pipe, {:X} bytes read] ", self.bytes_read);
+        }
+
+        if let (Some(size), Some(name)) = (self.gzip, self.file.file_name()) {
+            status += &format!(
+                "[decompressed from {}, original {:X} bytes] ",
+                name.to_string_lossy(),
+                size
+            );
+        }
+
+        // Writing to a disk by mistake is hard to undo, so it gets a loud warning
+        if self.kind == FileKind::Device && !self.readonly {
+            let status = format!(
+                "{:-<80}",
+                format!("{}!! DEVICE OPEN READ-WRITE !! ", status)
+            );
+
+            return match (self.dump.theme(), self.dump.colors) {
+                (Some(theme), _) => Theme::paint(theme.error, &status),
+                (None, true) => format!("\x1b[1;31m{}\x1b[0m", status),
+                (None, false) => status,
+            };
+        }
+
+        let status = format!("{:-<80}", status);
+        match self.dump.theme() {
+            Some(theme) => Theme::paint(theme.divider, &status),
+            None => status,
+        }
+    }
+
+    /// Draws the lines of the dump that fit on screen, the status bar, the message and the prompt
+    /// as one frame, rendering only the dump lines that aren't cached
+    pub fn draw(&mut self, prompt: &str, input: &InputLine) {
+        self.sync_changes();
+
+        let (width, height) = terminal_size();
+        let mut frame = Frame::new(width, height);
+
+        let rows = self.rows();
+        for &row in &rows {
+            let line = match row {
+                Row::Line(index) => {
+                    let line = self
+                        .lines
+                        .line(index, self.document.bytes(), &self.dump)
+                        .to_string();
+                    self.highlight_line(index, &line)
+                }
+                Row::Skipped { count, .. } => render_skipped(count, BYTES_PER_LINE, &self.dump),
+            };
+
+            let mut line = match &self.compare {
+                Some(other) => self.mark_compared_line(row, &line, other.document.bytes()),
+                None => line,
+            };
+            if let Row::Line(index) = row {
+                line += &self.label_note(index, width);
+            }
+            frame.line(&line);
+        }
+
+        if self.compare.is_some() {
+            // Short files leave empty rows, so the compared file always starts at the same row
+            for _ in rows.len()..self.visible_lines() {
+                frame.line("");
+            }
+
+            for line in self.compared_lines(&rows) {
+                frame.line(&line);
+            }
+        }
+
+        frame.line(&self.status_bar());
+        match self.dump.theme() {
+            Some(theme) if self.error.as_ref() == Some(&self.message) => {
+                frame.line(&Theme::paint(theme.error, &self.message))
+            }
+            _ => frame.line(&self.message),
+        }
+        if self.inspector {
+            let end = (self.cursor + MAX_WIDTH).min(self.len());
+            let bytes = self
+                .document
+                .bytes()
+                .get(self.cursor..end)
+                .unwrap_or_default();
+            frame.line(&summary(self.cursor, bytes, width));
+        }
+        frame.prompt(prompt, input);
+
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(frame.as_str().as_bytes()).unwrap();
+        stdout.flush().unwrap();
+    }
+
+    /// The labels of the offsets on a line, to go after its text column, cut to what is left
+    /// of a terminal `width` columns wide. Labels past the start of the line say how far
+    fn label_note(&self, index: usize, width: usize) -> String {
+        let start = index * BYTES_PER_LINE;
+        let names = self
+            .labels
+            .within(start..start + BYTES_PER_LINE)
+            .map(|(offset, name)| match offset - start {
+                0 => name.to_string(),
+                into => format!("+{into:X} {name}"),
+            })
+            .collect::<Vec<String>>();
+        let room = width.saturating_sub(self.dump.text_start() + BYTES_PER_LINE + 2);
+        if names.is_empty() || room == 0 {
+            return String::new();
+        }
+
+        let note = names.join(", ").chars().take(room).collect::<String>();
+        match self.dump.theme() {
+            Some(theme) => format!("  {}{note}{RESET}", theme.offset),
+            None => format!("  {note}"),
+        }
+    }
+}
+
+/// Gives some of the hex pairs of a dump line a style, `style` is asked for each byte of the
+/// line by its index. Everything up to a pair is copied as is, then the pair gets its style.
+/// A themed line has colors in it, so screen columns are looked up past them
+fn paint_pairs(
+    line: &str,
+    dump: &DumpOptions,
+    style: impl Fn(usize) -> Option<&'static str>,
+) -> String {
+    let mut painted = String::new();
+    let mut copied = 0;
+
+    for byte in 0..BYTES_PER_LINE {
+        if let Some(style) = style(byte) {
+            let column = dump.hex_column(byte);
+            let start = column_index(line, column);
+            let end = column_index(line, column + 2);
+            painted.push_str(&line[copied..start]);
+            painted.push_str(&format!(
+                "{}{}{}",
+                style,
+                &line[start..end].replace(RESET, ""),
+                RESET
+            ));
+            copied = end;
+        }
+    }
+
+    painted.push_str(&line[copied..]);
+    painted
+}
+
+/// Shows the char at a screen column in reverse video, the text mode cursor. `after` is the
+/// color the rest of the text column goes back to
+fn paint_char(line: &str, column: usize, after: &str) -> String {
+    let start = column_index(line, column);
+    let end = column_index(line, column + 1);
+
+    format!(
+        "{}\x1b[7m{}{}{}{}",
+        &line[..start],
+        &line[start..end],
+        RESET,
+        after,
+        &line[end..]
+    )
+}
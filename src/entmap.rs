@@ -0,0 +1,137 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Entropy maps for `entmap`: the entropy of every block of a buffer, worked out with
+//! `Entropy` a chunk at a time so it runs as a background job, and drawn as a character per
+//! block. Packed and encrypted data stands out as a run of full blocks, zeros as dots
+
+use crate::{digest::Entropy, theme::RESET};
+
+/// What each level of the map is drawn as, from low entropy to high
+pub const GLYPHS: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// The entropy in bits per byte each level starts at, after the first
+pub const LEVELS: [f64; 4] = [1.0, 4.0, 6.0, 7.5];
+
+/// The colors of the levels with colors on, from blue to red
+const COLORS: [&str; 5] = ["\x1b[34m", "\x1b[36m", "\x1b[32m", "\x1b[33m", "\x1b[31m"];
+
+/// Columns of each row taken by its offset
+const OFFSET_WIDTH: usize = 10;
+
+/// The entropy of each block of the bytes fed so far
+#[derive(Clone)]
+pub struct EntropyMap {
+    block: usize,
+    /// The block being counted
+    current: Entropy,
+    /// Entropy of every block counted in full
+    blocks: Vec<f64>,
+    /// What the map is drawn with once it is done
+    width: usize,
+    colors: bool,
+}
+
+impl EntropyMap {
+    /// A map of `block` byte blocks, drawn `width` columns wide and in colors when `colors` is set
+    pub fn new(block: usize, width: usize, colors: bool) -> EntropyMap {
+        EntropyMap {
+            block: block.max(1),
+            current: Entropy::new(),
+            blocks: Vec::new(),
+            width,
+            colors,
+        }
+    }
+
+    /// Counts the bytes that follow the ones fed before, a block can span chunks
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let room = self.block - self.current.total() as usize;
+            let (piece, rest) = bytes.split_at(room.min(bytes.len()));
+            self.current.update(piece);
+            bytes = rest;
+
+            if self.current.total() as usize == self.block {
+                self.blocks.push(self.current.bits_per_byte());
+                self.current = Entropy::new();
+            }
+        }
+    }
+
+    /// The entropy of every block, the last one short if the bytes ran out in it
+    pub fn finish(mut self) -> Vec<f64> {
+        if self.current.total() > 0 {
+            self.blocks.push(self.current.bits_per_byte());
+        }
+        self.blocks
+    }
+
+    /// Finishes the map and draws it, see `render`
+    pub fn finish_lines(self) -> Vec<String> {
+        let (block, width, colors) = (self.block, self.width, self.colors);
+        render(&self.finish(), block, width, colors)
+    }
+}
+
+/// The level of an entropy, an index into `GLYPHS`
+pub fn level(bits: f64) -> usize {
+    LEVELS.iter().filter(|&&start| bits >= start).count()
+}
+
+/// How many blocks go in a row of a map `width` columns wide: a multiple of 16 when there is
+/// room for one, so the offsets down the side stay round
+pub fn blocks_per_row(width: usize) -> usize {
+    let room = width.saturating_sub(OFFSET_WIDTH).max(8);
+    match room / 16 {
+        0 => room,
+        rows => rows * 16,
+    }
+}
+
+/// The smallest block of at least 256 bytes, a power of two, that maps `len` bytes in no more
+/// than `rows` rows of a map `width` columns wide
+pub fn default_block(len: usize, width: usize, rows: usize) -> usize {
+    let cells = blocks_per_row(width) * rows.max(1);
+    len.div_ceil(cells).max(256).next_power_of_two()
+}
+
+/// Draws a map: a line about the blocks, a row of cells per `blocks_per_row` blocks starting
+/// with the offset of its first one, and a legend of the levels
+pub fn render(blocks: &[f64], block: usize, width: usize, colors: bool) -> Vec<String> {
+    let cell = |level: usize| match colors {
+        true => format!("{}{}{}", COLORS[level], GLYPHS[level], RESET),
+        false => GLYPHS[level].to_string(),
+    };
+
+    let mut lines = vec![format!(
+        "{} block{} of {:X} bytes, a character each. goto block <n> jumps to block n, from 0:",
+        blocks.len(),
+        if blocks.len() == 1 { "" } else { "s" },
+        block
+    )];
+
+    let per_row = blocks_per_row(width);
+    for (row, bits) in blocks.chunks(per_row).enumerate() {
+        let mut line = format!("{:08X}  ", row * per_row * block);
+        for &bits in bits {
+            line.push_str(&cell(level(bits)));
+        }
+        lines.push(line);
+    }
+
+    let mut legend = String::new();
+    for level in 0..GLYPHS.len() {
+        let range = match level {
+            0 => format!("under {}", LEVELS[0]),
+            4 => format!("{} to 8", LEVELS[3]),
+            _ => format!("{} to {}", LEVELS[level - 1], LEVELS[level]),
+        };
+        legend += &format!("{} {range}  ", cell(level));
+    }
+    lines.push(format!("{}bits per byte", legend));
+
+    lines
+}
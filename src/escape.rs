@@ -0,0 +1,68 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The escapes understood inside double quotes, one set of rules for every command that
+//! takes text: `\x41` for any byte and the usual `\n`, `\t`, `\r`, `\0`, `\\`, `\"` and `\'`
+
+/// Every escape with what it stands for, as `help syntax` lists them
+pub const ESCAPES: [(&str, &str); 8] = [
+    ("\\xHH", "the byte with hex value HH, like \\x00 or \\xFF"),
+    ("\\n", "newline, 0A"),
+    ("\\r", "carriage return, 0D"),
+    ("\\t", "tab, 09"),
+    ("\\0", "zero byte, 00"),
+    ("\\\\", "a backslash"),
+    ("\\\"", "a double quote"),
+    ("\\'", "a single quote"),
+];
+
+/// Reads the escape after a backslash, `after` is the text following it.
+/// Returns the byte it stands for and how many chars of `after` it took
+pub fn escape(after: &str) -> Result<(u8, usize), String> {
+    let mut chars = after.chars();
+
+    let byte = match chars.next() {
+        Some('x') => {
+            let digits = chars.take(2).collect::<String>();
+            return match u8::from_str_radix(&digits, 16) {
+                Ok(byte) if digits.len() == 2 => Ok((byte, 3)),
+                _ => Err(format!("invalid escape '\\x{}'", digits)),
+            };
+        }
+        Some('n') => b'\n',
+        Some('r') => b'\r',
+        Some('t') => b'\t',
+        Some('0') => 0,
+        Some('\\') => b'\\',
+        Some('"') => b'"',
+        Some('\'') => b'\'',
+        Some(other) => return Err(format!("invalid escape '\\{}'", other)),
+        None => return Err("nothing after the backslash".to_string()),
+    };
+
+    Ok((byte, 1))
+}
+
+/// Turns text with escapes into the bytes it stands for. An invalid escape is an error
+/// with its column (1-based), nothing is passed through as is
+pub fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.char_indices().enumerate();
+
+    while let Some((column, (index, c))) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+
+        let (byte, taken) =
+            escape(&text[index + 1..]).map_err(|e| format!("{} at column {}", e, column + 1))?;
+        bytes.push(byte);
+        chars.nth(taken - 1);
+    }
+
+    Ok(bytes)
+}
@@ -0,0 +1,335 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Reading files and devices into memory and writing edits back to them
+
+use std::{
+    fs::{self, File, Metadata, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::Path,
+    time::SystemTime,
+};
+
+/// What kind of thing a path points at, which decides how it can be read and saved
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    /// An ordinary file, saved by writing it out in full
+    Regular,
+    /// A block or character device like /dev/sdb1. Its size can't change, so it is only ever
+    /// saved by writing the changed bytes back in place
+    Device,
+    /// A pipe, FIFO or socket, read until it ends. There is nothing to save back to
+    Stream,
+}
+
+/// Default for LoadOptions::stream_limit, 256 MiB
+pub const DEFAULT_STREAM_LIMIT: u64 = 256 * 1024 * 1024;
+
+/// How to read a file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Bytes to read from a device, instead of asking it. Ignored for other files
+    pub size: Option<u64>,
+    /// Most bytes read from a stream before giving up, so a runaway pipe can't eat all the memory
+    pub stream_limit: u64,
+}
+
+impl Default for LoadOptions {
+    fn default() -> LoadOptions {
+        LoadOptions {
+            size: None,
+            stream_limit: DEFAULT_STREAM_LIMIT,
+        }
+    }
+}
+
+impl FileKind {
+    /// Works out the kind from a file's metadata
+    pub fn of(metadata: &Metadata) -> FileKind {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+
+            let file_type = metadata.file_type();
+            if file_type.is_block_device() || file_type.is_char_device() {
+                return FileKind::Device;
+            }
+            if file_type.is_fifo() || file_type.is_socket() {
+                return FileKind::Stream;
+            }
+        }
+
+        #[cfg(not(unix))]
+        let _ = metadata;
+
+        FileKind::Regular
+    }
+}
+
+/// Files up to this size get their contents hashed in a Fingerprint
+pub const HASH_LIMIT: u64 = 4 * 1024 * 1024;
+
+/// What a file on disk looked like at some point, to notice when something else rewrites it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// Size in bytes
+    pub len: u64,
+    /// Last modification time, None where the platform doesn't keep one
+    pub modified: Option<SystemTime>,
+    /// Hash of the contents, only for files up to HASH_LIMIT
+    pub hash: Option<u64>,
+}
+
+impl Fingerprint {
+    /// Takes the fingerprint of a file as it is now
+    pub fn of(path: &Path) -> io::Result<Fingerprint> {
+        let metadata = fs::metadata(path)?;
+
+        let hash = match metadata.len() <= HASH_LIMIT {
+            true => Some(hash_bytes(&fs::read(path)?)),
+            false => None,
+        };
+
+        Ok(Fingerprint {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            hash,
+        })
+    }
+
+    /// Whether two fingerprints are of the same contents. Small files are compared by their
+    /// hash, so a file that was only touched still counts as the same
+    pub fn same_contents(&self, other: &Fingerprint) -> bool {
+        match (self.hash, other.hash) {
+            _ if self.len != other.len => false,
+            (Some(hash), Some(other_hash)) => hash == other_hash,
+            _ => self.modified == other.modified,
+        }
+    }
+
+    /// Whether the file at path still has the same contents, false if it can't be read any more
+    pub fn still_matches(&self, path: &Path) -> bool {
+        Fingerprint::of(path).is_ok_and(|now| self.same_contents(&now))
+    }
+}
+
+/// 64-bit FNV-1a, quick and good enough to tell versions of a file apart
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// An advisory lock on a file, held until it is dropped. Only programs that lock the file
+/// themselves notice it, nothing stops a plain write
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Takes an exclusive lock without waiting for it, Ok(None) if another process holds one.
+    /// Where advisory locks aren't supported this always gets the lock
+    pub fn try_lock(path: &Path) -> io::Result<Option<FileLock>> {
+        let file = File::open(path)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::fd::AsRawFd;
+
+            // SAFETY: the descriptor belongs to `file`, which is open for as long as the call
+            let result = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+            if result != 0 {
+                let e = io::Error::last_os_error();
+                return match e.kind() {
+                    io::ErrorKind::WouldBlock => Ok(None),
+                    _ => Err(e),
+                };
+            }
+        }
+
+        // The lock goes with the descriptor, closing it when dropped releases the lock
+        Ok(Some(FileLock { _file: file }))
+    }
+}
+
+/// Reads a whole file into memory.
+///
+/// Devices don't report a size in their metadata, so the size in the options is used when given
+/// and otherwise the size is found by seeking to the end. Devices that can't seek, like
+/// /dev/urandom, need the size to be given. Streams are read until they end, or fail once
+/// they go past the stream limit
+pub fn load(path: &Path, options: &LoadOptions) -> io::Result<(Vec<u8>, FileKind)> {
+    let mut file = File::open(path)?;
+    let mut kind = FileKind::of(&file.metadata()?);
+
+    // Some platforms hand out pipes that look like regular files, they can't seek though
+    if kind == FileKind::Regular && file.stream_position().is_err() {
+        kind = FileKind::Stream;
+    }
+
+    let bytes = match kind {
+        FileKind::Regular => {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            bytes
+        }
+        FileKind::Device => {
+            let size = match options.size {
+                Some(size) => size,
+                None => {
+                    let end = file.seek(SeekFrom::End(0)).unwrap_or(0);
+                    file.seek(SeekFrom::Start(0))?;
+                    end
+                }
+            };
+
+            if size == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "can't tell the size of this device, give it with --size",
+                ));
+            }
+
+            let mut bytes = Vec::new();
+            file.take(size).read_to_end(&mut bytes)?;
+            bytes
+        }
+        FileKind::Stream => {
+            // One byte past the limit tells a stream that is exactly at the limit apart from a longer one
+            let mut bytes = Vec::new();
+            file.take(options.stream_limit.saturating_add(1))
+                .read_to_end(&mut bytes)?;
+
+            if bytes.len() as u64 > options.stream_limit {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "the stream is longer than the limit of {} bytes, raise stream-limit in the config",
+                        options.stream_limit
+                    ),
+                ));
+            }
+
+            bytes
+        }
+    };
+
+    Ok((bytes, kind))
+}
+
+/// Writes a whole buffer to a file, replacing what was there
+pub fn save(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    fs::write(path, bytes)
+}
+
+/// Writes a whole file so that it is either all there or not changed at all: the bytes go to a
+/// temporary file next to it first, which then takes its place. A crash in the middle leaves
+/// the old file as it was and at most the temporary one behind
+pub fn save_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = Path::new(&temp);
+
+    let result = File::create(temp).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    });
+
+    match result.and_then(|()| fs::rename(temp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(temp);
+            Err(e)
+        }
+    }
+}
+
+/// Writes bytes to a file that doesn't exist yet, or over one that does with `overwrite`.
+/// A file created here that couldn't be written in full is removed again, so a failed write
+/// doesn't leave half a file behind that looks like it worked
+pub fn save_new(path: &Path, bytes: &[u8], overwrite: bool) -> io::Result<()> {
+    if overwrite {
+        return save(path, bytes);
+    }
+
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(bytes).inspect_err(|_| {
+        let _ = fs::remove_file(path);
+    })
+}
+
+/// Writes only the given ranges of a buffer back to where they came from, without truncating
+/// or growing the file. Ranges are clamped to the buffer and may overlap
+pub fn save_in_place(path: &Path, bytes: &[u8], ranges: &[Range<usize>]) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+
+    for range in merge_ranges(ranges) {
+        let range = range.start.min(bytes.len())..range.end.min(bytes.len());
+        if range.is_empty() {
+            continue;
+        }
+
+        file.seek(SeekFrom::Start(range.start as u64))?;
+        file.write_all(&bytes[range])?;
+    }
+
+    // Character devices can't be synced, the writes have gone through by then anyway
+    match file.sync_all() {
+        Err(e) if e.kind() == io::ErrorKind::InvalidInput => Ok(()),
+        result => result,
+    }
+}
+
+/// The paths a partly typed one can be completed to, sorted: the entries of its directory whose
+/// names start with what follows the last `/`, with a `/` on the end of directories. Relative
+/// paths are taken from the current directory, and hidden entries are left out unless the name
+/// typed so far starts with a `.`
+pub fn complete_path(typed: &str) -> Vec<String> {
+    let (directory, name) = match typed.rfind('/') {
+        Some(slash) => typed.split_at(slash + 1),
+        None => ("", typed),
+    };
+    let Ok(entries) = fs::read_dir(if directory.is_empty() { "." } else { directory }) else {
+        return Vec::new();
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let file_name = entry.file_name().into_string().ok()?;
+            if !file_name.starts_with(name)
+                || (file_name.starts_with('.') && !name.starts_with('.'))
+            {
+                return None;
+            }
+            // Following links, so a link to a directory completes like one
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{directory}{file_name}{slash}"))
+        })
+        .collect::<Vec<String>>();
+    paths.sort_unstable();
+    paths
+}
+
+/// Sorts ranges and joins the ones that overlap or touch
+pub fn merge_ranges(ranges: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut sorted = ranges
+        .iter()
+        .filter(|range| !range.is_empty())
+        .cloned()
+        .collect::<Vec<Range<usize>>>();
+    sorted.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in sorted {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
@@ -0,0 +1,110 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Running bytes through a shell command, for `!` and `!!`. The command goes to the user's
+//! shell as it was typed, so pipes, quotes and globs work the way they do at a prompt
+
+use std::{
+    ffi::OsStr,
+    io::{self, Read, Write},
+    process::{Child, Command, Stdio},
+    thread,
+};
+
+/// The shell commands run in: $SHELL, or /bin/sh when it isn't set
+pub fn shell(var: Option<&OsStr>) -> String {
+    match var.map(|shell| shell.to_string_lossy().trim().to_string()) {
+        Some(shell) if !shell.is_empty() => shell,
+        _ => "/bin/sh".to_string(),
+    }
+}
+
+/// Runs `command` with `shell -c`, feeds it `input` and returns what it wrote to stdout.
+/// A command that exits with an error fails with what it wrote to stderr, and one that writes
+/// more than `limit` bytes is killed. Not reading all of its input isn't an error, like `head`
+pub fn run(shell: &str, command: &str, input: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(shell);
+    child
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // In a group of its own, so stopping it stops everything in its pipeline too
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut child, 0);
+
+    let mut child = child
+        .spawn()
+        .map_err(|e| format!("can't run {shell}: {e}"))?;
+
+    let (Some(mut stdin), Some(stdout), Some(mut stderr)) =
+        (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    else {
+        return Err("can't talk to the command".to_string());
+    };
+
+    // Writing and reading at once, a command that outputs as it reads would fill its stdout
+    // pipe and never take the rest of its input otherwise
+    let (output, errors) = thread::scope(|scope| {
+        scope.spawn(move || match stdin.write_all(input) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e),
+            _ => Ok(()),
+        });
+        let errors = scope.spawn(move || {
+            let mut errors = Vec::new();
+            stderr.read_to_end(&mut errors).map(|_| errors)
+        });
+
+        let mut output = Vec::new();
+        let read = stdout
+            .take(limit as u64 + 1)
+            .read_to_end(&mut output)
+            .map(|_| output);
+
+        // Killing it closes its end of stderr too, which the thread reading it waits on
+        if !read.as_ref().is_ok_and(|output| output.len() <= limit) {
+            kill(&mut child);
+        }
+
+        (read, errors.join().unwrap_or_else(|_| Ok(Vec::new())))
+    });
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    let output = output.map_err(|e| format!("can't read the output: {e}"))?;
+
+    if output.len() > limit {
+        return Err(format!("the output is over {limit:X} bytes, stopped it"));
+    }
+
+    if !status.success() {
+        let errors = errors.unwrap_or_default();
+        let errors = String::from_utf8_lossy(&errors);
+        let errors = errors.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let status = match status.code() {
+            Some(code) => format!("exited with {code}"),
+            None => "was killed".to_string(),
+        };
+
+        return Err(match errors.is_empty() {
+            true => status,
+            false => format!("{status}: {errors}"),
+        });
+    }
+
+    Ok(output)
+}
+
+/// Stops a command and whatever it started
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    // SAFETY: only sends a signal, to the group the child leads
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+
+    let _ = child.kill();
+}
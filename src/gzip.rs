@@ -0,0 +1,160 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! gzip files (RFC 1952), so compressed files can be viewed and written back. This reads and
+//! writes the gzip header and trailer, the deflate data (RFC 1951) in between goes through
+//! `miniz_oxide`
+
+use crate::checksum::crc32;
+use miniz_oxide::{
+    deflate::compress_to_vec,
+    inflate::stream::{inflate as inflate_stream, InflateState},
+    DataFormat, MZError, MZFlush, MZStatus,
+};
+
+/// The two bytes every gzip file starts with
+pub const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// Header flags, which optional fields follow the fixed part of the header
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+/// How hard deflate tries, the level `gzip` uses by default
+const LEVEL: u8 = 6;
+
+/// Bytes inflated at a time before checking the limit
+const INFLATE_CHUNK: usize = 32 * 1024;
+
+/// Whether bytes start like a gzip file
+pub fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&GZIP_MAGIC)
+}
+
+/// Inflates a raw deflate stream onto `out`, stopping with an error once `out` would grow past
+/// `limit` bytes. Returns how many bytes of `data` the stream took
+pub fn inflate(data: &[u8], out: &mut Vec<u8>, limit: usize) -> Result<usize, String> {
+    let mut state = InflateState::new_boxed(DataFormat::Raw);
+    let mut chunk = vec![0; INFLATE_CHUNK];
+    let mut consumed = 0;
+
+    loop {
+        let result = inflate_stream(&mut state, &data[consumed..], &mut chunk, MZFlush::None);
+        consumed += result.bytes_consumed;
+
+        if out.len() + result.bytes_written > limit {
+            return Err(format!("it inflates to more than {:X} bytes", limit));
+        }
+        out.extend_from_slice(&chunk[..result.bytes_written]);
+
+        match result.status {
+            Ok(MZStatus::StreamEnd) => return Ok(consumed),
+            // Nothing left to read and nothing more came out, the stream needs more data
+            Ok(_) if result.bytes_consumed == 0 && result.bytes_written == 0 => {
+                return Err("the data ends in the middle".to_string())
+            }
+            Ok(_) => {}
+            Err(MZError::Buf) => return Err("the data ends in the middle".to_string()),
+            Err(_) => return Err("the deflate data is invalid".to_string()),
+        }
+    }
+}
+
+/// The length of a gzip member's header, checking it is one deflate data follows
+fn header_length(bytes: &[u8]) -> Result<usize, String> {
+    let truncated = || "the header ends in the middle".to_string();
+    if !is_gzip(bytes) {
+        return Err("not gzip data".to_string());
+    }
+
+    let header = bytes.get(..10).ok_or_else(truncated)?;
+    if header[2] != 8 {
+        return Err(format!("unknown compression method {}", header[2]));
+    }
+
+    let flags = header[3];
+    let mut length = 10;
+
+    if flags & FEXTRA != 0 {
+        let extra = bytes.get(length..length + 2).ok_or_else(truncated)?;
+        length += 2 + u16::from_le_bytes([extra[0], extra[1]]) as usize;
+    }
+
+    // The name and the comment both end with a NUL
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let rest = bytes.get(length..).ok_or_else(truncated)?;
+            length += rest
+                .iter()
+                .position(|&byte| byte == 0)
+                .ok_or_else(truncated)?
+                + 1;
+        }
+    }
+
+    if flags & FHCRC != 0 {
+        length += 2;
+    }
+
+    match length <= bytes.len() {
+        true => Ok(length),
+        false => Err(truncated()),
+    }
+}
+
+/// Decompresses a gzip file, members that follow each other are joined like `gzip -d` does.
+/// Each member's CRC and length are checked, and inflating more than `limit` bytes is an error
+/// so a small file can't fill up memory
+pub fn decompress(bytes: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+
+    loop {
+        let data = pos + header_length(&bytes[pos..]).map_err(|e| format!("{} at {:X}", e, pos))?;
+
+        let start = out.len();
+        let end = data
+            + inflate(&bytes[data..], &mut out, limit)
+                .map_err(|e| format!("corrupt data in the member at {:X}: {}", pos, e))?;
+
+        let trailer = bytes
+            .get(end..end + 8)
+            .ok_or_else(|| format!("the member at {:X} is cut short", pos))?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+
+        if crc != crc32(&out[start..]) {
+            return Err(format!("the CRC of the member at {:X} doesn't match", pos));
+        }
+        if size != (out.len() - start) as u32 {
+            return Err(format!(
+                "the length of the member at {:X} doesn't match",
+                pos
+            ));
+        }
+
+        pos = end + 8;
+
+        // Anything but another member after the last one is padding some tools add
+        if !is_gzip(&bytes[pos..]) {
+            return Ok(out);
+        }
+    }
+}
+
+/// Compresses bytes into a raw deflate stream
+pub fn deflate(bytes: &[u8]) -> Vec<u8> {
+    compress_to_vec(bytes, LEVEL)
+}
+
+/// Compresses bytes into a gzip file with a bare header: no name, no time, OS unknown
+pub fn compress(bytes: &[u8]) -> Vec<u8> {
+    let mut out = vec![GZIP_MAGIC[0], GZIP_MAGIC[1], 8, 0, 0, 0, 0, 0, 0, 0xFF];
+    out.extend_from_slice(&deflate(bytes));
+    out.extend_from_slice(&crc32(bytes).to_le_bytes());
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out
+}
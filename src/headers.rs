@@ -0,0 +1,565 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Just enough of ELF and PE executables to say where their headers, segments and sections
+//! are in the file, for `headers`. Damaged headers give whatever could still be read, with
+//! warnings about the rest
+
+use crate::template::Endian;
+
+/// What an entry of the listing is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// One of the header structures themselves, like the ELF header or a section table
+    Header,
+    /// An ELF program header, what gets loaded
+    Segment,
+    /// An ELF or PE section
+    Section,
+}
+
+impl Kind {
+    /// How the kind is written in the listing
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Header => "header",
+            Kind::Segment => "segment",
+            Kind::Section => "section",
+        }
+    }
+}
+
+/// A header, segment or section and where its bytes are in the file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// What it is
+    pub kind: Kind,
+    /// Like .text, LOAD or ELF header, empty for a section without a name
+    pub name: String,
+    /// Where its bytes start in the file
+    pub offset: u64,
+    /// How many bytes of the file it covers, 0 for ones that only take up memory like .bss
+    pub size: u64,
+    /// Permissions like r-x, and for ELF sections their flags like AX
+    pub flags: String,
+}
+
+/// What `parse` found in the headers of an executable
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Headers {
+    /// What the file is, like "ELF 64-bit little endian shared object for x86-64"
+    pub format: String,
+    /// Everything found, in the order of the headers
+    pub entries: Vec<Entry>,
+    /// What was wrong with the headers, the entries leave those parts out
+    pub warnings: Vec<String>,
+}
+
+/// The headers of an ELF or MZ/PE file, None when the bytes start with neither signature
+pub fn parse(bytes: &[u8]) -> Option<Headers> {
+    let mut headers = if bytes.starts_with(b"\x7FELF") {
+        elf(bytes)
+    } else if bytes.starts_with(b"MZ") {
+        pe(bytes)
+    } else {
+        return None;
+    };
+
+    for entry in &headers.entries {
+        if entry.offset.saturating_add(entry.size) > bytes.len() as u64 {
+            headers.warnings.push(format!(
+                "{} {} at {:X} runs past the end of the file",
+                entry.kind.name(),
+                entry.name,
+                entry.offset
+            ));
+        }
+    }
+
+    Some(headers)
+}
+
+/// Reads numbers out of the file, None for ones that run past its end
+struct Reader<'a> {
+    bytes: &'a [u8],
+    endian: Endian,
+}
+
+impl Reader<'_> {
+    fn uint(&self, offset: u64, width: usize) -> Option<u64> {
+        let start = usize::try_from(offset).ok()?;
+        let bytes = self.bytes.get(start..start.checked_add(width)?)?;
+
+        let fold = |value: u64, byte: &u8| value << 8 | u64::from(*byte);
+        Some(match self.endian {
+            Endian::Little => bytes.iter().rev().fold(0, fold),
+            Endian::Big => bytes.iter().fold(0, fold),
+        })
+    }
+
+    fn u16(&self, offset: u64) -> Option<u64> {
+        self.uint(offset, 2)
+    }
+
+    fn u32(&self, offset: u64) -> Option<u64> {
+        self.uint(offset, 4)
+    }
+
+    /// An address or offset, 8 bytes in a 64-bit ELF file and 4 in a 32-bit one
+    fn word(&self, offset: u64, wide: bool) -> Option<u64> {
+        self.uint(offset, if wide { 8 } else { 4 })
+    }
+
+    /// The NUL terminated string at an offset, cut off at `end`
+    fn string(&self, offset: u64, end: u64) -> Option<String> {
+        let start = usize::try_from(offset).ok()?;
+        let end = usize::try_from(end)
+            .unwrap_or(usize::MAX)
+            .min(self.bytes.len());
+        let bytes = self.bytes.get(start..end)?;
+        let bytes = bytes.split(|byte| *byte == 0).next().unwrap_or_default();
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// Flags written like ls does, r, w and x or a dash for each
+fn permissions(read: bool, write: bool, execute: bool) -> String {
+    [(read, 'r'), (write, 'w'), (execute, 'x')]
+        .iter()
+        .map(|(set, flag)| if *set { *flag } else { '-' })
+        .collect()
+}
+
+fn header(name: &str, offset: u64, size: u64) -> Entry {
+    Entry {
+        kind: Kind::Header,
+        name: name.to_string(),
+        offset,
+        size,
+        flags: String::new(),
+    }
+}
+
+/// The ELF header, then the program header table and its segments, then the section header
+/// table and its sections
+fn elf(bytes: &[u8]) -> Headers {
+    let mut headers = Headers {
+        format: "ELF".to_string(),
+        ..Headers::default()
+    };
+
+    let (wide, bits) = match bytes.get(4) {
+        Some(1) => (false, 32),
+        Some(2) => (true, 64),
+        Some(class) => {
+            headers
+                .warnings
+                .push(format!("unknown ELF class {class:X}"));
+            return headers;
+        }
+        None => {
+            headers
+                .warnings
+                .push("the ELF header is cut short".to_string());
+            return headers;
+        }
+    };
+    let endian = match bytes.get(5) {
+        Some(1) => Endian::Little,
+        Some(2) => Endian::Big,
+        Some(data) => {
+            headers
+                .warnings
+                .push(format!("unknown ELF byte order {data:X}"));
+            return headers;
+        }
+        None => {
+            headers
+                .warnings
+                .push("the ELF header is cut short".to_string());
+            return headers;
+        }
+    };
+    headers.format = format!(
+        "ELF {bits}-bit {} endian",
+        if endian == Endian::Little {
+            "little"
+        } else {
+            "big"
+        }
+    );
+
+    // Where a field is depends on the class, the offsets are (32-bit, 64-bit)
+    let at = |(narrow, wide_at): (u64, u64)| if wide { wide_at } else { narrow };
+    let size = at((52, 64));
+    let reader = Reader { bytes, endian };
+    if (bytes.len() as u64) < size {
+        headers
+            .warnings
+            .push("the ELF header is cut short".to_string());
+        return headers;
+    }
+
+    let field = |offset| reader.u16(at(offset)).unwrap_or_default();
+    let file_type = match field((16, 16)) {
+        1 => "relocatable".to_string(),
+        2 => "executable".to_string(),
+        3 => "shared object".to_string(),
+        4 => "core file".to_string(),
+        other => format!("file of type {other:X}"),
+    };
+    headers.format += &format!(" {file_type} for {}", elf_machine(field((18, 18))));
+    headers.entries.push(header("ELF header", 0, size));
+
+    let program_offset = reader.word(at((28, 32)), wide).unwrap_or_default();
+    let program_size = field((42, 54));
+    let program_count = field((44, 56));
+    if program_count > 0 {
+        if program_size < at((32, 56)) {
+            headers.warnings.push(format!(
+                "program headers of {program_size:X} bytes are too small, left them out"
+            ));
+        } else {
+            headers.entries.push(header(
+                "program headers",
+                program_offset,
+                program_count * program_size,
+            ));
+
+            for index in 0..program_count {
+                let offset = program_offset.saturating_add(index * program_size);
+                match elf_segment(&reader, offset, wide) {
+                    Some(segment) => headers.entries.push(segment),
+                    None => {
+                        headers.warnings.push(format!(
+                            "program header {index} at {offset:X} runs past the end of the file"
+                        ));
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    let section_offset = reader.word(at((32, 40)), wide).unwrap_or_default();
+    let section_size = field((46, 58));
+    let mut section_count = field((48, 60));
+    let mut names_index = field((50, 62));
+    if section_offset == 0 {
+        return headers;
+    }
+    if section_size < at((40, 64)) {
+        headers.warnings.push(format!(
+            "section headers of {section_size:X} bytes are too small, left them out"
+        ));
+        return headers;
+    }
+
+    // With too many sections for the ELF header, the first section header holds the count and
+    // the index of the names
+    if section_count == 0 {
+        section_count = reader
+            .word(section_offset.saturating_add(at((20, 32))), wide)
+            .unwrap_or_default();
+    }
+    if names_index == 0xFFFF {
+        names_index = reader
+            .u32(section_offset.saturating_add(at((24, 40))))
+            .unwrap_or_default();
+    }
+
+    // The section headers are in the file at most, which also stops a bogus count early
+    let section_count = section_count.min(bytes.len() as u64 / section_size + 1);
+    headers.entries.push(header(
+        "section headers",
+        section_offset,
+        section_count * section_size,
+    ));
+
+    let mut sections = Vec::new();
+    for index in 0..section_count {
+        let offset = section_offset.saturating_add(index * section_size);
+        match elf_section(&reader, offset, wide) {
+            Some(section) => sections.push(section),
+            None => {
+                headers.warnings.push(format!(
+                    "section header {index} at {offset:X} runs past the end of the file"
+                ));
+                break;
+            }
+        }
+    }
+
+    // Section names are offsets into one of the sections
+    let names = match sections.get(names_index as usize) {
+        Some((_, _, entry)) if names_index != 0 => {
+            Some((entry.offset, entry.offset.saturating_add(entry.size)))
+        }
+        _ if names_index == 0 => None,
+        _ => {
+            headers.warnings.push(format!(
+                "the section names are in section {names_index}, which isn't there"
+            ));
+            None
+        }
+    };
+
+    for (name, kind, mut entry) in sections {
+        // Like the first one, which is always empty
+        if kind == 0 {
+            continue;
+        }
+
+        entry.name = match names {
+            Some((start, end)) => reader
+                .string(start.saturating_add(name), end)
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+        headers.entries.push(entry);
+    }
+
+    headers
+}
+
+/// A program header, None when it runs past the end of the file
+fn elf_segment(reader: &Reader, offset: u64, wide: bool) -> Option<Entry> {
+    if offset > reader.bytes.len() as u64 {
+        return None;
+    }
+    let kind = reader.u32(offset)?;
+    let (flags, file_offset, size) = if wide {
+        (
+            reader.u32(offset + 4)?,
+            reader.word(offset + 8, true)?,
+            reader.word(offset + 32, true)?,
+        )
+    } else {
+        (
+            reader.u32(offset + 24)?,
+            reader.u32(offset + 4)?,
+            reader.u32(offset + 16)?,
+        )
+    };
+
+    let name = match kind {
+        0 => "NULL".to_string(),
+        1 => "LOAD".to_string(),
+        2 => "DYNAMIC".to_string(),
+        3 => "INTERP".to_string(),
+        4 => "NOTE".to_string(),
+        5 => "SHLIB".to_string(),
+        6 => "PHDR".to_string(),
+        7 => "TLS".to_string(),
+        0x6474E550 => "GNU_EH_FRAME".to_string(),
+        0x6474E551 => "GNU_STACK".to_string(),
+        0x6474E552 => "GNU_RELRO".to_string(),
+        0x6474E553 => "GNU_PROPERTY".to_string(),
+        other => format!("type {other:X}"),
+    };
+
+    Some(Entry {
+        kind: Kind::Segment,
+        name,
+        offset: file_offset,
+        size,
+        flags: permissions(flags & 4 != 0, flags & 2 != 0, flags & 1 != 0),
+    })
+}
+
+/// A section header with the offset of its name and its type, None when it runs past the end of
+/// the file. The name is left empty until it is looked up
+fn elf_section(reader: &Reader, offset: u64, wide: bool) -> Option<(u64, u64, Entry)> {
+    if offset > reader.bytes.len() as u64 {
+        return None;
+    }
+    let name = reader.u32(offset)?;
+    let kind = reader.u32(offset + 4)?;
+    let flags = reader.word(offset + 8, wide)?;
+    let (file_offset, size) = if wide {
+        (
+            reader.word(offset + 24, true)?,
+            reader.word(offset + 32, true)?,
+        )
+    } else {
+        (reader.u32(offset + 16)?, reader.u32(offset + 20)?)
+    };
+
+    // Flags the way readelf writes them
+    let letters = [
+        (1, 'W'),
+        (2, 'A'),
+        (4, 'X'),
+        (0x10, 'M'),
+        (0x20, 'S'),
+        (0x40, 'I'),
+        (0x80, 'L'),
+        (0x200, 'G'),
+        (0x400, 'T'),
+    ];
+    let flags = letters
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, letter)| *letter)
+        .collect();
+
+    Some((
+        name,
+        kind,
+        Entry {
+            kind: Kind::Section,
+            name: String::new(),
+            offset: file_offset,
+            // Sections like .bss take up memory but no bytes of the file
+            size: if kind == 8 { 0 } else { size },
+            flags,
+        },
+    ))
+}
+
+fn elf_machine(machine: u64) -> String {
+    match machine {
+        3 => "x86".to_string(),
+        8 => "MIPS".to_string(),
+        0x14 => "PowerPC".to_string(),
+        0x15 => "PowerPC64".to_string(),
+        0x28 => "ARM".to_string(),
+        0x3E => "x86-64".to_string(),
+        0xB7 => "AArch64".to_string(),
+        0xF3 => "RISC-V".to_string(),
+        other => format!("machine {other:X}"),
+    }
+}
+
+/// The DOS header, then the PE signature it points to, the COFF and optional headers and the
+/// section table. A DOS program without a PE header ends after the DOS header
+fn pe(bytes: &[u8]) -> Headers {
+    let mut headers = Headers {
+        format: "MZ executable".to_string(),
+        ..Headers::default()
+    };
+    let reader = Reader {
+        bytes,
+        endian: Endian::Little,
+    };
+
+    let Some(pe_offset) = reader.u32(0x3C) else {
+        headers
+            .warnings
+            .push("the DOS header is cut short".to_string());
+        return headers;
+    };
+    headers.entries.push(header("DOS header", 0, 0x40));
+
+    if reader.u32(pe_offset) != Some(0x4550) {
+        headers.format = "DOS MZ executable".to_string();
+        return headers;
+    }
+
+    let coff = pe_offset + 4;
+    let (Some(machine), Some(section_count), Some(optional_size), Some(characteristics)) = (
+        reader.u16(coff),
+        reader.u16(coff + 2),
+        reader.u16(coff + 16),
+        reader.u16(coff + 18),
+    ) else {
+        headers.format = "PE".to_string();
+        headers
+            .warnings
+            .push(format!("the COFF header at {coff:X} is cut short"));
+        return headers;
+    };
+    headers.entries.push(header("COFF header", coff, 20));
+
+    let optional = coff + 20;
+    let format = match optional_size {
+        0 => "PE".to_string(),
+        _ => {
+            headers
+                .entries
+                .push(header("optional header", optional, optional_size));
+            match reader.u16(optional) {
+                Some(0x10B) => "PE32".to_string(),
+                Some(0x20B) => "PE32+".to_string(),
+                Some(0x107) => "PE ROM image".to_string(),
+                Some(magic) => {
+                    headers
+                        .warnings
+                        .push(format!("unknown optional header magic {magic:X}"));
+                    "PE".to_string()
+                }
+                None => "PE".to_string(),
+            }
+        }
+    };
+    let file_type = if characteristics & 0x2000 != 0 {
+        "DLL"
+    } else {
+        "executable"
+    };
+    headers.format = format!("{format} {file_type} for {}", pe_machine(machine));
+
+    let table = optional + optional_size;
+    headers
+        .entries
+        .push(header("section table", table, section_count * 40));
+
+    for index in 0..section_count {
+        let offset = table + index * 40;
+        match pe_section(&reader, offset) {
+            Some(section) => headers.entries.push(section),
+            None => {
+                headers.warnings.push(format!(
+                    "section header {index} at {offset:X} runs past the end of the file"
+                ));
+                break;
+            }
+        }
+    }
+
+    headers
+}
+
+/// A section header of a PE file, None when it runs past the end of the file
+fn pe_section(reader: &Reader, offset: u64) -> Option<Entry> {
+    let start = usize::try_from(offset).ok()?;
+    let name = reader.bytes.get(start..start.checked_add(8)?)?;
+    let name = name.split(|byte| *byte == 0).next().unwrap_or_default();
+    let size = reader.u32(offset + 16)?;
+    let file_offset = reader.u32(offset + 20)?;
+    let characteristics = reader.u32(offset + 36)?;
+
+    let mut flags = permissions(
+        characteristics & 0x40000000 != 0,
+        characteristics & 0x80000000 != 0,
+        characteristics & 0x20000000 != 0,
+    );
+    for (bit, what) in [(0x20, "code"), (0x40, "data"), (0x80, "bss")] {
+        if characteristics & bit != 0 {
+            flags += " ";
+            flags += what;
+        }
+    }
+
+    Some(Entry {
+        kind: Kind::Section,
+        name: String::from_utf8_lossy(name).into_owned(),
+        offset: file_offset,
+        size,
+        flags,
+    })
+}
+
+fn pe_machine(machine: u64) -> String {
+    match machine {
+        0x14C => "x86".to_string(),
+        0x8664 => "x86-64".to_string(),
+        0x1C0 => "ARM".to_string(),
+        0x1C4 => "ARM Thumb-2".to_string(),
+        0xAA64 => "ARM64".to_string(),
+        0x200 => "Itanium".to_string(),
+        other => format!("machine {other:X}"),
+    }
+}
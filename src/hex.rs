@@ -0,0 +1,77 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Converting between bytes and the hex text the dump shows
+
+/// Hex digits by value, for writing bytes without going through format!
+const UPPER_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+const LOWER_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Appends the two uppercase hex digits of a byte
+pub fn push_hex(out: &mut String, byte: u8) {
+    out.push(UPPER_DIGITS[usize::from(byte >> 4)] as char);
+    out.push(UPPER_DIGITS[usize::from(byte & 0xF)] as char);
+}
+
+/// Appends the two lowercase hex digits of a byte
+pub fn push_hex_lower(out: &mut String, byte: u8) {
+    out.push(LOWER_DIGITS[usize::from(byte >> 4)] as char);
+    out.push(LOWER_DIGITS[usize::from(byte & 0xF)] as char);
+}
+
+/// Parses hex digits into bytes, two digits per byte.
+/// Whitespace between the digits is skipped, so the output of bytes_to_hex reads back as is.
+/// Returns None on anything that isn't a hex digit or if a digit is left over
+pub fn hex_to_bytes(hex_string: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut byte = 0;
+    let mut nibble_count = 0;
+
+    // Iterate over each char
+    for c in hex_string.chars().filter(|c| !c.is_whitespace()) {
+        // Convert each char to a 4-bit integer (a nibble)
+        let nibble = match c.to_digit(16) {
+            // If the character is a valid, convert it to a u8
+            Some(nibble) => nibble as u8,
+            None => return None,
+        };
+
+        // Shift the current byte left by 4 bits and OR it with the nibble
+        byte = (byte << 4) | nibble;
+
+        // Increment the nibble count
+        nibble_count += 1;
+
+        // If we've processed two nibbles, push the byte to the output vector
+        // and reset the byte and nibble count
+        if nibble_count == 2 {
+            bytes.push(byte);
+            byte = 0;
+            nibble_count = 0;
+        }
+    }
+
+    // If there are any remaining nibbles, the input is invalid, return None
+    if nibble_count != 0 {
+        None
+    } else {
+        // Otherwise, return the output vector of bytes
+        Some(bytes)
+    }
+}
+
+/// Writes bytes as uppercase hex pairs separated by spaces, like "48 65 6C"
+pub fn bytes_to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 3);
+
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            hex.push(' ');
+        }
+        push_hex(&mut hex, *byte);
+    }
+
+    hex
+}
@@ -0,0 +1,117 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Bar charts of how often byte values come up, for `histogram`. The counting is `Entropy`'s,
+//! this groups the counts into buckets or picks the most common values and draws a bar for
+//! each, scaled so the biggest fills the width
+
+/// Block characters for eighths of a cell, the bar ends with one of them
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// A bar never gets narrower than this, however narrow the terminal
+const MIN_BAR_WIDTH: usize = 8;
+
+/// One row of the chart
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Row {
+    /// What the row counts, like 00-0F or 41 'A'
+    pub label: String,
+    /// How many bytes it counts
+    pub count: u64,
+}
+
+/// Groups the counts of the 256 byte values into `buckets` ranges as even as they can be,
+/// from 1 to 256 of them
+pub fn buckets(counts: &[u64; 256], buckets: usize) -> Vec<Row> {
+    let buckets = buckets.clamp(1, 256);
+
+    (0..buckets)
+        .map(|bucket| {
+            let first = bucket * 256 / buckets;
+            let last = (bucket + 1) * 256 / buckets - 1;
+            let label = match first == last {
+                true => format!("{:02X}", first),
+                false => format!("{:02X}-{:02X}", first, last),
+            };
+            Row {
+                label,
+                count: counts[first..=last].iter().sum(),
+            }
+        })
+        .collect()
+}
+
+/// The `top` most common byte values that come up at all, the most common first and lower
+/// values first among equals. Printable ones show their character
+pub fn top_values(counts: &[u64; 256], top: usize) -> Vec<Row> {
+    let mut values = (0..=255u8)
+        .filter(|&value| counts[usize::from(value)] > 0)
+        .collect::<Vec<u8>>();
+    values.sort_by_key(|&value| std::cmp::Reverse(counts[usize::from(value)]));
+    values.truncate(top);
+
+    values
+        .into_iter()
+        .map(|value| Row {
+            label: match value {
+                b' '..=b'~' => format!("{:02X} '{}'", value, char::from(value)),
+                _ => format!("{:02X}", value),
+            },
+            count: counts[usize::from(value)],
+        })
+        .collect()
+}
+
+/// Draws the rows as lines of `width` columns: label, count, share of `total` and a bar. Bars
+/// are scaled to the biggest count, with no bar at all when every count is 0
+pub fn render(rows: &[Row], total: u64, width: usize) -> Vec<String> {
+    let label_width = rows.iter().map(|row| row.label.len()).max().unwrap_or(0);
+    let count_width = rows
+        .iter()
+        .map(|row| row.count.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let max = rows.iter().map(|row| row.count).max().unwrap_or(0);
+
+    // Label, count and a percentage like 100.0%, with two spaces between them and the bar
+    let text_width = label_width + 2 + count_width + 2 + 6 + 2;
+    let bar_width = width.saturating_sub(text_width + 1).max(MIN_BAR_WIDTH);
+
+    rows.iter()
+        .map(|row| {
+            let percent = match total {
+                0 => 0.0,
+                total => row.count as f64 * 100.0 / total as f64,
+            };
+            let line = format!(
+                "{:<label_width$}  {:>count_width$}  {:>5.1}%  {}",
+                row.label,
+                row.count,
+                percent,
+                bar(row.count, max, bar_width)
+            );
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// A bar of `count` against `max` that is `width` cells at most, in eighths of a cell
+fn bar(count: u64, max: u64, width: usize) -> String {
+    if max == 0 {
+        return String::new();
+    }
+
+    let eighths = (u128::from(count) * width as u128 * 8 / u128::from(max)) as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    let rest = eighths % 8;
+    if rest > 0 {
+        bar.push(EIGHTHS[rest]);
+    }
+    // Anything at all shows, even next to a count far bigger
+    if bar.is_empty() && count > 0 {
+        bar.push(EIGHTHS[1]);
+    }
+    bar
+}
@@ -0,0 +1,156 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The bytes at the cursor read as numbers, for `inspect` and the inspector line. Nothing here
+//! looks at more than 8 bytes, so it can run on every cursor move
+
+use crate::template::Endian;
+use std::fmt::{Display, LowerExp};
+
+/// Most bytes any number is read from
+pub const MAX_WIDTH: usize = 8;
+
+/// A type the bytes at the cursor can be read as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Number {
+    /// Unsigned, this many bytes
+    Unsigned(usize),
+    /// Two's complement, this many bytes
+    Signed(usize),
+    /// IEEE 754 single precision
+    F32,
+    /// IEEE 754 double precision
+    F64,
+}
+
+/// Every type `inspect` shows, in order
+pub const NUMBERS: [Number; 10] = [
+    Number::Unsigned(1),
+    Number::Signed(1),
+    Number::Unsigned(2),
+    Number::Signed(2),
+    Number::Unsigned(4),
+    Number::Signed(4),
+    Number::Unsigned(8),
+    Number::Signed(8),
+    Number::F32,
+    Number::F64,
+];
+
+/// The types on the inspector line, the ones at the end go first when it doesn't fit
+pub const SUMMARY: [Number; 5] = [
+    Number::Unsigned(1),
+    Number::Unsigned(2),
+    Number::Unsigned(4),
+    Number::Signed(4),
+    Number::F32,
+];
+
+impl Number {
+    /// How the type is written, like u16 or f32
+    pub fn name(self) -> String {
+        match self {
+            Number::Unsigned(width) => format!("u{}", width * 8),
+            Number::Signed(width) => format!("i{}", width * 8),
+            Number::F32 => "f32".to_string(),
+            Number::F64 => "f64".to_string(),
+        }
+    }
+
+    /// Bytes the type is read from
+    pub fn width(self) -> usize {
+        match self {
+            Number::Unsigned(width) | Number::Signed(width) => width,
+            Number::F32 => 4,
+            Number::F64 => 8,
+        }
+    }
+
+    /// The value of the first bytes, None when there are fewer than the type needs
+    pub fn decode(self, bytes: &[u8], endian: Endian) -> Option<String> {
+        let width = self.width();
+        let bytes = bytes.get(..width)?;
+
+        // The bytes as a little endian u64, signed values get sign extended from their top bit
+        let mut raw = [0; 8];
+        match endian {
+            Endian::Little => raw[..width].copy_from_slice(bytes),
+            Endian::Big => {
+                for (to, from) in raw[..width].iter_mut().zip(bytes.iter().rev()) {
+                    *to = *from;
+                }
+            }
+        }
+        let unsigned = u64::from_le_bytes(raw);
+        let shift = 64 - width as u32 * 8;
+
+        Some(match self {
+            Number::Unsigned(_) => unsigned.to_string(),
+            Number::Signed(_) => (((unsigned << shift) as i64) >> shift).to_string(),
+            Number::F32 => format_float(f32::from_bits(unsigned as u32)),
+            Number::F64 => format_float(f64::from_bits(unsigned)),
+        })
+    }
+}
+
+/// A float short enough for one line: plain when that is readable, with an exponent otherwise.
+/// An f32 is written with the digits an f32 needs, not those of the f64 it widens to
+pub fn format_float<F: Into<f64> + Display + LowerExp + Copy>(value: F) -> String {
+    let wide: f64 = value.into();
+    let magnitude = wide.abs();
+
+    if wide == 0.0 || !wide.is_finite() || (1e-4..1e9).contains(&magnitude) {
+        format!("{}", value)
+    } else {
+        format!("{:e}", value)
+    }
+}
+
+/// The lines `inspect` shows for the bytes at an offset, `bytes` being the ones from there on.
+/// Types that run past the end of the buffer are left out
+pub fn table(offset: usize, bytes: &[u8]) -> Vec<String> {
+    let mut lines = vec![
+        format!("Offset {:08X} ({})", offset, offset),
+        format!("{:<6}{:<26}{}", "type", "little endian", "big endian"),
+    ];
+
+    for number in NUMBERS {
+        let (Some(little), Some(big)) = (
+            number.decode(bytes, Endian::Little),
+            number.decode(bytes, Endian::Big),
+        ) else {
+            continue;
+        };
+
+        lines.push(match number.width() {
+            // One byte reads the same either way
+            1 => format!("{:<6}{}", number.name(), little),
+            _ => format!("{:<6}{:<26}{}", number.name(), little, big),
+        });
+    }
+
+    lines
+}
+
+/// The inspector line for the bytes at an offset, little endian, at most `width` chars wide.
+/// The offset always comes first, then as many of the `SUMMARY` types as fit. Types that run
+/// past the end of the buffer show a dash
+pub fn summary(offset: usize, bytes: &[u8], width: usize) -> String {
+    let mut line = format!("@{:X} ({})", offset, offset);
+
+    for number in SUMMARY {
+        let value = number
+            .decode(bytes, Endian::Little)
+            .unwrap_or_else(|| "-".to_string());
+        let part = format!("  {} {}", number.name(), value);
+
+        if line.chars().count() + part.chars().count() > width {
+            break;
+        }
+        line += &part;
+    }
+
+    line
+}
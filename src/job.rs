@@ -0,0 +1,261 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Read-only work over a whole buffer, like hashing it, run on a worker thread so the editor
+//! stays responsive. The job reads a snapshot of the buffer a chunk at a time, reports how far it
+//! got over a channel and checks between chunks whether it was cancelled, so a cancel takes
+//! effect within one chunk
+
+use crate::{
+    checksum::{Adler32, Crc, Fletcher16, Fletcher32, CRC_PRESETS},
+    digest::{Entropy, Sha256},
+    entmap::EntropyMap,
+    progress::Progress,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Bytes a job handles between looking for a cancel
+pub const JOB_CHUNK: usize = 1024 * 1024;
+
+/// Least time between two progress reports, so a fast job doesn't flood the editor with redraws
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Work a job does, fed the bytes in order a chunk at a time
+pub trait Task: Send + 'static {
+    /// Takes in the chunk that follows the ones fed before
+    fn update(&mut self, chunk: &[u8]);
+
+    /// What to show once every chunk was fed. More than one line is shown as a listing
+    fn finish(self: Box<Self>) -> String;
+}
+
+impl Task for Sha256 {
+    fn update(&mut self, chunk: &[u8]) {
+        Sha256::update(self, chunk);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        format!("sha256 {}", self.finish_hex())
+    }
+}
+
+impl Task for Entropy {
+    fn update(&mut self, chunk: &[u8]) {
+        Entropy::update(self, chunk);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        format!("Entropy {:.4} bits per byte", self.bits_per_byte())
+    }
+}
+
+impl Task for EntropyMap {
+    fn update(&mut self, chunk: &[u8]) {
+        EntropyMap::update(self, chunk);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        self.finish_lines().join("\n")
+    }
+}
+
+impl Task for Adler32 {
+    fn update(&mut self, chunk: &[u8]) {
+        Adler32::update(self, chunk);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        format!("adler32 {0:08X} ({0})", self.value())
+    }
+}
+
+impl Task for Fletcher16 {
+    fn update(&mut self, chunk: &[u8]) {
+        Fletcher16::update(self, chunk);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        format!("fletcher16 {0:04X} ({0})", self.value())
+    }
+}
+
+impl Task for Fletcher32 {
+    fn update(&mut self, chunk: &[u8]) {
+        Fletcher32::update(self, chunk);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        format!("fletcher32 {0:08X} ({0})", self.value())
+    }
+}
+
+impl Task for Crc {
+    fn update(&mut self, chunk: &[u8]) {
+        Crc::update(self, chunk);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        let params = self.params();
+        let name = CRC_PRESETS
+            .iter()
+            .find(|(_, preset)| preset == params)
+            .map_or("crc", |(name, _)| name);
+        let digits = params.width.div_ceil(4) as usize;
+
+        format!(
+            "{name} {0:01$X} ({0}), {2}",
+            self.value(),
+            digits,
+            params.describe()
+        )
+    }
+}
+
+/// What a job reports back
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobEvent {
+    /// Bytes handled so far
+    Progress(usize),
+    /// The job ran to the end, with what its task made of the bytes
+    Done(String),
+    /// The job stopped early because it was cancelled
+    Cancelled,
+    /// The worker went away without finishing, its task panicked
+    Failed,
+}
+
+/// A task running on a worker thread
+pub struct Job {
+    /// Bytes handled as of the last progress report, out of the bytes the job reads
+    progress: Progress,
+    cancel: Arc<AtomicBool>,
+    events: Receiver<JobEvent>,
+}
+
+impl Job {
+    /// Starts running a task over `bytes` in the background. `notify` is called from the worker
+    /// after each event is sent, to wake up whoever waits for them
+    pub fn spawn(
+        name: &str,
+        bytes: Arc<Vec<u8>>,
+        task: Box<dyn Task>,
+        notify: impl Fn() + Send + 'static,
+    ) -> Job {
+        Job::spawn_chunked(name, bytes, task, JOB_CHUNK, notify)
+    }
+
+    /// Like `spawn`, but handling `chunk` bytes at a time
+    pub fn spawn_chunked(
+        name: &str,
+        bytes: Arc<Vec<u8>>,
+        mut task: Box<dyn Task>,
+        chunk: usize,
+        notify: impl Fn() + Send + 'static,
+    ) -> Job {
+        let (tx, events) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancelled = cancel.clone();
+        let total = bytes.len();
+
+        thread::spawn(move || {
+            let mut last_report = Instant::now();
+
+            for (i, piece) in bytes.chunks(chunk.max(1)).enumerate() {
+                if cancelled.load(Ordering::Relaxed) {
+                    // Where it stopped, whenever the last report was
+                    let _ = tx.send(JobEvent::Progress((i * chunk.max(1)).min(total)));
+                    let _ = tx.send(JobEvent::Cancelled);
+                    notify();
+                    return;
+                }
+
+                task.update(piece);
+
+                if last_report.elapsed() >= PROGRESS_INTERVAL {
+                    last_report = Instant::now();
+                    let done = (i * chunk.max(1) + piece.len()).min(total);
+                    // Nobody listening any more means nobody wants the result either
+                    if tx.send(JobEvent::Progress(done)).is_err() {
+                        return;
+                    }
+                    notify();
+                }
+            }
+
+            // A cancel that came in during the last chunk still counts
+            let event = if cancelled.load(Ordering::Relaxed) {
+                JobEvent::Cancelled
+            } else {
+                JobEvent::Done(task.finish())
+            };
+            let _ = tx.send(event);
+            notify();
+        });
+
+        Job {
+            progress: Progress::new(name, total),
+            cancel,
+            events,
+        }
+    }
+
+    /// What the job was started as, like "hash sha256"
+    pub fn name(&self) -> &str {
+        self.progress.name()
+    }
+
+    /// How far the job got as of the last progress report, 0 to 100
+    pub fn percent(&self) -> usize {
+        self.progress.percent()
+    }
+
+    /// How far the job got as of the last progress report and how long it has been running
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// Asks the job to stop, it does so before its next chunk and reports `Cancelled`
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// Takes what the job reported since the last poll without waiting. Progress reports are
+    /// folded into the latest one, the end of the job is returned as soon as it is seen.
+    /// None if nothing new came in
+    pub fn poll(&mut self) -> Option<JobEvent> {
+        let mut latest = None;
+
+        loop {
+            match self.events.try_recv() {
+                Ok(JobEvent::Progress(done)) => {
+                    self.progress.update(done);
+                    latest = Some(JobEvent::Progress(done));
+                }
+                Ok(end) => return Some(end),
+                Err(TryRecvError::Empty) => return latest,
+                Err(TryRecvError::Disconnected) => return Some(JobEvent::Failed),
+            }
+        }
+    }
+
+    /// Blocks until the job ends, for when there is nothing else to do in the meantime
+    pub fn wait(mut self) -> JobEvent {
+        loop {
+            match self.events.recv() {
+                Ok(JobEvent::Progress(done)) => self.progress.update(done),
+                Ok(end) => return end,
+                Err(_) => return JobEvent::Failed,
+            }
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The edit journal, every edit and undo of a buffer appended to `<file>.hexit-journal` as it
+//! is made, so the session can be replayed after a crash, undo steps and all.
+//!
+//! The journal starts with `HXJOURNL`, a little endian u16 version and the u64 size of the file
+//! it was written against. Records follow, each one byte for what it is, then for an edit the
+//! u64 offset, the u64 lengths of the bytes it removed and inserted and the bytes themselves,
+//! and last the CRC-32 of all of that. A record that was only partly written or was damaged
+//! ends the journal, everything before it is still replayed
+
+use crate::{checksum::crc32, document::Document};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// What is added to a file's name for its journal
+pub const JOURNAL_SUFFIX: &str = ".hexit-journal";
+
+/// The bytes a journal starts with
+const MAGIC: &[u8; 8] = b"HXJOURNL";
+
+/// The version of the format written here
+pub const JOURNAL_VERSION: u16 = 1;
+
+/// Bytes in the header: the magic, the version and the size
+const HEADER_LEN: usize = 8 + 2 + 8;
+
+/// An edit that joins the undo step of the one before
+const EDIT: u8 = 1;
+/// An edit that starts an undo step of its own
+const EDIT_STEP: u8 = 2;
+/// The last undo step was undone
+const UNDO: u8 = 3;
+
+/// Where the journal of a file goes, next to it
+pub fn journal_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(JOURNAL_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// One thing that happened to the document
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Record {
+    /// The bytes `removed` at `offset` were replaced by `inserted`
+    Edit {
+        /// Whether the edit started an undo step, or was part of the one before
+        new_step: bool,
+        /// Where the edit was
+        offset: usize,
+        /// The bytes that were there
+        removed: Vec<u8>,
+        /// The bytes there now
+        inserted: Vec<u8>,
+    },
+    /// The last undo step was undone
+    Undo,
+}
+
+impl Record {
+    /// Appends the record as it is written to the journal
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+
+        match self {
+            Record::Edit {
+                new_step,
+                offset,
+                removed,
+                inserted,
+            } => {
+                out.push(if *new_step { EDIT_STEP } else { EDIT });
+                out.extend_from_slice(&(*offset as u64).to_le_bytes());
+                out.extend_from_slice(&(removed.len() as u64).to_le_bytes());
+                out.extend_from_slice(&(inserted.len() as u64).to_le_bytes());
+                out.extend_from_slice(removed);
+                out.extend_from_slice(inserted);
+            }
+            Record::Undo => out.push(UNDO),
+        }
+
+        let crc = crc32(&out[start..]);
+        out.extend_from_slice(&crc.to_le_bytes());
+    }
+}
+
+/// The start of a journal for a file of `size` bytes
+pub fn header(size: usize) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&JOURNAL_VERSION.to_le_bytes());
+    header.extend_from_slice(&(size as u64).to_le_bytes());
+    header
+}
+
+/// A journal as read back
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Journal {
+    /// How many bytes the file had when the journal was started
+    pub size: usize,
+    /// What happened, in order
+    pub records: Vec<Record>,
+    /// How many bytes at the start of the journal hold the header and the records, anything
+    /// past it was damaged or only partly written
+    pub intact: usize,
+}
+
+impl Journal {
+    /// Reads a journal, up to the first record that is damaged or cut short. Fails only if
+    /// the header itself is wrong
+    pub fn parse(bytes: &[u8]) -> Result<Journal, String> {
+        if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+            return Err("not a hex_it journal".to_string());
+        }
+
+        let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+        if version != JOURNAL_VERSION {
+            return Err(format!(
+                "journal version {version}, this hex_it reads version {JOURNAL_VERSION}"
+            ));
+        }
+
+        let size = usize::try_from(read_u64(bytes, 10).unwrap_or(u64::MAX))
+            .map_err(|_| "the journal is for a file too large to open here".to_string())?;
+
+        let mut journal = Journal {
+            size,
+            records: Vec::new(),
+            intact: HEADER_LEN,
+        };
+        while let Some((record, len)) = parse_record(&bytes[journal.intact..]) {
+            journal.records.push(record);
+            journal.intact += len;
+        }
+
+        Ok(journal)
+    }
+
+    /// Replays the journal over the bytes of the file, making the same edits and undo steps
+    /// again. Fails if the file isn't what the journal was written against: a different size,
+    /// or different bytes where an edit replaced them
+    pub fn replay(&self, bytes: Vec<u8>) -> Result<Document, String> {
+        if bytes.len() != self.size {
+            return Err(format!(
+                "the file has {:X} bytes, the journal was written for {:X}",
+                bytes.len(),
+                self.size
+            ));
+        }
+
+        let mut document = Document::new(bytes);
+        for record in &self.records {
+            match record {
+                Record::Edit {
+                    new_step,
+                    offset,
+                    removed,
+                    inserted,
+                } => {
+                    let range = offset.saturating_add(removed.len());
+                    if document.read(*offset..range).ok() != Some(&removed[..]) {
+                        return Err(format!(
+                            "the file changed at {offset:X} since the journal was written"
+                        ));
+                    }
+
+                    if *new_step {
+                        document.end_undo_group();
+                    }
+                    document.begin_undo_group();
+                    let _ = document.replace(*offset..range, inserted);
+                }
+                Record::Undo => {
+                    document
+                        .undo()
+                        .map_err(|_| "the journal undoes more than it did".to_string())?;
+                }
+            }
+        }
+        document.end_undo_group();
+
+        Ok(document)
+    }
+}
+
+/// Reads a little endian u64 at an offset
+fn read_u64(bytes: &[u8], at: usize) -> Option<u64> {
+    let bytes = bytes.get(at..at.checked_add(8)?)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Reads the record the bytes start with and how many bytes it took, None if it is damaged or
+/// cut short
+fn parse_record(bytes: &[u8]) -> Option<(Record, usize)> {
+    let (record, len) = match *bytes.first()? {
+        kind @ (EDIT | EDIT_STEP) => {
+            let offset = usize::try_from(read_u64(bytes, 1)?).ok()?;
+            let removed = usize::try_from(read_u64(bytes, 9)?).ok()?;
+            let inserted = usize::try_from(read_u64(bytes, 17)?).ok()?;
+
+            // Lengths from a damaged record can be anything, they have to fit what is there
+            let removed_end = 25usize.checked_add(removed)?;
+            let inserted_end = removed_end.checked_add(inserted)?;
+            let record = Record::Edit {
+                new_step: kind == EDIT_STEP,
+                offset,
+                removed: bytes.get(25..removed_end)?.to_vec(),
+                inserted: bytes.get(removed_end..inserted_end)?.to_vec(),
+            };
+            (record, inserted_end)
+        }
+        UNDO => (Record::Undo, 1),
+        _ => return None,
+    };
+
+    let crc = bytes.get(len..len + 4)?;
+    match u32::from_le_bytes(crc.try_into().ok()?) == crc32(&bytes[..len]) {
+        true => Some((record, len + 4)),
+        false => None,
+    }
+}
+
+/// Appends the records of a buffer to its journal, starting the file with the first of them
+pub struct JournalWriter {
+    path: PathBuf,
+    /// The size of the file the journal is for
+    size: usize,
+    /// Open once something was written
+    file: Option<File>,
+}
+
+impl JournalWriter {
+    /// A journal for a file of `size` bytes, nothing is written until there is a record
+    pub fn new(path: PathBuf, size: usize) -> JournalWriter {
+        JournalWriter {
+            path,
+            size,
+            file: None,
+        }
+    }
+
+    /// Carries on with a journal that was replayed, dropping what is past its intact records
+    pub fn resume(path: PathBuf, journal: &Journal) -> io::Result<JournalWriter> {
+        let file = OpenOptions::new().write(true).open(&path)?;
+        file.set_len(journal.intact as u64)?;
+
+        let mut writer = JournalWriter::new(path, journal.size);
+        writer.file = Some(OpenOptions::new().append(true).open(&writer.path)?);
+        Ok(writer)
+    }
+
+    /// Where the journal is
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes records at the end of the journal, each one whole or not at all as far as
+    /// reading it back goes. They go straight to the file, so they outlive the editor crashing
+    pub fn append(&mut self, records: &[Record]) -> io::Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut bytes = Vec::new();
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => {
+                bytes = header(self.size);
+                self.file.insert(File::create(&self.path)?)
+            }
+        };
+
+        for record in records {
+            record.encode(&mut bytes);
+        }
+        file.write_all(&bytes)
+    }
+
+    /// Deletes the journal, once the edits in it were saved or given up on
+    pub fn remove(self) {
+        if self.file.is_some() {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
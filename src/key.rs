@@ -0,0 +1,200 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Turning the bytes a terminal sends into keypresses
+
+use std::time::Duration;
+
+/// How long to wait after an ESC byte for the rest of an escape sequence
+pub const ESCAPE_TIMEOUT: Duration = Duration::from_millis(25);
+
+/// How long to wait for the rest of a bracketed paste, pasted text arrives in one burst
+pub const PASTE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Marks the end of a bracketed paste
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// What happened with the mouse, columns and rows are 1-based like the terminal reports them
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MouseEvent {
+    /// The wheel was turned up a notch
+    WheelUp,
+    /// The wheel was turned down a notch
+    WheelDown,
+    /// A button went down, 0 is the left button
+    Press {
+        /// Button number as the terminal reports it
+        button: u8,
+        /// 1-based column of the pointer
+        column: usize,
+        /// 1-based row of the pointer
+        row: usize,
+    },
+    /// A button was let go
+    Release {
+        /// 1-based column of the pointer
+        column: usize,
+        /// 1-based row of the pointer
+        row: usize,
+    },
+}
+
+/// Decodes the parameters of an SGR mouse report, "ESC [ < button ; column ; row M" (m on release)
+fn parse_mouse(params: &str, last: u8) -> Option<MouseEvent> {
+    let mut fields = params.strip_prefix('<')?.split(';');
+    let button = fields.next()?.parse::<u8>().ok()?;
+    let column = fields.next()?.parse::<usize>().ok()?;
+    let row = fields.next()?.parse::<usize>().ok()?;
+
+    Some(match (button, last) {
+        (64, _) => MouseEvent::WheelUp,
+        (65, _) => MouseEvent::WheelDown,
+        (_, b'm') => MouseEvent::Release { column, row },
+        _ => MouseEvent::Press {
+            button,
+            column,
+            row,
+        },
+    })
+}
+
+/// A single decoded keypress
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Key {
+    /// A printable ASCII char
+    Char(char),
+    /// Enter or Return
+    Enter,
+    /// Backspace, sent as either DEL or Ctrl+H
+    Backspace,
+    /// The Delete key
+    Delete,
+    /// Arrow left
+    Left,
+    /// Arrow right
+    Right,
+    /// Arrow up
+    Up,
+    /// Arrow down
+    Down,
+    /// Home
+    Home,
+    /// End
+    End,
+    /// Page Up
+    PageUp,
+    /// Page Down
+    PageDown,
+    /// Ctrl or Alt plus arrow left
+    CtrlLeft,
+    /// Ctrl or Alt plus arrow right
+    CtrlRight,
+    /// A lone Escape
+    Esc,
+    /// Ctrl+C, which arrives as a byte since raw mode turns off signals
+    CtrlC,
+    /// Ctrl plus a letter, other than the ones with their own variant
+    Ctrl(char),
+    /// Alt plus a printable char
+    Alt(char),
+    /// Text pasted while bracketed paste is on, exactly as the terminal sent it
+    Paste(String),
+    /// A mouse report
+    Mouse(MouseEvent),
+    /// Not a key, the editor was stopped and continued and has to set the terminal up again
+    Resume,
+    /// Not a key, the file being edited changed on disk
+    FileChanged,
+    /// Not a key, a background job has news
+    JobUpdate,
+    /// Not a key, time to autosave the buffers with unsaved changes
+    Autosave,
+    /// An escape sequence, control byte or non-ASCII byte we don't handle (yet)
+    Unknown,
+}
+
+/// Collects everything up to the end-of-paste marker, the start marker has already been read
+fn read_paste(next: &mut impl FnMut(Duration) -> Option<u8>) -> Key {
+    let mut pasted = Vec::new();
+
+    // Give up if the end marker never shows
+    while let Some(byte) = next(PASTE_TIMEOUT) {
+        pasted.push(byte);
+
+        if pasted.ends_with(PASTE_END) {
+            pasted.truncate(pasted.len() - PASTE_END.len());
+            break;
+        }
+    }
+
+    Key::Paste(String::from_utf8_lossy(&pasted).into_owned())
+}
+
+/// Decodes the key that starts with `first`, telling a lone ESC apart from the start of an escape sequence.
+/// `next` is asked for the rest of a sequence and returns None if nothing arrives within the timeout
+pub fn decode_key(first: u8, mut next: impl FnMut(Duration) -> Option<u8>) -> Key {
+    match first {
+        27 => match next(ESCAPE_TIMEOUT) {
+            // Nothing followed the ESC, so the user pressed the key itself
+            None => Key::Esc,
+            Some(b'[') | Some(b'O') => {
+                // Read the rest of the sequence, parameters followed by a final byte in the range 0x40-0x7E
+                let mut params = String::new();
+                let mut last = 0;
+                while let Some(byte) = next(ESCAPE_TIMEOUT) {
+                    if (0x40..=0x7E).contains(&byte) {
+                        last = byte;
+                        break;
+                    }
+                    params.push(byte as char);
+                }
+
+                // Ctrl+arrows come as "1;5C", xterm sends Alt+arrows as "1;3C", treat both the same
+                let word = params.ends_with(";5") || params.ends_with(";3");
+
+                match (last, params.as_str()) {
+                    (b'M', _) | (b'm', _) if params.starts_with('<') => {
+                        parse_mouse(&params, last).map_or(Key::Unknown, Key::Mouse)
+                    }
+                    (b'A', _) => Key::Up,
+                    (b'B', _) => Key::Down,
+                    (b'C', _) if word => Key::CtrlRight,
+                    (b'D', _) if word => Key::CtrlLeft,
+                    (b'C', _) => Key::Right,
+                    (b'D', _) => Key::Left,
+                    (b'H', _) | (b'~', "1") | (b'~', "7") => Key::Home,
+                    (b'F', _) | (b'~', "4") | (b'~', "8") => Key::End,
+                    (b'~', "3") => Key::Delete,
+                    (b'~', "5") => Key::PageUp,
+                    (b'~', "6") => Key::PageDown,
+                    (b'~', "200") => read_paste(&mut next),
+                    _ => Key::Unknown,
+                }
+            }
+            Some(byte @ 0x20..=0x7E) => Key::Alt(byte as char),
+            Some(_) => Key::Unknown,
+        },
+        3 => Key::CtrlC,
+        8 | 127 => Key::Backspace,
+        10 | 13 => Key::Enter,
+        1..=26 => Key::Ctrl((b'a' + first - 1) as char),
+        // Printable ASCII, quotes and backslashes are left for the command parser to deal with
+        0x20..=0x7E => Key::Char(first as char),
+        _ => Key::Unknown,
+    }
+}
+
+/// Decodes every key in a buffer of bytes that has already arrived in full,
+/// a sequence cut off by the end of the buffer decodes like one that timed out
+pub fn decode_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut bytes = bytes.iter().copied();
+    let mut keys = Vec::new();
+
+    while let Some(first) = bytes.next() {
+        keys.push(decode_key(first, |_| bytes.next()));
+    }
+
+    keys
+}
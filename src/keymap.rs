@@ -0,0 +1,413 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Which key does what in each mode
+
+use crate::key::Key;
+use std::collections::HashMap;
+
+/// Something the user can do with a single key, see Keymap
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    /// Runs what was typed on the command line
+    Submit,
+    /// Backs out of whatever is going on, one step at a time
+    Cancel,
+    /// Quits with the interrupted exit code
+    Interrupt,
+    /// Quits, asking first if there are unsaved changes
+    Quit,
+    /// Deletes the char before the command line cursor
+    Backspace,
+    /// Deletes the char under the command line cursor
+    Delete,
+    /// Moves the command line cursor left
+    Left,
+    /// Moves the command line cursor right
+    Right,
+    /// Moves the command line cursor to the start
+    LineStart,
+    /// Moves the command line cursor to the end
+    LineEnd,
+    /// Moves the command line cursor to the previous word
+    WordLeft,
+    /// Moves the command line cursor past the next word
+    WordRight,
+    /// Deletes the word before the command line cursor
+    DeleteWord,
+    /// Deletes everything before the command line cursor
+    DeleteToStart,
+    /// Completes the word before the command line cursor, like a label after goto
+    Complete,
+    /// Scrolls the dump up a line
+    ScrollUp,
+    /// Scrolls the dump down a line
+    ScrollDown,
+    /// Scrolls the dump up a screen
+    PageUp,
+    /// Scrolls the dump down a screen
+    PageDown,
+    /// Moves the grid cursor to the previous byte
+    CursorLeft,
+    /// Moves the grid cursor to the next byte
+    CursorRight,
+    /// Moves the grid cursor up a line
+    CursorUp,
+    /// Moves the grid cursor down a line
+    CursorDown,
+    /// Moves the grid cursor to the first byte, pressed twice
+    CursorTop,
+    /// Moves the grid cursor to the last byte
+    CursorBottom,
+    /// Switches to command mode
+    CommandMode,
+    /// Switches to overwrite mode
+    OverwriteMode,
+    /// Switches to text mode
+    TextMode,
+    /// Starts or drops a selection at the grid cursor
+    Select,
+    /// Suspends the editor like Ctrl+Z in a shell
+    Suspend,
+    /// Undoes the last change
+    Undo,
+    /// Jumps to the next search result or signature hit
+    NextMatch,
+    /// Jumps to the previous search result or signature hit
+    PreviousMatch,
+    /// Moves the grid cursor past the run of the byte under it
+    SkipRun,
+    /// Moves the grid cursor back before the run of the byte under it
+    SkipRunBack,
+    /// Copies the selection, or the byte under the cursor, to the clipboard
+    Yank,
+    /// Writes the clipboard over the bytes from the cursor
+    Put,
+}
+
+impl Action {
+    /// The modes an action is bound in when the config doesn't name one
+    pub fn default_modes(self) -> &'static [Mode] {
+        match self {
+            Action::Submit
+            | Action::Backspace
+            | Action::Delete
+            | Action::Left
+            | Action::Right
+            | Action::LineStart
+            | Action::LineEnd
+            | Action::WordLeft
+            | Action::WordRight
+            | Action::DeleteWord
+            | Action::DeleteToStart
+            | Action::Complete => &[Mode::Command],
+            Action::CommandMode
+            | Action::OverwriteMode
+            | Action::TextMode
+            | Action::Select
+            | Action::Undo
+            | Action::NextMatch
+            | Action::PreviousMatch
+            | Action::SkipRun
+            | Action::SkipRunBack
+            | Action::Yank
+            | Action::Put => &[Mode::Normal],
+            Action::CursorLeft
+            | Action::CursorRight
+            | Action::CursorUp
+            | Action::CursorDown
+            | Action::CursorTop
+            | Action::CursorBottom => &[Mode::Normal, Mode::Overwrite, Mode::Text],
+            Action::Cancel
+            | Action::Interrupt
+            | Action::Quit
+            | Action::ScrollUp
+            | Action::ScrollDown
+            | Action::PageUp
+            | Action::PageDown
+            | Action::Suspend => &[Mode::Normal, Mode::Command, Mode::Overwrite, Mode::Text],
+        }
+    }
+}
+
+/// Every action with the name used for it in the config file and `bindings`
+pub const ACTIONS: [(Action, &str); 37] = [
+    (Action::Submit, "submit"),
+    (Action::Cancel, "cancel"),
+    (Action::Interrupt, "interrupt"),
+    (Action::Quit, "quit"),
+    (Action::Backspace, "backspace"),
+    (Action::Delete, "delete"),
+    (Action::Left, "left"),
+    (Action::Right, "right"),
+    (Action::LineStart, "line-start"),
+    (Action::LineEnd, "line-end"),
+    (Action::WordLeft, "word-left"),
+    (Action::WordRight, "word-right"),
+    (Action::DeleteWord, "delete-word"),
+    (Action::DeleteToStart, "delete-to-start"),
+    (Action::Complete, "complete"),
+    (Action::ScrollUp, "scroll-up"),
+    (Action::ScrollDown, "scroll-down"),
+    (Action::PageUp, "pageup"),
+    (Action::PageDown, "pagedown"),
+    (Action::CursorLeft, "cursor-left"),
+    (Action::CursorRight, "cursor-right"),
+    (Action::CursorUp, "cursor-up"),
+    (Action::CursorDown, "cursor-down"),
+    (Action::CursorTop, "cursor-top"),
+    (Action::CursorBottom, "cursor-bottom"),
+    (Action::CommandMode, "command-mode"),
+    (Action::OverwriteMode, "overwrite-mode"),
+    (Action::TextMode, "text-mode"),
+    (Action::Select, "select"),
+    (Action::Suspend, "suspend"),
+    (Action::Undo, "undo"),
+    (Action::NextMatch, "next-match"),
+    (Action::PreviousMatch, "previous-match"),
+    (Action::SkipRun, "skip"),
+    (Action::SkipRunBack, "skip-back"),
+    (Action::Yank, "yank"),
+    (Action::Put, "put"),
+];
+
+/// Normal mode moves around the dump with single keys, command mode types into the command line,
+/// overwrite mode types hex straight onto the grid and text mode types chars onto the text column
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Mode {
+    /// Keys move around the dump
+    Normal,
+    /// Keys are typed into the command line
+    Command,
+    /// Hex digits typed over the grid replace the byte under the cursor
+    Overwrite,
+    /// Chars typed over the text column replace the byte under the cursor with their code
+    Text,
+}
+
+/// Every mode with the name used for it in the config file and `bindings`
+pub const MODES: [(Mode, &str); 4] = [
+    (Mode::Normal, "normal"),
+    (Mode::Command, "command"),
+    (Mode::Overwrite, "overwrite"),
+    (Mode::Text, "text"),
+];
+
+/// Names of the keys that aren't a plain char, as written in key specs
+pub const KEY_NAMES: [(Key, &str); 17] = [
+    (Key::Enter, "enter"),
+    (Key::Esc, "esc"),
+    (Key::Backspace, "backspace"),
+    (Key::Delete, "delete"),
+    (Key::Left, "left"),
+    (Key::Right, "right"),
+    (Key::Up, "up"),
+    (Key::Down, "down"),
+    (Key::Home, "home"),
+    (Key::End, "end"),
+    (Key::PageUp, "pageup"),
+    (Key::PageDown, "pagedown"),
+    (Key::CtrlLeft, "ctrl+left"),
+    (Key::CtrlRight, "ctrl+right"),
+    (Key::CtrlC, "ctrl+c"),
+    (Key::Char(' '), "space"),
+    (Key::Ctrl('i'), "tab"),
+];
+
+/// Parses a key spec like "ctrl+b", "alt+f", "pageup" or ";"
+pub fn parse_key(spec: &str) -> Option<Key> {
+    let spec = spec.trim().to_ascii_lowercase();
+
+    if let Some((key, _)) = KEY_NAMES.iter().find(|(_, name)| *name == spec) {
+        return Some(key.clone());
+    }
+
+    let single = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_graphic() => Some(c),
+            _ => None,
+        }
+    };
+
+    if let Some(rest) = spec.strip_prefix("ctrl+") {
+        return single(rest)
+            .filter(|c| c.is_ascii_lowercase())
+            .map(Key::Ctrl);
+    }
+
+    if let Some(rest) = spec.strip_prefix("alt+") {
+        return single(rest).map(Key::Alt);
+    }
+
+    single(&spec).map(Key::Char)
+}
+
+/// Writes a key the way parse_key reads it
+pub fn key_name(key: &Key) -> String {
+    match key {
+        // Tab arrives as Ctrl+I
+        Key::Ctrl(c) if *c != 'i' => format!("ctrl+{}", c),
+        Key::Alt(c) => format!("alt+{}", c),
+        Key::Char(c) if *c != ' ' => c.to_string(),
+        _ => KEY_NAMES
+            .iter()
+            .find(|(named, _)| named == key)
+            .map_or_else(|| format!("{:?}", key), |(_, name)| name.to_string()),
+    }
+}
+
+/// Maps keys to the actions they trigger in each mode.
+/// In command mode keys that aren't bound are typed into the command line
+pub struct Keymap {
+    bindings: HashMap<(Mode, Key), Action>,
+}
+
+impl Keymap {
+    /// The default bindings
+    pub fn new() -> Keymap {
+        let defaults = [
+            (Key::Enter, Action::Submit),
+            (Key::Esc, Action::Cancel),
+            (Key::CtrlC, Action::Interrupt),
+            (Key::Backspace, Action::Backspace),
+            (Key::Delete, Action::Delete),
+            (Key::Left, Action::Left),
+            (Key::Right, Action::Right),
+            (Key::Home, Action::LineStart),
+            (Key::Ctrl('a'), Action::LineStart),
+            (Key::End, Action::LineEnd),
+            (Key::Ctrl('e'), Action::LineEnd),
+            (Key::CtrlLeft, Action::WordLeft),
+            (Key::Alt('b'), Action::WordLeft),
+            (Key::CtrlRight, Action::WordRight),
+            (Key::Alt('f'), Action::WordRight),
+            (Key::Ctrl('w'), Action::DeleteWord),
+            (Key::Ctrl('u'), Action::DeleteToStart),
+            (Key::Ctrl('i'), Action::Complete),
+            (Key::PageUp, Action::PageUp),
+            (Key::PageDown, Action::PageDown),
+            (Key::Char('h'), Action::CursorLeft),
+            (Key::Left, Action::CursorLeft),
+            (Key::Char('l'), Action::CursorRight),
+            (Key::Right, Action::CursorRight),
+            (Key::Char('k'), Action::CursorUp),
+            (Key::Up, Action::CursorUp),
+            (Key::Char('j'), Action::CursorDown),
+            (Key::Down, Action::CursorDown),
+            (Key::Char('g'), Action::CursorTop),
+            (Key::Char('G'), Action::CursorBottom),
+            (Key::Char(':'), Action::CommandMode),
+            (Key::Char('i'), Action::OverwriteMode),
+            (Key::Char('t'), Action::TextMode),
+            (Key::Char('u'), Action::Undo),
+            (Key::Char('v'), Action::Select),
+            (Key::Char('n'), Action::NextMatch),
+            (Key::Char('N'), Action::PreviousMatch),
+            (Key::Char('s'), Action::SkipRun),
+            (Key::Char('S'), Action::SkipRunBack),
+            (Key::Char('y'), Action::Yank),
+            (Key::Char('p'), Action::Put),
+            (Key::Ctrl('b'), Action::PageUp),
+            (Key::Ctrl('f'), Action::PageDown),
+            (Key::Ctrl('z'), Action::Suspend),
+        ];
+
+        let mut keymap = Keymap {
+            bindings: HashMap::new(),
+        };
+
+        for (key, action) in defaults {
+            for mode in action.default_modes() {
+                // Typed chars are hex digits in overwrite mode and bytes in text mode, so only
+                // special keys are bound there
+                if matches!(mode, Mode::Overwrite | Mode::Text) && matches!(key, Key::Char(_)) {
+                    continue;
+                }
+
+                keymap.bindings.insert((*mode, key.clone()), action);
+            }
+        }
+
+        keymap
+    }
+
+    /// The action a key triggers in a mode, if any
+    pub fn action(&self, mode: Mode, key: &Key) -> Option<Action> {
+        self.bindings.get(&(mode, key.clone())).copied()
+    }
+
+    /// Parses and applies the rest of a "bind [mode] <action> = <key>" line
+    pub fn bind(&mut self, binding: &str) -> Result<(), String> {
+        let (action, key) = binding
+            .split_once('=')
+            .ok_or("expected 'bind [mode] <action> = <key>'")?;
+
+        let mut words = action.split_whitespace().collect::<Vec<&str>>();
+        let mode = match words.as_slice() {
+            [mode, _] => {
+                let mode = MODES
+                    .iter()
+                    .find(|(_, name)| name == mode)
+                    .map(|(mode, _)| *mode)
+                    .ok_or_else(|| format!("unknown mode '{}'", mode))?;
+                words.remove(0);
+                Some(mode)
+            }
+            [_] => None,
+            _ => return Err("expected 'bind [mode] <action> = <key>'".to_string()),
+        };
+
+        let action = ACTIONS
+            .iter()
+            .find(|(_, name)| *name == words[0])
+            .map(|(action, _)| *action)
+            .ok_or_else(|| format!("unknown action '{}'", words[0]))?;
+
+        let key = parse_key(key).ok_or_else(|| format!("can't parse key '{}'", key.trim()))?;
+
+        match mode {
+            Some(mode) => {
+                self.bindings.insert((mode, key), action);
+            }
+            None => {
+                for mode in action.default_modes() {
+                    self.bindings.insert((*mode, key.clone()), action);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every binding as "mode action = keys"
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for (mode, mode_name) in MODES {
+            for (action, name) in ACTIONS {
+                let mut keys = self
+                    .bindings
+                    .iter()
+                    .filter(|((bound_mode, _), bound)| *bound_mode == mode && **bound == action)
+                    .map(|((_, key), _)| key_name(key))
+                    .collect::<Vec<String>>();
+                keys.sort();
+
+                if !keys.is_empty() {
+                    lines.push(format!("{:<8} {} = {}", mode_name, name, keys.join(", ")));
+                }
+            }
+        }
+
+        lines
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap::new()
+    }
+}
@@ -0,0 +1,160 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Names for offsets, read from a symbol or map file by `labels load` so the dump can show
+//! what is where and `goto` can jump by name. A line is an address in hex and a name:
+//!
+//! ```text
+//! 0x0000 vector_table
+//! 0400   config_block
+//! ```
+//!
+//! `nm` output reads too, its type letter between the address and the name is skipped, and so
+//! do the symbol lines of a linker map. Lines that are neither, like section headers or `nm`
+//! lines of undefined symbols, are counted and skipped. `#` starts a comment
+
+use std::{collections::BTreeMap, ops::Range};
+
+/// The names loaded for a buffer, at most one per offset
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Labels {
+    names: BTreeMap<usize, String>,
+}
+
+/// What reading a label file found besides the labels
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LabelReport {
+    /// Lines that weren't a label, blank lines and comments aside
+    pub skipped: usize,
+    /// What went wrong with lines that were, one per line
+    pub warnings: Vec<String>,
+}
+
+impl Labels {
+    /// Reads a label file. Addresses are taken to be `base` plus the offset in the buffer, so
+    /// ones below it are left out. A later label for an address replaces the one before it,
+    /// with a warning
+    pub fn parse(text: &str, base: usize) -> (Labels, LabelReport) {
+        let mut labels = Labels::default();
+        let mut report = LabelReport::default();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((address, name)) = parse_line(line) else {
+                report.skipped += 1;
+                continue;
+            };
+
+            let Some(offset) = address.checked_sub(base) else {
+                report.warnings.push(format!(
+                    "line {}: {address:X} {name} is below the base {base:X}",
+                    number + 1
+                ));
+                continue;
+            };
+
+            if let Some(before) = labels.names.insert(offset, name.to_string()) {
+                report.warnings.push(format!(
+                    "line {}: {address:X} was {before}, now {name}",
+                    number + 1
+                ));
+            }
+        }
+
+        (labels, report)
+    }
+
+    /// How many labels there are
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether none are loaded
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Every label in order of offset
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.names
+            .iter()
+            .map(|(offset, name)| (*offset, name.as_str()))
+    }
+
+    /// The labels of the offsets in a range, in order
+    pub fn within(&self, range: Range<usize>) -> impl Iterator<Item = (usize, &str)> {
+        self.names
+            .range(range)
+            .map(|(offset, name)| (*offset, name.as_str()))
+    }
+
+    /// The offset of the first label with a name, in order of offset
+    pub fn offset_of(&self, name: &str) -> Option<usize> {
+        self.iter()
+            .find(|(_, label)| *label == name)
+            .map(|(offset, _)| offset)
+    }
+
+    /// The names that start with `prefix`, sorted and without repeats
+    pub fn complete(&self, prefix: &str) -> Vec<&str> {
+        let mut names = self
+            .names
+            .values()
+            .map(|name| name.as_str())
+            .filter(|name| name.starts_with(prefix))
+            .collect::<Vec<&str>>();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+}
+
+/// Reads an `address name` or `address type name` line, None if it isn't one
+fn parse_line(line: &str) -> Option<(usize, &str)> {
+    let words = line.split_whitespace().collect::<Vec<&str>>();
+    let (address, name) = match words[..] {
+        [address, name] => (address, name),
+        // nm puts a letter for the kind of symbol in between
+        [address, kind, name]
+            if kind.len() == 1 && kind.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            (address, name)
+        }
+        _ => return None,
+    };
+
+    let digits = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .unwrap_or(address);
+    let address = u64::from_str_radix(digits, 16).ok()?;
+
+    Some((usize::try_from(address).ok()?, name))
+}
+
+/// The longest start all the names share, what Tab can fill in for them
+pub fn common_prefix(names: &[&str]) -> String {
+    let Some((first, rest)) = names.split_first() else {
+        return String::new();
+    };
+
+    let mut len = first.len();
+    for name in rest {
+        len = first
+            .char_indices()
+            .zip(name.chars())
+            .take_while(|((_, a), b)| a == b)
+            .map(|((index, a), _)| index + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(len);
+    }
+
+    first[..len].to_string()
+}
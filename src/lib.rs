@@ -0,0 +1,64 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The parts of hex_it that don't need a terminal: the document being edited, the dump, and
+//! the searching, decoding and file handling behind the editor's commands. Each module's own
+//! docs say what it is for. The `hex_it` binary is the TUI built on top of these.
+
+#![warn(missing_docs)]
+
+pub mod args;
+pub mod base64;
+pub mod carve;
+pub mod checksum;
+pub mod chunks;
+pub mod compare;
+pub mod config;
+pub mod conv;
+pub mod cyclic;
+pub mod digest;
+pub mod document;
+pub mod dump;
+pub mod editlog;
+pub mod entmap;
+pub mod escape;
+pub mod file;
+pub mod filter;
+pub mod gzip;
+pub mod headers;
+pub mod hex;
+pub mod histogram;
+pub mod inspect;
+pub mod job;
+pub mod journal;
+pub mod key;
+pub mod keymap;
+pub mod labels;
+pub mod line;
+pub mod magic;
+pub mod matches;
+pub mod offset;
+pub mod pager;
+pub mod patchfile;
+pub mod progress;
+pub mod random;
+pub mod recover;
+pub mod registers;
+pub mod runs;
+pub mod screen;
+pub mod search;
+pub mod searches;
+pub mod session;
+pub mod stride;
+pub mod strings;
+pub mod suggest;
+pub mod template;
+pub mod theme;
+pub mod verify;
+
+pub use document::{Document, DocumentError};
+pub use dump::{render, DumpOptions};
+pub use hex::{bytes_to_hex, hex_to_bytes};
+pub use offset::{parse_offset, parse_size};
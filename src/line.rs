@@ -0,0 +1,127 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The line the user types commands into
+
+/// The text being typed at the bottom of the screen, with a cursor somewhere inside it
+#[derive(Default)]
+pub struct InputLine {
+    /// What has been typed so far
+    pub chars: Vec<char>,
+    /// Index of the char the cursor sits in front of, chars.len() when at the end
+    pub cursor: usize,
+}
+
+impl InputLine {
+    /// Empties the line
+    pub fn clear(&mut self) {
+        self.chars.clear();
+        self.cursor = 0;
+    }
+
+    /// Empties the line and returns what was typed
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        self.chars.drain(..).collect()
+    }
+
+    /// Types a char at the cursor
+    pub fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    /// Removes the char before the cursor
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// Removes the char under the cursor
+    pub fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// Moves the cursor a char left
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Moves the cursor a char right
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    /// Moves the cursor to the start
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// Moves the cursor to the end
+    pub fn end(&mut self) {
+        self.cursor = self.chars.len();
+    }
+
+    /// Whitespace, slashes and dots split words so paths can be edited a piece at a time
+    fn is_separator(c: char) -> bool {
+        c.is_whitespace() || c == '/' || c == '.'
+    }
+
+    /// Index of the start of the word before the cursor
+    fn previous_word(&self) -> usize {
+        let mut index = self.cursor;
+
+        // Skip separators, then the word itself
+        while index > 0 && Self::is_separator(self.chars[index - 1]) {
+            index -= 1;
+        }
+        while index > 0 && !Self::is_separator(self.chars[index - 1]) {
+            index -= 1;
+        }
+
+        index
+    }
+
+    /// Index just past the end of the word after the cursor
+    fn next_word(&self) -> usize {
+        let mut index = self.cursor;
+
+        while index < self.chars.len() && Self::is_separator(self.chars[index]) {
+            index += 1;
+        }
+        while index < self.chars.len() && !Self::is_separator(self.chars[index]) {
+            index += 1;
+        }
+
+        index
+    }
+
+    /// Moves the cursor to the start of the word before it
+    pub fn word_left(&mut self) {
+        self.cursor = self.previous_word();
+    }
+
+    /// Moves the cursor past the end of the word after it
+    pub fn word_right(&mut self) {
+        self.cursor = self.next_word();
+    }
+
+    /// Removes the word before the cursor
+    pub fn delete_word(&mut self) {
+        let start = self.previous_word();
+        self.chars.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    /// Removes everything before the cursor
+    pub fn delete_to_start(&mut self) {
+        self.chars.drain(..self.cursor);
+        self.cursor = 0;
+    }
+}
@@ -0,0 +1,333 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Guessing the type of a file from the magic number near its start
+
+use crate::hex::bytes_to_hex;
+
+/// Bytes that identify a file type, with a mask for the bits that have to match
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// Where in the file the bytes are
+    pub offset: usize,
+    /// The bytes to look for, bits cleared in the mask are 0 here
+    pub bytes: Vec<u8>,
+    /// Which bits of each byte have to match, the same length as `bytes`
+    pub mask: Vec<u8>,
+    /// What kind of file it is
+    pub description: String,
+}
+
+/// The built-in signatures as (offset, pattern, description), see Signature::parse for the pattern syntax
+const BUILTIN: [(usize, &str, &str); 36] = [
+    (0, "89 50 4E 47 0D 0A 1A 0A", "PNG image"),
+    (0, "FF D8 FF", "JPEG image"),
+    (0, "47 49 46 38 ?? 61", "GIF image"),
+    (0, "42 4D", "BMP image"),
+    (0, "49 49 2A 00", "TIFF image (little endian)"),
+    (0, "4D 4D 00 2A", "TIFF image (big endian)"),
+    (0, "00 00 01 00", "Windows icon"),
+    (0, "52 49 46 46 ?? ?? ?? ?? 57 45 42 50", "WebP image"),
+    (0, "52 49 46 46 ?? ?? ?? ?? 57 41 56 45", "WAV audio"),
+    (0, "52 49 46 46 ?? ?? ?? ?? 41 56 49 20", "AVI video"),
+    (0, "49 44 33", "MP3 audio with ID3 tag"),
+    (0, "66 4C 61 43", "FLAC audio"),
+    (0, "4F 67 67 53", "Ogg container"),
+    (4, "66 74 79 70", "MP4/QuickTime video"),
+    (0, "1A 45 DF A3", "Matroska/WebM video"),
+    (0, "50 4B 03 04", "ZIP archive"),
+    (0, "50 4B 05 06", "ZIP archive (empty)"),
+    (0, "1F 8B", "gzip compressed data"),
+    (0, "42 5A 68", "bzip2 compressed data"),
+    (0, "FD 37 7A 58 5A 00", "xz compressed data"),
+    (0, "28 B5 2F FD", "Zstandard compressed data"),
+    (0, "37 7A BC AF 27 1C", "7-Zip archive"),
+    (0, "52 61 72 21 1A 07", "RAR archive"),
+    (257, "75 73 74 61 72", "tar archive"),
+    (0x8001, "43 44 30 30 31", "ISO 9660 CD image"),
+    (0, "7F 45 4C 46", "ELF executable"),
+    (0, "4D 5A", "PE/MZ executable"),
+    (0, "FE ED FA CE", "Mach-O executable (32-bit, big endian)"),
+    (0, "FE ED FA CF", "Mach-O executable (64-bit, big endian)"),
+    (0, "CE FA ED FE", "Mach-O executable (32-bit)"),
+    (0, "CF FA ED FE", "Mach-O executable (64-bit)"),
+    (0, "CA FE BA BE", "Java class or Mach-O universal binary"),
+    (0, "00 61 73 6D", "WebAssembly module"),
+    (0, "25 50 44 46 2D", "PDF document"),
+    (
+        0,
+        "53 51 4C 69 74 65 20 66 6F 72 6D 61 74 20 33 00",
+        "SQLite database",
+    ),
+    (
+        0,
+        "D0 CF 11 E0 A1 B1 1A E1",
+        "Microsoft Office document (OLE)",
+    ),
+];
+
+impl Signature {
+    /// Makes a signature from a pattern of hex pairs, spaces between them are optional.
+    /// A '?' in place of a digit matches any value of that nibble, so "??" matches any byte
+    pub fn parse(offset: usize, pattern: &str, description: &str) -> Result<Signature, String> {
+        let digits = pattern
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<Vec<char>>();
+
+        if digits.is_empty() {
+            return Err(format!("empty pattern for '{}'", description));
+        }
+        if digits.len() % 2 != 0 {
+            return Err(format!("odd number of digits in '{}'", pattern.trim()));
+        }
+
+        let mut bytes = Vec::new();
+        let mut mask = Vec::new();
+
+        for pair in digits.chunks(2) {
+            let mut byte = 0;
+            let mut byte_mask = 0;
+
+            for &c in pair {
+                let (nibble, nibble_mask) = match c {
+                    '?' => (0, 0),
+                    c => match c.to_digit(16) {
+                        Some(digit) => (digit as u8, 0xF),
+                        None => {
+                            return Err(format!("invalid digit '{}' in '{}'", c, pattern.trim()))
+                        }
+                    },
+                };
+
+                byte = (byte << 4) | nibble;
+                byte_mask = (byte_mask << 4) | nibble_mask;
+            }
+
+            bytes.push(byte);
+            mask.push(byte_mask);
+        }
+
+        Ok(Signature {
+            offset,
+            bytes,
+            mask,
+            description: description.to_string(),
+        })
+    }
+
+    /// Whether data has the signature at its offset
+    pub fn matches(&self, data: &[u8]) -> bool {
+        let Some(found) = data.get(self.offset..self.offset + self.bytes.len()) else {
+            return false;
+        };
+
+        found
+            .iter()
+            .zip(&self.bytes)
+            .zip(&self.mask)
+            .all(|((byte, expected), mask)| byte & mask == *expected)
+    }
+
+    /// How many bits the signature checks, more specific signatures win over shorter ones
+    pub fn specificity(&self) -> u32 {
+        self.mask.iter().map(|mask| mask.count_ones()).sum()
+    }
+
+    /// The pattern written back out, with '?' for the nibbles that aren't checked
+    pub fn pattern(&self) -> String {
+        self.bytes
+            .iter()
+            .zip(&self.mask)
+            .map(|(byte, mask)| {
+                let nibble = |shift: u8| match (mask >> shift) & 0xF {
+                    0 => '?',
+                    _ => char::from_digit(((byte >> shift) & 0xF) as u32, 16)
+                        .unwrap_or('?')
+                        .to_ascii_uppercase(),
+                };
+                format!("{}{}", nibble(4), nibble(0))
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Describes a match in data, like "PNG image, 89 50 4E 47 0D 0A 1A 0A at offset 0"
+    pub fn describe_match(&self, data: &[u8]) -> String {
+        let found = data
+            .get(self.offset..self.offset + self.bytes.len())
+            .map_or_else(|| self.pattern(), bytes_to_hex);
+
+        format!(
+            "{}, {} at offset {:X}",
+            self.description, found, self.offset
+        )
+    }
+}
+
+/// The signatures hex_it knows out of the box
+pub fn builtin_signatures() -> Vec<Signature> {
+    BUILTIN
+        .iter()
+        .map(|(offset, pattern, description)| {
+            Signature::parse(*offset, pattern, description).expect("Invalid built-in signature")
+        })
+        .collect()
+}
+
+/// The best guess for the type of data, the most specific signature that matches.
+/// Ties go to the one listed first
+pub fn detect<'a>(signatures: &'a [Signature], data: &[u8]) -> Option<&'a Signature> {
+    signatures
+        .iter()
+        .filter(|signature| signature.matches(data))
+        .fold(None, |best: Option<&Signature>, signature| match best {
+            Some(best) if best.specificity() >= signature.specificity() => Some(best),
+            _ => Some(signature),
+        })
+}
+
+/// Hits past the start of the data need at least this many bits to match to be reported,
+/// shorter signatures like "MZ" turn up by chance all the time
+pub const MIN_EMBEDDED_BITS: u32 = 32;
+
+/// A signature found while scanning
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hit<'a> {
+    /// Where the embedded file starts, the signature's own bytes are `signature.offset` further in
+    pub offset: usize,
+    /// The signature that matched
+    pub signature: &'a Signature,
+}
+
+impl Hit<'_> {
+    /// The offsets of the bytes the signature matched
+    pub fn matched(&self) -> std::ops::Range<usize> {
+        let start = self.offset + self.signature.offset;
+
+        start..start + self.signature.bytes.len()
+    }
+}
+
+/// Finds every place in data that a signature matches, sorted by offset.
+///
+/// To keep the list useful, hits past offset 0 are only kept for signatures of at least
+/// MIN_EMBEDDED_BITS bits, and where the matched bytes of two hits overlap only the more
+/// specific one is kept
+pub fn scan<'a>(signatures: &'a [Signature], data: &[u8]) -> Vec<Hit<'a>> {
+    // Index the signatures by a byte they check in full, so the data only has to be read once
+    let mut anchored: Vec<Vec<(&Signature, usize)>> = vec![Vec::new(); 256];
+    for signature in signatures {
+        match signature.mask.iter().position(|mask| *mask == 0xFF) {
+            Some(anchor) => anchored[signature.bytes[anchor] as usize].push((signature, anchor)),
+            // Signatures without a whole byte to anchor on are too vague to scan for
+            None => continue,
+        }
+    }
+
+    // How far past the start of a hit its signature can reach
+    let reach = signatures
+        .iter()
+        .map(|signature| signature.offset + signature.bytes.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut hits: Vec<Hit> = Vec::new();
+
+    for (i, byte) in data.iter().enumerate() {
+        for (signature, anchor) in &anchored[*byte as usize] {
+            let Some(start) = i.checked_sub(signature.offset + anchor) else {
+                continue;
+            };
+
+            if start > 0 && signature.specificity() < MIN_EMBEDDED_BITS {
+                continue;
+            }
+
+            if !signature.matches(&data[start..]) {
+                continue;
+            }
+
+            let hit = Hit {
+                offset: start,
+                signature,
+            };
+
+            // Hits arrive in order of their anchor byte, so only the last few can overlap this one
+            let overlapping = hits
+                .iter()
+                .enumerate()
+                .rev()
+                .take_while(|(_, other)| other.offset + 2 * reach >= hit.offset)
+                .find(|(_, other)| overlaps(&other.matched(), &hit.matched()))
+                .map(|(index, _)| index);
+
+            match overlapping {
+                Some(index) if hits[index].signature.specificity() >= signature.specificity() => {}
+                Some(index) => hits[index] = hit,
+                None => hits.push(hit),
+            }
+        }
+    }
+
+    hits.sort_by_key(|hit| hit.offset);
+    hits
+}
+
+fn overlaps(a: &std::ops::Range<usize>, b: &std::ops::Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Reads a signature file, a line per signature:
+///
+/// ```text
+/// # comments start with a hash
+/// [offset:] pattern  description
+/// ```
+///
+/// The offset is written like any other offset and defaults to 0, the pattern is hex pairs with '?'
+/// for nibbles that don't matter, and two or more spaces or a tab separate it from the description.
+/// Lines with problems are skipped and described in the returned list
+pub fn parse_signatures(contents: &str) -> (Vec<Signature>, Vec<String>) {
+    let mut signatures = Vec::new();
+    let mut errors = Vec::new();
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+
+        // Skip blank lines and comments
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match parse_signature_line(line) {
+            Ok(signature) => signatures.push(signature),
+            Err(e) => errors.push(format!("signature line {} '{}': {}", number + 1, line, e)),
+        }
+    }
+
+    (signatures, errors)
+}
+
+fn parse_signature_line(line: &str) -> Result<Signature, String> {
+    let split = line
+        .find("  ")
+        .into_iter()
+        .chain(line.find('\t'))
+        .min()
+        .ok_or("expected '[offset:] pattern  description'")?;
+    let (pattern, description) = (&line[..split], line[split..].trim());
+
+    let (offset, pattern) = match pattern.split_once(':') {
+        Some((offset, pattern)) => (crate::offset::parse_offset(offset)?, pattern),
+        None => (0, pattern),
+    };
+
+    if description.is_empty() {
+        return Err("missing description".to_string());
+    }
+
+    Signature::parse(offset, pattern, description)
+}
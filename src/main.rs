@@ -3,21 +3,42 @@
 // Licensed under MIT
 //
 
+mod commands;
+mod editor;
+mod terminal;
+mod watch;
+
+use commands::{CommandLine, Pending};
+use editor::{inflate, EditorState, OpenSettings, BYTES_PER_LINE, WHEEL_SCROLL_LINES};
+use hex_it::{
+    config::Config,
+    dump::{write_dump, write_json},
+    file::{self, FileKind, FileLock, LoadOptions},
+    hex::{bytes_to_hex, hex_to_bytes},
+    key::{Key, MouseEvent},
+    keymap::{Action, Mode},
+    line::InputLine,
+    pager::{self, pager_command, should_page, write_paged},
+    parse_offset, patchfile,
+    session::Session,
+    suggest::{did_you_mean, suggestions},
+    theme::{ColorChoice, Theme},
+    verify::Expected,
+    Document, DumpOptions,
+};
 use std::{
-    env,
-    fs::File,
-    io::{self, Read, Write},
-    path::PathBuf,
-    process,
-    sync::mpsc,
+    env, fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, mpsc},
     thread,
 };
-use tui_tools::{cls, getch, same_line_input, Colors};
+use terminal::{
+    exit_editor, install_panic_hook, install_signal_handlers, move_cursor_bottom, poll_key,
+    read_byte, read_key, setup_terminal, suspend, terminal_size, Input, Polled, MOUSE_ENABLED,
+};
 
 fn print_help(args: &[String], commands: &[(&str, char, &str)]) {
-    // clear the screen
-    cls();
-
     println!("Usage: {} [OPTIONS]\n", args[0]);
     println!("Commands:");
 
@@ -26,45 +47,184 @@ fn print_help(args: &[String], commands: &[(&str, char, &str)]) {
     }
 }
 
-fn get_file_arg(args: &[String], i: usize) -> PathBuf {
-    // Skip the file name!!
-    PathBuf::from(args[i + 2].clone())
-}
-
-fn print_unknown_command_error(arg: &String, commands: &[(&str, char, &str)]) {
-    let mut closest_match = (0, String::new());
-
-    for command in commands {
-        let distance = levenshtein_distance(arg, command.0);
-
-        if distance < closest_match.0 || closest_match.0 == 0 {
-            closest_match = (distance, command.0.to_string());
+fn get_value_arg(args: &[String], i: usize) -> String {
+    match args.get(i + 2) {
+        Some(value) => value.clone(),
+        None => {
+            eprintln!("Missing value for '{}'.", args[i + 1]);
+            std::process::exit(1);
         }
     }
+}
 
-    eprintln!(
-        "Unknown command '{}'. Did you mean '{}'?",
-        arg, closest_match.1
-    );
+fn print_unknown_command_error(arg: &str, commands: &[(&str, char, &str)]) {
+    // Options are compared without their dashes and suggested in the long form
+    let typed = arg.trim_start_matches('-');
+    let suggestions = suggestions(typed, commands.iter().map(|command| command.0))
+        .into_iter()
+        .map(|name| format!("--{}", name))
+        .collect::<Vec<String>>();
+    let suggestions = suggestions
+        .iter()
+        .map(|s| s.as_str())
+        .collect::<Vec<&str>>();
+
+    match did_you_mean(&suggestions) {
+        Some(hint) => eprintln!("Unknown command '{}'. {}", arg, hint),
+        None => eprintln!("Unknown command '{}'. Try --help.", arg),
+    }
 }
 
 struct CliArgs {
+    /// Colors the whole dump with the default theme
     colors: bool,
+    /// When colors are used at all
+    color: ColorChoice,
+    mouse: bool,
     file: PathBuf,
+    /// Bytes to read from a device, instead of asking it
+    size: Option<u64>,
+    readonly: bool,
+    /// Devices are read-only unless this is given
+    read_write: bool,
+    /// Edit even if another process has the file locked
+    force: bool,
+    /// A session file to restore
+    session: Option<PathBuf>,
+    /// Print the dump in this format and exit instead of opening the editor
+    dump: Option<DumpFormat>,
+    /// Runs of identical lines in the dump are a single `*` row
+    squeeze: bool,
+    /// Never page the dump
+    no_pager: bool,
+    /// Show what is inside gzip files
+    decompress: bool,
+    /// Bytes to write over the file at offsets, in order, instead of opening the editor. The
+    /// edits of patch files given with --apply are among them
+    writes: Vec<(usize, Vec<u8>)>,
+    /// Where the file goes with the writes applied, instead of back over it
+    out: Option<PathBuf>,
+    /// Digests the file has to have, checked instead of opening the editor
+    verify: Vec<Expected>,
+    /// Where the bytes --verify checks start
+    offset: Option<usize>,
+    /// How many bytes --verify checks
+    length: Option<usize>,
 }
 
-const COMMANDS: [(&str, char, &str); 3] = [
+/// What `--dump` prints
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DumpFormat {
+    Text,
+    Json,
+}
+
+const COMMANDS: [(&str, char, &str); 21] = [
     ("help", 'h', "Prints the help menu"),
-    ("colors", 'c', "Enables ansi colors"),
+    (
+        "colors",
+        'c',
+        "Colors the offsets, hex and text of the dump",
+    ),
+    (
+        "color",
+        'C',
+        "When to use colors: auto (on a terminal without NO_COLOR, the default), always or never",
+    ),
     ("path", 'p', "The file to edit"),
+    ("no-mouse", 'm', "Disables mouse support"),
+    (
+        "size",
+        's',
+        "How many bytes of a block or character device to read",
+    ),
+    ("readonly", 'r', "Opens the file without allowing edits"),
+    (
+        "read-write",
+        'w',
+        "Allows editing a block or character device, which is read-only otherwise",
+    ),
+    (
+        "force",
+        'f',
+        "Opens the file for editing even if another process has it locked",
+    ),
+    (
+        "session",
+        'S',
+        "Restores a session saved with 'session save', a file is optional then",
+    ),
+    (
+        "dump",
+        'd',
+        "Prints the dump and exits instead of opening the editor",
+    ),
+    (
+        "format",
+        'F',
+        "The format --dump prints, text or json. Implies --dump",
+    ),
+    (
+        "squeeze",
+        'q',
+        "Prints runs of identical lines in a text --dump as a single * row, like xxd",
+    ),
+    (
+        "no-pager",
+        'P',
+        "Prints a --dump longer than the terminal straight out instead of through $PAGER",
+    ),
+    (
+        "decompress",
+        'z',
+        "Shows what is inside a gzip compressed file instead of its bytes, in the editor and --dump",
+    ),
+    (
+        "write",
+        'W',
+        "Writes hex bytes over the file at an offset, like 1C4=7F, and exits. Can be given more than once",
+    ),
+    (
+        "apply",
+        'a',
+        "Writes the edits of a patch file over the file and exits, like --write for each line",
+    ),
+    (
+        "out",
+        'o',
+        "Where --write saves the changed file, instead of over the file itself",
+    ),
+    (
+        "verify",
+        'v',
+        "Checks the file has a digest, like sha256=ab34... or crc32=CBF43926, prints OK or MISMATCH and exits 0 or 1. Can be given more than once",
+    ),
+    ("offset", 'O', "Where the bytes --verify checks start"),
+    ("length", 'l', "How many bytes --verify checks"),
 ];
 
 fn get_args() -> CliArgs {
     let args: Vec<String> = env::args().collect();
 
     let mut cliargs = CliArgs {
-        colors: true,
+        colors: false,
+        color: ColorChoice::Auto,
+        mouse: true,
         file: PathBuf::new(),
+        size: None,
+        readonly: false,
+        read_write: false,
+        force: false,
+        session: None,
+        dump: None,
+        squeeze: false,
+        no_pager: false,
+        decompress: false,
+        writes: Vec::new(),
+        out: None,
+        verify: Vec::new(),
+        offset: None,
+        length: None,
     };
 
     let mut skip_next = false;
@@ -74,26 +234,156 @@ fn get_args() -> CliArgs {
             continue;
         }
 
+        // A long option can take its value after an equals sign, like --color=never
+        let (arg, mut inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+
         let mut found_command = false;
         for command in &COMMANDS {
-            if arg == &format!("-{}", command.1) || arg == &format!("--{}", command.0) {
+            if arg == format!("-{}", command.1) || arg == format!("--{}", command.0) {
                 found_command = true;
+
+                // The value comes after the equals sign or as the next argument
+                let mut value = || match inline_value.take() {
+                    Some(value) => value,
+                    None => {
+                        skip_next = true;
+                        get_value_arg(&args, i)
+                    }
+                };
+
                 match (command.0, command.1) {
                     ("help", 'h') => {
                         print_help(&args, &COMMANDS);
                         std::process::exit(0);
                     }
                     ("colors", 'c') => {
-                        cliargs.colors = false;
+                        cliargs.colors = true;
+                    }
+                    ("color", 'C') => {
+                        cliargs.color = match ColorChoice::parse(&value()) {
+                            Ok(color) => color,
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                std::process::exit(1);
+                            }
+                        };
                     }
                     ("path", 'p') => {
-                        skip_next = true;
-                        cliargs.file = get_file_arg(&args, i);
+                        cliargs.file = PathBuf::from(value());
+                    }
+                    ("no-mouse", 'm') => {
+                        cliargs.mouse = false;
+                    }
+                    ("size", 's') => {
+                        let size = value();
+                        match parse_offset(&size) {
+                            Ok(size) => cliargs.size = Some(size as u64),
+                            Err(e) => {
+                                eprintln!("Invalid size '{}': {}", size, e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    ("readonly", 'r') => {
+                        cliargs.readonly = true;
+                    }
+                    ("read-write", 'w') => {
+                        cliargs.read_write = true;
+                    }
+                    ("force", 'f') => {
+                        cliargs.force = true;
+                    }
+                    ("dump", 'd') => {
+                        cliargs.dump.get_or_insert(DumpFormat::Text);
+                    }
+                    ("format", 'F') => {
+                        let format = value();
+                        cliargs.dump = match format.as_str() {
+                            "text" => Some(DumpFormat::Text),
+                            "json" => Some(DumpFormat::Json),
+                            _ => {
+                                eprintln!("Unknown format '{}', expected text or json.", format);
+                                std::process::exit(1);
+                            }
+                        };
+                    }
+                    ("squeeze", 'q') => {
+                        cliargs.squeeze = true;
+                    }
+                    ("no-pager", 'P') => {
+                        cliargs.no_pager = true;
+                    }
+                    ("decompress", 'z') => {
+                        cliargs.decompress = true;
+                    }
+                    ("session", 'S') => {
+                        cliargs.session = Some(PathBuf::from(value()));
+                    }
+                    ("write", 'W') => {
+                        let write = value();
+                        match parse_write(&write) {
+                            Ok(write) => cliargs.writes.push(write),
+                            Err(e) => {
+                                eprintln!("Invalid write '{}': {}", write, e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    ("apply", 'a') => {
+                        let path = value();
+                        match fs::read_to_string(&path)
+                            .map_err(|e| e.to_string())
+                            .and_then(|text| patchfile::parse(&text))
+                        {
+                            Ok(edits) => cliargs
+                                .writes
+                                .extend(edits.into_iter().map(|edit| (edit.offset, edit.bytes))),
+                            Err(e) => {
+                                eprintln!("Can't apply {}: {}", path, e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    ("out", 'o') => {
+                        cliargs.out = Some(PathBuf::from(value()));
+                    }
+                    // Scripts tell a file that didn't verify (1) from a bad --verify (2)
+                    ("verify", 'v') => {
+                        let verify = value();
+                        match Expected::parse(&verify) {
+                            Ok(expected) => cliargs.verify.push(expected),
+                            Err(e) => {
+                                eprintln!("Invalid --verify '{}': {}", verify, e);
+                                std::process::exit(2);
+                            }
+                        }
+                    }
+                    ("offset", 'O') | ("length", 'l') => {
+                        let text = value();
+                        let parsed = match parse_offset(&text) {
+                            Ok(parsed) => parsed,
+                            Err(e) => {
+                                eprintln!("Invalid {} '{}': {}", command.0, text, e);
+                                std::process::exit(2);
+                            }
+                        };
+                        match command.1 {
+                            'O' => cliargs.offset = Some(parsed),
+                            _ => cliargs.length = Some(parsed),
+                        }
                     }
                     _ => {
                         unreachable!("Unknown command");
                     }
                 }
+
+                if inline_value.is_some() {
+                    eprintln!("'{}' doesn't take a value.", arg);
+                    std::process::exit(1);
+                }
             }
         }
 
@@ -103,395 +393,654 @@ fn get_args() -> CliArgs {
         }
     }
 
-    if cliargs.file.to_str().unwrap().is_empty() {
+    if cliargs.file.to_str().unwrap().is_empty() && cliargs.session.is_none() {
         eprintln!("No file specified.");
         std::process::exit(1);
     }
 
-    cliargs
-}
-
-fn hex_to_bytes(hex_string: &str) -> Option<Vec<u8>> {
-    let mut bytes = Vec::new();
-    let mut byte = 0;
-    let mut nibble_count = 0;
-
-    // Iterate over each char
-    for c in hex_string.chars() {
-        // Convert each char to a 4-bit integer (a nibble)
-        let nibble = match c.to_digit(16) {
-            // If the character is a valid, convert it to a u8
-            Some(nibble) => nibble as u8,
-            None => return None,
-        };
-
-        // Shift the current byte left by 4 bits and OR it with the nibble
-        byte = (byte << 4) | nibble;
-
-        // Increment the nibble count
-        nibble_count += 1;
-
-        // If we've processed two nibbles, push the byte to the output vector
-        // and reset the byte and nibble count
-        if nibble_count == 2 {
-            bytes.push(byte);
-            byte = 0;
-            nibble_count = 0;
-        }
+    if cliargs.out.is_some() && cliargs.writes.is_empty() {
+        eprintln!("--out is only for --write and --apply.");
+        std::process::exit(1);
     }
-
-    // If there are any remaining nibbles, the input is invalid, return None
-    if nibble_count != 0 {
-        None
-    } else {
-        // Otherwise, return the output vector of bytes
-        Some(bytes)
+    if !cliargs.writes.is_empty() && (cliargs.dump.is_some() || cliargs.decompress) {
+        eprintln!("--write can't be combined with --dump or --decompress.");
+        std::process::exit(1);
     }
-}
-
-fn levenshtein_distance(string1: &str, string2: &str) -> usize {
-    let len1 = string1.chars().count();
-    let len2 = string2.chars().count();
-    let mut matrix = vec![vec![0; len2 + 1]; len1 + 1];
-
-    for i in 0..=len1 {
-        matrix[i][0] = i;
+    let range_given = cliargs.offset.is_some() || cliargs.length.is_some();
+    if cliargs.verify.is_empty() && range_given {
+        eprintln!("--offset and --length are only for --verify.");
+        std::process::exit(1);
     }
-
-    for j in 0..=len2 {
-        matrix[0][j] = j;
+    if !cliargs.verify.is_empty() && (cliargs.dump.is_some() || !cliargs.writes.is_empty()) {
+        eprintln!("--verify can't be combined with --dump, --write or --apply.");
+        std::process::exit(2);
     }
-
-    for i in 1..=len1 {
-        for j in 1..=len2 {
-            let cost = if string1.chars().nth(i - 1) == string2.chars().nth(j - 1) {
-                0
-            } else {
-                1
-            };
-            matrix[i][j] = (matrix[i - 1][j] + 1) // deletion
-                .min(matrix[i][j - 1] + 1) // insertion
-                .min(matrix[i - 1][j - 1] + cost); // substitution
-
-            if i > 1
-                && j > 1
-                && string1.chars().nth(i - 1) == string2.chars().nth(j - 2)
-                && string1.chars().nth(i - 2) == string2.chars().nth(j - 1)
-            {
-                matrix[i][j] = matrix[i][j].min(matrix[i - 2][j - 2] + cost); // transposition
-            }
-        }
+    if !cliargs.verify.is_empty() && cliargs.file.as_os_str().is_empty() {
+        eprintln!("--verify needs a file, give it with --path.");
+        std::process::exit(2);
+    }
+    if !cliargs.writes.is_empty() && cliargs.file.as_os_str().is_empty() {
+        eprintln!("--write needs a file, give it with --path.");
+        std::process::exit(1);
     }
 
-    matrix[len1][len2]
+    cliargs
 }
 
-fn move_cursor_bottom(command_string: &str) {
-    let terminal_dimensions = term_size::dimensions().unwrap();
-    let mut stdout = io::stdout();
-
-    write!(
-        stdout,
-        "\x1b[{};{}H\x1b[2K{}",
-        terminal_dimensions.1, 0, command_string
-    )
-    .unwrap();
+/// Splits a `--write` value like 0x1C4=7F into the offset and the bytes to write there
+fn parse_write(write: &str) -> Result<(usize, Vec<u8>), String> {
+    let Some((offset, hex)) = write.split_once('=') else {
+        return Err("expected OFFSET=HEX, like 1C4=7F".to_string());
+    };
+    let offset = parse_offset(offset)?;
+    match hex_to_bytes(hex) {
+        Some(bytes) if !bytes.is_empty() => Ok((offset, bytes)),
+        Some(_) => Err("no bytes to write".to_string()),
+        None => Err(format!("'{}' isn't hex bytes", hex)),
+    }
 }
 
-#[derive(Clone)]
-struct EditorState {
-    file: PathBuf,
-    offset: i32,
-    colors: bool,
-    hex_lines: Vec<String>,
-    pretty_print: String,
-}
+/// Applies the writes of `--write` to the file in order and saves it to `--out`, or back over
+/// the file without it. Nothing is saved unless every write fits, returns the exit code
+fn apply_writes(args: &CliArgs, load: &LoadOptions) -> i32 {
+    let (path, out) = (args.file.as_path(), args.out.as_deref());
+    let (bytes, kind) = match file::load(path, load) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Can't open {}: {}", path.display(), e);
+            return 1;
+        }
+    };
 
-impl EditorState {
-    fn new(file: PathBuf, offset: i32, hex_lines: Vec<String>, colors: bool) -> EditorState {
-        EditorState {
-            file,
-            offset,
-            colors,
-            hex_lines,
-            pretty_print: String::new(),
+    // Writing back follows the rules of saving in the editor
+    if out.is_none() {
+        let refusal = match kind {
+            FileKind::Stream => Some("it is a stream, give --out to save the result"),
+            FileKind::Device if !args.read_write => Some("it is a device, add --read-write"),
+            _ if args.readonly => Some("it was opened with --readonly"),
+            _ => None,
+        };
+        if let Some(refusal) = refusal {
+            eprintln!("Can't write to {}: {}", path.display(), refusal);
+            return 1;
         }
     }
 
-    /// Parses the file into chunks of 16 bytes and returns them as a vector of strings
-    fn parse_file(&mut self) -> Vec<String> {
-        // Open the file
-        let mut file = File::open(self.file.clone()).expect("Failed to open file");
-
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)
-            .expect("Failed to read file contents");
-
-        // Split the contents into chunks of 16 bytes
-        for chunk in contents.as_bytes().chunks(16) {
-            let mut hex_chunk = String::new();
-
-            // Split each chunk into groups of 2 bytes and convert them to hex
-            for group in chunk.chunks(2) {
-                for byte in group {
-                    // Add the byte to the hex chunk with a space for pretty printing
-                    hex_chunk.push_str(&format!("{:02X} ", byte));
-                }
-            }
-            self.hex_lines.push(hex_chunk);
+    let mut document = Document::new(bytes);
+    let mut summary = Vec::new();
+    for (offset, bytes) in &args.writes {
+        let before = document
+            .bytes()
+            .get(*offset..offset.saturating_add(bytes.len()))
+            .map(bytes_to_hex);
+        if let Err(e) = document.overwrite(*offset, bytes) {
+            eprintln!("Can't write {} at {:X}: {}", bytes_to_hex(bytes), offset, e);
+            return 1;
         }
-
-        // Return the vector of hex lines ex: ["00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 48", "65 6C 6C 6F 20 77 6F 72 6C 64 21 20 20 20 20 20"]
-        self.hex_lines.clone()
+        summary.push(format!(
+            "{:08X}  {} -> {}",
+            offset,
+            before.unwrap_or_default(),
+            bytes_to_hex(bytes)
+        ));
     }
 
-    /// Generates the pretty printed message and returns it as a string
-    fn generate_message(&mut self) -> String {
-        let mut message = String::new();
-
-        // Reset the offset to 0
-        self.offset = 0;
-
-        for line in &self.hex_lines {
-            // Remove the spaces from the hex line
-            let pure_hex = line.replace(' ', "");
+    // Held until the file is written, like the editor holds it while editing
+    let _lock = match out {
+        Some(_) => None,
+        None => match FileLock::try_lock(path) {
+            Ok(None) if !args.force => {
+                eprintln!(
+                    "Another process has {} locked. Add --force to write anyway",
+                    path.display()
+                );
+                return 1;
+            }
+            Ok(lock) => lock,
+            Err(_) => None,
+        },
+    };
 
-            // Convert the hex line into bytes
-            let bytes = hex_to_bytes(pure_hex.as_str()).expect("Failed to decode hex string");
+    let result = match out {
+        Some(out) => file::save(out, document.bytes()),
+        None if kind == FileKind::Device => {
+            let ranges = args
+                .writes
+                .iter()
+                .map(|(offset, bytes)| *offset..offset + bytes.len())
+                .collect::<Vec<_>>();
+            file::save_in_place(path, document.bytes(), &ranges)
+        }
+        None => file::save(path, document.bytes()),
+    };
+    if let Err(e) = result {
+        eprintln!("Can't save {}: {}", out.unwrap_or(path).display(), e);
+        return 1;
+    }
 
-            // Enable ansi colors based on the flag
-            let newline_replacement = if self.colors {
-                ".".bold_black()
-            } else {
-                ".".to_string()
-            };
+    for line in summary {
+        println!("{}", line);
+    }
+    0
+}
 
-            let text = String::from_utf8(bytes)
-                .expect("Failed to convert bytes to string")
-                .replace('\n', newline_replacement.as_str());
+/// Checks the file, or the part of it given by --offset and --length, against every digest of
+/// `--verify`. Prints a line per digest and returns 0 when all of them match, 1 when one
+/// doesn't and 2 when the file can't be read
+fn verify(args: &CliArgs, load: &LoadOptions) -> i32 {
+    let path = &args.file;
+    let bytes = match file::load(path, load) {
+        Ok((bytes, _)) => bytes,
+        Err(e) => {
+            eprintln!("Can't open {}: {}", path.display(), e);
+            return 2;
+        }
+    };
+    let bytes = match inflate(bytes, args.decompress, load) {
+        Ok((bytes, _)) => bytes,
+        Err(e) => {
+            eprintln!("Can't decompress {}: {}", path.display(), e);
+            return 2;
+        }
+    };
 
-            message += &format!("{:08X}  {:<48}  {}\n", self.offset, line, text);
+    let offset = args.offset.unwrap_or(0);
+    let length = args
+        .length
+        .unwrap_or_else(|| bytes.len().saturating_sub(offset));
+    let Some(bytes) = offset
+        .checked_add(length)
+        .and_then(|end| bytes.get(offset..end))
+    else {
+        eprintln!(
+            "{:X} bytes at {:X} run past the end of {} ({:X} bytes)",
+            length,
+            offset,
+            path.display(),
+            bytes.len()
+        );
+        return 2;
+    };
 
-            // Increment the offset by 16 bytes for the next line
-            self.offset += 16;
+    let mut code = 0;
+    for expected in &args.verify {
+        match expected.check(bytes) {
+            Ok(()) => println!("OK {}={}", expected.algorithm, expected.digest),
+            Err(got) => {
+                println!(
+                    "MISMATCH expected {0}={1} got {0}={2}",
+                    expected.algorithm, expected.digest, got
+                );
+                code = 1;
+            }
         }
-
-        message
     }
+    code
+}
 
-    /// Prints the pretty printed message to the console
-    fn print(&mut self) {
-        // Generate the pretty printed message if it hasn't been generated yet
-        if self.pretty_print.is_empty() {
-            self.pretty_print = self.generate_message();
+/// Prints the dump of a file to stdout for `--dump`, returns the exit code. More than a
+/// screen of it on a terminal goes through the pager unless `no_pager` is set. A gzip file is
+/// dumped inflated when `decompress` is set
+fn print_dump(
+    path: &Path,
+    load: &LoadOptions,
+    format: DumpFormat,
+    options: &DumpOptions,
+    no_pager: bool,
+    decompress: bool,
+) -> i32 {
+    let bytes = match file::load(path, load) {
+        Ok((bytes, _)) => bytes,
+        Err(e) => {
+            eprintln!("Can't open {}: {}", path.display(), e);
+            return 1;
         }
+    };
+    let bytes = match inflate(bytes, decompress, load) {
+        Ok((bytes, _)) => bytes,
+        Err(e) => {
+            eprintln!("Can't decompress {}: {}", path.display(), e);
+            return 1;
+        }
+    };
 
-        print!("{}", self.pretty_print);
+    // Squeezed dumps can be shorter, paging one that would have fit does no harm
+    let lines = bytes.len().div_ceil(options.cols.max(1));
+    let (_, height) = terminal_size();
+    let command = should_page(io::stdout().is_terminal(), no_pager, lines, height)
+        .then(|| pager_command(env::var_os("PAGER").as_deref()))
+        .flatten();
+
+    let mut direct = io::stdout().lock();
+    let result = write_paged(command.as_deref(), pager::spawn, &mut direct, |out| {
+        let mut out = io::BufWriter::new(out);
+        match format {
+            DumpFormat::Text => write_dump(&mut out, 0, &bytes, options)?,
+            DumpFormat::Json => write_json(&mut out, &path.display().to_string(), &bytes, options)?,
+        }
+        out.flush()
+    });
 
-        // Print the command line area
-        println!("{}", "-".repeat(80));
+    match result {
+        Ok(_) => 0,
+        // Piping into head and the like closes stdout early, that's not a problem
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(e) => {
+            eprintln!("Can't write the dump: {}", e);
+            1
+        }
     }
 }
 
-struct CommandLine {
-    editor: EditorState,
-    command_names: Vec<(String, String)>,
-}
+fn main() {
+    // Get command line arguments
+    let args = get_args();
 
-impl CommandLine {
-    fn new(editor: EditorState) -> CommandLine {
-        let commands = vec![
-            ("help", "Prints the help menu"),
-            ("quit", "Quit the menu"),
-            ("get", "Get a line of hex and be able to edit it."),
-            ("save", "Save the file"),
-        ];
-
-        CommandLine {
-            editor,
-            command_names: commands
-                .iter()
-                .map(|(name, description)| (name.to_string(), description.to_string()))
-                .collect(),
+    let (config, config_errors) = Config::load();
+    // Colors are decided once here, the dump, the editor and exports all go by it
+    let colors = args.color.enabled(env::var_os("NO_COLOR").as_deref(), || {
+        io::stdout().is_terminal()
+    });
+    let theme = (colors && (args.colors || !config.palette.is_empty())).then(|| {
+        let mut theme = Theme::default();
+        for (name, color) in &config.palette {
+            // The names were checked when the config was read
+            let _ = theme.set(name, color);
         }
+        theme
+    });
+
+    if !args.verify.is_empty() {
+        let options = LoadOptions {
+            size: args.size,
+            stream_limit: config.stream_limit,
+        };
+        std::process::exit(verify(&args, &options));
     }
 
-    fn new_command(&mut self, command: String) {
-        let args = command
-            .split(' ')
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
+    if !args.writes.is_empty() {
+        let options = LoadOptions {
+            size: args.size,
+            stream_limit: config.stream_limit,
+        };
+        std::process::exit(apply_writes(&args, &options));
+    }
 
-        self.parse_command(args);
+    if let Some(format) = args.dump {
+        let options = LoadOptions {
+            size: args.size,
+            stream_limit: config.stream_limit,
+        };
+        let dump = DumpOptions {
+            colors,
+            theme,
+            squeeze: args.squeeze,
+            gutter: config.gutter,
+            ..DumpOptions::default()
+        };
+        std::process::exit(print_dump(
+            &args.file,
+            &options,
+            format,
+            &dump,
+            args.no_pager,
+            args.decompress,
+        ));
     }
 
-    fn parse_command(&mut self, args: Vec<String>) {
-        match args[0].to_ascii_lowercase().as_str() {
-            "help" => {
-                println!("Commands:");
+    // Create a channel for sending keypresses from the input thread to the main thread,
+    // file watchers report on it too
+    let (tx, rx) = mpsc::channel();
 
-                for command in &self.command_names {
-                    println!("{} - {}", command.0, command.1);
-                }
+    let mut settings = OpenSettings {
+        load: LoadOptions {
+            size: args.size,
+            stream_limit: config.stream_limit,
+        },
+        colors,
+        theme,
+        gutter: config.gutter,
+        readonly: args.readonly,
+        read_write: args.read_write,
+        force: args.force,
+        decompress: args.decompress,
+        signatures: config.signatures,
+        watch: Some(tx.clone()),
+        journal: config.journal,
+    };
 
-                same_line_input("Press enter to continue: ");
-            }
-            "quit" => {
-                std::process::exit(0);
+    // A session is read before taking over the terminal too
+    let session = args.session.as_ref().map(|path| {
+        match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| Session::parse(&contents))
+        {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                eprintln!("Can't load the session {}: {}", path.display(), e);
+                std::process::exit(1);
             }
-            "get" => {
-                if let Some(line) = args.get(1) {
-                    if line.is_empty() {
-                        return eprintln!("No line specified.");
-                    }
-
-                    // Convert the hex string to a decimal value
-                    let decimal_value = i32::from_str_radix(line, 16).unwrap_or_else(|e| {
-                        eprintln!("Invalid value '{line}': {e}");
-                        std::process::exit(1);
-                    }) / 16;
-
-                    // Check if the line is out of range
-                    if decimal_value as usize > self.editor.hex_lines.len() {
-                        return eprintln!("Line out of range.");
-                    }
+        }
+    });
 
-                    let line_found = self.editor.hex_lines[decimal_value as usize].clone();
+    // Without --path the editor starts on the first file of the session that still opens,
+    // the session puts the rest back once the editor is running
+    let paths = match &session {
+        Some((session, _)) if args.file.as_os_str().is_empty() => session
+            .buffers
+            .iter()
+            .map(|buffer| buffer.path.clone())
+            .collect(),
+        _ => vec![args.file],
+    };
 
-                    println!("{}", line_found);
+    // Read the file before taking over the terminal, so problems are reported on a normal screen
+    let mut errors = Vec::new();
+    let Some(editor) = paths.into_iter().find_map(|path| {
+        EditorState::open(path, &settings)
+            .map_err(|e| errors.push(e))
+            .ok()
+    }) else {
+        if errors.is_empty() {
+            eprintln!("The session has no files.");
+        }
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        std::process::exit(1);
+    };
 
-                    println!("{}", "-".repeat(80));
+    // --size is for the device given on the command line, not for files opened later
+    settings.load.size = None;
 
-                    let input = same_line_input("");
+    MOUSE_ENABLED.store(args.mouse, Ordering::Relaxed);
+    install_panic_hook();
+    setup_terminal();
+    install_signal_handlers();
 
-                    if input.is_empty() {
-                        return;
-                    }
+    if let Some(interval) = config.autosave {
+        watch::autosave_timer(interval, tx.clone());
+    }
 
-                    // Replace the line with the new input
-                    self.editor.hex_lines[decimal_value as usize] = input;
+    let input_thread = thread::spawn(move || {
+        while let Some(byte) = read_byte() {
+            let input = match byte {
+                Some(byte) => Input::Byte(byte),
+                None => Input::Resumed,
+            };
 
-                    // Regenerate the pretty print
-                    self.editor.pretty_print = self.editor.generate_message();
-                } else {
-                    eprintln!("No line specified.")
-                }
+            if tx.send(input).is_err() {
+                break;
             }
-            "save" => {
-                let file_path = args
-                    .get(1)
-                    .unwrap_or(&self.editor.file.to_str().unwrap().to_string())
-                    .clone();
-
-                let mut file = File::create(&file_path).unwrap();
+        }
 
-                let contents = self
-                    .editor
-                    .hex_lines
-                    .iter()
-                    .map(|line| {
-                        let pure_hex = line.replace(' ', "");
-                        // Converts the hex line into bytes
-                        let bytes = hex_to_bytes(pure_hex.as_str()).unwrap();
+        // The watchers keep the channel open, so the main loop has to be told
+        let _ = tx.send(Input::Closed);
+    });
 
-                        String::from_utf8(bytes).unwrap()
-                    })
-                    .collect::<String>();
+    // Initialize command line state, it owns the editor from here on
+    let mut input_line = InputLine::default();
+    let mut command_line = CommandLine::new(editor, config.keymap, settings);
 
-                file.write_all(contents.as_bytes()).unwrap();
+    let session_errors = match session {
+        Some((session, mut errors)) => {
+            errors.extend(command_line.restore(&session));
+            errors
+        }
+        None => Vec::new(),
+    };
 
-                println!(
-                    "Saved to {}",
-                    PathBuf::from(file_path)
-                        .canonicalize()
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                );
+    // Changes a crash left behind come before anything else
+    command_line.offer_recovery();
+
+    let editor = &mut command_line.editor;
+    editor.message = [editor.message.clone()]
+        .into_iter()
+        .chain(config_errors)
+        .chain(session_errors)
+        .filter(|message| !message.is_empty())
+        .collect::<Vec<String>>()
+        .join("; ");
+    command_line.draw(&input_line);
+    let mut stdout = io::stdout();
 
-                std::process::exit(0);
-            }
-            _ if args[0].is_empty() => {}
-            _ => {
-                let mut closest_match = (0, String::new());
-                for command in &self.command_names {
-                    let distance = levenshtein_distance(&args[0], &command.0);
-
-                    if distance < closest_match.0 || closest_match.0 == 0 {
-                        closest_match = (distance, command.0.clone());
-                    }
+    // cursor-top needs two presses in a row, like gg in vim
+    let mut top_pressed = false;
+
+    // Main loop, sleeps until a key arrives
+    let exit_code = loop {
+        // A long search runs a slice at a time between keys, showing how far it got
+        let key = if command_line.searching() {
+            match poll_key(&rx) {
+                Polled::Key(key) => key,
+                Polled::Nothing => {
+                    command_line.continue_search();
+                    command_line.draw(&input_line);
+                    continue;
                 }
-
-                println!(
-                    "Unknown command '{}'. Did you mean '{}'?",
-                    args[0], closest_match.1
-                );
-
-                same_line_input("type 'help' for a list of commands. press enter to continue:");
+                Polled::Closed => break 0,
             }
+        } else {
+            // The input thread only stops on its own when stdin is closed
+            let Some(key) = read_key(&rx) else {
+                break 0;
+            };
+            key
+        };
+
+        if key == Key::Resume {
+            // Stopped and continued from outside, the shell has had the terminal in the meantime
+            setup_terminal();
+        } else if key == Key::FileChanged {
+            command_line.check_disk();
+        } else if key == Key::JobUpdate {
+            command_line.update_job();
+        } else if key == Key::Autosave {
+            command_line.autosave();
+        } else if let Some(Pending::Continue) = command_line.pending {
+            // Any key shows the next page of a listing, Esc or the last page brings the dump back
+            let action = command_line.keymap.action(Mode::Command, &key);
+            command_line.page_listing(action == Some(Action::Cancel));
+            command_line.draw(&input_line);
+            continue;
         }
-    }
-}
 
-fn main() {
-    // Get command line arguments
-    let args = get_args();
+        let page = command_line.editor.visible_lines() as isize;
+        let mode = command_line.editor.mode;
+        let action = command_line.keymap.action(mode, &key);
 
-    // Create a channel for sending keypresses from the main thread to the getch thread
-    let (tx, rx) = mpsc::channel();
-    let getch_thread = thread::spawn(move || loop {
-        let key = getch();
-        if tx.send(key).is_err() || key == 27 || key == 3 {
-            break;
+        if action != Some(Action::CursorTop) {
+            top_pressed = false;
         }
-        thread::sleep(std::time::Duration::from_millis(10));
-    });
 
-    // Initialize editor state
-    let mut editor = EditorState::new(args.file, 0, Vec::new(), args.colors);
-    editor.hex_lines = editor.parse_file();
-    editor.print();
+        // Nothing but stopping it goes while a search is reading the buffer
+        if command_line.searching()
+            && !matches!(
+                key,
+                Key::Resume | Key::FileChanged | Key::JobUpdate | Key::Autosave
+            )
+        {
+            if matches!(action, Some(Action::Cancel) | Some(Action::Interrupt)) {
+                command_line.cancel_search();
+                command_line.draw(&input_line);
+            }
+            continue;
+        }
 
-    // Initialize command line state
-    let mut command_string = String::new();
-    let mut command_line = CommandLine::new(editor.clone());
-    let mut stdout = io::stdout();
+        match action {
+            Some(Action::Cancel) => {
+                // Esc backs out one step at a time: typed input or a pending command,
+                // then a background job, then the selection, then the editor
+                if mode == Mode::Command {
+                    input_line.clear();
+                    command_line.cancel();
+                    command_line.editor.mode = Mode::Normal;
+                } else if matches!(mode, Mode::Overwrite | Mode::Text) {
+                    command_line.editor.mode = Mode::Normal;
+                    command_line.editor.document.end_undo_group();
+                } else if !command_line.cancel_job()
+                    && command_line.editor.selection.take().is_none()
+                {
+                    command_line.request_quit(0);
+                }
+            }
+            Some(Action::Interrupt) => {
+                input_line.clear();
+                command_line.interrupt();
+            }
+            Some(Action::Quit) => {
+                command_line.request_quit(0);
+            }
+            Some(Action::Submit) => {
+                command_line.new_command(input_line.take());
 
-    // Main loop
-    loop {
-        if let Ok(key) = rx.try_recv() {
-            match key {
-                27 => {
-                    break;
+                // Commands like edit switch to another mode themselves
+                if command_line.editor.mode == Mode::Command {
+                    command_line.editor.mode = Mode::Normal;
                 }
-                3 => {
-                    process::exit(0);
+            }
+            Some(Action::OverwriteMode) => {
+                command_line.editor.enter_overwrite_mode();
+            }
+            Some(Action::TextMode) => {
+                command_line.editor.enter_text_mode();
+            }
+            Some(Action::Undo) => {
+                if !command_line.editor.undo() {
+                    command_line.editor.message = "Nothing to undo.".to_string();
                 }
-                13 => {
-                    editor.print();
-                    command_line.new_command(command_string.clone());
-                    editor = command_line.editor.clone();
-                    editor.print();
-                    command_string.clear();
+            }
+            Some(Action::CommandMode) => {
+                command_line.editor.mode = Mode::Command;
+            }
+            Some(Action::Backspace) => input_line.backspace(),
+            Some(Action::Delete) => input_line.delete(),
+            Some(Action::Left) => input_line.left(),
+            Some(Action::Right) => input_line.right(),
+            Some(Action::LineStart) => input_line.home(),
+            Some(Action::LineEnd) => input_line.end(),
+            Some(Action::WordLeft) => input_line.word_left(),
+            Some(Action::WordRight) => input_line.word_right(),
+            Some(Action::DeleteWord) => input_line.delete_word(),
+            Some(Action::DeleteToStart) => input_line.delete_to_start(),
+            Some(Action::Complete) => command_line.complete(&mut input_line),
+            Some(Action::ScrollUp) => command_line.editor.scroll(-1),
+            Some(Action::ScrollDown) => command_line.editor.scroll(1),
+            Some(Action::PageUp) => command_line.editor.scroll(-page),
+            Some(Action::PageDown) => command_line.editor.scroll(page),
+            Some(Action::CursorLeft) => command_line.editor.move_cursor(-1),
+            Some(Action::CursorRight) => command_line.editor.move_cursor(1),
+            Some(Action::CursorUp) => command_line.editor.move_cursor(-(BYTES_PER_LINE as isize)),
+            Some(Action::CursorDown) => command_line.editor.move_cursor(BYTES_PER_LINE as isize),
+            Some(Action::CursorTop) => {
+                if top_pressed {
+                    command_line.editor.move_cursor(isize::MIN);
                 }
-                _ => {
-                    if (key > 64 && key < 90)
-                        || (key > 96 && key < 123)
-                        || (key > 47 && key < 58)
-                        || key == 32
-                    {
-                        command_string.push(key as char);
+                top_pressed = !top_pressed;
+            }
+            Some(Action::CursorBottom) => command_line.editor.move_cursor(isize::MAX),
+            Some(Action::Select) => {
+                let editor = &mut command_line.editor;
+                editor.selection = match editor.selection {
+                    Some(_) => None,
+                    None => Some(editor.cursor),
+                };
+            }
+            Some(Action::NextMatch) => command_line.editor.jump_to_match(true),
+            Some(Action::PreviousMatch) => command_line.editor.jump_to_match(false),
+            Some(Action::SkipRun) => command_line.skip_run(None, false),
+            Some(Action::SkipRunBack) => command_line.skip_run(None, true),
+            Some(Action::Yank) => command_line.yank(&[]),
+            Some(Action::Put) => command_line.put(&[]),
+            Some(Action::Suspend) => {
+                suspend();
+                setup_terminal();
+            }
+            None => match &key {
+                Key::Char(c) if mode == Mode::Command => input_line.insert(*c),
+                Key::Char(c) if mode == Mode::Overwrite => {
+                    if let Some(digit) = c.to_digit(16) {
+                        command_line.editor.type_nibble(digit as u8);
                     }
-                    if key == 8 {
-                        command_string.pop();
+                }
+                Key::Char(c) if mode == Mode::Text => {
+                    command_line.editor.type_char(*c);
+                }
+                // A paste types its chars one after the other, stopping at one that can't be typed
+                Key::Paste(text) if mode == Mode::Text => {
+                    let editor = &mut command_line.editor;
+                    for c in text.chars() {
+                        if !editor.type_char(c) {
+                            break;
+                        }
                     }
-                    // move the cursor to the bottom of the screen
-                    move_cursor_bottom(&command_string);
-                    stdout.flush().unwrap();
                 }
-            }
+                Key::Paste(text) if mode == Mode::Command => {
+                    // A paste is one insertion, line breaks become spaces so nothing gets run early
+                    for c in text.replace("\r\n", " ").chars() {
+                        match c {
+                            '\r' | '\n' | '\t' => input_line.insert(' '),
+                            c if !c.is_control() => input_line.insert(c),
+                            _ => {}
+                        }
+                    }
+                }
+                Key::Mouse(event) => {
+                    let editor = &mut command_line.editor;
+
+                    match *event {
+                        MouseEvent::WheelUp => editor.scroll(-(WHEEL_SCROLL_LINES as isize)),
+                        MouseEvent::WheelDown => editor.scroll(WHEEL_SCROLL_LINES as isize),
+                        MouseEvent::Press {
+                            button: 0,
+                            column,
+                            row,
+                        } => {
+                            // Clicks outside the dump, like on the status or command line, do nothing
+                            if let Some(offset) = editor.offset_at(column, row) {
+                                editor.cursor = offset;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            },
+        }
+
+        command_line.flush_journals();
+
+        if let Some(code) = command_line.exit_code {
+            break code;
         }
+
+        // A command waiting on input needs the command line, whichever mode it was started from
+        if command_line.pending.is_some() {
+            command_line.editor.mode = Mode::Command;
+        }
+
+        // Typing only changes the command line, everything else can change the dump or status bar
+        let typing = mode == Mode::Command
+            && command_line.editor.mode == Mode::Command
+            && !matches!(action, Some(Action::Submit) | Some(Action::Interrupt))
+            && !matches!(
+                key,
+                Key::Mouse(_) | Key::Resume | Key::FileChanged | Key::JobUpdate | Key::Autosave
+            );
+        if !typing {
+            command_line.draw(&input_line);
+        } else {
+            // Only the command line changed
+            move_cursor_bottom(command_line.prompt(), &input_line);
+            stdout.flush().unwrap();
+        }
+    };
+
+    // Quitting means the changes were saved or given up on. A closed terminal leaves the
+    // recovery files for next time
+    if command_line.exit_code.is_some() {
+        command_line.remove_recovery_files();
     }
 
-    getch_thread.join().unwrap();
+    exit_editor(input_thread, exit_code);
 }
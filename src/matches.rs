@@ -0,0 +1,118 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Writing the matches of the last search to a file for `matches export`, as CSV or JSON. Each
+//! match is written as it is reached, so a search with a hundred thousand matches never sits in
+//! memory as one big string
+
+use crate::{dump::json_string, editlog::csv_field};
+use std::io::{self, Write};
+
+/// Bytes of context kept on either side of a match
+pub const CONTEXT: usize = 8;
+
+/// The first line of a CSV export
+pub const CSV_HEADER: &str = "search,offset_hex,offset,length,context_offset,context\n";
+
+/// The file formats matches can be exported as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchFormat {
+    /// A header line and a line per match
+    Csv,
+    /// An object with the search and an array of matches
+    Json,
+}
+
+impl MatchFormat {
+    /// Reads the name given to --format
+    pub fn parse(name: &str) -> Option<MatchFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(MatchFormat::Csv),
+            "json" => Some(MatchFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// The format a file name asks for, JSON for `.json` and CSV for anything else
+    pub fn for_path(path: &str) -> MatchFormat {
+        match path.to_ascii_lowercase().ends_with(".json") {
+            true => MatchFormat::Json,
+            false => MatchFormat::Csv,
+        }
+    }
+}
+
+/// The matches of a search, with what they were matched in
+#[derive(Clone, Copy, Debug)]
+pub struct MatchList<'a> {
+    /// What was searched for, as typed
+    pub search: &'a str,
+    /// The bytes that were searched
+    pub bytes: &'a [u8],
+    /// Where each match starts, in order
+    pub offsets: &'a [usize],
+    /// How many bytes each match covers
+    pub width: usize,
+}
+
+impl MatchList<'_> {
+    /// Where the context of a match starts and its bytes as hex, CONTEXT bytes either side of
+    /// it or as many as there are
+    fn context(&self, offset: usize) -> (usize, String) {
+        let start = offset.saturating_sub(CONTEXT).min(self.bytes.len());
+        let end = offset
+            .saturating_add(self.width)
+            .saturating_add(CONTEXT)
+            .min(self.bytes.len());
+
+        let hex = self.bytes[start..end]
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<String>();
+        (start, hex)
+    }
+
+    /// Writes the matches as CSV, under CSV_HEADER
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(CSV_HEADER.as_bytes())?;
+
+        let search = csv_field(self.search);
+        for &offset in self.offsets {
+            let (start, context) = self.context(offset);
+            writeln!(
+                out,
+                "{search},{offset:08X},{offset},{},{start},{context}",
+                self.width
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the matches as a JSON object, with the name of the file they were found in
+    pub fn write_json<W: Write>(&self, out: &mut W, name: &str) -> io::Result<()> {
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"file\": {},", json_string(name))?;
+        writeln!(out, "  \"search\": {},", json_string(self.search))?;
+        writeln!(out, "  \"count\": {},", self.offsets.len())?;
+        write!(out, "  \"matches\": [")?;
+
+        for (index, &offset) in self.offsets.iter().enumerate() {
+            let (start, context) = self.context(offset);
+            write!(
+                out,
+                "{}\n    {{\"offset_hex\": \"{offset:08X}\", \"offset\": {offset}, \"length\": {}, \"context_offset\": {start}, \"context\": \"{context}\"}}",
+                if index == 0 { "" } else { "," },
+                self.width
+            )?;
+        }
+
+        if !self.offsets.is_empty() {
+            write!(out, "\n  ")?;
+        }
+        writeln!(out, "]")?;
+        writeln!(out, "}}")
+    }
+}
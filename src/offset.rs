@@ -0,0 +1,220 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Offsets typed by the user, like "1F0", "0x1F0 + 16" or "cursor + 4 * 0d10", and sizes
+//! like "512" or "4k"
+
+use std::ops::Range;
+
+/// Parses a number literal. Bare digits are in `radix`, hex for offsets like in the dump,
+/// 0x, 0o and 0b pick a base explicitly and 0d makes it decimal
+fn parse_number(literal: &str, radix: u32) -> Result<usize, String> {
+    let lower = literal.to_ascii_lowercase();
+    let (digits, radix) = match lower.get(..2) {
+        Some("0x") => (&lower[2..], 16),
+        Some("0o") => (&lower[2..], 8),
+        Some("0b") => (&lower[2..], 2),
+        Some("0d") => (&lower[2..], 10),
+        _ => (lower.as_str(), radix),
+    };
+
+    // Underscores can split up long numbers
+    let digits = digits.replace('_', "");
+
+    usize::from_str_radix(&digits, radix).map_err(|e| match e.kind() {
+        std::num::IntErrorKind::Empty => format!("missing number in '{}'", literal),
+        std::num::IntErrorKind::PosOverflow => format!("'{}' is too large", literal),
+        _ => format!("invalid number '{}'", literal),
+    })
+}
+
+/// What the names in an expression stand for, in the editor. Offsets typed elsewhere, like
+/// in a session file, can't use any
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Variables {
+    /// `cursor`, the offset of the cursor
+    pub cursor: usize,
+    /// `len`, the length of the buffer
+    pub len: usize,
+    /// `sel.start` and `sel.len`, None without a selection
+    pub selection: Option<Range<usize>>,
+}
+
+/// The names an expression can use
+const NAMES: &str = "cursor, len, sel.start and sel.len";
+
+/// A piece of an expression
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// A number or a name, told apart when it is evaluated
+    Word(String),
+    Operator(char),
+}
+
+/// An expression being evaluated, one token at a time
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    variables: Option<&'a Variables>,
+    /// The whole expression, for errors
+    expr: &'a str,
+}
+
+impl Parser<'_> {
+    /// The tokens from here on, combined by operators that bind at least as tightly as
+    /// `precedence`: 1 for + and -, 2 for *, / and %
+    fn expression(&mut self, precedence: u8) -> Result<usize, String> {
+        let mut value = self.operand()?;
+
+        while let Some(Token::Operator(operator)) = self.tokens.get(self.position) {
+            let operator = *operator;
+            let binds = match operator {
+                '+' | '-' => 1,
+                '*' | '/' | '%' => 2,
+                _ => break,
+            };
+            if binds < precedence {
+                break;
+            }
+
+            self.position += 1;
+            // Operators of the same precedence go left to right, so the right side only takes
+            // the ones that bind tighter
+            let right = self.expression(binds + 1)?;
+            value = match operator {
+                '+' => value.checked_add(right).ok_or("the result is too large")?,
+                '-' => value.checked_sub(right).ok_or("the result is below zero")?,
+                '*' => value.checked_mul(right).ok_or("the result is too large")?,
+                '/' => value.checked_div(right).ok_or("division by zero")?,
+                _ => value.checked_rem(right).ok_or("division by zero")?,
+            };
+        }
+
+        Ok(value)
+    }
+
+    /// A number, a name or an expression in parentheses
+    fn operand(&mut self) -> Result<usize, String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+
+        match token {
+            Some(Token::Word(word)) => self.word(&word),
+            Some(Token::Operator('(')) => {
+                let value = self.expression(1)?;
+                match self.tokens.get(self.position) {
+                    Some(Token::Operator(')')) => {
+                        self.position += 1;
+                        Ok(value)
+                    }
+                    _ => Err(format!("missing ) in '{}'", self.expr)),
+                }
+            }
+            _ => Err(format!("missing number in '{}'", self.expr)),
+        }
+    }
+
+    /// The value of a number or a name. Names win over hex numbers that look the same
+    fn word(&self, word: &str) -> Result<usize, String> {
+        let lower = word.to_ascii_lowercase();
+        let variables = self.variables;
+
+        let name = |value: Option<usize>| {
+            value.ok_or_else(|| match variables {
+                Some(_) => format!("nothing is selected for {lower}"),
+                None => format!("names like {lower} can't be used here"),
+            })
+        };
+        let selection = variables.and_then(|variables| variables.selection.clone());
+
+        match lower.as_str() {
+            "cursor" => name(variables.map(|variables| variables.cursor)),
+            "len" => name(variables.map(|variables| variables.len)),
+            "sel.start" => name(selection.map(|range| range.start)),
+            "sel.len" => name(selection.map(|range| range.len())),
+            _ if word.starts_with(|c: char| c.is_ascii_digit())
+                || word.chars().all(|c| c.is_ascii_hexdigit() || c == '_') =>
+            {
+                parse_number(word, 16)
+            }
+            _ => Err(format!("unknown name '{word}', there are {NAMES}")),
+        }
+    }
+}
+
+/// Splits an expression into numbers, names and operators
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '+' | '-' | '*' | '/' | '%' | '(' | ')' => {
+                tokens.push(Token::Operator(c));
+                chars.next();
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' || c == '.' => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            c => return Err(format!("unexpected '{c}' in '{}'", expr.trim())),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Evaluates an expression of numbers, names, parentheses and the operators + - * / %, where
+/// multiplying and dividing come before adding. Bare digits are hex like in the dump, 0d makes
+/// them decimal. Nothing can go below zero, overflow and division by zero are errors. Without
+/// variables names are errors too
+pub fn evaluate(expr: &str, variables: Option<&Variables>) -> Result<usize, String> {
+    let mut parser = Parser {
+        tokens: tokenize(expr)?,
+        position: 0,
+        variables,
+        expr: expr.trim(),
+    };
+
+    let value = parser.expression(1)?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(value),
+        Some(Token::Operator(')')) => Err(format!("unmatched ) in '{}'", expr.trim())),
+        Some(_) => Err(format!("expected an operator in '{}'", expr.trim())),
+    }
+}
+
+/// Parses an offset expression, like "1F0", "0x1F0 + 16" or "(10 + 4) * 0d3". The result
+/// can't go below zero
+pub fn parse_offset(expr: &str) -> Result<usize, String> {
+    evaluate(expr, None)
+}
+
+/// Parses a size, like "512", "0x200" or "4k". Unlike offsets bare digits are decimal, since
+/// that is how sizes are usually counted. A k, M or G at the end multiplies by 1024, 1024²
+/// or 1024³
+pub fn parse_size(text: &str) -> Result<usize, String> {
+    let text = text.trim();
+    let (number, multiplier) = match text.char_indices().last() {
+        Some((index, 'k' | 'K')) => (&text[..index], 1 << 10),
+        Some((index, 'm' | 'M')) => (&text[..index], 1 << 20),
+        Some((index, 'g' | 'G')) => (&text[..index], 1 << 30),
+        _ => (text, 1),
+    };
+
+    parse_number(number, 10)?
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("'{}' is too large", text))
+}
@@ -0,0 +1,82 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Paging `--dump` output the way git does: through $PAGER, or `less -R` so colors survive,
+//! when it goes to a terminal and is longer than a screen. Starting the pager is passed in,
+//! so what happens when it can't start is tested without one
+
+use std::{
+    ffi::OsStr,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// A pager that is running: what is written to `input` is shown, `wait` waits for the user
+/// to quit it
+pub struct Paging {
+    /// The pager's stdin
+    pub input: Box<dyn Write>,
+    /// Closes nothing, just waits for the pager to exit
+    pub wait: Box<dyn FnOnce() -> io::Result<()>>,
+}
+
+/// Whether output of `lines` lines goes through a pager: only into a terminal of `height`
+/// rows it doesn't fit on, and not with --no-pager
+pub fn should_page(is_tty: bool, no_pager: bool, lines: usize, height: usize) -> bool {
+    is_tty && !no_pager && lines > height
+}
+
+/// The command to page with from the value of $PAGER. An empty one or `cat` means no paging,
+/// anything else runs through the shell like git does, so it can have arguments
+pub fn pager_command(pager: Option<&OsStr>) -> Option<Vec<String>> {
+    match pager.map(|pager| pager.to_string_lossy().trim().to_string()) {
+        None => Some(vec!["less".to_string(), "-R".to_string()]),
+        Some(pager) if pager.is_empty() || pager == "cat" => None,
+        Some(pager) => Some(vec!["sh".to_string(), "-c".to_string(), pager]),
+    }
+}
+
+/// Starts a pager with its stdin piped to us
+pub fn spawn(command: &[String]) -> io::Result<Paging> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no pager command"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    let input = child.stdin.take().ok_or(io::ErrorKind::BrokenPipe)?;
+
+    Ok(Paging {
+        input: Box::new(input),
+        wait: Box::new(move || child.wait().map(|_| ())),
+    })
+}
+
+/// Writes output through a pager started by `spawn` with `command`, or straight to `direct`
+/// when there is no command or the pager can't be started. Returns whether a pager was used.
+/// The pager is always waited on, and a pager quit before everything was written isn't an error
+pub fn write_paged(
+    command: Option<&[String]>,
+    spawn: impl FnOnce(&[String]) -> io::Result<Paging>,
+    direct: &mut dyn Write,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<bool> {
+    let Some(Ok(paging)) = command.map(spawn) else {
+        write(direct)?;
+        return direct.flush().map(|()| false);
+    };
+
+    let Paging { mut input, wait } = paging;
+    let written = write(&mut input).and_then(|()| input.flush());
+    // Closing its stdin is what tells the pager the output ended
+    drop(input);
+    let waited = wait();
+
+    match written {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e),
+        _ => waited.map(|()| true),
+    }
+}
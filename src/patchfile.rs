@@ -0,0 +1,136 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Patch files, bytes to write over a file as plain text, a line per edit:
+//!
+//! ```text
+//! # hex_it patch, 2 edits
+//! 1C4: 7F
+//! 200: DE AD BE EF  # the magic
+//! ```
+//!
+//! Offsets are read like `goto` reads them, the bytes are hex with spaces allowed between
+//! them and `#` starts a comment. `patchfile apply` and `--apply` read them, `patchfile create`
+//! writes the unsaved changes of a buffer as one
+
+use crate::{
+    document::Modification,
+    hex::{bytes_to_hex, hex_to_bytes},
+    offset::parse_offset,
+};
+use std::fmt::Write;
+
+/// One line of a patch file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit {
+    /// The line it is on, counted from 1
+    pub line: usize,
+    /// Where the bytes go
+    pub offset: usize,
+    /// The bytes written there
+    pub bytes: Vec<u8>,
+}
+
+impl Edit {
+    /// The offset just past the bytes, None if it doesn't fit in an offset
+    pub fn end(&self) -> Option<usize> {
+        self.offset.checked_add(self.bytes.len())
+    }
+}
+
+/// Reads the edits of a patch file in order. Every line is read before any error is returned,
+/// the error names the first line that is wrong and how many are
+pub fn parse(text: &str) -> Result<Vec<Edit>, String> {
+    let mut edits = Vec::new();
+    let mut errors = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let content = line.split('#').next().unwrap_or_default().trim();
+        if content.is_empty() {
+            continue;
+        }
+
+        let result = match content.split_once(':') {
+            None => Err("expected 'offset: hex bytes'".to_string()),
+            Some((offset, hex)) => {
+                parse_offset(offset.trim()).and_then(|offset| match hex_to_bytes(hex) {
+                    Some(bytes) if bytes.is_empty() => Err("no bytes to write".to_string()),
+                    Some(bytes) => Ok(Edit {
+                        line: number + 1,
+                        offset,
+                        bytes,
+                    }),
+                    None => Err(format!("'{}' isn't hex bytes", hex.trim())),
+                })
+            }
+        };
+
+        match result {
+            Ok(edit) => edits.push(edit),
+            Err(e) => errors.push(format!("line {} '{}': {}", number + 1, line.trim(), e)),
+        }
+    }
+
+    match errors.len() {
+        0 => Ok(edits),
+        1 => Err(errors.remove(0)),
+        2 => Err(format!("{}, and 1 more line is wrong", errors[0])),
+        more => Err(format!(
+            "{}, and {} more lines are wrong",
+            errors[0],
+            more - 1
+        )),
+    }
+}
+
+/// Checks that every edit fits inside `len` bytes, so they can all be applied or none
+pub fn check_bounds(edits: &[Edit], len: usize) -> Result<(), String> {
+    match edits
+        .iter()
+        .find(|edit| edit.end().is_none_or(|end| end > len))
+    {
+        Some(edit) => Err(format!(
+            "line {}: {:X} bytes at {:X} run past the end ({:X} bytes)",
+            edit.line,
+            edit.bytes.len(),
+            edit.offset,
+            len
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Writes modifications as a patch file, with the bytes that were there as a comment above
+/// each edit. A patch file only overwrites, so modifications that changed the length of the
+/// buffer can't be written
+pub fn create(modifications: &[Modification]) -> Result<String, String> {
+    if let Some(resized) = modifications
+        .iter()
+        .find(|modification| modification.original.len() != modification.current.len())
+    {
+        return Err(format!(
+            "bytes were inserted or deleted at {:X}, a patch file can only overwrite",
+            resized.offset
+        ));
+    }
+
+    let mut text = format!(
+        "# hex_it patch, {} edit{}\n",
+        modifications.len(),
+        if modifications.len() == 1 { "" } else { "s" }
+    );
+    for modification in modifications {
+        // Writing into a String can't fail
+        let _ = writeln!(text, "# was {}", bytes_to_hex(&modification.original));
+        let _ = writeln!(
+            text,
+            "{:X}: {}",
+            modification.offset,
+            bytes_to_hex(&modification.current)
+        );
+    }
+
+    Ok(text)
+}
@@ -0,0 +1,106 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! How far a long operation over the buffer got, for the status bar: searches, which run a
+//! slice at a time on the main loop, and background jobs. Both stop between chunks, so `Esc`
+//! takes effect within one chunk, and say how far they got when they do
+
+use std::time::{Duration, Instant};
+
+/// How far an operation over `total` bytes got, and since when it has been running
+#[derive(Clone, Debug)]
+pub struct Progress {
+    name: String,
+    total: usize,
+    done: usize,
+    started: Instant,
+}
+
+impl Progress {
+    /// An operation over `total` bytes starting now
+    pub fn new(name: &str, total: usize) -> Progress {
+        Progress::since(name, total, Instant::now())
+    }
+
+    /// An operation that started at `started`
+    pub fn since(name: &str, total: usize, started: Instant) -> Progress {
+        Progress {
+            name: name.to_string(),
+            total,
+            done: 0,
+            started,
+        }
+    }
+
+    /// What the operation is, like "hash sha256"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Records how many bytes are done
+    pub fn update(&mut self, done: usize) {
+        self.done = done.min(self.total);
+    }
+
+    /// Bytes done so far
+    pub fn done(&self) -> usize {
+        self.done
+    }
+
+    /// How far the operation got, 0 to 100
+    pub fn percent(&self) -> usize {
+        match self.total {
+            0 => 100,
+            total => (self.done as u128 * 100 / total as u128) as usize,
+        }
+    }
+
+    /// How long the operation has been running
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// The status bar line while it runs, like "hash sha256: 43%, 210.5 MiB/s, 2.1s. Esc to
+    /// stop". The speed is left out until there is something to measure
+    pub fn status(&self) -> String {
+        self.status_at(self.elapsed())
+    }
+
+    /// The status bar line after running for `elapsed`
+    pub fn status_at(&self, elapsed: Duration) -> String {
+        let speed = match elapsed.as_secs_f64() {
+            seconds if seconds > 0.0 && self.done > 0 => {
+                format!(", {}", rate(self.done as f64 / seconds))
+            }
+            _ => String::new(),
+        };
+
+        format!(
+            "{}: {}%{speed}, {:.1}s. Esc to stop",
+            self.name,
+            self.percent(),
+            elapsed.as_secs_f64()
+        )
+    }
+
+    /// The status bar line once it was cancelled, like "hash sha256 cancelled at 43%"
+    pub fn cancelled(&self) -> String {
+        format!("{} cancelled at {}%", self.name, self.percent())
+    }
+}
+
+/// Bytes per second in the largest unit that keeps the number at 1 or more
+pub fn rate(bytes_per_second: f64) -> String {
+    let units = ["B/s", "KiB/s", "MiB/s", "GiB/s"];
+    let mut value = bytes_per_second;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit + 1 < units.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{value:.1} {}", units[unit])
+}
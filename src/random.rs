@@ -0,0 +1,61 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Random bytes for `randfill`. They always come from a seeded generator, so a fill can be
+//! repeated: the same seed gives the same bytes, on any machine and in any version. Only the
+//! seed itself comes from the system when none is given
+
+use std::{
+    fs::File,
+    io::Read,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A SplitMix64 generator. Not for cryptography, but fast and the same everywhere
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// A generator that always gives the same bytes for the same seed
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    /// The next 64 random bits
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Fills a buffer, eight bytes of each number in little endian order
+    pub fn fill(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            let value = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&value[..chunk.len()]);
+        }
+    }
+}
+
+/// A seed from the system's random source. Where there is none, the time and process id
+/// mixed together still differ from run to run
+pub fn system_seed() -> u64 {
+    let mut bytes = [0; 8];
+    if File::open("/dev/urandom")
+        .and_then(|mut random| random.read_exact(&mut bytes))
+        .is_ok()
+    {
+        return u64::from_le_bytes(bytes);
+    }
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64);
+    SplitMix64::new(nanos ^ u64::from(std::process::id()).rotate_left(32)).next_u64()
+}
@@ -0,0 +1,190 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Recovery files for `autosave`: the unsaved changes of a buffer, kept next to its file as
+//! `<file>.hexit-recover` so a crash or a closed terminal doesn't take them along. Only the
+//! changes are written, not the whole buffer, a line per run of changed bytes with the bytes
+//! that were there before and the ones there now:
+//!
+//! ```text
+//! # hex_it recovery of 4000 bytes
+//! 1C4: 00 > 7F
+//! 200: > DE AD
+//! ```
+//!
+//! Offsets are where the runs start once the runs above them are in, as
+//! Document::modifications lists them. The size is the file's, without the changes
+
+use crate::{
+    document::{Document, Modification},
+    hex::{bytes_to_hex, hex_to_bytes},
+    offset::parse_offset,
+};
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// What is added to a file's name for its recovery file
+pub const RECOVERY_SUFFIX: &str = ".hexit-recover";
+
+/// The line a recovery file starts with, up to the size
+const HEADER: &str = "# hex_it recovery of ";
+
+/// Where the recovery file of a file goes, next to it
+pub fn recovery_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(RECOVERY_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// The recovery file of a file, if there is one written after the file was last changed.
+/// An older one was left from before a save and has nothing to add
+pub fn newer_recovery(path: &Path) -> Option<PathBuf> {
+    let recovery = recovery_path(path);
+    let written = fs::metadata(&recovery).and_then(|m| m.modified()).ok()?;
+
+    match fs::metadata(path).and_then(|m| m.modified()) {
+        Ok(changed) if changed > written => None,
+        _ => Some(recovery),
+    }
+}
+
+/// The unsaved changes of a buffer, to be put back over its file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recovery {
+    /// How many bytes the file has without the changes
+    pub size: usize,
+    /// The runs of changed bytes, in order
+    pub changes: Vec<Modification>,
+}
+
+impl Recovery {
+    /// The changes a document has since it was last clean
+    pub fn of(document: &Document) -> Recovery {
+        let changes = document.modifications();
+        let size = changes.iter().fold(document.len(), |size, change| {
+            size + change.original.len() - change.current.len()
+        });
+
+        Recovery { size, changes }
+    }
+
+    /// Reads a recovery file. Any line that is wrong fails the whole file, putting back only
+    /// some of the changes would make a buffer nobody ever had
+    pub fn parse(text: &str) -> Result<Recovery, String> {
+        let mut lines = text.lines().enumerate();
+
+        let size = lines
+            .next()
+            .and_then(|(_, line)| line.strip_prefix(HEADER)?.strip_suffix(" bytes"))
+            .ok_or("not a hex_it recovery file")
+            .and_then(|size| {
+                usize::from_str_radix(size, 16).map_err(|_| "not a hex_it recovery file")
+            })?;
+
+        let mut changes = Vec::new();
+        for (number, line) in lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let change =
+                parse_change(line).map_err(|e| format!("line {} '{}': {}", number + 1, line, e))?;
+            changes.push(change);
+        }
+
+        Ok(Recovery { size, changes })
+    }
+
+    /// The recovery file for the changes, as `parse` reads it
+    pub fn contents(&self) -> String {
+        let mut text = format!("{HEADER}{:X} bytes\n", self.size);
+
+        for change in &self.changes {
+            // Writing into a String can't fail
+            let _ = writeln!(
+                text,
+                "{:X}: {} > {}",
+                change.offset,
+                bytes_to_hex(&change.original),
+                bytes_to_hex(&change.current)
+            );
+        }
+
+        text
+    }
+
+    /// Puts the changes back over a document holding the file as it is on disk, as one undo
+    /// step. Fails without touching it unless the file is still what the changes were made
+    /// to, as far as its size and the bytes they replace tell. Returns how many runs changed
+    pub fn apply(&self, document: &mut Document) -> Result<usize, String> {
+        if document.len() != self.size {
+            return Err(format!(
+                "it is for {:X} bytes, the file has {:X} now",
+                self.size,
+                document.len()
+            ));
+        }
+
+        // Offsets are past the runs before, which aren't in the document yet
+        let mut shift = 0isize;
+        for change in &self.changes {
+            let start = change.offset.checked_add_signed(-shift);
+            let end = start.and_then(|start| start.checked_add(change.original.len()));
+            let found = match (start, end) {
+                (Some(start), Some(end)) => document.read(start..end).ok(),
+                _ => None,
+            };
+
+            if found != Some(&change.original[..]) {
+                return Err(format!(
+                    "the file changed at {:X} since it was written",
+                    change.offset
+                ));
+            }
+
+            shift += change.current.len() as isize - change.original.len() as isize;
+        }
+
+        document.end_undo_group();
+        document.begin_undo_group();
+        for change in &self.changes {
+            let range = change.offset..change.offset + change.original.len();
+            // Checked above, each run lands where the ones before it left room for it
+            let _ = document.replace(range, &change.current);
+        }
+        document.end_undo_group();
+
+        Ok(self.changes.len())
+    }
+}
+
+/// Reads an 'offset: old bytes > new bytes' line
+fn parse_change(line: &str) -> Result<Modification, String> {
+    let (offset, bytes) = line
+        .split_once(':')
+        .ok_or("expected 'offset: old bytes > new bytes'")?;
+    let (original, current) = bytes
+        .split_once('>')
+        .ok_or("expected 'offset: old bytes > new bytes'")?;
+
+    let offset = parse_offset(offset.trim())?;
+    let original =
+        hex_to_bytes(original).ok_or(format!("'{}' isn't hex bytes", original.trim()))?;
+    let current = hex_to_bytes(current).ok_or(format!("'{}' isn't hex bytes", current.trim()))?;
+
+    if original.is_empty() && current.is_empty() {
+        return Err("nothing changed".to_string());
+    }
+
+    Ok(Modification {
+        offset,
+        original,
+        current,
+    })
+}
@@ -0,0 +1,55 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Registers for `yank` and `put`, like vim's: the unnamed one `y` and `p` use, and one per
+//! letter from a to z for keeping several runs of bytes around at once
+
+use std::collections::BTreeMap;
+
+/// The register `yank` and `put` use when none is named
+pub const UNNAMED: char = '"';
+
+/// The register an argument names: a single letter from a to z, either case. Anything else,
+/// like a hex offset of two or more digits, isn't one
+pub fn parse_register(arg: &str) -> Option<char> {
+    let mut chars = arg.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Some(c.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+/// The bytes yanked into each register, shared by every buffer
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Registers {
+    contents: BTreeMap<char, Vec<u8>>,
+}
+
+impl Registers {
+    /// The bytes in a register, None if nothing was yanked into it
+    pub fn get(&self, name: char) -> Option<&[u8]> {
+        self.contents
+            .get(&name)
+            .map(Vec::as_slice)
+            .filter(|bytes| !bytes.is_empty())
+    }
+
+    /// Replaces what is in a register
+    pub fn set(&mut self, name: char, bytes: Vec<u8>) {
+        self.contents.insert(name, bytes);
+    }
+
+    /// The registers that hold something, the unnamed one first and then a to z
+    pub fn filled(&self) -> impl Iterator<Item = (char, &[u8])> {
+        let unnamed = self.get(UNNAMED).map(|bytes| (UNNAMED, bytes));
+        let named = self
+            .contents
+            .iter()
+            .filter(|(name, bytes)| **name != UNNAMED && !bytes.is_empty())
+            .map(|(name, bytes)| (*name, bytes.as_slice()));
+
+        unnamed.into_iter().chain(named)
+    }
+}
@@ -0,0 +1,107 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Runs of one repeated byte for `runs`: where the padding is and how big it gets. The bytes
+//! are fed a chunk at a time and a run carries on across chunks, only the longest few are kept
+
+use crate::search::find_other;
+
+/// A run of the same byte
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Run {
+    /// Where the run starts
+    pub offset: usize,
+    /// How many bytes it covers
+    pub len: usize,
+    /// The byte repeated
+    pub byte: u8,
+}
+
+/// The longest runs of the bytes fed so far
+pub struct RunCounter {
+    /// The byte looked for, any byte when None
+    byte: Option<u8>,
+    /// How many runs to keep
+    top: usize,
+    /// The run the bytes fed so far end in
+    current: Option<Run>,
+    /// How many bytes were fed
+    fed: usize,
+    /// The longest runs that ended, longest first and then by offset
+    longest: Vec<Run>,
+}
+
+impl RunCounter {
+    /// Keeps the `top` longest runs of `byte`, or of any byte repeated at least twice
+    pub fn new(byte: Option<u8>, top: usize) -> RunCounter {
+        RunCounter {
+            byte,
+            top,
+            current: None,
+            fed: 0,
+            longest: Vec::new(),
+        }
+    }
+
+    /// Takes in the chunk that follows the ones fed before
+    pub fn update(&mut self, chunk: &[u8]) {
+        let mut at = 0;
+
+        while at < chunk.len() {
+            let byte = chunk[at];
+            let len = find_other(&chunk[at..], byte).unwrap_or(chunk.len() - at);
+
+            match &mut self.current {
+                // Carries on from the end of the last chunk
+                Some(run) if run.byte == byte && run.offset + run.len == self.fed + at => {
+                    run.len += len
+                }
+                _ => {
+                    self.end_run();
+                    self.current = Some(Run {
+                        offset: self.fed + at,
+                        len,
+                        byte,
+                    });
+                }
+            }
+
+            at += len;
+        }
+
+        self.fed += chunk.len();
+    }
+
+    /// The longest runs, longest first and the earliest of the same length first
+    pub fn finish(mut self) -> Vec<Run> {
+        self.end_run();
+        self.longest
+    }
+
+    /// Keeps the current run if it is one of the longest
+    fn end_run(&mut self) {
+        let Some(run) = self.current.take() else {
+            return;
+        };
+
+        let counts = match self.byte {
+            Some(byte) => run.byte == byte,
+            None => run.len > 1,
+        };
+        // Runs end in order, so an earlier run of the same length always stays ahead
+        let index = self.longest.partition_point(|kept| kept.len >= run.len);
+        if counts && index < self.top {
+            self.longest.insert(index, run);
+            self.longest.truncate(self.top);
+        }
+    }
+}
+
+/// The `top` longest runs of `byte` in the bytes, or of any byte repeated when None
+pub fn longest_runs(bytes: &[u8], byte: Option<u8>, top: usize) -> Vec<Run> {
+    let mut counter = RunCounter::new(byte, top);
+    counter.update(bytes);
+    counter.finish()
+}
@@ -0,0 +1,203 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Building what goes on screen. A whole frame is put together in one string and written in
+//! one go, drawing over the last frame instead of clearing the screen first, so nothing flickers.
+//! Listings like the help are drawn the same way, over the whole screen
+
+use crate::line::InputLine;
+use std::fmt::Write;
+
+/// Moves the terminal cursor to the top left corner
+const CURSOR_HOME: &str = "\x1b[H";
+
+/// Clears from the terminal cursor to the end of the line
+const CLEAR_LINE_END: &str = "\x1b[K";
+
+/// Clears from the terminal cursor to the end of the screen
+const CLEAR_SCREEN_END: &str = "\x1b[J";
+
+/// One screen of output, top to bottom
+pub struct Frame {
+    /// Terminal size in columns and rows
+    width: usize,
+    height: usize,
+    output: String,
+}
+
+impl Frame {
+    /// Starts a frame for a terminal of this size, drawing from the top left corner
+    pub fn new(width: usize, height: usize) -> Frame {
+        Frame {
+            width,
+            height,
+            output: CURSOR_HOME.to_string(),
+        }
+    }
+
+    /// Adds a line, what was on screen after it from the last frame is cleared
+    pub fn line(&mut self, text: &str) {
+        self.output.push_str(text);
+        self.output.push_str(CLEAR_LINE_END);
+        self.output.push_str("\r\n");
+    }
+
+    /// Clears whatever is left below the lines, then draws the prompt and input on the last row
+    /// and leaves the terminal cursor where the input cursor is
+    pub fn prompt(&mut self, prompt: &str, input: &InputLine) {
+        self.output.push_str(CLEAR_SCREEN_END);
+        self.output
+            .push_str(&prompt_line(prompt, input, self.width, self.height));
+    }
+
+    /// Everything to write to the terminal
+    pub fn as_str(&self) -> &str {
+        &self.output
+    }
+}
+
+/// Lines shown over the whole screen instead of the dump, like the help, a page at a time.
+/// The dump underneath isn't touched, it is simply drawn again once the listing is closed
+pub struct Listing {
+    lines: Vec<String>,
+    /// Index of the first line on screen
+    top: usize,
+}
+
+impl Listing {
+    /// A listing showing its first page
+    pub fn new(lines: Vec<String>) -> Listing {
+        Listing { lines, top: 0 }
+    }
+
+    /// Columns a line of a listing can fill on a terminal `columns` wide without wrapping. The
+    /// last column stays free: a line reaching it leaves the cursor waiting to wrap, where
+    /// clearing the rest of the line would take its last character with it
+    pub fn content_width(columns: usize) -> usize {
+        columns.saturating_sub(1).max(1)
+    }
+
+    /// The lines on screen when there are `rows` rows for them
+    pub fn page(&self, rows: usize) -> &[String] {
+        let end = (self.top + rows.max(1)).min(self.lines.len());
+        &self.lines[self.top.min(end)..end]
+    }
+
+    /// Whether there are lines after the page on screen
+    pub fn has_more(&self, rows: usize) -> bool {
+        self.top + rows.max(1) < self.lines.len()
+    }
+
+    /// Moves on to the next page, false if the last one was on screen already
+    pub fn next_page(&mut self, rows: usize) -> bool {
+        if !self.has_more(rows) {
+            return false;
+        }
+
+        self.top += rows.max(1);
+        true
+    }
+
+    /// Adds the page that fits to a frame, lines cut to its width, and a prompt saying
+    /// how to go on below it
+    pub fn draw(&self, frame: &mut Frame) {
+        let rows = frame.height.saturating_sub(1);
+
+        // Colors in a line don't take up room, only what shows is cut
+        let width = Listing::content_width(frame.width);
+        for line in self.page(rows) {
+            frame.line(&line[..column_index(line, width)]);
+        }
+
+        let prompt = match self.has_more(rows) {
+            true => format!(
+                "-- Lines {}-{} of {}, any key for more, Esc to close --",
+                self.top + 1,
+                self.top + self.page(rows).len(),
+                self.lines.len()
+            ),
+            false => "Press any key to continue".to_string(),
+        };
+        frame.prompt(&prompt, &InputLine::default());
+    }
+}
+
+/// Draws the prompt and input on the last row and puts the terminal cursor where the input cursor is.
+/// If the input doesn't fit, it is scrolled so the cursor stays visible
+pub fn prompt_line(prompt: &str, input: &InputLine, width: usize, height: usize) -> String {
+    let prompt_width = prompt.chars().count();
+    let available = width.saturating_sub(prompt_width).max(1);
+
+    // First visible char of the input
+    let start = input.cursor.saturating_sub(available - 1);
+    let end = (start + available).min(input.chars.len());
+    let visible = input.chars[start..end].iter().collect::<String>();
+
+    let mut line = String::new();
+    // Writing into a String can't fail
+    let _ = write!(
+        line,
+        "\x1b[{};{}H\x1b[2K{}{}\x1b[{};{}H",
+        height,
+        0,
+        prompt,
+        visible,
+        height,
+        prompt_width + input.cursor - start + 1
+    );
+
+    line
+}
+
+/// Length of the escape sequence at the start of `text`, 0 if it doesn't start with one.
+/// Only CSI sequences like colors (`ESC [ ... m`) are used on screen
+fn escape_len(text: &str) -> usize {
+    let Some(rest) = text.strip_prefix("\x1b[") else {
+        return 0;
+    };
+
+    match rest.bytes().position(|byte| (0x40..=0x7E).contains(&byte)) {
+        Some(end) => 2 + end + 1,
+        None => text.len(),
+    }
+}
+
+/// Columns the text takes up on screen, leaving out escape sequences. Every char is one column
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        match escape_len(rest) {
+            0 => {
+                width += 1;
+                rest = &rest[c.len_utf8()..];
+            }
+            len => rest = &rest[len..],
+        }
+    }
+
+    width
+}
+
+/// Byte index of the char shown at a screen column (0-based), past any escape sequences in
+/// front of it. The length of the text if it doesn't reach that far
+pub fn column_index(text: &str, column: usize) -> usize {
+    let mut width = 0;
+    let mut index = 0;
+
+    while index < text.len() {
+        match escape_len(&text[index..]) {
+            0 if width == column => return index,
+            0 => {
+                width += 1;
+                index += text[index..].chars().next().map_or(1, char::len_utf8);
+            }
+            len => index += len,
+        }
+    }
+
+    text.len()
+}
@@ -0,0 +1,464 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Finding bytes in big buffers. `Finder` is a Boyer-Moore-Horspool search, which skips ahead
+//! by up to the length of the needle after a mismatch, and `ChunkedSearch` feeds it a chunk at
+//! a time so a long search can stop in between and matches across chunks are still found.
+//! A `Pattern` lets some bits of each byte match anything, for `findb` and the `??` of `find`,
+//! and `FloatScan` compares the float at every offset with a value, for `find -f32 1.5 ~0.01`.
+//! `find_other` and `rfind_other` skip over runs of one byte, for `skip`
+
+use crate::template::Endian;
+
+/// What a `ChunkedSearch` looks for
+pub trait Matcher {
+    /// How many bytes a match covers
+    fn width(&self) -> usize;
+
+    /// Every match in the haystack, overlapping ones included
+    fn find_all(&self, haystack: &[u8]) -> Vec<usize>;
+}
+
+/// Bytes to look for where only some bits of each byte count. A byte of the haystack matches
+/// when `byte & mask == value & mask`, so a mask of 00 matches any byte
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    /// The bits each byte has to have, the ones outside the mask are always clear
+    pub values: Vec<u8>,
+    /// Which bits of each byte are compared
+    pub masks: Vec<u8>,
+}
+
+impl Pattern {
+    /// Reads a pattern, one byte per word. A byte is either two hex digits where x is a
+    /// nibble that matches anything (`1F`, `Ax`, `xx`), eight bits where x is a bit that
+    /// matches anything (`1010xxxx`), or a value and a mask in hex (`A0/F0`). `0x` and `0b`
+    /// in front are allowed
+    pub fn parse(text: &str) -> Result<Pattern, String> {
+        let mut pattern = Pattern::exact(&[]);
+
+        for word in text.split_whitespace() {
+            let (value, mask) = parse_masked_byte(word)?;
+            pattern.values.push(value & mask);
+            pattern.masks.push(mask);
+        }
+
+        pattern.check()?;
+        Ok(pattern)
+    }
+
+    /// Reads hex digits into a pattern, two per byte like `hex_to_bytes` with whitespace
+    /// skipped, where `?` is a nibble that matches anything: `E8 ?? ?? ?? ?? 48 8B` or `4?`
+    pub fn parse_hex(text: &str) -> Result<Pattern, String> {
+        let mut pattern = Pattern::exact(&[]);
+        let (mut value, mut mask, mut nibbles) = (0u8, 0u8, 0);
+
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            let (digit, digit_mask) = match c {
+                '?' => (0, 0),
+                c => match c.to_digit(16) {
+                    Some(digit) => (digit as u8, 0xF),
+                    None => return Err(format!("'{}' isn't a hex digit or ?", c)),
+                },
+            };
+
+            value = (value << 4) | digit;
+            mask = (mask << 4) | digit_mask;
+            nibbles += 1;
+
+            if nibbles == 2 {
+                pattern.values.push(value);
+                pattern.masks.push(mask);
+                (value, mask, nibbles) = (0, 0, 0);
+            }
+        }
+
+        if nibbles != 0 {
+            return Err("a digit is left over, bytes take two".to_string());
+        }
+
+        pattern.check()?;
+        Ok(pattern)
+    }
+
+    /// A pattern where every bit counts
+    pub fn exact(bytes: &[u8]) -> Pattern {
+        Pattern {
+            values: bytes.to_vec(),
+            masks: vec![0xFF; bytes.len()],
+        }
+    }
+
+    /// Rejects patterns that can't mean anything: empty ones and ones that match anything
+    fn check(&self) -> Result<(), String> {
+        if self.values.is_empty() {
+            return Err("empty pattern".to_string());
+        }
+
+        if self.masks.iter().all(|&mask| mask == 0) {
+            return Err("the pattern is all wildcards, it would match anything".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Whether a byte matches the byte of the pattern at an index
+    fn matches(&self, index: usize, byte: u8) -> bool {
+        byte & self.masks[index] == self.values[index]
+    }
+}
+
+/// One byte of a pattern with the mask of the bits that count
+fn parse_masked_byte(word: &str) -> Result<(u8, u8), String> {
+    let invalid = || {
+        format!(
+            "invalid byte '{}', expected hex like 1F or Ax, bits like 1010xxxx or value/mask like A0/F0",
+            word
+        )
+    };
+    let lower = word.to_ascii_lowercase();
+
+    if let Some((value, mask)) = lower.split_once('/') {
+        let hex = |text: &str| {
+            u8::from_str_radix(text.strip_prefix("0x").unwrap_or(text), 16).map_err(|_| invalid())
+        };
+        return Ok((hex(value)?, hex(mask)?));
+    }
+
+    // Each digit is a nibble or a bit, x leaves it out of the mask
+    let (digits, radix) = match lower.strip_prefix("0b") {
+        Some(bits) => (bits, 2),
+        None if lower.len() == 8 => (lower.as_str(), 2),
+        None => (lower.strip_prefix("0x").unwrap_or(&lower), 16),
+    };
+    let width = if radix == 2 { 1 } else { 4 };
+    if digits.len() * width != 8 {
+        return Err(invalid());
+    }
+
+    let (mut value, mut mask) = (0u8, 0u8);
+    for digit in digits.chars() {
+        value <<= width;
+        mask <<= width;
+
+        if digit != 'x' {
+            value |= digit.to_digit(radix).ok_or_else(invalid)? as u8;
+            mask |= (1 << width) - 1;
+        }
+    }
+
+    Ok((value, mask))
+}
+
+/// A needle ready to be searched for
+pub struct Finder {
+    needle: Vec<u8>,
+    /// Which bits of each byte of the needle count, all of them unless it came from a `Pattern`
+    masks: Vec<u8>,
+    /// How far the needle can move ahead when this byte is under its last byte
+    skip: [usize; 256],
+}
+
+impl Finder {
+    /// Works out the skip table for a needle. An empty needle is never found
+    pub fn new(needle: &[u8]) -> Finder {
+        Finder::masked(&Pattern::exact(needle))
+    }
+
+    /// Works out the skip table for a pattern. A byte can be skipped past only if no byte of
+    /// the pattern before the last one matches it, so bytes that are mostly x skip little
+    pub fn masked(pattern: &Pattern) -> Finder {
+        let len = pattern.values.len();
+        let mut skip = [len.max(1); 256];
+
+        for i in 0..len.saturating_sub(1) {
+            // Later bytes of the pattern are closer to the end and skip less, so they win
+            for byte in 0..=255u8 {
+                if pattern.matches(i, byte) {
+                    skip[usize::from(byte)] = len - 1 - i;
+                }
+            }
+        }
+
+        Finder {
+            needle: pattern.values.clone(),
+            masks: pattern.masks.clone(),
+            skip,
+        }
+    }
+
+    /// The bytes being searched for, with the bits that don't count cleared
+    pub fn needle(&self) -> &[u8] {
+        &self.needle
+    }
+
+    /// The first match starting at or after `from`
+    pub fn find_from(&self, haystack: &[u8], from: usize) -> Option<usize> {
+        let needle = self.needle.as_slice();
+        let (&last_byte, rest) = needle.split_last()?;
+        let (&last_mask, rest_masks) = self.masks.split_last()?;
+        let matches_rest = |start: usize| {
+            haystack[start..start + rest.len()]
+                .iter()
+                .zip(rest.iter().zip(rest_masks))
+                .all(|(byte, (value, mask))| byte & mask == *value)
+        };
+
+        // A single byte has nothing to skip by, a plain scan is as fast as it gets
+        if rest.is_empty() {
+            let start = from.min(haystack.len());
+            return haystack[start..]
+                .iter()
+                .position(|byte| byte & last_mask == last_byte)
+                .map(|position| start + position);
+        }
+
+        let last = rest.len();
+        let mut position = from;
+
+        while position + needle.len() <= haystack.len() {
+            let byte = haystack[position + last];
+
+            if byte & last_mask == last_byte && matches_rest(position) {
+                return Some(position);
+            }
+
+            position += self.skip[usize::from(byte)];
+        }
+
+        None
+    }
+
+    /// The first match in the haystack
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        self.find_from(haystack, 0)
+    }
+
+    /// Every match, overlapping ones included
+    pub fn find_all(&self, haystack: &[u8]) -> Vec<usize> {
+        let mut matches = Vec::new();
+        let mut from = 0;
+
+        while let Some(position) = self.find_from(haystack, from) {
+            matches.push(position);
+            from = position + 1;
+        }
+
+        matches
+    }
+}
+
+impl Matcher for Finder {
+    fn width(&self) -> usize {
+        self.needle.len()
+    }
+
+    fn find_all(&self, haystack: &[u8]) -> Vec<usize> {
+        Finder::find_all(self, haystack)
+    }
+}
+
+/// The first byte that isn't `byte`, for skipping over padding. Compares eight bytes at a time,
+/// so runs of hundreds of megabytes go by quickly
+pub fn find_other(haystack: &[u8], byte: u8) -> Option<usize> {
+    let run = u64::from_ne_bytes([byte; 8]);
+    let mut words = haystack.chunks_exact(8);
+
+    for (index, word) in words.by_ref().enumerate() {
+        if u64::from_ne_bytes(word.try_into().unwrap()) != run {
+            return word
+                .iter()
+                .position(|&b| b != byte)
+                .map(|at| index * 8 + at);
+        }
+    }
+
+    let rest = words.remainder();
+    let start = haystack.len() - rest.len();
+    rest.iter().position(|&b| b != byte).map(|at| start + at)
+}
+
+/// The last byte that isn't `byte`, `find_other` going backwards
+pub fn rfind_other(haystack: &[u8], byte: u8) -> Option<usize> {
+    let run = u64::from_ne_bytes([byte; 8]);
+    let mut words = haystack.rchunks_exact(8);
+
+    for (index, word) in words.by_ref().enumerate() {
+        if u64::from_ne_bytes(word.try_into().unwrap()) != run {
+            let start = haystack.len() - (index + 1) * 8;
+            return word.iter().rposition(|&b| b != byte).map(|at| start + at);
+        }
+    }
+
+    words.remainder().iter().rposition(|&b| b != byte)
+}
+
+/// The bytes of a float typed by the user, `width` is 4 for an f32 and 8 for an f64
+pub fn float_bytes(text: &str, width: usize, endian: Endian) -> Result<Vec<u8>, String> {
+    let invalid = |_| format!("invalid number '{}'", text);
+
+    Ok(match (width, endian) {
+        (4, Endian::Little) => text.parse::<f32>().map_err(invalid)?.to_le_bytes().to_vec(),
+        (4, Endian::Big) => text.parse::<f32>().map_err(invalid)?.to_be_bytes().to_vec(),
+        (_, Endian::Little) => text.parse::<f64>().map_err(invalid)?.to_le_bytes().to_vec(),
+        (_, Endian::Big) => text.parse::<f64>().map_err(invalid)?.to_be_bytes().to_vec(),
+    })
+}
+
+/// Reads the float at every offset and compares it with a value, for floats that only have to
+/// be close. NaN finds every NaN whatever its bits, an infinity only the same infinity
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FloatScan {
+    /// 4 for an f32, 8 for an f64
+    pub width: usize,
+    /// Byte order of the floats
+    pub endian: Endian,
+    /// The value looked for
+    pub value: f64,
+    /// How far off a float can be and still match
+    pub tolerance: f64,
+}
+
+impl FloatScan {
+    /// The float at the start of the bytes, which have to hold at least `width` of them
+    pub fn decode(&self, bytes: &[u8]) -> f64 {
+        match (self.width, self.endian) {
+            (4, Endian::Little) => f32::from_le_bytes(bytes[..4].try_into().unwrap()).into(),
+            (4, Endian::Big) => f32::from_be_bytes(bytes[..4].try_into().unwrap()).into(),
+            (_, Endian::Little) => f64::from_le_bytes(bytes[..8].try_into().unwrap()),
+            (_, Endian::Big) => f64::from_be_bytes(bytes[..8].try_into().unwrap()),
+        }
+    }
+
+    /// Whether a float counts as the value looked for
+    pub fn matches(&self, float: f64) -> bool {
+        if self.value.is_nan() {
+            float.is_nan()
+        } else if self.value.is_infinite() {
+            float == self.value
+        } else {
+            (float - self.value).abs() <= self.tolerance
+        }
+    }
+}
+
+impl Matcher for FloatScan {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn find_all(&self, haystack: &[u8]) -> Vec<usize> {
+        haystack
+            .windows(self.width)
+            .enumerate()
+            .filter(|(_, bytes)| self.matches(self.decode(bytes)))
+            .map(|(position, _)| position)
+            .collect()
+    }
+}
+
+/// A search fed a chunk at a time, in order, collecting the offsets of every match
+pub struct ChunkedSearch {
+    finder: Box<dyn Matcher>,
+    /// Matches are only kept at offsets that are a multiple of this
+    align: usize,
+    /// The end of what was fed so far, too short to hold a match yet
+    carry: Vec<u8>,
+    /// Bytes fed so far
+    searched: usize,
+    /// Most matches kept, the search is full after that
+    limit: usize,
+    matches: Vec<usize>,
+}
+
+impl ChunkedSearch {
+    /// Starts a search that stops collecting after `limit` matches
+    pub fn new(finder: impl Matcher + 'static, limit: usize) -> ChunkedSearch {
+        ChunkedSearch {
+            finder: Box::new(finder),
+            align: 1,
+            carry: Vec::new(),
+            searched: 0,
+            limit,
+            matches: Vec::new(),
+        }
+    }
+
+    /// Only keeps matches at offsets that are a multiple of `align`, like 4 for f32s in an
+    /// array of them
+    pub fn aligned(mut self, align: usize) -> ChunkedSearch {
+        self.align = align.max(1);
+        self
+    }
+
+    /// Searches the chunk that follows the ones fed before
+    pub fn feed(&mut self, chunk: &[u8]) {
+        let overlap = self.finder.width().saturating_sub(1);
+        let carried = self.carry.len();
+
+        // Matches that start in the end of the chunks before and run into this one
+        if carried > 0 {
+            let mut joint = self.carry.clone();
+            joint.extend_from_slice(&chunk[..overlap.min(chunk.len())]);
+
+            let start = self.searched - carried;
+            for position in self.finder.find_all(&joint) {
+                if position < carried {
+                    self.push(start + position);
+                }
+            }
+        }
+
+        for position in self.finder.find_all(chunk) {
+            self.push(self.searched + position);
+        }
+
+        // The last bytes fed are kept for the next chunk, whichever chunks they came from
+        self.carry
+            .extend_from_slice(&chunk[chunk.len() - overlap.min(chunk.len())..]);
+        let excess = self.carry.len().saturating_sub(overlap);
+        self.carry.drain(..excess);
+        self.searched += chunk.len();
+    }
+
+    /// Keeps a match, matches come in order so one seen already through an earlier,
+    /// shorter chunk is skipped
+    fn push(&mut self, offset: usize) {
+        if !offset.is_multiple_of(self.align)
+            || self.matches.last().is_some_and(|&last| last >= offset)
+        {
+            return;
+        }
+
+        if self.matches.len() < self.limit {
+            self.matches.push(offset);
+        }
+    }
+
+    /// How many bytes a match covers
+    pub fn width(&self) -> usize {
+        self.finder.width()
+    }
+
+    /// Bytes searched so far
+    pub fn searched(&self) -> usize {
+        self.searched
+    }
+
+    /// Whether the limit of matches was reached
+    pub fn is_full(&self) -> bool {
+        self.matches.len() >= self.limit
+    }
+
+    /// The offsets found so far, in order
+    pub fn matches(&self) -> &[usize] {
+        &self.matches
+    }
+
+    /// Ends the search, returning the offsets found
+    pub fn into_matches(self) -> Vec<usize> {
+        self.matches
+    }
+}
@@ -0,0 +1,151 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The searches run before, for `find` without arguments and `searches`. Each is kept as the
+//! command line that ran it, flags, byte order and quoting included, so running it again
+//! finds exactly the same bytes. They are saved in `searches` next to the config file, a line
+//! per search with its kind in front:
+//!
+//! ```text
+//! hex   find DE AD ?? EF
+//! text  find "Error: \x00"
+//! float find -f32 3.14159 ~0.001 -be
+//! ```
+
+use crate::args::tokenize;
+
+/// Most searches kept, the oldest go first
+pub const MAX_SEARCHES: usize = 100;
+
+/// What a search looked for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchKind {
+    /// Hex bytes, `??` wildcards included
+    Hex,
+    /// Text in quotes
+    Text,
+    /// Text as UTF-16, `find -u16` or `find -u16be`
+    Utf16,
+    /// The value of a float, `find -f32` or `find -f64`
+    Float,
+    /// Bytes where some bits match anything, `findb`
+    Bits,
+}
+
+/// Every kind with the name it is saved and listed with
+pub const SEARCH_KINDS: [(SearchKind, &str); 5] = [
+    (SearchKind::Hex, "hex"),
+    (SearchKind::Text, "text"),
+    (SearchKind::Utf16, "utf16"),
+    (SearchKind::Float, "float"),
+    (SearchKind::Bits, "bits"),
+];
+
+impl SearchKind {
+    /// The name the kind is saved and listed with
+    pub fn name(self) -> &'static str {
+        SEARCH_KINDS
+            .iter()
+            .find(|(kind, _)| *kind == self)
+            .map_or("hex", |(_, name)| name)
+    }
+}
+
+/// A search as it was run
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchEntry {
+    /// What it looked for
+    pub kind: SearchKind,
+    /// The command line that ran it
+    pub command: String,
+}
+
+impl SearchEntry {
+    /// The search a command line runs, None if it isn't a `find` or `findb` with something to
+    /// look for
+    pub fn new(command: &str) -> Option<SearchEntry> {
+        let tokens = tokenize(command).ok()?;
+        let (name, flag) = (tokens.first()?.text(), tokens.get(1)?);
+
+        let kind = match (name.to_ascii_lowercase().as_str(), flag.text().as_str()) {
+            ("findb", _) => SearchKind::Bits,
+            ("find", "-f32" | "-f64") => SearchKind::Float,
+            ("find", "-u16" | "-u16be") => SearchKind::Utf16,
+            ("find", _) if flag.quoted => SearchKind::Text,
+            ("find", _) => SearchKind::Hex,
+            _ => return None,
+        };
+
+        Some(SearchEntry {
+            kind,
+            command: command.trim().to_string(),
+        })
+    }
+}
+
+/// The searches run before, oldest first
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchHistory {
+    entries: Vec<SearchEntry>,
+}
+
+impl SearchHistory {
+    /// Reads the searches file. Lines that aren't a search are skipped, so a damaged file
+    /// still gives back what is left of it
+    pub fn parse(contents: &str) -> SearchHistory {
+        let mut history = SearchHistory::default();
+
+        for line in contents.lines() {
+            let Some((kind, command)) = line.split_once(' ') else {
+                continue;
+            };
+            let entry = SearchEntry::new(command).filter(|entry| entry.kind.name() == kind);
+            if let Some(entry) = entry {
+                history.record(entry);
+            }
+        }
+
+        history
+    }
+
+    /// The searches file for the history, as `parse` reads it
+    pub fn contents(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| format!("{} {}\n", entry.kind.name(), entry.command))
+            .collect()
+    }
+
+    /// Adds a search as the latest. The same search run before moves up instead of showing
+    /// twice, and past MAX_SEARCHES the oldest is dropped
+    pub fn record(&mut self, entry: SearchEntry) {
+        self.entries.retain(|kept| kept.command != entry.command);
+        self.entries.push(entry);
+
+        if self.entries.len() > MAX_SEARCHES {
+            self.entries.remove(0);
+        }
+    }
+
+    /// The latest search
+    pub fn last(&self) -> Option<&SearchEntry> {
+        self.entries.last()
+    }
+
+    /// The searches, latest first, so #1 is the one `find` alone runs again
+    pub fn latest_first(&self) -> impl Iterator<Item = &SearchEntry> {
+        self.entries.iter().rev()
+    }
+
+    /// How many searches there are
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing was searched for yet
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
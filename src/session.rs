@@ -0,0 +1,186 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Sessions, the open files and where the editor was in each of them, a line per setting
+//! like the config file:
+//!
+//! ```text
+//! version 1
+//! colors on
+//! cols 16
+//! group 0
+//! current 2
+//! file /home/me/first.bin
+//! size 0x2000
+//! cursor 0x1F0
+//! top-line 0x1A
+//! file /home/me/second.bin
+//! ...
+//! ```
+//!
+//! `size`, `cursor`, `top-line` and `selection` belong to the `file` line above them.
+//! Settings this version doesn't know are skipped, so sessions written by newer versions still load.
+//! Unsaved changes to the bytes are never part of a session.
+
+use crate::offset::parse_offset;
+use std::{fmt, path::PathBuf};
+
+/// The session format written by this version
+pub const SESSION_VERSION: u32 = 1;
+
+/// Where the editor was in one open file
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BufferSession {
+    /// The file, absolute so the session works from any directory
+    pub path: PathBuf,
+    /// The size of the file when the session was saved, to notice it changed since
+    pub size: u64,
+    /// The grid cursor
+    pub cursor: usize,
+    /// The first line on screen
+    pub top_line: usize,
+    /// Where the selection started, if there was one
+    pub selection: Option<usize>,
+}
+
+impl BufferSession {
+    /// A buffer at the start of a file
+    pub fn new(path: PathBuf, size: u64) -> BufferSession {
+        BufferSession {
+            path,
+            size,
+            cursor: 0,
+            top_line: 0,
+            selection: None,
+        }
+    }
+}
+
+/// Everything a session restores
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Session {
+    /// The format version the session was written with
+    pub version: u32,
+    /// The open files, in the order of the buffer list
+    pub buffers: Vec<BufferSession>,
+    /// Index of the buffer that was on screen
+    pub current: usize,
+    /// Whether the dump was colored
+    pub colors: bool,
+    /// Bytes per line of the dump
+    pub cols: usize,
+    /// Bytes per group of the dump, 0 for no grouping
+    pub group: usize,
+}
+
+impl Default for Session {
+    fn default() -> Session {
+        Session {
+            version: SESSION_VERSION,
+            buffers: Vec::new(),
+            current: 0,
+            colors: true,
+            cols: 16,
+            group: 0,
+        }
+    }
+}
+
+impl fmt::Display for Session {
+    /// Writes the session in the format `parse` reads
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# hex_it session")?;
+        writeln!(f, "version {}", self.version)?;
+        writeln!(f, "colors {}", if self.colors { "on" } else { "off" })?;
+        writeln!(f, "cols {}", self.cols)?;
+        writeln!(f, "group {}", self.group)?;
+        writeln!(f, "current {}", self.current + 1)?;
+
+        for buffer in &self.buffers {
+            writeln!(f, "file {}", buffer.path.display())?;
+            writeln!(f, "size {:#X}", buffer.size)?;
+            writeln!(f, "cursor {:#X}", buffer.cursor)?;
+            writeln!(f, "top-line {:#X}", buffer.top_line)?;
+            if let Some(selection) = buffer.selection {
+                writeln!(f, "selection {:#X}", selection)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Session {
+    /// Reads a session. Lines with problems are skipped and described in the returned list,
+    /// a session without a version isn't a session at all
+    pub fn parse(contents: &str) -> Result<(Session, Vec<String>), String> {
+        let mut session = Session::default();
+        let mut version = None;
+        let mut errors = Vec::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (setting, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let buffer = session.buffers.last_mut();
+            let result = match (setting, buffer) {
+                ("version", _) => parse_count(rest).map(|number| version = Some(number as u32)),
+                ("colors", _) if rest == "on" || rest == "off" => {
+                    session.colors = rest == "on";
+                    Ok(())
+                }
+                ("colors", _) => Err("expected on or off".to_string()),
+                ("cols", _) => match parse_count(rest) {
+                    Ok(0) => Err("cols can't be 0".to_string()),
+                    cols => cols.map(|cols| session.cols = cols),
+                },
+                ("group", _) => parse_count(rest).map(|group| session.group = group),
+                ("current", _) => match parse_count(rest) {
+                    Ok(0) => Err("buffers are numbered from 1".to_string()),
+                    current => current.map(|current| session.current = current - 1),
+                },
+                ("file", _) if rest.is_empty() => Err("no path given".to_string()),
+                ("file", _) => {
+                    session
+                        .buffers
+                        .push(BufferSession::new(PathBuf::from(rest), 0));
+                    Ok(())
+                }
+                ("size" | "cursor" | "top-line" | "selection", None) => {
+                    Err("comes before any file".to_string())
+                }
+                ("size", Some(buffer)) => parse_offset(rest).map(|size| buffer.size = size as u64),
+                ("cursor", Some(buffer)) => parse_offset(rest).map(|cursor| buffer.cursor = cursor),
+                ("top-line", Some(buffer)) => parse_offset(rest).map(|line| buffer.top_line = line),
+                ("selection", Some(buffer)) => {
+                    parse_offset(rest).map(|anchor| buffer.selection = Some(anchor))
+                }
+                // Written by a newer version, it can't hurt to leave out
+                _ => Ok(()),
+            };
+
+            if let Err(e) = result {
+                errors.push(format!("session line {} '{}': {}", number + 1, line, e));
+            }
+        }
+
+        session.version = version.ok_or("not a hex_it session, it has no version")?;
+        if session.current >= session.buffers.len() {
+            session.current = 0;
+        }
+
+        Ok((session, errors))
+    }
+}
+
+/// Reads a plain decimal number, for counts rather than offsets
+fn parse_count(text: &str) -> Result<usize, String> {
+    text.parse()
+        .map_err(|_| format!("'{}' isn't a decimal number", text))
+}
@@ -0,0 +1,72 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Arrays of fixed-size records: which byte of its record an offset is, for the tint `stride`
+//! puts on one field of every record, and where the field of each record is for `colset`
+
+/// Records of `size` bytes one after the other from `base`, with a field `field` bytes into each
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stride {
+    /// Bytes per record
+    pub size: usize,
+    /// Where the field is within a record
+    pub field: usize,
+    /// Where the first record starts, bytes before it aren't in any record
+    pub base: usize,
+}
+
+impl Stride {
+    /// Records of `size` bytes from `base`, failing when the field doesn't start inside one
+    pub fn new(size: usize, field: usize, base: usize) -> Result<Stride, String> {
+        if size == 0 {
+            return Err("The record size can't be 0".to_string());
+        }
+        if field >= size {
+            return Err(format!(
+                "Field offset {field} is past the end of a {size} byte record"
+            ));
+        }
+
+        Ok(Stride { size, field, base })
+    }
+
+    /// Which byte of its record an offset is, None before the first record
+    pub fn position(&self, offset: usize) -> Option<usize> {
+        offset.checked_sub(self.base).map(|into| into % self.size)
+    }
+
+    /// Whether an offset is the byte at the field offset of its record
+    pub fn is_field(&self, offset: usize) -> bool {
+        self.position(offset) == Some(self.field)
+    }
+
+    /// Whether an offset is the first byte of a record
+    pub fn is_record_start(&self, offset: usize) -> bool {
+        self.position(offset) == Some(0)
+    }
+
+    /// Fails when `width` bytes at the field offset run past the end of the record
+    pub fn fits(&self, width: usize) -> Result<(), String> {
+        match self.field + width <= self.size {
+            true => Ok(()),
+            false => Err(format!(
+                "{width} bytes at {} don't fit in a record of {} bytes",
+                self.field, self.size
+            )),
+        }
+    }
+
+    /// How many whole records there are from the base up to `end`, and how many bytes of a
+    /// record that doesn't fit are left after them
+    pub fn whole_records(&self, end: usize) -> (usize, usize) {
+        let len = end.saturating_sub(self.base);
+        (len / self.size, len % self.size)
+    }
+
+    /// Where the field of a record starts, counting records from 0
+    pub fn field_offset(&self, record: usize) -> usize {
+        self.base + record * self.size + self.field
+    }
+}
@@ -0,0 +1,184 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Text in the bytes: runs of it for `strings`, as ASCII or as the UTF-16 Windows stores its
+//! strings in, and text encoded as UTF-16 for `find -u16`. Both go through `Encoding`, so
+//! `find -u16 "Error"` and `strings -u16` agree on the byte order and on surrogate pairs
+
+use crate::template::Endian;
+
+/// How text is stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// A byte per character
+    Ascii,
+    /// Two bytes per character, four for the surrogate pairs of characters above U+FFFF
+    Utf16(Endian),
+}
+
+impl Encoding {
+    /// The flag that picks the encoding, like -u16be
+    pub fn flag(self) -> &'static str {
+        match self {
+            Encoding::Ascii => "",
+            Encoding::Utf16(Endian::Little) => "-u16",
+            Encoding::Utf16(Endian::Big) => "-u16be",
+        }
+    }
+
+    /// The encoding picked by a flag, None for flags that aren't an encoding
+    pub fn from_flag(flag: &str) -> Option<Encoding> {
+        match flag {
+            "-u16" => Some(Encoding::Utf16(Endian::Little)),
+            "-u16be" => Some(Encoding::Utf16(Endian::Big)),
+            _ => None,
+        }
+    }
+
+    /// Text typed as UTF-8 bytes stored this way. ASCII keeps the bytes as they are, escapes
+    /// like `\xFF` included, UTF-16 needs them to be text
+    pub fn encode(self, text: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            Encoding::Ascii => Ok(text.to_vec()),
+            Encoding::Utf16(endian) => match std::str::from_utf8(text) {
+                Ok(text) => Ok(utf16_bytes(text, endian)),
+                Err(e) => Err(format!(
+                    "byte {:X} isn't text, only text can be written as UTF-16",
+                    e.valid_up_to()
+                )),
+            },
+        }
+    }
+}
+
+/// Text as UTF-16 bytes
+pub fn utf16_bytes(text: &str, endian: Endian) -> Vec<u8> {
+    text.encode_utf16()
+        .flat_map(|unit| match endian {
+            Endian::Little => unit.to_le_bytes(),
+            Endian::Big => unit.to_be_bytes(),
+        })
+        .collect()
+}
+
+/// Where the UTF-16 code unit holding `offset` starts, with units at even offsets or, with
+/// `odd_units`, at odd ones. Offset 0 isn't in a unit when they start at odd offsets, the
+/// first unit is at 1 then
+pub fn unit_start(offset: usize, odd_units: bool) -> usize {
+    match (offset % 2 == 1) == odd_units {
+        true => offset,
+        false if offset == 0 => 1,
+        false => offset - 1,
+    }
+}
+
+/// Characters `strings` takes for text: printable ASCII and tabs, and in UTF-16 also the
+/// letters of Latin-1 and Latin Extended, so accented words don't break up. Other scripts are
+/// left out because random pairs of bytes, and ASCII text read two bytes at a time, would
+/// decode to them
+pub fn is_text(c: char) -> bool {
+    c == '\t' || (' '..='~').contains(&c)
+}
+
+fn is_wide_text(c: char) -> bool {
+    is_text(c) || (('\u{A0}'..='\u{24F}').contains(&c) && !c.is_control())
+}
+
+/// A run of text found by `find_strings`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Found {
+    /// Where it starts
+    pub offset: usize,
+    /// How many bytes it takes up
+    pub length: usize,
+    /// The text
+    pub text: String,
+}
+
+/// Every run of at least `min` characters of text, at most `limit` of them
+pub fn find_strings(bytes: &[u8], encoding: Encoding, min: usize, limit: usize) -> Vec<Found> {
+    let mut found = Vec::new();
+    let mut run: Option<Found> = None;
+    let mut chars = 0;
+
+    // Ends the run in progress, keeping it when it is long enough
+    let end = |run: &mut Option<Found>, chars: &mut usize, found: &mut Vec<Found>| {
+        if let Some(text) = run.take().filter(|_| *chars >= min.max(1)) {
+            found.push(text);
+        }
+        *chars = 0;
+    };
+
+    match encoding {
+        Encoding::Ascii => {
+            for (offset, byte) in bytes.iter().enumerate() {
+                if found.len() >= limit {
+                    break;
+                }
+
+                let c = char::from(*byte);
+                if !is_text(c) {
+                    end(&mut run, &mut chars, &mut found);
+                    continue;
+                }
+
+                let text = run.get_or_insert_with(|| Found {
+                    offset,
+                    length: 0,
+                    text: String::new(),
+                });
+                text.length += 1;
+                text.text.push(c);
+                chars += 1;
+            }
+        }
+        Encoding::Utf16(endian) => {
+            // Text can start at either byte, each alignment is a pass of its own
+            for start in 0..2 {
+                let units = bytes[start.min(bytes.len())..].chunks_exact(2).map(|pair| {
+                    let pair = [pair[0], pair[1]];
+                    match endian {
+                        Endian::Little => u16::from_le_bytes(pair),
+                        Endian::Big => u16::from_be_bytes(pair),
+                    }
+                });
+
+                let mut offset = start;
+                for decoded in char::decode_utf16(units) {
+                    if found.len() >= limit {
+                        break;
+                    }
+
+                    // A pair of surrogates is one character of 4 bytes, a lone one is 2 bytes of
+                    // not text
+                    let width = decoded.as_ref().map_or(1, |c| c.len_utf16()) * 2;
+                    let c = decoded.ok().filter(|c| is_wide_text(*c));
+                    match c {
+                        Some(c) => {
+                            let text = run.get_or_insert_with(|| Found {
+                                offset,
+                                length: 0,
+                                text: String::new(),
+                            });
+                            text.length += width;
+                            text.text.push(c);
+                            chars += 1;
+                        }
+                        None => end(&mut run, &mut chars, &mut found),
+                    }
+                    offset += width;
+                }
+                end(&mut run, &mut chars, &mut found);
+            }
+
+            found.sort_by_key(|text| text.offset);
+            found.truncate(limit);
+        }
+    }
+    end(&mut run, &mut chars, &mut found);
+    found.truncate(limit);
+
+    found
+}
@@ -0,0 +1,86 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Suggestions for mistyped commands and options
+
+/// Most suggestions offered for one mistake
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// Number of single char insertions, deletions, substitutions and swaps of neighbouring chars
+/// it takes to turn one string into the other. This is the optimal string alignment distance,
+/// a restricted Damerau-Levenshtein where no char is edited twice, so "ca" to "abc" is 3 and not 2
+pub fn levenshtein_distance(string1: &str, string2: &str) -> usize {
+    let a = string1.chars().collect::<Vec<char>>();
+    let b = string2.chars().collect::<Vec<char>>();
+
+    // Distances from the first i chars of a to every prefix of b, for the row before last,
+    // the last row and the one being worked out
+    let mut before_previous = vec![0; b.len() + 1];
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            current[j] = (previous[j] + 1) // deletion
+                .min(current[j - 1] + 1) // insertion
+                .min(previous[j - 1] + cost); // substitution
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1); // transposition
+            }
+        }
+
+        std::mem::swap(&mut before_previous, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// How far off a typo of this length can be and still get suggestions, about one edit in three
+fn threshold(typed: &str) -> usize {
+    typed.chars().count().div_ceil(3).max(1)
+}
+
+/// The candidates close enough to what was typed, closest first and at most `MAX_SUGGESTIONS`.
+/// Candidates as far off as each other keep their order
+pub fn suggestions<'a>(typed: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let limit = threshold(typed);
+    let typed = typed.to_lowercase();
+
+    let mut close = candidates
+        .into_iter()
+        .map(|candidate| {
+            (
+                levenshtein_distance(&typed, &candidate.to_lowercase()),
+                candidate,
+            )
+        })
+        .filter(|(distance, _)| *distance <= limit)
+        .collect::<Vec<(usize, &str)>>();
+
+    close.sort_by_key(|(distance, _)| *distance);
+    close.truncate(MAX_SUGGESTIONS);
+
+    close.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+/// "Did you mean 'a', 'b' or 'c'?", None without any suggestions
+pub fn did_you_mean(suggestions: &[&str]) -> Option<String> {
+    let quoted = suggestions
+        .iter()
+        .map(|suggestion| format!("'{}'", suggestion))
+        .collect::<Vec<String>>();
+
+    match quoted.as_slice() {
+        [] => None,
+        [only] => Some(format!("Did you mean {}?", only)),
+        [rest @ .., last] => Some(format!("Did you mean {} or {}?", rest.join(", "), last)),
+    }
+}
@@ -0,0 +1,568 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Structure templates: a file format described as a list of fields, written in a small
+//! subset of TOML:
+//!
+//! ```toml
+//! name = "Save file"
+//! endian = "little"
+//!
+//! [[field]]
+//! name = "magic"
+//! type = "bytes[4]"
+//!
+//! [[field]]
+//! name = "length"
+//! type = "u32"
+//! endian = "big"
+//! offset = 0x10
+//! ```
+//!
+//! Fields without an offset follow straight after the one before, offsets count from where
+//! the template is put in the data. Types are u8 to u64, i8 to i64, f32, f64, bytes[N] (or
+//! bytes(N)) and cstr, a string that runs up to and including a NUL byte. The top level endian
+//! is the default for the fields and is little endian if left out
+
+use crate::{
+    conv,
+    hex::{bytes_to_hex, hex_to_bytes},
+};
+use std::{fmt, ops::Range};
+
+/// Byte order of a number field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endian {
+    /// Least significant byte first
+    Little,
+    /// Most significant byte first
+    Big,
+}
+
+/// What a field holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    /// An unsigned number of this many bytes, 1, 2, 4 or 8
+    Unsigned(usize),
+    /// A two's complement number of this many bytes, 1, 2, 4 or 8
+    Signed(usize),
+    /// An IEEE 754 float of this many bytes, 4 or 8
+    Float(usize),
+    /// A fixed number of raw bytes
+    Bytes(usize),
+    /// A NUL terminated string
+    CStr,
+}
+
+/// One field of a template
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    /// What the field is called
+    pub name: String,
+    /// Where the field starts, None to follow the field before it
+    pub offset: Option<usize>,
+    /// What the field holds
+    pub kind: FieldType,
+    /// Byte order, only used by numbers
+    pub endian: Endian,
+}
+
+/// A file format as a list of fields
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    /// What the format is called, empty if the template doesn't say
+    pub name: String,
+    /// The fields in the order they are decoded
+    pub fields: Vec<Field>,
+    /// Where the structure starts in the data, the offsets of the fields count from here
+    pub base: usize,
+}
+
+/// A field's value read from the data
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// An unsigned number field
+    Unsigned(u64),
+    /// A signed number field
+    Signed(i64),
+    /// An f32 field
+    Float32(f32),
+    /// An f64 field
+    Float64(f64),
+    /// A bytes field
+    Bytes(Vec<u8>),
+    /// A cstr field without its NUL, `terminated` is false if the data ended first
+    Text {
+        /// The bytes of the string
+        bytes: Vec<u8>,
+        /// Whether a NUL was found
+        terminated: bool,
+    },
+}
+
+/// A field decoded from the data
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decoded<'a> {
+    /// The field from the template
+    pub field: &'a Field,
+    /// The bytes the field covers, clamped to the data
+    pub range: Range<usize>,
+    /// The value, None if the field runs past the end of the data
+    pub value: Option<Value>,
+}
+
+/// Longest bytes value shown in full
+const SHOWN_BYTES: usize = 16;
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldType::Unsigned(size) => write!(f, "u{}", size * 8),
+            FieldType::Signed(size) => write!(f, "i{}", size * 8),
+            FieldType::Float(size) => write!(f, "f{}", size * 8),
+            FieldType::Bytes(len) => write!(f, "bytes[{}]", len),
+            FieldType::CStr => write!(f, "cstr"),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Unsigned(value) => write!(f, "{} (0x{:X})", value, value),
+            Value::Signed(value) => write!(f, "{}", value),
+            Value::Float32(value) => write!(f, "{}", value),
+            Value::Float64(value) => write!(f, "{}", value),
+            Value::Bytes(bytes) if bytes.len() > SHOWN_BYTES => {
+                write!(f, "{} ...", bytes_to_hex(&bytes[..SHOWN_BYTES]))
+            }
+            Value::Bytes(bytes) => write!(f, "{}", bytes_to_hex(bytes)),
+            Value::Text { bytes, terminated } => {
+                write!(f, "{:?}", String::from_utf8_lossy(bytes))?;
+                if !*terminated {
+                    write!(f, " (unterminated)")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FieldType {
+    /// Parses a type name like "u16", "f32" or "bytes[4]"
+    pub fn parse(name: &str) -> Result<FieldType, String> {
+        match name {
+            "u8" => Ok(FieldType::Unsigned(1)),
+            "u16" => Ok(FieldType::Unsigned(2)),
+            "u32" => Ok(FieldType::Unsigned(4)),
+            "u64" => Ok(FieldType::Unsigned(8)),
+            "i8" => Ok(FieldType::Signed(1)),
+            "i16" => Ok(FieldType::Signed(2)),
+            "i32" => Ok(FieldType::Signed(4)),
+            "i64" => Ok(FieldType::Signed(8)),
+            "f32" => Ok(FieldType::Float(4)),
+            "f64" => Ok(FieldType::Float(8)),
+            "cstr" => Ok(FieldType::CStr),
+            _ => {
+                let len = name
+                    .strip_prefix("bytes[")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .or_else(|| {
+                        name.strip_prefix("bytes(")
+                            .and_then(|rest| rest.strip_suffix(')'))
+                    })
+                    .ok_or_else(|| format!("unknown type '{}'", name))?;
+
+                match parse_integer(len.trim()) {
+                    Ok(0) => Err("bytes[N] needs a length of at least 1".to_string()),
+                    Ok(len) => Ok(FieldType::Bytes(len)),
+                    Err(_) => Err(format!("invalid length in '{}'", name)),
+                }
+            }
+        }
+    }
+}
+
+impl Field {
+    /// The bytes that store a value typed for the field: a number for the number types, hex
+    /// for bytes with or without 0x in front, and the text as it is for cstr, with its NUL
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, String> {
+        let mut bytes = match self.kind {
+            FieldType::Unsigned(size) | FieldType::Signed(size) => {
+                let value = conv::parse_value(text)?;
+                let bits = 8 * size as u32;
+                let range = match self.kind {
+                    FieldType::Signed(_) => -(1i128 << (bits - 1))..=(1i128 << (bits - 1)) - 1,
+                    _ => 0..=(1i128 << bits) - 1,
+                };
+                if !range.contains(&value) {
+                    return Err(format!("{} doesn't fit in {}", text.trim(), self.kind));
+                }
+                (value as u64).to_le_bytes()[..size].to_vec()
+            }
+            FieldType::Float(size) => {
+                let text = text.trim();
+                let value = text
+                    .parse::<f64>()
+                    .map_err(|_| format!("'{}' is not a number", text))?;
+                match size {
+                    4 if value.is_finite() && !(value as f32).is_finite() => {
+                        return Err(format!("{} doesn't fit in f32", text))
+                    }
+                    4 => (value as f32).to_le_bytes().to_vec(),
+                    _ => value.to_le_bytes().to_vec(),
+                }
+            }
+            FieldType::Bytes(len) => {
+                let text = text.trim();
+                let digits = text
+                    .strip_prefix("0x")
+                    .or_else(|| text.strip_prefix("0X"))
+                    .unwrap_or(text);
+                let bytes =
+                    hex_to_bytes(digits).ok_or_else(|| format!("'{}' is not hex bytes", text))?;
+                if bytes.len() != len {
+                    return Err(format!(
+                        "{} takes {} bytes, got {}",
+                        self.kind,
+                        len,
+                        bytes.len()
+                    ));
+                }
+                return Ok(bytes);
+            }
+            FieldType::CStr => {
+                if text.contains('\0') {
+                    return Err("a cstr can't hold a NUL".to_string());
+                }
+                let mut bytes = text.as_bytes().to_vec();
+                bytes.push(0);
+                return Ok(bytes);
+            }
+        };
+
+        if self.endian == Endian::Big {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+}
+
+/// A value on the right of '='
+enum TomlValue {
+    String(String),
+    Integer(usize),
+}
+
+/// A problem with a template and the line it is on
+type LineError = (usize, String);
+
+/// Keys set on a table, with the line each came from
+#[derive(Default)]
+struct Table {
+    /// Line of the table's header, 0 for the top level
+    line: usize,
+    entries: Vec<(String, TomlValue, usize)>,
+}
+
+impl Table {
+    fn take(&mut self, key: &str) -> Option<(TomlValue, usize)> {
+        let index = self.entries.iter().position(|(name, _, _)| name == key)?;
+        let (_, value, line) = self.entries.remove(index);
+        Some((value, line))
+    }
+
+    /// A string value and its line
+    fn string(&mut self, key: &str) -> Result<Option<(String, usize)>, LineError> {
+        match self.take(key) {
+            Some((TomlValue::String(value), line)) => Ok(Some((value, line))),
+            Some((TomlValue::Integer(_), line)) => {
+                Err((line, format!("{} should be a string", key)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn integer(&mut self, key: &str) -> Result<Option<usize>, LineError> {
+        match self.take(key) {
+            Some((TomlValue::Integer(value), _)) => Ok(Some(value)),
+            Some((TomlValue::String(_), line)) => {
+                Err((line, format!("{} should be a number", key)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Fails if any keys haven't been taken
+    fn finish(self) -> Result<(), LineError> {
+        match self.entries.first() {
+            Some((key, _, line)) => Err((*line, format!("unknown key '{}'", key))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Puts the line in front of an error
+fn with_line((line, e): LineError) -> String {
+    format!("line {}: {}", line, e)
+}
+
+impl Template {
+    /// Reads a template, see the module docs for the format. Errors start with the line,
+    /// problems with a field name the field too, like "line 8: field 2 'length': unknown type
+    /// 'u24'"
+    pub fn parse(contents: &str) -> Result<Template, String> {
+        let mut top = Table::default();
+        let mut tables: Vec<Table> = Vec::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let number = number + 1;
+            let line = strip_comment(line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                if line != "[[field]]" {
+                    return Err(format!(
+                        "line {}: unknown table '{}', expected [[field]]",
+                        number, line
+                    ));
+                }
+                tables.push(Table {
+                    line: number,
+                    entries: Vec::new(),
+                });
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'key = value'", number))?;
+            let key = key.trim().to_string();
+            let value = parse_value(value.trim()).map_err(|e| format!("line {}: {}", number, e))?;
+
+            let table = tables.last_mut().unwrap_or(&mut top);
+            if table.entries.iter().any(|(name, _, _)| *name == key) {
+                return Err(format!("line {}: {} is set twice", number, key));
+            }
+            table.entries.push((key, value, number));
+        }
+
+        let name = top.string("name").map_err(with_line)?.unwrap_or_default().0;
+        let endian = match top.string("endian").map_err(with_line)? {
+            Some((endian, line)) => parse_endian(&endian).map_err(|e| with_line((line, e)))?,
+            None => Endian::Little,
+        };
+        top.finish().map_err(with_line)?;
+
+        if tables.is_empty() {
+            return Err("no fields, add a [[field]] table".to_string());
+        }
+
+        let fields = tables
+            .into_iter()
+            .enumerate()
+            .map(|(index, table)| {
+                parse_field(table, endian).map_err(|(name, (line, e))| match name {
+                    Some(name) => format!("line {}: field {} '{}': {}", line, index + 1, name, e),
+                    None => format!("line {}: field {}: {}", line, index + 1, e),
+                })
+            })
+            .collect::<Result<Vec<Field>, String>>()?;
+
+        Ok(Template {
+            name,
+            fields,
+            base: 0,
+        })
+    }
+
+    /// Reads the fields out of data, one entry per field in template order
+    pub fn decode<'a>(&'a self, data: &[u8]) -> Vec<Decoded<'a>> {
+        let mut position = self.base;
+
+        self.fields
+            .iter()
+            .map(|field| {
+                let start = match field.offset {
+                    Some(offset) => self.base.saturating_add(offset),
+                    None => position,
+                };
+                let decoded = decode_field(field, start, data);
+                position = start.saturating_add(decoded.1);
+
+                Decoded {
+                    field,
+                    range: start.min(data.len())..position.min(data.len()),
+                    value: decoded.0,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The value of a field starting at `start` and how many bytes it takes up
+fn decode_field(field: &Field, start: usize, data: &[u8]) -> (Option<Value>, usize) {
+    match field.kind {
+        FieldType::Unsigned(size) | FieldType::Signed(size) | FieldType::Float(size) => {
+            let value = data.get(start..start.saturating_add(size)).map(|bytes| {
+                let fold = |value: u64, byte: &u8| (value << 8) | *byte as u64;
+                let value = match field.endian {
+                    Endian::Big => bytes.iter().fold(0, fold),
+                    Endian::Little => bytes.iter().rev().fold(0, fold),
+                };
+
+                match field.kind {
+                    // Shifting the sign bit up to the top and back copies it into the rest
+                    FieldType::Signed(_) => {
+                        let unused = 64 - 8 * size as u32;
+                        Value::Signed((value << unused) as i64 >> unused)
+                    }
+                    FieldType::Float(4) => Value::Float32(f32::from_bits(value as u32)),
+                    FieldType::Float(_) => Value::Float64(f64::from_bits(value)),
+                    _ => Value::Unsigned(value),
+                }
+            });
+            (value, size)
+        }
+        FieldType::Bytes(len) => {
+            let value = data
+                .get(start..start.saturating_add(len))
+                .map(|bytes| Value::Bytes(bytes.to_vec()));
+            (value, len)
+        }
+        FieldType::CStr => {
+            let Some(rest) = data.get(start..) else {
+                return (None, 1);
+            };
+
+            match rest.iter().position(|byte| *byte == 0) {
+                Some(nul) => (
+                    Some(Value::Text {
+                        bytes: rest[..nul].to_vec(),
+                        terminated: true,
+                    }),
+                    nul + 1,
+                ),
+                None => (
+                    Some(Value::Text {
+                        bytes: rest.to_vec(),
+                        terminated: false,
+                    }),
+                    rest.len(),
+                ),
+            }
+        }
+    }
+}
+
+/// Builds a field from its table, errors come with the field's name if it has one. Problems
+/// with no key of their own are put on the `[[field]]` line
+fn parse_field(mut table: Table, endian: Endian) -> Result<Field, (Option<String>, LineError)> {
+    let header = table.line;
+    let name = match table.string("name") {
+        Ok(Some((name, _))) => name,
+        Ok(None) => return Err((None, (header, "missing name".to_string()))),
+        Err(e) => return Err((None, e)),
+    };
+
+    let with_name = |e: LineError| (Some(name.clone()), e);
+
+    let kind = match table.string("type").map_err(with_name)? {
+        Some((kind, line)) => FieldType::parse(&kind).map_err(|e| with_name((line, e)))?,
+        None => return Err(with_name((header, "missing type".to_string()))),
+    };
+    let offset = table.integer("offset").map_err(with_name)?;
+    let endian = match table.string("endian").map_err(with_name)? {
+        Some((endian, line)) => parse_endian(&endian).map_err(|e| with_name((line, e)))?,
+        None => endian,
+    };
+    table.finish().map_err(with_name)?;
+
+    Ok(Field {
+        name,
+        offset,
+        kind,
+        endian,
+    })
+}
+
+fn parse_endian(endian: &str) -> Result<Endian, String> {
+    match endian {
+        "little" | "le" => Ok(Endian::Little),
+        "big" | "be" => Ok(Endian::Big),
+        _ => Err(format!(
+            "unknown endian '{}', expected little or big",
+            endian
+        )),
+    }
+}
+
+/// Cuts a line at a '#' that isn't inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+fn parse_value(value: &str) -> Result<TomlValue, String> {
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut string = String::new();
+        let mut chars = rest.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    if !chars.as_str().trim().is_empty() {
+                        return Err(format!(
+                            "unexpected '{}' after string",
+                            chars.as_str().trim()
+                        ));
+                    }
+                    return Ok(TomlValue::String(string));
+                }
+                '\\' => match chars.next() {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some(c) => return Err(format!("unknown escape '\\{}'", c)),
+                    None => break,
+                },
+                c => string.push(c),
+            }
+        }
+
+        return Err("unterminated string".to_string());
+    }
+
+    parse_integer(value).map(TomlValue::Integer)
+}
+
+/// A TOML integer: decimal, or hex, octal or binary with 0x, 0o or 0b, with optional underscores
+fn parse_integer(literal: &str) -> Result<usize, String> {
+    let (digits, radix) = match literal.get(..2) {
+        Some("0x") => (&literal[2..], 16),
+        Some("0o") => (&literal[2..], 8),
+        Some("0b") => (&literal[2..], 2),
+        _ => (literal, 10),
+    };
+
+    usize::from_str_radix(&digits.replace('_', ""), radix)
+        .map_err(|_| format!("expected a string or a number, got '{}'", literal))
+}
@@ -0,0 +1,336 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Everything that talks to the terminal directly: raw mode, signals and reading input
+
+use hex_it::{
+    file::FileLock,
+    key::{decode_key, Key},
+    line::InputLine,
+    screen::prompt_line,
+};
+#[cfg(unix)]
+use std::sync::OnceLock;
+use std::{
+    io::{self, Write},
+    panic,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+    sync::mpsc::{Receiver, TryRecvError},
+    sync::Mutex,
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// Width and height of the terminal, falling back to 80x24 when it can't be asked
+pub fn terminal_size() -> (usize, usize) {
+    match term_size::dimensions() {
+        Some((width, height)) if width > 0 && height > 0 => (width, height),
+        _ => (80, 24),
+    }
+}
+
+/// Redraws just the prompt and input on the last line, for when only the input changed
+pub fn move_cursor_bottom(prompt: &str, input: &InputLine) {
+    let (width, height) = terminal_size();
+
+    print!("{}", prompt_line(prompt, input, width, height));
+}
+
+/// Makes the terminal wrap pasted text in markers instead of sending it as typed keys
+const BRACKETED_PASTE_ON: &str = "\x1b[?2004h";
+const BRACKETED_PASTE_OFF: &str = "\x1b[?2004l";
+
+/// Turns on mouse button reporting in the SGR format
+const MOUSE_ON: &str = "\x1b[?1000h\x1b[?1006h";
+const MOUSE_OFF: &str = "\x1b[?1006l\x1b[?1000l";
+
+/// Whether setup_terminal turns mouse reporting on, cleared by --no-mouse
+pub static MOUSE_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Set by the SIGCONT handler, the terminal has to be set up again after being suspended
+static RESUMED: AtomicBool = AtomicBool::new(false);
+
+/// The terminal settings from before we switched to raw mode
+#[cfg(unix)]
+static ORIGINAL_TERMIOS: OnceLock<libc::termios> = OnceLock::new();
+
+/// Switches the terminal to raw mode and turns on the modes the editor relies on.
+/// Safe to call again after restore_terminal, like when resuming from a suspend
+pub fn setup_terminal() {
+    #[cfg(unix)]
+    unsafe {
+        let mut termios = std::mem::zeroed::<libc::termios>();
+
+        if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) == 0 {
+            let original = *ORIGINAL_TERMIOS.get_or_init(|| termios);
+
+            // Keys arrive one at a time without echo, and Ctrl+C/Ctrl+Z/Ctrl+S come through as bytes
+            let mut raw = original;
+            raw.c_iflag &= !(libc::IXON | libc::ICRNL | libc::BRKINT | libc::ISTRIP);
+            raw.c_lflag &= !(libc::ECHO | libc::ICANON | libc::ISIG | libc::IEXTEN);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw);
+        }
+    }
+
+    // Terminals without bracketed paste just ignore this
+    print!("{}", BRACKETED_PASTE_ON);
+
+    if MOUSE_ENABLED.load(Ordering::Relaxed) {
+        print!("{}", MOUSE_ON);
+    }
+
+    let _ = io::stdout().flush();
+}
+
+/// Puts the terminal back the way we found it: original settings, attributes reset,
+/// mouse and paste modes off, cursor visible and parked on the last line
+pub fn restore_terminal() {
+    let mut stdout = io::stdout();
+
+    let _ = write!(stdout, "\x1b[{};0H", terminal_size().1);
+
+    let _ = writeln!(
+        stdout,
+        "\x1b[0m\x1b[?25h{}{}",
+        BRACKETED_PASTE_OFF, MOUSE_OFF
+    );
+    let _ = stdout.flush();
+
+    #[cfg(unix)]
+    if let Some(original) = ORIGINAL_TERMIOS.get() {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original);
+        }
+    }
+}
+
+/// SIGTSTP handler, hands the terminal back before actually stopping.
+/// Only async-signal-safe calls in here: write, tcsetattr, signal and raise
+#[cfg(unix)]
+extern "C" fn on_suspend(_: libc::c_int) {
+    // The same as restore_terminal writes, spelled out since formatting allocates
+    const MODES: &[u8] = b"\x1b[0m\x1b[?25h\x1b[?2004l\x1b[?1006l\x1b[?1000l\r\n";
+
+    unsafe {
+        libc::write(libc::STDOUT_FILENO, MODES.as_ptr().cast(), MODES.len());
+
+        if let Some(original) = ORIGINAL_TERMIOS.get() {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original);
+        }
+
+        // Stop for real, then put our handler back once we are continued
+        libc::signal(libc::SIGTSTP, libc::SIG_DFL);
+        libc::raise(libc::SIGTSTP);
+        libc::signal(libc::SIGTSTP, on_suspend as *const () as libc::sighandler_t);
+    }
+}
+
+/// SIGCONT handler, flags that the terminal needs to be set up and redrawn
+#[cfg(unix)]
+extern "C" fn on_resume(_: libc::c_int) {
+    RESUMED.store(true, Ordering::Relaxed);
+}
+
+/// Installs the suspend/resume handlers. SIGCONT is installed without SA_RESTART so a
+/// blocked read in the input thread wakes up and can pass the resume on
+pub fn install_signal_handlers() {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGTSTP, on_suspend as *const () as libc::sighandler_t);
+
+        let mut action = std::mem::zeroed::<libc::sigaction>();
+        action.sa_sigaction = on_resume as *const () as libc::sighandler_t;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGCONT, &action, std::ptr::null_mut());
+    }
+}
+
+/// Suspends the editor like Ctrl+Z does in a shell, returns once it has been continued
+pub fn suspend() {
+    #[cfg(unix)]
+    unsafe {
+        // Goes through on_suspend, which restores the terminal first
+        libc::raise(libc::SIGTSTP);
+    }
+}
+
+/// Tells the input thread to finish, it checks this at least every INPUT_POLL_INTERVAL.
+/// The file watcher stops on it too
+pub static STOP_INPUT: AtomicBool = AtomicBool::new(false);
+
+/// How long the input thread waits for a key before checking STOP_INPUT again
+#[cfg(unix)]
+const INPUT_POLL_INTERVAL: libc::c_int = 50;
+
+/// Reads one byte of input, None once stdin is closed or the thread is told to stop.
+/// Returns Some(None) if the wait was interrupted by a resume instead
+#[cfg(unix)]
+pub fn read_byte() -> Option<Option<u8>> {
+    let mut byte = 0u8;
+    let mut stdin = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+
+    loop {
+        if STOP_INPUT.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let ready = unsafe { libc::poll(&mut stdin, 1, INPUT_POLL_INTERVAL) };
+        let read = match ready {
+            0 => continue,
+            1 => unsafe { libc::read(libc::STDIN_FILENO, (&mut byte as *mut u8).cast(), 1) },
+            _ => -1,
+        };
+
+        match read {
+            1 => return Some(Some(byte)),
+            0 => return None,
+            _ if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => {
+                if RESUMED.swap(false, Ordering::Relaxed) {
+                    return Some(None);
+                }
+            }
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn read_byte() -> Option<Option<u8>> {
+    Some(Some(tui_tools::getch()))
+}
+
+/// The locks on the files being edited, released when their buffer closes or on the way out
+static HELD_LOCKS: Mutex<Vec<(PathBuf, FileLock)>> = Mutex::new(Vec::new());
+
+/// Keeps a lock on a file until its buffer is closed or the editor exits
+pub fn hold_lock(path: &Path, lock: FileLock) {
+    if let Ok(mut held) = HELD_LOCKS.lock() {
+        held.push((path.to_path_buf(), lock));
+    }
+}
+
+/// Lets go of the lock on a file, if it is held
+pub fn release_lock(path: &Path) {
+    if let Ok(mut held) = HELD_LOCKS.lock() {
+        held.retain(|(locked, _)| locked != path);
+    }
+}
+
+/// Lets go of every file lock. Never waits, so it is safe from the panic hook
+fn release_locks() {
+    if let Ok(mut held) = HELD_LOCKS.try_lock() {
+        held.clear();
+    }
+}
+
+/// Makes panics restore the terminal before the message is printed, so it lands on a clean line
+/// instead of in the middle of the dump, and leaves the terminal usable afterwards
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        release_locks();
+        default_hook(info);
+        eprintln!(
+            "hex_it crashed, please report this at {}/issues",
+            env!("CARGO_PKG_REPOSITORY")
+        );
+        process::exit(101);
+    }));
+}
+
+/// Stops the input thread, restores the terminal, releases the file locks and exits,
+/// every way out of the editor goes through here
+pub fn exit_editor(input_thread: JoinHandle<()>, code: i32) -> ! {
+    STOP_INPUT.store(true, Ordering::Relaxed);
+
+    // getch can't be interrupted, so elsewhere the thread is left to die with the process
+    if cfg!(unix) {
+        let _ = input_thread.join();
+    }
+
+    restore_terminal();
+    release_locks();
+    process::exit(code);
+}
+
+/// What the input thread passes on to the main loop
+pub enum Input {
+    Byte(u8),
+    /// The process was continued after being stopped from outside
+    Resumed,
+    /// The file watcher saw the file change
+    FileChanged,
+    /// A background job sent progress or its result
+    JobUpdate,
+    /// The autosave timer went off
+    Autosave,
+    /// stdin was closed, no more keys will come
+    Closed,
+}
+
+/// Waits a little while for the next byte of a sequence that has already started
+fn next_byte(rx: &Receiver<Input>, timeout: Duration) -> Option<u8> {
+    match rx.recv_timeout(timeout).ok()? {
+        Input::Byte(byte) => Some(byte),
+        // Anything else in the middle of a sequence cuts it short
+        Input::Resumed
+        | Input::FileChanged
+        | Input::JobUpdate
+        | Input::Autosave
+        | Input::Closed => None,
+    }
+}
+
+/// Blocks until the next keypress. Returns None once stdin is closed
+pub fn read_key(rx: &Receiver<Input>) -> Option<Key> {
+    key_from(rx.recv().ok()?, rx)
+}
+
+/// What `poll_key` found
+pub enum Polled {
+    Key(Key),
+    /// Nothing was typed yet
+    Nothing,
+    /// stdin was closed, no more keys will come
+    Closed,
+}
+
+/// Takes the next keypress if there is one, without waiting, for work that runs between keys
+pub fn poll_key(rx: &Receiver<Input>) -> Polled {
+    let input = match rx.try_recv() {
+        Ok(input) => input,
+        Err(TryRecvError::Empty) => return Polled::Nothing,
+        Err(TryRecvError::Disconnected) => return Polled::Closed,
+    };
+
+    match key_from(input, rx) {
+        Some(key) => Polled::Key(key),
+        None => Polled::Closed,
+    }
+}
+
+/// Decodes the key an input starts, reading the rest of an escape sequence if it is one
+fn key_from(input: Input, rx: &Receiver<Input>) -> Option<Key> {
+    let first = match input {
+        Input::Byte(byte) => byte,
+        Input::Resumed => return Some(Key::Resume),
+        Input::FileChanged => return Some(Key::FileChanged),
+        Input::JobUpdate => return Some(Key::JobUpdate),
+        Input::Autosave => return Some(Key::Autosave),
+        Input::Closed => return None,
+    };
+
+    Some(decode_key(first, |timeout| next_byte(rx, timeout)))
+}
@@ -0,0 +1,419 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! The colors of the dump, status bar and messages. Every color is an SGR escape sequence,
+//! so a theme is just a handful of strings and other themes can be added next to the default one.
+//! `palette set` and the config change single colors, written like `bold+red` or `208`
+
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    sync::{Mutex, OnceLock},
+};
+
+/// Ends whatever color came before
+pub const RESET: &str = "\x1b[0m";
+
+/// When to use colors at all, from --color
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Even into pipes and files, and with NO_COLOR set
+    Always,
+    /// Only on a terminal and only without NO_COLOR
+    #[default]
+    Auto,
+    /// Never
+    Never,
+}
+
+impl ColorChoice {
+    /// Reads the value of --color
+    pub fn parse(value: &str) -> Result<ColorChoice, String> {
+        match value {
+            "always" => Ok(ColorChoice::Always),
+            "auto" => Ok(ColorChoice::Auto),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "Unknown color choice '{}', expected always, auto or never.",
+                value
+            )),
+        }
+    }
+
+    /// Whether to use colors, the one place this is decided. `no_color` is the NO_COLOR
+    /// environment variable, which turns colors off when set to anything but an empty string.
+    /// `is_tty` says whether the output goes to a terminal, it is only asked for `Auto`
+    pub fn enabled(self, no_color: Option<&OsStr>, is_tty: impl FnOnce() -> bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => no_color.is_none_or(OsStr::is_empty) && is_tty(),
+        }
+    }
+}
+
+/// The kinds of bytes the hex column tells apart by color
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteClass {
+    /// 00
+    Null,
+    /// Space, tab, newline, carriage return, vertical tab and form feed
+    Whitespace,
+    /// The rest of printable ASCII
+    Printable,
+    /// The other ASCII control codes and DEL
+    Control,
+    /// 80 to FF, outside ASCII
+    High,
+}
+
+impl ByteClass {
+    /// The class a byte falls in
+    pub fn of(byte: u8) -> ByteClass {
+        match byte {
+            0x00 => ByteClass::Null,
+            b' ' | b'\t' | b'\n' | b'\r' | 0x0B | 0x0C => ByteClass::Whitespace,
+            0x21..=0x7E => ByteClass::Printable,
+            0x01..=0x1F | 0x7F => ByteClass::Control,
+            0x80..=0xFF => ByteClass::High,
+        }
+    }
+}
+
+/// What each part of the screen is colored with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// The offset gutter
+    pub offset: &'static str,
+    /// Hex pairs of zero bytes
+    pub null: &'static str,
+    /// Hex pairs of whitespace
+    pub whitespace: &'static str,
+    /// Hex pairs of the rest of printable ASCII
+    pub printable: &'static str,
+    /// Hex pairs of control codes
+    pub control: &'static str,
+    /// Hex pairs of bytes outside ASCII
+    pub high: &'static str,
+    /// The text column
+    pub text: &'static str,
+    /// The dot a newline shows as in the text column
+    pub newline: &'static str,
+    /// The status bar between the dump and the command line
+    pub divider: &'static str,
+    /// Error messages and warnings
+    pub error: &'static str,
+    /// Hex pairs of bytes changed since the last save
+    pub modified: &'static str,
+    /// Hex pairs of bytes in a search match
+    pub found: &'static str,
+}
+
+/// Names of the colors a palette can change, as `palette` and the config write them
+pub const PALETTE_NAMES: [&str; 12] = [
+    "offset",
+    "zero",
+    "whitespace",
+    "printable",
+    "control",
+    "high",
+    "text",
+    "newline",
+    "divider",
+    "error",
+    "modified",
+    "match",
+];
+
+/// What else is going on at a byte besides its class, for `Theme::layer`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Marks {
+    /// The byte is in a search match
+    pub found: bool,
+    /// The byte changed since the last save
+    pub modified: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            offset: "\x1b[2m",
+            null: "\x1b[90m",
+            whitespace: "\x1b[32m",
+            printable: "\x1b[36m",
+            control: "\x1b[33m",
+            high: "\x1b[35m",
+            text: "\x1b[94m",
+            newline: "\x1b[1;30m",
+            divider: "\x1b[1;34m",
+            error: "\x1b[1;31m",
+            modified: "\x1b[1;31m",
+            found: "\x1b[30;46m",
+        }
+    }
+}
+
+impl Theme {
+    /// The color of a byte's hex pair
+    pub fn byte(&self, byte: u8) -> &'static str {
+        match ByteClass::of(byte) {
+            ByteClass::Null => self.null,
+            ByteClass::Whitespace => self.whitespace,
+            ByteClass::Printable => self.printable,
+            ByteClass::Control => self.control,
+            ByteClass::High => self.high,
+        }
+    }
+
+    /// The color of a byte's hex pair once its marks are taken into account. A search match
+    /// wins over a modification, which wins over the class of the byte
+    pub fn layer(&self, byte: u8, marks: Marks) -> &'static str {
+        match marks {
+            Marks { found: true, .. } => self.found,
+            Marks { modified: true, .. } => self.modified,
+            _ => self.byte(byte),
+        }
+    }
+
+    /// Text in one of the theme's colors, reset after
+    pub fn paint(color: &str, text: &str) -> String {
+        format!("{}{}{}", color, text, RESET)
+    }
+
+    /// A color by its name in `PALETTE_NAMES`
+    pub fn get(&self, name: &str) -> Option<&'static str> {
+        let mut theme = *self;
+        theme.color_mut(name).map(|color| *color)
+    }
+
+    /// Changes a color by its name in `PALETTE_NAMES`
+    pub fn set(&mut self, name: &str, color: &'static str) -> Result<(), String> {
+        let slot = self.color_mut(name).ok_or_else(|| {
+            format!(
+                "unknown palette entry '{}', expected one of {}",
+                name,
+                PALETTE_NAMES.join(", ")
+            )
+        })?;
+        *slot = color;
+        Ok(())
+    }
+
+    fn color_mut(&mut self, name: &str) -> Option<&mut &'static str> {
+        Some(match name {
+            "offset" => &mut self.offset,
+            "zero" => &mut self.null,
+            "whitespace" => &mut self.whitespace,
+            "printable" => &mut self.printable,
+            "control" => &mut self.control,
+            "high" => &mut self.high,
+            "text" => &mut self.text,
+            "newline" => &mut self.newline,
+            "divider" => &mut self.divider,
+            "error" => &mut self.error,
+            "modified" => &mut self.modified,
+            "match" => &mut self.found,
+            _ => return None,
+        })
+    }
+}
+
+/// The 8 basic colors in SGR order, `bright-` in front of one picks its bright version
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// Attributes a color can have besides its colors, with their SGR codes
+const ATTRIBUTES: [(&str, u8); 4] = [("bold", 1), ("dim", 2), ("underline", 4), ("reverse", 7)];
+
+/// The SGR parameters of a color, `base` is 30 for the foreground and 40 for the background
+fn color_params(name: &str, base: u8) -> Result<String, String> {
+    if let Some(index) = COLOR_NAMES.iter().position(|&color| color == name) {
+        return Ok((base + index as u8).to_string());
+    }
+
+    if let Some(index) = name
+        .strip_prefix("bright-")
+        .and_then(|bright| COLOR_NAMES.iter().position(|&color| color == bright))
+    {
+        return Ok((base + 60 + index as u8).to_string());
+    }
+
+    match name.parse::<u8>() {
+        Ok(index) => Ok(format!("{};5;{}", base + 8, index)),
+        Err(_) if name.chars().all(|c| c.is_ascii_digit()) => {
+            Err(format!("color {} is past 255", name))
+        }
+        Err(_) => Err(format!(
+            "unknown color '{}', expected {}, bright-<color> or 0 to 255",
+            name,
+            COLOR_NAMES.join(", ")
+        )),
+    }
+}
+
+/// Parses a color like `red`, `bright-blue`, `208`, `bold+red` or `black+on-cyan` into its SGR
+/// sequence. Parts are joined with `+`: one foreground color, one background color after `on-`
+/// and any of bold, dim, underline and reverse. `default` is no color at all
+pub fn parse_color(spec: &str) -> Result<&'static str, String> {
+    let spec = spec.trim().to_ascii_lowercase();
+    if spec == "default" {
+        return Ok("");
+    }
+
+    let mut params = Vec::new();
+    let (mut foreground, mut background) = (false, false);
+
+    for part in spec.split('+') {
+        if let Some((_, code)) = ATTRIBUTES.iter().find(|(name, _)| *name == part) {
+            params.push(code.to_string());
+        } else if let Some(color) = part.strip_prefix("on-") {
+            if std::mem::replace(&mut background, true) {
+                return Err(format!("'{}' has two background colors", spec));
+            }
+            params.push(color_params(color, 40)?);
+        } else if part.is_empty() {
+            return Err(format!("'{}' has an empty part", spec));
+        } else {
+            if std::mem::replace(&mut foreground, true) {
+                return Err(format!("'{}' has two foreground colors", spec));
+            }
+            params.push(color_params(part, 30)?);
+        }
+    }
+
+    Ok(intern(format!("\x1b[{}m", params.join(";"))))
+}
+
+/// Writes an SGR sequence back the way `parse_color` reads it
+pub fn describe_color(sgr: &str) -> String {
+    let Some(params) = sgr
+        .strip_prefix("\x1b[")
+        .and_then(|rest| rest.strip_suffix('m'))
+    else {
+        return "default".to_string();
+    };
+
+    let mut parts = Vec::new();
+    let mut codes = params.split(';').filter_map(|code| code.parse::<u8>().ok());
+
+    while let Some(code) = codes.next() {
+        let (prefix, code) = match code {
+            40..=49 | 100..=107 => ("on-", code - 10),
+            _ => ("", code),
+        };
+        let name = match code {
+            30..=37 => COLOR_NAMES[(code - 30) as usize].to_string(),
+            90..=97 => format!("bright-{}", COLOR_NAMES[(code - 90) as usize]),
+            38 => {
+                // 38;5;N, the 5 says a palette index follows
+                codes.next();
+                codes
+                    .next()
+                    .map_or_else(String::new, |index| index.to_string())
+            }
+            _ => match ATTRIBUTES.iter().find(|(_, attribute)| *attribute == code) {
+                Some((name, _)) => name.to_string(),
+                None => continue,
+            },
+        };
+        parts.push(format!("{}{}", prefix, name));
+    }
+
+    match parts.is_empty() {
+        true => "default".to_string(),
+        false => parts.join("+"),
+    }
+}
+
+/// The 16 basic terminal colors as xterm shows them, for `css`
+const BASIC_RGB: [u32; 16] = [
+    0x000000, 0xCD0000, 0x00CD00, 0xCDCD00, 0x0000EE, 0xCD00CD, 0x00CDCD, 0xE5E5E5, 0x7F7F7F,
+    0xFF0000, 0x00FF00, 0xFFFF00, 0x5C5CFF, 0xFF00FF, 0x00FFFF, 0xFFFFFF,
+];
+
+/// A color of the 256-color palette as `#RRGGBB`: the basic 16, a 6x6x6 cube, then grays
+fn palette_rgb(index: u8) -> String {
+    let rgb = match index {
+        0..=15 => BASIC_RGB[index as usize],
+        16..=231 => {
+            let level = |n: u8| match n {
+                0 => 0,
+                n => 55 + n as u32 * 40,
+            };
+            let cube = index - 16;
+            level(cube / 36) << 16 | level(cube / 6 % 6) << 8 | level(cube % 6)
+        }
+        232..=255 => 0x010101 * (8 + (index - 232) as u32 * 10),
+    };
+
+    format!("#{:06x}", rgb)
+}
+
+/// The CSS declarations that show text the way an SGR sequence from `parse_color` does, for
+/// exporting the dump as HTML. `foreground` and `background` are the page's colors, which
+/// `reverse` swaps in where the sequence has none. Bold makes a basic color bright like most
+/// terminals do, so bold black stays readable on a dark page
+pub fn css(sgr: &str, foreground: &str, background: &str) -> String {
+    let params = sgr
+        .strip_prefix("\x1b[")
+        .and_then(|rest| rest.strip_suffix('m'))
+        .unwrap_or("");
+    let mut codes = params.split(';').filter_map(|code| code.parse::<u8>().ok());
+
+    let (mut bold, mut reverse) = (false, false);
+    let (mut color, mut fill) = (None, None);
+    let mut declarations = Vec::new();
+
+    while let Some(code) = codes.next() {
+        match code {
+            1 => bold = true,
+            2 => declarations.push("opacity:0.6".to_string()),
+            4 => declarations.push("text-decoration:underline".to_string()),
+            7 => reverse = true,
+            30..=37 => color = Some(code - 30),
+            90..=97 => color = Some(code - 90 + 8),
+            40..=47 => fill = Some(code - 40),
+            100..=107 => fill = Some(code - 100 + 8),
+            // 38;5;N and 48;5;N, the 5 says a palette index follows
+            38 => color = codes.nth(1),
+            48 => fill = codes.nth(1),
+            _ => {}
+        }
+    }
+
+    if bold {
+        declarations.insert(0, "font-weight:bold".to_string());
+        color = color.map(|color| if color < 8 { color + 8 } else { color });
+    }
+
+    let mut color = color.map(palette_rgb);
+    let mut fill = fill.map(palette_rgb);
+    if reverse {
+        (color, fill) = (
+            Some(fill.unwrap_or_else(|| background.to_string())),
+            Some(color.unwrap_or_else(|| foreground.to_string())),
+        );
+    }
+
+    declarations.extend(color.map(|color| format!("color:{}", color)));
+    declarations.extend(fill.map(|fill| format!("background:{}", fill)));
+    declarations.join(";")
+}
+
+/// Gives a color a 'static lifetime so themes stay `Copy`. Each distinct color is kept once,
+/// and there are only so many of them
+fn intern(sgr: String) -> &'static str {
+    static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+    let mut interned = INTERNED.get_or_init(Default::default).lock().unwrap();
+    if let Some(&known) = interned.get(sgr.as_str()) {
+        return known;
+    }
+
+    let leaked: &'static str = Box::leak(sgr.into_boxed_str());
+    interned.insert(leaked);
+    leaked
+}
@@ -0,0 +1,111 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+//! Checking bytes against a digest given on the command line, for `--verify sha256=ab34...`.
+//! The names are the ones `hash` and `checksum` take
+
+use crate::{
+    checksum::{Adler32, Crc, CrcParams, Fletcher16, Fletcher32, CRC_PRESETS},
+    digest::Sha256,
+};
+
+/// A digest the bytes should have
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expected {
+    /// The algorithm, like sha256 or crc32, in lowercase
+    pub algorithm: String,
+    /// The digest as hex, in the case `digest` writes it
+    pub digest: String,
+}
+
+impl Expected {
+    /// Reads `name=hex`, like crc32=CBF43926. Unknown names and digests that aren't hex of the
+    /// algorithm's length are refused
+    pub fn parse(text: &str) -> Result<Expected, String> {
+        let Some((algorithm, digest)) = text.split_once('=') else {
+            return Err("expected ALGORITHM=DIGEST, like crc32=CBF43926".to_string());
+        };
+        let algorithm = algorithm.to_ascii_lowercase();
+        let digest = match algorithm.as_str() {
+            "sha256" => digest.trim().to_ascii_lowercase(),
+            _ => digest.trim().to_ascii_uppercase(),
+        };
+
+        let Some(digits) = digest_digits(&algorithm) else {
+            let names = ["sha256", "adler32", "fletcher16", "fletcher32"]
+                .into_iter()
+                .chain(CRC_PRESETS.iter().map(|(name, _)| *name))
+                .collect::<Vec<&str>>()
+                .join(", ");
+            return Err(format!(
+                "unknown algorithm '{}', expected one of {}",
+                algorithm, names
+            ));
+        };
+        if digest.len() != digits || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "a {} digest is {} hex digits, not '{}'",
+                algorithm, digits, digest
+            ));
+        }
+
+        Ok(Expected { algorithm, digest })
+    }
+
+    /// Works out the digest of the bytes, Ok when it is the expected one and the digest that
+    /// came out otherwise, an empty one for an algorithm that doesn't exist
+    pub fn check(&self, bytes: &[u8]) -> Result<(), String> {
+        let got = digest(&self.algorithm, bytes).unwrap_or_default();
+        match !got.is_empty() && got.eq_ignore_ascii_case(&self.digest) {
+            true => Ok(()),
+            false => Err(got),
+        }
+    }
+}
+
+/// How many hex digits a digest of the algorithm has, None for unknown algorithms
+fn digest_digits(algorithm: &str) -> Option<usize> {
+    match algorithm {
+        "sha256" => Some(64),
+        "adler32" | "fletcher32" => Some(8),
+        "fletcher16" => Some(4),
+        name => CrcParams::preset(name).map(|params| params.width.div_ceil(4) as usize),
+    }
+}
+
+/// The digest of the bytes as hex, the way `hash` and `checksum` show it: lowercase for
+/// sha256 like sha256sum, uppercase for checksums. None for unknown algorithms
+pub fn digest(algorithm: &str, bytes: &[u8]) -> Option<String> {
+    let digits = digest_digits(algorithm)?;
+    let value = match algorithm {
+        "sha256" => {
+            let mut sha = Sha256::new();
+            sha.update(bytes);
+            return Some(sha.finish_hex());
+        }
+        "adler32" => {
+            let mut adler = Adler32::new();
+            adler.update(bytes);
+            u64::from(adler.value())
+        }
+        "fletcher16" => {
+            let mut fletcher = Fletcher16::new();
+            fletcher.update(bytes);
+            u64::from(fletcher.value())
+        }
+        "fletcher32" => {
+            let mut fletcher = Fletcher32::new();
+            fletcher.update(bytes);
+            u64::from(fletcher.value())
+        }
+        name => {
+            let mut crc = Crc::new(CrcParams::preset(name)?);
+            crc.update(bytes);
+            crc.value()
+        }
+    };
+
+    Some(format!("{:0digits$X}", value))
+}
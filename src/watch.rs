@@ -0,0 +1,79 @@
+//
+// Copyright 2023, [object Object]
+// Licensed under MIT
+//
+
+use crate::terminal::{Input, STOP_INPUT};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// How often the watched file is looked at
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A running file watcher, its thread stops once this is dropped
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Size and modification time, cheap enough to check every interval
+fn stat(path: &Path) -> Option<(u64, Option<SystemTime>)> {
+    let metadata = fs::metadata(path).ok()?;
+
+    Some((metadata.len(), metadata.modified().ok()))
+}
+
+/// Polls a file from a thread of its own and sends Input::FileChanged whenever its size or
+/// modification time changes. Whether the contents really changed is for the editor to work out,
+/// so the main loop is never held up by a slow disk
+pub fn watch_file(path: PathBuf, tx: Sender<Input>) -> Watcher {
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+
+    thread::spawn(move || {
+        let mut last = stat(&path);
+
+        while !STOP_INPUT.load(Ordering::Relaxed) && !stopped.load(Ordering::Relaxed) {
+            thread::sleep(WATCH_INTERVAL);
+
+            let now = stat(&path);
+            if now != last {
+                last = now;
+
+                if tx.send(Input::FileChanged).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    Watcher { stop }
+}
+
+/// Sends Input::Autosave every interval from a thread of its own, until the editor exits.
+/// The main loop does the autosaving, so it never happens in the middle of an edit
+pub fn autosave_timer(interval: Duration, tx: Sender<Input>) {
+    thread::spawn(move || {
+        while !STOP_INPUT.load(Ordering::Relaxed) {
+            thread::sleep(interval);
+
+            if tx.send(Input::Autosave).is_err() {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,155 @@
+use hex_it::args::{tokenize, Token};
+
+/// Just the text of each argument
+fn args(line: &str) -> Vec<String> {
+    tokenize(line)
+        .unwrap()
+        .into_iter()
+        .map(|token| token.text())
+        .collect()
+}
+
+#[test]
+fn whitespace_separates_arguments() {
+    assert_eq!(args("save out.bin"), ["save", "out.bin"]);
+    assert_eq!(args("  find   DE AD\tBE  "), ["find", "DE", "AD", "BE"]);
+    assert!(tokenize("").unwrap().is_empty());
+    assert!(tokenize("   ").unwrap().is_empty());
+}
+
+#[test]
+fn quoted_paths_stay_one_argument() {
+    assert_eq!(
+        args("save \"/home/me/My Files/out.bin\""),
+        ["save", "/home/me/My Files/out.bin"]
+    );
+    assert_eq!(
+        args("save '/home/me/My Files/out.bin'"),
+        ["save", "/home/me/My Files/out.bin"]
+    );
+    assert_eq!(
+        args("save /home/me/My\\ Files/out.bin"),
+        ["save", "/home/me/My Files/out.bin"]
+    );
+    // Quotes in the middle of an argument only group what's between them
+    assert_eq!(args("open My\" \"Files/a.bin"), ["open", "My Files/a.bin"]);
+}
+
+#[test]
+fn embedded_quotes() {
+    assert_eq!(args(r#"find "say \"hi\"""#), ["find", "say \"hi\""]);
+    assert_eq!(args(r#"find 'say "hi"'"#), ["find", "say \"hi\""]);
+    assert_eq!(args(r#"find "it's""#), ["find", "it's"]);
+    assert_eq!(args(r"find it\'s"), ["find", "it's"]);
+    // Single quotes take backslashes as they are
+    assert_eq!(args(r"open 'C:\dir'"), ["open", r"C:\dir"]);
+    // In double quotes a backslash is always an escape
+    assert_eq!(args(r#"find "a\\b\n""#), ["find", "a\\b\n"]);
+}
+
+#[test]
+fn quoting_is_remembered() {
+    assert_eq!(
+        tokenize("find \"cafe\" cafe \"\"").unwrap(),
+        [
+            Token {
+                bytes: b"find".to_vec(),
+                quoted: false
+            },
+            Token {
+                bytes: b"cafe".to_vec(),
+                quoted: true
+            },
+            Token {
+                bytes: b"cafe".to_vec(),
+                quoted: false
+            },
+            Token {
+                bytes: Vec::new(),
+                quoted: true
+            },
+        ]
+    );
+}
+
+#[test]
+fn free_text_arguments() {
+    // A command taking free text like `note` gets words it can join back up,
+    // or one argument with the spacing kept when it's quoted
+    assert_eq!(
+        args("note check  this header"),
+        ["note", "check", "this", "header"]
+    );
+    assert_eq!(
+        args("note \"check  this header\""),
+        ["note", "check  this header"]
+    );
+    assert_eq!(
+        args("ftext \"two  spaces\" 'and tabs\t'"),
+        ["ftext", "two  spaces", "and tabs\t"]
+    );
+}
+
+#[test]
+fn unterminated_quotes_are_errors() {
+    assert_eq!(
+        tokenize("save \"My Files/out.bin").unwrap_err(),
+        "unterminated \" at column 6"
+    );
+    assert_eq!(
+        tokenize("note 'it").unwrap_err(),
+        "unterminated ' at column 6"
+    );
+    assert_eq!(
+        tokenize("find \"a\\\"").unwrap_err(),
+        "unterminated \" at column 6"
+    );
+    assert_eq!(
+        tokenize("open dir\\").unwrap_err(),
+        "nothing after the backslash at column 9"
+    );
+}
+
+#[test]
+fn escapes_in_double_quotes() {
+    let tokens = tokenize(r#"ftext "foo\x00bar" "\xff\xFE\t\r\0\'""#).unwrap();
+    assert_eq!(tokens[1].bytes, b"foo\0bar");
+    assert_eq!(tokens[2].bytes, b"\xFF\xFE\t\r\0'");
+
+    // Escapes are only for double quotes, the other two take backslashes as they are
+    assert_eq!(args(r"ftext '\x41' \x41"), ["ftext", r"\x41", "x41"]);
+    assert_eq!(
+        args(r#"replace-text "a\"b" "c""#),
+        ["replace-text", "a\"b", "c"]
+    );
+}
+
+#[test]
+fn multi_byte_text_and_escapes() {
+    // Text is written as UTF-8 and escapes can spell out the same bytes one at a time
+    let tokens = tokenize(r#"settext 10 "€é" "\xE2\x82\xAC\xC3\xA9" -z"#).unwrap();
+    assert_eq!(tokens[2].bytes, "€é".as_bytes());
+    assert_eq!(tokens[3].bytes, tokens[2].bytes);
+    assert!(!tokens[4].quoted);
+}
+
+#[test]
+fn invalid_escapes_are_errors() {
+    assert_eq!(
+        tokenize(r#"find "ab\qc""#).unwrap_err(),
+        "invalid escape '\\q' at column 9"
+    );
+    assert_eq!(
+        tokenize(r#"find "\x4""#).unwrap_err(),
+        "invalid escape '\\x4\"' at column 7"
+    );
+    assert_eq!(
+        tokenize(r#"find "\xZZ""#).unwrap_err(),
+        "invalid escape '\\xZZ' at column 7"
+    );
+    // Columns count chars, not bytes
+    assert_eq!(
+        tokenize(r#"find "é\é""#).unwrap_err(),
+        "invalid escape '\\é' at column 8"
+    );
+}
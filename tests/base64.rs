@@ -0,0 +1,62 @@
+use hex_it::base64::{decode, encode, Base64Error};
+
+/// The test vectors of RFC 4648
+const VECTORS: [(&str, &str); 7] = [
+    ("", ""),
+    ("f", "Zg=="),
+    ("fo", "Zm8="),
+    ("foo", "Zm9v"),
+    ("foob", "Zm9vYg=="),
+    ("fooba", "Zm9vYmE="),
+    ("foobar", "Zm9vYmFy"),
+];
+
+#[test]
+fn encodes_and_decodes_the_rfc_vectors() {
+    for (bytes, text) in VECTORS {
+        assert_eq!(encode(bytes.as_bytes()), text);
+        assert_eq!(decode(text.as_bytes()).unwrap(), bytes.as_bytes());
+    }
+}
+
+#[test]
+fn every_byte_round_trips() {
+    let bytes = (0..=255).collect::<Vec<u8>>();
+    let text = encode(&bytes);
+
+    assert!(text.starts_with("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4v"));
+    assert!(text.ends_with("+/w=="));
+    assert_eq!(decode(text.as_bytes()).unwrap(), bytes);
+}
+
+#[test]
+fn url_safe_text_whitespace_and_no_padding_decode() {
+    assert_eq!(decode(b"-_8=").unwrap(), [0xFB, 0xFF]);
+    assert_eq!(decode(b"+/8=").unwrap(), [0xFB, 0xFF]);
+    assert_eq!(decode(b"-/8").unwrap(), [0xFB, 0xFF]);
+    assert_eq!(decode(b"Zm9v\r\nYmFy\n").unwrap(), b"foobar");
+    assert_eq!(decode(b"  Zg ").unwrap(), b"f");
+}
+
+#[test]
+fn bad_text_says_where() {
+    assert_eq!(
+        decode(b"Zm9v!mFy"),
+        Err(Base64Error {
+            position: 4,
+            reason: "not a base64 character"
+        })
+    );
+    assert_eq!(decode(b"Zg==Zg").unwrap_err().position, 4);
+    assert_eq!(
+        decode(b"Zg==Zg").unwrap_err().reason,
+        "data after the padding"
+    );
+    assert_eq!(decode(b"Z===").unwrap_err().position, 1);
+    assert_eq!(decode(b"Zm8==").unwrap_err().position, 4);
+    assert_eq!(decode(b"Zm9vY").unwrap_err().position, 4);
+    assert_eq!(
+        decode(b"Zm9vY\n").unwrap_err().to_string(),
+        "one character more than whole bytes take at 4"
+    );
+}
@@ -0,0 +1,150 @@
+use hex_it::{
+    carve::{carve, file_name, Carver, Extent, Preset, Region, Status, PRESETS},
+    search::Pattern,
+};
+
+const PNG: &[u8] = include_bytes!("fixtures/tiny.png");
+
+/// A disk image of sorts: junk, a PNG, junk, a JPEG and a PNG that was cut off
+fn image() -> Vec<u8> {
+    let mut bytes = vec![0xAA; 0x10];
+    bytes.extend_from_slice(PNG);
+    bytes.extend_from_slice(&[0x55; 0x7]);
+    bytes.extend_from_slice(b"\xFF\xD8\xFF\xE0 jpeg \xFF\xD9");
+    bytes.extend_from_slice(&PNG[..0x30]);
+    bytes
+}
+
+fn preset(name: &str) -> (Pattern, Extent) {
+    Preset::find(name).unwrap().patterns()
+}
+
+#[test]
+fn presets_find_whole_files() {
+    let bytes = image();
+    let (start, extent) = preset("png");
+
+    let regions = carve(&bytes, &start, &extent, 100);
+    assert_eq!(
+        regions,
+        [
+            Region {
+                offset: 0x10,
+                length: PNG.len(),
+                status: Status::Whole,
+            },
+            Region {
+                offset: 0x10 + PNG.len() + 0x13,
+                length: 0,
+                status: Status::NoEnd,
+            },
+        ]
+    );
+    assert_eq!(&bytes[0x10..0x10 + PNG.len()], PNG);
+
+    let (start, extent) = preset("JPEG");
+    let regions = carve(&bytes, &start, &extent, 100);
+    assert_eq!(regions.len(), 1);
+    assert_eq!(regions[0].length, 0xC);
+}
+
+#[test]
+fn fixed_lengths_are_cut_short_by_the_end() {
+    let bytes = b"..MZ1234..MZ56";
+    let regions = carve(bytes, &Pattern::exact(b"MZ"), &Extent::Length(6), 100);
+
+    assert_eq!(
+        regions,
+        [
+            Region {
+                offset: 2,
+                length: 6,
+                status: Status::Whole,
+            },
+            Region {
+                offset: 0xA,
+                length: 4,
+                status: Status::CutShort,
+            },
+        ]
+    );
+    assert!(regions.iter().all(Region::carved));
+}
+
+#[test]
+fn matches_inside_a_carved_region_are_left_out() {
+    let bytes = b"ABAB..AB";
+    let regions = carve(bytes, &Pattern::exact(b"AB"), &Extent::Length(4), 100);
+
+    let statuses: Vec<Status> = regions.iter().map(|region| region.status).collect();
+    assert_eq!(
+        statuses,
+        [Status::Whole, Status::Inside(0), Status::CutShort]
+    );
+    assert!(!regions[1].carved());
+}
+
+#[test]
+fn wildcards_and_trailing_bytes() {
+    let (start, extent) = preset("gif");
+    let bytes = b"GIF87a..\x00\x3B GIF89a\x00\x3B";
+    let offsets: Vec<usize> = carve(bytes, &start, &extent, 100)
+        .iter()
+        .map(|region| region.offset)
+        .collect();
+    assert_eq!(offsets, [0, 0xB]);
+
+    // The end record of a zip has 18 bytes after its signature
+    let (start, extent) = preset("zip");
+    let mut zip = b"PK\x03\x04 data PK\x05\x06".to_vec();
+    zip.extend_from_slice(&[0; 18]);
+    let regions = carve(&zip, &start, &extent, 100);
+    assert_eq!(regions[0].length, zip.len());
+    assert_eq!(
+        carve(&zip[..zip.len() - 1], &start, &extent, 100)[0].status,
+        Status::NoEnd
+    );
+}
+
+#[test]
+fn carving_a_chunk_at_a_time_finds_the_same() {
+    let mut bytes = image();
+    bytes.extend_from_slice(PNG);
+    for name in ["png", "jpeg"] {
+        let (start, extent) = preset(name);
+        let whole = carve(&bytes, &start, &extent, 100);
+
+        // Chunks that end inside a signature still find it
+        for chunk in [1, 3, 0x40] {
+            let mut carver = Carver::new(&start, &extent, 100);
+            let mut feeds = 0;
+            while !carver.is_done(bytes.len()) {
+                carver.feed(&bytes, chunk);
+                feeds += 1;
+            }
+            assert_eq!(carver.searched(), bytes.len());
+            assert_eq!(feeds, bytes.len().div_ceil(chunk), "{name} {chunk}");
+            assert!(!carver.is_full());
+            assert_eq!(carver.into_regions(), whole, "{name} {chunk}");
+        }
+    }
+}
+
+#[test]
+fn stops_at_the_limit() {
+    let bytes = [0x42; 10];
+    assert_eq!(
+        carve(&bytes, &Pattern::exact(&[0x42]), &Extent::Length(1), 3).len(),
+        3
+    );
+    assert_eq!(carve(b"", &Pattern::exact(b"A"), &Extent::Length(1), 3), []);
+}
+
+#[test]
+fn every_preset_parses() {
+    for preset in PRESETS {
+        preset.patterns();
+    }
+    assert_eq!(Preset::find("tiff"), None);
+    assert_eq!(file_name(0x1F00), "carve_00001F00.bin");
+}
@@ -0,0 +1,147 @@
+use hex_it::checksum::{Adler32, Crc, CrcParams, Fletcher16, Fletcher32};
+
+/// What every CRC catalogue checks its entries with
+const CHECK: &[u8] = b"123456789";
+
+fn crc(params: CrcParams, bytes: &[u8]) -> u64 {
+    let mut crc = Crc::new(params);
+    crc.update(bytes);
+    crc.value()
+}
+
+fn params(width: u32, poly: u64, init: u64, reflect: bool, xorout: u64) -> CrcParams {
+    CrcParams {
+        width,
+        poly,
+        init,
+        reflect_in: reflect,
+        reflect_out: reflect,
+        xorout,
+    }
+}
+
+#[test]
+fn adler32_matches_zlib() {
+    let adler = |bytes: &[u8]| {
+        let mut adler = Adler32::new();
+        adler.update(bytes);
+        adler.value()
+    };
+
+    assert_eq!(adler(b""), 1);
+    assert_eq!(adler(b"Wikipedia"), 0x11E60398);
+    assert_eq!(adler(b"a"), 0x00620062);
+    assert_eq!(adler(b"abc"), 0x024D0127);
+    // Long enough for the sums to wrap many times over
+    assert_eq!(adler(&[0xFF; 100_000]), 0x149A302C);
+}
+
+#[test]
+fn fletcher_matches_the_published_vectors() {
+    let fletcher16 = |bytes: &[u8]| {
+        let mut sum = Fletcher16::new();
+        sum.update(bytes);
+        sum.value()
+    };
+    let fletcher32 = |bytes: &[u8]| {
+        let mut sum = Fletcher32::new();
+        sum.update(bytes);
+        sum.value()
+    };
+
+    assert_eq!(fletcher16(b"abcde"), 0xC8F0);
+    assert_eq!(fletcher16(b"abcdef"), 0x2057);
+    assert_eq!(fletcher16(b"abcdefgh"), 0x0627);
+
+    assert_eq!(fletcher32(b"abcde"), 0xF04FC729);
+    assert_eq!(fletcher32(b"abcdef"), 0x56502D2A);
+    assert_eq!(fletcher32(b"abcdefgh"), 0xEBE19591);
+}
+
+#[test]
+fn presets_match_their_check_values() {
+    let check = |name: &str| crc(CrcParams::preset(name).unwrap(), CHECK);
+
+    assert_eq!(check("crc32"), 0xCBF43926);
+    assert_eq!(check("CRC32C"), 0xE3069283);
+    assert_eq!(check("crc16-ccitt"), 0x29B1);
+    assert_eq!(check("crc16-arc"), 0xBB3D);
+    assert!(CrcParams::preset("crc33").is_none());
+}
+
+#[test]
+fn any_rocksoft_crc_can_be_described() {
+    // CRC-16/XMODEM and CRC-16/KERMIT, one polynomial both ways round
+    assert_eq!(crc(params(16, 0x1021, 0, false, 0), CHECK), 0x31C3);
+    assert_eq!(crc(params(16, 0x1021, 0, true, 0), CHECK), 0x2189);
+    // CRC-8/SMBUS
+    assert_eq!(crc(params(8, 0x07, 0, false, 0), CHECK), 0xF4);
+    // CRC-5/USB and CRC-3/GSM, narrower than a byte
+    assert_eq!(crc(params(5, 0x05, 0x1F, true, 0x1F), CHECK), 0x19);
+    assert_eq!(crc(params(3, 0x3, 0, false, 0x7), CHECK), 0x4);
+    // CRC-64/ECMA-182 and CRC-64/XZ, as wide as the register goes
+    assert_eq!(
+        crc(params(64, 0x42F0E1EBA9EA3693, 0, false, 0), CHECK),
+        0x6C40DF5F0B497347
+    );
+    assert_eq!(
+        crc(
+            params(64, 0x42F0E1EBA9EA3693, u64::MAX, true, u64::MAX),
+            CHECK
+        ),
+        0x995DC9BBDF1939FA
+    );
+}
+
+#[test]
+fn reflecting_only_the_output_is_allowed() {
+    // CRC-12/UMTS feeds bytes as they are but reverses the result
+    let umts = CrcParams {
+        reflect_out: true,
+        ..params(12, 0x80F, 0, false, 0)
+    };
+    assert_eq!(crc(umts, CHECK), 0xDAF);
+}
+
+#[test]
+fn parameters_have_to_fit_the_width() {
+    assert!(params(16, 0x1021, 0xFFFF, false, 0).check().is_ok());
+    assert!(params(0, 0x1, 0, false, 0).check().is_err());
+    assert!(params(65, 0x1, 0, false, 0).check().is_err());
+    assert!(params(8, 0x107, 0, false, 0).check().is_err());
+    assert!(params(8, 0x07, 0x100, false, 0).check().is_err());
+    assert_eq!(
+        params(16, 0x1021, 0xFFFF, false, 0).describe(),
+        "width 16 poly 1021 init FFFF xorout 0000 refin false refout false"
+    );
+}
+
+#[test]
+fn feeding_in_pieces_makes_no_difference() {
+    let bytes = (0..1001u32)
+        .map(|i| (i * 37 % 256) as u8)
+        .collect::<Vec<u8>>();
+    let crc32 = CrcParams::preset("crc32").unwrap();
+
+    // Every checksum of the bytes fed in pieces of some size
+    let sums = |piece: usize| {
+        let (mut adler, mut f16, mut f32, mut crc) = (
+            Adler32::new(),
+            Fletcher16::new(),
+            Fletcher32::new(),
+            Crc::new(crc32),
+        );
+        for chunk in bytes.chunks(piece) {
+            adler.update(chunk);
+            f16.update(chunk);
+            f32.update(chunk);
+            crc.update(chunk);
+        }
+        (adler.value(), f16.value(), f32.value(), crc.value())
+    };
+
+    let whole = sums(bytes.len());
+    for piece in [1, 2, 3, 7, 64, 1000] {
+        assert_eq!(sums(piece), whole, "pieces of {piece}");
+    }
+}
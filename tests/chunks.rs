@@ -0,0 +1,160 @@
+use hex_it::chunks::{walk, walk_layout, Chunk, Walk, IFF, RIFF};
+
+const PNG: &[u8] = include_bytes!("fixtures/tiny.png");
+const WAV: &[u8] = include_bytes!("fixtures/tiny.wav");
+
+/// The chunks as (type, offset, length, depth) to compare in one go
+fn summary(walk: &Walk) -> Vec<(&str, usize, usize, usize)> {
+    walk.chunks
+        .iter()
+        .map(|chunk| (chunk.kind.as_str(), chunk.offset, chunk.length, chunk.depth))
+        .collect()
+}
+
+#[test]
+fn walks_the_png_fixture() {
+    let found = walk(PNG).unwrap();
+
+    assert_eq!(found.format, "PNG");
+    assert_eq!(found.note, None);
+    assert_eq!(
+        summary(&found),
+        [
+            ("IHDR", 8, 0xD, 0),
+            ("tEXt", 0x21, 0xE, 0),
+            ("IDAT", 0x3B, 0xC, 0),
+            ("IEND", 0x53, 0, 0),
+        ]
+    );
+    assert!(found.chunks.iter().all(|chunk| chunk.crc == Some(true)));
+}
+
+#[test]
+fn a_changed_byte_fails_the_crc_of_its_chunk_only() {
+    let mut png = PNG.to_vec();
+    png[0x2D] ^= 0x20;
+
+    let found = walk(&png).unwrap();
+    let crcs: Vec<Option<bool>> = found.chunks.iter().map(|chunk| chunk.crc).collect();
+    assert_eq!(crcs, [Some(true), Some(false), Some(true), Some(true)]);
+}
+
+#[test]
+fn walks_into_riff_containers() {
+    let found = walk(WAV).unwrap();
+
+    assert_eq!(found.format, "RIFF");
+    assert_eq!(found.note, None);
+    assert_eq!(
+        summary(&found),
+        [
+            ("RIFF", 0, 0x44, 0),
+            ("fmt", 0xC, 0x10, 1),
+            ("LIST", 0x24, 0x14, 1),
+            // Odd, so the next chunk starts after a padding byte
+            ("INAM", 0x30, 7, 2),
+            ("data", 0x40, 3, 1),
+        ]
+    );
+    assert_eq!(found.chunks[0].form.as_deref(), Some("WAVE"));
+    assert_eq!(found.chunks[2].form.as_deref(), Some("INFO"));
+    assert_eq!(found.chunks[1].crc, None);
+}
+
+#[test]
+fn iff_is_riff_in_big_endian() {
+    let form = b"FORM\0\0\0\x0EAIFFCOMM\0\0\0\x02\x01\x02";
+
+    let found = walk(form).unwrap();
+    assert_eq!(found.format, "IFF");
+    assert_eq!(summary(&found), [("FORM", 0, 0xE, 0), ("COMM", 0xC, 2, 1)]);
+
+    // Read as RIFF, the length is far past the end and FORM holds nothing
+    let found = walk_layout(&RIFF, form);
+    assert_eq!(found.chunks.len(), 1);
+    assert_eq!(
+        found.note.as_deref(),
+        Some("the FORM chunk at 0 runs past the end, E000000 bytes long")
+    );
+    assert_eq!(walk_layout(&IFF, b"FORM").chunks, []);
+}
+
+#[test]
+fn other_files_have_no_chunks() {
+    assert_eq!(walk(b""), None);
+    assert_eq!(walk(b"\x7fELF\x02\x01\x01\0"), None);
+}
+
+#[test]
+fn bytes_after_the_last_chunk_are_noted() {
+    let mut png = PNG.to_vec();
+    png.extend_from_slice(b"trailing");
+
+    let found = walk(&png).unwrap();
+    assert_eq!(found.chunks.len(), 4);
+    assert_eq!(
+        found.note.as_deref(),
+        Some("8 more bytes after the last chunk, at 5F")
+    );
+}
+
+#[test]
+fn cut_short_files_stop_the_walk_with_a_note() {
+    let found = walk(&PNG[..0x50]).unwrap();
+    assert_eq!(found.chunks.len(), 3);
+    assert_eq!(found.chunks[2].crc, None);
+    assert_eq!(
+        found.note.as_deref(),
+        Some("the IDAT chunk at 3B runs past the end, C bytes long")
+    );
+
+    let found = walk(&PNG[..0x24]).unwrap();
+    assert_eq!(
+        found.note.as_deref(),
+        Some("the chunk header at 21 is cut short")
+    );
+
+    // What the cut short RIFF chunk holds is still walked
+    let found = walk(&WAV[..0x3A]).unwrap();
+    assert_eq!(summary(&found).len(), 4);
+    assert_eq!(
+        found.note.as_deref(),
+        Some("the INAM chunk at 30 runs past the end, 7 bytes long")
+    );
+
+    for length in 0..PNG.len() {
+        walk(&PNG[..length]);
+    }
+    for length in 0..WAV.len() {
+        walk(&WAV[..length]);
+    }
+}
+
+#[test]
+fn corrupt_chunk_headers_stop_the_walk() {
+    let mut png = PNG.to_vec();
+    png[0x25..0x29].copy_from_slice(&[0, 1, 2, 3]);
+
+    let found = walk(&png).unwrap();
+    assert_eq!(
+        found.chunks,
+        [Chunk {
+            kind: "IHDR".to_string(),
+            offset: 8,
+            length: 0xD,
+            depth: 0,
+            form: None,
+            crc: Some(true),
+        }]
+    );
+    assert_eq!(
+        found.note.as_deref(),
+        Some("the chunk at 21 has no type, 00 01 02 03 instead")
+    );
+
+    // A length near the end of the address space
+    let mut png = PNG.to_vec();
+    png[0x21..0x25].fill(0xFF);
+    let found = walk(&png).unwrap();
+    assert_eq!(found.chunks.len(), 2);
+}
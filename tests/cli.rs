@@ -0,0 +1,219 @@
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+/// A path in the temp directory that is removed again when dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, contents: &[u8]) -> TempFile {
+        let file = TempFile::path(name);
+        fs::write(&file.0, contents).unwrap();
+        file
+    }
+
+    /// A path that doesn't exist yet
+    fn path(name: &str) -> TempFile {
+        TempFile(std::env::temp_dir().join(format!("hex_it-cli-{}-{}", std::process::id(), name)))
+    }
+
+    fn contents(&self) -> Vec<u8> {
+        fs::read(&self.0).unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Runs hex_it on a file with more arguments after -p
+fn hex_it(file: &TempFile, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_hex_it"))
+        .arg("-p")
+        .arg(&file.0)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn writes_apply_in_order_in_place() {
+    let file = TempFile::new("in-place", &[0; 8]);
+
+    let output = hex_it(
+        &file,
+        &["--write", "0x2=DEADBEEF", "-W", "3=7f", "--write=7=01"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(file.contents(), [0, 0, 0xDE, 0x7F, 0xBE, 0xEF, 0, 1]);
+    assert_eq!(
+        stdout(&output),
+        "00000002  00 00 00 00 -> DE AD BE EF\n00000003  AD -> 7F\n00000007  00 -> 01\n"
+    );
+}
+
+#[test]
+fn out_leaves_the_file_alone() {
+    let file = TempFile::new("original", b"abcd");
+    let out = TempFile::path("patched");
+
+    let output = hex_it(
+        &file,
+        &["--write", "1=42 43", "--out", out.0.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(file.contents(), b"abcd");
+    assert_eq!(out.contents(), b"aBCd");
+}
+
+#[test]
+fn an_offset_out_of_range_writes_nothing() {
+    let file = TempFile::new("out-of-range", b"abcd");
+
+    // The first write fits, the second runs past the end
+    let output = hex_it(&file, &["--write", "0=58", "--write", "3=5959"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(file.contents(), b"abcd");
+    assert_eq!(stdout(&output), "");
+    assert!(
+        stderr(&output).contains("past the end"),
+        "{}",
+        stderr(&output)
+    );
+
+    let out = TempFile::path("not-written");
+    let output = hex_it(
+        &file,
+        &["--write", "10=00", "--out", out.0.to_str().unwrap()],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!out.0.exists());
+}
+
+#[test]
+fn invalid_writes_are_refused_before_anything_is_read() {
+    let file = TempFile::new("invalid", b"abcd");
+
+    for write in ["0=7G", "0=ABC", "0=", "7F", "nope=00"] {
+        let output = hex_it(&file, &["--write", "0=00", "--write", write]);
+        assert_eq!(output.status.code(), Some(1), "{}", write);
+        assert!(stderr(&output).starts_with("Invalid write"), "{}", write);
+    }
+    assert_eq!(file.contents(), b"abcd");
+}
+
+#[test]
+fn readonly_files_need_out() {
+    let file = TempFile::new("readonly", b"abcd");
+
+    let output = hex_it(&file, &["--readonly", "--write", "0=00"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(file.contents(), b"abcd");
+
+    let out = TempFile::path("readonly-out");
+    let output = hex_it(&file, &["-r", "-W", "0=00", "-o", out.0.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(out.contents(), b"\0bcd");
+}
+
+#[test]
+fn out_needs_a_write() {
+    let file = TempFile::new("no-write", b"abcd");
+    let out = TempFile::path("no-write-out");
+
+    let output = hex_it(&file, &["--out", out.0.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!out.0.exists());
+}
+
+#[test]
+fn apply_writes_a_patch_file_all_or_nothing() {
+    let file = TempFile::new("apply", &[0; 8]);
+    let patch = TempFile::new("apply.patch", b"# two edits\n1: AA BB\n6: CC # last\n");
+
+    let output = hex_it(&file, &["--apply", patch.0.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(file.contents(), [0, 0xAA, 0xBB, 0, 0, 0, 0xCC, 0]);
+
+    let broken = TempFile::new("broken.patch", b"0: 11\n8: 22\n");
+    let output = hex_it(&file, &["-a", broken.0.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(file.contents(), [0, 0xAA, 0xBB, 0, 0, 0, 0xCC, 0]);
+
+    let typo = TempFile::new("typo.patch", b"0: 11\n1: 2Z\n");
+    let output = hex_it(&file, &["-a", typo.0.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("line 2"), "{}", stderr(&output));
+    assert_eq!(file.contents()[0], 0);
+}
+
+#[test]
+fn verify_exits_by_whether_every_digest_matches() {
+    let file = TempFile::new("verify", b"xx123456789");
+
+    let region = ["--offset", "2", "--length", "0d9"];
+    let output = hex_it(
+        &file,
+        &[&region[..], &["--verify", "crc32=CBF43926"]].concat(),
+    );
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "OK crc32=CBF43926\n");
+
+    // One of the two doesn't match
+    let output = hex_it(
+        &file,
+        &[
+            &region[..],
+            &["-v", "crc32=cbf43926", "-v", "crc16-ccitt=0000"],
+        ]
+        .concat(),
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "OK crc32=CBF43926\nMISMATCH expected crc16-ccitt=0000 got crc16-ccitt=29B1\n"
+    );
+
+    // The whole file is something else
+    let output = hex_it(&file, &["--verify", "crc32=CBF43926"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).starts_with("MISMATCH"));
+}
+
+#[test]
+fn bad_verify_arguments_exit_with_2() {
+    let file = TempFile::new("verify-usage", b"abc");
+
+    for args in [
+        &["--verify", "md5=d41d8cd98f00b204e9800998ecf8427e"][..],
+        &["--verify", "sha256=abc"],
+        &[
+            "--verify",
+            "crc32=CBF43926",
+            "--offset",
+            "2",
+            "--length",
+            "2",
+        ],
+        &["--verify", "crc32=CBF43926", "--offset", "zz"],
+    ] {
+        let output = hex_it(&file, args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert_eq!(stdout(&output), "");
+    }
+
+    let output = hex_it(&file, &["--offset", "1", "--dump"]);
+    assert_eq!(output.status.code(), Some(1));
+}
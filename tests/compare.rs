@@ -0,0 +1,96 @@
+use hex_it::compare::{
+    compare_ranges, differs, next_difference, prev_difference, range_differs, RangeDifference,
+};
+
+#[test]
+fn bytes_past_the_shorter_buffer_differ() {
+    let (a, b) = (b"abcd".as_slice(), b"abXde".as_slice());
+
+    assert!(!differs(a, b, 0));
+    assert!(differs(a, b, 2));
+    assert!(differs(a, b, 4));
+    assert!(!differs(a, b, 5));
+    assert!(!range_differs(a, b, 0..2));
+    assert!(range_differs(a, b, 0..3));
+    assert!(!range_differs(a, a, 0..16));
+}
+
+#[test]
+fn differences_are_walked_a_run_at_a_time() {
+    let a = b"aaXXaaaYaa";
+    let b = b"aaaaaaaaaa";
+
+    assert_eq!(next_difference(a, b, 0), Some(2));
+    // From inside a run, the next one is where the following run starts
+    assert_eq!(next_difference(a, b, 2), Some(7));
+    assert_eq!(next_difference(a, b, 3), Some(7));
+    assert_eq!(next_difference(a, b, 7), None);
+
+    assert_eq!(prev_difference(a, b, 9), Some(7));
+    assert_eq!(prev_difference(a, b, 7), Some(2));
+    assert_eq!(prev_difference(a, b, 3), None);
+    assert_eq!(prev_difference(a, b, 2), None);
+}
+
+#[test]
+fn a_longer_buffer_differs_from_where_the_shorter_one_ends() {
+    assert_eq!(next_difference(b"abc", b"abcdef", 0), Some(3));
+    assert_eq!(prev_difference(b"abcdef", b"abc", 5), None);
+    assert_eq!(prev_difference(b"aXcdef", b"abc", 5), Some(1));
+    assert_eq!(next_difference(b"abc", b"abc", 0), None);
+}
+
+#[test]
+fn ranges_of_one_buffer_are_compared() {
+    let bytes = b"header\x01\x02\x03\x04header\x01\xFF\x03\x00";
+
+    let (differences, total) = compare_ranges(bytes, 0, 10, 10, 100).unwrap();
+    assert_eq!(total, 2);
+    assert_eq!(
+        differences,
+        [
+            RangeDifference {
+                offset: 7,
+                a: 0x02,
+                b: 0xFF
+            },
+            RangeDifference {
+                offset: 9,
+                a: 0x04,
+                b: 0x00
+            }
+        ]
+    );
+
+    assert_eq!(compare_ranges(bytes, 0, 10, 7, 100).unwrap(), (vec![], 0));
+    assert_eq!(compare_ranges(bytes, 3, 3, 0, 100).unwrap(), (vec![], 0));
+
+    // Only the first are kept, all of them counted
+    let (differences, total) = compare_ranges(bytes, 0, 10, 10, 1).unwrap();
+    assert_eq!((differences.len(), total), (1, 2));
+}
+
+#[test]
+fn overlapping_ranges_compare_the_bytes_as_they_are() {
+    let bytes = b"abababab";
+    assert_eq!(compare_ranges(bytes, 0, 2, 6, 100).unwrap().1, 0);
+
+    let (differences, total) = compare_ranges(bytes, 0, 1, 4, 100).unwrap();
+    assert_eq!(total, 4);
+    assert_eq!((differences[1].a, differences[1].b), (b'b', b'a'));
+}
+
+#[test]
+fn ranges_past_the_end_are_refused() {
+    let bytes = [0; 16];
+
+    assert!(compare_ranges(&bytes, 0, 8, 8, 100).is_ok());
+    assert_eq!(
+        compare_ranges(&bytes, 0, 9, 8, 100).unwrap_err(),
+        "range B 9+8 goes past the end at 10"
+    );
+    assert!(compare_ranges(&bytes, 12, 0, 8, 100)
+        .unwrap_err()
+        .starts_with("range A"));
+    assert!(compare_ranges(&bytes, usize::MAX, 0, 2, 100).is_err());
+}
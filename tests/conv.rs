@@ -0,0 +1,125 @@
+use hex_it::conv::{bit_width, describe, describe_position, encode, parse_value};
+
+#[test]
+fn prefixes_pick_the_base_and_bare_digits_are_decimal() {
+    assert_eq!(parse_value("255"), Ok(255));
+    assert_eq!(parse_value("0xFF"), Ok(255));
+    assert_eq!(parse_value("0XfF"), Ok(255));
+    assert_eq!(parse_value("0o377"), Ok(255));
+    assert_eq!(parse_value("0b1111_1111"), Ok(255));
+    assert_eq!(parse_value("-1"), Ok(-1));
+    assert_eq!(parse_value("-0x80"), Ok(-128));
+}
+
+#[test]
+fn errors_cite_the_offending_character() {
+    assert_eq!(
+        parse_value("12a"),
+        Err("'a' is not a base 10 digit in '12a'".to_string())
+    );
+    assert_eq!(
+        parse_value("0b102"),
+        Err("'2' is not a base 2 digit in '0b102'".to_string())
+    );
+    assert!(parse_value("0x").is_err());
+    assert!(parse_value("-").is_err());
+}
+
+#[test]
+fn values_have_to_fit_64_bits() {
+    assert_eq!(parse_value("18446744073709551615"), Ok(u64::MAX.into()));
+    assert!(parse_value("18446744073709551616").is_err());
+    assert_eq!(parse_value("-9223372036854775808"), Ok(i64::MIN.into()));
+    assert!(parse_value("-9223372036854775809").is_err());
+    assert!(parse_value("0x1_0000_0000_0000_0000_0000").is_err());
+}
+
+#[test]
+fn widths_count_the_sign_bit_of_negative_values() {
+    assert_eq!(bit_width(0), 1);
+    assert_eq!(bit_width(255), 8);
+    assert_eq!(bit_width(256), 9);
+    assert_eq!(bit_width(-1), 1);
+    assert_eq!(bit_width(-128), 8);
+    assert_eq!(bit_width(-129), 9);
+    assert_eq!(bit_width(u64::MAX.into()), 64);
+}
+
+#[test]
+fn encodings_are_shown_where_they_fit() {
+    assert_eq!(encode(0x1234, 1), None);
+    assert_eq!(encode(0x1234, 2), Some(vec![0x34, 0x12]));
+    assert_eq!(encode(0x1234, 4), Some(vec![0x34, 0x12, 0, 0]));
+    assert_eq!(encode(-1, 1), Some(vec![0xFF]));
+    assert_eq!(encode(-2, 4), Some(vec![0xFE, 0xFF, 0xFF, 0xFF]));
+    assert_eq!(encode(-129, 1), None);
+    assert_eq!(encode(u64::MAX.into(), 8), Some(vec![0xFF; 8]));
+    assert_eq!(
+        encode(i64::MIN.into(), 8),
+        Some(vec![0, 0, 0, 0, 0, 0, 0, 0x80])
+    );
+}
+
+#[test]
+fn descriptions_list_every_base_and_encoding() {
+    let lines = describe(-1);
+    assert_eq!(
+        lines[..5],
+        [
+            "hex  -0x1",
+            "dec  -1",
+            "oct  -0o1",
+            "bin  -0b1",
+            "1 bit with the sign, 1 byte"
+        ]
+    );
+    assert_eq!(lines.len(), 10);
+    assert_eq!(lines[6], "1      FF                       FF");
+    assert_eq!(
+        lines[9],
+        "8      FF FF FF FF FF FF FF FF  FF FF FF FF FF FF FF FF"
+    );
+
+    let lines = describe(u64::MAX.into());
+    assert_eq!(lines[0], "hex  0xFFFFFFFFFFFFFFFF");
+    assert_eq!(lines[4], "64 bits, 8 bytes");
+    // Only the 8 byte encoding fits
+    assert_eq!(lines.len(), 7);
+
+    let lines = describe(0x1234);
+    assert_eq!(lines[7], "4      34 12 00 00              00 00 12 34");
+}
+
+#[test]
+fn positions_show_in_every_base_with_their_byte() {
+    let mut bytes = vec![0u8; 0x400];
+    bytes[0x1C4] = 0x7F;
+    bytes[0x3FF] = b'A';
+
+    assert_eq!(
+        describe_position(0x1C4, &bytes, 16).unwrap(),
+        [
+            "offset  0x1C4  0d452  0o704",
+            "dump    line 0x1C (0d28), column 0x4 (0d4)",
+            "byte    0x7F  0d127  0b01111111  not printable",
+            "44.14% through, 0x23C (0d572) bytes from here to the end",
+        ]
+    );
+
+    let last = describe_position(0x3FF, &bytes, 16).unwrap();
+    assert_eq!(last[2], "byte    0x41  0d65  0b01000001  'A'");
+    assert_eq!(
+        last[3],
+        "99.90% through, 0x1 (0d1) bytes from here to the end"
+    );
+    assert_eq!(
+        describe_position(0, &bytes, 16).unwrap()[3],
+        "0.00% through, 0x400 (0d1024) bytes from here to the end"
+    );
+}
+
+#[test]
+fn positions_past_the_end_have_nothing_to_show() {
+    assert_eq!(describe_position(4, &[0; 4], 16), None);
+    assert_eq!(describe_position(0, &[], 16), None);
+}
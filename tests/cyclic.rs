@@ -0,0 +1,58 @@
+use hex_it::cyclic::{cyclic, cyclic_find, DeBruijn, DEFAULT_ORDER, FIND_LIMIT};
+
+#[test]
+fn the_pattern_is_the_one_pwntools_writes() {
+    assert_eq!(
+        cyclic(40, DEFAULT_ORDER),
+        b"aaaabaaacaaadaaaeaaafaaagaaahaaaiaaajaaa"
+    );
+    assert_eq!(cyclic(40, 8), b"aaaaaaaabaaaaaaacaaaaaaadaaaaaaaeaaaaaaa");
+
+    let pattern = cyclic(usize::MAX, DEFAULT_ORDER);
+    assert_eq!(pattern.len(), 26usize.pow(4));
+    assert_eq!(&pattern[1000..1012], b"zaakbaakcaak");
+    assert_eq!(&pattern[pattern.len() - 12..], b"zyyzzyzyzzzz");
+    assert_eq!(DeBruijn::new(2).count(), 26 * 26);
+}
+
+#[test]
+fn every_run_of_the_order_is_in_it_once() {
+    let pattern = cyclic(usize::MAX, 3);
+    let mut seen = vec![false; 26 * 26 * 26];
+    for window in pattern.windows(3) {
+        let index = window
+            .iter()
+            .fold(0, |index, byte| index * 26 + (byte - b'a') as usize);
+        assert!(!seen[index], "{:?} twice", std::str::from_utf8(window));
+        seen[index] = true;
+    }
+    // The pattern is cyclic, the runs that wrap around the end are the ones missing
+    assert_eq!(seen.iter().filter(|seen| !**seen).count(), 2);
+}
+
+#[test]
+fn bytes_are_found_where_pwntools_finds_them() {
+    assert_eq!(cyclic_find(b"aaaa", 4, FIND_LIMIT), Some(0));
+    assert_eq!(cyclic_find(b"gaaa", 4, FIND_LIMIT), Some(24));
+    assert_eq!(cyclic_find(b"xaab", 4, FIND_LIMIT), Some(192));
+    assert_eq!(cyclic_find(b"cnaa", 4, FIND_LIMIT), Some(251));
+    assert_eq!(cyclic_find(b"uaaf", 4, FIND_LIMIT), Some(580));
+    assert_eq!(cyclic_find(b"zzzz", 4, FIND_LIMIT), Some(456972));
+    assert_eq!(cyclic_find(b"uaafvaaf", 4, FIND_LIMIT), Some(580));
+
+    assert_eq!(cyclic_find(b"baaaaaaa", 8, FIND_LIMIT), Some(8));
+    assert_eq!(cyclic_find(b"aaaafaaa", 8, FIND_LIMIT), Some(36));
+
+    // Found by going through the pattern, a limit too short misses it
+    assert_eq!(cyclic_find(b"uaaf", 4, 583), None);
+    assert_eq!(cyclic_find(b"uaaf", 4, 584), Some(580));
+}
+
+#[test]
+fn bytes_from_outside_the_pattern_are_not_found() {
+    assert_eq!(cyclic_find(b"AAAA", 4, FIND_LIMIT), None);
+    assert_eq!(cyclic_find(&[0x61, 0x61, 0x00, 0x61], 4, FIND_LIMIT), None);
+    assert_eq!(cyclic_find(b"", 4, FIND_LIMIT), None);
+    // Every letter is there, just never in that order
+    assert_eq!(cyclic_find(b"aaaabaaad", 4, FIND_LIMIT), None);
+}
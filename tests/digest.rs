@@ -0,0 +1,86 @@
+use hex_it::digest::{Entropy, Sha256};
+
+fn sha256(bytes: &[u8]) -> String {
+    let mut hash = Sha256::new();
+    hash.update(bytes);
+    hash.finish_hex()
+}
+
+#[test]
+fn known_hashes() {
+    assert_eq!(
+        sha256(b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        sha256(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    // Two blocks once padded
+    assert_eq!(
+        sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+    assert_eq!(
+        sha256(&vec![b'a'; 1_000_000]),
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+    );
+}
+
+#[test]
+fn feeding_in_pieces_makes_no_difference() {
+    let bytes = (0..1000u32)
+        .map(|i| (i * 31 % 256) as u8)
+        .collect::<Vec<u8>>();
+    let whole = sha256(&bytes);
+
+    for piece in [1, 3, 55, 56, 63, 64, 65, 128, 999] {
+        let mut hash = Sha256::new();
+        for chunk in bytes.chunks(piece) {
+            hash.update(chunk);
+        }
+        assert_eq!(hash.finish_hex(), whole, "pieces of {}", piece);
+    }
+}
+
+#[test]
+fn padding_around_the_block_edge() {
+    // Lengths where the length field just fits or spills into another block
+    let expected = [
+        (
+            55,
+            "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318",
+        ),
+        (
+            56,
+            "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a",
+        ),
+        (
+            64,
+            "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb",
+        ),
+    ];
+
+    for (length, hash) in expected {
+        assert_eq!(sha256(&vec![b'a'; length]), hash, "{} bytes", length);
+    }
+}
+
+#[test]
+fn entropy_bounds() {
+    let mut entropy = Entropy::new();
+    assert_eq!(entropy.bits_per_byte(), 0.0);
+
+    entropy.update(&[0x41; 100]);
+    assert_eq!(entropy.bits_per_byte(), 0.0);
+
+    let mut entropy = Entropy::new();
+    entropy.update(b"abab");
+    assert!((entropy.bits_per_byte() - 1.0).abs() < 1e-12);
+
+    let mut entropy = Entropy::new();
+    for _ in 0..4 {
+        entropy.update(&(0..=255).collect::<Vec<u8>>());
+    }
+    assert!((entropy.bits_per_byte() - 8.0).abs() < 1e-12);
+}
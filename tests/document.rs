@@ -0,0 +1,652 @@
+use hex_it::document::{moved_offset, Change, Document, DocumentError};
+
+fn doc(bytes: &[u8]) -> Document {
+    Document::new(bytes.to_vec())
+}
+
+fn out_of_bounds(start: usize, end: usize, len: usize) -> DocumentError {
+    DocumentError::OutOfBounds { start, end, len }
+}
+
+#[test]
+fn new_document_is_clean() {
+    let document = doc(b"abc");
+
+    assert_eq!(document.len(), 3);
+    assert!(!document.is_empty());
+    assert!(!document.is_dirty());
+    assert!(!document.can_undo());
+    assert_eq!(document.bytes(), b"abc");
+}
+
+#[test]
+fn empty_document() {
+    let mut document = Document::default();
+
+    assert!(document.is_empty());
+    assert_eq!(document.read(0..0), Ok(&b""[..]));
+    assert_eq!(document.get(0), None);
+    assert_eq!(document.overwrite(0, b"x"), Err(out_of_bounds(0, 1, 0)));
+    assert_eq!(document.delete(0..1), Err(out_of_bounds(0, 1, 0)));
+    assert_eq!(document.insert(0, b"x"), Ok(()));
+    assert_eq!(document.bytes(), b"x");
+}
+
+#[test]
+fn read_checks_bounds() {
+    let document = doc(b"hello");
+
+    assert_eq!(document.read(0..5), Ok(&b"hello"[..]));
+    assert_eq!(document.read(1..3), Ok(&b"el"[..]));
+    assert_eq!(document.read(5..5), Ok(&b""[..]));
+    assert_eq!(document.read(4..6), Err(out_of_bounds(4, 6, 5)));
+    assert_eq!(document.read(6..6), Err(out_of_bounds(6, 6, 5)));
+    #[allow(clippy::reversed_empty_ranges)]
+    let backwards = document.read(3..2);
+    assert_eq!(backwards, Err(out_of_bounds(3, 2, 5)));
+}
+
+#[test]
+fn overwrite_at_start_and_end() {
+    let mut document = doc(b"hello");
+
+    document.overwrite(0, b"J").unwrap();
+    document.overwrite(4, b"y").unwrap();
+    assert_eq!(document.bytes(), b"Jelly");
+    assert_eq!(document.len(), 5);
+}
+
+#[test]
+fn overwrite_past_the_end_fails_without_changing_anything() {
+    let mut document = doc(b"hello");
+
+    assert_eq!(document.overwrite(4, b"yy"), Err(out_of_bounds(4, 6, 5)));
+    assert_eq!(document.overwrite(5, b"y"), Err(out_of_bounds(5, 6, 5)));
+    assert_eq!(
+        document.overwrite(usize::MAX, b"y"),
+        Err(out_of_bounds(usize::MAX, usize::MAX, 5))
+    );
+    assert_eq!(document.bytes(), b"hello");
+    assert!(!document.is_dirty());
+    assert!(!document.can_undo());
+}
+
+#[test]
+fn insert_at_start_middle_and_end() {
+    let mut document = doc(b"bd");
+
+    document.insert(0, b"a").unwrap();
+    document.insert(2, b"c").unwrap();
+    document.insert(4, b"e").unwrap();
+    assert_eq!(document.bytes(), b"abcde");
+    assert_eq!(document.insert(6, b"f"), Err(out_of_bounds(6, 6, 5)));
+}
+
+#[test]
+fn overwrite_extending_grows_past_the_end() {
+    let mut document = doc(b"DEMO");
+
+    document.overwrite_extending(0, b"FU").unwrap();
+    assert_eq!(document.bytes(), b"FUMO");
+
+    // Up to the end exactly, then past it and from the end itself
+    document.overwrite_extending(2, b"LL").unwrap();
+    assert_eq!(document.bytes(), b"FULL");
+    document.overwrite_extending(3, b"L!!").unwrap();
+    assert_eq!(document.bytes(), b"FULL!!");
+    document.overwrite_extending(6, b"\0").unwrap();
+    assert_eq!(document.bytes(), b"FULL!!\0");
+
+    assert_eq!(
+        document.overwrite_extending(8, b"x"),
+        Err(out_of_bounds(8, 8, 7))
+    );
+
+    // Each write is one undo step, the growing ones included
+    document.undo().unwrap();
+    document.undo().unwrap();
+    assert_eq!(document.bytes(), b"FULL");
+}
+
+#[test]
+fn delete_returns_what_was_removed() {
+    let mut document = doc(b"abcdef");
+
+    assert_eq!(document.delete(0..1), Ok(b"a".to_vec()));
+    assert_eq!(document.delete(3..5), Ok(b"ef".to_vec()));
+    assert_eq!(document.bytes(), b"bcd");
+    assert_eq!(document.delete(2..4), Err(out_of_bounds(2, 4, 3)));
+    assert_eq!(document.delete(0..3), Ok(b"bcd".to_vec()));
+    assert!(document.is_empty());
+}
+
+#[test]
+fn zero_length_edits_do_nothing() {
+    let mut document = doc(b"abc");
+    let changes = document.subscribe();
+
+    document.overwrite(3, b"").unwrap();
+    document.insert(0, b"").unwrap();
+    document.insert(3, b"").unwrap();
+    assert_eq!(document.delete(1..1), Ok(vec![]));
+
+    assert_eq!(document.bytes(), b"abc");
+    assert!(!document.is_dirty());
+    assert!(!document.can_undo());
+    assert_eq!(changes.try_iter().count(), 0);
+}
+
+#[test]
+fn zero_length_edits_still_check_bounds() {
+    let mut document = doc(b"abc");
+
+    assert_eq!(document.overwrite(4, b""), Err(out_of_bounds(4, 4, 3)));
+    assert_eq!(document.insert(4, b""), Err(out_of_bounds(4, 4, 3)));
+    assert_eq!(document.delete(4..4), Err(out_of_bounds(4, 4, 3)));
+}
+
+#[test]
+fn each_edit_is_an_undo_step() {
+    let mut document = doc(b"abc");
+
+    document.overwrite(0, b"x").unwrap();
+    document.insert(3, b"yz").unwrap();
+    document.delete(1..2).unwrap();
+    assert_eq!(document.bytes(), b"xcyz");
+
+    assert_eq!(document.undo(), Ok(1));
+    assert_eq!(document.bytes(), b"xbcyz");
+    assert_eq!(document.undo(), Ok(3));
+    assert_eq!(document.bytes(), b"xbc");
+    assert_eq!(document.undo(), Ok(0));
+    assert_eq!(document.bytes(), b"abc");
+    assert_eq!(document.undo(), Err(DocumentError::NothingToUndo));
+}
+
+#[test]
+fn overlapping_edits_undo_in_reverse() {
+    let mut document = doc(b"0123456789");
+
+    document.begin_undo_group();
+    document.overwrite(2, b"abcd").unwrap();
+    document.delete(3..7).unwrap();
+    document.insert(1, b"XY").unwrap();
+    document.overwrite(0, b"zzzz").unwrap();
+    document.end_undo_group();
+    assert_eq!(document.bytes(), b"zzzza789");
+
+    assert_eq!(document.undo(), Ok(2));
+    assert_eq!(document.bytes(), b"0123456789");
+    assert!(!document.is_dirty());
+}
+
+#[test]
+fn undo_groups() {
+    let mut document = doc(b"aaaa");
+
+    document.begin_undo_group();
+    document.overwrite(0, b"b").unwrap();
+    // Beginning again inside a group doesn't start a new one
+    document.begin_undo_group();
+    document.overwrite(1, b"b").unwrap();
+    document.end_undo_group();
+    document.overwrite(2, b"c").unwrap();
+
+    document.undo().unwrap();
+    assert_eq!(document.bytes(), b"bbaa");
+    document.undo().unwrap();
+    assert_eq!(document.bytes(), b"aaaa");
+    assert!(!document.can_undo());
+}
+
+#[test]
+fn empty_undo_group_is_not_a_step() {
+    let mut document = doc(b"a");
+
+    document.begin_undo_group();
+    document.end_undo_group();
+    assert!(!document.can_undo());
+    assert!(!document.is_dirty());
+
+    // Failed edits inside a group don't count either
+    document.begin_undo_group();
+    assert!(document.overwrite(5, b"x").is_err());
+    document.end_undo_group();
+    assert!(!document.can_undo());
+}
+
+#[test]
+fn undo_ends_an_open_group() {
+    let mut document = doc(b"ab");
+
+    document.begin_undo_group();
+    document.overwrite(0, b"x").unwrap();
+    document.undo().unwrap();
+    document.overwrite(1, b"y").unwrap();
+    document.overwrite(0, b"z").unwrap();
+
+    document.undo().unwrap();
+    assert_eq!(document.bytes(), b"ay");
+}
+
+#[test]
+fn dirty_follows_undo_and_mark_clean() {
+    let mut document = doc(b"ab");
+
+    document.overwrite(0, b"x").unwrap();
+    assert!(document.is_dirty());
+    document.mark_clean();
+    assert!(!document.is_dirty());
+
+    document.overwrite(1, b"y").unwrap();
+    assert!(document.is_dirty());
+    document.undo().unwrap();
+    assert!(!document.is_dirty());
+
+    // Undoing past the save makes it dirty again
+    document.undo().unwrap();
+    assert!(document.is_dirty());
+    assert_eq!(document.bytes(), b"ab");
+}
+
+#[test]
+fn edit_after_undoing_to_the_clean_point_is_dirty() {
+    let mut document = doc(b"ab");
+
+    document.overwrite(0, b"x").unwrap();
+    document.mark_clean();
+    document.undo().unwrap();
+    // Back at the same undo depth as the save, but with different contents
+    document.overwrite(1, b"y").unwrap();
+    assert!(document.is_dirty());
+}
+
+#[test]
+fn unsaved_documents_stay_dirty_until_marked_clean() {
+    let mut document = Document::unsaved(vec![0; 4]);
+    assert!(document.is_dirty());
+
+    document.overwrite(0, b"x").unwrap();
+    document.undo().unwrap();
+    assert!(document.is_dirty());
+    assert_eq!(document.bytes(), [0; 4]);
+
+    document.mark_clean();
+    assert!(!document.is_dirty());
+}
+
+#[test]
+fn mark_clean_ends_an_open_group() {
+    let mut document = doc(b"ab");
+
+    document.begin_undo_group();
+    document.overwrite(0, b"x").unwrap();
+    document.mark_clean();
+    document.overwrite(1, b"y").unwrap();
+    assert!(document.is_dirty());
+    document.undo().unwrap();
+    assert!(!document.is_dirty());
+}
+
+#[test]
+fn subscribers_get_every_change() {
+    let mut document = doc(b"abc");
+    let changes = document.subscribe();
+
+    document.overwrite(1, b"xy").unwrap();
+    document.insert(0, b"12").unwrap();
+    document.delete(4..5).unwrap();
+    document.undo().unwrap();
+
+    let changes = changes.try_iter().collect::<Vec<Change>>();
+    assert_eq!(
+        changes,
+        vec![
+            Change {
+                offset: 1,
+                removed: 2,
+                inserted: 2
+            },
+            Change {
+                offset: 0,
+                removed: 0,
+                inserted: 2
+            },
+            Change {
+                offset: 4,
+                removed: 1,
+                inserted: 0
+            },
+            Change {
+                offset: 4,
+                removed: 0,
+                inserted: 1
+            },
+        ]
+    );
+    assert!(!changes[0].shifts());
+    assert!(changes[1].shifts());
+}
+
+#[test]
+fn failed_edits_are_not_announced() {
+    let mut document = doc(b"abc");
+    let changes = document.subscribe();
+
+    assert!(document.overwrite(2, b"xy").is_err());
+    assert!(document.insert(9, b"x").is_err());
+    assert_eq!(changes.try_iter().count(), 0);
+}
+
+#[test]
+fn dropped_subscribers_are_forgotten() {
+    let mut document = doc(b"abc");
+    drop(document.subscribe());
+    let kept = document.subscribe();
+
+    document.overwrite(0, b"x").unwrap();
+    assert_eq!(kept.try_iter().count(), 1);
+}
+
+#[test]
+fn errors_describe_themselves() {
+    assert_eq!(
+        out_of_bounds(0x10, 0x10, 0x8).to_string(),
+        "offset 10 is past the end of the buffer (8 bytes)"
+    );
+    assert_eq!(
+        out_of_bounds(0x4, 0x10, 0x8).to_string(),
+        "range 4-10 is past the end of the buffer (8 bytes)"
+    );
+    assert_eq!(DocumentError::NothingToUndo.to_string(), "nothing to undo");
+}
+
+#[test]
+fn snapshots_keep_the_bytes_they_were_taken_with() {
+    let mut document = doc(b"hello");
+    let snapshot = document.snapshot();
+
+    document.overwrite(0, b"J").unwrap();
+    let edited = document.snapshot();
+    document.undo().unwrap();
+
+    assert_eq!(snapshot.as_slice(), b"hello");
+    assert_eq!(edited.as_slice(), b"Jello");
+    assert_eq!(document.bytes(), b"hello");
+}
+
+/// Modifications as offset, original and current bytes
+fn modifications(doc: &Document) -> Vec<(usize, Vec<u8>, Vec<u8>)> {
+    doc.modifications()
+        .into_iter()
+        .map(|m| (m.offset, m.original, m.current))
+        .collect()
+}
+
+#[test]
+fn modifications_list_what_changed_since_clean() {
+    let mut d = doc(b"abcdefgh");
+    assert!(modifications(&d).is_empty());
+
+    d.overwrite(1, b"XY").unwrap();
+    d.overwrite(6, b"Z").unwrap();
+    assert_eq!(
+        modifications(&d),
+        [
+            (1, b"bc".to_vec(), b"XY".to_vec()),
+            (6, b"g".to_vec(), b"Z".to_vec())
+        ]
+    );
+
+    // Typing next to a run makes it longer, typing a byte over itself changes nothing
+    d.overwrite(3, b"Q").unwrap();
+    d.overwrite(7, b"h").unwrap();
+    assert_eq!(
+        modifications(&d),
+        [
+            (1, b"bcd".to_vec(), b"XYQ".to_vec()),
+            (6, b"g".to_vec(), b"Z".to_vec())
+        ]
+    );
+
+    d.mark_clean();
+    assert!(modifications(&d).is_empty());
+}
+
+#[test]
+fn modifications_follow_inserts_and_deletes() {
+    let mut d = doc(b"abcdefgh");
+    d.overwrite(6, b"Z").unwrap();
+    d.insert(2, b"123").unwrap();
+    assert_eq!(
+        modifications(&d),
+        [
+            (2, Vec::new(), b"123".to_vec()),
+            (9, b"g".to_vec(), b"Z".to_vec())
+        ]
+    );
+
+    // Deleting across an inserted run and the bytes after it
+    d.delete(4..7).unwrap();
+    assert_eq!(
+        modifications(&d),
+        [
+            (2, b"cd".to_vec(), b"12".to_vec()),
+            (6, b"g".to_vec(), b"Z".to_vec())
+        ]
+    );
+    assert_eq!(d.bytes(), b"ab12efZh");
+
+    d.delete(0..8).unwrap();
+    assert_eq!(modifications(&d), [(0, b"abcdefgh".to_vec(), Vec::new())]);
+}
+
+#[test]
+fn modifications_stay_right_through_undo() {
+    let mut d = doc(b"abcdefgh");
+    d.overwrite(0, b"X").unwrap();
+    d.insert(4, b"++").unwrap();
+    d.undo().unwrap();
+    assert_eq!(modifications(&d), [(0, b"a".to_vec(), b"X".to_vec())]);
+    d.undo().unwrap();
+    assert!(modifications(&d).is_empty());
+
+    // Undoing past a save changes the bytes the save wrote
+    d.delete(2..4).unwrap();
+    d.mark_clean();
+    d.undo().unwrap();
+    assert_eq!(modifications(&d), [(2, Vec::new(), b"cd".to_vec())]);
+}
+
+#[test]
+fn replace_changes_the_length_in_one_undo_step() {
+    let mut d = doc(b"abcdef");
+    assert_eq!(d.replace(1..3, b"XYZW"), Ok(b"bc".to_vec()));
+    assert_eq!(d.bytes(), b"aXYZWdef");
+    assert_eq!(d.replace(0..5, b""), Ok(b"aXYZW".to_vec()));
+    assert_eq!(d.bytes(), b"def");
+
+    assert_eq!(d.undo(), Ok(0));
+    assert_eq!(d.bytes(), b"aXYZWdef");
+    assert_eq!(d.undo(), Ok(1));
+    assert_eq!(d.bytes(), b"abcdef");
+    assert!(!d.can_undo());
+
+    assert_eq!(d.replace(4..7, b"x"), Err(out_of_bounds(4, 7, 6)));
+}
+
+#[test]
+fn swap_exchanges_ranges_in_one_undo_step() {
+    // Touching ranges, and ranges that span dump lines
+    let mut d = doc(b"abcdef");
+    d.swap(0, 3, 3).unwrap();
+    assert_eq!(d.bytes(), b"defabc");
+    d.undo().unwrap();
+    assert_eq!(d.bytes(), b"abcdef");
+    assert!(!d.can_undo());
+
+    let mut bytes = (0..48u8).collect::<Vec<u8>>();
+    let mut d = doc(&bytes);
+    d.swap(30, 12, 8).unwrap();
+    bytes[12..20].copy_from_slice(&(30..38).collect::<Vec<u8>>());
+    bytes[30..38].copy_from_slice(&(12..20).collect::<Vec<u8>>());
+    assert_eq!(d.bytes(), bytes);
+    d.undo().unwrap();
+    assert_eq!(d.bytes(), (0..48u8).collect::<Vec<u8>>());
+}
+
+#[test]
+fn swap_refuses_overlapping_and_out_of_bounds_ranges() {
+    let mut d = doc(b"abcdef");
+    assert_eq!(
+        d.swap(0, 2, 3),
+        Err(DocumentError::Overlapping {
+            first: 0,
+            second: 2,
+            len: 3
+        })
+    );
+    assert_eq!(
+        d.swap(3, 2, 3).unwrap_err().to_string(),
+        "ranges 3-6 and 2-5 overlap".to_string()
+    );
+    assert_eq!(d.swap(0, 4, 3), Err(out_of_bounds(4, 7, 6)));
+    assert_eq!(d.bytes(), b"abcdef");
+    assert!(!d.can_undo());
+}
+
+#[test]
+fn move_counts_the_destination_before_the_move() {
+    // Forward: the bytes end up in front of the one that was at the destination
+    let mut d = doc(b"abcdefgh");
+    assert_eq!(d.move_range(1, 2, 6), Ok(4));
+    assert_eq!(d.bytes(), b"adefbcgh");
+    d.undo().unwrap();
+    assert_eq!(d.bytes(), b"abcdefgh");
+    assert!(!d.can_undo());
+
+    // Back, and to either end of the document
+    assert_eq!(d.move_range(5, 3, 1), Ok(1));
+    assert_eq!(d.bytes(), b"afghbcde");
+    d.undo().unwrap();
+    assert_eq!(d.move_range(0, 2, 8), Ok(6));
+    assert_eq!(d.bytes(), b"cdefghab");
+    d.undo().unwrap();
+    assert_eq!(d.move_range(6, 2, 0), Ok(0));
+    assert_eq!(d.bytes(), b"ghabcdef");
+}
+
+#[test]
+fn moving_a_range_onto_itself_does_nothing() {
+    let mut d = doc(b"abcdefgh");
+    assert_eq!(d.move_range(2, 3, 2), Ok(2));
+    assert_eq!(d.move_range(2, 3, 5), Ok(2));
+    assert!(!d.can_undo());
+
+    assert_eq!(
+        d.move_range(2, 3, 3),
+        Err(DocumentError::MoveIntoItself {
+            start: 2,
+            end: 5,
+            destination: 3
+        })
+    );
+    assert_eq!(d.move_range(6, 3, 0), Err(out_of_bounds(6, 9, 8)));
+    assert_eq!(d.move_range(0, 3, 9), Err(out_of_bounds(9, 9, 8)));
+    assert_eq!(d.bytes(), b"abcdefgh");
+}
+
+#[test]
+fn moved_offsets_follow_their_bytes() {
+    let original = b"abcdefgh";
+    for (source, len, destination) in [(1, 2, 6), (5, 3, 1), (0, 2, 8), (6, 2, 0), (2, 3, 5)] {
+        let mut d = doc(original);
+        d.move_range(source, len, destination).unwrap();
+
+        for (offset, byte) in original.iter().enumerate() {
+            let moved = moved_offset(offset, source, len, destination);
+            assert_eq!(
+                d.get(moved),
+                Some(*byte),
+                "{offset} in {source} {len} {destination}"
+            );
+        }
+    }
+}
+
+#[test]
+fn trailing_runs_count_filler_at_the_end() {
+    // No trailing filler, all filler, and filler interrupted by a data byte near the end
+    assert_eq!(doc(b"\x01\x02\x03").trailing_run(0), 0);
+    assert_eq!(doc(&[0xFF; 5]).trailing_run(0xFF), 5);
+    assert_eq!(doc(b"\x01\x00\x00\x07\x00\x00").trailing_run(0), 2);
+    assert_eq!(doc(b"").trailing_run(0), 0);
+}
+
+#[test]
+fn reversing_twice_changes_nothing() {
+    let bytes = (0..100u8).map(|i| i.wrapping_mul(37)).collect::<Vec<u8>>();
+
+    for range in [0..100, 3..50, 15..17, 0..1, 40..40] {
+        let mut d = doc(&bytes);
+        d.reverse(range.clone()).unwrap();
+
+        let mut expected = bytes.clone();
+        expected[range.clone()].reverse();
+        assert_eq!(d.bytes(), expected);
+
+        d.reverse(range.clone()).unwrap();
+        assert_eq!(d.bytes(), bytes);
+    }
+
+    let mut d = doc(b"abcd");
+    d.reverse(1..4).unwrap();
+    assert_eq!(d.bytes(), b"adcb");
+    d.undo().unwrap();
+    assert_eq!(d.bytes(), b"abcd");
+    // Nothing to reverse, nothing to undo
+    d.reverse(2..3).unwrap();
+    assert!(!d.can_undo());
+    assert_eq!(d.reverse(2..5), Err(out_of_bounds(2, 5, 4)));
+}
+
+#[test]
+fn bytes_are_modified_until_they_are_changed_back() {
+    let mut document = Document::new(b"abcdefgh".to_vec());
+    document.overwrite(2, b"XY").unwrap();
+
+    let modified = (0..8)
+        .filter(|&offset| document.is_modified(offset))
+        .collect::<Vec<usize>>();
+    assert_eq!(modified, vec![2, 3]);
+
+    // Typed back to what it was, only Y is left
+    document.overwrite(2, b"c").unwrap();
+    assert!(!document.is_modified(2));
+    assert!(document.is_modified(3));
+
+    // A run that grew has nothing to line up with, so all of it counts
+    document.insert(6, b"!").unwrap();
+    assert!(document.is_modified(6));
+    assert!(!document.is_modified(7));
+
+    document.mark_clean();
+    assert!(!(0..document.len()).any(|offset| document.is_modified(offset)));
+}
+
+#[test]
+fn the_version_goes_up_with_every_change() {
+    let mut document = doc(b"abc");
+    assert_eq!(document.version(), 0);
+
+    document.overwrite(0, b"x").unwrap();
+    document.insert(3, b"yz").unwrap();
+    assert_eq!(document.version(), 2);
+
+    // Nothing happened, so nothing to tell
+    document.insert(1, b"").unwrap();
+    assert_eq!(document.version(), 2);
+
+    document.undo().unwrap();
+    assert_eq!(document.version(), 3);
+}
@@ -0,0 +1,638 @@
+use hex_it::{
+    dump::{
+        html_escape, json_string, markdown_code, previous_row, printable_text, render_line,
+        render_line_within, render_skipped, repeats, squeezed_rows, utf16_cells, write_dump,
+        write_html, write_json, write_markdown, LineCache, Row,
+    },
+    render,
+    screen::visible_width,
+    strings::{utf16_bytes, Encoding},
+    template::Endian,
+    theme::{Marks, Theme, RESET},
+    Document, DumpOptions,
+};
+use tui_tools::Colors;
+
+fn plain() -> DumpOptions {
+    DumpOptions {
+        colors: false,
+        ..DumpOptions::default()
+    }
+}
+
+#[test]
+fn renders_offset_hex_and_text() {
+    let lines = render(b"Hello world\nand more text", &plain());
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(
+        lines[0],
+        "00000000  48 65 6C 6C 6F 20 77 6F 72 6C 64 0A 61 6E 64 20   Hello world.and "
+    );
+    assert_eq!(
+        lines[1],
+        format!("00000010  {:<48}  more text", "6D 6F 72 65 20 74 65 78 74 ")
+    );
+}
+
+#[test]
+fn renders_nothing_for_no_bytes() {
+    assert!(render(&[], &plain()).is_empty());
+}
+
+#[test]
+fn groups_get_an_extra_space() {
+    let options = DumpOptions {
+        cols: 8,
+        group: 4,
+        colors: false,
+        theme: None,
+        squeeze: false,
+        gutter: true,
+        text: Encoding::Ascii,
+        odd_units: false,
+    };
+
+    assert_eq!(options.hex_width(), 25);
+    assert_eq!(
+        render_line(0x20, b"abcdefgh", &options),
+        "00000020  61 62 63 64  65 66 67 68   abcdefgh"
+    );
+}
+
+#[test]
+fn invalid_utf8_does_not_panic() {
+    let lines = render(&[0xFF, 0xFE, b'a'], &plain());
+
+    assert!(lines[0].ends_with("\u{FFFD}\u{FFFD}a"));
+}
+
+#[test]
+fn columns_map_to_bytes() {
+    let options = DumpOptions::default();
+
+    // Gutter
+    assert_eq!(options.column_to_byte(0), None);
+    assert_eq!(options.column_to_byte(9), None);
+    // Both digits of a pair and the space after it
+    assert_eq!(options.column_to_byte(10), Some(0));
+    assert_eq!(options.column_to_byte(11), Some(0));
+    assert_eq!(options.column_to_byte(12), Some(0));
+    assert_eq!(options.column_to_byte(13), Some(1));
+    assert_eq!(options.column_to_byte(57), Some(15));
+    // Gap before the text column
+    assert_eq!(options.column_to_byte(58), None);
+    assert_eq!(options.column_to_byte(59), None);
+    // Text column
+    assert_eq!(options.column_to_byte(60), Some(0));
+    assert_eq!(options.column_to_byte(75), Some(15));
+    assert_eq!(options.column_to_byte(76), None);
+}
+
+#[test]
+fn grouped_columns_map_to_bytes() {
+    let options = DumpOptions {
+        cols: 8,
+        group: 4,
+        colors: false,
+        theme: None,
+        squeeze: false,
+        gutter: true,
+        text: Encoding::Ascii,
+        odd_units: false,
+    };
+
+    assert_eq!(options.hex_column(4), 23);
+    // The gap after a group belongs to the byte before it
+    assert_eq!(options.column_to_byte(22), Some(3));
+    assert_eq!(options.column_to_byte(23), Some(4));
+    assert_eq!(options.column_to_byte(options.text_start()), Some(0));
+}
+
+#[test]
+fn json_matches_the_fixture() {
+    let mut out = Vec::new();
+    write_json(
+        &mut out,
+        "weird.bin",
+        include_bytes!("fixtures/weird.bin"),
+        &plain(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        include_str!("fixtures/weird.json")
+    );
+}
+
+fn html(offset: usize, bytes: &[u8], options: &DumpOptions) -> String {
+    let mut out = Vec::new();
+    // A modified run and a match, the match wins where they overlap
+    write_html(&mut out, "<weird>.bin", offset, bytes, options, |offset| {
+        Marks {
+            found: (0x20..0x23).contains(&offset),
+            modified: (0x05..0x08).contains(&offset) || offset == 0x21,
+        }
+    })
+    .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn html_matches_the_fixture() {
+    let html = html(0, include_bytes!("fixtures/weird.bin"), &plain());
+
+    assert_eq!(html, include_str!("fixtures/weird.html"));
+}
+
+#[test]
+fn html_lines_are_anchors_and_line_up() {
+    let bytes = include_bytes!("fixtures/weird.bin");
+    let html = html(0x100, &bytes[..0x14], &plain());
+
+    assert!(html.contains("<span id=\"0x100\"><a class=\"o\" href=\"#0x100\">00000100</a>"));
+    assert!(html.contains("<span id=\"0x110\">"));
+    // Without the tags every line is the plain dump line
+    let lines = html
+        .lines()
+        .filter(|line| line.starts_with("<span id="))
+        .map(|line| {
+            let mut text = String::new();
+            let mut in_tag = false;
+            for c in line.chars() {
+                match c {
+                    '<' => in_tag = true,
+                    '>' => in_tag = false,
+                    c if !in_tag => text.push(c),
+                    _ => {}
+                }
+            }
+            text.replace("&quot;", "\"")
+        })
+        .collect::<Vec<String>>();
+    let plain_lines = render(&bytes[..0x14], &plain())
+        .iter()
+        .map(|line| {
+            line.replacen("000000", "000001", 1)
+                .replace(['\0', '\x01', '\x7f'], ".")
+        })
+        .collect::<Vec<String>>();
+    assert_eq!(lines, plain_lines);
+}
+
+#[test]
+fn html_escapes_what_it_has_to() {
+    assert_eq!(
+        html_escape("<a href=\"x\">&'</a>"),
+        "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+    );
+
+    let html = html(0, b"<b>&", &plain());
+    assert!(html.contains("<span class=\"t\">&lt;b&gt;&amp;</span>"));
+    assert!(html.contains("<title>&lt;weird&gt;.bin</title>"));
+}
+
+fn markdown(offset: usize, bytes: &[u8], options: &DumpOptions, fenced: bool) -> String {
+    let mut out = Vec::new();
+    write_markdown(&mut out, offset, bytes, options, fenced).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn markdown_matches_the_fixtures() {
+    let bytes = include_bytes!("fixtures/weird.bin");
+    let table = markdown(0, bytes, &plain(), false);
+    let fenced = markdown(0, bytes, &plain(), true);
+
+    assert_eq!(table, include_str!("fixtures/weird.md"));
+    assert_eq!(fenced, include_str!("fixtures/weird.fenced.md"));
+}
+
+#[test]
+fn markdown_groups_and_keeps_offsets() {
+    let grouped = DumpOptions {
+        cols: 8,
+        group: 4,
+        ..plain()
+    };
+
+    assert_eq!(
+        markdown(0x1F0, b"ab|`c`d\x00\xFFxyz", &grouped, false),
+        "| Offset | Hex | ASCII |\n\
+         |--------|-----|-------|\n\
+         | `000001F0` | `61 62 7C 60  63 60 64 00` | ``ab\\|`c`d.`` |\n\
+         | `000001F8` | `FF 78 79 7A` | `.xyz` |\n"
+    );
+    assert_eq!(
+        markdown(0x1F0, b"ab|`c`d\x00\xFFxyz", &grouped, true),
+        "```\n\
+         000001F0  61 62 7C 60  63 60 64 00  ab|`c`d.\n\
+         000001F8  FF 78 79 7A               .xyz\n\
+         ```\n"
+    );
+}
+
+#[test]
+fn markdown_code_spans_survive_backticks_and_pipes() {
+    assert_eq!(markdown_code("abc"), "`abc`");
+    assert_eq!(markdown_code("a|b"), "`a\\|b`");
+    assert_eq!(markdown_code("a`b"), "``a`b``");
+    assert_eq!(markdown_code("``x"), "``` ``x ```");
+    assert_eq!(markdown_code(" x "), "`  x  `");
+    assert_eq!(markdown_code("   "), "`   `");
+}
+
+#[test]
+fn json_strings_escape_what_they_have_to() {
+    assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+    assert_eq!(printable_text(b"ok\x00\xff~"), "ok..~");
+}
+
+fn dump(offset: usize, bytes: &[u8], options: &DumpOptions) -> String {
+    let mut out = Vec::new();
+    write_dump(&mut out, offset, bytes, options).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn plain_and_colored_dumps_line_up() {
+    let bytes = include_bytes!("fixtures/weird.bin");
+    let plain_dump = dump(0, bytes, &plain());
+    let colored_dump = dump(0, bytes, &DumpOptions::default());
+
+    assert_eq!(plain_dump, include_str!("fixtures/weird.dump"));
+    assert_ne!(colored_dump, plain_dump);
+    // The colors are the only difference, the columns are the same
+    assert_eq!(colored_dump.replace(&".".bold_black(), "."), plain_dump);
+}
+
+#[test]
+fn dumps_of_a_range_keep_their_offsets() {
+    let bytes = include_bytes!("fixtures/weird.bin");
+    let lines = dump(0x10, &bytes[0x10..0x18], &plain());
+
+    assert_eq!(
+        lines,
+        format!("{}\n", render_line(0x10, &bytes[0x10..0x18], &plain()))
+    );
+    assert!(lines.starts_with("00000010  01 7F FF C3 A9 20 65 6E "));
+}
+
+#[test]
+fn line_cache_renders_again_only_what_an_edit_touched() {
+    let mut document = Document::new((0..=255).collect());
+    let changes = document.subscribe();
+    let mut cache = LineCache::default();
+
+    for index in 0..16 {
+        cache.line(index, document.bytes(), &plain());
+    }
+    assert_eq!(cache.cached(), 16);
+
+    // Two bytes straddling lines 1 and 2
+    document.overwrite(0x1F, &[0xAA, 0xBB]).unwrap();
+    for change in changes.try_iter() {
+        cache.invalidate(&change, 16);
+    }
+    assert_eq!(cache.cached(), 14);
+    assert_eq!(
+        cache.line(2, document.bytes(), &plain()),
+        render_line(0x20, &document.bytes()[0x20..0x30], &plain())
+    );
+
+    // Inserting moves everything after it, so those lines go
+    document.insert(0x85, &[0]).unwrap();
+    for change in changes.try_iter() {
+        cache.invalidate(&change, 16);
+    }
+    assert_eq!(cache.cached(), 7);
+    assert!(cache
+        .line(0x10, document.bytes(), &plain())
+        .starts_with("00000100  FF"));
+}
+
+#[test]
+fn offsets_past_four_gigabytes_keep_the_layout() {
+    let line = render_line(0x1_0000_0000, b"ab", &plain());
+    assert_eq!(line, format!("100000000  {:<48}  ab", "61 62 "));
+}
+
+fn themed() -> DumpOptions {
+    DumpOptions {
+        theme: Some(Theme::default()),
+        ..DumpOptions::default()
+    }
+}
+
+#[test]
+fn a_theme_colors_every_part() {
+    let theme = Theme::default();
+    let line = render_line(0x10, b"A\0\n\xFF", &themed());
+
+    assert!(line.starts_with(&format!("{}00000010{}  ", theme.offset, RESET)));
+    assert!(line.contains(&format!("{}41{} ", theme.printable, RESET)));
+    assert!(line.contains(&format!("{}00{} ", theme.null, RESET)));
+    assert!(line.contains(&format!("{}0A{} ", theme.whitespace, RESET)));
+    assert!(line.contains(&format!("{}FF{} ", theme.high, RESET)));
+    assert!(line.contains(&format!("{}A", theme.text)));
+    assert!(line.contains(&format!("{}.{}{}", theme.newline, RESET, theme.text)));
+    assert!(line.ends_with(RESET));
+}
+
+/// The line without its escape sequences
+fn strip_colors(line: &str) -> String {
+    let mut stripped = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find("\x1b[") {
+        stripped.push_str(&rest[..start]);
+        let end = rest[start..].find('m').unwrap();
+        rest = &rest[start + end + 1..];
+    }
+
+    stripped + rest
+}
+
+#[test]
+fn themed_lines_line_up_with_plain_ones() {
+    for length in 0..=16 {
+        let bytes = (0..length).map(|i| i as u8 * 17).collect::<Vec<u8>>();
+        let themed_line = render_line(0x30, &bytes, &themed());
+
+        assert_eq!(
+            strip_colors(&themed_line),
+            render_line(0x30, &bytes, &plain()),
+            "{} bytes",
+            length
+        );
+        assert_eq!(
+            visible_width(&themed_line),
+            themed().text_start() + length,
+            "{} bytes",
+            length
+        );
+    }
+}
+
+#[test]
+fn a_theme_needs_colors_on() {
+    let options = DumpOptions {
+        colors: false,
+        ..themed()
+    };
+
+    assert_eq!(options.theme(), None);
+    assert_eq!(
+        render_line(0, b"Hi\n", &options),
+        render_line(0, b"Hi\n", &plain())
+    );
+}
+
+#[test]
+fn without_a_theme_colors_only_touch_the_newline() {
+    // The same line as before themes existed
+    assert_eq!(
+        render_line(0, b"a\nb", &DumpOptions::default()),
+        format!(
+            "00000000  61 0A 62 {}a{}b",
+            " ".repeat(41),
+            ".".bold_black()
+        )
+    );
+}
+
+/// Lines of 4 bytes: a, then five lines of zeros, b, zeros again and a short last line
+fn runs() -> Vec<u8> {
+    let mut bytes = b"aaaa".to_vec();
+    bytes.extend([0; 20]);
+    bytes.extend(b"bbbb");
+    bytes.extend([0; 8]);
+    bytes.extend([0; 2]);
+    bytes
+}
+
+#[test]
+fn squeezing_leaves_out_lines_that_repeat_the_one_above() {
+    let bytes = runs();
+
+    assert_eq!(
+        squeezed_rows(&bytes, 4, 0, 100, None),
+        vec![
+            Row::Line(0),
+            Row::Line(1),
+            Row::Skipped { first: 2, count: 4 },
+            Row::Line(6),
+            Row::Line(7),
+            Row::Skipped { first: 8, count: 1 },
+            Row::Line(9),
+        ]
+    );
+    // The last line is always there, even when it repeats, so the end of the bytes shows
+    let zeros = [0; 16];
+    assert_eq!(
+        squeezed_rows(&zeros, 4, 0, 100, None),
+        vec![
+            Row::Line(0),
+            Row::Skipped { first: 1, count: 2 },
+            Row::Line(3)
+        ]
+    );
+}
+
+#[test]
+fn squeezing_keeps_the_cursor_line() {
+    let bytes = runs();
+
+    // The cursor on line 4 splits the run around it
+    assert_eq!(
+        squeezed_rows(&bytes, 4, 0, 5, Some(4)),
+        vec![
+            Row::Line(0),
+            Row::Line(1),
+            Row::Skipped { first: 2, count: 2 },
+            Row::Line(4),
+            Row::Skipped { first: 5, count: 1 },
+        ]
+    );
+    assert!(!repeats(&bytes, 4, 4, Some(4)));
+    assert!(repeats(&bytes, 4, 5, Some(4)));
+}
+
+#[test]
+fn squeezed_rows_start_anywhere_and_stop_at_the_count() {
+    let bytes = runs();
+
+    // A top line inside a run starts with what is left of it
+    assert_eq!(
+        squeezed_rows(&bytes, 4, 4, 2, None),
+        vec![Row::Skipped { first: 4, count: 2 }, Row::Line(6)]
+    );
+    assert_eq!(squeezed_rows(&bytes, 4, 10, 5, None), Vec::new());
+    assert!(Row::Skipped { first: 2, count: 4 }.lines().contains(&5));
+    assert_eq!(Row::Skipped { first: 2, count: 4 }.line(), 2);
+}
+
+#[test]
+fn the_row_above_starts_at_the_beginning_of_a_run() {
+    let bytes = runs();
+
+    assert_eq!(previous_row(&bytes, 4, 6, None), 2);
+    assert_eq!(previous_row(&bytes, 4, 2, None), 1);
+    assert_eq!(previous_row(&bytes, 4, 1, None), 0);
+    assert_eq!(previous_row(&bytes, 4, 0, None), 0);
+    // With the cursor on line 4 the rows above line 6 are 5, 4 and the run of 2 and 3
+    assert_eq!(previous_row(&bytes, 4, 6, Some(4)), 5);
+    assert_eq!(previous_row(&bytes, 4, 5, Some(4)), 4);
+    assert_eq!(previous_row(&bytes, 4, 4, Some(4)), 2);
+}
+
+#[test]
+fn editing_a_byte_breaks_a_run() {
+    let mut bytes = runs();
+    bytes[13] = 1;
+
+    assert_eq!(
+        squeezed_rows(&bytes, 4, 0, 5, None),
+        vec![
+            Row::Line(0),
+            Row::Line(1),
+            Row::Skipped { first: 2, count: 1 },
+            Row::Line(3),
+            Row::Line(4),
+        ]
+    );
+}
+
+#[test]
+fn squeezed_dumps_have_a_star_row_for_each_run() {
+    let options = DumpOptions {
+        cols: 4,
+        squeeze: true,
+        ..plain()
+    };
+    let mut out = Vec::new();
+    write_dump(&mut out, 0, &runs(), &options).unwrap();
+
+    let out = String::from_utf8(out).unwrap();
+    let lines = out.lines().collect::<Vec<&str>>();
+
+    assert_eq!(lines.len(), 7);
+    assert!(lines[1].starts_with("00000004  00 00 00 00"));
+    assert_eq!(lines[2], "*         4 identical lines (10 bytes)");
+    assert!(lines[3].starts_with("00000018  62 62 62 62"));
+    assert_eq!(lines[5], "*         1 identical line (4 bytes)");
+    assert!(lines[6].starts_with("00000024  00 00"));
+}
+
+#[test]
+fn lines_without_the_gutter_start_with_the_hex() {
+    let options = DumpOptions {
+        cols: 4,
+        gutter: false,
+        ..plain()
+    };
+
+    assert_eq!(options.gutter_width(), 0);
+    assert_eq!(render_line(0x40, b"abcd", &options), "61 62 63 64   abcd");
+    assert_eq!(options.text_start(), 14);
+    assert_eq!(options.hex_column(1), 3);
+    // Clicks map to bytes from the left edge on
+    assert_eq!(options.column_to_byte(0), Some(0));
+    assert_eq!(options.column_to_byte(4), Some(1));
+    assert_eq!(options.column_to_byte(15), Some(1));
+    assert_eq!(
+        render_skipped(3, 4, &options),
+        "* 3 identical lines (C bytes)"
+    );
+}
+
+/// The cells of a line as a string, `·` for the bytes after a character's first
+fn cells(buffer: &[u8], odd: bool, endian: Endian, line: std::ops::Range<usize>) -> String {
+    utf16_cells(buffer, (0, odd), endian, line)
+        .into_iter()
+        .map(|cell| cell.unwrap_or('·'))
+        .collect()
+}
+
+#[test]
+fn utf16_text_shows_in_the_cell_of_its_first_byte() {
+    let little = utf16_bytes("Héllo", Endian::Little);
+    assert_eq!(cells(&little, false, Endian::Little, 0..10), "H·é·l·l·o·");
+    let big = utf16_bytes("Héllo", Endian::Big);
+    assert_eq!(cells(&big, false, Endian::Big, 0..10), "H·é·l·l·o·");
+
+    // Read the wrong way round, or a byte off, the units aren't text
+    let hello = utf16_bytes("Hello", Endian::Little);
+    assert_eq!(cells(&hello, false, Endian::Big, 0..4), ".·.·");
+    assert_eq!(cells(&hello, true, Endian::Little, 0..4), "..·.");
+}
+
+#[test]
+fn odd_units_cross_line_boundaries() {
+    let mut bytes = vec![0xFF];
+    bytes.extend(utf16_bytes("abcdefghi", Endian::Little));
+
+    // The unit at 0xF ends on the next line, which starts with its continuation
+    assert_eq!(
+        cells(&bytes, true, Endian::Little, 0..16),
+        ".a·b·c·d·e·f·g·h"
+    );
+    assert_eq!(cells(&bytes, true, Endian::Little, 16..19), "·i·");
+
+    // Where the buffer starts in the whole buffer decides the alignment too
+    let cells = utf16_cells(&bytes[1..], (1, true), Endian::Little, 0..4);
+    assert_eq!(cells, [Some('a'), None, Some('b'), None]);
+}
+
+#[test]
+fn surrogate_pairs_span_lines() {
+    let bytes = utf16_bytes("abcdefg\u{10400}x", Endian::Little);
+
+    assert_eq!(
+        cells(&bytes, false, Endian::Little, 0..16),
+        "a·b·c·d·e·f·g·\u{10400}·"
+    );
+    assert_eq!(cells(&bytes, false, Endian::Little, 16..20), "··x·");
+}
+
+#[test]
+fn characters_that_arent_one_column_wide_are_dots() {
+    // A wide CJK character, a lone surrogate, a control and a combining accent
+    let mut bytes = utf16_bytes("中", Endian::Little);
+    bytes.extend([0x00, 0xDC]);
+    bytes.extend(utf16_bytes("\n\u{301}A", Endian::Little));
+    bytes.push(0x41);
+
+    assert_eq!(
+        cells(&bytes, false, Endian::Little, 0..bytes.len()),
+        ".·.·.·.·A·."
+    );
+}
+
+#[test]
+fn utf16_lines_line_up_with_ascii_ones() {
+    let bytes = utf16_bytes("UTF-16 text over two lines", Endian::Little);
+    let utf16 = DumpOptions {
+        text: Encoding::Utf16(Endian::Little),
+        ..plain()
+    };
+
+    let line = render_line_within(0, &bytes, 16..32, &utf16);
+    assert_eq!(
+        line,
+        "00000010  65 00 78 00 74 00 20 00 6F 00 76 00 65 00 72 00   e·x·t· ·o·v·e·r·"
+    );
+    assert_eq!(
+        visible_width(&line),
+        visible_width(&render_line(0x10, &bytes[16..32], &plain()))
+    );
+    let themed = DumpOptions {
+        text: utf16.text,
+        ..themed()
+    };
+    assert_eq!(
+        strip_colors(&render_line_within(0, &bytes, 16..32, &themed)),
+        line
+    );
+}
@@ -0,0 +1,137 @@
+use hex_it::{
+    document::Document,
+    editlog::{csv_field, format_seconds, EditLog, CSV_HEADER},
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn at(seconds: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+#[test]
+fn times_show_as_the_time_of_day_or_iso() {
+    assert_eq!(format_seconds(0, 0, true), "1970-01-01T00:00:00+00:00");
+    assert_eq!(format_seconds(1709991127, 3600, false), "14:32:07");
+    assert_eq!(
+        format_seconds(1709991127, 3600, true),
+        "2024-03-09T14:32:07+01:00"
+    );
+
+    // A leap day, and an offset behind UTC that takes the date back a day
+    assert_eq!(
+        format_seconds(1709164800, 0, true),
+        "2024-02-29T00:00:00+00:00"
+    );
+    assert_eq!(
+        format_seconds(1709164800, -16200, true),
+        "2024-02-28T19:30:00-04:30"
+    );
+    assert_eq!(format_seconds(-1, 0, true), "1969-12-31T23:59:59+00:00");
+}
+
+#[test]
+fn every_edit_is_logged_with_its_command() {
+    let mut document = Document::new(vec![0; 0x400]);
+    document.start_journal();
+    let mut log = EditLog::default();
+
+    document.overwrite(0x1C4, b"\x7f").unwrap();
+    log.record(&document.take_journal(), "put 1C4", at(100));
+    document.overwrite(0x200, &[0xAA; 0x100]).unwrap();
+    log.record(&document.take_journal(), "randfill 200 100", at(200));
+    document.insert(0, b"hi").unwrap();
+    log.record(&document.take_journal(), "settext 0 \"hi\"", at(300));
+
+    let entries = log.entries();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].range(), "1C4");
+    assert_eq!(entries[0].change(), "00 → 7F");
+    assert_eq!(entries[0].time, at(100));
+    assert_eq!(entries[1].range(), "200+100");
+    assert_eq!(
+        entries[1].change(),
+        "00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 … → \
+         AA AA AA AA AA AA AA AA AA AA AA AA AA AA AA AA …"
+    );
+    assert_eq!(entries[2].change(), "→ 68 69");
+    assert_eq!(entries[2].command, "settext 0 \"hi\"");
+}
+
+#[test]
+fn typing_over_bytes_is_one_entry() {
+    let mut document = Document::new(vec![0x11; 8]);
+    document.start_journal();
+    let mut log = EditLog::default();
+
+    // A nibble at a time, each key its own batch of records
+    document.begin_undo_group();
+    for (offset, byte) in [(2, 0xD1), (2, 0xDE), (3, 0xA1), (3, 0xAD)] {
+        document.overwrite(offset, &[byte]).unwrap();
+        log.record(&document.take_journal(), "typed hex", at(offset as u64));
+    }
+    document.end_undo_group();
+
+    assert_eq!(log.entries().len(), 1);
+    assert_eq!(log.entries()[0].range(), "2+2");
+    assert_eq!(log.entries()[0].change(), "11 11 → DE AD");
+    assert_eq!(log.entries()[0].time, at(2));
+}
+
+#[test]
+fn undone_edits_stay_in_the_log() {
+    let mut document = Document::new(vec![0; 16]);
+    document.start_journal();
+    let mut log = EditLog::default();
+
+    document.overwrite(0, b"\x01").unwrap();
+    document.swap(4, 8, 2).unwrap();
+    document.overwrite(0, b"\x02").unwrap();
+    log.record(&document.take_journal(), "edits", at(10));
+
+    document.undo().unwrap();
+    document.undo().unwrap();
+    log.record(&document.take_journal(), "undo", at(20));
+
+    let undone = log
+        .entries()
+        .iter()
+        .map(|entry| entry.undone)
+        .collect::<Vec<_>>();
+    assert_eq!(undone, [None, Some(at(20)), Some(at(20)), Some(at(20))]);
+
+    // Undoing what was there before the log started marks nothing
+    document.undo().unwrap();
+    log.record(&document.take_journal(), "undo", at(30));
+    assert_eq!(log.entries()[0].undone, Some(at(30)));
+    let mut before = Document::new(vec![0; 4]);
+    before.overwrite(0, b"\x01").unwrap();
+    before.start_journal();
+    before.undo().unwrap();
+    let mut empty = EditLog::default();
+    empty.record(&before.take_journal(), "undo", at(40));
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn exported_rows_escape_the_command() {
+    assert_eq!(csv_field("put 1C4"), "put 1C4");
+    assert_eq!(csv_field("settext 0 \"a,b\""), "\"settext 0 \"\"a,b\"\"\"");
+    assert_eq!(csv_field("! tr\na"), "\"! tr\na\"");
+
+    let mut document = Document::new(vec![0; 4]);
+    document.start_journal();
+    let mut log = EditLog::default();
+    document.overwrite(1, b"\x7f").unwrap();
+    log.record(&document.take_journal(), "settext 1 \"a,b\"", at(0));
+    document.undo().unwrap();
+    log.record(&document.take_journal(), "undo", at(60));
+
+    let row = log.entries()[0].csv(true);
+    let (_, rest) = row.split_once(',').unwrap();
+    assert_eq!(
+        rest.rsplit_once(',').unwrap().0,
+        "\"settext 1 \"\"a,b\"\"\",1,1,1,00,7F"
+    );
+    assert!(row.ends_with(":00\n"), "{row}");
+    assert_eq!(CSV_HEADER.split(',').count(), row.split(',').count() - 1);
+}
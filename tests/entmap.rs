@@ -0,0 +1,57 @@
+use hex_it::{
+    entmap::{blocks_per_row, default_block, level, render, EntropyMap, GLYPHS},
+    screen::visible_width,
+};
+
+#[test]
+fn blocks_span_chunks() {
+    let bytes = (0..=255u8).cycle().take(1000).collect::<Vec<u8>>();
+
+    let mut whole = EntropyMap::new(256, 80, false);
+    whole.update(&bytes);
+
+    let mut chunked = EntropyMap::new(256, 80, false);
+    for chunk in bytes.chunks(100) {
+        chunked.update(chunk);
+    }
+
+    let (whole, chunked) = (whole.finish(), chunked.finish());
+    assert_eq!(whole, chunked);
+    // 3 full blocks and a short one
+    assert_eq!(whole.len(), 4);
+    assert_eq!(whole[0], 8.0);
+}
+
+#[test]
+fn levels_go_from_zeros_to_random() {
+    assert_eq!(level(0.0), 0);
+    assert_eq!(level(2.0), 1);
+    assert_eq!(level(7.0), 3);
+    assert_eq!(level(8.0), GLYPHS.len() - 1);
+}
+
+#[test]
+fn rows_keep_round_offsets() {
+    assert_eq!(blocks_per_row(80), 64);
+    assert_eq!(blocks_per_row(20), 10);
+
+    // Fits 1 MiB in 20 rows of 64
+    assert_eq!(default_block(1 << 20, 80, 20), 1024);
+    assert_eq!(default_block(10, 80, 20), 256);
+}
+
+#[test]
+fn map_has_header_rows_and_legend() {
+    let mut map = EntropyMap::new(16, 30, false);
+    map.update(&[0; 16 * 20]);
+    let lines = map.finish_lines();
+
+    assert!(lines[0].starts_with("20 blocks of 10 bytes"));
+    assert_eq!(lines[1], format!("00000000  {}", "·".repeat(16)));
+    assert_eq!(lines[2], format!("00000100  {}", "·".repeat(4)));
+    assert!(lines[3].ends_with("bits per byte"));
+
+    // Colors don't take up room
+    let colored = render(&[8.0; 16], 16, 30, true);
+    assert_eq!(visible_width(&colored[1]), 26);
+}
@@ -0,0 +1,55 @@
+use hex_it::escape::{escape, unescape, ESCAPES};
+
+#[test]
+fn every_escape() {
+    assert_eq!(
+        unescape(r#"\x41\x00\xff\n\r\t\0\\\"\'"#).unwrap(),
+        b"A\0\xFF\n\r\t\0\\\"'"
+    );
+}
+
+#[test]
+fn text_without_escapes_is_its_utf8() {
+    assert_eq!(unescape("plain text é").unwrap(), "plain text é".as_bytes());
+    assert_eq!(unescape("").unwrap(), b"");
+}
+
+#[test]
+fn escape_says_how_much_it_took() {
+    assert_eq!(escape("x41 rest"), Ok((0x41, 3)));
+    assert_eq!(escape("n rest"), Ok((b'\n', 1)));
+    assert_eq!(escape("\\"), Ok((b'\\', 1)));
+}
+
+#[test]
+fn invalid_escapes_say_where() {
+    assert_eq!(
+        unescape(r"ab\q").unwrap_err(),
+        "invalid escape '\\q' at column 3"
+    );
+    assert_eq!(
+        unescape(r"\x4").unwrap_err(),
+        "invalid escape '\\x4' at column 1"
+    );
+    assert_eq!(
+        unescape(r"ok\xg1").unwrap_err(),
+        "invalid escape '\\xg1' at column 3"
+    );
+    assert_eq!(
+        unescape("end\\").unwrap_err(),
+        "nothing after the backslash at column 4"
+    );
+}
+
+#[test]
+fn every_listed_escape_is_understood() {
+    for (escape, _) in ESCAPES {
+        let example = escape.replace("HH", "7F");
+        assert_eq!(
+            unescape(&example).map(|bytes| bytes.len()),
+            Ok(1),
+            "{}",
+            escape
+        );
+    }
+}
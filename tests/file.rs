@@ -0,0 +1,310 @@
+use hex_it::file::{
+    complete_path, load, merge_ranges, save, save_atomic, save_in_place, save_new, FileKind,
+    FileLock, Fingerprint, LoadOptions, HASH_LIMIT,
+};
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// A path in the temp directory that is removed again when dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, contents: &[u8]) -> TempFile {
+        let path = std::env::temp_dir().join(format!("hex_it-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        TempFile(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn sized(size: u64) -> LoadOptions {
+    LoadOptions {
+        size: Some(size),
+        ..LoadOptions::default()
+    }
+}
+
+#[test]
+fn loads_regular_files_in_full() {
+    let file = TempFile::new("regular", b"\x00\xffnot utf-8 \xc3");
+
+    let (bytes, kind) = load(&file.0, &LoadOptions::default()).unwrap();
+    assert_eq!(bytes, b"\x00\xffnot utf-8 \xc3");
+    assert_eq!(kind, FileKind::Regular);
+
+    // The size only applies to devices
+    assert_eq!(load(&file.0, &sized(2)).unwrap().0.len(), 13);
+}
+
+#[test]
+fn missing_files_are_an_error() {
+    assert!(load(
+        &std::env::temp_dir().join("hex_it-does-not-exist"),
+        &LoadOptions::default()
+    )
+    .is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn devices_need_a_size_they_cannot_report() {
+    let path = PathBuf::from("/dev/zero");
+
+    let (bytes, kind) = load(&path, &sized(0x20)).unwrap();
+    assert_eq!(bytes, vec![0; 0x20]);
+    assert_eq!(kind, FileKind::Device);
+
+    let error = load(&path, &LoadOptions::default()).unwrap_err();
+    assert!(error.to_string().contains("--size"));
+}
+
+/// Makes a FIFO and writes contents into it from another thread, once something opens it
+#[cfg(unix)]
+fn fifo(name: &str, contents: &'static [u8]) -> TempFile {
+    let file =
+        TempFile(std::env::temp_dir().join(format!("hex_it-{}-{}", std::process::id(), name)));
+    let status = std::process::Command::new("mkfifo")
+        .arg(&file.0)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let path = file.0.clone();
+    // The reader may stop early, so a failed write is fine
+    std::thread::spawn(move || fs::write(path, contents));
+
+    file
+}
+
+#[cfg(unix)]
+#[test]
+fn reads_fifos_until_they_end() {
+    let file = fifo("fifo", b"through a pipe");
+
+    let (bytes, kind) = load(&file.0, &LoadOptions::default()).unwrap();
+    assert_eq!(bytes, b"through a pipe");
+    assert_eq!(kind, FileKind::Stream);
+}
+
+#[cfg(unix)]
+#[test]
+fn streams_past_the_limit_are_an_error() {
+    let options = LoadOptions {
+        stream_limit: 4,
+        ..LoadOptions::default()
+    };
+
+    let file = fifo("at-limit", b"four");
+    assert_eq!(load(&file.0, &options).unwrap().0, b"four");
+
+    let file = fifo("past-limit", b"five!");
+    let error = load(&file.0, &options).unwrap_err();
+    assert!(error.to_string().contains("stream-limit"));
+}
+
+/// Like `hex_it -p <(echo ...)`, the shell hands over the read end of a pipe as /dev/fd/N
+#[cfg(target_os = "linux")]
+#[test]
+fn reads_the_output_of_a_process() {
+    use std::os::fd::AsRawFd;
+
+    let mut child = std::process::Command::new("printf")
+        .arg("from a process")
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = child.stdout.take().unwrap();
+
+    let path = PathBuf::from(format!("/dev/fd/{}", stdout.as_raw_fd()));
+    let (bytes, kind) = load(&path, &LoadOptions::default()).unwrap();
+    assert_eq!(bytes, b"from a process");
+    assert_eq!(kind, FileKind::Stream);
+
+    child.wait().unwrap();
+}
+
+#[test]
+fn save_replaces_the_whole_file() {
+    let file = TempFile::new("save", b"longer contents");
+
+    save(&file.0, b"short").unwrap();
+    assert_eq!(fs::read(&file.0).unwrap(), b"short");
+}
+
+#[test]
+fn save_in_place_only_writes_the_ranges() {
+    let file = TempFile::new("in-place", b"0123456789");
+
+    // The buffer differs everywhere, only the ranges should reach the file
+    save_in_place(&file.0, b"abcdefghij", &[1..3, 8..20, 5..5]).unwrap();
+    assert_eq!(fs::read(&file.0).unwrap(), b"0bc34567ij");
+}
+
+#[test]
+fn save_in_place_does_not_truncate() {
+    let file = TempFile::new("no-truncate", b"0123456789");
+
+    save_in_place(&file.0, b"ab", &[0..1, 1..2]).unwrap();
+    assert_eq!(fs::read(&file.0).unwrap(), b"ab23456789");
+}
+
+#[test]
+fn ranges_merge_when_they_touch_or_overlap() {
+    assert_eq!(
+        merge_ranges(&[8..10, 0..2, 2..4, 3..5, 6..6, 7..8]),
+        vec![0..5, 7..10]
+    );
+    assert!(merge_ranges(&[]).is_empty());
+}
+
+/// Moves a file's modification time, as if it had been written again
+fn touch(path: &PathBuf, seconds_ago: u64) {
+    let file = File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() - Duration::from_secs(seconds_ago))
+        .unwrap();
+}
+
+#[test]
+fn touching_a_small_file_keeps_its_fingerprint() {
+    let file = TempFile::new("touched", b"same contents");
+    let before = Fingerprint::of(&file.0).unwrap();
+    assert_eq!(before.len, 13);
+    assert!(before.hash.is_some());
+
+    touch(&file.0, 60);
+    let after = Fingerprint::of(&file.0).unwrap();
+    assert_ne!(before.modified, after.modified);
+    assert!(before.same_contents(&after));
+}
+
+#[test]
+fn rewriting_a_file_changes_its_fingerprint() {
+    let file = TempFile::new("rewritten", b"some contents");
+    let before = Fingerprint::of(&file.0).unwrap();
+
+    // Same length, so only the hash tells them apart
+    fs::write(&file.0, b"some CONTENTS").unwrap();
+    assert!(!before.same_contents(&Fingerprint::of(&file.0).unwrap()));
+
+    fs::write(&file.0, b"longer contents").unwrap();
+    assert!(!before.same_contents(&Fingerprint::of(&file.0).unwrap()));
+}
+
+#[test]
+fn large_files_are_compared_by_mtime() {
+    let file = TempFile::new("large", &vec![0; HASH_LIMIT as usize + 1]);
+    let before = Fingerprint::of(&file.0).unwrap();
+    assert_eq!(before.hash, None);
+    assert!(before.same_contents(&Fingerprint::of(&file.0).unwrap()));
+
+    touch(&file.0, 60);
+    assert!(!before.same_contents(&Fingerprint::of(&file.0).unwrap()));
+}
+
+#[cfg(unix)]
+#[test]
+fn a_held_lock_keeps_others_out_until_dropped() {
+    let file = TempFile::new("locked", b"contents");
+
+    // Another thread holds the lock, like a second editor would
+    let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+    let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+    let path = file.0.clone();
+    let holder = std::thread::spawn(move || {
+        let lock = FileLock::try_lock(&path).unwrap();
+        locked_tx.send(lock.is_some()).unwrap();
+        let _ = release_rx.recv();
+        drop(lock);
+    });
+
+    assert!(locked_rx.recv().unwrap());
+    assert!(FileLock::try_lock(&file.0).unwrap().is_none());
+
+    release_tx.send(()).unwrap();
+    holder.join().unwrap();
+    assert!(FileLock::try_lock(&file.0).unwrap().is_some());
+}
+
+#[test]
+fn locking_a_missing_file_is_an_error() {
+    assert!(FileLock::try_lock(&std::env::temp_dir().join("hex_it-does-not-exist")).is_err());
+}
+
+#[test]
+fn saving_checks_the_file_is_still_as_loaded() {
+    let file = TempFile::new("conflict", b"as loaded");
+    let (bytes, _) = load(&file.0, &LoadOptions::default()).unwrap();
+    let loaded = Fingerprint::of(&file.0).unwrap();
+    assert_eq!(bytes, b"as loaded");
+
+    // Touching it doesn't count, the contents are the same
+    touch(&file.0, 60);
+    assert!(loaded.still_matches(&file.0));
+
+    fs::write(&file.0, b"rewritten").unwrap();
+    assert!(!loaded.still_matches(&file.0));
+
+    fs::remove_file(&file.0).unwrap();
+    assert!(!loaded.still_matches(&file.0));
+}
+
+#[test]
+fn save_new_refuses_existing_files_unless_told_to_overwrite() {
+    let existing = TempFile::new("save-new-existing", b"keep");
+    let error = save_new(&existing.0, b"new", false).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+    assert_eq!(fs::read(&existing.0).unwrap(), b"keep");
+
+    save_new(&existing.0, b"new", true).unwrap();
+    assert_eq!(fs::read(&existing.0).unwrap(), b"new");
+
+    let created = TempFile::new("save-new-created", b"");
+    fs::remove_file(&created.0).unwrap();
+    save_new(&created.0, b"\x00payload", false).unwrap();
+    assert_eq!(fs::read(&created.0).unwrap(), b"\x00payload");
+}
+
+#[test]
+fn atomic_saves_replace_the_file_and_leave_nothing_behind() {
+    let file = TempFile::new("atomic", b"old contents");
+    save_atomic(&file.0, b"new").unwrap();
+    assert_eq!(fs::read(&file.0).unwrap(), b"new");
+
+    let mut temp = file.0.clone().into_os_string();
+    temp.push(".tmp");
+    assert!(!PathBuf::from(temp).exists());
+
+    // A directory that isn't there fails before anything is written
+    let missing = file.0.with_file_name("hex_it-no-such-dir").join("file");
+    assert!(save_atomic(&missing, b"new").is_err());
+}
+
+#[test]
+fn paths_complete_among_the_entries_of_their_directory() {
+    let directory = std::env::temp_dir().join(format!("hex_it-{}-complete", std::process::id()));
+    fs::create_dir_all(directory.join("firmware")).unwrap();
+    for name in ["fw.bin", "fw.map", ".fw.swp", "other.bin"] {
+        fs::write(directory.join(name), b"").unwrap();
+    }
+    let typed = |name: &str| format!("{}/{}", directory.display(), name);
+
+    assert_eq!(
+        complete_path(&typed("f")),
+        [typed("firmware/"), typed("fw.bin"), typed("fw.map")]
+    );
+    assert_eq!(complete_path(&typed("o")), [typed("other.bin")]);
+    // Hidden entries only when a dot is typed
+    assert_eq!(complete_path(&typed(".f")), [typed(".fw.swp")]);
+    assert!(complete_path(&typed("x")).is_empty());
+    assert!(complete_path(&typed("missing/f")).is_empty());
+
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,63 @@
+use hex_it::filter::{run, shell};
+use std::ffi::OsStr;
+
+const LIMIT: usize = 1024 * 1024;
+
+#[test]
+fn output_of_the_command_fed_the_input() {
+    assert_eq!(
+        run("/bin/sh", "tr a-z A-Z", b"hex_it\0", LIMIT).unwrap(),
+        b"HEX_IT\0"
+    );
+    assert_eq!(
+        run("/bin/sh", "wc -c | tr -d ' '", &[0xFF; 300], LIMIT).unwrap(),
+        b"300\n"
+    );
+    assert_eq!(run("/bin/sh", "true", b"gone", LIMIT).unwrap(), b"");
+}
+
+#[test]
+fn the_shell_does_quoting_and_pipes() {
+    let output = run("/bin/sh", "printf '%s|' 'a b' \"c\" | tr '|' :", b"", LIMIT).unwrap();
+    assert_eq!(output, b"a b:c:");
+}
+
+#[test]
+fn input_the_command_doesnt_read_is_fine() {
+    let input = vec![b'x'; 4 * 1024 * 1024];
+    assert_eq!(run("/bin/sh", "head -c 3", &input, LIMIT).unwrap(), b"xxx");
+}
+
+#[test]
+fn a_failing_command_reports_its_errors() {
+    assert_eq!(
+        run("/bin/sh", "echo 'no such thing' >&2; exit 3", b"", LIMIT),
+        Err("exited with 3: no such thing".to_string())
+    );
+    assert_eq!(
+        run("/bin/sh", "exit 1", b"", LIMIT),
+        Err("exited with 1".to_string())
+    );
+    assert!(run("/no/such/shell", "true", b"", LIMIT)
+        .unwrap_err()
+        .starts_with("can't run /no/such/shell"));
+}
+
+#[test]
+fn runaway_output_is_stopped() {
+    assert_eq!(
+        run("/bin/sh", "yes | cat", b"", LIMIT),
+        Err("the output is over 100000 bytes, stopped it".to_string())
+    );
+    assert_eq!(
+        run("/bin/sh", "head -c 16 /dev/zero", b"", 16).unwrap(),
+        [0; 16]
+    );
+}
+
+#[test]
+fn shell_from_the_environment() {
+    assert_eq!(shell(Some(OsStr::new("/bin/bash"))), "/bin/bash");
+    assert_eq!(shell(Some(OsStr::new(""))), "/bin/sh");
+    assert_eq!(shell(None), "/bin/sh");
+}
@@ -0,0 +1,131 @@
+use hex_it::{
+    gzip::{compress, decompress, deflate, inflate, is_gzip},
+    random::SplitMix64,
+};
+
+const LIMIT: usize = 64 * 1024 * 1024;
+
+fn lines() -> Vec<u8> {
+    (0..200)
+        .map(|n| {
+            format!(
+                "line {n}: the quick brown fox jumps over the lazy dog {}\n",
+                n * n % 97
+            )
+        })
+        .collect::<String>()
+        .into_bytes()
+}
+
+fn random(len: usize) -> Vec<u8> {
+    let mut random = SplitMix64::new(0x5EED);
+    let mut bytes = vec![0; len];
+    random.fill(&mut bytes);
+    bytes
+}
+
+#[test]
+fn decompresses_gzip_files() {
+    let hello = include_bytes!("fixtures/hello.txt.gz");
+    assert!(is_gzip(hello));
+    assert_eq!(decompress(hello, LIMIT).unwrap(), b"hello\n");
+
+    // Made by gzip -9, so it has a dynamic Huffman block
+    let compressed = include_bytes!("fixtures/lines.txt.gz");
+    assert_eq!(decompress(compressed, LIMIT).unwrap(), lines());
+
+    assert!(!is_gzip(b"hello"));
+}
+
+#[test]
+fn members_that_follow_each_other_are_joined() {
+    let mut both = include_bytes!("fixtures/hello.txt.gz").to_vec();
+    both.extend_from_slice(&compress(b"world\n"));
+    assert_eq!(decompress(&both, LIMIT).unwrap(), b"hello\nworld\n");
+
+    // Padding after the last member isn't
+    both.extend_from_slice(&[0; 16]);
+    assert_eq!(decompress(&both, LIMIT).unwrap(), b"hello\nworld\n");
+}
+
+#[test]
+fn what_is_compressed_comes_back() {
+    let mut mixed = lines();
+    mixed.extend(random(5000));
+    mixed.extend(vec![0; 100_000]);
+    mixed.extend(lines());
+
+    for bytes in [
+        Vec::new(),
+        b"a".to_vec(),
+        b"abcabcabcabcabcabcabc".to_vec(),
+        lines(),
+        random(100_000),
+        vec![0; 300_000],
+        mixed,
+    ] {
+        let compressed = compress(&bytes);
+        assert!(is_gzip(&compressed));
+        assert_eq!(decompress(&compressed, LIMIT).unwrap(), bytes);
+
+        let mut inflated = Vec::new();
+        let deflated = deflate(&bytes);
+        assert_eq!(inflate(&deflated, &mut inflated, LIMIT), Ok(deflated.len()));
+        assert_eq!(inflated, bytes);
+    }
+}
+
+#[test]
+fn repeats_shrink_and_noise_barely_grows() {
+    assert!(compress(&vec![0; 300_000]).len() < 3000);
+    assert!(compress(&lines()).len() < lines().len() / 4);
+    // Blocks that wouldn't shrink are stored as they are
+    assert!(compress(&random(100_000)).len() < 100_000 + 100);
+}
+
+#[test]
+fn corrupt_files_are_errors() {
+    let good = compress(&lines());
+
+    let mut flipped = good.clone();
+    flipped[good.len() / 2] ^= 0x10;
+    assert!(decompress(&flipped, LIMIT).is_err());
+
+    let mut bad_crc = good.clone();
+    let crc = good.len() - 8;
+    bad_crc[crc] ^= 1;
+    assert_eq!(
+        decompress(&bad_crc, LIMIT),
+        Err("the CRC of the member at 0 doesn't match".to_string())
+    );
+
+    assert_eq!(
+        decompress(&good[..good.len() - 4], LIMIT),
+        Err("the member at 0 is cut short".to_string())
+    );
+    assert!(decompress(&good[..good.len() / 2], LIMIT)
+        .unwrap_err()
+        .contains("the data ends in the middle"));
+    assert_eq!(
+        decompress(&good[..6], LIMIT),
+        Err("the header ends in the middle at 0".to_string())
+    );
+    assert_eq!(
+        decompress(b"\x1f\x8b\x07\0\0\0\0\0\0\xff", LIMIT),
+        Err("unknown compression method 7 at 0".to_string())
+    );
+    assert_eq!(
+        decompress(b"PK\x03\x04", LIMIT),
+        Err("not gzip data at 0".to_string())
+    );
+}
+
+#[test]
+fn inflating_stops_at_the_limit() {
+    let bomb = compress(&vec![0; 1_000_000]);
+    assert_eq!(
+        decompress(&bomb, 0x10000),
+        Err("corrupt data in the member at 0: it inflates to more than 10000 bytes".to_string())
+    );
+    assert_eq!(decompress(&bomb, 1_000_000).unwrap().len(), 1_000_000);
+}
@@ -0,0 +1,161 @@
+use hex_it::headers::{parse, Entry, Headers, Kind};
+
+const ELF: &[u8] = include_bytes!("fixtures/tiny.elf");
+const PE: &[u8] = include_bytes!("fixtures/tiny.exe");
+
+/// The entries as (name, offset, size, flags) to compare in one go
+fn summary(headers: &Headers) -> Vec<(&str, u64, u64, &str)> {
+    headers
+        .entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.name.as_str(),
+                entry.offset,
+                entry.size,
+                entry.flags.as_str(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn parses_the_elf_fixture() {
+    let headers = parse(ELF).unwrap();
+
+    assert_eq!(
+        headers.format,
+        "ELF 64-bit little endian executable for x86-64"
+    );
+    assert!(headers.warnings.is_empty(), "{:?}", headers.warnings);
+    assert_eq!(
+        summary(&headers),
+        [
+            ("ELF header", 0, 0x40, ""),
+            ("program headers", 0x40, 0x70, ""),
+            ("LOAD", 0, 0xC0, "r-x"),
+            ("GNU_STACK", 0, 0, "rw-"),
+            ("section headers", 0xE8, 0x140, ""),
+            (".text", 0xB0, 0x10, "AX"),
+            (".data", 0xC0, 8, "WA"),
+            // Takes up memory only
+            (".bss", 0xC8, 0, "WA"),
+            (".shstrtab", 0xC8, 0x1C, ""),
+        ]
+    );
+    assert_eq!(headers.entries[2].kind, Kind::Segment);
+    assert_eq!(headers.entries[5].kind, Kind::Section);
+}
+
+#[test]
+fn parses_the_pe_fixture() {
+    let headers = parse(PE).unwrap();
+
+    assert_eq!(headers.format, "PE32+ executable for x86-64");
+    assert!(headers.warnings.is_empty(), "{:?}", headers.warnings);
+    assert_eq!(
+        summary(&headers),
+        [
+            ("DOS header", 0, 0x40, ""),
+            ("COFF header", 0x44, 0x14, ""),
+            ("optional header", 0x58, 0xF0, ""),
+            ("section table", 0x148, 0x50, ""),
+            (".text", 0x200, 0x200, "r-x code"),
+            (".data", 0x400, 0x200, "rw- data"),
+        ]
+    );
+}
+
+#[test]
+fn other_files_have_no_headers() {
+    assert_eq!(parse(b""), None);
+    assert_eq!(parse(b"\x89PNG\r\n\x1a\n"), None);
+}
+
+#[test]
+fn a_dos_program_stops_after_the_dos_header() {
+    let mut dos = PE[..0x40].to_vec();
+    dos[0x3C] = 0x10;
+
+    let headers = parse(&dos).unwrap();
+    assert_eq!(headers.format, "DOS MZ executable");
+    assert!(headers.warnings.is_empty());
+    assert_eq!(headers.entries.len(), 1);
+}
+
+#[test]
+fn cut_short_files_keep_what_was_read() {
+    // Only the header, the tables point past the end
+    let headers = parse(&ELF[..0x40]).unwrap();
+    assert_eq!(headers.entries[0].name, "ELF header");
+    assert_eq!(
+        headers.warnings[0],
+        "program header 0 at 40 runs past the end of the file"
+    );
+
+    // The section table stops after the first real section, the names are gone with the rest
+    let headers = parse(&ELF[..0xE8 + 0x80]).unwrap();
+    assert_eq!(
+        headers.entries.last(),
+        Some(&Entry {
+            kind: Kind::Section,
+            name: String::new(),
+            offset: 0xB0,
+            size: 0x10,
+            flags: "AX".to_string(),
+        })
+    );
+    assert!(headers
+        .warnings
+        .contains(&"section header 2 at 168 runs past the end of the file".to_string()));
+    assert!(headers
+        .warnings
+        .contains(&"the section names are in section 4, which isn't there".to_string()));
+
+    let headers = parse(&PE[..0x170]).unwrap();
+    assert_eq!(headers.entries.last().unwrap().name, ".text");
+    assert!(headers
+        .warnings
+        .contains(&"section header 1 at 170 runs past the end of the file".to_string()));
+    assert!(headers
+        .warnings
+        .contains(&"section .text at 200 runs past the end of the file".to_string()));
+
+    for length in 4..ELF.len() {
+        parse(&ELF[..length]).unwrap();
+    }
+    for length in 2..PE.len() {
+        parse(&PE[..length]).unwrap();
+    }
+}
+
+#[test]
+fn garbage_headers_warn_instead_of_failing() {
+    let mut elf = ELF.to_vec();
+    elf[4] = 9;
+    let headers = parse(&elf).unwrap();
+    assert_eq!(headers.format, "ELF");
+    assert_eq!(headers.warnings, ["unknown ELF class 9"]);
+
+    // Offsets at the very end of the address space
+    let mut elf = ELF.to_vec();
+    elf[0x20..0x30].fill(0xFF);
+    let headers = parse(&elf).unwrap();
+    assert_eq!(headers.entries[0].name, "ELF header");
+    assert!(!headers.warnings.is_empty());
+
+    // Program headers smaller than one
+    let mut elf = ELF.to_vec();
+    elf[0x36] = 8;
+    let headers = parse(&elf).unwrap();
+    assert_eq!(
+        headers.warnings,
+        ["program headers of 8 bytes are too small, left them out"]
+    );
+
+    let mut pe = PE.to_vec();
+    pe[0x58] = 0x42;
+    let headers = parse(&pe).unwrap();
+    assert_eq!(headers.format, "PE executable for x86-64");
+    assert_eq!(headers.warnings, ["unknown optional header magic 242"]);
+}
@@ -0,0 +1,47 @@
+use hex_it::{
+    bytes_to_hex,
+    hex::{push_hex, push_hex_lower},
+    hex_to_bytes,
+};
+
+#[test]
+fn hex_round_trips() {
+    let bytes = [0x00, 0x48, 0x7F, 0xFF];
+
+    assert_eq!(bytes_to_hex(&bytes), "00 48 7F FF");
+    assert_eq!(hex_to_bytes(&bytes_to_hex(&bytes)), Some(bytes.to_vec()));
+}
+
+#[test]
+fn hex_to_bytes_accepts_either_case_and_spacing() {
+    assert_eq!(hex_to_bytes("deadBEEF"), Some(vec![0xDE, 0xAD, 0xBE, 0xEF]));
+    assert_eq!(
+        hex_to_bytes(" de ad\tbe ef "),
+        Some(vec![0xDE, 0xAD, 0xBE, 0xEF])
+    );
+    assert_eq!(hex_to_bytes(""), Some(vec![]));
+}
+
+#[test]
+fn hex_to_bytes_rejects_bad_input() {
+    assert_eq!(hex_to_bytes("abc"), None);
+    assert_eq!(hex_to_bytes("zz"), None);
+    assert_eq!(hex_to_bytes("0x41"), None);
+}
+
+#[test]
+fn bytes_to_hex_of_nothing_is_empty() {
+    assert_eq!(bytes_to_hex(&[]), "");
+}
+
+#[test]
+fn every_byte_matches_format() {
+    for byte in 0..=255u8 {
+        let (mut upper, mut lower) = (String::new(), String::new());
+        push_hex(&mut upper, byte);
+        push_hex_lower(&mut lower, byte);
+
+        assert_eq!(upper, format!("{:02X}", byte));
+        assert_eq!(lower, format!("{:02x}", byte));
+    }
+}
@@ -0,0 +1,89 @@
+use hex_it::{
+    digest::Entropy,
+    histogram::{buckets, render, top_values, Row},
+    screen::visible_width,
+};
+
+fn counts(bytes: &[u8]) -> Entropy {
+    let mut counted = Entropy::new();
+    counted.update(bytes);
+    counted
+}
+
+#[test]
+fn buckets_cover_every_value_once() {
+    let bytes = (0..=255u8).chain([0x41; 16]).collect::<Vec<u8>>();
+    let counted = counts(&bytes);
+
+    let rows = buckets(counted.counts(), 16);
+    assert_eq!(rows.len(), 16);
+    assert_eq!(rows[0].label, "00-0F");
+    assert_eq!(rows[4].count, 32);
+    assert_eq!(
+        rows.iter().map(|row| row.count).sum::<u64>(),
+        counted.total()
+    );
+
+    // Uneven splits still cover everything
+    let rows = buckets(counted.counts(), 3);
+    let labels: Vec<&str> = rows.iter().map(|row| row.label.as_str()).collect();
+    assert_eq!(labels, ["00-54", "55-A9", "AA-FF"]);
+    assert_eq!(buckets(counted.counts(), 256)[0x41].label, "41");
+}
+
+#[test]
+fn top_values_go_by_count_then_value() {
+    let counted = counts(b"aaabbc\n\n\n");
+    let rows = top_values(counted.counts(), 3);
+
+    assert_eq!(
+        rows,
+        [
+            Row {
+                label: "0A".to_string(),
+                count: 3
+            },
+            Row {
+                label: "61 'a'".to_string(),
+                count: 3
+            },
+            Row {
+                label: "62 'b'".to_string(),
+                count: 2
+            },
+        ]
+    );
+    assert_eq!(top_values(counted.counts(), 100).len(), 4);
+}
+
+#[test]
+fn bars_are_scaled_to_the_biggest_count() {
+    let rows = [
+        Row {
+            label: "a".to_string(),
+            count: 4,
+        },
+        Row {
+            label: "b".to_string(),
+            count: 1,
+        },
+    ];
+    let lines = render(&rows, 5, 40);
+
+    assert_eq!(lines[0], format!("a  4   80.0%  {}", "█".repeat(25)));
+    assert_eq!(lines[1], "b  1   20.0%  ██████▎");
+    assert!(lines.iter().all(|line| visible_width(line) < 40));
+}
+
+#[test]
+fn nothing_counted_draws_no_bars() {
+    let counted = counts(b"");
+    let lines = render(&buckets(counted.counts(), 4), counted.total(), 80);
+    assert_eq!(lines[0], "00-3F  0    0.0%");
+
+    // A single value fills its bar and leaves the others empty
+    let counted = counts(&[7; 1000]);
+    let lines = render(&buckets(counted.counts(), 2), counted.total(), 30);
+    assert_eq!(lines[0], format!("00-7F  1000  100.0%  {}", "█".repeat(8)));
+    assert_eq!(lines[1], "80-FF     0    0.0%");
+}
@@ -0,0 +1,94 @@
+use hex_it::{
+    inspect::{format_float, summary, table, Number},
+    template::Endian,
+};
+
+#[test]
+fn numbers_read_either_byte_order() {
+    let bytes = [0x34, 0x12, 0xFF, 0xFF, 0, 0, 0, 0x80];
+    let decode = |number: Number, endian| number.decode(&bytes, endian);
+
+    assert_eq!(
+        decode(Number::Unsigned(1), Endian::Little),
+        Some("52".into())
+    );
+    assert_eq!(
+        decode(Number::Unsigned(2), Endian::Little),
+        Some("4660".into())
+    );
+    assert_eq!(
+        decode(Number::Unsigned(2), Endian::Big),
+        Some("13330".into())
+    );
+    assert_eq!(
+        decode(Number::Signed(4), Endian::Little),
+        Some("-60876".into())
+    );
+    assert_eq!(
+        decode(Number::Unsigned(4), Endian::Little),
+        Some("4294906420".into())
+    );
+    assert_eq!(
+        decode(Number::Signed(8), Endian::Little),
+        Some((0x800000_00FFFF1234u64 as i64).to_string())
+    );
+    assert_eq!(decode(Number::Signed(1), Endian::Big), Some("52".into()));
+    assert_eq!(
+        Number::Signed(1).decode(&[0xFF], Endian::Little),
+        Some("-1".into())
+    );
+}
+
+#[test]
+fn floats_stay_short() {
+    let one = 1.0f32.to_le_bytes();
+    assert_eq!(Number::F32.decode(&one, Endian::Little), Some("1".into()));
+    assert_eq!(
+        Number::F64.decode(&2.5f64.to_be_bytes(), Endian::Big),
+        Some("2.5".into())
+    );
+    assert_eq!(format_float(1.5e30), "1.5e30");
+    assert_eq!(format_float(-0.00001), "-1e-5");
+    assert_eq!(format_float(f64::NAN), "NaN");
+    assert_eq!(format_float(0.0), "0");
+    assert_eq!(
+        Number::F32.decode(&1.1431415e27f32.to_le_bytes(), Endian::Little),
+        Some("1.1431415e27".into())
+    );
+}
+
+#[test]
+fn too_few_bytes_decode_to_nothing() {
+    assert_eq!(Number::Unsigned(4).decode(&[1, 2, 3], Endian::Little), None);
+    assert_eq!(Number::F64.decode(&[], Endian::Big), None);
+
+    // Near the end only the types that fit are listed
+    let lines = table(0x10, &[1, 0, 0]);
+    assert_eq!(lines[0], "Offset 00000010 (16)");
+    let types = lines[2..]
+        .iter()
+        .map(|line| line.split_whitespace().next().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(types, vec!["u8", "i8", "u16", "i16"]);
+    assert_eq!(lines[4], format!("{:<6}{:<26}{}", "u16", "1", "256"));
+}
+
+#[test]
+fn the_summary_fits_the_width() {
+    let bytes = 0x3F800000u32.to_le_bytes();
+    let full = summary(0x20, &bytes, 200);
+    assert_eq!(
+        full,
+        "@20 (32)  u8 0  u16 0  u32 1065353216  i32 1065353216  f32 1"
+    );
+
+    // Types drop off the end first, the offset always stays
+    assert_eq!(summary(0x20, &bytes, 30), "@20 (32)  u8 0  u16 0");
+    assert_eq!(summary(0x20, &bytes, 3), "@20 (32)");
+
+    // Past the end of the buffer there is nothing to read
+    assert_eq!(
+        summary(0x20, &[7, 0], 200),
+        "@20 (32)  u8 7  u16 7  u32 -  i32 -  f32 -"
+    );
+}
@@ -0,0 +1,183 @@
+use hex_it::{
+    digest::Sha256,
+    job::{Job, JobEvent, Task},
+};
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+/// Counts the bytes it is fed, taking its time over every chunk
+struct SlowCount {
+    bytes: usize,
+    delay: Duration,
+}
+
+impl Task for SlowCount {
+    fn update(&mut self, chunk: &[u8]) {
+        thread::sleep(self.delay);
+        self.bytes += chunk.len();
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        format!("{} bytes", self.bytes)
+    }
+}
+
+fn slow(delay: Duration) -> Box<SlowCount> {
+    Box::new(SlowCount { bytes: 0, delay })
+}
+
+#[test]
+fn result_is_delivered() {
+    let bytes = Arc::new(b"abc".repeat(1000));
+    let job = Job::spawn_chunked("count", bytes, slow(Duration::from_millis(1)), 100, || {});
+
+    assert_eq!(job.wait(), JobEvent::Done("3000 bytes".to_string()));
+}
+
+#[test]
+fn hash_job_matches_hashing_inline() {
+    let bytes = (0..100_000u32)
+        .map(|i| (i % 253) as u8)
+        .collect::<Vec<u8>>();
+    let mut inline = Sha256::new();
+    inline.update(&bytes);
+    let expected = format!("sha256 {}", inline.finish_hex());
+
+    let job = Job::spawn_chunked(
+        "hash sha256",
+        Arc::new(bytes),
+        Box::new(Sha256::new()),
+        777,
+        || {},
+    );
+    assert_eq!(job.wait(), JobEvent::Done(expected));
+}
+
+#[test]
+fn cancelling_stops_the_job() {
+    let (tx, rx) = mpsc::channel();
+    let bytes = Arc::new(vec![0; 10_000]);
+    let mut job = Job::spawn_chunked(
+        "count",
+        bytes,
+        slow(Duration::from_millis(20)),
+        10,
+        move || {
+            let _ = tx.send(());
+        },
+    );
+
+    // Let it get going, a full run would take 20 seconds
+    rx.recv_timeout(Duration::from_secs(5))
+        .expect("a progress report");
+    assert!(matches!(job.poll(), Some(JobEvent::Progress(_))));
+    let percent = job.percent();
+    assert!(percent < 100);
+
+    job.cancel();
+    rx.recv_timeout(Duration::from_secs(5))
+        .expect("the job to stop");
+    loop {
+        match job.poll() {
+            Some(JobEvent::Cancelled) => break,
+            Some(JobEvent::Progress(_)) | None => {
+                rx.recv_timeout(Duration::from_secs(5))
+                    .expect("the job to stop");
+            }
+            other => panic!("expected a cancel, got {:?}", other),
+        }
+    }
+    assert!(job.percent() < 100);
+}
+
+/// Takes a chunk only when let through, saying when it has one
+struct Gated {
+    fed: mpsc::Sender<()>,
+    gate: mpsc::Receiver<()>,
+}
+
+impl Task for Gated {
+    fn update(&mut self, _chunk: &[u8]) {
+        let _ = self.fed.send(());
+        let _ = self.gate.recv();
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        "finished".to_string()
+    }
+}
+
+#[test]
+fn cancelling_stops_at_the_next_chunk() {
+    let (fed, chunks) = mpsc::channel();
+    let (open, gate) = mpsc::channel();
+    let mut job = Job::spawn_chunked(
+        "gated",
+        Arc::new(vec![0; 100]),
+        Box::new(Gated { fed, gate }),
+        10,
+        || {},
+    );
+
+    // Cancel while the fourth chunk is being handled, the fifth is never started
+    for chunk in 1..=4 {
+        chunks
+            .recv_timeout(Duration::from_secs(5))
+            .expect("a chunk");
+        if chunk == 4 {
+            job.cancel();
+        }
+        open.send(()).unwrap();
+    }
+
+    let end = loop {
+        match job.poll() {
+            Some(JobEvent::Progress(_)) | None => thread::sleep(Duration::from_millis(5)),
+            Some(end) => break end,
+        }
+    };
+    assert_eq!(end, JobEvent::Cancelled);
+    assert_eq!(job.percent(), 40);
+    assert_eq!(job.progress().cancelled(), "gated cancelled at 40%");
+    assert!(chunks.try_recv().is_err());
+}
+
+#[test]
+fn progress_is_reported_while_running() {
+    let (tx, rx) = mpsc::channel();
+    let bytes = Arc::new(vec![0; 50]);
+    let mut job = Job::spawn_chunked(
+        "count",
+        bytes,
+        slow(Duration::from_millis(30)),
+        10,
+        move || {
+            let _ = tx.send(());
+        },
+    );
+
+    let mut progress = Vec::new();
+    let result = loop {
+        rx.recv_timeout(Duration::from_secs(5)).expect("an event");
+        match job.poll() {
+            Some(JobEvent::Progress(done)) => progress.push(done),
+            Some(end) => break end,
+            None => {}
+        }
+    };
+
+    assert_eq!(result, JobEvent::Done("50 bytes".to_string()));
+    assert!(!progress.is_empty());
+    assert!(progress.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn empty_buffer_finishes_at_once() {
+    let mut job = Job::spawn("count", Arc::new(Vec::new()), slow(Duration::ZERO), || {});
+    assert_eq!(job.percent(), 100);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(job.poll(), Some(JobEvent::Done("0 bytes".to_string())));
+}
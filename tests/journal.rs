@@ -0,0 +1,185 @@
+use hex_it::{
+    document::Document,
+    journal::{header, journal_path, Journal, JournalWriter, Record, JOURNAL_VERSION},
+};
+use std::{fs, path::PathBuf};
+
+const ORIGINAL: &[u8] = b"0123456789abcdef";
+
+/// A journaled session: single edits, a grouped one, an undo and a swap
+fn session() -> (Document, Vec<Record>) {
+    let mut document = Document::new(ORIGINAL.to_vec());
+    document.start_journal();
+
+    document.overwrite(0, b"A").unwrap();
+    document.begin_undo_group();
+    document.overwrite(1, b"B").unwrap();
+    document.overwrite(2, b"C").unwrap();
+    document.end_undo_group();
+    document.insert(16, b"tail").unwrap();
+    document.undo().unwrap();
+    document.delete(4..6).unwrap();
+    document.swap(6, 10, 2).unwrap();
+
+    let records = document.take_journal();
+    (document, records)
+}
+
+/// The journal file for records
+fn journal_bytes(records: &[Record]) -> Vec<u8> {
+    let mut bytes = header(ORIGINAL.len());
+    for record in records {
+        record.encode(&mut bytes);
+    }
+    bytes
+}
+
+#[test]
+fn only_journaled_documents_keep_records() {
+    let mut document = Document::new(ORIGINAL.to_vec());
+    document.overwrite(0, b"x").unwrap();
+    assert!(document.take_journal().is_empty());
+
+    document.start_journal();
+    document.overwrite(0, b"y").unwrap();
+    assert_eq!(
+        document.take_journal(),
+        vec![Record::Edit {
+            new_step: true,
+            offset: 0,
+            removed: b"x".to_vec(),
+            inserted: b"y".to_vec(),
+        }]
+    );
+    assert!(document.take_journal().is_empty());
+
+    // Saved, the records so far are in the file now
+    document.overwrite(1, b"z").unwrap();
+    document.mark_clean();
+    assert!(document.take_journal().is_empty());
+}
+
+#[test]
+fn replaying_brings_back_the_bytes_and_the_undo_steps() {
+    let (mut edited, records) = session();
+    let journal = Journal::parse(&journal_bytes(&records)).unwrap();
+    assert_eq!(journal.size, ORIGINAL.len());
+    assert_eq!(journal.records, records);
+
+    let mut replayed = journal.replay(ORIGINAL.to_vec()).unwrap();
+    assert_eq!(replayed.bytes(), edited.bytes());
+    assert!(replayed.is_dirty());
+
+    // The same undo steps, in the same order
+    while edited.can_undo() {
+        assert_eq!(replayed.undo(), edited.undo());
+        assert_eq!(replayed.bytes(), edited.bytes());
+    }
+    assert!(!replayed.can_undo());
+    assert_eq!(replayed.bytes(), ORIGINAL);
+    assert!(!replayed.is_dirty());
+}
+
+#[test]
+fn replay_refuses_a_file_that_changed() {
+    let (_, records) = session();
+    let journal = Journal::parse(&journal_bytes(&records)).unwrap();
+
+    let Err(error) = journal.replay(ORIGINAL[1..].to_vec()) else {
+        panic!("replayed over a different file");
+    };
+    assert!(error.contains("the journal was written for 10"), "{error}");
+
+    // Byte 5 is one the delete took out
+    let mut changed = ORIGINAL.to_vec();
+    changed[5] = b'!';
+    let Err(error) = journal.replay(changed) else {
+        panic!("replayed over a different file");
+    };
+    assert!(error.contains("changed at 4 since"), "{error}");
+
+    // Bytes no edit touched don't matter
+    let mut untouched = ORIGINAL.to_vec();
+    untouched[15] = b'!';
+    assert!(journal.replay(untouched).is_ok());
+}
+
+#[test]
+fn damaged_journals_are_read_up_to_the_first_bad_record() {
+    let (_, records) = session();
+    let bytes = journal_bytes(&records);
+    let whole = Journal::parse(&bytes).unwrap();
+    assert_eq!(whole.intact, bytes.len());
+
+    // Cut short in the middle of the last record
+    let cut = Journal::parse(&bytes[..bytes.len() - 3]).unwrap();
+    assert_eq!(cut.records, records[..records.len() - 1]);
+    assert!(cut.intact < bytes.len() - 3);
+
+    // A flipped byte in the second record loses it and everything after
+    let mut first = header(ORIGINAL.len());
+    records[0].encode(&mut first);
+    let mut flipped = bytes.clone();
+    flipped[first.len() + 9] ^= 0xFF;
+    let damaged = Journal::parse(&flipped).unwrap();
+    assert_eq!(damaged.records, records[..1]);
+    assert_eq!(damaged.intact, first.len());
+
+    // Lengths that run past the end are damage too, not something to allocate
+    let mut huge = first.clone();
+    huge.push(1);
+    huge.extend_from_slice(&0u64.to_le_bytes());
+    huge.extend_from_slice(&u64::MAX.to_le_bytes());
+    huge.extend_from_slice(&0u64.to_le_bytes());
+    assert_eq!(Journal::parse(&huge).unwrap().records, records[..1]);
+}
+
+#[test]
+fn journals_of_other_versions_or_other_files_are_refused() {
+    assert!(Journal::parse(b"").is_err());
+    assert!(Journal::parse(b"# hex_it recovery of 10 bytes\n").is_err());
+
+    let mut newer = header(4);
+    newer[8..10].copy_from_slice(&(JOURNAL_VERSION + 1).to_le_bytes());
+    let error = Journal::parse(&newer).unwrap_err();
+    assert!(error.contains("journal version 2"), "{error}");
+
+    // A header with nothing after it is a journal of no edits
+    let empty = Journal::parse(&header(4)).unwrap();
+    assert!(empty.records.is_empty());
+    assert_eq!(empty.replay(vec![0; 4]).unwrap().bytes(), [0; 4]);
+}
+
+#[test]
+fn writers_start_the_file_with_the_first_record_and_resume_after_damage() {
+    let file = std::env::temp_dir().join(format!("hex_it-{}-journaled", std::process::id()));
+    let path = journal_path(&file);
+    assert_eq!(
+        path,
+        PathBuf::from(format!("{}.hexit-journal", file.display()))
+    );
+
+    let (_, records) = session();
+    let mut writer = JournalWriter::new(path.clone(), ORIGINAL.len());
+    writer.append(&[]).unwrap();
+    assert!(!path.exists());
+
+    writer.append(&records[..2]).unwrap();
+    writer.append(&records[2..]).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), journal_bytes(&records));
+
+    // A crash in the middle of a record, then a session that replays and carries on
+    let mut bytes = journal_bytes(&records);
+    bytes.extend_from_slice(&[1, 2, 3]);
+    fs::write(&path, &bytes).unwrap();
+    let journal = Journal::parse(&bytes).unwrap();
+    let mut resumed = JournalWriter::resume(path.clone(), &journal).unwrap();
+    resumed.append(&[Record::Undo]).unwrap();
+
+    let mut expected = journal_bytes(&records);
+    Record::Undo.encode(&mut expected);
+    assert_eq!(fs::read(&path).unwrap(), expected);
+
+    resumed.remove();
+    assert!(!path.exists());
+}
@@ -0,0 +1,105 @@
+use hex_it::key::{decode_keys, Key, MouseEvent};
+
+#[test]
+fn decodes_plain_and_control_bytes() {
+    assert_eq!(
+        decode_keys(b"a:\r\n\x7f\x08\x03\x01"),
+        vec![
+            Key::Char('a'),
+            Key::Char(':'),
+            Key::Enter,
+            Key::Enter,
+            Key::Backspace,
+            Key::Backspace,
+            Key::CtrlC,
+            Key::Ctrl('a'),
+        ]
+    );
+}
+
+#[test]
+fn lone_escape_is_esc() {
+    assert_eq!(decode_keys(b"\x1b"), vec![Key::Esc]);
+}
+
+#[test]
+fn decodes_arrows_and_editing_keys() {
+    assert_eq!(
+        decode_keys(b"\x1b[A\x1b[B\x1b[C\x1b[D\x1bOH\x1b[F\x1b[3~\x1b[5~\x1b[6~"),
+        vec![
+            Key::Up,
+            Key::Down,
+            Key::Right,
+            Key::Left,
+            Key::Home,
+            Key::End,
+            Key::Delete,
+            Key::PageUp,
+            Key::PageDown,
+        ]
+    );
+}
+
+#[test]
+fn home_and_end_have_several_spellings() {
+    assert_eq!(
+        decode_keys(b"\x1b[H\x1b[1~\x1b[7~\x1bOF\x1b[4~\x1b[8~"),
+        vec![
+            Key::Home,
+            Key::Home,
+            Key::Home,
+            Key::End,
+            Key::End,
+            Key::End
+        ]
+    );
+}
+
+#[test]
+fn ctrl_and_alt_arrows_move_by_word() {
+    assert_eq!(
+        decode_keys(b"\x1b[1;5C\x1b[1;5D\x1b[1;3C\x1b[1;3D"),
+        vec![Key::CtrlRight, Key::CtrlLeft, Key::CtrlRight, Key::CtrlLeft]
+    );
+}
+
+#[test]
+fn escape_then_char_is_alt() {
+    assert_eq!(
+        decode_keys(b"\x1bb\x1bf"),
+        vec![Key::Alt('b'), Key::Alt('f')]
+    );
+}
+
+#[test]
+fn decodes_bracketed_paste() {
+    assert_eq!(
+        decode_keys(b"\x1b[200~ab\x1b[Ac\r\n\x1b[201~x"),
+        vec![Key::Paste("ab\x1b[Ac\r\n".to_string()), Key::Char('x')]
+    );
+}
+
+#[test]
+fn decodes_mouse_reports() {
+    assert_eq!(
+        decode_keys(b"\x1b[<0;12;3M\x1b[<0;12;3m\x1b[<64;1;1M\x1b[<65;1;1M"),
+        vec![
+            Key::Mouse(MouseEvent::Press {
+                button: 0,
+                column: 12,
+                row: 3
+            }),
+            Key::Mouse(MouseEvent::Release { column: 12, row: 3 }),
+            Key::Mouse(MouseEvent::WheelUp),
+            Key::Mouse(MouseEvent::WheelDown),
+        ]
+    );
+}
+
+#[test]
+fn unknown_sequences_are_unknown() {
+    assert_eq!(
+        decode_keys(b"\x1b[99~\x1b[<0;1M\xff"),
+        vec![Key::Unknown, Key::Unknown, Key::Unknown]
+    );
+}
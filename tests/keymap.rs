@@ -0,0 +1,179 @@
+use hex_it::{
+    config::Config,
+    file::DEFAULT_STREAM_LIMIT,
+    key::Key,
+    keymap::{key_name, parse_key, Action, Keymap, Mode},
+};
+use std::time::Duration;
+
+#[test]
+fn key_specs_round_trip() {
+    for spec in [
+        "ctrl+b",
+        "alt+f",
+        "pageup",
+        "space",
+        ";",
+        "G",
+        "ctrl+left",
+        "tab",
+    ] {
+        let key = parse_key(spec).unwrap();
+        assert_eq!(parse_key(&key_name(&key)), Some(key));
+    }
+
+    assert_eq!(parse_key("CTRL+B"), Some(Key::Ctrl('b')));
+    // Tab comes in as Ctrl+I and is named for the key that was pressed
+    assert_eq!(parse_key("tab"), Some(Key::Ctrl('i')));
+    assert_eq!(key_name(&Key::Ctrl('i')), "tab");
+    assert_eq!(parse_key("ctrl+1"), None);
+    assert_eq!(parse_key("nonsense"), None);
+}
+
+#[test]
+fn default_bindings_depend_on_the_mode() {
+    let keymap = Keymap::new();
+
+    assert_eq!(
+        keymap.action(Mode::Normal, &Key::Char('j')),
+        Some(Action::CursorDown)
+    );
+    // Typed chars are input in command mode and hex digits in overwrite mode
+    assert_eq!(keymap.action(Mode::Command, &Key::Char('j')), None);
+    assert_eq!(keymap.action(Mode::Overwrite, &Key::Char('j')), None);
+    assert_eq!(
+        keymap.action(Mode::Overwrite, &Key::Down),
+        Some(Action::CursorDown)
+    );
+    assert_eq!(keymap.action(Mode::Command, &Key::Left), Some(Action::Left));
+    assert_eq!(
+        keymap.action(Mode::Normal, &Key::Char('p')),
+        Some(Action::Put)
+    );
+    assert_eq!(keymap.action(Mode::Overwrite, &Key::Char('y')), None);
+    // Text mode types every char, arrows still move
+    assert_eq!(
+        keymap.action(Mode::Normal, &Key::Char('t')),
+        Some(Action::TextMode)
+    );
+    assert_eq!(keymap.action(Mode::Text, &Key::Char('j')), None);
+    assert_eq!(
+        keymap.action(Mode::Text, &Key::Right),
+        Some(Action::CursorRight)
+    );
+    assert_eq!(keymap.action(Mode::Text, &Key::Esc), Some(Action::Cancel));
+    assert_eq!(
+        keymap.action(Mode::Command, &Key::Ctrl('i')),
+        Some(Action::Complete)
+    );
+    assert_eq!(keymap.action(Mode::Normal, &Key::Ctrl('i')), None);
+}
+
+#[test]
+fn config_binds_keys() {
+    let (config, errors) = Config::parse(
+        "# my bindings\n\
+         \n\
+         bind quit = ctrl+q\n\
+         bind normal cursor-down = n\n",
+    );
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    for mode in [Mode::Normal, Mode::Command, Mode::Overwrite, Mode::Text] {
+        assert_eq!(
+            config.keymap.action(mode, &Key::Ctrl('q')),
+            Some(Action::Quit)
+        );
+    }
+    assert_eq!(
+        config.keymap.action(Mode::Normal, &Key::Char('n')),
+        Some(Action::CursorDown)
+    );
+    assert_eq!(config.keymap.action(Mode::Command, &Key::Char('n')), None);
+}
+
+#[test]
+fn config_reports_bad_lines_and_keeps_the_rest() {
+    let (config, errors) = Config::parse(
+        "bind fly = x\n\
+         bind sideways quit = x\n\
+         bind quit = notakey\n\
+         colour red\n\
+         bind quit = q\n",
+    );
+
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].starts_with("config line 1 "));
+    assert!(errors[0].contains("unknown action 'fly'"));
+    assert!(errors[1].contains("unknown mode 'sideways'"));
+    assert!(errors[2].contains("can't parse key"));
+    assert!(errors[3].contains("unknown setting 'colour'"));
+    assert_eq!(
+        config.keymap.action(Mode::Normal, &Key::Char('q')),
+        Some(Action::Quit)
+    );
+}
+
+#[test]
+fn config_sets_the_stream_limit() {
+    assert_eq!(Config::parse("").0.stream_limit, DEFAULT_STREAM_LIMIT);
+
+    let (config, errors) = Config::parse("stream-limit 0x1000\nstream-limit lots\n");
+    assert_eq!(config.stream_limit, 0x1000);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("config line 2 "));
+}
+
+#[test]
+fn config_turns_on_autosave() {
+    assert_eq!(Config::parse("").0.autosave, None);
+
+    let (config, errors) = Config::parse("autosave 30\nautosave 0\nautosave often\n");
+    assert_eq!(config.autosave, Some(Duration::from_secs(30)));
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors[0].starts_with("config line 2 "));
+
+    assert_eq!(
+        Config::parse("autosave 30\nautosave off\n").0.autosave,
+        None
+    );
+}
+
+#[test]
+fn config_turns_off_the_journal() {
+    assert!(Config::parse("").0.journal);
+
+    let (config, errors) = Config::parse("journal off\njournal maybe\n");
+    assert!(!config.journal);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+}
+
+#[test]
+fn config_changes_palette_colors() {
+    let (config, errors) = Config::parse(
+        "palette zero = dim\n\
+         palette modified = bold+magenta\n\
+         palette zero = purple\n\
+         palette sparkles = red\n",
+    );
+
+    assert_eq!(
+        config.palette,
+        vec![
+            ("zero".to_string(), "\x1b[2m"),
+            ("modified".to_string(), "\x1b[1;35m")
+        ]
+    );
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors[0].contains("unknown color 'purple'"));
+    assert!(errors[1].contains("unknown palette entry 'sparkles'"));
+}
+
+#[test]
+fn config_turns_the_gutter_off() {
+    assert!(Config::parse("").0.gutter);
+
+    let (config, errors) = Config::parse("gutter off\ngutter sideways\n");
+    assert!(!config.gutter);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+}
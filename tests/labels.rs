@@ -0,0 +1,94 @@
+use hex_it::labels::{common_prefix, Labels};
+
+#[test]
+fn labels_are_an_address_and_a_name() {
+    let (labels, report) = Labels::parse(
+        "# memory map\n\
+         0x0000 vector_table\n\
+         \n\
+         0400   config_block  # 1 KiB in\n\
+         0X1C4 reset\n",
+        0,
+    );
+
+    assert_eq!(
+        labels.iter().collect::<Vec<_>>(),
+        [
+            (0, "vector_table"),
+            (0x1C4, "reset"),
+            (0x400, "config_block")
+        ]
+    );
+    assert_eq!(report.skipped, 0);
+    assert!(report.warnings.is_empty());
+    assert_eq!(labels.offset_of("reset"), Some(0x1C4));
+    assert_eq!(labels.offset_of("nothing"), None);
+    assert_eq!(
+        labels.within(0x10..0x410).collect::<Vec<_>>(),
+        [(0x1C4, "reset"), (0x400, "config_block")]
+    );
+}
+
+#[test]
+fn nm_and_linker_map_output_reads() {
+    let nm = "0000000000001139 T main\n\
+              0000000000004010 B counter\n\
+              \x20                U puts\n";
+    let (labels, report) = Labels::parse(nm, 0);
+    assert_eq!(
+        labels.iter().collect::<Vec<_>>(),
+        [(0x1139, "main"), (0x4010, "counter")]
+    );
+    assert_eq!(report.skipped, 1);
+
+    let map = ".text           0x0000000000401000       0x25 start.o\n\
+               \x20               0x0000000000401000                _start\n\
+               \x20               0x0000000000401010                loop\n\
+               LOAD start.o\n";
+    let (labels, report) = Labels::parse(map, 0x401000);
+    assert_eq!(
+        labels.iter().collect::<Vec<_>>(),
+        [(0, "_start"), (0x10, "loop")]
+    );
+    assert_eq!(report.skipped, 2);
+}
+
+#[test]
+fn the_base_address_is_taken_off() {
+    let (labels, report) = Labels::parse(
+        "8000000 vectors\n8000400 config\n7FFFFFF below\n",
+        0x8000000,
+    );
+
+    assert_eq!(
+        labels.iter().collect::<Vec<_>>(),
+        [(0, "vectors"), (0x400, "config")]
+    );
+    assert_eq!(
+        report.warnings,
+        ["line 3: 7FFFFFF below is below the base 8000000"]
+    );
+}
+
+#[test]
+fn a_later_label_for_an_address_wins() {
+    let (labels, report) = Labels::parse("400 first\n400 second\n", 0);
+
+    assert_eq!(labels.iter().collect::<Vec<_>>(), [(0x400, "second")]);
+    assert_eq!(report.warnings, ["line 2: 400 was first, now second"]);
+}
+
+#[test]
+fn names_complete_as_far_as_they_agree() {
+    let (labels, _) = Labels::parse(
+        "0 config_block\n10 config_crc\n20 counter\n30 reset\n40 config_block\n",
+        0,
+    );
+
+    assert_eq!(labels.complete("conf"), ["config_block", "config_crc"]);
+    assert_eq!(common_prefix(&labels.complete("conf")), "config_");
+    assert_eq!(common_prefix(&labels.complete("co")), "co");
+    assert_eq!(common_prefix(&labels.complete("r")), "reset");
+    assert_eq!(common_prefix(&labels.complete("x")), "");
+    assert_eq!(common_prefix(&["über", "übel"]), "übe");
+}
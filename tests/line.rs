@@ -0,0 +1,56 @@
+use hex_it::line::InputLine;
+
+fn typed(text: &str) -> InputLine {
+    let mut line = InputLine::default();
+    text.chars().for_each(|c| line.insert(c));
+    line
+}
+
+fn text(line: &InputLine) -> String {
+    line.chars.iter().collect()
+}
+
+#[test]
+fn edits_at_the_cursor() {
+    let mut line = typed("helo");
+    line.left();
+    line.insert('l');
+    assert_eq!(text(&line), "hello");
+
+    line.home();
+    line.delete();
+    line.end();
+    line.backspace();
+    assert_eq!(text(&line), "ell");
+    assert_eq!(line.take(), "ell");
+    assert_eq!(line.cursor, 0);
+}
+
+#[test]
+fn words_split_on_slashes_and_dots() {
+    let mut line = typed("save /tmp/out.bin");
+    line.word_left();
+    assert_eq!(line.cursor, 14);
+    line.word_left();
+    line.word_left();
+    assert_eq!(line.cursor, 6);
+    line.word_right();
+    assert_eq!(line.cursor, 9);
+
+    line.end();
+    line.delete_word();
+    assert_eq!(text(&line), "save /tmp/out.");
+    line.delete_to_start();
+    assert_eq!(text(&line), "");
+}
+
+#[test]
+fn moving_stays_inside_the_line() {
+    let mut line = typed("ab");
+    line.right();
+    assert_eq!(line.cursor, 2);
+    line.home();
+    line.left();
+    line.backspace();
+    assert_eq!((text(&line), line.cursor), ("ab".to_string(), 0));
+}
@@ -0,0 +1,207 @@
+use hex_it::magic::{builtin_signatures, detect, parse_signatures, scan, Signature};
+
+/// The first bytes of files of each type and the type they should be detected as
+const HEADERS: [(&[u8], &str); 14] = [
+    (b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", "PNG image"),
+    (b"\xff\xd8\xff\xe0\0\x10JFIF", "JPEG image"),
+    (b"GIF89a\x01\0\x01\0", "GIF image"),
+    (b"GIF87a\x01\0\x01\0", "GIF image"),
+    (b"PK\x03\x04\x14\0\0\0", "ZIP archive"),
+    (b"\x1f\x8b\x08\0\0\0\0\0", "gzip compressed data"),
+    (b"\x7fELF\x02\x01\x01\0", "ELF executable"),
+    (b"MZ\x90\0\x03\0\0\0", "PE/MZ executable"),
+    (b"%PDF-1.7\n", "PDF document"),
+    (b"SQLite format 3\0\x10\0", "SQLite database"),
+    (b"\0asm\x01\0\0\0", "WebAssembly module"),
+    (
+        b"\xcf\xfa\xed\xfe\x07\0\0\x01",
+        "Mach-O executable (64-bit)",
+    ),
+    (b"RIFF\x24\0\0\0WAVEfmt ", "WAV audio"),
+    (b"\0\0\0\x20ftypisom", "MP4/QuickTime video"),
+];
+
+#[test]
+fn detects_fixture_headers() {
+    let signatures = builtin_signatures();
+
+    for (header, expected) in HEADERS {
+        let found = detect(&signatures, header).map(|signature| signature.description.as_str());
+        assert_eq!(found, Some(expected), "header {:02X?}", header);
+    }
+}
+
+#[test]
+fn detects_signatures_past_the_start() {
+    let signatures = builtin_signatures();
+
+    let mut tar = vec![0; 512];
+    tar[..8].copy_from_slice(b"file.txt");
+    tar[257..263].copy_from_slice(b"ustar\0");
+    let found = detect(&signatures, &tar).unwrap();
+    assert_eq!(found.description, "tar archive");
+    assert_eq!(
+        found.describe_match(&tar),
+        "tar archive, 75 73 74 61 72 at offset 101"
+    );
+
+    let mut iso = vec![0; 0x8010];
+    iso[0x8001..0x8006].copy_from_slice(b"CD001");
+    assert_eq!(
+        detect(&signatures, &iso).unwrap().description,
+        "ISO 9660 CD image"
+    );
+}
+
+#[test]
+fn unknown_and_short_data_match_nothing() {
+    let signatures = builtin_signatures();
+
+    assert!(detect(&signatures, b"just some text").is_none());
+    assert!(detect(&signatures, b"").is_none());
+    // Only half of the PNG signature
+    assert!(detect(&signatures, b"\x89PNG").is_none());
+}
+
+#[test]
+fn more_specific_signatures_win() {
+    let signatures = vec![
+        Signature::parse(0, "50 4B", "short").unwrap(),
+        Signature::parse(0, "50 4B 03 04", "long").unwrap(),
+        Signature::parse(0, "50 4B ?? ??", "masked").unwrap(),
+    ];
+
+    assert_eq!(
+        detect(&signatures, b"PK\x03\x04").unwrap().description,
+        "long"
+    );
+    assert_eq!(
+        detect(&signatures, b"PK\x05\x06").unwrap().description,
+        "short"
+    );
+}
+
+#[test]
+fn patterns_mask_nibbles() {
+    let signature = Signature::parse(2, "4? ??  5A", "test").unwrap();
+
+    assert_eq!(signature.bytes, vec![0x40, 0x00, 0x5A]);
+    assert_eq!(signature.mask, vec![0xF0, 0x00, 0xFF]);
+    assert_eq!(signature.specificity(), 12);
+    assert_eq!(signature.pattern(), "4? ?? 5A");
+    assert!(signature.matches(b"..\x4F\x00Z"));
+    assert!(signature.matches(b"..\x41\xffZ"));
+    assert!(!signature.matches(b"..\x51\xffZ"));
+}
+
+#[test]
+fn bad_patterns_are_rejected() {
+    assert!(Signature::parse(0, "", "empty").is_err());
+    assert!(Signature::parse(0, "ABC", "odd").is_err());
+    assert!(Signature::parse(0, "GG", "not hex").is_err());
+}
+
+fn descriptions(signatures: &[Signature], data: &[u8]) -> Vec<(usize, String)> {
+    scan(signatures, data)
+        .iter()
+        .map(|hit| (hit.offset, hit.signature.description.clone()))
+        .collect()
+}
+
+#[test]
+fn scan_finds_embedded_files() {
+    let signatures = builtin_signatures();
+
+    let mut firmware = vec![0x11; 0x400];
+    firmware[..4].copy_from_slice(b"\x7fELF");
+    firmware[0x100..0x108].copy_from_slice(b"\x89PNG\r\n\x1a\n");
+    firmware[0x300..0x304].copy_from_slice(b"PK\x03\x04");
+
+    assert_eq!(
+        descriptions(&signatures, &firmware),
+        vec![
+            (0, "ELF executable".to_string()),
+            (0x100, "PNG image".to_string()),
+            (0x300, "ZIP archive".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn scan_places_hits_at_the_start_of_the_embedded_file() {
+    let signatures = builtin_signatures();
+
+    let mut data = vec![0; 0x1000];
+    data[0x200 + 257..0x200 + 262].copy_from_slice(b"ustar");
+
+    let hits = scan(&signatures, &data);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].offset, 0x200);
+    assert_eq!(hits[0].matched(), 0x301..0x306);
+}
+
+#[test]
+fn scan_skips_short_signatures_past_the_start() {
+    let signatures = builtin_signatures();
+
+    // "MZ" and "BM" are everywhere in ordinary text, only the start of the file counts for them
+    let hits = descriptions(&signatures, b"MZ then BM and MZ again, 1F 8B: \x1f\x8b");
+    assert_eq!(hits, vec![(0, "PE/MZ executable".to_string())]);
+}
+
+#[test]
+fn scan_keeps_the_most_specific_of_overlapping_hits() {
+    let signatures = vec![
+        Signature::parse(0, "AA BB CC DD", "short").unwrap(),
+        Signature::parse(0, "BB CC DD EE FF", "long").unwrap(),
+        Signature::parse(0, "CC DD EE FF 00 11", "longest").unwrap(),
+    ];
+
+    let data = b"\0\0\xaa\xbb\xcc\xdd\xee\xff\0\x11\0\xaa\xbb\xcc\xdd";
+    assert_eq!(
+        descriptions(&signatures, data),
+        vec![(4, "longest".to_string()), (11, "short".to_string())]
+    );
+}
+
+#[test]
+fn scan_of_nothing_finds_nothing() {
+    assert!(scan(&builtin_signatures(), b"").is_empty());
+    assert!(scan(&[], b"\x89PNG\r\n\x1a\n").is_empty());
+}
+
+#[test]
+fn signature_files() {
+    let (signatures, errors) = parse_signatures(
+        "# my formats\n\
+         \n\
+         53 41 56 45 ?? 01  My game save\n\
+         0x10: DE AD BE EF\tFirmware blob\n\
+         41 42 43 no description\n\
+         ZZ  Not hex\n",
+    );
+
+    assert_eq!(
+        signatures,
+        vec![
+            Signature::parse(0, "53 41 56 45 ?? 01", "My game save").unwrap(),
+            Signature::parse(0x10, "DEADBEEF", "Firmware blob").unwrap(),
+        ]
+    );
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].starts_with("signature line 5 "));
+    assert!(errors[1].contains("invalid digit 'Z'"));
+}
+
+#[test]
+fn user_signatures_are_scanned_too() {
+    let mut signatures = builtin_signatures();
+    let (extra, _) = parse_signatures("53 41 56 45 ?? 01  My game save");
+    signatures.extend(extra);
+
+    let data = b"header..SAVE\x07\x01 and the rest";
+    assert_eq!(
+        descriptions(&signatures, data),
+        vec![(8, "My game save".to_string())]
+    );
+}
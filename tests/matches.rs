@@ -0,0 +1,80 @@
+use hex_it::matches::{MatchFormat, MatchList, CSV_HEADER};
+
+const WEIRD: &[u8] = include_bytes!("fixtures/weird.bin");
+
+/// Every "e" in weird.bin
+fn list(search: &str) -> MatchList<'_> {
+    MatchList {
+        search,
+        bytes: WEIRD,
+        offsets: &[0x01, 0x16, 0x1F, 0x24],
+        width: 1,
+    }
+}
+
+fn csv(list: &MatchList) -> String {
+    let mut out = Vec::new();
+    list.write_csv(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn json(list: &MatchList) -> String {
+    let mut out = Vec::new();
+    list.write_json(&mut out, "weird \"1\".bin").unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn csv_matches_the_fixture() {
+    assert_eq!(
+        csv(&list("\"e\", or E")),
+        include_str!("fixtures/weird.matches.csv")
+    );
+}
+
+#[test]
+fn json_matches_the_fixture() {
+    assert_eq!(
+        json(&list("\"e\"\\\n")),
+        include_str!("fixtures/weird.matches.json")
+    );
+}
+
+#[test]
+fn context_stops_at_the_ends() {
+    let csv = csv(&MatchList {
+        search: "6C 69 6E 65 21",
+        bytes: WEIRD,
+        offsets: &[0x21],
+        width: 5,
+    });
+
+    let row = csv.strip_prefix(CSV_HEADER).unwrap();
+    assert_eq!(
+        row,
+        "6C 69 6E 65 21,00000021,33,5,25,206F6620746865206C696E6521\n"
+    );
+}
+
+#[test]
+fn no_matches_is_still_a_valid_file() {
+    let empty = MatchList {
+        offsets: &[],
+        ..list("e")
+    };
+
+    assert_eq!(csv(&empty), CSV_HEADER);
+    assert_eq!(
+        json(&empty),
+        "{\n  \"file\": \"weird \\\"1\\\".bin\",\n  \"search\": \"e\",\n  \"count\": 0,\n  \"matches\": []\n}\n"
+    );
+}
+
+#[test]
+fn the_format_comes_from_the_flag_or_the_file_name() {
+    assert_eq!(MatchFormat::parse("JSON"), Some(MatchFormat::Json));
+    assert_eq!(MatchFormat::parse("csv"), Some(MatchFormat::Csv));
+    assert_eq!(MatchFormat::parse("xml"), None);
+    assert_eq!(MatchFormat::for_path("hits.json"), MatchFormat::Json);
+    assert_eq!(MatchFormat::for_path("hits.txt"), MatchFormat::Csv);
+}
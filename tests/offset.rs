@@ -0,0 +1,120 @@
+use hex_it::{
+    offset::{evaluate, Variables},
+    parse_offset, parse_size,
+};
+
+#[test]
+fn bare_numbers_are_hex() {
+    assert_eq!(parse_offset("0"), Ok(0));
+    assert_eq!(parse_offset("1F0"), Ok(0x1F0));
+    assert_eq!(parse_offset("ff"), Ok(0xFF));
+}
+
+#[test]
+fn prefixes_pick_the_base() {
+    assert_eq!(parse_offset("0x10"), Ok(16));
+    assert_eq!(parse_offset("0o10"), Ok(8));
+    assert_eq!(parse_offset("0b10"), Ok(2));
+    assert_eq!(parse_offset("0d10"), Ok(10));
+    assert_eq!(parse_offset("0X1_000"), Ok(0x1000));
+}
+
+#[test]
+fn adds_and_subtracts_left_to_right() {
+    assert_eq!(parse_offset("10 + 0d16"), Ok(0x20));
+    assert_eq!(parse_offset("0x100-0x10+1"), Ok(0xF1));
+}
+
+#[test]
+fn rejects_bad_expressions() {
+    assert!(parse_offset("").is_err());
+    assert!(parse_offset("10 +").is_err());
+    assert!(parse_offset("+ 10").is_err());
+    assert!(parse_offset("0xzz").is_err());
+    assert!(parse_offset("1 2").is_err());
+    assert!(parse_offset("0x").is_err());
+}
+
+#[test]
+fn rejects_results_below_zero() {
+    assert!(parse_offset("1 - 2").is_err());
+    assert_eq!(parse_offset("2 - 2"), Ok(0));
+}
+
+#[test]
+fn rejects_overflow() {
+    assert!(parse_offset("FFFFFFFFFFFFFFFFFF").is_err());
+    assert!(parse_offset(&format!("0d{} + 1", usize::MAX)).is_err());
+}
+
+#[test]
+fn sizes_are_decimal_with_suffixes() {
+    assert_eq!(parse_size("512"), Ok(512));
+    assert_eq!(parse_size("0x200"), Ok(512));
+    assert_eq!(parse_size("4k"), Ok(4096));
+    assert_eq!(parse_size("4K"), Ok(4096));
+    assert_eq!(parse_size("1M"), Ok(1 << 20));
+    assert_eq!(parse_size("0x2m"), Ok(2 << 20));
+    assert_eq!(parse_size("1G"), Ok(1 << 30));
+    assert_eq!(parse_size("0"), Ok(0));
+}
+
+#[test]
+fn rejects_bad_sizes() {
+    assert!(parse_size("").is_err());
+    assert!(parse_size("k").is_err());
+    assert!(parse_size("1F").is_err());
+    assert!(parse_size("4 k").is_err());
+    assert!(parse_size(&format!("{}k", usize::MAX)).is_err());
+}
+
+#[test]
+fn multiplies_and_divides_before_adding() {
+    assert_eq!(parse_offset("10 + 2 * 3"), Ok(0x16));
+    assert_eq!(parse_offset("(10 + 2) * 3"), Ok(0x36));
+    assert_eq!(parse_offset("0d100 / 0d7 % 4"), Ok(2));
+    assert_eq!(parse_offset("0x100 - 0x10 - 0x10"), Ok(0xE0));
+    assert_eq!(parse_offset("((1))"), Ok(1));
+}
+
+#[test]
+fn division_by_zero_and_overflow_are_errors() {
+    assert_eq!(parse_offset("10 / 0"), Err("division by zero".to_string()));
+    assert!(parse_offset("10 % (4 - 4)").is_err());
+    assert!(parse_offset(&format!("0d{} * 2", usize::MAX)).is_err());
+}
+
+#[test]
+fn rejects_unbalanced_parentheses() {
+    assert!(parse_offset("(1 + 2").is_err());
+    assert!(parse_offset("1 + 2)").is_err());
+    assert!(parse_offset("()").is_err());
+    assert!(parse_offset("1 (2)").is_err());
+    assert!(parse_offset("1 & 2").is_err());
+}
+
+#[test]
+fn names_stand_for_the_editor_state() {
+    let variables = Variables {
+        cursor: 0x40,
+        len: 0x1000,
+        selection: Some(0x100..0x180),
+    };
+    let eval = |expr: &str| evaluate(expr, Some(&variables));
+
+    assert_eq!(eval("cursor + 10"), Ok(0x50));
+    assert_eq!(eval("len - CURSOR"), Ok(0xFC0));
+    assert_eq!(eval("sel.start + sel.len * 2"), Ok(0x200));
+    assert_eq!(eval("(len - 1) / 0d16"), Ok(0xFF));
+    assert!(eval("cursr")
+        .unwrap_err()
+        .starts_with("unknown name 'cursr'"));
+
+    let nothing_selected = Variables::default();
+    assert_eq!(
+        evaluate("sel.len", Some(&nothing_selected)),
+        Err("nothing is selected for sel.len".to_string())
+    );
+    // Offsets elsewhere have no editor to look at
+    assert!(parse_offset("cursor").is_err());
+}
@@ -0,0 +1,145 @@
+use hex_it::pager::{pager_command, should_page, write_paged, Paging};
+use std::{
+    cell::{Cell, RefCell},
+    ffi::OsStr,
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// A pager that keeps what it was given, or stops taking it after `limit` bytes like a pager
+/// quit early
+struct FakePager {
+    shown: Rc<RefCell<Vec<u8>>>,
+    limit: usize,
+}
+
+impl Write for FakePager {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let mut shown = self.shown.borrow_mut();
+        if shown.len() >= self.limit {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        let taken = bytes.len().min(self.limit - shown.len());
+        shown.extend_from_slice(&bytes[..taken]);
+        Ok(taken)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// What the fake pager was given, and whether it was waited on
+type Seen = (Rc<RefCell<Vec<u8>>>, Rc<Cell<bool>>);
+
+fn fake(limit: usize) -> (Seen, Paging) {
+    let shown = Rc::new(RefCell::new(Vec::new()));
+    let waited = Rc::new(Cell::new(false));
+    let paging = Paging {
+        input: Box::new(FakePager {
+            shown: shown.clone(),
+            limit,
+        }),
+        wait: Box::new({
+            let waited = waited.clone();
+            move || {
+                waited.set(true);
+                Ok(())
+            }
+        }),
+    };
+    ((shown, waited), paging)
+}
+
+fn dump(out: &mut dyn Write) -> io::Result<()> {
+    for line in 0..100 {
+        writeln!(out, "{:08X}  00 00 00 00", line * 4)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn only_long_output_to_a_terminal_is_paged() {
+    assert!(should_page(true, false, 100, 24));
+    assert!(!should_page(true, false, 24, 24));
+    assert!(!should_page(false, false, 100, 24));
+    assert!(!should_page(true, true, 100, 24));
+}
+
+#[test]
+fn pager_comes_from_the_environment() {
+    assert_eq!(
+        pager_command(None),
+        Some(vec!["less".to_string(), "-R".to_string()])
+    );
+    assert_eq!(
+        pager_command(Some(OsStr::new("most -s"))),
+        Some(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "most -s".to_string()
+        ])
+    );
+    assert_eq!(pager_command(Some(OsStr::new(""))), None);
+    assert_eq!(pager_command(Some(OsStr::new("cat"))), None);
+}
+
+#[test]
+fn output_goes_through_the_pager_which_is_waited_on() {
+    let ((shown, waited), paging) = fake(usize::MAX);
+    let command = ["less".to_string()];
+    let mut direct = Vec::new();
+
+    let paged = write_paged(Some(&command), |_| Ok(paging), &mut direct, dump).unwrap();
+
+    assert!(paged);
+    assert!(waited.get());
+    assert!(direct.is_empty());
+    assert_eq!(shown.borrow().len(), 100 * 22);
+}
+
+#[test]
+fn output_goes_straight_out_when_the_pager_cant_start() {
+    let command = ["no-such-pager".to_string()];
+    let mut direct = Vec::new();
+
+    let paged = write_paged(
+        Some(&command),
+        |_| Err(io::ErrorKind::NotFound.into()),
+        &mut direct,
+        dump,
+    )
+    .unwrap();
+
+    assert!(!paged);
+    assert_eq!(direct.len(), 100 * 22);
+
+    // No command at all, like --no-pager, never starts one
+    let mut direct = Vec::new();
+    let paged = write_paged(None, |_| panic!("no pager wanted"), &mut direct, dump).unwrap();
+    assert!(!paged);
+    assert_eq!(direct.len(), 100 * 22);
+}
+
+#[test]
+fn quitting_the_pager_early_is_not_an_error() {
+    let ((shown, waited), paging) = fake(50);
+    let command = ["less".to_string()];
+
+    let result = write_paged(Some(&command), |_| Ok(paging), &mut Vec::new(), dump);
+
+    assert!(result.unwrap());
+    assert!(waited.get());
+    assert_eq!(shown.borrow().len(), 50);
+
+    // Other write errors still are
+    let ((_, waited), paging) = fake(usize::MAX);
+    let result = write_paged(
+        Some(&command),
+        |_| Ok(paging),
+        &mut Vec::new(),
+        |_| Err(io::Error::other("disk on fire")),
+    );
+    assert!(result.is_err());
+    assert!(waited.get());
+}
@@ -0,0 +1,102 @@
+use hex_it::{
+    patchfile::{check_bounds, create, parse, Edit},
+    Document,
+};
+
+const BMP: &[u8] = include_bytes!("fixtures/tiny.bmp");
+const PATCH: &str = include_str!("fixtures/tiny.patch");
+
+/// Applies edits to a copy of the bytes like `patchfile apply` does
+fn apply(bytes: &[u8], edits: &[Edit]) -> Vec<u8> {
+    let mut document = Document::new(bytes.to_vec());
+    check_bounds(edits, document.len()).unwrap();
+    for edit in edits {
+        document.overwrite(edit.offset, &edit.bytes).unwrap();
+    }
+    document.bytes().to_vec()
+}
+
+#[test]
+fn reads_the_fixture() {
+    let edits = parse(PATCH).unwrap();
+    assert_eq!(
+        edits,
+        [
+            Edit {
+                line: 4,
+                offset: 0x12,
+                bytes: vec![3, 0, 0, 0],
+            },
+            Edit {
+                line: 5,
+                offset: 0x36,
+                bytes: vec![0xFF, 0, 0xFF],
+            },
+        ]
+    );
+
+    let patched = apply(BMP, &edits);
+    assert_eq!(patched[0x12], 3);
+    assert_eq!(patched[0x36..0x39], [0xFF, 0, 0xFF]);
+    assert_eq!(patched[..0x12], BMP[..0x12]);
+}
+
+#[test]
+fn created_patches_read_back() {
+    let mut document = Document::new(BMP.to_vec());
+    document.overwrite(2, &[0x47]).unwrap();
+    document.overwrite(0x36, &[1, 2, 3, 4]).unwrap();
+    // Written back to what it was, so not a change
+    document.overwrite(0x40, &[0]).unwrap();
+
+    let text = create(&document.modifications()).unwrap();
+    assert_eq!(
+        text,
+        "# hex_it patch, 2 edits\n# was 46\n2: 47\n# was 00 00 FF 00\n36: 01 02 03 04\n"
+    );
+    assert_eq!(apply(BMP, &parse(&text).unwrap()), document.bytes());
+
+    assert_eq!(create(&[]).unwrap(), "# hex_it patch, 0 edits\n");
+}
+
+#[test]
+fn inserts_and_deletes_cant_be_patched() {
+    let mut document = Document::new(BMP.to_vec());
+    document.insert(0x10, &[0xAA]).unwrap();
+
+    let error = create(&document.modifications()).unwrap_err();
+    assert!(error.contains("at 10"), "{}", error);
+}
+
+#[test]
+fn any_wrong_line_fails_the_whole_file() {
+    let mut text = PATCH.to_string();
+    text.push_str("40: 0G\n41 00\n");
+
+    assert_eq!(
+        parse(&text).unwrap_err(),
+        "line 6 '40: 0G': '0G' isn't hex bytes, and 1 more line is wrong"
+    );
+    assert!(parse("nope: 00")
+        .unwrap_err()
+        .starts_with("line 1 'nope: 00': "));
+    assert!(parse("10:").unwrap_err().contains("no bytes"));
+    assert_eq!(parse("# only a comment\n\n   \n"), Ok(Vec::new()));
+}
+
+#[test]
+fn edits_past_the_end_fail_the_bounds_check() {
+    let edits = parse("0: 00\n10: 01 02\n").unwrap();
+    assert_eq!(check_bounds(&edits, 0x12), Ok(()));
+    assert_eq!(
+        check_bounds(&edits, 0x11),
+        Err("line 2: 2 bytes at 10 run past the end (11 bytes)".to_string())
+    );
+
+    let huge = [Edit {
+        line: 1,
+        offset: usize::MAX,
+        bytes: vec![0],
+    }];
+    assert!(check_bounds(&huge, usize::MAX).is_err());
+}
@@ -0,0 +1,46 @@
+use hex_it::progress::{rate, Progress};
+use std::time::{Duration, Instant};
+
+#[test]
+fn status_has_percent_speed_and_time() {
+    let mut progress = Progress::new("hash sha256", 4 << 20);
+    let two_seconds = Duration::from_secs(2);
+    assert_eq!(
+        progress.status_at(two_seconds),
+        "hash sha256: 0%, 2.0s. Esc to stop"
+    );
+
+    // 2 MiB in 2 seconds
+    progress.update(2 << 20);
+    assert_eq!(progress.percent(), 50);
+    assert_eq!(
+        progress.status_at(two_seconds),
+        "hash sha256: 50%, 1.0 MiB/s, 2.0s. Esc to stop"
+    );
+    assert_eq!(
+        progress.status_at(Duration::ZERO),
+        "hash sha256: 50%, 0.0s. Esc to stop"
+    );
+    assert!(progress.status().starts_with("hash sha256: 50%"));
+    assert_eq!(progress.cancelled(), "hash sha256 cancelled at 50%");
+
+    // Past the end counts as done
+    progress.update(5 << 20);
+    assert_eq!(progress.percent(), 100);
+    assert_eq!(Progress::new("empty", 0).percent(), 100);
+}
+
+#[test]
+fn elapsed_time_counts_from_the_start() {
+    let started = Instant::now() - Duration::from_secs(2);
+    let progress = Progress::since("hash sha256", 4 << 20, started);
+    assert!(progress.elapsed() >= Duration::from_secs(2));
+}
+
+#[test]
+fn rates_pick_their_unit() {
+    assert_eq!(rate(0.0), "0.0 B/s");
+    assert_eq!(rate(1536.0), "1.5 KiB/s");
+    assert_eq!(rate(3.0 * 1024.0 * 1024.0 * 1024.0), "3.0 GiB/s");
+    assert_eq!(rate(5e13), "46566.1 GiB/s");
+}
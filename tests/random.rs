@@ -0,0 +1,35 @@
+use hex_it::random::{system_seed, SplitMix64};
+
+fn bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    SplitMix64::new(seed).fill(&mut bytes);
+    bytes
+}
+
+#[test]
+fn a_seed_always_gives_the_same_bytes() {
+    // The reference SplitMix64 outputs for seed 0, these must never change
+    let mut random = SplitMix64::new(0);
+    assert_eq!(random.next_u64(), 0xE220A8397B1DCDAF);
+    assert_eq!(random.next_u64(), 0x6E789E6AA1B965F4);
+
+    assert_eq!(
+        bytes(0, 10),
+        [0xAF, 0xCD, 0x1D, 0x7B, 0x39, 0xA8, 0x20, 0xE2, 0xF4, 0x65]
+    );
+    assert_eq!(bytes(42, 1000), bytes(42, 1000));
+    assert_ne!(bytes(42, 16), bytes(43, 16));
+}
+
+#[test]
+fn shorter_fills_are_the_start_of_longer_ones() {
+    let long = bytes(7, 100);
+    for len in [0, 1, 7, 8, 9, 63] {
+        assert_eq!(bytes(7, len), long[..len]);
+    }
+}
+
+#[test]
+fn system_seeds_differ() {
+    assert_ne!(system_seed(), system_seed());
+}
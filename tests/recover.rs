@@ -0,0 +1,129 @@
+use hex_it::{
+    document::{Document, Modification},
+    recover::{newer_recovery, recovery_path, Recovery},
+};
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Edits of every kind: an overwrite, an insert and a delete
+fn edited(bytes: &[u8]) -> Document {
+    let mut document = Document::new(bytes.to_vec());
+    document.overwrite(1, b"XY").unwrap();
+    document.insert(6, b"new").unwrap();
+    document.delete(12..14).unwrap();
+    document
+}
+
+#[test]
+fn recovery_files_sit_next_to_the_file() {
+    assert_eq!(
+        recovery_path(Path::new("dir/firmware.bin")),
+        PathBuf::from("dir/firmware.bin.hexit-recover")
+    );
+}
+
+#[test]
+fn recovery_puts_every_kind_of_edit_back() {
+    let original = b"0123456789abcdef";
+    let document = edited(original);
+    let recovery = Recovery::of(&document);
+    assert_eq!(recovery.size, original.len());
+
+    let read = Recovery::parse(&recovery.contents()).unwrap();
+    assert_eq!(read, recovery);
+
+    let mut restored = Document::new(original.to_vec());
+    assert_eq!(read.apply(&mut restored), Ok(3));
+    assert_eq!(restored.bytes(), document.bytes());
+    assert!(restored.is_dirty());
+
+    // One undo step takes all of it back
+    restored.undo().unwrap();
+    assert_eq!(restored.bytes(), original);
+}
+
+#[test]
+fn the_file_looks_like_the_format_says() {
+    let mut document = Document::new(vec![0; 0x10]);
+    document.overwrite(4, b"\x7f").unwrap();
+    document.insert(8, b"\xde\xad").unwrap();
+
+    assert_eq!(
+        Recovery::of(&document).contents(),
+        "# hex_it recovery of 10 bytes\n4: 00 > 7F\n8:  > DE AD\n"
+    );
+}
+
+#[test]
+fn recovery_refuses_a_file_that_changed() {
+    let original = b"0123456789abcdef";
+    let recovery = Recovery::of(&edited(original));
+
+    let mut shorter = Document::new(original[1..].to_vec());
+    let error = recovery.apply(&mut shorter).unwrap_err();
+    assert!(error.contains("it is for 10 bytes"), "{error}");
+
+    let mut changed = Document::new(b"0Z23456789abcdef".to_vec());
+    let error = recovery.apply(&mut changed).unwrap_err();
+    assert!(error.contains("changed at"), "{error}");
+    assert_eq!(changed.bytes(), b"0Z23456789abcdef");
+    assert!(!changed.is_dirty());
+}
+
+#[test]
+fn damaged_recovery_files_are_refused() {
+    assert!(Recovery::parse("").is_err());
+    assert!(Recovery::parse("1C4: 00 > 7F\n").is_err());
+
+    let error =
+        Recovery::parse("# hex_it recovery of 10 bytes\n4: 00 > 7F\n8: 0 > 1\n").unwrap_err();
+    assert!(error.starts_with("line 3 "), "{error}");
+
+    let error = Recovery::parse("# hex_it recovery of 10 bytes\n4:  > \n").unwrap_err();
+    assert!(error.contains("nothing changed"), "{error}");
+
+    assert_eq!(
+        Recovery::parse("# hex_it recovery of 10 bytes\n\n# a comment\n4: 00 > 7F\n").unwrap(),
+        Recovery {
+            size: 0x10,
+            changes: vec![Modification {
+                offset: 4,
+                original: vec![0],
+                current: vec![0x7f],
+            }],
+        }
+    );
+}
+
+#[test]
+fn only_recovery_files_newer_than_the_file_are_offered() {
+    let path = std::env::temp_dir().join(format!("hex_it-{}-recover", std::process::id()));
+    let recovery = recovery_path(&path);
+    fs::write(&path, b"file").unwrap();
+    assert_eq!(newer_recovery(&path), None);
+
+    fs::write(&recovery, b"# hex_it recovery of 4 bytes\n").unwrap();
+    let now = SystemTime::now();
+    let set = |path: &Path, time: SystemTime| {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap()
+    };
+
+    set(&path, now - Duration::from_secs(60));
+    set(&recovery, now);
+    assert_eq!(newer_recovery(&path), Some(recovery.clone()));
+
+    // Saved after the recovery file was written, it has nothing to add
+    set(&path, now + Duration::from_secs(60));
+    assert_eq!(newer_recovery(&path), None);
+
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(&recovery);
+}
@@ -0,0 +1,30 @@
+use hex_it::registers::{parse_register, Registers, UNNAMED};
+
+#[test]
+fn registers_are_single_letters() {
+    assert_eq!(parse_register("a"), Some('a'));
+    assert_eq!(parse_register("Q"), Some('q'));
+    assert_eq!(parse_register("0A"), None);
+    assert_eq!(parse_register("ab"), None);
+    assert_eq!(parse_register("1"), None);
+    assert_eq!(parse_register(""), None);
+}
+
+#[test]
+fn filled_registers_list_unnamed_first() {
+    let mut registers = Registers::default();
+    assert_eq!(registers.get('a'), None);
+
+    registers.set('b', vec![2]);
+    registers.set('a', vec![1, 1]);
+    registers.set('c', Vec::new());
+    registers.set(UNNAMED, vec![0]);
+
+    let filled: Vec<(char, &[u8])> = registers.filled().collect();
+    assert_eq!(
+        filled,
+        [(UNNAMED, &[0][..]), ('a', &[1, 1][..]), ('b', &[2][..])]
+    );
+    // Yanking nothing leaves a register as good as empty
+    assert_eq!(registers.get('c'), None);
+}
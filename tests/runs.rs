@@ -0,0 +1,77 @@
+use hex_it::runs::{longest_runs, Run, RunCounter};
+
+#[test]
+fn longest_runs_come_first() {
+    let mut bytes = vec![1, 2, 3];
+    bytes.extend([0; 5]);
+    bytes.extend([0xFF, 0xFF, 7]);
+    bytes.extend([0; 9]);
+    bytes.push(0);
+    bytes.extend([0xFF; 5]);
+
+    let runs = longest_runs(&bytes, Some(0), 16);
+    assert_eq!(
+        runs,
+        [
+            Run {
+                offset: 11,
+                len: 10,
+                byte: 0
+            },
+            Run {
+                offset: 3,
+                len: 5,
+                byte: 0
+            },
+        ]
+    );
+
+    // Any byte, ties in order of offset, single bytes left out
+    let runs = longest_runs(&bytes, None, 3);
+    let found: Vec<(usize, usize)> = runs.iter().map(|run| (run.offset, run.len)).collect();
+    assert_eq!(found, [(11, 10), (3, 5), (21, 5)]);
+    assert_eq!(longest_runs(b"abcabc", None, 3), []);
+}
+
+#[test]
+fn runs_carry_on_across_chunks() {
+    let mut bytes = vec![0xAA; 1000];
+    bytes[300] = 0;
+    bytes[301] = 0;
+
+    for chunk in [1, 7, 64, 299] {
+        let mut counter = RunCounter::new(None, 2);
+        for piece in bytes.chunks(chunk) {
+            counter.update(piece);
+        }
+        assert_eq!(
+            counter.finish(),
+            longest_runs(&bytes, None, 2),
+            "in chunks of {chunk}"
+        );
+    }
+
+    assert_eq!(
+        longest_runs(&bytes, None, 1),
+        [Run {
+            offset: 302,
+            len: 698,
+            byte: 0xAA
+        }]
+    );
+}
+
+#[test]
+fn a_file_of_one_byte_is_one_run() {
+    let runs = longest_runs(&[0xFF; 4096], Some(0xFF), 16);
+    assert_eq!(
+        runs,
+        [Run {
+            offset: 0,
+            len: 4096,
+            byte: 0xFF
+        }]
+    );
+    assert_eq!(longest_runs(&[0xFF; 4096], Some(0), 16), []);
+    assert_eq!(longest_runs(&[], None, 16), []);
+}
@@ -0,0 +1,113 @@
+use hex_it::{
+    line::InputLine,
+    screen::{column_index, prompt_line, visible_width, Frame, Listing},
+};
+
+fn typed(text: &str) -> InputLine {
+    let mut input = InputLine::default();
+    for c in text.chars() {
+        input.insert(c);
+    }
+    input
+}
+
+#[test]
+fn frames_draw_over_the_last_one_in_a_single_string() {
+    let mut frame = Frame::new(20, 5);
+    frame.line("00000000  41");
+    frame.line("-- NORMAL --");
+    frame.line("Saved");
+    frame.prompt(":", &typed("quit"));
+
+    assert_eq!(
+        frame.as_str(),
+        "\x1b[H\
+         00000000  41\x1b[K\r\n\
+         -- NORMAL --\x1b[K\r\n\
+         Saved\x1b[K\r\n\
+         \x1b[J\x1b[5;0H\x1b[2K:quit\x1b[5;6H"
+    );
+}
+
+#[test]
+fn long_input_scrolls_to_keep_the_cursor_visible() {
+    let mut input = typed("abcdefghij");
+    assert_eq!(
+        prompt_line(":", &input, 6, 3),
+        "\x1b[3;0H\x1b[2K:ghij\x1b[3;6H"
+    );
+
+    input.home();
+    assert_eq!(
+        prompt_line(":", &input, 6, 3),
+        "\x1b[3;0H\x1b[2K:abcde\x1b[3;2H"
+    );
+}
+
+#[test]
+fn colors_take_no_columns() {
+    assert_eq!(visible_width("plain"), 5);
+    assert_eq!(visible_width("\x1b[2m0010\x1b[0m  \x1b[1;31mAB\x1b[0m"), 8);
+    assert_eq!(visible_width("é\x1b[0m"), 1);
+    assert_eq!(visible_width(""), 0);
+}
+
+#[test]
+fn columns_are_found_past_colors() {
+    let line = "\x1b[2m01\x1b[0m \x1b[36m41\x1b[0m";
+
+    assert_eq!(column_index(line, 0), 4);
+    assert_eq!(
+        &line[column_index(line, 3)..column_index(line, 5)],
+        "41\x1b[0m"
+    );
+    assert_eq!(column_index(line, 9), line.len());
+    assert_eq!(column_index("abc", 1), 1);
+}
+
+#[test]
+fn listings_page_through_what_does_not_fit() {
+    let lines = (1..=5)
+        .map(|n| format!("line {n}"))
+        .collect::<Vec<String>>();
+    let mut listing = Listing::new(lines);
+
+    assert_eq!(listing.page(2), ["line 1", "line 2"]);
+    assert!(listing.has_more(2));
+    assert!(listing.next_page(2));
+    assert_eq!(listing.page(2), ["line 3", "line 4"]);
+    assert!(listing.next_page(2));
+    assert_eq!(listing.page(2), ["line 5"]);
+    assert!(!listing.has_more(2));
+    assert!(!listing.next_page(2));
+}
+
+#[test]
+fn listing_lines_leave_the_last_column_free() {
+    assert_eq!(Listing::content_width(80), 79);
+    assert_eq!(Listing::content_width(1), 1);
+    assert_eq!(Listing::content_width(0), 1);
+}
+
+#[test]
+fn listings_draw_a_page_and_say_how_to_go_on() {
+    let lines = vec![
+        "Commands:".to_string(),
+        "help - a rather long line".to_string(),
+    ];
+
+    let mut frame = Frame::new(10, 3);
+    Listing::new(lines.clone()).draw(&mut frame);
+    // The last column stays free
+    assert!(frame.as_str().contains("help - a \x1b[K"));
+    assert!(!frame.as_str().contains("long"));
+    assert!(frame.as_str().contains("Press any key to continue"));
+
+    let mut frame = Frame::new(80, 2);
+    Listing::new(lines).draw(&mut frame);
+    assert!(frame.as_str().contains("Commands:"));
+    assert!(!frame.as_str().contains("help -"));
+    assert!(frame
+        .as_str()
+        .contains("-- Lines 1-1 of 2, any key for more, Esc to close --"));
+}
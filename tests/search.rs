@@ -0,0 +1,356 @@
+use hex_it::{
+    search::{
+        find_other, float_bytes, rfind_other, ChunkedSearch, Finder, FloatScan, Matcher, Pattern,
+    },
+    template::Endian,
+};
+
+/// Every match found the slow way, overlapping ones included
+fn naive(haystack: &[u8], needle: &[u8]) -> Vec<usize> {
+    haystack
+        .windows(needle.len())
+        .enumerate()
+        .filter(|(_, window)| *window == needle)
+        .map(|(position, _)| position)
+        .collect()
+}
+
+/// Searches in chunks of a fixed size
+fn chunked(haystack: &[u8], needle: &[u8], chunk: usize) -> Vec<usize> {
+    let mut search = ChunkedSearch::new(Finder::new(needle), usize::MAX);
+    for piece in haystack.chunks(chunk) {
+        search.feed(piece);
+    }
+    assert_eq!(search.searched(), haystack.len());
+    search.into_matches()
+}
+
+fn sample() -> Vec<u8> {
+    (0..5000u32).map(|i| (i * 7 % 251) as u8).collect()
+}
+
+#[test]
+fn needles_at_the_start_and_the_end() {
+    let haystack = sample();
+    let finder = Finder::new(&haystack[..6]);
+    assert_eq!(finder.find(&haystack), Some(0));
+
+    // The sample repeats every 251 bytes, so the last needle is found there too
+    let end = &haystack[haystack.len() - 9..];
+    let matches = Finder::new(end).find_all(&haystack);
+    assert_eq!(matches.last(), Some(&(haystack.len() - 9)));
+    assert_eq!(matches, naive(&haystack, end));
+}
+
+#[test]
+fn absent_needles_and_edge_cases() {
+    let haystack = sample();
+
+    assert_eq!(Finder::new(b"\xFF\xFF\xFF").find(&haystack), None);
+    assert_eq!(Finder::new(b"").find(&haystack), None);
+    assert_eq!(
+        Finder::new(b"longer than the haystack").find(b"short"),
+        None
+    );
+    assert_eq!(Finder::new(b"aa").find_all(b"aaaa"), vec![0, 1, 2]);
+    assert_eq!(Finder::new(b"a").find_from(b"abca", 1), Some(3));
+}
+
+#[test]
+fn matches_agree_with_a_naive_search() {
+    let haystack = sample();
+
+    for needle in [
+        &haystack[100..101],
+        &haystack[10..14],
+        &haystack[1000..1040],
+    ] {
+        assert_eq!(
+            Finder::new(needle).find_all(&haystack),
+            naive(&haystack, needle)
+        );
+    }
+}
+
+#[test]
+fn matches_straddling_chunk_boundaries() {
+    let mut haystack = vec![0u8; 64];
+    haystack.extend_from_slice(b"NEEDLE");
+    haystack.extend_from_slice(&[0; 61]);
+    haystack.extend_from_slice(b"NEEDLE");
+
+    // Every split of the needles, and chunks shorter than the needle
+    for chunk in [1, 2, 3, 5, 16, 65, 67, 128, 1000] {
+        assert_eq!(
+            chunked(&haystack, b"NEEDLE", chunk),
+            vec![64, 131],
+            "chunk {chunk}"
+        );
+    }
+
+    let bytes = sample();
+    let needle = &bytes[4095..4101];
+    assert_eq!(chunked(&bytes, needle, 4096), naive(&bytes, needle));
+    assert_eq!(chunked(b"aaaaaa", b"aa", 1), vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn searches_stop_collecting_at_the_limit() {
+    let mut search = ChunkedSearch::new(Finder::new(b"a"), 3);
+    search.feed(b"aaaaa");
+
+    assert!(search.is_full());
+    assert_eq!(search.matches(), &[0, 1, 2]);
+}
+
+/// Every match of a pattern found the slow way
+fn naive_masked(haystack: &[u8], pattern: &Pattern) -> Vec<usize> {
+    haystack
+        .windows(pattern.values.len())
+        .enumerate()
+        .filter(|(_, window)| {
+            window
+                .iter()
+                .zip(pattern.values.iter().zip(&pattern.masks))
+                .all(|(byte, (value, mask))| byte & mask == *value)
+        })
+        .map(|(position, _)| position)
+        .collect()
+}
+
+#[test]
+fn patterns_parse_hex_bits_and_masks() {
+    assert_eq!(
+        Pattern::parse("1F 0x2a Ax xB xx"),
+        Ok(Pattern {
+            values: vec![0x1F, 0x2A, 0xA0, 0x0B, 0x00],
+            masks: vec![0xFF, 0xFF, 0xF0, 0x0F, 0x00],
+        })
+    );
+    assert_eq!(
+        Pattern::parse("1010xxxx 0b0000000x"),
+        Ok(Pattern {
+            values: vec![0xA0, 0x00],
+            masks: vec![0xF0, 0xFE],
+        })
+    );
+
+    // Bits outside the mask are cleared from the value
+    assert_eq!(
+        Pattern::parse("A5/F0 0x12/0x0F"),
+        Ok(Pattern {
+            values: vec![0xA0, 0x02],
+            masks: vec![0xF0, 0x0F],
+        })
+    );
+}
+
+#[test]
+fn bad_patterns_are_rejected() {
+    for pattern in [
+        "",
+        "   ",
+        "xx",
+        "xx xxxxxxxx",
+        "1",
+        "123",
+        "1G",
+        "0b101",
+        "10102xxx",
+        "A0/",
+        "A0/F00",
+    ] {
+        assert!(Pattern::parse(pattern).is_err(), "{pattern:?}");
+    }
+    assert!(Pattern::parse("1F zz")
+        .unwrap_err()
+        .contains("invalid byte 'zz'"));
+}
+
+#[test]
+fn masked_matches_agree_with_a_naive_search() {
+    let haystack = sample();
+
+    for text in [
+        "1010xxxx",
+        "Ax 1x",
+        "xx 07",
+        "xx xx 0E",
+        "x7 xx 11/F1",
+        "07 xx xx 0E",
+    ] {
+        let pattern = Pattern::parse(text).unwrap();
+        assert_eq!(
+            Finder::masked(&pattern).find_all(&haystack),
+            naive_masked(&haystack, &pattern),
+            "{text}"
+        );
+    }
+}
+
+#[test]
+fn fully_masked_first_byte_finds_every_position() {
+    let haystack = b"\x00\x7F\xFF\x7F\x7F";
+    let pattern = Pattern::parse("xx 7F").unwrap();
+
+    assert_eq!(Finder::masked(&pattern).find_all(haystack), vec![0, 2, 3]);
+    assert_eq!(
+        Finder::masked(&pattern).find_all(haystack),
+        naive_masked(haystack, &pattern)
+    );
+}
+
+#[test]
+fn masked_matches_straddling_chunk_boundaries() {
+    let haystack = sample();
+    let pattern = Pattern::parse("xx xx 0E 1x").unwrap();
+    let expected = naive_masked(&haystack, &pattern);
+    assert!(!expected.is_empty());
+
+    for chunk in [1, 2, 3, 7, 250, 4096] {
+        let mut search = ChunkedSearch::new(Finder::masked(&pattern), usize::MAX);
+        for piece in haystack.chunks(chunk) {
+            search.feed(piece);
+        }
+        assert_eq!(search.into_matches(), expected, "chunks of {chunk}");
+    }
+}
+
+#[test]
+fn hex_patterns_take_wildcard_nibbles() {
+    assert_eq!(
+        Pattern::parse_hex("E8 ?? 4? ?f 8B"),
+        Ok(Pattern {
+            values: vec![0xE8, 0x00, 0x40, 0x0F, 0x8B],
+            masks: vec![0xFF, 0x00, 0xF0, 0x0F, 0xFF],
+        })
+    );
+    assert_eq!(
+        Pattern::parse_hex("DEAD BEEF"),
+        Ok(Pattern::exact(b"\xDE\xAD\xBE\xEF"))
+    );
+}
+
+#[test]
+fn meaningless_hex_patterns_are_rejected() {
+    for pattern in ["", "??", "?? ??  ??", "?", "E8 ?", "E8 G1"] {
+        assert!(Pattern::parse_hex(pattern).is_err(), "{pattern:?}");
+    }
+    assert!(Pattern::parse_hex("?? ??")
+        .unwrap_err()
+        .contains("all wildcards"));
+}
+
+#[test]
+fn wildcards_at_either_end_agree_with_a_naive_search() {
+    let haystack = sample();
+
+    for text in [
+        "?? ?? 0E",
+        "07 ?? ??",
+        "?? 07 ?? 15 ??",
+        "0? ?E",
+        "?7 0E 1?",
+    ] {
+        let pattern = Pattern::parse_hex(text).unwrap();
+        let matches = Finder::masked(&pattern).find_all(&haystack);
+        assert!(!matches.is_empty(), "{text}");
+        assert_eq!(matches, naive_masked(&haystack, &pattern), "{text}");
+    }
+}
+
+fn floats(values: &[f32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+fn scan(value: f64, tolerance: f64) -> FloatScan {
+    FloatScan {
+        width: 4,
+        endian: Endian::Little,
+        value,
+        tolerance,
+    }
+}
+
+#[test]
+fn float_bytes_follow_the_width_and_byte_order() {
+    assert_eq!(
+        float_bytes("1.5", 4, Endian::Little),
+        Ok(vec![0x00, 0x00, 0xC0, 0x3F])
+    );
+    assert_eq!(
+        float_bytes("1.5", 4, Endian::Big),
+        Ok(vec![0x3F, 0xC0, 0x00, 0x00])
+    );
+    assert_eq!(
+        float_bytes("-2", 8, Endian::Big),
+        Ok(vec![0xC0, 0, 0, 0, 0, 0, 0, 0])
+    );
+    assert!(float_bytes("pi", 4, Endian::Little).is_err());
+}
+
+#[test]
+fn floats_match_within_the_tolerance() {
+    let haystack = floats(&[1.23456, 2.0, 1.2346, -1.23456, 1.3]);
+    let close = scan(1.23456, 0.001);
+
+    assert_eq!(close.width(), 4);
+    assert_eq!(close.find_all(&haystack), vec![0, 8]);
+    assert_eq!(scan(-1.23456, 0.0001).find_all(&haystack), vec![12]);
+}
+
+#[test]
+fn nan_finds_every_nan_and_infinity_only_itself() {
+    let mut haystack = floats(&[f32::NAN, 1.0, f32::INFINITY, f32::NEG_INFINITY, 2.0]);
+    // A NaN with other bits than the one Rust makes
+    haystack.extend_from_slice(&[0x01, 0x00, 0x80, 0x7F]);
+
+    assert_eq!(scan(f64::NAN, 0.0).find_all(&haystack), vec![0, 20]);
+    assert_eq!(scan(f64::INFINITY, 1e300).find_all(&haystack), vec![8]);
+    assert!(!scan(1.0, f64::INFINITY).matches(f64::NAN));
+}
+
+#[test]
+fn aligned_searches_skip_offsets_between_floats() {
+    // 1.0 in the middle of two floats, then at a float boundary
+    let mut haystack = vec![0u8; 2];
+    haystack.extend(floats(&[1.0, 0.0, 1.0]));
+    haystack.extend_from_slice(&[0; 2]);
+
+    let run = |mut search: ChunkedSearch, haystack: &[u8]| {
+        for piece in haystack.chunks(3) {
+            search.feed(piece);
+        }
+        search.into_matches()
+    };
+
+    let exact = || ChunkedSearch::new(Finder::new(&1.0f32.to_le_bytes()), usize::MAX);
+    assert_eq!(run(exact(), &haystack), vec![2, 10]);
+    assert_eq!(run(exact().aligned(4), &haystack), Vec::<usize>::new());
+
+    let close = || ChunkedSearch::new(scan(1.0, 0.5), usize::MAX);
+    assert_eq!(run(close(), &haystack), vec![2, 10]);
+    assert_eq!(run(close().aligned(4), &haystack[2..]), vec![0, 8]);
+}
+
+#[test]
+fn runs_are_skipped_a_word_at_a_time() {
+    let mut bytes = vec![0u8; 100];
+    bytes[37] = 1;
+    bytes[90] = 2;
+
+    assert_eq!(find_other(&bytes, 0), Some(37));
+    assert_eq!(find_other(&bytes[38..], 0), Some(52));
+    assert_eq!(rfind_other(&bytes, 0), Some(90));
+    assert_eq!(rfind_other(&bytes[..90], 0), Some(37));
+
+    // In the tail that doesn't fill a word, and nothing but the run
+    assert_eq!(find_other(&[7, 7, 7, 7, 7, 7, 7, 7, 7, 3], 7), Some(9));
+    assert_eq!(rfind_other(&[3, 7, 7, 7, 7, 7, 7, 7, 7, 7], 7), Some(0));
+    assert_eq!(find_other(&[0xFF; 1000], 0xFF), None);
+    assert_eq!(rfind_other(&[0xFF; 1000], 0xFF), None);
+    assert_eq!(find_other(&[], 0), None);
+}
@@ -0,0 +1,49 @@
+use hex_it::searches::{SearchEntry, SearchHistory, SearchKind, MAX_SEARCHES};
+
+#[test]
+fn entries_know_what_they_looked_for() {
+    let kind = |command: &str| SearchEntry::new(command).map(|entry| entry.kind);
+
+    assert_eq!(kind("find DE AD ?? EF"), Some(SearchKind::Hex));
+    assert_eq!(kind("find \"Error\""), Some(SearchKind::Text));
+    assert_eq!(kind("find -u16be \"Error\""), Some(SearchKind::Utf16));
+    assert_eq!(kind("find -f32 3.14 ~0.01 -be"), Some(SearchKind::Float));
+    assert_eq!(kind("FINDB 1010xxxx 1F"), Some(SearchKind::Bits));
+    assert_eq!(kind("find"), None);
+    assert_eq!(kind("goto 10"), None);
+    assert_eq!(kind("find \"open"), None);
+}
+
+#[test]
+fn history_round_trips_and_skips_damage() {
+    let mut history = SearchHistory::default();
+    for command in ["find 00 FF", "find -f64 2.5 -unaligned", "find \"a\\tb\""] {
+        history.record(SearchEntry::new(command).unwrap());
+    }
+
+    let contents = history.contents();
+    assert!(contents.starts_with("hex find 00 FF\n"));
+    assert_eq!(SearchHistory::parse(&contents), history);
+
+    let damaged = format!("garbage\nhex goto 5\ntext find 00\n{contents}float \u{0}");
+    assert_eq!(SearchHistory::parse(&damaged), history);
+}
+
+#[test]
+fn repeats_move_up_and_the_oldest_go() {
+    let mut history = SearchHistory::default();
+    for n in 0..MAX_SEARCHES + 5 {
+        history.record(SearchEntry::new(&format!("find {n:02X}")).unwrap());
+    }
+    history.record(SearchEntry::new("find 10").unwrap());
+
+    assert_eq!(history.len(), MAX_SEARCHES);
+    assert_eq!(history.last().unwrap().command, "find 10");
+    let commands: Vec<&str> = history
+        .latest_first()
+        .map(|entry| entry.command.as_str())
+        .collect();
+    assert_eq!(commands[1], format!("find {:02X}", MAX_SEARCHES + 4));
+    assert_eq!(*commands.last().unwrap(), "find 05");
+    assert_eq!(commands.iter().filter(|c| **c == "find 10").count(), 1);
+}
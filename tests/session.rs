@@ -0,0 +1,62 @@
+use hex_it::session::{BufferSession, Session, SESSION_VERSION};
+use std::path::PathBuf;
+
+#[test]
+fn sessions_round_trip() {
+    let session = Session {
+        buffers: vec![
+            BufferSession::new(PathBuf::from("/tmp/first file.bin"), 0x2000),
+            BufferSession {
+                path: PathBuf::from("/tmp/second.bin"),
+                size: 0x40,
+                cursor: 0x31,
+                top_line: 2,
+                selection: Some(0x20),
+            },
+        ],
+        current: 1,
+        colors: false,
+        ..Session::default()
+    };
+
+    let (parsed, errors) = Session::parse(&session.to_string()).unwrap();
+    assert_eq!(parsed, session);
+    assert!(errors.is_empty());
+    assert_eq!(parsed.version, SESSION_VERSION);
+}
+
+#[test]
+fn unknown_settings_are_skipped() {
+    let (session, errors) = Session::parse(
+        "version 2\nfile /tmp/a.bin\nbookmark 0x10 header\ncursor 0x8\nencoding ebcdic\n",
+    )
+    .unwrap();
+
+    assert!(errors.is_empty());
+    assert_eq!(session.version, 2);
+    assert_eq!(session.buffers[0].cursor, 8);
+}
+
+#[test]
+fn bad_lines_are_reported_and_the_rest_kept() {
+    let (session, errors) = Session::parse(
+        "version 1\ncursor 0x10\nfile /tmp/a.bin\ncursor nope\ntop-line 0x3\ncurrent 0\n",
+    )
+    .unwrap();
+
+    assert_eq!(session.buffers.len(), 1);
+    assert_eq!(session.buffers[0].top_line, 3);
+    assert_eq!(errors.len(), 3);
+    assert_eq!(
+        errors[0],
+        "session line 2 'cursor 0x10': comes before any file"
+    );
+}
+
+#[test]
+fn a_session_needs_a_version() {
+    assert_eq!(
+        Session::parse("file /tmp/a.bin\n").unwrap_err(),
+        "not a hex_it session, it has no version"
+    );
+}
@@ -0,0 +1,48 @@
+use hex_it::stride::Stride;
+
+#[test]
+fn fields_repeat_every_record_from_the_base() {
+    let stride = Stride::new(16, 3, 0).unwrap();
+    assert!(stride.is_field(3));
+    assert!(stride.is_field(0x13));
+    assert!(!stride.is_field(4));
+    assert!(stride.is_record_start(0x20));
+
+    let stride = Stride::new(24, 5, 0x40).unwrap();
+    assert_eq!(stride.position(0x3F), None);
+    assert_eq!(stride.position(0x40), Some(0));
+    assert!(stride.is_field(0x40 + 24 + 5));
+    // Would be a field if records started at 0
+    assert!(!stride.is_field(53));
+}
+
+#[test]
+fn fields_must_be_inside_a_record() {
+    assert_eq!(
+        Stride::new(4, 4, 0),
+        Err("Field offset 4 is past the end of a 4 byte record".to_string())
+    );
+    assert!(Stride::new(0, 0, 0).is_err());
+    assert!(Stride::new(1, 0, 0).unwrap().is_field(12345));
+}
+
+#[test]
+fn whole_records_start_at_the_base() {
+    let stride = Stride::new(16, 3, 0x20).unwrap();
+    // 0x20 to 0x75 is 5 records and 6 bytes of another
+    assert_eq!(stride.whole_records(0x76), (5, 6));
+    assert_eq!(stride.whole_records(0x10), (0, 0));
+    assert_eq!(stride.field_offset(0), 0x23);
+    assert_eq!(stride.field_offset(4), 0x63);
+    assert!(stride.is_field(stride.field_offset(4)));
+}
+
+#[test]
+fn multi_byte_fields_must_fit() {
+    let stride = Stride::new(8, 4, 0).unwrap();
+    assert_eq!(stride.fits(4), Ok(()));
+    assert_eq!(
+        stride.fits(5),
+        Err("5 bytes at 4 don't fit in a record of 8 bytes".to_string())
+    );
+}
@@ -0,0 +1,112 @@
+use hex_it::{
+    strings::{find_strings, unit_start, utf16_bytes, Encoding, Found},
+    template::Endian,
+};
+
+const LITTLE: Encoding = Encoding::Utf16(Endian::Little);
+const BIG: Encoding = Encoding::Utf16(Endian::Big);
+
+/// The found runs as (offset, length, text) to compare in one go
+fn summary(found: &[Found]) -> Vec<(usize, usize, &str)> {
+    found
+        .iter()
+        .map(|text| (text.offset, text.length, text.text.as_str()))
+        .collect()
+}
+
+#[test]
+fn encodes_utf16_with_surrogate_pairs() {
+    assert_eq!(utf16_bytes("Error", Endian::Little), b"E\0r\0r\0o\0r\0");
+    assert_eq!(utf16_bytes("Error", Endian::Big), b"\0E\0r\0r\0o\0r");
+    assert_eq!(utf16_bytes("é", Endian::Little), [0xE9, 0]);
+    assert_eq!(
+        utf16_bytes("a\u{1F600}", Endian::Little),
+        [0x61, 0, 0x3D, 0xD8, 0, 0xDE]
+    );
+    assert_eq!(utf16_bytes("\u{1F600}", Endian::Big), [0xD8, 0x3D, 0xDE, 0]);
+}
+
+#[test]
+fn typed_text_encodes_in_the_encoding() {
+    assert_eq!(Encoding::Ascii.encode(b"a\xFF"), Ok(b"a\xFF".to_vec()));
+    assert_eq!(LITTLE.encode("é!".as_bytes()), Ok(b"\xE9\0!\0".to_vec()));
+    assert_eq!(BIG.encode(b"ok\0"), Ok(b"\0o\0k\0\0".to_vec()));
+    assert_eq!(
+        LITTLE.encode(b"a\xFF"),
+        Err("byte 1 isn't text, only text can be written as UTF-16".to_string())
+    );
+}
+
+#[test]
+fn units_start_at_even_or_odd_offsets() {
+    assert_eq!(unit_start(4, false), 4);
+    assert_eq!(unit_start(5, false), 4);
+    assert_eq!(unit_start(5, true), 5);
+    assert_eq!(unit_start(6, true), 5);
+    assert_eq!(unit_start(0, true), 1);
+}
+
+#[test]
+fn flags_pick_the_encoding() {
+    assert_eq!(Encoding::from_flag("-u16"), Some(LITTLE));
+    assert_eq!(Encoding::from_flag("-u16be"), Some(BIG));
+    assert_eq!(Encoding::from_flag("-n"), None);
+    assert_eq!(BIG.flag(), "-u16be");
+}
+
+#[test]
+fn finds_ascii_runs() {
+    let bytes = b"\x01\x02hello\0abc\0\xFFworld, again\tand\x7F";
+
+    let found = find_strings(bytes, Encoding::Ascii, 4, 100);
+    assert_eq!(
+        summary(&found),
+        [(2, 5, "hello"), (13, 16, "world, again\tand")]
+    );
+    assert_eq!(find_strings(bytes, Encoding::Ascii, 3, 100).len(), 3);
+    assert_eq!(find_strings(bytes, Encoding::Ascii, 4, 1).len(), 1);
+}
+
+#[test]
+fn finds_what_find_u16_looks_for() {
+    for (endian, encoding) in [(Endian::Little, LITTLE), (Endian::Big, BIG)] {
+        // At an odd offset, after bytes that aren't text in either byte order
+        let mut bytes = vec![0xFF, 0xFE, 0xFF];
+        bytes.extend(utf16_bytes("Error: café", endian));
+        bytes.extend([0xFF, 0xFE, 0xFF, 0xFE]);
+
+        let found = find_strings(&bytes, encoding, 4, 100);
+        assert_eq!(summary(&found), [(3, 22, "Error: café")]);
+    }
+}
+
+#[test]
+fn ascii_text_isnt_utf16_text() {
+    let bytes = b"plain ASCII text that goes on for a while";
+    assert_eq!(find_strings(bytes, LITTLE, 4, 100), []);
+    assert_eq!(find_strings(bytes, BIG, 4, 100), []);
+}
+
+#[test]
+fn surrogate_pairs_take_four_bytes() {
+    // Characters above U+FFFF are left out of runs, but the run after one starts where it ends
+    let mut bytes = utf16_bytes("Smile\u{1F600}again", Endian::Little);
+    // A lone surrogate
+    bytes.extend([0x3D, 0xD8]);
+    bytes.extend(utf16_bytes("more", Endian::Little));
+
+    let found = find_strings(&bytes, LITTLE, 4, 100);
+    assert_eq!(
+        summary(&found),
+        [(0, 10, "Smile"), (14, 10, "again"), (26, 8, "more")]
+    );
+}
+
+#[test]
+fn short_runs_are_left_out() {
+    let bytes = utf16_bytes("abc", Endian::Little);
+    assert_eq!(find_strings(&bytes, LITTLE, 4, 100), []);
+    assert_eq!(find_strings(&bytes, LITTLE, 3, 100).len(), 1);
+    assert_eq!(find_strings(b"", LITTLE, 1, 100), []);
+    assert_eq!(find_strings(b"a", LITTLE, 1, 100), []);
+}
@@ -0,0 +1,48 @@
+use hex_it::suggest::{did_you_mean, levenshtein_distance, suggestions};
+
+#[test]
+fn known_distances() {
+    for (a, b, distance) in [
+        ("", "", 0),
+        ("", "abc", 3),
+        ("save", "save", 0),
+        ("kitten", "sitting", 3),
+        ("flaw", "lawn", 2),
+        ("ab", "ba", 1),
+        ("abcdef", "abdcef", 1),
+        ("qiut", "quit", 1),
+        // Restricted to one edit per char, the swapped pair can't also get an insertion
+        ("ca", "abc", 3),
+        ("café", "cafe", 1),
+        ("buffer", "buffers", 1),
+    ] {
+        assert_eq!(levenshtein_distance(a, b), distance, "{a} -> {b}");
+        assert_eq!(levenshtein_distance(b, a), distance, "{b} -> {a}");
+    }
+}
+
+#[test]
+fn suggests_up_to_three_close_candidates() {
+    let commands = ["save", "save!", "quit", "buffer", "buffers", "session"];
+
+    assert_eq!(suggestions("sav", commands), ["save"]);
+    assert_eq!(suggestions("saev", commands), ["save", "save!"]);
+    assert_eq!(suggestions("bufer", commands), ["buffer", "buffers"]);
+    assert_eq!(suggestions("QUIT", commands), ["quit"]);
+    assert!(suggestions("xyzzy", commands).is_empty());
+    assert_eq!(
+        suggestions("a", ["b", "c", "d", "e"]),
+        ["b", "c", "d"],
+        "only three make it"
+    );
+}
+
+#[test]
+fn describes_the_suggestions() {
+    assert_eq!(did_you_mean(&[]), None);
+    assert_eq!(did_you_mean(&["quit"]).unwrap(), "Did you mean 'quit'?");
+    assert_eq!(
+        did_you_mean(&["a", "b", "c"]).unwrap(),
+        "Did you mean 'a', 'b' or 'c'?"
+    );
+}
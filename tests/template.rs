@@ -0,0 +1,316 @@
+use hex_it::template::{Endian, FieldType, Template, Value};
+
+fn values(template: &Template, data: &[u8]) -> Vec<(String, usize, usize, Option<Value>)> {
+    template
+        .decode(data)
+        .into_iter()
+        .map(|decoded| {
+            (
+                decoded.field.name.clone(),
+                decoded.range.start,
+                decoded.range.len(),
+                decoded.value,
+            )
+        })
+        .collect()
+}
+
+fn field(name: &str, kind: &str) -> String {
+    format!("[[field]]\nname = \"{}\"\ntype = \"{}\"\n", name, kind)
+}
+
+#[test]
+fn decodes_the_bmp_fixture() {
+    let template = Template::parse(include_str!("../templates/bmp.toml")).unwrap();
+    let decoded = values(&template, include_bytes!("fixtures/tiny.bmp"));
+
+    assert_eq!(template.name, "BMP image");
+    assert_eq!(
+        decoded[0],
+        (
+            "magic".to_string(),
+            0,
+            2,
+            Some(Value::Bytes(b"BM".to_vec()))
+        )
+    );
+    assert_eq!(
+        decoded[1],
+        ("file_size".to_string(), 2, 4, Some(Value::Unsigned(70)))
+    );
+    assert_eq!(
+        decoded[3],
+        ("pixel_offset".to_string(), 10, 4, Some(Value::Unsigned(54)))
+    );
+    assert_eq!(
+        decoded[5],
+        ("width".to_string(), 0x12, 4, Some(Value::Unsigned(2)))
+    );
+    assert_eq!(
+        decoded[8],
+        (
+            "bits_per_pixel".to_string(),
+            0x1C,
+            2,
+            Some(Value::Unsigned(24))
+        )
+    );
+    assert_eq!(
+        decoded[10],
+        ("image_size".to_string(), 0x22, 4, Some(Value::Unsigned(16)))
+    );
+}
+
+#[test]
+fn decodes_the_gzip_fixture() {
+    let template = Template::parse(include_str!("../templates/gzip.toml")).unwrap();
+    let decoded = values(&template, include_bytes!("fixtures/hello.txt.gz"));
+
+    assert_eq!(decoded[2].3, Some(Value::Unsigned(8)));
+    assert_eq!(decoded[3].3, Some(Value::Unsigned(0x5F5E1000)));
+    assert_eq!(
+        decoded[6],
+        (
+            "file_name".to_string(),
+            10,
+            10,
+            Some(Value::Text {
+                bytes: b"hello.txt".to_vec(),
+                terminated: true
+            })
+        )
+    );
+}
+
+#[test]
+fn offsets_and_endianness() {
+    let template = Template::parse(&format!(
+        "endian = \"big\"\n{}{}offset = 0x6\nendian = \"little\"\n{}",
+        field("a", "u16"),
+        field("b", "u16"),
+        field("c", "u8"),
+    ))
+    .unwrap();
+
+    assert_eq!(template.fields[0].endian, Endian::Big);
+    assert_eq!(template.fields[1].offset, Some(6));
+    assert_eq!(template.fields[1].endian, Endian::Little);
+
+    let decoded = values(&template, &[0x12, 0x34, 0, 0, 0, 0, 0x12, 0x34, 0xAB]);
+    assert_eq!(decoded[0].3, Some(Value::Unsigned(0x1234)));
+    assert_eq!(decoded[1].3, Some(Value::Unsigned(0x3412)));
+    // Fields without an offset follow the one before, even if it had one
+    assert_eq!(decoded[2].1, 8);
+    assert_eq!(decoded[2].3, Some(Value::Unsigned(0xAB)));
+}
+
+#[test]
+fn signed_and_float_fields() {
+    let template = Template::parse(&format!(
+        "{}{}{}{}endian = \"big\"\n",
+        field("small", "i8"),
+        field("wide", "i16"),
+        field("single", "f32"),
+        field("double", "f64"),
+    ))
+    .unwrap();
+
+    let mut data = vec![0xFE, 0x00, 0x80];
+    data.extend_from_slice(&1.5f32.to_le_bytes());
+    data.extend_from_slice(&(-0.1f64).to_be_bytes());
+    let decoded = values(&template, &data);
+
+    assert_eq!(decoded[0].3, Some(Value::Signed(-2)));
+    assert_eq!(decoded[1].3, Some(Value::Signed(-32768)));
+    assert_eq!(decoded[2].3, Some(Value::Float32(1.5)));
+    assert_eq!(decoded[3].3, Some(Value::Float64(-0.1)));
+    assert_eq!(Value::Float32(0.1).to_string(), "0.1");
+}
+
+#[test]
+fn offsets_count_from_the_base() {
+    let mut template = Template::parse(&format!(
+        "{}{}offset = 4\n",
+        field("a", "u8"),
+        field("b", "u8")
+    ))
+    .unwrap();
+    template.base = 2;
+
+    let decoded = values(&template, &[0, 0, 0xA, 0, 0, 0, 0xB]);
+    assert_eq!(
+        decoded[0],
+        ("a".to_string(), 2, 1, Some(Value::Unsigned(0xA)))
+    );
+    assert_eq!(
+        decoded[1],
+        ("b".to_string(), 6, 1, Some(Value::Unsigned(0xB)))
+    );
+
+    // Past the end the fields are cut short and have no value
+    template.base = 6;
+    let decoded = values(&template, &[0, 0, 0, 0, 0, 0, 0xB]);
+    assert_eq!(decoded[1], ("b".to_string(), 7, 0, None));
+}
+
+#[test]
+fn values_encode_by_type() {
+    let template = Template::parse(&format!(
+        "{}{}endian = \"big\"\n{}{}{}",
+        field("magic", "bytes(4)"),
+        field("length", "u32"),
+        field("delta", "i16"),
+        field("scale", "f32"),
+        field("name", "cstr"),
+    ))
+    .unwrap();
+    let encode = |index: usize, text: &str| template.fields[index].encode(text);
+
+    assert_eq!(encode(0, "0x7F454C46"), Ok(vec![0x7F, 0x45, 0x4C, 0x46]));
+    assert_eq!(encode(0, "7F 45 4C 46"), Ok(vec![0x7F, 0x45, 0x4C, 0x46]));
+    assert_eq!(
+        encode(0, "7F45"),
+        Err("bytes[4] takes 4 bytes, got 2".to_string())
+    );
+    assert_eq!(encode(1, "0x1234"), Ok(vec![0, 0, 0x12, 0x34]));
+    assert_eq!(encode(1, "-1"), Err("-1 doesn't fit in u32".to_string()));
+    assert_eq!(encode(2, "-2"), Ok(vec![0xFE, 0xFF]));
+    assert_eq!(
+        encode(2, "32768"),
+        Err("32768 doesn't fit in i16".to_string())
+    );
+    assert_eq!(encode(3, "1.5"), Ok(1.5f32.to_le_bytes().to_vec()));
+    assert_eq!(
+        encode(3, "1e40"),
+        Err("1e40 doesn't fit in f32".to_string())
+    );
+    assert_eq!(encode(4, " hi"), Ok(b" hi\0".to_vec()));
+}
+
+#[test]
+fn fields_past_the_end_have_no_value() {
+    let template = Template::parse(&format!(
+        "{}{}",
+        field("long", "u64"),
+        field("name", "cstr")
+    ))
+    .unwrap();
+    let decoded = values(&template, b"abc");
+
+    assert_eq!(decoded[0], ("long".to_string(), 0, 3, None));
+    assert_eq!(decoded[1], ("name".to_string(), 3, 0, None));
+
+    let decoded = values(&template, b"12345678abc");
+    assert_eq!(
+        decoded[1].3,
+        Some(Value::Text {
+            bytes: b"abc".to_vec(),
+            terminated: false
+        })
+    );
+}
+
+#[test]
+fn values_describe_themselves() {
+    assert_eq!(Value::Unsigned(255).to_string(), "255 (0xFF)");
+    assert_eq!(Value::Bytes(vec![0xDE, 0xAD]).to_string(), "DE AD");
+    assert_eq!(
+        Value::Bytes(vec![0; 20]).to_string(),
+        format!("{} ...", ["00"; 16].join(" "))
+    );
+    assert_eq!(
+        Value::Text {
+            bytes: b"a\"b".to_vec(),
+            terminated: false
+        }
+        .to_string(),
+        "\"a\\\"b\" (unterminated)"
+    );
+}
+
+#[test]
+fn types_parse_and_print() {
+    for name in [
+        "u8",
+        "u16",
+        "u32",
+        "u64",
+        "i8",
+        "i16",
+        "i32",
+        "i64",
+        "f32",
+        "f64",
+        "bytes[12]",
+        "cstr",
+    ] {
+        assert_eq!(FieldType::parse(name).unwrap().to_string(), name);
+    }
+
+    assert_eq!(FieldType::parse("bytes[0x10]"), Ok(FieldType::Bytes(16)));
+    assert_eq!(FieldType::parse("bytes(4)"), Ok(FieldType::Bytes(4)));
+    assert!(FieldType::parse("bytes[0]").is_err());
+    assert!(FieldType::parse("u24").is_err());
+}
+
+#[test]
+fn comments_and_strings() {
+    let template = Template::parse(
+        "# A comment\nname = \"Has # and \\\" in it\" # trailing comment\n\n[[field]]\nname = \"x\"\ntype = \"u8\"\n",
+    )
+    .unwrap();
+
+    assert_eq!(template.name, "Has # and \" in it");
+    assert_eq!(template.fields[0].kind, FieldType::Unsigned(1));
+}
+
+#[test]
+fn errors_cite_the_field() {
+    let error = |contents: &str| Template::parse(contents).unwrap_err();
+
+    assert_eq!(
+        error(&format!(
+            "{}{}",
+            field("magic", "u8"),
+            field("length", "u24")
+        )),
+        "line 6: field 2 'length': unknown type 'u24'"
+    );
+    assert_eq!(
+        error("[[field]]\ntype = \"u8\"\n"),
+        "line 1: field 1: missing name"
+    );
+    assert_eq!(
+        error("[[field]]\nname = \"size\"\n"),
+        "line 1: field 1 'size': missing type"
+    );
+    assert_eq!(
+        error(&format!("{}endian = \"middle\"\n", field("size", "u32"))),
+        "line 4: field 1 'size': unknown endian 'middle', expected little or big"
+    );
+    assert_eq!(
+        error(&format!("{}offset = \"4\"\n", field("size", "u32"))),
+        "line 4: field 1 'size': offset should be a number"
+    );
+    assert_eq!(
+        error(&format!("{}colour = \"red\"\n", field("size", "u32"))),
+        "line 4: field 1 'size': unknown key 'colour'"
+    );
+}
+
+#[test]
+fn syntax_errors_cite_the_line() {
+    let error = |contents: &str| Template::parse(contents).unwrap_err();
+
+    assert_eq!(error("name \"x\""), "line 1: expected 'key = value'");
+    assert_eq!(error("name = \"x"), "line 1: unterminated string");
+    assert_eq!(
+        error("\n[fields]"),
+        "line 2: unknown table '[fields]', expected [[field]]"
+    );
+    assert_eq!(
+        error("name = \"a\"\nname = \"b\""),
+        "line 2: name is set twice"
+    );
+    assert_eq!(error("name = \"x\""), "no fields, add a [[field]] table");
+}
@@ -0,0 +1,194 @@
+use hex_it::theme::{
+    css, describe_color, parse_color, ByteClass, ColorChoice, Marks, Theme, PALETTE_NAMES,
+};
+use std::ffi::OsStr;
+
+#[test]
+fn every_byte_has_a_class() {
+    let count = |class| {
+        (0..=255u8)
+            .filter(|&byte| ByteClass::of(byte) == class)
+            .count()
+    };
+
+    assert_eq!(count(ByteClass::Null), 1);
+    assert_eq!(count(ByteClass::Whitespace), 6);
+    assert_eq!(count(ByteClass::Printable), 94);
+    assert_eq!(count(ByteClass::Control), 27);
+    assert_eq!(count(ByteClass::High), 128);
+}
+
+#[test]
+fn bytes_get_the_color_of_their_class() {
+    let theme = Theme::default();
+
+    assert_eq!(theme.byte(0x00), theme.null);
+    assert_eq!(theme.byte(b'\n'), theme.whitespace);
+    assert_eq!(theme.byte(b'A'), theme.printable);
+    assert_eq!(theme.byte(0x7F), theme.control);
+    assert_eq!(theme.byte(0xC3), theme.high);
+    assert_eq!(
+        Theme::paint(theme.error, "no"),
+        format!("{}no\x1b[0m", theme.error)
+    );
+}
+
+#[test]
+fn color_choices_parse() {
+    assert_eq!(ColorChoice::parse("always"), Ok(ColorChoice::Always));
+    assert_eq!(ColorChoice::parse("auto"), Ok(ColorChoice::Auto));
+    assert_eq!(ColorChoice::parse("never"), Ok(ColorChoice::Never));
+    assert!(ColorChoice::parse("sometimes").is_err());
+    assert_eq!(ColorChoice::default(), ColorChoice::Auto);
+}
+
+#[test]
+fn colors_by_choice_environment_and_terminal() {
+    let set = Some(OsStr::new("1"));
+    let empty = Some(OsStr::new(""));
+
+    // (choice, NO_COLOR, output is a terminal, colors)
+    let matrix = [
+        (ColorChoice::Auto, None, true, true),
+        (ColorChoice::Auto, None, false, false),
+        (ColorChoice::Auto, set, true, false),
+        (ColorChoice::Auto, set, false, false),
+        // An empty NO_COLOR counts as not set
+        (ColorChoice::Auto, empty, true, true),
+        (ColorChoice::Auto, empty, false, false),
+        (ColorChoice::Always, None, true, true),
+        (ColorChoice::Always, None, false, true),
+        (ColorChoice::Always, set, true, true),
+        (ColorChoice::Always, set, false, true),
+        (ColorChoice::Never, None, true, false),
+        (ColorChoice::Never, None, false, false),
+        (ColorChoice::Never, set, true, false),
+        (ColorChoice::Never, set, false, false),
+    ];
+
+    for (choice, no_color, tty, expected) in matrix {
+        assert_eq!(
+            choice.enabled(no_color, || tty),
+            expected,
+            "{:?} with NO_COLOR {:?} and a terminal {}",
+            choice,
+            no_color,
+            tty
+        );
+    }
+}
+
+#[test]
+fn only_auto_asks_about_the_terminal() {
+    let probe = || panic!("asked whether the output is a terminal");
+
+    assert!(ColorChoice::Always.enabled(None, probe));
+    assert!(!ColorChoice::Never.enabled(None, probe));
+    // NO_COLOR already settles it
+    assert!(!ColorChoice::Auto.enabled(Some(OsStr::new("1")), probe));
+}
+
+#[test]
+fn colors_parse_into_sgr_sequences() {
+    assert_eq!(parse_color("red"), Ok("\x1b[31m"));
+    assert_eq!(parse_color("Bright-Blue"), Ok("\x1b[94m"));
+    assert_eq!(parse_color("208"), Ok("\x1b[38;5;208m"));
+    assert_eq!(parse_color("dim"), Ok("\x1b[2m"));
+    assert_eq!(parse_color("bold+red"), Ok("\x1b[1;31m"));
+    assert_eq!(parse_color("black+on-cyan"), Ok("\x1b[30;46m"));
+    assert_eq!(parse_color("on-17"), Ok("\x1b[48;5;17m"));
+    assert_eq!(parse_color("default"), Ok(""));
+    // The same color is the same string every time
+    assert!(std::ptr::eq(
+        parse_color("bold+208").unwrap(),
+        parse_color("bold+208").unwrap()
+    ));
+}
+
+#[test]
+fn bad_colors_say_what_is_wrong() {
+    assert!(parse_color("purple")
+        .unwrap_err()
+        .contains("unknown color 'purple'"));
+    assert!(parse_color("256").unwrap_err().contains("past 255"));
+    assert!(parse_color("red+blue")
+        .unwrap_err()
+        .contains("two foreground"));
+    assert!(parse_color("on-red+on-blue")
+        .unwrap_err()
+        .contains("two background"));
+    assert!(parse_color("bold+").unwrap_err().contains("empty part"));
+    assert!(parse_color("bright-orange").is_err());
+}
+
+#[test]
+fn colors_are_described_the_way_they_are_parsed() {
+    for spec in [
+        "red",
+        "bright-white",
+        "208",
+        "bold+red",
+        "black+on-cyan",
+        "dim",
+        "underline+on-bright-red",
+    ] {
+        assert_eq!(describe_color(parse_color(spec).unwrap()), spec);
+    }
+    assert_eq!(describe_color(""), "default");
+
+    // Every color of the default theme has a name
+    let theme = Theme::default();
+    for name in PALETTE_NAMES {
+        let color = theme.get(name).unwrap();
+        assert_eq!(parse_color(&describe_color(color)), Ok(color), "{name}");
+    }
+}
+
+#[test]
+fn palette_entries_change_single_colors() {
+    let mut theme = Theme::default();
+
+    theme.set("zero", parse_color("dim").unwrap()).unwrap();
+    theme.set("match", parse_color("reverse").unwrap()).unwrap();
+    assert_eq!(theme.null, "\x1b[2m");
+    assert_eq!(theme.get("match"), Some("\x1b[7m"));
+    assert_eq!(theme.printable, Theme::default().printable);
+    assert!(theme
+        .set("nul", "")
+        .unwrap_err()
+        .starts_with("unknown palette entry 'nul'"));
+    assert_eq!(theme.get("nul"), None);
+}
+
+#[test]
+fn matches_win_over_modifications_over_classes() {
+    let theme = Theme::default();
+    let marks = |found, modified| Marks { found, modified };
+
+    assert_eq!(theme.layer(b'A', marks(false, false)), theme.printable);
+    assert_eq!(theme.layer(0, marks(false, false)), theme.null);
+    assert_eq!(theme.layer(b'A', marks(false, true)), theme.modified);
+    assert_eq!(theme.layer(b'A', marks(true, false)), theme.found);
+    assert_eq!(theme.layer(b'A', marks(true, true)), theme.found);
+}
+
+#[test]
+fn colors_as_css() {
+    let css = |spec: &str| css(parse_color(spec).unwrap(), "#d0d0d0", "#1e1e1e");
+
+    assert_eq!(css("cyan"), "color:#00cdcd");
+    assert_eq!(css("bright-blue"), "color:#5c5cff");
+    // Bold makes a basic color bright, like terminals do
+    assert_eq!(css("bold+black"), "font-weight:bold;color:#7f7f7f");
+    assert_eq!(css("black+on-cyan"), "color:#000000;background:#00cdcd");
+    assert_eq!(css("208"), "color:#ff8700");
+    assert_eq!(css("on-244"), "background:#808080");
+    assert_eq!(
+        css("dim+underline"),
+        "opacity:0.6;text-decoration:underline"
+    );
+    // Reverse swaps in the page colors where the color has none
+    assert_eq!(css("reverse"), "color:#1e1e1e;background:#d0d0d0");
+    assert_eq!(css("reverse+red"), "color:#1e1e1e;background:#cd0000");
+    assert_eq!(css("default"), "");
+}
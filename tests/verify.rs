@@ -0,0 +1,52 @@
+use hex_it::verify::{digest, Expected};
+
+const PNG: &[u8] = include_bytes!("fixtures/tiny.png");
+
+#[test]
+fn digests_match_the_check_values() {
+    assert_eq!(
+        digest("sha256", b"abc").unwrap(),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(digest("crc32", b"123456789").unwrap(), "CBF43926");
+    assert_eq!(digest("crc16-ccitt", b"123456789").unwrap(), "29B1");
+    assert_eq!(digest("adler32", b"Wikipedia").unwrap(), "11E60398");
+    assert_eq!(digest("fletcher16", b"abcde").unwrap(), "C8F0");
+    // Short values keep their leading zeros
+    assert_eq!(digest("fletcher16", b"").unwrap(), "0000");
+    assert_eq!(digest("md5", b""), None);
+}
+
+#[test]
+fn expected_digests_check_in_any_case() {
+    let expected = Expected::parse("crc32=cbf43926").unwrap();
+    assert_eq!(expected.algorithm, "crc32");
+    assert_eq!(expected.digest, "CBF43926");
+    assert_eq!(expected.check(b"123456789"), Ok(()));
+    assert_eq!(
+        expected.check(b"12345678"),
+        Err(digest("crc32", b"12345678").unwrap())
+    );
+
+    let sha = Expected::parse(&format!(
+        "SHA256={}",
+        digest("sha256", PNG).unwrap().to_uppercase()
+    ))
+    .unwrap();
+    assert_eq!(sha.check(PNG), Ok(()));
+    assert!(sha.check(&PNG[1..]).is_err());
+}
+
+#[test]
+fn unknown_names_and_malformed_digests_are_refused() {
+    assert!(Expected::parse("md5=d41d8cd98f00b204e9800998ecf8427e")
+        .unwrap_err()
+        .starts_with("unknown algorithm 'md5'"));
+    assert_eq!(
+        Expected::parse("crc32=CBF4392").unwrap_err(),
+        "a crc32 digest is 8 hex digits, not 'CBF4392'"
+    );
+    assert!(Expected::parse("crc32=CBF4392G").is_err());
+    assert!(Expected::parse("crc32").is_err());
+    assert!(Expected::parse("sha256=").is_err());
+}